@@ -0,0 +1,410 @@
+use async_trait::async_trait;
+use serde_json::json;
+use wp_connector_api::{
+    ConnectorDef, ConnectorScope, ParamMap, SinkBuildCtx, SinkDefProvider, SinkError, SinkFactory,
+    SinkHandle, SinkReason, SinkResult, SinkSpec,
+};
+
+use super::config::Clickhouse;
+use super::sink::ClickhouseSink;
+use crate::common::enrich::{EnrichConf, EnrichSink};
+use crate::common::flush_notify::FlushNotifier;
+use crate::common::health::{CheckConnection, Readiness, probe};
+use crate::common::params::string_list;
+use crate::common::retry::{self, RetryingSink};
+use crate::common::secret;
+use crate::common::stats;
+use crate::common::trace_context::TraceContext;
+use crate::common::transform::FieldTransforms;
+
+/// 布尔型参数
+const BOOL_KEYS: [&str; 6] = [
+    "skip_unknown",
+    "date_time_best_effort",
+    "async_insert",
+    "wait_for_async_insert",
+    "staging_mode",
+    "tls_insecure_skip_verify",
+];
+
+pub struct ClickhouseSinkFactory;
+
+#[async_trait]
+impl SinkFactory for ClickhouseSinkFactory {
+    fn kind(&self) -> &'static str {
+        "clickhouse"
+    }
+    fn validate_spec(&self, spec: &SinkSpec) -> SinkResult<()> {
+        let spec = &secret::resolve_sink_spec(spec)?;
+        let endpoint = spec
+            .params
+            .get("endpoint")
+            .and_then(|v| v.as_str())
+            .unwrap_or("");
+        let endpoints = string_list(&spec.params, "endpoints", "clickhouse")?;
+        if endpoint.trim().is_empty() && endpoints.is_empty() {
+            return Err(SinkReason::sink("clickhouse.endpoint must not be empty").into());
+        }
+        string_list(&spec.params, "tls_insecure_hosts", "clickhouse")?;
+        for key in [
+            "hash_field",
+            "table_field",
+            "ingest_id_field",
+            "tls_ca_cert",
+            "tls_client_cert",
+            "tls_client_key",
+        ] {
+            if let Some(v) = spec.params.get(key)
+                && v.as_str().is_none_or(|s| s.trim().is_empty())
+            {
+                return Err(SinkReason::sink(format!(
+                    "clickhouse.{key} must be a non-empty string"
+                ))
+                .into());
+            }
+        }
+        let database = spec
+            .params
+            .get("database")
+            .and_then(|v| v.as_str())
+            .unwrap_or("");
+        if database.trim().is_empty() {
+            return Err(SinkReason::sink("clickhouse.database must not be empty").into());
+        }
+        if let Some(i) = spec.params.get("batch").and_then(|v| v.as_i64())
+            && i <= 0
+        {
+            return Err(SinkReason::sink("clickhouse.batch must be > 0").into());
+        }
+        for key in BOOL_KEYS {
+            if let Some(v) = spec.params.get(key)
+                && !v.is_boolean()
+            {
+                return Err(SinkReason::sink(format!("clickhouse.{key} must be a boolean")).into());
+            }
+        }
+        if spec.params.contains_key("tls_client_cert") != spec.params.contains_key("tls_client_key")
+        {
+            return Err(SinkReason::sink(
+                "clickhouse.tls_client_cert and tls_client_key must be set together",
+            )
+            .into());
+        }
+        let flag = |key: &str| spec.params.get(key).and_then(|v| v.as_bool()) == Some(true);
+        if flag("staging_mode") && flag("async_insert") {
+            return Err(SinkReason::sink(
+                "clickhouse.staging_mode cannot be combined with async_insert",
+            )
+            .into());
+        }
+        if let Some(v) = spec.params.get("staging_table")
+            && !v.as_str().is_some_and(|s| s.contains("{table}"))
+        {
+            return Err(SinkReason::sink(
+                "clickhouse.staging_table must be a string containing {table}",
+            )
+            .into());
+        }
+        if let Some(v) = spec.params.get("on_row_error")
+            && !matches!(v.as_str(), Some("fail") | Some("skip"))
+        {
+            return Err(
+                SinkReason::sink("clickhouse.on_row_error must be one of: fail, skip").into(),
+            );
+        }
+        for key in ["pool_size", "timeout_ms"] {
+            if let Some(v) = spec.params.get(key)
+                && v.as_u64().is_none_or(|n| n == 0)
+            {
+                return Err(SinkReason::sink(format!("clickhouse.{key} must be > 0")).into());
+            }
+        }
+        if let Some(v) = spec.params.get("finalize_query")
+            && v.as_str().is_none_or(|s| s.trim().is_empty())
+        {
+            return Err(
+                SinkReason::sink("clickhouse.finalize_query must be a non-empty string").into(),
+            );
+        }
+        EnrichConf::from_params(&spec.params)?;
+        retry::validate_params(&spec.params)?;
+        FieldTransforms::from_params(&spec.params)?;
+        FlushNotifier::from_params(&spec.params)?;
+        TraceContext::from_params(&spec.params)?;
+        Ok(())
+    }
+    async fn build(&self, spec: &SinkSpec, _ctx: &SinkBuildCtx) -> SinkResult<SinkHandle> {
+        let spec = &secret::resolve_sink_spec(spec)?;
+        let conf = build_conf(spec)?;
+        let table = conf.table.clone().unwrap_or_else(|| spec.name.clone());
+        let sink = ClickhouseSink::new(conf, table)
+            .await?
+            .with_name(spec.name.clone())
+            .with_stats(stats::register(&spec.name, self.kind()))
+            .with_flush_notifier(FlushNotifier::from_params(&spec.params)?)
+            .with_trace_context(TraceContext::from_params(&spec.params)?);
+        let enrich = EnrichConf::from_params(&spec.params)?;
+        let transforms = FieldTransforms::from_params(&spec.params)?;
+        let sink = RetryingSink::from_spec(sink, spec).await?;
+        let sink = EnrichSink::new(sink, enrich).with_transforms(transforms);
+        Ok(SinkHandle::new(Box::new(sink)))
+    }
+}
+
+#[async_trait]
+impl CheckConnection for ClickhouseSinkFactory {
+    /// 对每个节点执行认证后的 `SELECT 1`（同 sink 的 `reconnect`，但不重试）；预检不加载表结构。
+    async fn check_connection(&self, spec: &SinkSpec) -> Readiness {
+        let mut conf = match secret::resolve_sink_spec(spec).and_then(|spec| build_conf(&spec)) {
+            Ok(conf) => conf,
+            Err(err) => return Readiness::not_ready(self.kind(), err),
+        };
+        conf.load_schema = false;
+        let target = if conf.endpoints.is_empty() {
+            conf.get_endpoint()
+        } else {
+            conf.endpoints.join(",")
+        };
+        let table = conf.table.clone().unwrap_or_else(|| spec.name.clone());
+        probe(self.kind(), &target, async move {
+            let mut sink = ClickhouseSink::new(conf, table)
+                .await
+                .map_err(|e| e.to_string())?;
+            sink.ping_all().await.map_err(|e| e.to_string())?;
+            Ok(None)
+        })
+        .await
+    }
+}
+
+/// 由参数组装 [`Clickhouse`] 配置（经 TOML 反序列化，沿用其默认值）。
+fn build_conf(spec: &SinkSpec) -> SinkResult<Clickhouse> {
+    // Build Clickhouse conf via serde (fields有私有的)
+    let mut tbl = toml::map::Map::new();
+    if let Some(s) = spec.params.get("endpoint").and_then(|v| v.as_str()) {
+        tbl.insert("endpoint".to_string(), toml::Value::String(s.to_string()));
+    }
+    if let Some(s) = spec.params.get("username").and_then(|v| v.as_str()) {
+        tbl.insert("username".to_string(), toml::Value::String(s.to_string()));
+    }
+    if let Some(s) = spec.params.get("password").and_then(|v| v.as_str()) {
+        tbl.insert("password".to_string(), toml::Value::String(s.to_string()));
+    }
+    if let Some(s) = spec.params.get("database").and_then(|v| v.as_str()) {
+        tbl.insert("database".to_string(), toml::Value::String(s.to_string()));
+    }
+    if let Some(i) = spec.params.get("batch").and_then(|v| v.as_i64()) {
+        tbl.insert("batch".to_string(), toml::Value::Integer(i));
+    }
+    if let Some(s) = spec.params.get("table").and_then(|v| v.as_str()) {
+        tbl.insert("table".to_string(), toml::Value::String(s.to_string()));
+    }
+    if let Some(arr) = spec
+        .params
+        .get("nullable_columns")
+        .and_then(|v| v.as_array())
+    {
+        let mut cols = Vec::with_capacity(arr.len());
+        for item in arr {
+            let Some(s) = item.as_str() else {
+                return Err(
+                    SinkReason::sink("clickhouse.nullable_columns entries must be string").into(),
+                );
+            };
+            cols.push(toml::Value::String(s.to_string()));
+        }
+        tbl.insert("nullable_columns".to_string(), toml::Value::Array(cols));
+    }
+    if let Some(b) = spec.params.get("load_schema").and_then(|v| v.as_bool()) {
+        tbl.insert("load_schema".to_string(), toml::Value::Boolean(b));
+    }
+    for key in ["finalize_query", "staging_table"] {
+        if let Some(s) = spec.params.get(key).and_then(|v| v.as_str()) {
+            tbl.insert(key.to_string(), toml::Value::String(s.to_string()));
+        }
+    }
+    for key in BOOL_KEYS {
+        if let Some(b) = spec.params.get(key).and_then(|v| v.as_bool()) {
+            tbl.insert(key.to_string(), toml::Value::Boolean(b));
+        }
+    }
+    if let Some(s) = spec.params.get("on_row_error").and_then(|v| v.as_str()) {
+        tbl.insert(
+            "on_row_error".to_string(),
+            toml::Value::String(s.to_string()),
+        );
+    }
+    for key in ["pool_size", "timeout_ms"] {
+        if let Some(i) = spec.params.get(key).and_then(|v| v.as_i64()) {
+            tbl.insert(key.to_string(), toml::Value::Integer(i));
+        }
+    }
+    let endpoints = string_list(&spec.params, "endpoints", "clickhouse")?;
+    if !endpoints.is_empty() {
+        tbl.insert(
+            "endpoints".to_string(),
+            toml::Value::Array(endpoints.into_iter().map(toml::Value::String).collect()),
+        );
+    }
+    let insecure_hosts = string_list(&spec.params, "tls_insecure_hosts", "clickhouse")?;
+    if !insecure_hosts.is_empty() {
+        tbl.insert(
+            "tls_insecure_hosts".to_string(),
+            toml::Value::Array(
+                insecure_hosts
+                    .into_iter()
+                    .map(toml::Value::String)
+                    .collect(),
+            ),
+        );
+    }
+    for key in [
+        "hash_field",
+        "table_field",
+        "ingest_id_field",
+        "tls_ca_cert",
+        "tls_client_cert",
+        "tls_client_key",
+    ] {
+        if let Some(s) = spec.params.get(key).and_then(|v| v.as_str()) {
+            tbl.insert(key.to_string(), toml::Value::String(s.trim().to_string()));
+        }
+    }
+    let value = toml::Value::Table(tbl);
+    let serialized = toml::to_string(&value).map_err(|err| {
+        SinkError::from(SinkReason::sink(format!(
+            "encode clickhouse conf failed: {err}"
+        )))
+    })?;
+    toml::from_str(&serialized).map_err(|err| {
+        SinkError::from(SinkReason::sink(format!(
+            "parse clickhouse conf failed: {err}"
+        )))
+    })
+}
+
+impl SinkDefProvider for ClickhouseSinkFactory {
+    fn sink_def(&self) -> ConnectorDef {
+        ConnectorDef {
+            id: "clickhouse_sink".into(),
+            kind: self.kind().into(),
+            scope: ConnectorScope::Sink,
+            allow_override: vec![
+                "endpoint",
+                "database",
+                "table",
+                "table_field",
+                "username",
+                "secret_ref",
+                "batch",
+                "nullable_columns",
+                "load_schema",
+                "finalize_query",
+                "endpoints",
+                "hash_field",
+                "enrich",
+                "enrich_overwrite",
+                "field_transforms",
+                "retry",
+                "write_deadline_ms",
+                "quarantine",
+                "flush_notify",
+                "trace_context",
+                "trace_id_field",
+                "tracestate",
+                "pool_size",
+                "timeout_ms",
+                "tls_insecure_hosts",
+                "tls_ca_cert",
+                "tls_client_cert",
+                "tls_client_key",
+                "tls_insecure_skip_verify",
+                "on_row_error",
+                "async_insert",
+                "wait_for_async_insert",
+                "staging_mode",
+                "staging_table",
+                "ingest_id_field",
+                "skip_unknown",
+                "date_time_best_effort",
+            ]
+            .into_iter()
+            .map(str::to_string)
+            .collect(),
+            default_params: clickhouse_defaults(),
+            origin: Some("wp-connectors:clickhouse_sink".into()),
+        }
+    }
+}
+
+fn clickhouse_defaults() -> ParamMap {
+    let mut params = ParamMap::new();
+    params.insert("endpoint".into(), json!("http://localhost:8123"));
+    params.insert("database".into(), json!("default"));
+    params.insert("table".into(), json!("wp_events"));
+    params.insert("username".into(), json!("default"));
+    params.insert("batch".into(), json!(1000));
+    params
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::health::ReadinessState;
+    use httpmock::prelude::*;
+
+    fn spec(params: ParamMap) -> SinkSpec {
+        SinkSpec {
+            name: "ck".into(),
+            kind: "clickhouse".into(),
+            connector_id: String::new(),
+            group: "test".into(),
+            params,
+            filter: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn check_connection_pings_every_endpoint() {
+        let up = MockServer::start_async().await;
+        let ping = up.mock(|when, then| {
+            when.method(GET)
+                .query_param("database", "default")
+                .query_param("query", "SELECT 1");
+            then.status(200).body("1\n");
+        });
+        let denied = MockServer::start_async().await;
+        denied.mock(|when, then| {
+            when.method(GET).query_param("query", "SELECT 1");
+            then.status(516).body(
+                "Code: 516. DB::Exception: default: Authentication failed: password is incorrect. \
+                 (AUTHENTICATION_FAILED)",
+            );
+        });
+
+        let mut params = clickhouse_defaults();
+        params.insert("endpoint".into(), json!(up.base_url()));
+        params.insert("load_schema".into(), json!(true));
+        let readiness = ClickhouseSinkFactory
+            .check_connection(&spec(params.clone()))
+            .await;
+        assert!(readiness.is_ready(), "{readiness:?}");
+        assert_eq!(readiness.target.as_deref(), Some(up.base_url().as_str()));
+        ping.assert_hits(1);
+
+        params.insert(
+            "endpoints".into(),
+            json!([up.base_url(), denied.base_url()]),
+        );
+        let readiness = ClickhouseSinkFactory.check_connection(&spec(params)).await;
+        assert_eq!(readiness.state, ReadinessState::NotReady);
+        assert!(
+            readiness
+                .detail
+                .as_deref()
+                .is_some_and(|d| d.contains("code 516")),
+            "{readiness:?}"
+        );
+        ping.assert_hits(2);
+    }
+}
@@ -0,0 +1,16 @@
+//! ClickHouse sink：经 HTTP 接口以 JSONEachRow 格式批量写入。
+//!
+//! 模块划分：
+//! - config：Clickhouse 配置与 `clickhouse://` 连接串解析
+//! - sink：ClickhouseSink（按节点/表攒批、多节点哈希、staging 写入、行级错误处理）
+//! - factory：Sink 工厂
+//! - adapter：dev 适配器（连接串转参数）
+
+mod adapter;
+pub mod config;
+mod factory;
+mod sink;
+
+pub use config::{Clickhouse, RowErrorPolicy};
+pub use factory::ClickhouseSinkFactory;
+pub use sink::{ClickhouseColumn, ClickhouseSink};
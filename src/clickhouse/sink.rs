@@ -0,0 +1,1712 @@
+use async_trait::async_trait;
+use reqwest::StatusCode;
+use serde::Deserialize;
+use serde_json::{Map, Value as JsonValue};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use wp_connector_api::{
+    AsyncCtrl, AsyncRawDataSink, AsyncRecordSink, SinkError, SinkReason, SinkResult,
+};
+use wp_data_fmt::{DataFormat, FormatType};
+use wp_log::warn_data;
+use wp_model_core::model::{DataField, DataRecord, fmt_def::TextFmt};
+
+use super::config::{Clickhouse, RowErrorPolicy};
+use crate::common::flush_notify::FlushNotifier;
+use crate::common::ingest_id::ingest_id;
+use crate::common::reconnect::{ReconnectCoordinator, backend_key};
+use crate::common::retry::{self, FailureKind, PendingFlush};
+use crate::common::stats::{ConnectorStats, StatsHandle};
+use crate::common::tls::{self, TlsFiles, is_insecure_host};
+use crate::common::trace_context::TraceContext;
+
+const DEFAULT_BATCH: usize = 100;
+/// 开启 `async_insert` 时的默认批量：合并交给服务端，客户端以小批次降低延迟
+const DEFAULT_ASYNC_BATCH: usize = 10;
+/// 响应体中异常信息的标记，如 `Code: 27. DB::Exception: Cannot parse input ... (at row 3)`
+const EXCEPTION_MARKER: &str = "DB::Exception";
+/// `reconnect` 使用的探测语句
+const PING_QUERY: &str = "SELECT 1";
+/// 未配置 `staging_table` 时的 staging 表名模板
+const DEFAULT_STAGING_TABLE: &str = "{table}_staging";
+/// staging 表名后缀的进程内序号
+static STAGING_SEQ: AtomicU64 = AtomicU64::new(0);
+/// 可重试的异常码：超时、网络、内存不足、并发查询或 part 过多、只读副本、Keeper 异常
+const TRANSIENT_CODES: [u32; 9] = [159, 202, 209, 210, 241, 242, 252, 425, 999];
+
+pub struct ClickhouseSink {
+    pub(crate) conf: Clickhouse,
+    pub(crate) table: String,
+    pub(crate) proc_cnt: usize,
+    // 写入节点池；未配置 endpoints 时只有单个 endpoint
+    pub(crate) endpoints: Vec<String>,
+    // 按 (节点, 目标表) 缓存的待写入行
+    pub(crate) values: HashMap<(String, String), Vec<String>>,
+    pub(crate) nullable_columns: HashSet<String>,
+    pub(crate) columns: Option<Vec<ClickhouseColumn>>,
+    // 构建时创建、所有请求共用的 HTTP 客户端，保留 keep-alive 连接与 TLS 会话
+    pub(crate) client: reqwest::Client,
+    // 仅用于 tls_insecure_hosts 中主机的客户端（不校验证书）；未配置时为 None
+    pub(crate) insecure_client: Option<reqwest::Client>,
+    // sink 名称，作为摄入 id 与 query_id 的前缀；默认为目标表名
+    pub(crate) name: String,
+    // 当前批次序号（每次 flush 后递增）与批内已写入的行数，用于生成摄入 id
+    pub(crate) batch_seq: u64,
+    pub(crate) batch_rows: usize,
+    pub(crate) stats: StatsHandle,
+    // 各缓存中记录的 wp_event_id（按到达顺序），随 flush 通知带出
+    event_ids: HashMap<(String, String), Vec<String>>,
+    // 各缓存中记录的接收序号；各缓存独立写出，供 `pending_seqs` 定位未写入的记录
+    seqs: HashMap<(String, String), Vec<u64>>,
+    pub(crate) flush_notifier: FlushNotifier,
+    // 为每个 INSERT 请求附加 W3C trace 头；为 None 时不附加
+    trace_context: Option<TraceContext>,
+    // 各缓存中第一条记录携带的 trace id
+    trace_ids: HashMap<(String, String), String>,
+}
+
+/// `DESCRIBE TABLE` 返回的列定义。
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct ClickhouseColumn {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub ty: String,
+}
+
+impl ClickhouseColumn {
+    pub fn is_nullable(&self) -> bool {
+        self.ty.starts_with("Nullable(")
+    }
+}
+
+impl ClickhouseSink {
+    /// 构建 ClickHouse Sink；开启 `load_schema` 时通过 `DESCRIBE TABLE` 预先加载表结构。
+    pub async fn new(conf: Clickhouse, table: String) -> SinkResult<Self> {
+        let mut nullable_columns: HashSet<String> = conf.nullable_columns.iter().cloned().collect();
+        let endpoints = if conf.endpoints.is_empty() {
+            vec![conf.get_endpoint()]
+        } else {
+            conf.endpoints.clone()
+        };
+        if conf.tls_insecure_skip_verify {
+            warn_data!(
+                "ck tls_insecure_skip_verify enabled: endpoint certificates are not verified"
+            );
+        }
+        let client = build_client(&conf, false)?;
+        let insecure_client = if conf.tls_insecure_hosts.is_empty() {
+            None
+        } else {
+            Some(build_client(&conf, true)?)
+        };
+        let mut sink = Self {
+            conf,
+            table: table.clone(),
+            proc_cnt: 0,
+            endpoints,
+            values: Default::default(),
+            nullable_columns: HashSet::new(),
+            columns: None,
+            client,
+            insecure_client,
+            stats: StatsHandle::detached(&table, "clickhouse"),
+            name: table,
+            batch_seq: 0,
+            batch_rows: 0,
+            event_ids: HashMap::new(),
+            seqs: HashMap::new(),
+            flush_notifier: FlushNotifier::default(),
+            trace_context: None,
+            trace_ids: HashMap::new(),
+        };
+        if sink.conf.load_schema {
+            let columns = sink.describe_table(&sink.table).await?;
+            if columns.is_empty() {
+                return Err(SinkError::from(SinkReason::Sink(format!(
+                    "ck table `{}` has no columns",
+                    sink.table
+                ))));
+            }
+            nullable_columns.extend(
+                columns
+                    .iter()
+                    .filter(|c| c.is_nullable())
+                    .map(|c| c.name.clone()),
+            );
+            sink.columns = Some(columns);
+        }
+        sink.nullable_columns = nullable_columns;
+        Ok(sink)
+    }
+
+    /// 设置 sink 名称（摄入 id 与 query_id 的前缀）。
+    pub fn with_name(mut self, name: String) -> Self {
+        self.name = name;
+        self
+    }
+
+    /// 使用外部登记的状态句柄（见 [`crate::common::stats::register`]），
+    /// 以配置的默认表作为指标的写入目标。
+    pub fn with_stats(mut self, stats: StatsHandle) -> Self {
+        self.stats = stats.with_destination(self.table.as_str());
+        self
+    }
+
+    /// 每个节点/表缓存成功写出后的通知（见 [`crate::common::flush_notify`]）。
+    pub fn with_flush_notifier(mut self, flush_notifier: FlushNotifier) -> Self {
+        self.flush_notifier = flush_notifier;
+        self
+    }
+
+    /// 为每个 INSERT 请求附加 W3C trace 头（见 [`crate::common::trace_context`]）。
+    pub fn with_trace_context(mut self, trace_context: Option<TraceContext>) -> Self {
+        self.trace_context = trace_context;
+        self
+    }
+
+    /// 缓存条数、累计投递条数与最近错误的快照。
+    pub fn stats(&self) -> ConnectorStats {
+        self.stats.snapshot()
+    }
+
+    /// 执行 `DESCRIBE TABLE` 并解析列名与类型。
+    pub async fn describe_table(&self, table: &str) -> SinkResult<Vec<ClickhouseColumn>> {
+        let query = [
+            ("database", self.conf.database.to_string()),
+            (
+                "query",
+                format!("DESCRIBE TABLE \"{}\" FORMAT JSONEachRow", table),
+            ),
+        ];
+        let resp = self
+            .client_for(&self.endpoints[0])
+            .post(&self.endpoints[0])
+            .basic_auth(&self.conf.username, Some(&self.conf.password))
+            .query(&query)
+            .send()
+            .await
+            .map_err(|e| SinkError::from(SinkReason::Sink(format!("ck describe fail: {}", e))))?;
+        let status = resp.status();
+        let text = resp.text().await.unwrap_or_default();
+        if status != StatusCode::OK {
+            return Err(SinkError::from(SinkReason::Sink(format!(
+                "ck describe fail: {}",
+                text
+            ))));
+        }
+        parse_describe_output(&text)
+    }
+
+    /// 生成一行 JSONEachRow 数据。
+    ///
+    /// 未配置 `nullable_columns` 且未加载表结构时保持原有输出；否则对空值做类型感知处理：
+    /// Nullable 列写入 `null`，其余列省略以便使用列默认值。
+    /// 加载了表结构时，未知字段在 `skip_unknown=false` 下直接报错，否则丢弃。
+    fn format_row(&self, data: &DataRecord) -> SinkResult<String> {
+        let line = FormatType::from(&TextFmt::Json).format_record(data);
+        if self.nullable_columns.is_empty() && self.columns.is_none() {
+            return Ok(line);
+        }
+        let Ok(JsonValue::Object(mut row)) = serde_json::from_str::<JsonValue>(&line) else {
+            return Ok(line);
+        };
+        if let Some(columns) = &self.columns {
+            let known: HashSet<&str> = columns.iter().map(|c| c.name.as_str()).collect();
+            if !self.conf.skip_unknown
+                && let Some(unknown) = row.keys().find(|k| !known.contains(k.as_str()))
+            {
+                return Err(SinkError::from(SinkReason::Sink(format!(
+                    "ck field `{}` not found in table `{}`",
+                    unknown, self.table
+                ))));
+            }
+            row.retain(|k, _| known.contains(k.as_str()));
+        }
+        apply_nullable_columns(&mut row, &self.nullable_columns);
+        Ok(JsonValue::Object(row).to_string())
+    }
+
+    /// 目标节点的主机名在 `tls_insecure_hosts` 中时返回不校验证书的客户端，否则返回常规客户端。
+    fn client_for(&self, endpoint: &str) -> &reqwest::Client {
+        match &self.insecure_client {
+            Some(insecure) if is_insecure_host(&self.conf.tls_insecure_hosts, endpoint) => insecure,
+            _ => &self.client,
+        }
+    }
+
+    /// 客户端批量：未配置 `batch` 时按是否开启 `async_insert` 取默认值。
+    fn batch_size(&self) -> usize {
+        let default = if self.conf.async_insert {
+            DEFAULT_ASYNC_BATCH
+        } else {
+            DEFAULT_BATCH
+        };
+        self.conf.batch.unwrap_or(default)
+    }
+
+    /// 生成 INSERT 语句；已加载表结构时显式列出列名。
+    fn insert_statement(&self, table: &str) -> String {
+        match self.columns.as_ref().filter(|_| table == self.table) {
+            Some(columns) => format!(
+                "INSERT INTO \"{}\" ({}) FORMAT JSONEachRow",
+                table,
+                columns
+                    .iter()
+                    .map(|c| format!("\"{}\"", c.name))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            None => format!("INSERT INTO \"{}\" FORMAT JSONEachRow", table),
+        }
+    }
+
+    /// 按 `hash_field` 选择写入节点（rendezvous 一致性哈希）；字段缺失时轮询。
+    fn pick_endpoint(&self, data: &DataRecord) -> String {
+        if self.endpoints.len() == 1 {
+            return self.endpoints[0].clone();
+        }
+        let key = self
+            .conf
+            .hash_field
+            .as_deref()
+            .and_then(|f| data.get2(f))
+            .map(|f| f.get_value().to_string());
+        match key {
+            Some(key) => pick_by_hash(&self.endpoints, &key).to_string(),
+            None => self.endpoints[self.proc_cnt % self.endpoints.len()].clone(),
+        }
+    }
+
+    /// 按 `table_field` 选择目标表；字段缺失或为空时写入 `table`。
+    /// 字段值直接拼入 INSERT 语句，因此只接受 `[A-Za-z_][A-Za-z0-9_]*` 形式的表名。
+    fn route_table(&self, data: &DataRecord) -> SinkResult<String> {
+        let routed = self
+            .conf
+            .table_field
+            .as_deref()
+            .and_then(|f| data.get2(f))
+            .map(|f| f.get_value().to_string())
+            .filter(|v| !v.trim().is_empty());
+        let Some(routed) = routed else {
+            return Ok(self.table.clone());
+        };
+        let routed = routed.trim();
+        if !is_safe_identifier(routed) {
+            return Err(SinkError::from(SinkReason::Sink(format!(
+                "ck table `{}` from field `{}` is not a valid identifier",
+                routed,
+                self.conf.table_field.as_deref().unwrap_or_default()
+            ))));
+        }
+        Ok(routed.to_string())
+    }
+
+    /// 各 (节点, 表) 缓存独立刷新：成功的缓存清空，失败的保留缓存并汇总报错。
+    /// 无论成败批次序号都会递增；保留的行已带有摄入 id，重试时 id 不变。
+    async fn flush(&mut self) -> SinkResult<()> {
+        if self.values.is_empty() {
+            return Ok(());
+        }
+        let result = self.flush_buffers().await;
+        self.batch_seq += 1;
+        self.batch_rows = 0;
+        result
+    }
+
+    async fn flush_buffers(&mut self) -> SinkResult<()> {
+        let mut flushed = Vec::new();
+        let mut failures = Vec::new();
+        let mut transient = None;
+        let (mut succeeded, mut failed) = (0, 0);
+        for ((endpoint, table), values) in &self.values {
+            let mut buf = Vec::new();
+            for v in values {
+                buf.extend_from_slice(format!("{}\n", v).as_bytes());
+            }
+            let key = (endpoint.clone(), table.clone());
+            let trace_id = self.trace_ids.get(&key).map(String::as_str);
+            let result = if self.conf.staging_mode {
+                self.insert_via_staging(endpoint, table, buf, trace_id)
+                    .await
+            } else {
+                self.insert_values(endpoint, table, buf, trace_id).await
+            };
+            match result {
+                Ok(()) => {
+                    succeeded += values.len() as u64;
+                    flushed.push((key, values.len()));
+                }
+                Err(e) => {
+                    failed += values.len() as u64;
+                    failures.push(format!("{} `{}`: {}", endpoint, table, e));
+                    if transient.is_none() && FailureKind::of(&e) == FailureKind::Retryable {
+                        transient = Some(e);
+                    }
+                }
+            }
+        }
+        for (key, records) in flushed {
+            self.values.remove(&key);
+            self.trace_ids.remove(&key);
+            self.seqs.remove(&key);
+            let event_ids = self.event_ids.remove(&key).unwrap_or_default();
+            self.flush_notifier.notify(&key.1, records, event_ids);
+        }
+        self.stats.record_delivery(succeeded, failed);
+        self.stats
+            .set_buffered(self.values.values().map(Vec::len).sum());
+        if failures.is_empty() {
+            self.stats.mark_flush();
+            return Ok(());
+        }
+        let msg = format!(
+            "ck insert fail on {} endpoint/table(s): {}",
+            failures.len(),
+            failures.join("; ")
+        );
+        self.stats.record_error(&msg);
+        // 保留的缓存只含失败的节点/表，任一失败可重试时整体按可重试上报
+        Err(match &transient {
+            Some(e) => retry::reclassify(e, msg),
+            None => SinkError::from(SinkReason::Sink(msg)),
+        })
+    }
+
+    /// 写入一批 JSONEachRow 数据（每行以换行结尾）。
+    ///
+    /// 服务端可能在 HTTP 200 的响应体中报告异常（如类型不匹配、无法解析的行），
+    /// 因此除状态码外还会检查响应体；能定位到出错行时按 `on_row_error` 处理：
+    /// `fail` 整批失败并附带出错行，`skip` 丢弃该行后重发其余行。
+    /// 因未知字段被拒的行不受 `on_row_error` 影响：`skip_unknown=false` 时总是整批失败。
+    /// 配置了 trace 上下文时各请求以 `trace_id`（为 None 时随机生成）附加 trace 头。
+    pub async fn insert_values(
+        &self,
+        endpoint: &str,
+        table: &str,
+        values: Vec<u8>,
+        trace_id: Option<&str>,
+    ) -> SinkResult<()> {
+        let mut rows: Vec<&[u8]> = values
+            .split(|b| *b == b'\n')
+            .filter(|row| !row.is_empty())
+            .collect();
+        while !rows.is_empty() {
+            let mut body = rows.join(&b'\n');
+            body.push(b'\n');
+            let Some((status, text)) = self.post_insert(endpoint, table, body, trace_id).await?
+            else {
+                return Ok(());
+            };
+            let exception = ClickhouseException::parse(&text);
+            let row = exception
+                .as_ref()
+                .and_then(|e| e.row)
+                .filter(|n| (1..=rows.len()).contains(n));
+            let policy = match &exception {
+                Some(e) if e.is_unknown_field() && !self.conf.skip_unknown => RowErrorPolicy::Fail,
+                _ => self.conf.on_row_error,
+            };
+            match (policy, row) {
+                (RowErrorPolicy::Skip, Some(n)) => {
+                    let skipped = rows.remove(n - 1);
+                    warn_data!(
+                        "ck skip rejected row {} of `{}`: {}; row: {}",
+                        n,
+                        table,
+                        exception.map(|e| e.describe()).unwrap_or_default(),
+                        String::from_utf8_lossy(skipped)
+                    );
+                }
+                (_, Some(n)) => {
+                    return Err(SinkError::from(SinkReason::Sink(format!(
+                        "CK insert fail at row {}: {}; row: {}",
+                        n,
+                        exception.map(|e| e.describe()).unwrap_or_default(),
+                        String::from_utf8_lossy(rows[n - 1])
+                    ))));
+                }
+                (_, None) => {
+                    return Err(server_error(
+                        status,
+                        &text,
+                        format!("CK insert fail: {}", text),
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// staging 模式写入：新建本批次独享的 staging 表并写入整批，成功后以一条 `INSERT ... SELECT`
+    /// 搬入目标表。无论成败都会删除 staging 表；失败时目标表未被写入，缓存保留以便重试。
+    async fn insert_via_staging(
+        &self,
+        endpoint: &str,
+        table: &str,
+        values: Vec<u8>,
+        trace_id: Option<&str>,
+    ) -> SinkResult<()> {
+        let staging = self.staging_table(table)?;
+        let drop = format!("DROP TABLE IF EXISTS \"{}\"", staging);
+        let result = async {
+            let create = format!("CREATE TABLE \"{}\" AS \"{}\"", staging, table);
+            self.execute_query(endpoint, &create).await?;
+            self.insert_values(endpoint, &staging, values, trace_id)
+                .await?;
+            let swap = format!("INSERT INTO \"{}\" SELECT * FROM \"{}\"", table, staging);
+            self.execute_query(endpoint, &swap).await
+        }
+        .await;
+        if let Err(e) = self.execute_query(endpoint, &drop).await {
+            warn_data!("ck drop staging `{}` fail: {}", staging, e);
+        }
+        result
+    }
+
+    /// 按 `staging_table` 模板生成 staging 表名，结果须为合法标识符；再追加 sink 名哈希、批次号、
+    /// 时间戳、进程号与进程内序号，同一目标表的并发 sink、多副本与重试各用各的 staging 表。
+    fn staging_table(&self, table: &str) -> SinkResult<String> {
+        let pattern = self
+            .conf
+            .staging_table
+            .as_deref()
+            .unwrap_or(DEFAULT_STAGING_TABLE);
+        let staging = pattern.replace("{table}", table);
+        if staging == table || !is_safe_identifier(&staging) {
+            return Err(SinkError::from(SinkReason::Sink(format!(
+                "ck staging table `{}` is not a valid identifier distinct from `{}`",
+                staging, table
+            ))));
+        }
+        let millis = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_millis());
+        Ok(format!(
+            "{}_{:08x}_{}_{}_{}_{}",
+            staging,
+            fnv1a(&[self.name.as_bytes()]) as u32,
+            self.batch_seq,
+            millis,
+            std::process::id(),
+            STAGING_SEQ.fetch_add(1, Ordering::Relaxed)
+        ))
+    }
+
+    /// 发送一次 INSERT 请求。
+    ///
+    /// # return
+    /// * `SinkResult<Option<(StatusCode, String)>>` - 成功为 `None`；服务端报错（非 200 或响应体含异常）
+    ///   时为状态码与响应体。
+    async fn post_insert(
+        &self,
+        endpoint: &str,
+        table: &str,
+        body: Vec<u8>,
+        trace_id: Option<&str>,
+    ) -> SinkResult<Option<(StatusCode, String)>> {
+        let mut query = vec![
+            ("database", self.conf.database.to_string()),
+            ("input_format_import_nested_json", "1".to_string()),
+            ("enable_http_compression", "1".to_string()),
+        ];
+        if self.conf.skip_unknown {
+            query.push(("input_format_skip_unknown_fields", "1".to_string()));
+        }
+        if self.conf.date_time_best_effort {
+            query.push(("date_time_input_format", "best_effort".to_string()));
+        }
+        if self.conf.ingest_id_field.is_some() {
+            let query_id = format!("{}-{}-{}", self.name, self.batch_seq, table);
+            query.push(("query_id", query_id));
+        }
+        if self.conf.async_insert {
+            query.push(("async_insert", "1".to_string()));
+            let wait = if self.conf.wait_for_async_insert {
+                "1"
+            } else {
+                "0"
+            };
+            query.push(("wait_for_async_insert", wait.to_string()));
+        }
+        query.push(("query", self.insert_statement(table)));
+
+        let mut req = self
+            .client_for(endpoint)
+            .post(endpoint)
+            .basic_auth(&self.conf.username, Some(&self.conf.password))
+            .query(&query);
+        if let Some(trace) = &self.trace_context {
+            for (name, value) in trace.headers_with_id(trace_id.map(str::to_string)) {
+                req = req.header(name, value);
+            }
+        }
+        let resp =
+            req.body(body).send().await.map_err(|e| {
+                retry::transport_error(format!("ck send fail: {}", e), e.is_timeout())
+            })?;
+        let status = resp.status();
+        let text = resp.text().await.unwrap_or_default();
+        if status.ne(&StatusCode::OK) || text.contains(EXCEPTION_MARKER) {
+            return Ok(Some((status, text)));
+        }
+        Ok(None)
+    }
+
+    /// 逐个探测写入节点，不重试（连通性预检使用）。
+    pub(crate) async fn ping_all(&self) -> SinkResult<()> {
+        for endpoint in &self.endpoints {
+            self.ping(endpoint).await?;
+        }
+        Ok(())
+    }
+
+    /// 以认证后的 `SELECT 1` 探测节点：仅返回 200 不足以说明凭据与数据库可用，
+    /// 因此还需响应体中不含异常（如认证失败、数据库不存在）。
+    async fn ping(&self, endpoint: &str) -> SinkResult<()> {
+        let query = [
+            ("database", self.conf.database.to_string()),
+            ("query", PING_QUERY.to_string()),
+        ];
+        let resp = self
+            .client_for(endpoint)
+            .get(endpoint)
+            .basic_auth(&self.conf.username, Some(&self.conf.password))
+            .query(&query)
+            .send()
+            .await
+            .map_err(|e| SinkError::from(SinkReason::Sink(format!("ck reconnect fail: {}", e))))?;
+        let status = resp.status();
+        let text = resp.text().await.unwrap_or_default();
+        if status != StatusCode::OK || text.contains(EXCEPTION_MARKER) {
+            let reason = ClickhouseException::parse(&text)
+                .map(|e| e.describe())
+                .unwrap_or_else(|| format!("status {}: {}", status, text.trim()));
+            return Err(SinkError::from(SinkReason::Sink(format!(
+                "ck reconnect fail on {}: {}",
+                endpoint, reason
+            ))));
+        }
+        Ok(())
+    }
+
+    /// 执行不带数据体的语句（收尾的 `finalize_query`，staging 表的建表、搬迁与清理）。
+    async fn execute_query(&self, endpoint: &str, sql: &str) -> SinkResult<()> {
+        let query = [
+            ("database", self.conf.database.to_string()),
+            ("query", sql.to_string()),
+        ];
+        let resp = self
+            .client_for(endpoint)
+            .post(endpoint)
+            .basic_auth(&self.conf.username, Some(&self.conf.password))
+            .query(&query)
+            .send()
+            .await
+            .map_err(|e| {
+                retry::transport_error(format!("ck query `{}` fail: {}", sql, e), e.is_timeout())
+            })?;
+        let status = resp.status();
+        if status != StatusCode::OK {
+            let text = resp.text().await.unwrap_or_default();
+            let msg = format!("ck query `{}` fail: {}", sql, text);
+            return Err(server_error(status, &text, msg));
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl PendingFlush for ClickhouseSink {
+    fn pending_len(&self) -> usize {
+        self.values.values().map(Vec::len).sum()
+    }
+
+    async fn flush_now(&mut self) -> SinkResult<()> {
+        self.flush().await
+    }
+
+    fn discard_pending(&mut self) -> usize {
+        let discarded = self.pending_len();
+        self.values.clear();
+        self.event_ids.clear();
+        self.seqs.clear();
+        self.trace_ids.clear();
+        self.stats.set_buffered(0);
+        discarded
+    }
+
+    fn accepted(&self) -> u64 {
+        self.proc_cnt as u64
+    }
+
+    fn pending_seqs(&self) -> Vec<u64> {
+        self.seqs.values().flatten().copied().collect()
+    }
+}
+
+#[async_trait]
+impl AsyncCtrl for ClickhouseSink {
+    async fn stop(&mut self) -> SinkResult<()> {
+        self.flush().await?;
+        if let Some(sql) = &self.conf.finalize_query {
+            for endpoint in &self.endpoints {
+                self.execute_query(endpoint, sql).await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn reconnect(&mut self) -> SinkResult<()> {
+        for endpoint in &self.endpoints {
+            ReconnectCoordinator::shared(backend_key("clickhouse", endpoint), Default::default())
+                .run(|| self.ping(endpoint))
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl AsyncRecordSink for ClickhouseSink {
+    async fn sink_record(&mut self, data: &DataRecord) -> SinkResult<()> {
+        // build json line
+        let v = match self.conf.ingest_id_field.as_deref() {
+            Some(field) => {
+                let id = ingest_id(&self.name, self.batch_seq, self.batch_rows);
+                let mut stamped = data.clone();
+                stamped.append(DataField::from_chars(field, id.as_str()));
+                self.format_row(&stamped)?
+            }
+            None => self.format_row(data)?,
+        };
+        let table = self.route_table(data)?;
+        let endpoint = self.pick_endpoint(data);
+        let key = (endpoint, table);
+        self.seqs
+            .entry(key.clone())
+            .or_default()
+            .push(self.proc_cnt as u64);
+        self.proc_cnt += 1;
+        self.batch_rows += 1;
+        if let Some(field) = data.get2("wp_event_id") {
+            self.event_ids
+                .entry(key.clone())
+                .or_default()
+                .push(field.get_value().to_string());
+        }
+        if !self.trace_ids.contains_key(&key)
+            && let Some(trace_id) = self
+                .trace_context
+                .as_ref()
+                .and_then(|trace| trace.record_trace_id(data))
+        {
+            self.trace_ids.insert(key.clone(), trace_id);
+        }
+        self.values.entry(key).or_default().push(v);
+        if self.proc_cnt.is_multiple_of(self.batch_size()) {
+            self.flush().await?;
+        }
+        Ok(())
+    }
+
+    async fn sink_records(&mut self, data: Vec<Arc<DataRecord>>) -> SinkResult<()> {
+        for record in data {
+            self.sink_record(record.as_ref()).await?;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl AsyncRawDataSink for ClickhouseSink {
+    async fn sink_str(&mut self, _data: &str) -> SinkResult<()> {
+        Err(SinkReason::Sink("ClickHouse sink does not support raw input".into()).into())
+    }
+    async fn sink_bytes(&mut self, _data: &[u8]) -> SinkResult<()> {
+        Err(SinkReason::Sink("ClickHouse sink does not support raw bytes".into()).into())
+    }
+
+    async fn sink_str_batch(&mut self, _data: Vec<&str>) -> SinkResult<()> {
+        Err(SinkReason::Sink("ClickHouse sink does not support raw batch input".into()).into())
+    }
+
+    async fn sink_bytes_batch(&mut self, _data: Vec<&[u8]>) -> SinkResult<()> {
+        Err(SinkReason::Sink("ClickHouse sink does not support raw batch input".into()).into())
+    }
+}
+
+/// 服务端返回的异常信息。
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ClickhouseException {
+    code: Option<u32>,
+    message: String,
+    /// 出错行（从 1 开始），取自 `(at row N)`
+    row: Option<usize>,
+}
+
+impl ClickhouseException {
+    /// 从响应体中提取异常；不含 `DB::Exception` 时返回 `None`。
+    fn parse(text: &str) -> Option<Self> {
+        let start = text.find(EXCEPTION_MARKER)?;
+        let code = text[..start]
+            .rfind("Code:")
+            .and_then(|pos| leading_number(&text[pos + "Code:".len()..]));
+        let message = text[start + EXCEPTION_MARKER.len()..]
+            .trim_start_matches(':')
+            .lines()
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_string();
+        let row = text
+            .find("at row ")
+            .and_then(|pos| leading_number(&text[pos + "at row ".len()..]));
+        Some(Self { code, message, row })
+    }
+
+    fn describe(&self) -> String {
+        match self.code {
+            Some(code) => format!("code {}: {}", code, self.message),
+            None => self.message.clone(),
+        }
+    }
+
+    /// 行中含有表里不存在的字段（未开启 `input_format_skip_unknown_fields` 时）。
+    fn is_unknown_field(&self) -> bool {
+        self.message.contains("Unknown field")
+    }
+
+    fn is_transient(&self) -> bool {
+        self.code
+            .is_some_and(|code| TRANSIENT_CODES.contains(&code))
+    }
+}
+
+/// 服务端报错：按异常码归类，没有异常信息时按 HTTP 状态码归类。
+fn server_error(status: StatusCode, text: &str, msg: String) -> SinkError {
+    match ClickhouseException::parse(text) {
+        Some(e) if e.is_transient() => retry::busy_error(msg),
+        Some(_) => SinkError::from(SinkReason::Sink(msg)),
+        None => retry::status_error(status.as_u16(), msg),
+    }
+}
+
+/// 解析字符串开头（忽略前导空白）的十进制数字。
+fn leading_number<T: std::str::FromStr>(text: &str) -> Option<T> {
+    let text = text.trim_start();
+    let end = text
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(text.len());
+    text[..end].parse().ok()
+}
+
+/// 按配置构建共享的 HTTP 客户端（连接池大小、请求超时与 TLS）；
+/// `insecure` 或开启 `tls_insecure_skip_verify` 时不校验证书与主机名。
+fn build_client(conf: &Clickhouse, insecure: bool) -> SinkResult<reqwest::Client> {
+    let files = TlsFiles {
+        ca_cert: conf.tls_ca_cert.as_deref(),
+        client_cert: conf.tls_client_cert.as_deref(),
+        client_key: conf.tls_client_key.as_deref(),
+    };
+    let insecure = insecure || conf.tls_insecure_skip_verify;
+    let mut builder = tls::configure(reqwest::Client::builder(), &files, insecure, "ck")?;
+    if let Some(size) = conf.pool_size {
+        builder = builder.pool_max_idle_per_host(size);
+    }
+    if let Some(ms) = conf.timeout_ms {
+        builder = builder.timeout(Duration::from_millis(ms));
+    }
+    builder
+        .build()
+        .map_err(|e| SinkError::from(SinkReason::Sink(format!("ck client build fail: {}", e))))
+}
+
+/// 对一行数据做空值归一：Nullable 列缺失或为空串时置为 `null`，非 Nullable 列的空值直接移除。
+fn apply_nullable_columns(row: &mut Map<String, JsonValue>, nullable: &HashSet<String>) {
+    row.retain(|name, value| nullable.contains(name) || !is_empty_value(value));
+    for name in nullable {
+        match row.get_mut(name) {
+            Some(value) if is_empty_value(value) => *value = JsonValue::Null,
+            Some(_) => {}
+            None => {
+                row.insert(name.clone(), JsonValue::Null);
+            }
+        }
+    }
+}
+
+/// 解析 `DESCRIBE TABLE ... FORMAT JSONEachRow` 的输出。
+fn parse_describe_output(text: &str) -> SinkResult<Vec<ClickhouseColumn>> {
+    text.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            serde_json::from_str::<ClickhouseColumn>(line).map_err(|e| {
+                SinkError::from(SinkReason::Sink(format!(
+                    "ck describe parse fail: {}, line: {}",
+                    e, line
+                )))
+            })
+        })
+        .collect()
+}
+
+/// rendezvous 哈希：对每个节点计算 `fnv1a(endpoint, key)`，取最大者；增删节点只影响少量 key。
+fn pick_by_hash<'a>(endpoints: &'a [String], key: &str) -> &'a str {
+    endpoints
+        .iter()
+        .max_by_key(|ep| fnv1a(&[ep.as_bytes(), b"\0", key.as_bytes()]))
+        .map(String::as_str)
+        .unwrap_or_default()
+}
+
+fn fnv1a(parts: &[&[u8]]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for part in parts {
+        for b in *part {
+            hash ^= u64::from(*b);
+            hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+    hash
+}
+
+/// 不加引号也可安全使用的标识符：字母或 `_` 开头，其后为字母、数字或 `_`。
+fn is_safe_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn is_empty_value(value: &JsonValue) -> bool {
+    match value {
+        JsonValue::Null => true,
+        JsonValue::String(s) => s.is_empty(),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::testing::TestServer;
+    use httpmock::prelude::*;
+    use serde_json::json;
+    use wp_model_core::model::DataField;
+
+    fn nullable(cols: &[&str]) -> HashSet<String> {
+        cols.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn absent_nullable_string_becomes_null() {
+        // message 列为 Nullable(String)，记录中缺失该字段
+        let mut row = json!({"id": 1, "host": "a"}).as_object().cloned().unwrap();
+        apply_nullable_columns(&mut row, &nullable(&["message"]));
+        assert_eq!(row.get("message"), Some(&JsonValue::Null));
+        assert_eq!(row.get("host"), Some(&json!("a")));
+    }
+
+    #[test]
+    fn empty_values_follow_column_kind() {
+        let mut row = json!({"id": 1, "message": "", "region": ""})
+            .as_object()
+            .cloned()
+            .unwrap();
+        apply_nullable_columns(&mut row, &nullable(&["message"]));
+        assert_eq!(row.get("message"), Some(&JsonValue::Null));
+        // region 为带默认值的普通列：空值省略
+        assert!(!row.contains_key("region"));
+        assert_eq!(row.get("id"), Some(&json!(1)));
+    }
+
+    #[tokio::test]
+    async fn load_schema_issues_describe_and_lists_columns() {
+        let server = MockServer::start_async().await;
+        let describe = server.mock(|when, then| {
+            when.method(POST)
+                .query_param("query", "DESCRIBE TABLE \"events\" FORMAT JSONEachRow");
+            then.status(200).body(
+                "{\"name\":\"id\",\"type\":\"String\"}\n{\"name\":\"msg\",\"type\":\"Nullable(String)\"}\n",
+            );
+        });
+        let insert = server.mock(|when, then| {
+            when.method(POST)
+                .query_param(
+                    "query",
+                    "INSERT INTO \"events\" (\"id\", \"msg\") FORMAT JSONEachRow",
+                )
+                .body_contains("\"msg\":null");
+            then.status(200);
+        });
+
+        let conf = Clickhouse {
+            endpoint: server.base_url(),
+            batch: Some(1),
+            load_schema: true,
+            ..Default::default()
+        };
+        let mut sink = ClickhouseSink::new(conf, "events".into())
+            .await
+            .expect("build sink");
+        describe.assert();
+        assert_eq!(
+            sink.columns.as_ref().map(|c| c.len()),
+            Some(2),
+            "columns parsed from DESCRIBE"
+        );
+        assert!(sink.nullable_columns.contains("msg"));
+
+        let mut record = DataRecord::default();
+        record.append(DataField::from_chars("id", "1"));
+        record.append(DataField::from_chars("extra", "dropped"));
+        sink.sink_record(&record).await.expect("insert ok");
+        insert.assert();
+    }
+
+    #[tokio::test]
+    async fn finalize_query_runs_once_after_final_flush() {
+        let server = MockServer::start_async().await;
+        let insert = server.mock(|when, then| {
+            when.method(POST)
+                .query_param("query", "INSERT INTO \"events\" FORMAT JSONEachRow")
+                .body_contains("\"id\":\"1\"");
+            then.status(200);
+        });
+        let finalize = server.mock(|when, then| {
+            when.method(POST)
+                .query_param("query", "OPTIMIZE TABLE events FINAL");
+            then.status(200);
+        });
+
+        let conf = Clickhouse {
+            endpoint: server.base_url(),
+            batch: Some(100),
+            finalize_query: Some("OPTIMIZE TABLE events FINAL".into()),
+            ..Default::default()
+        };
+        let mut sink = ClickhouseSink::new(conf, "events".into())
+            .await
+            .expect("build sink");
+        let mut record = DataRecord::default();
+        record.append(DataField::from_chars("id", "1"));
+        sink.sink_record(&record).await.expect("buffered");
+        assert_eq!(finalize.hits(), 0, "finalize waits for stop");
+
+        sink.stop().await.expect("stop ok");
+        insert.assert_hits(1);
+        finalize.assert_hits(1);
+        assert!(sink.values.is_empty());
+    }
+
+    #[tokio::test]
+    async fn trace_context_headers_attached_to_inserts() {
+        let server = MockServer::start_async().await;
+        let insert = server.mock(|when, then| {
+            when.method(POST)
+                .query_param("query", "INSERT INTO \"events\" FORMAT JSONEachRow")
+                .matches(|req| {
+                    req.headers.iter().flatten().any(|(name, value)| {
+                        name.eq_ignore_ascii_case("traceparent")
+                            && value.starts_with("00-4bf92f3577b34da6a3ce929d0e0e4736-")
+                    })
+                });
+            then.status(200);
+        });
+        let conf = Clickhouse {
+            endpoint: server.base_url(),
+            batch: Some(2),
+            ..Default::default()
+        };
+        let trace = TraceContext {
+            trace_id_field: Some("trace_id".into()),
+            tracestate: None,
+        };
+        let mut sink = ClickhouseSink::new(conf, "events".into())
+            .await
+            .expect("build sink")
+            .with_trace_context(Some(trace));
+        let mut first = DataRecord::default();
+        first.append(DataField::from_chars("id", "1"));
+        let mut traced = DataRecord::default();
+        traced.append(DataField::from_chars("id", "2"));
+        traced.append(DataField::from_chars(
+            "trace_id",
+            "4bf92f3577b34da6a3ce929d0e0e4736",
+        ));
+        sink.sink_record(&first).await.expect("buffered");
+        sink.sink_record(&traced).await.expect("traced insert");
+        insert.assert_hits(1);
+        assert!(sink.trace_ids.is_empty());
+    }
+
+    #[tokio::test]
+    async fn flush_notifier_fires_after_successful_insert() {
+        let server = MockServer::start_async().await;
+        server.mock(|when, then| {
+            when.method(POST)
+                .query_param("query", "INSERT INTO \"events\" FORMAT JSONEachRow");
+            then.status(200);
+        });
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let conf = Clickhouse {
+            endpoint: server.base_url(),
+            batch: Some(100),
+            ..Default::default()
+        };
+        let mut sink = ClickhouseSink::new(conf, "events".into())
+            .await
+            .expect("build sink")
+            .with_flush_notifier(FlushNotifier::from_channel(tx));
+        for id in ["7", "8"] {
+            let mut record = id_record(id);
+            record.append(DataField::from_chars("wp_event_id", id));
+            sink.sink_record(&record).await.expect("buffered");
+        }
+        assert!(rx.try_recv().is_err(), "nothing flushed yet");
+
+        sink.flush().await.expect("flush ok");
+        let event = rx.try_recv().expect("notified");
+        assert_eq!((event.target.as_str(), event.records), ("events", 2));
+        assert_eq!(event.event_ids, ["7", "8"]);
+    }
+
+    #[tokio::test]
+    async fn records_spread_across_endpoint_pool_by_hash() {
+        let servers = [
+            MockServer::start_async().await,
+            MockServer::start_async().await,
+            MockServer::start_async().await,
+        ];
+        let mocks = servers
+            .iter()
+            .map(|server| {
+                server.mock(|when, then| {
+                    when.method(POST)
+                        .query_param("query", "INSERT INTO \"events\" FORMAT JSONEachRow");
+                    then.status(200);
+                })
+            })
+            .collect::<Vec<_>>();
+        let endpoints = servers.iter().map(|s| s.base_url()).collect::<Vec<_>>();
+
+        let conf = Clickhouse {
+            endpoints: endpoints.clone(),
+            hash_field: Some("user".into()),
+            batch: Some(1000),
+            ..Default::default()
+        };
+        let mut sink = ClickhouseSink::new(conf, "events".into())
+            .await
+            .expect("build sink");
+        for i in 0..60 {
+            let mut record = DataRecord::default();
+            record.append(DataField::from_chars("user", format!("u{}", i).as_str()));
+            sink.sink_record(&record).await.expect("buffered");
+        }
+        // 同一 key 始终落到同一节点
+        for ((endpoint, _), rows) in &sink.values {
+            for row in rows {
+                let user = serde_json::from_str::<JsonValue>(row).unwrap()["user"]
+                    .as_str()
+                    .unwrap()
+                    .to_string();
+                assert_eq!(pick_by_hash(&endpoints, &user), endpoint.as_str());
+            }
+        }
+        assert_eq!(sink.values.len(), 3, "all endpoints receive records");
+        sink.stop().await.expect("flush ok");
+        for mock in &mocks {
+            mock.assert_hits(1);
+        }
+    }
+
+    #[tokio::test]
+    async fn down_endpoint_fails_only_its_buffer() {
+        let up = MockServer::start_async().await;
+        let down = MockServer::start_async().await;
+        let up_mock = up.mock(|when, then| {
+            when.method(POST);
+            then.status(200);
+        });
+        down.mock(|when, then| {
+            when.method(POST);
+            then.status(503).body("unavailable");
+        });
+        let conf = Clickhouse {
+            endpoints: vec![up.base_url(), down.base_url()],
+            hash_field: Some("user".into()),
+            batch: Some(1000),
+            ..Default::default()
+        };
+        let mut sink = ClickhouseSink::new(conf, "events".into())
+            .await
+            .expect("build sink");
+        for i in 0..40 {
+            let mut record = DataRecord::default();
+            record.append(DataField::from_chars("user", format!("u{}", i).as_str()));
+            sink.sink_record(&record).await.expect("buffered");
+        }
+        let err = sink.stop().await.expect_err("down endpoint reported");
+        assert!(format!("{err}").contains(&down.base_url()));
+        up_mock.assert_hits(1);
+        assert_eq!(sink.values.len(), 1, "only the failed buffer is kept");
+        assert!(
+            sink.values
+                .contains_key(&(down.base_url(), "events".to_string()))
+        );
+
+        let kept = sink.values.values().map(Vec::len).sum::<usize>() as u64;
+        let stats = sink.stats();
+        assert_eq!(stats.attempted_records, 40);
+        assert_eq!(stats.failed_records, kept);
+        assert_eq!(stats.succeeded_records, 40 - kept);
+        assert_eq!(stats.buffered_records, kept);
+        assert!(stats.last_error.is_some_and(|e| e.contains("unavailable")));
+    }
+
+    const UNKNOWN_FIELD_EXCEPTION: &str = "Code: 117. DB::Exception: Unknown field found while \
+        parsing JSONEachRow format: extra: (at row 2)\n: While executing ParallelParsingBlockInputFormat. \
+        (INCORRECT_DATA) (version 24.3.1.1)\n";
+    const ROW_EXCEPTION: &str = "Code: 27. DB::Exception: Cannot parse input: expected \'\"\' \
+        before: \'abc}\': (at row 2)\n: While executing ParallelParsingBlockInputFormat. \
+        (CANNOT_PARSE_INPUT_ASSERTION_FAILED) (version 23.8.2.7)";
+
+    fn id_record(id: &str) -> DataRecord {
+        let mut record = DataRecord::default();
+        record.append(DataField::from_chars("id", id));
+        record
+    }
+
+    #[test]
+    fn exception_body_is_parsed() {
+        let e = ClickhouseException::parse(ROW_EXCEPTION).expect("exception");
+        assert_eq!(e.code, Some(27));
+        assert_eq!(e.row, Some(2));
+        assert!(e.message.starts_with("Cannot parse input"), "{}", e.message);
+        assert_eq!(ClickhouseException::parse("Ok."), None);
+    }
+
+    #[tokio::test]
+    async fn ok_status_with_exception_body_fails_with_row_context() {
+        let server = MockServer::start_async().await;
+        let insert = server.mock(|when, then| {
+            when.method(POST);
+            then.status(200).body(ROW_EXCEPTION);
+        });
+        let conf = Clickhouse {
+            endpoint: server.base_url(),
+            batch: Some(3),
+            skip_unknown: false,
+            ..Default::default()
+        };
+        let mut sink = ClickhouseSink::new(conf, "events".into())
+            .await
+            .expect("build sink");
+        sink.sink_record(&id_record("1")).await.expect("buffered");
+        sink.sink_record(&id_record("2")).await.expect("buffered");
+        let err = sink
+            .sink_record(&id_record("3"))
+            .await
+            .expect_err("rejected");
+        let msg = format!("{err}");
+        assert!(msg.contains("at row 2"), "{msg}");
+        assert!(msg.contains("code 27"), "{msg}");
+        assert!(msg.contains("\"id\":\"2\""), "{msg}");
+        insert.assert_hits(1);
+        assert_eq!(
+            sink.values.values().map(Vec::len).sum::<usize>(),
+            3,
+            "batch kept"
+        );
+    }
+
+    #[tokio::test]
+    async fn skip_policy_drops_rejected_row_and_resends_rest() {
+        let server = MockServer::start_async().await;
+        let rejected = server.mock(|when, then| {
+            when.method(POST).body_contains("\"id\":\"2\"");
+            then.status(200).body(ROW_EXCEPTION);
+        });
+        let accepted = server.mock(|when, then| {
+            when.method(POST).body_contains("\"id\":\"3\"");
+            then.status(200);
+        });
+        let conf = Clickhouse {
+            endpoint: server.base_url(),
+            batch: Some(3),
+            on_row_error: RowErrorPolicy::Skip,
+            ..Default::default()
+        };
+        let mut sink = ClickhouseSink::new(conf, "events".into())
+            .await
+            .expect("build sink");
+        for id in ["1", "2", "3"] {
+            sink.sink_record(&id_record(id))
+                .await
+                .expect("skipped bad row");
+        }
+        rejected.assert_hits(1);
+        accepted.assert_hits(1);
+        assert!(sink.values.is_empty());
+    }
+
+    #[tokio::test]
+    async fn unknown_field_fails_batch_unless_skip_unknown() {
+        let server = MockServer::start_async().await;
+        let insert = server.mock(|when, then| {
+            when.method(POST);
+            then.status(200).body(UNKNOWN_FIELD_EXCEPTION);
+        });
+        let conf = Clickhouse {
+            endpoint: server.base_url(),
+            batch: Some(3),
+            skip_unknown: false,
+            on_row_error: RowErrorPolicy::Skip,
+            ..Default::default()
+        };
+        let mut sink = ClickhouseSink::new(conf, "events".into())
+            .await
+            .expect("build sink");
+        sink.sink_record(&id_record("1")).await.expect("buffered");
+        sink.sink_record(&id_record("2")).await.expect("buffered");
+        let err = sink
+            .sink_record(&id_record("3"))
+            .await
+            .expect_err("unknown field rejected");
+        let msg = format!("{err}");
+        assert!(msg.contains("at row 2"), "{msg}");
+        assert!(msg.contains("Unknown field"), "{msg}");
+        insert.assert_hits(1);
+    }
+
+    #[tokio::test]
+    async fn async_insert_query_params_follow_config() {
+        for (async_insert, wait) in [(false, true), (true, true), (true, false)] {
+            let server = MockServer::start_async().await;
+            let expected_wait = if wait { "1" } else { "0" };
+            let async_mock = server.mock(|when, then| {
+                when.method(POST)
+                    .query_param("async_insert", "1")
+                    .query_param("wait_for_async_insert", expected_wait);
+                then.status(200);
+            });
+            let plain = server.mock(|when, then| {
+                when.method(POST).query_param_exists("query");
+                then.status(200);
+            });
+            let conf = Clickhouse {
+                endpoint: server.base_url(),
+                async_insert,
+                wait_for_async_insert: wait,
+                ..Default::default()
+            };
+            let mut sink = ClickhouseSink::new(conf, "events".into())
+                .await
+                .expect("build sink");
+            let expected_batch = if async_insert {
+                DEFAULT_ASYNC_BATCH
+            } else {
+                DEFAULT_BATCH
+            };
+            assert_eq!(sink.batch_size(), expected_batch);
+            sink.sink_record(&id_record("1")).await.expect("buffered");
+            sink.stop().await.expect("flush ok");
+            if async_insert {
+                async_mock.assert_hits(1);
+            } else {
+                async_mock.assert_hits(0);
+                plain.assert_hits(1);
+            }
+        }
+    }
+
+    fn kind_record(id: &str, kind: Option<&str>) -> DataRecord {
+        let mut record = id_record(id);
+        if let Some(kind) = kind {
+            record.append(DataField::from_chars("kind", kind));
+        }
+        record
+    }
+
+    fn insert_mock<'a>(server: &'a MockServer, table: &str) -> httpmock::Mock<'a> {
+        let query = format!("INSERT INTO \"{}\" FORMAT JSONEachRow", table);
+        server.mock(|when, then| {
+            when.method(POST).query_param("query", query.as_str());
+            then.status(200);
+        })
+    }
+
+    #[tokio::test]
+    async fn table_field_routes_records_to_separate_tables() {
+        let server = MockServer::start_async().await;
+        let audit = insert_mock(&server, "audit");
+        let metrics = insert_mock(&server, "metrics");
+        let fallback = insert_mock(&server, "events");
+        let conf = Clickhouse {
+            endpoint: server.base_url(),
+            batch: Some(100),
+            table_field: Some("kind".into()),
+            ..Default::default()
+        };
+        let mut sink = ClickhouseSink::new(conf, "events".into())
+            .await
+            .expect("build sink");
+        sink.sink_record(&kind_record("1", Some("audit")))
+            .await
+            .expect("buffered");
+        sink.sink_record(&kind_record("2", Some("metrics")))
+            .await
+            .expect("buffered");
+        sink.sink_record(&kind_record("3", Some("audit")))
+            .await
+            .expect("buffered");
+        assert_eq!(sink.values.len(), 2, "one buffer per table");
+
+        sink.stop().await.expect("flush ok");
+        audit.assert_hits(1);
+        metrics.assert_hits(1);
+        fallback.assert_hits(0);
+        assert!(sink.values.is_empty());
+    }
+
+    #[tokio::test]
+    async fn table_field_falls_back_and_rejects_unsafe_names() {
+        let server = MockServer::start_async().await;
+        let fallback = insert_mock(&server, "events");
+        let conf = Clickhouse {
+            endpoint: server.base_url(),
+            batch: Some(100),
+            table_field: Some("kind".into()),
+            ..Default::default()
+        };
+        let mut sink = ClickhouseSink::new(conf, "events".into())
+            .await
+            .expect("build sink");
+        sink.sink_record(&kind_record("1", None))
+            .await
+            .expect("buffered");
+        sink.sink_record(&kind_record("2", Some(" ")))
+            .await
+            .expect("buffered");
+        for bad in ["a\"; DROP TABLE x", "1abc", "db.t"] {
+            let err = sink
+                .sink_record(&kind_record("3", Some(bad)))
+                .await
+                .expect_err("unsafe table name");
+            assert!(format!("{err}").contains("not a valid identifier"), "{err}");
+        }
+        sink.stop().await.expect("flush ok");
+        fallback.assert_hits(1);
+    }
+
+    async fn staging_sink(endpoint: String) -> ClickhouseSink {
+        let conf = Clickhouse {
+            endpoint,
+            batch: Some(100),
+            staging_mode: true,
+            ..Default::default()
+        };
+        let mut sink = ClickhouseSink::new(conf, "events".into())
+            .await
+            .expect("build sink");
+        sink.sink_record(&id_record("1")).await.expect("buffered");
+        sink.sink_record(&id_record("2")).await.expect("buffered");
+        sink
+    }
+
+    /// 按请求的 `query` 参数前缀匹配的 mock。
+    macro_rules! query_mock {
+        ($server:expr, $prefix:literal, $status:expr) => {
+            $server.mock(|when, then| {
+                when.method(POST).matches(|req| {
+                    req.query_params
+                        .iter()
+                        .flatten()
+                        .any(|(k, v)| k == "query" && v.starts_with($prefix))
+                });
+                then.status($status);
+            })
+        };
+    }
+
+    #[tokio::test]
+    async fn staging_mode_swaps_batch_into_target() {
+        let server = MockServer::start_async().await;
+        let create = query_mock!(server, "CREATE TABLE \"events_staging_", 200);
+        let fill = query_mock!(server, "INSERT INTO \"events_staging_", 200);
+        let swap = query_mock!(
+            server,
+            "INSERT INTO \"events\" SELECT * FROM \"events_staging_",
+            200
+        );
+        let drop = query_mock!(server, "DROP TABLE IF EXISTS \"events_staging_", 200);
+        let mut sink = staging_sink(server.base_url()).await;
+        sink.stop().await.expect("staged flush ok");
+        for mock in [&create, &fill, &swap, &drop] {
+            mock.assert_hits(1);
+        }
+        assert!(sink.values.is_empty());
+
+        // 每批次的 staging 表名不同，并发 sink 与重试不会共用同一张表
+        let first = sink.staging_table("events").expect("valid name");
+        let second = sink.staging_table("events").expect("valid name");
+        assert!(first.starts_with("events_staging_"), "{first}");
+        assert_ne!(first, second);
+    }
+
+    #[tokio::test]
+    async fn staging_mode_failed_swap_leaves_target_unchanged() {
+        let server = MockServer::start_async().await;
+        query_mock!(server, "CREATE TABLE", 200);
+        query_mock!(server, "INSERT INTO \"events_staging_", 200);
+        let swap = query_mock!(server, "INSERT INTO \"events\"", 500);
+        let drop = query_mock!(server, "DROP TABLE IF EXISTS \"events_staging_", 200);
+        let mut sink = staging_sink(server.base_url()).await;
+        assert!(sink.stop().await.is_err(), "swap failure surfaces");
+        swap.assert_hits(1);
+        drop.assert_hits(1);
+        assert_eq!(
+            sink.values.values().map(Vec::len).sum::<usize>(),
+            2,
+            "batch kept"
+        );
+    }
+
+    #[tokio::test]
+    async fn client_is_reused_across_inserts() {
+        let server = TestServer::start(|_| (200, String::new())).await;
+        let conf = Clickhouse {
+            endpoint: server.url.clone(),
+            batch: Some(1),
+            pool_size: Some(4),
+            timeout_ms: Some(5_000),
+            ..Default::default()
+        };
+        let mut sink = ClickhouseSink::new(conf, "events".into())
+            .await
+            .expect("build sink");
+        for i in 0..5 {
+            let mut record = DataRecord::default();
+            record.append(DataField::from_chars("id", format!("{}", i).as_str()));
+            sink.sink_record(&record).await.expect("insert ok");
+        }
+        sink.reconnect().await.expect("ping ok");
+        assert_eq!(
+            server.connections(),
+            1,
+            "inserts and reconnect share one keep-alive connection"
+        );
+    }
+
+    #[test]
+    fn tls_client_builds_for_each_combination() {
+        let fixture = |name: &str| {
+            Some(format!(
+                "{}/tests/fixtures/tls/{}",
+                env!("CARGO_MANIFEST_DIR"),
+                name
+            ))
+        };
+        let tls = |ca: bool, identity: bool, skip_verify: bool| Clickhouse {
+            tls_ca_cert: if ca { fixture("ca.pem") } else { None },
+            tls_client_cert: if identity {
+                fixture("client.pem")
+            } else {
+                None
+            },
+            tls_client_key: if identity {
+                fixture("client.key")
+            } else {
+                None
+            },
+            tls_insecure_skip_verify: skip_verify,
+            ..Default::default()
+        };
+        for ca in [false, true] {
+            for identity in [false, true] {
+                for skip_verify in [false, true] {
+                    build_client(&tls(ca, identity, skip_verify), false)
+                        .unwrap_or_else(|e| panic!("{ca}/{identity}/{skip_verify}: {e}"));
+                }
+            }
+        }
+
+        let cert_only = Clickhouse {
+            tls_client_cert: fixture("client.pem"),
+            ..Default::default()
+        };
+        let err = build_client(&cert_only, false).expect_err("key missing");
+        assert!(format!("{err}").contains("set together"));
+        let missing_ca = Clickhouse {
+            tls_ca_cert: fixture("missing.pem"),
+            ..Default::default()
+        };
+        let err = build_client(&missing_ca, false).expect_err("unreadable ca");
+        assert!(format!("{err}").contains("tls_ca_cert"));
+    }
+
+    #[tokio::test]
+    async fn insecure_client_is_built_only_when_hosts_are_listed() {
+        let conf = Clickhouse {
+            tls_insecure_hosts: vec!["ch-internal.local".to_string()],
+            ..Default::default()
+        };
+        let sink = ClickhouseSink::new(conf, "events".into())
+            .await
+            .expect("build sink");
+        assert!(sink.insecure_client.is_some());
+
+        // 未配置名单时不构建不校验证书的客户端
+        let sink = ClickhouseSink::new(Clickhouse::default(), "events".into())
+            .await
+            .expect("build sink");
+        assert!(sink.insecure_client.is_none());
+    }
+
+    #[tokio::test]
+    async fn reconnect_pings_with_credentials_and_database() {
+        let server = MockServer::start_async().await;
+        let healthy = server.mock(|when, then| {
+            when.method(GET)
+                .query_param("query", PING_QUERY)
+                .query_param("database", "wparse")
+                .header("authorization", "Basic ZGF5dTp3cGFyc2U=");
+            then.status(200).body("1\n");
+        });
+        let denied = server.mock(|when, then| {
+            when.method(GET).query_param("query", PING_QUERY);
+            then.status(516).body(
+                "Code: 516. DB::Exception: dayu: Authentication failed: password is incorrect. \
+                 (AUTHENTICATION_FAILED)",
+            );
+        });
+        let conf = Clickhouse {
+            endpoint: server.base_url(),
+            ..Default::default()
+        };
+        let mut sink = ClickhouseSink::new(conf.clone(), "events".into())
+            .await
+            .expect("build sink");
+        sink.reconnect().await.expect("healthy ping");
+        healthy.assert_hits(1);
+
+        let conf = Clickhouse {
+            password: "wrong".into(),
+            ..conf
+        };
+        let mut sink = ClickhouseSink::new(conf, "events".into())
+            .await
+            .expect("build sink");
+        let err = sink.ping_all().await.expect_err("auth failure reported");
+        assert!(format!("{err}").contains("code 516"), "{err}");
+        denied.assert_hits(1);
+        healthy.assert_hits(1);
+    }
+
+    #[tokio::test]
+    async fn finalize_query_skipped_when_final_flush_fails() {
+        let server = MockServer::start_async().await;
+        let insert = server.mock(|when, then| {
+            when.method(POST)
+                .query_param("query", "INSERT INTO \"events\" FORMAT JSONEachRow");
+            then.status(500).body("boom");
+        });
+        let finalize = server.mock(|when, then| {
+            when.method(POST)
+                .query_param("query", "OPTIMIZE TABLE events FINAL");
+            then.status(200);
+        });
+
+        let conf = Clickhouse {
+            endpoint: server.base_url(),
+            batch: Some(100),
+            finalize_query: Some("OPTIMIZE TABLE events FINAL".into()),
+            ..Default::default()
+        };
+        let mut sink = ClickhouseSink::new(conf, "events".into())
+            .await
+            .expect("build sink");
+        let mut record = DataRecord::default();
+        record.append(DataField::from_chars("id", "1"));
+        sink.sink_record(&record).await.expect("buffered");
+
+        assert!(sink.stop().await.is_err());
+        insert.assert_hits(1);
+        finalize.assert_hits(0);
+    }
+
+    async fn ingest_sink(endpoint: String) -> ClickhouseSink {
+        let conf = Clickhouse {
+            endpoint,
+            batch: Some(100),
+            ingest_id_field: Some("_ingest_id".into()),
+            ..Default::default()
+        };
+        let mut sink = ClickhouseSink::new(conf, "events".into())
+            .await
+            .expect("build sink")
+            .with_name("ck_main".into());
+        for i in 0..3 {
+            sink.sink_record(&id_record(&i.to_string()))
+                .await
+                .expect("buffered");
+        }
+        sink
+    }
+
+    fn buffered_ingest_ids(sink: &ClickhouseSink) -> Vec<String> {
+        sink.values
+            .values()
+            .flatten()
+            .map(|line| {
+                let row: JsonValue = serde_json::from_str(line).expect("json row");
+                row["_ingest_id"].as_str().expect("stamped").to_string()
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn ingest_ids_are_reconstructable_and_stable_across_retries() {
+        let server = MockServer::start_async().await;
+        let mut failing = server.mock(|when, then| {
+            when.method(POST)
+                .query_param("query_id", "ck_main-0-events");
+            then.status(503).body("unavailable");
+        });
+        let mut sink = ingest_sink(server.base_url()).await;
+        let ids = buffered_ingest_ids(&sink);
+        let expected = (0..3)
+            .map(|i| ingest_id("ck_main", 0, i))
+            .collect::<Vec<_>>();
+        assert_eq!(ids, expected);
+        assert_eq!(
+            ids.iter().collect::<HashSet<_>>().len(),
+            3,
+            "unique per record"
+        );
+        // 同样的输入重新跑一遍得到相同的 id
+        let replay = ingest_sink(server.base_url()).await;
+        assert_eq!(buffered_ingest_ids(&replay), ids);
+
+        assert!(sink.stop().await.is_err());
+        failing.assert_hits(1);
+        failing.delete();
+        assert_eq!(
+            buffered_ingest_ids(&sink),
+            ids,
+            "retained rows keep their ids"
+        );
+
+        let retried = server.mock(|when, then| {
+            when.method(POST)
+                .query_param("query_id", "ck_main-1-events")
+                .body_contains("ck_main-0-2");
+            then.status(200);
+        });
+        sink.stop().await.expect("retry ok");
+        retried.assert_hits(1);
+        assert!(sink.values.is_empty());
+    }
+}
@@ -1,6 +0,0 @@
-mod adapter;
-mod factory;
-mod sink;
-
-pub use adapter::register_clickhouse_adapter;
-pub use factory::{register_builder, register_factory_only};
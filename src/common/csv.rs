@@ -0,0 +1,208 @@
+//! sink 侧 CSV 写出：列序由首条记录确定，之后新出现的字段按出现顺序追加到列尾；
+//! 表头按文件/对象输出一次。
+//!
+//! 字段值按 RFC 4180 引用：含 `,`、`"`、换行时以双引号包裹并将 `"` 写为 `""`；
+//! 记录中缺失的列写为空值。
+
+use wp_model_core::model::{DataRecord, DataType};
+
+/// CSV 列布局。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CsvColumns {
+    names: Vec<String>,
+}
+
+impl CsvColumns {
+    /// 由已有文件的表头行恢复列序（按 RFC 4180 解析引号）。
+    pub fn from_header(line: &str) -> Self {
+        let mut names = Vec::new();
+        let mut current = String::new();
+        let mut quoted = false;
+        let mut chars = line.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '"' if quoted && chars.peek() == Some(&'"') => {
+                    chars.next();
+                    current.push('"');
+                }
+                '"' => quoted = !quoted,
+                ',' if !quoted => names.push(std::mem::take(&mut current)),
+                _ => current.push(c),
+            }
+        }
+        names.push(current);
+        names.retain(|n| !n.is_empty());
+        Self { names }
+    }
+
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    /// 并入记录中尚未出现的字段（忽略 `Ignore` 类型字段）。
+    pub fn extend(&mut self, record: &DataRecord) {
+        for field in &record.items {
+            if *field.get_meta() == DataType::Ignore {
+                continue;
+            }
+            let name = field.get_name();
+            if !self.names.iter().any(|n| n == name) {
+                self.names.push(name.to_string());
+            }
+        }
+    }
+
+    /// 表头行（不含换行）。
+    pub fn header(&self) -> String {
+        self.names
+            .iter()
+            .map(|n| escape(n))
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    /// 按当前列序渲染一行（不含换行）；不在列中的字段不输出。
+    pub fn row(&self, record: &DataRecord) -> String {
+        self.names
+            .iter()
+            .map(|name| {
+                record
+                    .get2(name)
+                    .filter(|f| *f.get_meta() != DataType::Ignore)
+                    .map(|f| escape(&f.get_value().to_string()))
+                    .unwrap_or_default()
+            })
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+}
+
+/// 一个批次（如一个对象）的 CSV 内容：列取批内所有记录字段的并集，
+/// [`CsvBatch::finish`] 时输出一次表头，较早的行按最终列数补齐空值。
+#[derive(Debug, Default)]
+pub struct CsvBatch {
+    columns: CsvColumns,
+    /// 已渲染的行及渲染时的列数
+    rows: Vec<(String, usize)>,
+    bytes: usize,
+}
+
+impl CsvBatch {
+    pub fn push(&mut self, record: &DataRecord) {
+        self.columns.extend(record);
+        let row = self.columns.row(record);
+        self.bytes += row.len() + 1;
+        self.rows.push((row, self.columns.names.len()));
+    }
+
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// 已缓冲行的字节数（不含表头与补齐），用于滚动判断。
+    pub fn bytes(&self) -> usize {
+        self.bytes
+    }
+
+    pub fn columns(&self) -> &CsvColumns {
+        &self.columns
+    }
+
+    /// 表头加全部行，每行以 `\n` 结尾；空批次返回空内容。
+    pub fn finish(&self) -> Vec<u8> {
+        if self.rows.is_empty() {
+            return Vec::new();
+        }
+        let width = self.columns.names.len();
+        let mut out = String::with_capacity(self.bytes + width * (self.rows.len() + 8));
+        out.push_str(&self.columns.header());
+        out.push('\n');
+        for (row, cols) in &self.rows {
+            out.push_str(row);
+            for _ in *cols..width {
+                out.push(',');
+            }
+            out.push('\n');
+        }
+        out.into_bytes()
+    }
+
+    pub fn clear(&mut self) {
+        self.columns = CsvColumns::default();
+        self.rows.clear();
+        self.bytes = 0;
+    }
+}
+
+/// 按需为单个值加引号。
+pub fn escape(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wp_model_core::model::DataField;
+
+    fn record(fields: &[(&str, &str)]) -> DataRecord {
+        let mut record = DataRecord::default();
+        for (name, value) in fields {
+            record.append(DataField::from_chars(*name, *value));
+        }
+        record
+    }
+
+    #[test]
+    fn columns_follow_first_record_and_union_new_fields() {
+        let mut columns = CsvColumns::default();
+        columns.extend(&record(&[("host", "a"), ("msg", "x")]));
+        columns.extend(&record(&[("msg", "y"), ("level", "warn"), ("host", "b")]));
+        assert_eq!(columns.names(), ["host", "msg", "level"]);
+        assert_eq!(columns.header(), "host,msg,level");
+        assert_eq!(
+            columns.row(&record(&[("level", "info"), ("host", "c")])),
+            "c,,info"
+        );
+    }
+
+    #[test]
+    fn columns_restore_from_header_line() {
+        let columns = CsvColumns::from_header("host,\"a,\"\"b\"\"\",code");
+        assert_eq!(columns.names(), ["host", "a,\"b\"", "code"]);
+        assert_eq!(CsvColumns::from_header(&columns.header()), columns);
+        assert!(CsvColumns::from_header("").is_empty());
+    }
+
+    #[test]
+    fn batch_emits_header_once_and_pads_earlier_rows() {
+        let mut batch = CsvBatch::default();
+        assert!(batch.finish().is_empty());
+        batch.push(&record(&[("host", "a"), ("msg", "hello, world")]));
+        batch.push(&record(&[("host", "b"), ("code", "500")]));
+        batch.push(&record(&[("msg", "say \"hi\"")]));
+        assert_eq!(batch.len(), 3);
+        assert_eq!(
+            String::from_utf8(batch.finish()).unwrap(),
+            "host,msg,code\n\
+             a,\"hello, world\",\n\
+             b,,500\n\
+             ,\"say \"\"hi\"\"\",\n"
+        );
+
+        batch.clear();
+        batch.push(&record(&[("code", "200")]));
+        assert_eq!(String::from_utf8(batch.finish()).unwrap(), "code\n200\n");
+    }
+}
@@ -0,0 +1,245 @@
+//! Sink 侧静态字段富化：在格式化之前为每条记录追加固定字段（如 `cluster=prod`），
+//! 并按 `field_transforms` 变换字段值（见 [`crate::common::transform`]）。
+//!
+//! 配置示例：
+//! ```toml
+//! enrich = { cluster = "prod", region = "us-east" }
+//! enrich_overwrite = false   # 默认不覆盖记录中已存在的同名字段
+//! ```
+
+use async_trait::async_trait;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use wp_connector_api::{
+    AsyncCtrl, AsyncRawDataSink, AsyncRecordSink, ParamMap, SinkReason, SinkResult,
+};
+use wp_model_core::model::{DataField, DataRecord};
+
+use crate::common::transform::FieldTransforms;
+
+/// 静态富化配置。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EnrichConf {
+    pub fields: BTreeMap<String, String>,
+    pub overwrite: bool,
+}
+
+impl EnrichConf {
+    /// 从 sink 参数中读取 `enrich`（对象）与 `enrich_overwrite`（布尔）。
+    pub fn from_params(params: &ParamMap) -> SinkResult<Self> {
+        let mut conf = Self::default();
+        match params.get("enrich") {
+            None | Some(Value::Null) => {}
+            Some(Value::Object(map)) => {
+                for (key, value) in map {
+                    let key = key.trim();
+                    if key.is_empty() {
+                        return Err(SinkReason::sink("enrich keys must not be empty").into());
+                    }
+                    let value = match value {
+                        Value::String(s) => s.clone(),
+                        Value::Number(n) => n.to_string(),
+                        Value::Bool(b) => b.to_string(),
+                        _ => {
+                            return Err(SinkReason::sink(format!(
+                                "enrich.{key} must be a string, number or boolean"
+                            ))
+                            .into());
+                        }
+                    };
+                    conf.fields.insert(key.to_string(), value);
+                }
+            }
+            Some(_) => return Err(SinkReason::sink("enrich must be a table").into()),
+        }
+        if let Some(v) = params.get("enrich_overwrite") {
+            conf.overwrite = v
+                .as_bool()
+                .ok_or_else(|| SinkReason::sink("enrich_overwrite must be a boolean"))?;
+        }
+        Ok(conf)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    /// 将静态字段写入记录；未开启 overwrite 时保留记录中已有的同名字段。
+    pub fn apply(&self, record: &mut DataRecord) {
+        for (name, value) in &self.fields {
+            let exists = record.items.iter().any(|f| f.get_name() == name);
+            if exists {
+                if !self.overwrite {
+                    continue;
+                }
+                record.items.retain(|f| f.get_name() != name);
+            }
+            record.append(DataField::from_chars(name.as_str(), value.as_str()));
+        }
+    }
+}
+
+/// 富化装饰器：包裹任意 sink，在记录下发前追加静态字段并变换字段值；原始数据（raw）直接透传。
+pub struct EnrichSink<S> {
+    inner: S,
+    enrich: EnrichConf,
+    transforms: FieldTransforms,
+}
+
+impl<S> EnrichSink<S> {
+    pub fn new(inner: S, enrich: EnrichConf) -> Self {
+        Self {
+            inner,
+            enrich,
+            transforms: FieldTransforms::default(),
+        }
+    }
+
+    pub fn with_transforms(mut self, transforms: FieldTransforms) -> Self {
+        self.transforms = transforms;
+        self
+    }
+
+    fn is_noop(&self) -> bool {
+        self.enrich.is_empty() && self.transforms.is_empty()
+    }
+
+    fn prepare(&self, record: &mut DataRecord) {
+        self.enrich.apply(record);
+        self.transforms.apply(record);
+    }
+
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+}
+
+#[async_trait]
+impl<S: AsyncCtrl + Send> AsyncCtrl for EnrichSink<S> {
+    async fn stop(&mut self) -> SinkResult<()> {
+        self.inner.stop().await
+    }
+
+    async fn reconnect(&mut self) -> SinkResult<()> {
+        self.inner.reconnect().await
+    }
+}
+
+#[async_trait]
+impl<S: AsyncRecordSink + Send> AsyncRecordSink for EnrichSink<S> {
+    async fn sink_record(&mut self, data: &DataRecord) -> SinkResult<()> {
+        if self.is_noop() {
+            return self.inner.sink_record(data).await;
+        }
+        let mut record = data.clone();
+        self.prepare(&mut record);
+        self.inner.sink_record(&record).await
+    }
+
+    async fn sink_records(&mut self, data: Vec<Arc<DataRecord>>) -> SinkResult<()> {
+        if self.is_noop() {
+            return self.inner.sink_records(data).await;
+        }
+        let records = data
+            .into_iter()
+            .map(|item| {
+                let mut record = item.as_ref().clone();
+                self.prepare(&mut record);
+                Arc::new(record)
+            })
+            .collect();
+        self.inner.sink_records(records).await
+    }
+}
+
+#[async_trait]
+impl<S: AsyncRawDataSink + Send> AsyncRawDataSink for EnrichSink<S> {
+    async fn sink_str(&mut self, data: &str) -> SinkResult<()> {
+        self.inner.sink_str(data).await
+    }
+
+    async fn sink_bytes(&mut self, data: &[u8]) -> SinkResult<()> {
+        self.inner.sink_bytes(data).await
+    }
+
+    async fn sink_str_batch(&mut self, data: Vec<&str>) -> SinkResult<()> {
+        self.inner.sink_str_batch(data).await
+    }
+
+    async fn sink_bytes_batch(&mut self, data: Vec<&[u8]>) -> SinkResult<()> {
+        self.inner.sink_bytes_batch(data).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::testing::CaptureSink;
+    use serde_json::json;
+
+    fn params(overwrite: bool) -> ParamMap {
+        let mut params = ParamMap::new();
+        params.insert(
+            "enrich".into(),
+            json!({"cluster": "prod", "region": "us-east"}),
+        );
+        params.insert("enrich_overwrite".into(), json!(overwrite));
+        params
+    }
+
+    fn record() -> DataRecord {
+        let mut rec = DataRecord::default();
+        rec.append(DataField::from_chars("msg", "hello"));
+        rec.append(DataField::from_chars("region", "eu-west"));
+        rec
+    }
+
+    #[tokio::test]
+    async fn enrich_adds_static_fields_and_keeps_existing() {
+        let conf = EnrichConf::from_params(&params(false)).expect("valid enrich");
+        let mut sink = EnrichSink::new(CaptureSink::default(), conf);
+        sink.sink_record(&record()).await.unwrap();
+
+        let payload = &sink.inner().json_lines()[0];
+        assert_eq!(payload["cluster"], json!("prod"));
+        assert_eq!(payload["region"], json!("eu-west"));
+        assert_eq!(payload["msg"], json!("hello"));
+    }
+
+    #[tokio::test]
+    async fn enrich_overwrites_existing_when_enabled() {
+        let conf = EnrichConf::from_params(&params(true)).expect("valid enrich");
+        let mut sink = EnrichSink::new(CaptureSink::default(), conf);
+        sink.sink_records(vec![Arc::new(record())]).await.unwrap();
+
+        let payload = &sink.inner().json_lines()[0];
+        assert_eq!(payload["cluster"], json!("prod"));
+        assert_eq!(payload["region"], json!("us-east"));
+    }
+
+    #[tokio::test]
+    async fn transforms_run_after_enrich() {
+        let mut params = params(false);
+        params.insert(
+            "field_transforms".into(),
+            json!({"cluster": "uppercase", "msg": "sha256"}),
+        );
+        let conf = EnrichConf::from_params(&params).expect("valid enrich");
+        let transforms = FieldTransforms::from_params(&params).expect("valid transforms");
+        let mut sink = EnrichSink::new(CaptureSink::default(), conf).with_transforms(transforms);
+        sink.sink_record(&record()).await.unwrap();
+
+        let payload = &sink.inner().json_lines()[0];
+        assert_eq!(payload["cluster"], json!("PROD"));
+        assert_eq!(payload["region"], json!("eu-west"));
+        assert_eq!(payload["msg"].as_str().map(str::len), Some(64));
+    }
+
+    #[test]
+    fn enrich_rejects_nested_values() {
+        let mut params = ParamMap::new();
+        params.insert("enrich".into(), json!({"cluster": {"a": 1}}));
+        assert!(EnrichConf::from_params(&params).is_err());
+    }
+}
@@ -0,0 +1,109 @@
+//! 源端字段白名单：解析 JSON 负载时只构造白名单内的顶层字段，其余字段在解析阶段直接跳过。
+//!
+//! 与解析后再投影不同，被丢弃的字段不会被构造成 `serde_json::Value`，
+//! 对高基数、大字段的消息可显著降低解析开销与内存占用。
+
+use serde::de::{DeserializeSeed, Deserializer, IgnoredAny, MapAccess, Visitor};
+use serde_json::{Map, Value};
+use std::collections::HashSet;
+use std::fmt;
+
+/// 顶层 JSON 字段白名单。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FieldAllowlist {
+    fields: HashSet<String>,
+}
+
+impl FieldAllowlist {
+    pub fn new<I, S>(fields: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            fields: fields.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    /// 解析 JSON 对象，仅保留白名单字段。
+    pub fn parse(&self, payload: &[u8]) -> serde_json::Result<Map<String, Value>> {
+        let mut de = serde_json::Deserializer::from_slice(payload);
+        let map = AllowlistSeed(&self.fields).deserialize(&mut de)?;
+        de.end()?;
+        Ok(map)
+    }
+
+    /// 解析并重新序列化为仅包含白名单字段的 JSON 负载。
+    pub fn project(&self, payload: &[u8]) -> serde_json::Result<Vec<u8>> {
+        let map = self.parse(payload)?;
+        serde_json::to_vec(&Value::Object(map))
+    }
+}
+
+struct AllowlistSeed<'a>(&'a HashSet<String>);
+
+impl<'de> DeserializeSeed<'de> for AllowlistSeed<'_> {
+    type Value = Map<String, Value>;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de> Visitor<'de> for AllowlistSeed<'_> {
+    type Value = Map<String, Value>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a JSON object")
+    }
+
+    fn visit_map<A>(self, mut access: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut out = Map::new();
+        while let Some(key) = access.next_key::<String>()? {
+            if self.0.contains(&key) {
+                let value = access.next_value::<Value>()?;
+                out.insert(key, value);
+            } else {
+                access.next_value::<IgnoredAny>()?;
+            }
+        }
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn allowlist_keeps_only_listed_keys() {
+        let allow = FieldAllowlist::new(["host", "level"]);
+        let payload = br#"{"a":1,"b":[1,2,3],"host":"h1","c":{"x":"y"},"d":null,
+            "level":"warn","e":true,"f":"big","g":2.5,"h":"z"}"#;
+        let map = allow.parse(payload).expect("parse ok");
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.get("host"), Some(&Value::String("h1".into())));
+        assert_eq!(map.get("level"), Some(&Value::String("warn".into())));
+    }
+
+    #[test]
+    fn allowlist_project_rejects_non_object() {
+        let allow = FieldAllowlist::new(["host"]);
+        assert!(allow.project(b"[1,2]").is_err());
+        assert!(allow.project(b"{\"host\":").is_err());
+        assert_eq!(
+            allow.project(br#"{"host":"h","x":1}"#).unwrap(),
+            br#"{"host":"h"}"#.to_vec()
+        );
+    }
+}
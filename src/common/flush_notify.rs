@@ -0,0 +1,179 @@
+//! Sink 侧 flush 完成通知：每批数据成功写出后回调（或发送到通道），
+//! 携带本批条数与高水位信息，供外部协调方据此推进自身的 checkpoint。
+//!
+//! 未设置回调时 [`FlushNotifier::notify`] 只累计计数，不产生其他开销。
+//!
+//! sink 参数 `flush_notify = "<通道名>"` 把通知发布到进程内的命名通道，
+//! 外部协调方（或 Kafka source 的 `ack_channel`）以 [`subscribe`] 订阅同名通道：
+//!
+//! ```toml
+//! flush_notify = "events-ckpt"
+//! ```
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex, OnceLock, PoisonError};
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender, unbounded_channel};
+use wp_connector_api::{ParamMap, SinkError, SinkReason, SinkResult};
+
+/// 一次成功 flush 的描述。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FlushEvent {
+    /// 写出的目标（表名、主题等）
+    pub target: String,
+    /// 本批写出的记录数
+    pub records: usize,
+    /// 自 sink 创建以来累计成功写出的记录数
+    pub total_records: u64,
+    /// 本批写出记录的 `wp_event_id`（按到达顺序）；没有该字段的记录不计入
+    pub event_ids: Vec<String>,
+    /// 本批最后一条记录的 `wp_event_id`，即 `event_ids` 的末项
+    pub last_event_id: Option<String>,
+}
+
+type FlushCallback = Arc<dyn Fn(&FlushEvent) + Send + Sync>;
+
+/// flush 完成通知器；默认不通知。
+#[derive(Clone, Default)]
+pub struct FlushNotifier {
+    callback: Option<FlushCallback>,
+    total_records: u64,
+}
+
+impl fmt::Debug for FlushNotifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FlushNotifier")
+            .field("enabled", &self.callback.is_some())
+            .field("total_records", &self.total_records)
+            .finish()
+    }
+}
+
+impl FlushNotifier {
+    /// 以回调接收通知；回调在 sink 的 flush 路径上同步执行，应尽快返回。
+    pub fn from_fn(callback: impl Fn(&FlushEvent) + Send + Sync + 'static) -> Self {
+        Self {
+            callback: Some(Arc::new(callback)),
+            total_records: 0,
+        }
+    }
+
+    /// 以通道接收通知；接收端关闭后的通知被忽略。
+    pub fn from_channel(tx: UnboundedSender<FlushEvent>) -> Self {
+        Self::from_fn(move |event| {
+            let _ = tx.send(event.clone());
+        })
+    }
+
+    /// 发布到命名通道，通知送达该名称当前的全部订阅者（见 [`subscribe`]）；没有订阅者时忽略。
+    pub fn named(name: &str) -> Self {
+        let name = name.to_string();
+        Self::from_fn(move |event| {
+            let mut channels = channels().lock().unwrap_or_else(PoisonError::into_inner);
+            if let Some(subscribers) = channels.get_mut(&name) {
+                subscribers.retain(|tx| tx.send(event.clone()).is_ok());
+            }
+        })
+    }
+
+    /// 读取 sink 参数 `flush_notify`（命名通道）；未配置时返回不通知的默认值。
+    pub fn from_params(params: &ParamMap) -> SinkResult<Self> {
+        match params.get("flush_notify") {
+            None | Some(serde_json::Value::Null) => Ok(Self::default()),
+            Some(serde_json::Value::String(name)) if !name.trim().is_empty() => {
+                Ok(Self::named(name.trim()))
+            }
+            Some(_) => Err(SinkError::from(SinkReason::Sink(
+                "flush_notify must be a non-empty channel name".into(),
+            ))),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.callback.is_some()
+    }
+
+    /// 记录一次成功 flush 并通知。
+    ///
+    /// # args
+    /// * `target` - 写出的目标。
+    /// * `records` - 本批记录数。
+    /// * `event_ids` - 本批写出记录的 `wp_event_id`。
+    pub fn notify(&mut self, target: &str, records: usize, event_ids: Vec<String>) {
+        self.total_records += records as u64;
+        if let Some(callback) = &self.callback {
+            callback(&FlushEvent {
+                target: target.to_string(),
+                records,
+                total_records: self.total_records,
+                last_event_id: event_ids.last().cloned(),
+                event_ids,
+            });
+        }
+    }
+}
+
+fn channels() -> &'static Mutex<HashMap<String, Vec<UnboundedSender<FlushEvent>>>> {
+    static CHANNELS: OnceLock<Mutex<HashMap<String, Vec<UnboundedSender<FlushEvent>>>>> =
+        OnceLock::new();
+    CHANNELS.get_or_init(Default::default)
+}
+
+/// 订阅命名通道 `name` 上的 flush 通知；接收端释放后自动退订。
+pub fn subscribe(name: &str) -> UnboundedReceiver<FlushEvent> {
+    let (tx, rx) = unbounded_channel();
+    channels()
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .entry(name.to_string())
+        .or_default()
+        .push(tx);
+    rx
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn channel_receives_cumulative_counts() {
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let mut notifier = FlushNotifier::from_channel(tx);
+        notifier.notify("events", 3, vec!["3".into()]);
+        notifier.notify("audit", 2, Vec::new());
+        assert_eq!(rx.try_recv().unwrap().total_records, 3);
+        let second = rx.try_recv().unwrap();
+        assert_eq!((second.target.as_str(), second.records), ("audit", 2));
+        assert_eq!(second.total_records, 5);
+
+        drop(rx);
+        notifier.notify("events", 1, Vec::new());
+
+        let mut noop = FlushNotifier::default();
+        noop.notify("events", 1, Vec::new());
+        assert!(!noop.is_enabled());
+    }
+
+    #[test]
+    fn flush_notify_param_publishes_to_named_subscribers() {
+        let params = ParamMap::from([(
+            "flush_notify".to_string(),
+            serde_json::json!("notify-test-ckpt"),
+        )]);
+        let mut notifier = FlushNotifier::from_params(&params).expect("valid");
+        let mut rx = subscribe("notify-test-ckpt");
+        notifier.notify("events", 2, vec!["41".into(), "42".into()]);
+        let event = rx.try_recv().expect("delivered");
+        assert_eq!(event.records, 2);
+        assert_eq!(event.event_ids, ["41", "42"]);
+        assert_eq!(event.last_event_id.as_deref(), Some("42"));
+
+        assert!(
+            !FlushNotifier::from_params(&ParamMap::new())
+                .expect("unset")
+                .is_enabled()
+        );
+        let bad = ParamMap::from([("flush_notify".to_string(), serde_json::json!(1))]);
+        assert!(FlushNotifier::from_params(&bad).is_err());
+    }
+}
@@ -0,0 +1,328 @@
+//! 源端字节流分帧：把按任意边界到达的数据块切分为完整的记录负载。
+//!
+//! 配置示例：
+//! ```toml
+//! framing = "length_prefixed"   # newline | length_prefixed | raw，默认 newline
+//! length_width = 4              # 长度头字节数：1 | 2 | 4 | 8，默认 4
+//! length_endian = "big"         # big | little，默认 big
+//! max_frame_bytes = 16777216    # 单帧上限，防止损坏的长度头触发超大分配
+//! ```
+//!
+//! 目前由 TCP source 使用；消息队列类 source 的每条消息本身即一帧，不经过分帧。
+
+use serde_json::Value;
+use std::str::FromStr;
+use wp_connector_api::ParamMap;
+
+const DEFAULT_LENGTH_WIDTH: usize = 4;
+const DEFAULT_MAX_FRAME_BYTES: usize = 16 * 1024 * 1024;
+
+/// 长度头的字节序。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Endian {
+    #[default]
+    Big,
+    Little,
+}
+
+/// 分帧方式。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Framing {
+    /// 以 `\n` 分隔，去掉行尾 `\r`
+    #[default]
+    Newline,
+    /// 定宽长度头 + 对应字节数的负载
+    LengthPrefixed { width: usize, endian: Endian },
+    /// 每个数据块原样作为一帧
+    Raw,
+}
+
+impl FromStr for Framing {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "newline" => Ok(Self::Newline),
+            "length_prefixed" => Ok(Self::LengthPrefixed {
+                width: DEFAULT_LENGTH_WIDTH,
+                endian: Endian::Big,
+            }),
+            "raw" => Ok(Self::Raw),
+            other => Err(format!(
+                "invalid framing '{other}'; allowed: newline,length_prefixed,raw"
+            )),
+        }
+    }
+}
+
+/// 分帧配置。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FramingConf {
+    pub framing: Framing,
+    pub max_frame_bytes: usize,
+}
+
+impl Default for FramingConf {
+    fn default() -> Self {
+        Self {
+            framing: Framing::Newline,
+            max_frame_bytes: DEFAULT_MAX_FRAME_BYTES,
+        }
+    }
+}
+
+impl FramingConf {
+    /// 读取 `framing`/`length_width`/`length_endian`/`max_frame_bytes` 参数。
+    pub fn from_params(params: &ParamMap) -> Result<Self, String> {
+        let mut framing = match params.get("framing") {
+            None | Some(Value::Null) => Framing::Newline,
+            Some(Value::String(s)) => s.parse()?,
+            Some(_) => return Err("framing must be a string".into()),
+        };
+        if let Framing::LengthPrefixed { width, endian } = &mut framing {
+            if let Some(v) = params.get("length_width") {
+                *width = match v.as_u64() {
+                    Some(n @ (1 | 2 | 4 | 8)) => n as usize,
+                    _ => return Err("length_width must be one of 1, 2, 4, 8".into()),
+                };
+            }
+            if let Some(v) = params.get("length_endian") {
+                *endian = match v.as_str().map(str::to_ascii_lowercase).as_deref() {
+                    Some("big") => Endian::Big,
+                    Some("little") => Endian::Little,
+                    _ => return Err("length_endian must be 'big' or 'little'".into()),
+                };
+            }
+        }
+        let max_frame_bytes = match params.get("max_frame_bytes") {
+            None | Some(Value::Null) => DEFAULT_MAX_FRAME_BYTES,
+            Some(v) => match v.as_u64() {
+                Some(n) if n > 0 => n as usize,
+                _ => return Err("max_frame_bytes must be > 0".into()),
+            },
+        };
+        Ok(Self {
+            framing,
+            max_frame_bytes,
+        })
+    }
+}
+
+/// 分帧失败：`frames` 为同一数据块中出错前已切出的完整帧，调用方应先交付它们再断开。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrameError {
+    pub frames: Vec<Vec<u8>>,
+    pub reason: String,
+}
+
+impl std::fmt::Display for FrameError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.reason)
+    }
+}
+
+/// 增量分帧器：跨数据块保留未完整的帧。
+#[derive(Debug)]
+pub struct FrameDecoder {
+    conf: FramingConf,
+    buf: Vec<u8>,
+}
+
+impl FrameDecoder {
+    pub fn new(conf: FramingConf) -> Self {
+        Self {
+            conf,
+            buf: Vec::new(),
+        }
+    }
+
+    /// 追加一个数据块，返回其中已完整的帧。
+    ///
+    /// # return
+    /// * `Result<Vec<Vec<u8>>, FrameError>` - 帧超过 `max_frame_bytes` 时返回错误，
+    ///   错误中带回此前已切出的帧。
+    pub fn push(&mut self, chunk: &[u8]) -> Result<Vec<Vec<u8>>, FrameError> {
+        match self.conf.framing {
+            Framing::Raw => Ok(if chunk.is_empty() {
+                Vec::new()
+            } else {
+                vec![chunk.to_vec()]
+            }),
+            Framing::Newline => {
+                self.buf.extend_from_slice(chunk);
+                let mut frames = Vec::new();
+                while let Some(pos) = self.buf.iter().position(|b| *b == b'\n') {
+                    let mut line = self.buf.drain(..=pos).collect::<Vec<_>>();
+                    line.pop();
+                    if line.last() == Some(&b'\r') {
+                        line.pop();
+                    }
+                    frames.push(line);
+                }
+                if self.buf.len() > self.conf.max_frame_bytes {
+                    return Err(FrameError {
+                        frames,
+                        reason: format!(
+                            "line exceeds max_frame_bytes {}",
+                            self.conf.max_frame_bytes
+                        ),
+                    });
+                }
+                Ok(frames)
+            }
+            Framing::LengthPrefixed { width, endian } => {
+                self.buf.extend_from_slice(chunk);
+                let mut frames = Vec::new();
+                let mut offset = 0;
+                while self.buf.len() - offset >= width {
+                    let len = read_length(&self.buf[offset..offset + width], endian);
+                    if len > self.conf.max_frame_bytes as u64 {
+                        self.buf.drain(..offset);
+                        return Err(FrameError {
+                            frames,
+                            reason: format!(
+                                "frame length {} exceeds max_frame_bytes {}",
+                                len, self.conf.max_frame_bytes
+                            ),
+                        });
+                    }
+                    let end = offset + width + len as usize;
+                    if self.buf.len() < end {
+                        break;
+                    }
+                    frames.push(self.buf[offset + width..end].to_vec());
+                    offset = end;
+                }
+                self.buf.drain(..offset);
+                Ok(frames)
+            }
+        }
+    }
+
+    /// 流结束时取出剩余数据：按行分帧时未以换行结尾的最后一行作为一帧；
+    /// 长度前缀分帧时残留半帧视为截断错误。
+    pub fn finish(&mut self) -> Result<Option<Vec<u8>>, String> {
+        if self.buf.is_empty() {
+            return Ok(None);
+        }
+        let rest = std::mem::take(&mut self.buf);
+        match self.conf.framing {
+            Framing::LengthPrefixed { .. } => Err(format!(
+                "stream ended inside a frame ({} bytes left)",
+                rest.len()
+            )),
+            _ => Ok(Some(rest)),
+        }
+    }
+}
+
+/// 按字节序解析长度头（宽度不超过 8 字节）。
+fn read_length(header: &[u8], endian: Endian) -> u64 {
+    let fold = |acc: u64, b: &u8| (acc << 8) | u64::from(*b);
+    match endian {
+        Endian::Big => header.iter().fold(0, fold),
+        Endian::Little => header.iter().rev().fold(0, fold),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn length_prefixed(width: usize, endian: Endian) -> FrameDecoder {
+        FrameDecoder::new(FramingConf {
+            framing: Framing::LengthPrefixed { width, endian },
+            max_frame_bytes: 1024,
+        })
+    }
+
+    #[test]
+    fn length_prefixed_frames_split_across_chunk_boundaries() {
+        let payloads: [&[u8]; 3] = [b"hello", b"", b"{\"a\":\"line\nbreak\"}"];
+        let mut stream = Vec::new();
+        for p in payloads {
+            stream.extend_from_slice(&(p.len() as u32).to_be_bytes());
+            stream.extend_from_slice(p);
+        }
+        for chunk_size in [1, 3, 7, stream.len()] {
+            let mut decoder = length_prefixed(4, Endian::Big);
+            let mut frames = Vec::new();
+            for chunk in stream.chunks(chunk_size) {
+                frames.extend(decoder.push(chunk).unwrap());
+            }
+            assert_eq!(
+                frames,
+                payloads.map(<[u8]>::to_vec),
+                "chunk size {chunk_size}"
+            );
+            assert_eq!(decoder.finish().unwrap(), None);
+        }
+
+        let mut decoder = length_prefixed(2, Endian::Little);
+        assert_eq!(
+            decoder.push(&[3, 0, b'a', b'b']).unwrap(),
+            Vec::<Vec<u8>>::new()
+        );
+        assert_eq!(decoder.push(&[b'c', 9]).unwrap(), vec![b"abc".to_vec()]);
+        assert!(decoder.finish().is_err(), "truncated header");
+    }
+
+    #[test]
+    fn oversized_frames_are_rejected_after_returning_complete_ones() {
+        let mut decoder = length_prefixed(4, Endian::Big);
+        let mut chunk = 2u32.to_be_bytes().to_vec();
+        chunk.extend_from_slice(b"ok");
+        chunk.extend_from_slice(&u32::MAX.to_be_bytes());
+        let err = decoder.push(&chunk).expect_err("oversized header");
+        assert_eq!(err.frames, vec![b"ok".to_vec()]);
+        assert!(err.reason.contains("exceeds max_frame_bytes"), "{err}");
+
+        let mut decoder = FrameDecoder::new(FramingConf {
+            max_frame_bytes: 4,
+            ..Default::default()
+        });
+        let err = decoder
+            .push(b"a\nbc\nlong-line")
+            .expect_err("oversized line");
+        assert_eq!(err.frames, vec![b"a".to_vec(), b"bc".to_vec()]);
+    }
+
+    #[test]
+    fn newline_and_raw_framing() {
+        let mut decoder = FrameDecoder::new(FramingConf::default());
+        assert_eq!(decoder.push(b"a\r\nb").unwrap(), vec![b"a".to_vec()]);
+        assert_eq!(decoder.push(b"c\n").unwrap(), vec![b"bc".to_vec()]);
+        decoder.push(b"tail").unwrap();
+        assert_eq!(decoder.finish().unwrap(), Some(b"tail".to_vec()));
+
+        let mut raw = FrameDecoder::new(FramingConf {
+            framing: Framing::Raw,
+            ..Default::default()
+        });
+        assert_eq!(raw.push(b"x\ny").unwrap(), vec![b"x\ny".to_vec()]);
+    }
+
+    #[test]
+    fn framing_conf_from_params() {
+        let mut params = ParamMap::new();
+        assert_eq!(
+            FramingConf::from_params(&params).unwrap(),
+            FramingConf::default()
+        );
+        params.insert("framing".into(), json!("length_prefixed"));
+        params.insert("length_width".into(), json!(2));
+        params.insert("length_endian".into(), json!("little"));
+        assert_eq!(
+            FramingConf::from_params(&params).unwrap().framing,
+            Framing::LengthPrefixed {
+                width: 2,
+                endian: Endian::Little
+            }
+        );
+        params.insert("length_width".into(), json!(3));
+        assert!(FramingConf::from_params(&params).is_err());
+        params.insert("framing".into(), json!("csv"));
+        assert!(FramingConf::from_params(&params).is_err());
+    }
+}
@@ -0,0 +1,149 @@
+//! sink 构建前的连通性预检：`validate_spec` 只校验参数形态，预检则实际连接目标
+//! （ClickHouse/ES 的 HTTP 探测、MySQL/Doris 的 `SELECT 1`），让错误的地址或凭据在写入首条记录前暴露。
+//!
+//! 工厂通过实现 [`CheckConnection`] 提供预检；无法低成本检查的连接器沿用默认实现，
+//! 返回 [`ReadinessState::Unchecked`]。[`check_connection`] 按 `kind` 分派到内置工厂。
+
+use async_trait::async_trait;
+use serde::Serialize;
+use std::future::Future;
+use std::time::{Duration, Instant};
+use wp_connector_api::{SinkFactory, SinkSpec};
+
+/// 单次预检的超时
+pub const CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReadinessState {
+    Ready,
+    /// 参数无法解析、连接失败或目标返回异常
+    NotReady,
+    /// 连接器未提供预检
+    Unchecked,
+}
+
+/// 预检结果。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Readiness {
+    pub kind: String,
+    pub state: ReadinessState,
+    /// 被检查的地址（不含凭据）
+    pub target: Option<String>,
+    /// 失败原因或目标返回的状态描述
+    pub detail: Option<String>,
+    pub elapsed_ms: u64,
+}
+
+impl Readiness {
+    pub fn unchecked(kind: &str) -> Self {
+        Self {
+            kind: kind.to_string(),
+            state: ReadinessState::Unchecked,
+            target: None,
+            detail: None,
+            elapsed_ms: 0,
+        }
+    }
+
+    /// 检查前即失败（如参数无法解析）。
+    pub fn not_ready(kind: &str, detail: impl ToString) -> Self {
+        Self {
+            state: ReadinessState::NotReady,
+            detail: Some(detail.to_string()),
+            ..Self::unchecked(kind)
+        }
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.state == ReadinessState::Ready
+    }
+}
+
+/// `SinkFactory` 的预检扩展；默认不检查。
+#[async_trait]
+pub trait CheckConnection: SinkFactory {
+    async fn check_connection(&self, _spec: &SinkSpec) -> Readiness {
+        Readiness::unchecked(self.kind())
+    }
+}
+
+/// 在 [`CHECK_TIMEOUT`] 内执行探测并计时；探测返回 `Ok(detail)` 时为就绪。
+pub async fn probe<F>(kind: &str, target: &str, check: F) -> Readiness
+where
+    F: Future<Output = Result<Option<String>, String>>,
+{
+    let started = Instant::now();
+    let result = match tokio::time::timeout(CHECK_TIMEOUT, check).await {
+        Ok(result) => result,
+        Err(_) => Err(format!("timed out after {}s", CHECK_TIMEOUT.as_secs())),
+    };
+    let (state, detail) = match result {
+        Ok(detail) => (ReadinessState::Ready, detail),
+        Err(err) => (ReadinessState::NotReady, Some(err)),
+    };
+    Readiness {
+        kind: kind.to_string(),
+        state,
+        target: Some(target.to_string()),
+        detail,
+        elapsed_ms: started.elapsed().as_millis() as u64,
+    }
+}
+
+/// 按 `spec.kind` 执行内置工厂的预检；未提供预检的 kind 返回 `Unchecked`。
+pub async fn check_connection(spec: &SinkSpec) -> Readiness {
+    match spec.kind.as_str() {
+        #[cfg(feature = "mysql")]
+        "mysql" => crate::mysql::MySQLSinkFactory.check_connection(spec).await,
+        #[cfg(feature = "doris")]
+        "doris" => crate::doris::DorisSinkFactory.check_connection(spec).await,
+        #[cfg(feature = "clickhouse")]
+        "clickhouse" => {
+            crate::clickhouse::ClickhouseSinkFactory
+                .check_connection(spec)
+                .await
+        }
+        #[cfg(feature = "elasticsearch")]
+        "elasticsearch" => {
+            crate::elasticsearch::ElasticsearchSinkFactory
+                .check_connection(spec)
+                .await
+        }
+        other => Readiness::unchecked(other),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn probe_reports_state_and_detail() {
+        let ready = probe("clickhouse", "http://ck:8123", async { Ok(None) }).await;
+        assert!(ready.is_ready());
+        assert_eq!(ready.target.as_deref(), Some("http://ck:8123"));
+
+        let down = probe("clickhouse", "http://ck:8123", async {
+            Err("connection refused".to_string())
+        })
+        .await;
+        assert_eq!(down.state, ReadinessState::NotReady);
+        assert_eq!(down.detail.as_deref(), Some("connection refused"));
+    }
+
+    #[tokio::test]
+    async fn kinds_without_check_are_unchecked() {
+        let spec = SinkSpec {
+            name: "out".into(),
+            kind: "null".into(),
+            connector_id: String::new(),
+            group: String::new(),
+            params: Default::default(),
+            filter: None,
+        };
+        let readiness = check_connection(&spec).await;
+        assert_eq!(readiness.state, ReadinessState::Unchecked);
+        assert_eq!(readiness.kind, "null");
+    }
+}
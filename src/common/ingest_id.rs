@@ -0,0 +1,7 @@
+//! 确定性摄入 id：sink 为每条记录生成 `<sink>-<批次序号>-<批内序号>` 写入 `ingest_id_field`，
+//! 重试时同一批次的 id 不变，下游可据此去重或回溯到批次（ClickHouse、Elasticsearch sink 共用）。
+
+/// 确定性摄入 id：`<sink>-<批次序号>-<批内序号>`，可由三者重新拼出。
+pub fn ingest_id(sink: &str, batch_seq: u64, index: usize) -> String {
+    format!("{}-{}-{}", sink, batch_seq, index)
+}
@@ -0,0 +1,59 @@
+//! 跨连接器共享的通用组件。
+//!
+//! - field_allowlist：源端按白名单解析 JSON 顶层字段
+//! - framing：源端字节流分帧（换行、长度前缀、原样）
+//! - csv：文件/对象类 sink 的 CSV 写出（表头与列并集）
+//! - enrich：sink 侧静态字段富化装饰器
+//! - quarantine：解析失败数据的隔离 sink
+//! - transform：sink 侧字段值变换（大小写、去空白、哈希、掩码）
+//! - reconnect：按后端共享的重连退避协调器
+//! - schema_file：DB sink 从文件加载列类型映射
+//! - secret：构建期按 `secret_ref` 从 secret 存储注入敏感参数
+//! - table_route：sink 侧按记录字段渲染目标表名
+//! - params：sink 参数的通用读取（字符串数组等）
+//! - partition：文件类 sink 按记录时间/字段渲染分区目录
+//! - record_time：记录时间字段解析（分区目录、VictoriaLogs `_time` 共用）
+//! - type_map：字段类型到各 SQL 方言列类型的映射（自动建表）
+//! - stats：连接器运行状态快照（`/stats` 自省）
+//! - health：sink 构建前的连通性预检
+//! - flush_notify：sink 成功 flush 后通知外部协调方
+//! - ingest_id：sink 写入记录的确定性摄入 id
+//! - trace_context：HTTP 类 sink 请求附加 W3C `traceparent`/`tracestate` 头
+//! - reconfigure：sink 运行期参数（批量大小、重试等）热更新
+//! - pull_batch：消息队列类 source 的攒批拉取（NATS、Pulsar source 共用）
+//! - retry：sink 写入失败按退避重试的通用装饰器
+//! - url_query：dev 适配器连接串查询参数的解析
+//! - sigv4：AWS Signature V4 请求签名（S3、OpenSearch sink 共用）
+//! - tls：HTTP 类 sink 的 TLS 客户端配置（ClickHouse、Elasticsearch sink 共用）
+
+pub mod csv;
+pub mod enrich;
+pub mod field_allowlist;
+pub mod flush_notify;
+pub mod framing;
+pub mod health;
+pub mod ingest_id;
+pub mod params;
+pub mod partition;
+#[cfg(any(feature = "nats", feature = "pulsar"))]
+pub mod pull_batch;
+pub mod quarantine;
+pub mod reconfigure;
+pub mod reconnect;
+pub mod record_time;
+pub mod retry;
+pub mod schema_file;
+pub mod secret;
+#[cfg(any(feature = "s3", feature = "opensearch"))]
+pub mod sigv4;
+pub mod stats;
+pub mod table_route;
+#[cfg(any(feature = "elasticsearch", feature = "clickhouse"))]
+pub mod tls;
+pub mod trace_context;
+pub mod transform;
+pub mod type_map;
+pub mod url_query;
+
+#[cfg(test)]
+pub(crate) mod testing;
@@ -0,0 +1,51 @@
+//! sink 参数的通用读取。
+
+use wp_connector_api::{ParamMap, SinkReason, SinkResult};
+
+/// 可选的字符串数组参数，如 `endpoints`、`tls_insecure_hosts`；元素去除首尾空白，须非空。
+///
+/// # args
+/// * `sink` - 错误信息中的 sink 名称。
+pub fn string_list(params: &ParamMap, key: &str, sink: &str) -> SinkResult<Vec<String>> {
+    let Some(value) = params.get(key).filter(|v| !v.is_null()) else {
+        return Ok(Vec::new());
+    };
+    let Some(arr) = value.as_array() else {
+        return Err(SinkReason::sink(format!("{sink}.{key} must be an array")).into());
+    };
+    let mut items = Vec::with_capacity(arr.len());
+    for item in arr {
+        match item.as_str().map(str::trim) {
+            Some(s) if !s.is_empty() => items.push(s.to_string()),
+            _ => {
+                return Err(SinkReason::sink(format!(
+                    "{sink}.{key} entries must be non-empty string"
+                ))
+                .into());
+            }
+        }
+    }
+    Ok(items)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn string_list_trims_and_rejects_blank_entries() {
+        let mut params = ParamMap::new();
+        assert!(string_list(&params, "hosts", "es").unwrap().is_empty());
+        params.insert("hosts".into(), json!([" a.local ", "b.local"]));
+        assert_eq!(
+            string_list(&params, "hosts", "es").unwrap(),
+            vec!["a.local", "b.local"]
+        );
+        params.insert("hosts".into(), json!(["a.local", " "]));
+        let err = string_list(&params, "hosts", "es").unwrap_err();
+        assert!(err.to_string().contains("es.hosts"), "{err}");
+        params.insert("hosts".into(), json!("a.local"));
+        assert!(string_list(&params, "hosts", "es").is_err());
+    }
+}
@@ -0,0 +1,237 @@
+//! 文件类 sink 的分区目录模板：`partition_template = "{table}/dt=%Y-%m-%d/hour=%H"`
+//! 中的 strftime 占位符以记录时间（`partition_time_field`）格式化，`{field}` 以记录中同名
+//! 字段的值替换，渲染结果为相对目录，供 Hive/Spark 按 `dt=`/`hour=` 分区读取。
+//!
+//! 记录时间取自时间值、epoch 毫秒或 RFC3339 字符串，缺失或无法解析时使用当前时间；
+//! 字段缺失、为空或仅由 `.` 组成时替换为 Hive 的默认分区名，字段值中除字母、数字与
+//! `_-.=` 以外的字符一律替换为 `_`，避免越出分区目录。
+
+use chrono::NaiveDateTime;
+use chrono::format::{Item, StrftimeItems};
+use serde_json::Value as JsonValue;
+use wp_connector_api::{ParamMap, SinkReason, SinkResult};
+use wp_model_core::model::{DataRecord, DataType};
+
+use crate::common::record_time::record_time;
+
+/// 字段缺失或为空时使用的分区目录名（与 Hive 一致）
+pub const DEFAULT_PARTITION: &str = "__HIVE_DEFAULT_PARTITION__";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    /// 原样保留的片段，可含 strftime 占位符
+    Literal(String),
+    Field(String),
+}
+
+/// 已解析的分区目录模板。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PartitionTemplate {
+    parts: Vec<Part>,
+    time_field: Option<String>,
+}
+
+impl PartitionTemplate {
+    /// 解析模板；花括号须成对、占位符非空，strftime 占位符须可识别，且不得为绝对路径或含 `..`。
+    pub fn parse(raw: &str, time_field: Option<String>) -> Result<Self, String> {
+        let raw = raw.trim().trim_end_matches('/');
+        if raw.is_empty() {
+            return Err("partition_template must not be empty".into());
+        }
+        if raw.starts_with('/') || raw.split('/').any(|seg| seg == "..") {
+            return Err(format!(
+                "partition_template '{raw}' must be a relative path without '..'"
+            ));
+        }
+        let mut parts = Vec::new();
+        let mut rest = raw;
+        while let Some(start) = rest.find('{') {
+            let (literal, tail) = rest.split_at(start);
+            if literal.contains('}') {
+                return Err(format!("partition_template '{raw}' has an unmatched '}}'"));
+            }
+            let end = tail
+                .find('}')
+                .ok_or_else(|| format!("partition_template '{raw}' has an unmatched '{{'"))?;
+            let field = tail[1..end].trim();
+            if field.is_empty() || field.contains('{') {
+                return Err(format!(
+                    "partition_template '{raw}' has an empty placeholder"
+                ));
+            }
+            if !literal.is_empty() {
+                parts.push(Part::Literal(literal.to_string()));
+            }
+            parts.push(Part::Field(field.to_string()));
+            rest = &tail[end + 1..];
+        }
+        if rest.contains('}') {
+            return Err(format!("partition_template '{raw}' has an unmatched '}}'"));
+        }
+        if !rest.is_empty() {
+            parts.push(Part::Literal(rest.to_string()));
+        }
+        for part in &parts {
+            if let Part::Literal(text) = part
+                && StrftimeItems::new(text).any(|item| item == Item::Error)
+            {
+                return Err(format!(
+                    "partition_template '{raw}' has an invalid strftime pattern"
+                ));
+            }
+        }
+        Ok(Self { parts, time_field })
+    }
+
+    /// 读取 `partition_template` 与 `partition_time_field` 参数；`kind` 用于错误信息前缀。
+    pub fn from_params(params: &ParamMap, kind: &str) -> SinkResult<Option<Self>> {
+        let time_field = match params.get("partition_time_field") {
+            None | Some(JsonValue::Null) => None,
+            Some(JsonValue::String(field)) if !field.trim().is_empty() => {
+                Some(field.trim().to_string())
+            }
+            Some(_) => {
+                return Err(SinkReason::sink(format!(
+                    "{kind}.partition_time_field must be a non-empty string"
+                ))
+                .into());
+            }
+        };
+        match params.get("partition_template") {
+            None | Some(JsonValue::Null) => Ok(None),
+            Some(JsonValue::String(raw)) => Self::parse(raw, time_field)
+                .map(Some)
+                .map_err(|e| SinkReason::sink(format!("{kind}.{e}")).into()),
+            Some(_) => {
+                Err(SinkReason::sink(format!("{kind}.partition_template must be a string")).into())
+            }
+        }
+    }
+
+    /// 按记录渲染分区目录（相对路径，不含首尾 `/`）；记录无可用时间时使用 `now`。
+    pub fn render(&self, record: &DataRecord, now: NaiveDateTime) -> String {
+        let at = self
+            .time_field
+            .as_deref()
+            .and_then(|field| record_time(record, field))
+            .unwrap_or(now);
+        let mut dir = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Literal(text) => dir.push_str(&at.format(text).to_string()),
+                Part::Field(name) => {
+                    let value = record
+                        .items
+                        .iter()
+                        .filter(|f| *f.get_meta() != DataType::Ignore)
+                        .find(|f| f.get_name() == name.as_str())
+                        .map(|f| sanitize(&f.get_value().to_string()))
+                        .filter(|v| !v.is_empty() && !v.chars().all(|c| c == '.'));
+                    dir.push_str(value.as_deref().unwrap_or(DEFAULT_PARTITION));
+                }
+            }
+        }
+        dir
+    }
+}
+
+/// 字段值中除字母、数字与 `_-.=` 以外的字符替换为 `_`。
+fn sanitize(value: &str) -> String {
+    value
+        .trim()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | '=') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use serde_json::json;
+    use wp_model_core::model::DataField;
+
+    fn record(table: Option<&str>, ts: &str) -> DataRecord {
+        let mut rec = DataRecord::default();
+        if let Some(table) = table {
+            rec.append(DataField::from_chars("table", table));
+        }
+        rec.append(DataField::from_chars("ts", ts));
+        rec
+    }
+
+    fn now() -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 1, 1)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn renders_record_time_and_fields() {
+        let tpl =
+            PartitionTemplate::parse("{table}/dt=%Y-%m-%d/hour=%H/", Some("ts".into())).unwrap();
+        assert_eq!(
+            tpl.render(&record(Some("events"), "2024-06-01T09:59:59Z"), now()),
+            "events/dt=2024-06-01/hour=09"
+        );
+        assert_eq!(
+            tpl.render(&record(Some("events"), "2024-06-01T10:00:00+00:00"), now()),
+            "events/dt=2024-06-01/hour=10"
+        );
+        // epoch 毫秒
+        assert_eq!(
+            tpl.render(&record(Some("events"), "1717236000000"), now()),
+            "events/dt=2024-06-01/hour=10"
+        );
+        // 时间不可用时回退到 now；字段缺失时为默认分区，越界字符被替换
+        assert_eq!(
+            tpl.render(&record(None, "yesterday"), now()),
+            "__HIVE_DEFAULT_PARTITION__/dt=2024-01-01/hour=00"
+        );
+        assert_eq!(
+            tpl.render(&record(Some("../etc"), "2024-06-01T10:00:00Z"), now()),
+            ".._etc/dt=2024-06-01/hour=10"
+        );
+        assert_eq!(
+            tpl.render(&record(Some(".."), "2024-06-01T10:00:00Z"), now()),
+            "__HIVE_DEFAULT_PARTITION__/dt=2024-06-01/hour=10"
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_templates() {
+        for raw in [
+            "",
+            "/abs/dt=%Y",
+            "a/../b",
+            "dt=%Q",
+            "{table",
+            "table}",
+            "{}/dt=%Y",
+        ] {
+            assert!(PartitionTemplate::parse(raw, None).is_err(), "{raw}");
+        }
+
+        let mut params = ParamMap::new();
+        assert_eq!(
+            PartitionTemplate::from_params(&params, "file").unwrap(),
+            None
+        );
+        params.insert("partition_template".into(), json!("dt=%Y-%m-%d"));
+        params.insert("partition_time_field".into(), json!("ts"));
+        let tpl = PartitionTemplate::from_params(&params, "file")
+            .unwrap()
+            .expect("template");
+        assert_eq!(tpl.time_field.as_deref(), Some("ts"));
+        params.insert("partition_template".into(), json!(3));
+        let err = PartitionTemplate::from_params(&params, "file").unwrap_err();
+        assert!(format!("{err}").contains("file.partition_template"));
+    }
+}
@@ -0,0 +1,89 @@
+//! 消息队列类 source 的攒批拉取：阻塞等待第一条消息，再取走已到达的消息。
+//!
+//! 批内续取出错时先交付已取到的消息，错误留到下一次拉取返回；
+//! 否则已取出的消息会随错误丢弃，而它们在下一次 `receive` 时仍被确认。
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures_util::FutureExt;
+use wp_connector_api::{SourceError, SourceResult};
+
+/// 逐条拉取消息，返回（`WP_SRC_VAL` 标签值, 负载）。
+#[async_trait]
+pub(crate) trait MessagePull: Send {
+    async fn pull(&mut self) -> SourceResult<(String, Bytes)>;
+}
+
+pub(crate) struct PullBatcher {
+    limit: usize,
+    /// 上一批续取时遇到、尚未返回的错误
+    deferred: Option<SourceError>,
+}
+
+impl PullBatcher {
+    pub(crate) fn new(limit: usize) -> Self {
+        Self {
+            limit: limit.max(1),
+            deferred: None,
+        }
+    }
+
+    pub(crate) async fn next_batch<P: MessagePull>(
+        &mut self,
+        inbox: &mut P,
+    ) -> SourceResult<Vec<(String, Bytes)>> {
+        if let Some(err) = self.deferred.take() {
+            return Err(err);
+        }
+        let mut batch = vec![inbox.pull().await?];
+        while batch.len() < self.limit {
+            match inbox.pull().now_or_never() {
+                Some(Ok(msg)) => batch.push(msg),
+                Some(Err(err)) => {
+                    self.deferred = Some(err);
+                    break;
+                }
+                None => break,
+            }
+        }
+        Ok(batch)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use wp_connector_api::SourceReason;
+
+    use super::*;
+
+    struct Scripted(VecDeque<SourceResult<(String, Bytes)>>);
+
+    #[async_trait]
+    impl MessagePull for Scripted {
+        async fn pull(&mut self) -> SourceResult<(String, Bytes)> {
+            match self.0.pop_front() {
+                Some(next) => next,
+                None => std::future::pending().await,
+            }
+        }
+    }
+
+    fn msg(n: u8) -> SourceResult<(String, Bytes)> {
+        Ok(("t".to_string(), Bytes::from(vec![n])))
+    }
+
+    #[tokio::test]
+    async fn mid_batch_error_returns_partial_batch_then_error() {
+        let err = SourceError::from(SourceReason::SupplierError("broken".into()));
+        let mut inbox = Scripted(VecDeque::from(vec![msg(1), msg(2), Err(err), msg(3)]));
+        let mut batcher = PullBatcher::new(10);
+
+        let first = batcher.next_batch(&mut inbox).await.expect("partial batch");
+        assert_eq!(first.len(), 2);
+        assert!(batcher.next_batch(&mut inbox).await.is_err());
+        let next = batcher.next_batch(&mut inbox).await.expect("recovered");
+        assert_eq!(next[0].1, Bytes::from(vec![3]));
+    }
+}
@@ -0,0 +1,212 @@
+//! 隔离区（quarantine）：解析失败的原始负载连同错误与来源元数据写入指定 sink，便于排查与重放。
+//!
+//! 配置示例（挂在 source 参数下）：
+//! ```toml
+//! quarantine = { kind = "kafka", params = { brokers = "localhost:9092", topic = "wp_quarantine" } }
+//! ```
+//! 隔离 sink 通过其工厂构建，写入内容为每条一行的 JSON：
+//! `{"source":..,"error":..,"payload":..,"meta":{..}}`。
+
+use serde_json::{Map, Value, json};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use wp_connector_api::{
+    AsyncRawDataSink, ParamMap, SinkBuildCtx, SinkFactory, SinkHandle, SinkReason, SinkResult,
+    SinkSpec,
+};
+
+/// 隔离 sink 的引用：目标 kind 与其参数。
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuarantineConf {
+    pub kind: String,
+    pub params: ParamMap,
+}
+
+impl QuarantineConf {
+    /// 从参数中读取 `quarantine`（对象，含 `kind` 与可选 `params`）；未配置时返回 None。
+    pub fn from_params(params: &ParamMap) -> SinkResult<Option<Self>> {
+        let obj = match params.get("quarantine") {
+            None | Some(Value::Null) => return Ok(None),
+            Some(Value::Object(obj)) => obj,
+            Some(_) => return Err(SinkReason::sink("quarantine must be a table").into()),
+        };
+        let kind = obj
+            .get("kind")
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .unwrap_or("");
+        if kind.is_empty() {
+            return Err(SinkReason::sink("quarantine.kind must not be empty").into());
+        }
+        let params = match obj.get("params") {
+            None | Some(Value::Null) => ParamMap::new(),
+            Some(Value::Object(map)) => map.iter().map(|(k, v)| (k.clone(), v.clone())).collect(),
+            Some(_) => return Err(SinkReason::sink("quarantine.params must be a table").into()),
+        };
+        Ok(Some(Self {
+            kind: kind.to_string(),
+            params,
+        }))
+    }
+
+    fn spec(&self, owner: &str) -> SinkSpec {
+        SinkSpec {
+            name: format!("{owner}_quarantine"),
+            kind: self.kind.clone(),
+            connector_id: String::new(),
+            group: "quarantine".into(),
+            params: self.params.clone(),
+            filter: None,
+        }
+    }
+}
+
+/// 一条被隔离的数据：原始字节、解析错误与来源元数据（topic/partition/offset 等）。
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuarantineEntry {
+    pub source: String,
+    pub error: String,
+    pub payload: Vec<u8>,
+    pub meta: BTreeMap<String, Value>,
+}
+
+impl QuarantineEntry {
+    pub fn new(source: impl Into<String>, error: impl Into<String>, payload: &[u8]) -> Self {
+        Self {
+            source: source.into(),
+            error: error.into(),
+            payload: payload.to_vec(),
+            meta: BTreeMap::new(),
+        }
+    }
+
+    pub fn with_meta(mut self, key: &str, value: impl Into<Value>) -> Self {
+        self.meta.insert(key.to_string(), value.into());
+        self
+    }
+
+    /// 渲染为单行 JSON；负载为合法 UTF-8 时写字符串，否则写字节数组，保证原样可重放。
+    pub fn to_json_line(&self) -> String {
+        let payload = match std::str::from_utf8(&self.payload) {
+            Ok(s) => Value::String(s.to_string()),
+            Err(_) => Value::Array(self.payload.iter().map(|b| json!(b)).collect()),
+        };
+        let meta: Map<String, Value> = self
+            .meta
+            .iter()
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect();
+        json!({
+            "source": self.source,
+            "error": self.error,
+            "payload": payload,
+            "meta": meta,
+        })
+        .to_string()
+    }
+}
+
+/// 将隔离数据写入任意原始数据 sink。
+pub async fn send_entry<S>(sink: &mut S, entry: &QuarantineEntry) -> SinkResult<()>
+where
+    S: AsyncRawDataSink + ?Sized,
+{
+    sink.sink_str(&entry.to_json_line()).await
+}
+
+/// 通过本 crate 内已启用的 sink 工厂构建隔离 sink。
+pub async fn build_quarantine_sink(conf: &QuarantineConf, owner: &str) -> SinkResult<SinkHandle> {
+    let spec = conf.spec(owner);
+    // source 侧没有 sink 构建上下文，隔离 sink 以当前目录为工作目录
+    let ctx = SinkBuildCtx::new(PathBuf::from("."));
+    let factory: Box<dyn SinkFactory> = match conf.kind.as_str() {
+        #[cfg(feature = "kafka")]
+        "kafka" => Box::new(crate::kafka::KafkaSinkFactory),
+        #[cfg(feature = "mysql")]
+        "mysql" => Box::new(crate::mysql::MySQLSinkFactory),
+        #[cfg(feature = "doris")]
+        "doris" => Box::new(crate::doris::DorisSinkFactory),
+        #[cfg(feature = "clickhouse")]
+        "clickhouse" => Box::new(crate::clickhouse::ClickhouseSinkFactory),
+        #[cfg(feature = "elasticsearch")]
+        "elasticsearch" => Box::new(crate::elasticsearch::ElasticsearchSinkFactory),
+        #[cfg(feature = "opensearch")]
+        "opensearch" => Box::new(crate::opensearch::OpenSearchSinkFactory),
+        #[cfg(feature = "tdengine")]
+        "tdengine" => Box::new(crate::tdengine::TdengineSinkFactory),
+        #[cfg(feature = "starrocks")]
+        "starrocks" => Box::new(crate::starrocks::StarRocksSinkFactory),
+        #[cfg(feature = "victorialogs")]
+        "victorialogs" => Box::new(crate::victorialogs::VictoriaLogSinkFactory),
+        #[cfg(feature = "redis")]
+        "redis" => Box::new(crate::redis::RedisSinkFactory),
+        #[cfg(feature = "s3")]
+        "s3" => Box::new(crate::s3::S3SinkFactory),
+        #[cfg(feature = "file")]
+        "file" => Box::new(crate::file::FileSinkFactory),
+        #[cfg(feature = "http")]
+        "http" => Box::new(crate::http::HttpSinkFactory),
+        #[cfg(feature = "nats")]
+        "nats" => Box::new(crate::nats::NatsSinkFactory),
+        #[cfg(feature = "pulsar")]
+        "pulsar" => Box::new(crate::pulsar::PulsarSinkFactory),
+        other => {
+            return Err(
+                SinkReason::sink(format!("unsupported quarantine sink kind: {other}")).into(),
+            );
+        }
+    };
+    factory.validate_spec(&spec)?;
+    factory.build(&spec, &ctx).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::field_allowlist::FieldAllowlist;
+    use crate::common::testing::CaptureSink;
+
+    #[test]
+    fn quarantine_conf_requires_kind() {
+        let mut params = ParamMap::new();
+        assert_eq!(QuarantineConf::from_params(&params).unwrap(), None);
+
+        params.insert(
+            "quarantine".into(),
+            json!({"kind": "kafka", "params": {"topic": "bad"}}),
+        );
+        let conf = QuarantineConf::from_params(&params).unwrap().unwrap();
+        assert_eq!(conf.kind, "kafka");
+        assert_eq!(conf.params.get("topic"), Some(&json!("bad")));
+
+        params.insert("quarantine".into(), json!({"params": {}}));
+        assert!(QuarantineConf::from_params(&params).is_err());
+    }
+
+    #[tokio::test]
+    async fn quarantine_entry_carries_error_and_meta() {
+        let raw = br#"{"host": "a", "level": "#;
+        let allow = FieldAllowlist::new(["host"]);
+        let err = allow.project(raw).expect_err("malformed json");
+
+        let entry = QuarantineEntry::new("kafka_src", err.to_string(), raw)
+            .with_meta("topic", "events")
+            .with_meta("offset", 42);
+        let mut capture = CaptureSink::default();
+        send_entry(&mut capture, &entry).await.unwrap();
+
+        assert_eq!(capture.raw.len(), 1);
+        let line: Value = serde_json::from_slice(&capture.raw[0]).unwrap();
+        assert_eq!(line["payload"], json!(std::str::from_utf8(raw).unwrap()));
+        assert_eq!(line["error"], json!(err.to_string()));
+        assert_eq!(line["meta"]["offset"], json!(42));
+        assert_eq!(line["meta"]["topic"], json!("events"));
+    }
+
+    #[test]
+    fn binary_payload_kept_as_bytes() {
+        let entry = QuarantineEntry::new("src", "bad", &[0xff, 0x00]);
+        let line: Value = serde_json::from_str(&entry.to_json_line()).unwrap();
+        assert_eq!(line["payload"], json!([255, 0]));
+    }
+}
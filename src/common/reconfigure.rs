@@ -0,0 +1,138 @@
+//! Sink 运行期参数热更新：不重建 sink（不断开连接、不丢弃缓存）地就地调整批量大小、
+//! 重试次数等可变参数。
+//!
+//! 各 sink 只接受自身支持的键，键名与构建 sink 时相同；出现不支持的键或取值非法时整体拒绝，
+//! 不做部分更新。新的批量大小在下一条记录到达时生效，缓存已达到新阈值的表随即 flush。
+//!
+//! sink 交给引擎后只剩 `SinkHandle`，因此支持热更新的 sink 在工厂构建时以 sink 名 [`register`]
+//! 一个热更新入口，运行期经 [`reconfigure`] 按名调用。
+
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex, OnceLock};
+use wp_connector_api::{ParamMap, SinkReason, SinkResult};
+
+/// 已登记 sink 的热更新入口。
+pub type ReconfigureFn = Arc<dyn Fn(&ParamMap) -> SinkResult<()> + Send + Sync>;
+
+/// 支持运行期参数热更新的 sink。
+pub trait Reconfigure {
+    /// 按参数就地更新运行期配置。
+    ///
+    /// # args
+    /// * `params` - 要更新的参数；未出现的键保持不变。
+    ///
+    /// # return
+    /// * `SinkResult<()>` - 含不支持的键或取值非法时返回错误，此时配置不变。
+    fn reconfigure(&mut self, params: &ParamMap) -> SinkResult<()>;
+}
+
+fn registry() -> &'static Mutex<BTreeMap<String, ReconfigureFn>> {
+    static REGISTRY: OnceLock<Mutex<BTreeMap<String, ReconfigureFn>>> = OnceLock::new();
+    REGISTRY.get_or_init(Default::default)
+}
+
+/// 以 sink 名登记热更新入口；同名重复登记时替换旧入口（例如重建 sink）。
+pub fn register(name: &str, handle: ReconfigureFn) {
+    registry()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(name.to_string(), handle);
+}
+
+/// 按 sink 名热更新运行期参数。
+///
+/// # args
+/// * `name` - sink 名（构建时的 `spec.name`）。
+/// * `params` - 要更新的参数；未出现的键保持不变。
+///
+/// # return
+/// * `SinkResult<()>` - sink 未登记、已释放、含不支持的键或取值非法时返回错误，此时配置不变；
+///   sink 正在写出时返回可重试错误。
+pub fn reconfigure(name: &str, params: &ParamMap) -> SinkResult<()> {
+    let handle = registry()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(name)
+        .cloned()
+        .ok_or_else(|| {
+            SinkReason::sink(format!(
+                "sink '{name}' does not support runtime reconfigure"
+            ))
+        })?;
+    handle(params)
+}
+
+/// 校验参数只包含可热更新的键。
+///
+/// # args
+/// * `allowed` - 该 sink 可热更新的键。
+/// * `sink` - 错误信息中的 sink 名称。
+pub fn check_keys(params: &ParamMap, allowed: &[&str], sink: &str) -> SinkResult<()> {
+    let mut unsupported = params
+        .keys()
+        .filter(|key| !allowed.contains(&key.as_str()))
+        .map(String::as_str)
+        .collect::<Vec<_>>();
+    if unsupported.is_empty() {
+        return Ok(());
+    }
+    unsupported.sort_unstable();
+    Err(SinkReason::sink(format!(
+        "{sink}: [{}] cannot be reconfigured at runtime; allowed: {}",
+        unsupported.join(", "),
+        allowed.join(",")
+    ))
+    .into())
+}
+
+/// 读取非负整数参数；依次尝试 `keys` 中的别名，取第一个出现的。
+pub fn opt_u64(params: &ParamMap, keys: &[&str], sink: &str) -> SinkResult<Option<u64>> {
+    for key in keys {
+        match params.get(*key) {
+            None | Some(Value::Null) => continue,
+            Some(v) => {
+                return v.as_u64().map(Some).ok_or_else(|| {
+                    SinkReason::sink(format!("{sink}.{key} must be a non-negative integer")).into()
+                });
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// 读取批量大小：须为正整数。
+pub fn opt_batch(params: &ParamMap, keys: &[&str], sink: &str) -> SinkResult<Option<usize>> {
+    match opt_u64(params, keys, sink)? {
+        Some(n) if n > 0 => Ok(Some(usize::try_from(n).unwrap_or(usize::MAX))),
+        Some(_) => Err(SinkReason::sink(format!("{sink}.{} must be > 0", keys[0])).into()),
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn unsupported_keys_and_invalid_values_are_rejected() {
+        let mut params = ParamMap::new();
+        params.insert("batch_size".into(), json!(5));
+        assert!(check_keys(&params, &["batch", "batch_size"], "doris").is_ok());
+        assert_eq!(
+            opt_batch(&params, &["batch", "batch_size"], "doris").unwrap(),
+            Some(5)
+        );
+
+        params.insert("pool_size".into(), json!(2));
+        params.insert("endpoint".into(), json!("x"));
+        let err = check_keys(&params, &["batch", "batch_size"], "doris").unwrap_err();
+        assert!(err.to_string().contains("[endpoint, pool_size]"), "{err}");
+
+        params.insert("batch".into(), json!(0));
+        assert!(opt_batch(&params, &["batch", "batch_size"], "doris").is_err());
+        params.insert("max_retries".into(), json!("3"));
+        assert!(opt_u64(&params, &["max_retries"], "doris").is_err());
+    }
+}
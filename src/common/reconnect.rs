@@ -0,0 +1,390 @@
+//! 重连节流：同一后端（按 key 区分，如 `mysql:db-host:3306`）的重连在进程内共享退避状态，
+//! 采用带抖动的指数退避并限制尝试次数，避免多个 sink 同时冲击正在恢复的后端。
+//!
+//! 各 sink 的 `AsyncCtrl::reconnect` 通过 [`ReconnectCoordinator::run`] 执行实际的重连动作；
+//! 没有常驻连接的 HTTP 类 sink 以 [`probe_endpoint`] 确认后端可达。
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::hash::{BuildHasher, RandomState};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use wp_connector_api::{SinkReason, SinkResult};
+
+const DEFAULT_BASE_DELAY: Duration = Duration::from_millis(200);
+const DEFAULT_MAX_DELAY: Duration = Duration::from_secs(30);
+const DEFAULT_MAX_ATTEMPTS: u32 = 8;
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 退避策略：第 n 次重试前等待 `base * 2^(n-1)`（不超过 `max_delay`），共尝试 `max_attempts` 次。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReconnectPolicy {
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    pub max_attempts: u32,
+    /// 开启时实际等待取 `[delay/2, delay]` 内的随机值，打散同时发起的重连
+    pub jitter: bool,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            base_delay: DEFAULT_BASE_DELAY,
+            max_delay: DEFAULT_MAX_DELAY,
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+            jitter: true,
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    /// 第 `retry` 次重试（从 1 开始）前的退避时长（未加抖动）。
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let factor = 1u32
+            .checked_shl(retry.saturating_sub(1))
+            .unwrap_or(u32::MAX);
+        self.base_delay
+            .checked_mul(factor)
+            .unwrap_or(self.max_delay)
+            .min(self.max_delay)
+    }
+
+    /// 第 `retry` 次重试前的实际等待（按 `jitter` 加抖动）。
+    pub(crate) fn delay_for(&self, retry: u32) -> Duration {
+        let delay = self.backoff(retry);
+        if self.jitter { jittered(delay) } else { delay }
+    }
+}
+
+/// 重连计数快照。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReconnectStats {
+    pub attempts: u64,
+    pub failures: u64,
+    pub give_ups: u64,
+}
+
+#[derive(Debug, Default)]
+struct BackendState {
+    /// 当前这一轮已进行的尝试次数
+    attempts: u32,
+    /// 下一次尝试最早可开始的时刻（所有共享该后端的 sink 依次排队）
+    next_slot: Option<Instant>,
+}
+
+#[derive(Debug, Default)]
+struct Shared {
+    state: Mutex<BackendState>,
+    attempts: AtomicU64,
+    failures: AtomicU64,
+    give_ups: AtomicU64,
+}
+
+/// 按后端共享的重连协调器；克隆后共享同一状态。
+#[derive(Debug, Clone)]
+pub struct ReconnectCoordinator {
+    key: String,
+    policy: ReconnectPolicy,
+    shared: Arc<Shared>,
+}
+
+fn registry() -> &'static Mutex<HashMap<String, Arc<Shared>>> {
+    static REGISTRY: OnceLock<Mutex<HashMap<String, Arc<Shared>>>> = OnceLock::new();
+    REGISTRY.get_or_init(Default::default)
+}
+
+impl ReconnectCoordinator {
+    /// 获取进程内按 `key` 共享的协调器。
+    pub fn shared(key: impl Into<String>, policy: ReconnectPolicy) -> Self {
+        let key = key.into();
+        let shared = registry()
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(key.clone())
+            .or_default()
+            .clone();
+        Self {
+            key,
+            policy,
+            shared,
+        }
+    }
+
+    /// 独立状态的协调器（不与其他 sink 共享）。
+    pub fn standalone(key: impl Into<String>, policy: ReconnectPolicy) -> Self {
+        Self {
+            key: key.into(),
+            policy,
+            shared: Arc::default(),
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn stats(&self) -> ReconnectStats {
+        ReconnectStats {
+            attempts: self.shared.attempts.load(Ordering::Relaxed),
+            failures: self.shared.failures.load(Ordering::Relaxed),
+            give_ups: self.shared.give_ups.load(Ordering::Relaxed),
+        }
+    }
+
+    /// 按退避节奏反复执行 `connect` 直至成功；达到 `max_attempts` 仍失败时返回最后一次错误。
+    pub async fn run<T, F, Fut>(&self, mut connect: F) -> SinkResult<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = SinkResult<T>>,
+    {
+        loop {
+            let slot = self.reserve_slot();
+            tokio::time::sleep_until(slot.into()).await;
+            self.shared.attempts.fetch_add(1, Ordering::Relaxed);
+            #[cfg(feature = "prometheus")]
+            metrics::record(&self.key, "attempt");
+            match connect().await {
+                Ok(value) => {
+                    self.lock_state().attempts = 0;
+                    #[cfg(feature = "prometheus")]
+                    metrics::record(&self.key, "success");
+                    return Ok(value);
+                }
+                Err(err) => {
+                    self.shared.failures.fetch_add(1, Ordering::Relaxed);
+                    #[cfg(feature = "prometheus")]
+                    metrics::record(&self.key, "failure");
+                    let mut state = self.lock_state();
+                    if state.attempts >= self.policy.max_attempts {
+                        // 放弃本轮；保留 next_slot，下一轮仍需排在其后
+                        state.attempts = 0;
+                        drop(state);
+                        self.shared.give_ups.fetch_add(1, Ordering::Relaxed);
+                        #[cfg(feature = "prometheus")]
+                        metrics::record(&self.key, "give_up");
+                        return Err(SinkReason::Sink(format!(
+                            "reconnect to {} gave up after {} attempt(s): {}",
+                            self.key, self.policy.max_attempts, err
+                        ))
+                        .into());
+                    }
+                    wp_log::warn_data!(
+                        "[reconnect] {} attempt {}/{} failed: {}",
+                        self.key,
+                        state.attempts,
+                        self.policy.max_attempts,
+                        err
+                    );
+                }
+            }
+        }
+    }
+
+    /// 领取下一次尝试的时刻：首次尝试立即进行，其后按退避间隔排在共享队列末尾。
+    fn reserve_slot(&self) -> Instant {
+        let mut state = self.lock_state();
+        state.attempts += 1;
+        let now = Instant::now();
+        let slot = if state.attempts == 1 {
+            state.next_slot.map_or(now, |next| next.max(now))
+        } else {
+            let earliest = state.next_slot.map_or(now, |next| next.max(now));
+            earliest.max(now + self.policy.delay_for(state.attempts - 1))
+        };
+        state.next_slot = Some(slot);
+        slot
+    }
+
+    fn lock_state(&self) -> std::sync::MutexGuard<'_, BackendState> {
+        self.shared.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// 去掉 URL 中的账号密码，作为协调器 key 与指标标签使用。
+pub fn backend_key(kind: &str, endpoint: &str) -> String {
+    let (scheme, rest) = match endpoint.split_once("://") {
+        Some((scheme, rest)) => (Some(scheme), rest),
+        None => (None, endpoint),
+    };
+    let host = rest.split(['/', '?']).next().unwrap_or(rest);
+    let host = host.rsplit_once('@').map_or(host, |(_, host)| host);
+    match scheme {
+        Some(scheme) => format!("{kind}:{scheme}://{host}"),
+        None => format!("{kind}:{host}"),
+    }
+}
+
+/// 与 endpoint（`scheme://[user@]host[:port]/...`）的主机建立 TCP 连接，成功即视为后端可达；
+/// 未写端口时按 scheme 取 80/443。只建连不发请求，避免对写入接口产生副作用。
+pub async fn probe_endpoint(endpoint: &str) -> SinkResult<()> {
+    let addr = socket_addr(endpoint);
+    match tokio::time::timeout(PROBE_TIMEOUT, tokio::net::TcpStream::connect(&addr)).await {
+        Ok(Ok(_)) => Ok(()),
+        Ok(Err(e)) => Err(SinkReason::Sink(format!("connect {addr} fail: {e}")).into()),
+        Err(_) => Err(SinkReason::Sink(format!("connect {addr} timed out")).into()),
+    }
+}
+
+/// endpoint 中的 `host:port`；IPv6 地址保留方括号。
+fn socket_addr(endpoint: &str) -> String {
+    let (scheme, rest) = endpoint.split_once("://").unwrap_or(("http", endpoint));
+    let host = rest.split(['/', '?']).next().unwrap_or(rest);
+    let host = host.rsplit_once('@').map_or(host, |(_, host)| host);
+    let has_port = host
+        .rsplit_once(':')
+        .is_some_and(|(_, port)| !port.is_empty() && port.bytes().all(|b| b.is_ascii_digit()))
+        && !host.ends_with(']');
+    if has_port {
+        return host.to_string();
+    }
+    let port = if scheme.eq_ignore_ascii_case("https") {
+        443
+    } else {
+        80
+    };
+    format!("{host}:{port}")
+}
+
+fn jittered(delay: Duration) -> Duration {
+    let half = delay / 2;
+    let spread = half.as_nanos() as u64;
+    if spread == 0 {
+        return delay;
+    }
+    let seed = RandomState::new().hash_one(Instant::now());
+    half + Duration::from_nanos(seed % (spread + 1))
+}
+
+#[cfg(feature = "prometheus")]
+mod metrics {
+    use lazy_static::lazy_static;
+    use prometheus::{IntCounterVec, register_int_counter_vec};
+
+    lazy_static! {
+        static ref RECONNECT_TOTAL: IntCounterVec = register_int_counter_vec!(
+            "wparse_sink_reconnect_total",
+            "Sink reconnect attempts by backend and result.",
+            &["backend", "result"]
+        )
+        .expect("register wparse_sink_reconnect_total fail");
+    }
+
+    pub(super) fn record(backend: &str, result: &str) {
+        RECONNECT_TOTAL.with_label_values(&[backend, result]).inc();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(max_attempts: u32) -> ReconnectPolicy {
+        ReconnectPolicy {
+            base_delay: Duration::from_millis(20),
+            max_delay: Duration::from_millis(80),
+            max_attempts,
+            jitter: false,
+        }
+    }
+
+    #[test]
+    fn backoff_doubles_and_caps() {
+        let policy = policy(5);
+        let delays = (1..=5).map(|n| policy.backoff(n)).collect::<Vec<_>>();
+        assert_eq!(
+            delays,
+            [20, 40, 80, 80, 80].map(Duration::from_millis).to_vec()
+        );
+        for _ in 0..32 {
+            let d = jittered(Duration::from_millis(80));
+            assert!(d >= Duration::from_millis(40) && d <= Duration::from_millis(80));
+        }
+    }
+
+    #[tokio::test]
+    async fn attempts_follow_schedule_and_give_up_after_cap() {
+        let coordinator = ReconnectCoordinator::standalone("test:a", policy(4));
+        let mut seen = Vec::new();
+        let result: SinkResult<()> = coordinator
+            .run(|| {
+                seen.push(Instant::now());
+                async { Err(SinkReason::Sink("connection refused".into()).into()) }
+            })
+            .await;
+
+        let err = result.expect_err("gives up");
+        assert!(format!("{err}").contains("gave up after 4"));
+        assert_eq!(seen.len(), 4);
+        let gaps = seen.windows(2).map(|w| w[1] - w[0]).collect::<Vec<_>>();
+        for (gap, expected) in gaps.iter().zip([20, 40, 80]) {
+            assert!(
+                *gap >= Duration::from_millis(expected),
+                "gap {gap:?} shorter than {expected}ms"
+            );
+        }
+        assert_eq!(
+            coordinator.stats(),
+            ReconnectStats {
+                attempts: 4,
+                failures: 4,
+                give_ups: 1,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn shared_backend_spaces_out_concurrent_sinks() {
+        let a = ReconnectCoordinator::shared("test:shared", policy(3));
+        let b = ReconnectCoordinator::shared("test:shared", policy(3));
+        let mut calls = 0;
+        let value = a
+            .run(|| {
+                calls += 1;
+                let ok = calls == 2;
+                async move {
+                    if ok {
+                        Ok(7)
+                    } else {
+                        Err(SinkReason::Sink("refused".into()).into())
+                    }
+                }
+            })
+            .await
+            .expect("second attempt succeeds");
+        assert_eq!(value, 7);
+        // 共享状态：b 看到的计数包含 a 的尝试
+        assert_eq!(b.stats().attempts, 2);
+        assert_eq!(b.stats().failures, 1);
+    }
+
+    #[tokio::test]
+    async fn probe_connects_to_endpoint_host() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        probe_endpoint(&format!("http://u:p@127.0.0.1:{port}/_bulk?x=1"))
+            .await
+            .expect("reachable");
+        drop(listener);
+        assert!(
+            probe_endpoint(&format!("http://127.0.0.1:{port}"))
+                .await
+                .is_err()
+        );
+        assert_eq!(socket_addr("https://es.local/_bulk"), "es.local:443");
+        assert_eq!(socket_addr("http://[::1]:8123"), "[::1]:8123");
+        assert_eq!(socket_addr("http://[::1]"), "[::1]:80");
+    }
+
+    #[test]
+    fn backend_key_strips_credentials() {
+        assert_eq!(
+            backend_key("mysql", "mysql://root:secret@db:3306/wp?ssl=true"),
+            "mysql:mysql://db:3306"
+        );
+        assert_eq!(
+            backend_key("kafka", "b1:9092,b2:9092"),
+            "kafka:b1:9092,b2:9092"
+        );
+    }
+}
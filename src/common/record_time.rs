@@ -0,0 +1,20 @@
+//! 记录时间字段的解析：时间值、epoch 毫秒或 RFC3339 字符串，统一换算为 UTC。
+
+use chrono::{DateTime, NaiveDateTime};
+use wp_model_core::model::{DataRecord, Value};
+
+/// 读取记录中 `field` 字段的时间（UTC）；字段缺失或无法解析时返回 `None`。
+pub fn record_time(record: &DataRecord, field: &str) -> Option<NaiveDateTime> {
+    let field = record.get2(field)?;
+    if let Value::Time(dt) = &field.value {
+        return Some(*dt);
+    }
+    let raw = field.get_value().to_string();
+    let raw = raw.trim();
+    match raw.parse::<i64>() {
+        Ok(millis) => DateTime::from_timestamp_millis(millis).map(|dt| dt.naive_utc()),
+        Err(_) => DateTime::parse_from_rfc3339(raw)
+            .ok()
+            .map(|dt| dt.naive_utc()),
+    }
+}
@@ -0,0 +1,1146 @@
+//! Sink 写入重试：包裹任意 sink，对 `sink_record`/`sink_records`/`stop`（最后一次 flush）
+//! 的暂时性失败按带抖动的指数退避重试；配置、数据等永久性错误直接返回。
+//!
+//! 由 sink 参数中共享的 `retry` 块开启，未配置时装饰器直接透传：
+//!
+//! ```toml
+//! retry = { max_attempts = 3, base_delay_ms = 200, max_delay_ms = 30000, jitter = true }
+//! ```
+//!
+//! 已被内部 sink 接收（进入缓存或已写出）的记录不会再次交给它：失败时先重试 flush 缓存，
+//! 成功后只补写尚未接收的记录，因此攒批 sink 重试不会重复追加。接收量由
+//! [`PendingFlush::accepted`] 给出；flush 部分成功后失败（如分片请求中途出错）仍可能重复，
+//! 应配合幂等写入（主键、确定性 `_id` 等）使用。
+//!
+//! 只有 `SinkReason::Uvs` 中的网络、超时与资源不足（过载、锁冲突）错误会重试，
+//! sink 按 [`network_error`]、[`timeout_error`]、[`busy_error`] 构造此类错误；其余均视为永久性错误。
+//!
+//! `write_deadline_ms` 为记录从到达到写入的总时长上限（SLA），统一约束攒批、flush 与重试：
+//! - 缓存中最早的记录到期时跳过攒批立即 flush；
+//! - 重试的退避不会越过期限；
+//! - 到期仍未写入的记录转投 `quarantine` 指定的 sink（未配置时丢弃并告警），不再阻塞后续写入。
+//!
+//! 期限由后台定时任务（间隔为期限的 1/4）与每次写入、`stop` 检查，没有新写入时到期记录也会被处理；
+//! 已写出的记录按接收序号与内部缓存比对移出，按表、分区分别 flush 的 sink 同样适用。
+//! 内部 sink 需实现 [`PendingFlush`]。
+
+use async_trait::async_trait;
+use orion_error::UvsReason;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashSet};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{MappedMutexGuard, Mutex, MutexGuard};
+use tokio::task::JoinHandle;
+use tokio::time::MissedTickBehavior;
+use wp_connector_api::{
+    AsyncCtrl, AsyncRawDataSink, AsyncRecordSink, ParamMap, SinkError, SinkHandle, SinkReason,
+    SinkResult, SinkSpec,
+};
+use wp_data_fmt::{DataFormat, FormatType};
+use wp_model_core::model::{DataRecord, fmt_def::TextFmt};
+
+use crate::common::quarantine::{
+    QuarantineConf, QuarantineEntry, build_quarantine_sink, send_entry,
+};
+use crate::common::reconfigure::{Reconfigure, ReconfigureFn};
+use crate::common::reconnect::ReconnectPolicy;
+
+const DEFAULT_MAX_ATTEMPTS: u32 = 3;
+const RETRY_KEYS: [&str; 4] = ["max_attempts", "base_delay_ms", "max_delay_ms", "jitter"];
+/// 写入期限定时检查的最小间隔。
+const MIN_TICK: Duration = Duration::from_millis(10);
+/// 写入失败的性质。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailureKind {
+    /// 网络抖动、超时、服务端过载等，稍后重试可能成功
+    Retryable,
+    /// 配置错误、数据被拒绝等，重试无意义
+    Permanent,
+}
+
+impl FailureKind {
+    /// 按错误原因归类：网络、超时与资源不足为 `Retryable`，其余按 `Permanent` 处理。
+    pub fn of(err: &SinkError) -> Self {
+        match err.reason() {
+            SinkReason::Uvs(
+                UvsReason::NetworkError(_)
+                | UvsReason::TimeoutError(_)
+                | UvsReason::ResourceError(_),
+            ) => Self::Retryable,
+            _ => Self::Permanent,
+        }
+    }
+}
+
+/// 连接失败、请求未送达等网络错误（可重试）。
+pub fn network_error(msg: impl Into<String>) -> SinkError {
+    SinkError::from(SinkReason::Uvs(UvsReason::NetworkError(msg.into())))
+}
+
+/// 请求或 flush 超时（可重试）。
+pub fn timeout_error(msg: impl Into<String>) -> SinkError {
+    SinkError::from(SinkReason::Uvs(UvsReason::TimeoutError(msg.into())))
+}
+
+/// 服务端过载、限流或锁冲突（可重试）。
+pub fn busy_error(msg: impl Into<String>) -> SinkError {
+    SinkError::from(SinkReason::Uvs(UvsReason::ResourceError(msg.into())))
+}
+
+/// 发送失败：`timed_out` 时为超时错误，否则为网络错误。
+pub fn transport_error(msg: impl Into<String>, timed_out: bool) -> SinkError {
+    if timed_out {
+        timeout_error(msg)
+    } else {
+        network_error(msg)
+    }
+}
+
+/// HTTP 状态是否值得重试：408/429 与 5xx。
+pub fn retryable_status(status: u16) -> bool {
+    matches!(status, 408 | 429 | 500..=599)
+}
+
+/// HTTP 非成功状态：408/429 与 5xx 为可重试的过载错误，其余为永久性错误。
+pub fn status_error(status: u16, msg: impl Into<String>) -> SinkError {
+    match retryable_status(status) {
+        true => busy_error(msg),
+        false => SinkError::from(SinkReason::Sink(msg.into())),
+    }
+}
+
+/// 用新的描述包装 `source`，保留其网络/超时/过载归类；其余错误为永久性错误。
+pub fn reclassify(source: &SinkError, msg: impl Into<String>) -> SinkError {
+    match source.reason() {
+        SinkReason::Uvs(UvsReason::NetworkError(_)) => network_error(msg),
+        SinkReason::Uvs(UvsReason::TimeoutError(_)) => timeout_error(msg),
+        SinkReason::Uvs(UvsReason::ResourceError(_)) => busy_error(msg),
+        _ => SinkError::from(SinkReason::Sink(msg.into())),
+    }
+}
+
+/// 读取共享的 `retry` 参数块；未配置时返回 `None`（不重试）。
+///
+/// # args
+/// * `params` - sink 参数；`retry` 可含 `max_attempts`（总尝试次数，默认 3）、
+///   `base_delay_ms`、`max_delay_ms`、`jitter`。
+///
+/// # return
+/// * `SinkResult<Option<ReconnectPolicy>>` - 块内含未知键或取值非法时返回错误。
+pub fn policy_from_params(params: &ParamMap) -> SinkResult<Option<ReconnectPolicy>> {
+    let block = match params.get("retry") {
+        None | Some(Value::Null) => return Ok(None),
+        Some(Value::Object(block)) => block,
+        Some(_) => return Err(SinkReason::sink("retry must be a table").into()),
+    };
+    if let Some(key) = block.keys().find(|k| !RETRY_KEYS.contains(&k.as_str())) {
+        return Err(SinkReason::sink(format!(
+            "retry.{key} is not supported; allowed: {}",
+            RETRY_KEYS.join(",")
+        ))
+        .into());
+    }
+    let millis = |key: &str| -> SinkResult<Option<Duration>> {
+        match block.get(key) {
+            None => Ok(None),
+            Some(v) => v
+                .as_u64()
+                .map(|ms| Some(Duration::from_millis(ms)))
+                .ok_or_else(|| {
+                    SinkReason::sink(format!("retry.{key} must be a non-negative integer")).into()
+                }),
+        }
+    };
+    let mut policy = ReconnectPolicy {
+        max_attempts: DEFAULT_MAX_ATTEMPTS,
+        ..Default::default()
+    };
+    if let Some(v) = block.get("max_attempts") {
+        policy.max_attempts = v
+            .as_u64()
+            .filter(|n| *n > 0)
+            .and_then(|n| u32::try_from(n).ok())
+            .ok_or_else(|| SinkReason::sink("retry.max_attempts must be > 0"))?;
+    }
+    if let Some(delay) = millis("base_delay_ms")? {
+        policy.base_delay = delay;
+    }
+    if let Some(delay) = millis("max_delay_ms")? {
+        policy.max_delay = delay;
+    }
+    if policy.max_delay < policy.base_delay {
+        return Err(SinkReason::sink("retry.max_delay_ms must be >= retry.base_delay_ms").into());
+    }
+    if let Some(v) = block.get("jitter") {
+        policy.jitter = v
+            .as_bool()
+            .ok_or_else(|| SinkReason::sink("retry.jitter must be a boolean"))?;
+    }
+    Ok(Some(policy))
+}
+
+/// 读取 `write_deadline_ms`；未配置时返回 `None`（不限时）。
+pub fn deadline_from_params(params: &ParamMap) -> SinkResult<Option<Duration>> {
+    match params.get("write_deadline_ms") {
+        None | Some(Value::Null) => Ok(None),
+        Some(v) => match v.as_u64() {
+            Some(ms) if ms > 0 => Ok(Some(Duration::from_millis(ms))),
+            _ => Err(SinkReason::sink("write_deadline_ms must be > 0").into()),
+        },
+    }
+}
+
+/// 校验 `retry`、`write_deadline_ms` 与 `quarantine` 参数。
+pub fn validate_params(params: &ParamMap) -> SinkResult<()> {
+    policy_from_params(params)?;
+    deadline_from_params(params)?;
+    QuarantineConf::from_params(params)?;
+    Ok(())
+}
+
+/// 可由外部触发立即写入缓存的 sink，供 `write_deadline_ms` 跳过攒批与丢弃到期记录。
+#[async_trait]
+pub trait PendingFlush {
+    /// 缓存中尚未写入的记录数。
+    fn pending_len(&self) -> usize;
+
+    /// 立即写入全部缓存；失败时缓存保留。
+    async fn flush_now(&mut self) -> SinkResult<()>;
+
+    /// 丢弃全部缓存，返回丢弃的记录数。
+    fn discard_pending(&mut self) -> usize;
+
+    /// 累计接收的记录数（进入缓存或已写出，含转投死信的记录）；
+    /// 写入调用失败时，调用前后的差值即为本次已接收、无需再交给 sink 的前缀长度。
+    fn accepted(&self) -> u64;
+
+    /// 缓存中记录的接收序号（第 n 条接收的记录为 n - 1）。
+    /// 默认按接收顺序写出，即最近接收的 `pending_len()` 条；按表、分区等分别写出的 sink 需覆盖。
+    fn pending_seqs(&self) -> Vec<u64> {
+        let accepted = self.accepted();
+        (accepted.saturating_sub(self.pending_len() as u64)..accepted).collect()
+    }
+}
+
+/// 记录的到达时刻与内容。
+type Tracked = (Instant, Arc<DataRecord>);
+
+/// 重试装饰器：未配置策略与期限时直接透传；原始数据（raw）不重试。
+pub struct RetryingSink<S> {
+    core: Arc<Mutex<RetryCore<S>>>,
+    deadline: Option<Duration>,
+    // 写入期限的定时检查任务，首次写入时启动，随装饰器释放而终止
+    ticker: Option<JoinHandle<()>>,
+}
+
+struct RetryCore<S> {
+    inner: S,
+    policy: Option<ReconnectPolicy>,
+    retries: u64,
+    deadline: Option<Duration>,
+    // 已被内部 sink 接收、尚未写入的记录，按接收序号索引（仅配置期限时记录）
+    unwritten: BTreeMap<u64, Tracked>,
+    // 内部缓存被丢弃时尚未到期、待重新交给内部 sink 的记录
+    carry: Vec<Tracked>,
+    // 本次调用的重试是否因期限而提前停止
+    budget_hit: bool,
+    name: String,
+    dead_letter: Option<SinkHandle>,
+    expired: u64,
+}
+
+impl<S> RetryingSink<S> {
+    pub fn new(inner: S, policy: Option<ReconnectPolicy>) -> Self {
+        let core = RetryCore {
+            inner,
+            policy,
+            retries: 0,
+            deadline: None,
+            unwritten: BTreeMap::new(),
+            carry: Vec::new(),
+            budget_hit: false,
+            name: String::new(),
+            dead_letter: None,
+            expired: 0,
+        };
+        Self {
+            core: Arc::new(Mutex::new(core)),
+            deadline: None,
+            ticker: None,
+        }
+    }
+
+    /// 按 sink 参数构建：`retry`、`write_deadline_ms` 与到期记录转投的 `quarantine` sink。
+    pub async fn from_spec(inner: S, spec: &SinkSpec) -> SinkResult<Self> {
+        let dead_letter = match QuarantineConf::from_params(&spec.params)? {
+            Some(conf) => Some(build_quarantine_sink(&conf, &spec.name).await?),
+            None => None,
+        };
+        Ok(Self::new(inner, policy_from_params(&spec.params)?)
+            .with_write_deadline(deadline_from_params(&spec.params)?)
+            .with_dead_letter(spec.name.clone(), dead_letter))
+    }
+
+    pub fn with_write_deadline(mut self, deadline: Option<Duration>) -> Self {
+        self.configure().deadline = deadline;
+        self.deadline = deadline;
+        self
+    }
+
+    /// 到期记录转投的 sink；`name` 作为隔离数据的来源。
+    pub fn with_dead_letter(mut self, name: String, dead_letter: Option<SinkHandle>) -> Self {
+        let core = self.configure();
+        core.name = name;
+        core.dead_letter = dead_letter;
+        self
+    }
+
+    /// 构建期修改配置：定时任务在首次写入时才启动，此前没有其他引用。
+    fn configure(&mut self) -> &mut RetryCore<S> {
+        Arc::get_mut(&mut self.core)
+            .expect("RetryingSink is configured before its first write")
+            .get_mut()
+    }
+
+    pub async fn inner(&self) -> MappedMutexGuard<'_, S> {
+        MutexGuard::map(self.core.lock().await, |core| &mut core.inner)
+    }
+
+    /// 累计重试次数（不含首次尝试）。
+    pub async fn retries(&self) -> u64 {
+        self.core.lock().await.retries
+    }
+
+    /// 因超过写入期限被转投或丢弃的记录数。
+    pub async fn expired(&self) -> u64 {
+        self.core.lock().await.expired
+    }
+}
+
+impl<S: AsyncRecordSink + PendingFlush + Send + 'static> RetryingSink<S> {
+    /// 配置期限时启动定时任务，按期限的 1/4 检查到期记录；任务只持有弱引用。
+    fn ensure_ticker(&mut self) {
+        let (Some(deadline), None) = (self.deadline, &self.ticker) else {
+            return;
+        };
+        let core = Arc::downgrade(&self.core);
+        let period = (deadline / 4).max(MIN_TICK);
+        self.ticker = Some(tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            loop {
+                interval.tick().await;
+                let Some(core) = core.upgrade() else {
+                    break;
+                };
+                core.lock().await.tick().await;
+            }
+        }));
+    }
+}
+
+impl<S> Drop for RetryingSink<S> {
+    fn drop(&mut self) {
+        if let Some(ticker) = self.ticker.take() {
+            ticker.abort();
+        }
+    }
+}
+
+impl<S> RetryCore<S> {
+    /// 未写入记录中最早的一条距期限的剩余时间；未配置期限或没有未写入记录时为 `None`。
+    fn budget(&self) -> Option<Duration> {
+        let deadline = self.deadline?;
+        let oldest = self
+            .unwritten
+            .values()
+            .chain(&self.carry)
+            .map(|(arrival, _)| *arrival)
+            .min()?;
+        Some(deadline.saturating_sub(oldest.elapsed()))
+    }
+
+    /// 登记内部 sink 刚接收的记录，`first` 为其中第一条的接收序号。
+    fn track(
+        &mut self,
+        first: u64,
+        arrival: Instant,
+        records: impl Iterator<Item = Arc<DataRecord>>,
+    ) {
+        if self.deadline.is_some() {
+            for (seq, record) in (first..).zip(records) {
+                self.unwritten.insert(seq, (arrival, record));
+            }
+        }
+    }
+}
+
+impl<S: PendingFlush + Send> RetryCore<S> {
+    /// 移出内部 sink 已写出（或已丢弃）的记录：按接收序号与其缓存比对，不假定按接收顺序写出。
+    fn sync_written(&mut self) {
+        if self.unwritten.len() <= self.inner.pending_len() {
+            return;
+        }
+        let pending: HashSet<u64> = self.inner.pending_seqs().into_iter().collect();
+        self.unwritten.retain(|seq, _| pending.contains(seq));
+    }
+
+    /// 第 `attempt` 次尝试（从 1 开始）结束后是否需要重试；需要时返回退避时长。
+    /// 退避会越过写入期限时不再重试。
+    fn backoff_after(
+        &mut self,
+        result: &SinkResult<()>,
+        attempt: u32,
+        op: &str,
+    ) -> Option<Duration> {
+        let (Err(err), Some(policy)) = (result, &self.policy) else {
+            return None;
+        };
+        if attempt >= policy.max_attempts || FailureKind::of(err) == FailureKind::Permanent {
+            return None;
+        }
+        let (max_attempts, delay) = (policy.max_attempts, policy.delay_for(attempt));
+        self.sync_written();
+        if self.budget().is_some_and(|left| delay >= left) {
+            self.budget_hit = true;
+            return None;
+        }
+        self.retries += 1;
+        wp_log::warn_data!(
+            "[retry] {} attempt {}/{} failed: {}",
+            op,
+            attempt,
+            max_attempts,
+            err
+        );
+        Some(delay)
+    }
+
+    /// 最早的记录到期时立即 flush，仍失败则转投/丢弃到期记录。
+    /// 失败调用中未被接收的记录 `rest` 在到期处理时一并接管，留待重新写入。
+    async fn settle(
+        &mut self,
+        result: SinkResult<()>,
+        rest: Vec<Arc<DataRecord>>,
+        arrival: Instant,
+    ) -> SinkResult<()> {
+        if self.deadline.is_none() {
+            return result;
+        }
+        self.sync_written();
+        let due = self.budget() == Some(Duration::ZERO);
+        let result = match result {
+            Ok(()) if due => self.inner.flush_now().await,
+            other => other,
+        };
+        self.sync_written();
+        match result {
+            Ok(()) => Ok(()),
+            Err(err) if due || self.budget_hit => {
+                self.carry.extend(rest.into_iter().map(|r| (arrival, r)));
+                self.expire(&err).await
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    /// 将已到期的记录转投 dead letter sink（未配置时只告警）。
+    /// 内部缓存整体丢弃，其中未到期的记录暂存，之后重新交给内部 sink；
+    /// 转投失败不中断其余记录，结束后返回第一个错误。
+    async fn expire(&mut self, err: &SinkError) -> SinkResult<()> {
+        let Some(deadline) = self.deadline else {
+            return Ok(());
+        };
+        let is_due = |(arrival, _): &Tracked| arrival.elapsed() >= deadline;
+        if !self.unwritten.values().chain(&self.carry).any(is_due) {
+            return Ok(());
+        }
+        self.inner.discard_pending();
+        let (expired, mut kept): (Vec<Tracked>, Vec<Tracked>) = std::mem::take(&mut self.unwritten)
+            .into_values()
+            .chain(self.carry.drain(..))
+            .partition(is_due);
+        kept.sort_by_key(|(arrival, _)| *arrival);
+        self.carry = kept;
+        self.expired += expired.len() as u64;
+        let Some(dead_letter) = self.dead_letter.as_mut() else {
+            wp_log::warn_data!(
+                "[retry] {} dropped {} record(s) past write deadline: {}",
+                self.name,
+                expired.len(),
+                err
+            );
+            return Ok(());
+        };
+        let fmt = FormatType::from(&TextFmt::Json);
+        let mut first_err = None;
+        for (arrival, record) in expired {
+            let payload = fmt.format_record(record.as_ref()).to_string();
+            let entry =
+                QuarantineEntry::new(self.name.clone(), err.to_string(), payload.as_bytes())
+                    .with_meta("age_ms", arrival.elapsed().as_millis() as u64);
+            if let Err(e) = send_entry(dead_letter.sink.as_mut(), &entry).await {
+                first_err.get_or_insert(e);
+            }
+        }
+        first_err.map_or(Ok(()), Err)
+    }
+}
+
+impl<S: AsyncRecordSink + PendingFlush + Send> RetryCore<S> {
+    /// 把暂存的未到期记录重新交给内部 sink；未被接收的部分继续暂存。
+    async fn resubmit(&mut self) -> SinkResult<()> {
+        if self.carry.is_empty() {
+            return Ok(());
+        }
+        let before = self.inner.accepted();
+        let records = self.carry.iter().map(|(_, r)| r.clone()).collect();
+        let result = self.inner.sink_records(records).await;
+        let taken = ((self.inner.accepted() - before) as usize).min(self.carry.len());
+        for (seq, entry) in (before..).zip(self.carry.drain(..taken)) {
+            self.unwritten.insert(seq, entry);
+        }
+        result
+    }
+
+    /// 定时检查：先重新交出暂存的记录，再按期限 flush 或转投到期记录。
+    async fn tick(&mut self) {
+        self.budget_hit = false;
+        let result = self.resubmit().await;
+        if let Err(err) = self.settle(result, Vec::new(), Instant::now()).await {
+            wp_log::warn_data!("[retry] {} deadline check failed: {}", self.name, err);
+        }
+    }
+
+    /// 写入 `rest` 并重试暂时性失败：失败时移出内部 sink 已接收的前缀，
+    /// 重试先 flush 其缓存，成功后再交出剩余记录，已接收的记录不会重复交给内部 sink。
+    async fn write_retrying(&mut self, mut rest: Vec<Arc<DataRecord>>, op: &str) -> SinkResult<()> {
+        self.budget_hit = false;
+        let arrival = Instant::now();
+        let mut attempt = 1;
+        let mut flush_only = false;
+        loop {
+            let result = if flush_only {
+                self.inner.flush_now().await
+            } else {
+                match self.resubmit().await {
+                    Ok(()) => {
+                        let before = self.inner.accepted();
+                        let result = match rest.as_slice() {
+                            [record] => self.inner.sink_record(record.as_ref()).await,
+                            _ => self.inner.sink_records(rest.clone()).await,
+                        };
+                        let taken = ((self.inner.accepted() - before) as usize).min(rest.len());
+                        self.track(before, arrival, rest.drain(..taken));
+                        result
+                    }
+                    Err(err) => Err(err),
+                }
+            };
+            if result.is_ok() {
+                if flush_only && !rest.is_empty() {
+                    flush_only = false;
+                    continue;
+                }
+                return self.settle(result, rest, arrival).await;
+            }
+            flush_only = self.inner.pending_len() > 0;
+            if !flush_only && rest.is_empty() && self.carry.is_empty() {
+                // 记录均已写出，没有可重试的内容
+                return self.settle(result, rest, arrival).await;
+            }
+            match self.backoff_after(&result, attempt, op) {
+                Some(delay) => tokio::time::sleep(delay).await,
+                None => return self.settle(result, rest, arrival).await,
+            }
+            attempt += 1;
+        }
+    }
+}
+
+impl<S: Reconfigure + Send + 'static> RetryingSink<S> {
+    /// 内部 sink 的热更新入口，供工厂以 sink 名登记（见 [`crate::common::reconfigure::register`]）。
+    /// 入口只持有弱引用：sink 释放后调用返回错误，正在写出时返回可重试的忙错误。
+    pub fn reconfigure_handle(&self) -> ReconfigureFn {
+        let core = Arc::downgrade(&self.core);
+        Arc::new(move |params: &ParamMap| {
+            let core = core
+                .upgrade()
+                .ok_or_else(|| SinkError::from(SinkReason::sink("sink has been released")))?;
+            let mut core = core
+                .try_lock()
+                .map_err(|_| busy_error("sink is flushing, reconfigure later"))?;
+            core.inner.reconfigure(params)
+        })
+    }
+}
+
+#[async_trait]
+impl<S: AsyncCtrl + PendingFlush + Send> AsyncCtrl for RetryingSink<S> {
+    async fn stop(&mut self) -> SinkResult<()> {
+        let mut core = self.core.lock().await;
+        core.budget_hit = false;
+        let mut attempt = 1;
+        loop {
+            let result = core.inner.stop().await;
+            match core.backoff_after(&result, attempt, "stop") {
+                Some(delay) => tokio::time::sleep(delay).await,
+                None => return core.settle(result, Vec::new(), Instant::now()).await,
+            }
+            attempt += 1;
+        }
+    }
+
+    async fn reconnect(&mut self) -> SinkResult<()> {
+        self.core.lock().await.inner.reconnect().await
+    }
+}
+
+#[async_trait]
+impl<S: AsyncRecordSink + PendingFlush + Send + 'static> AsyncRecordSink for RetryingSink<S> {
+    async fn sink_record(&mut self, data: &DataRecord) -> SinkResult<()> {
+        self.ensure_ticker();
+        let mut core = self.core.lock().await;
+        if core.policy.is_none() && core.deadline.is_none() {
+            return core.inner.sink_record(data).await;
+        }
+        core.write_retrying(vec![Arc::new(data.clone())], "sink_record")
+            .await
+    }
+
+    async fn sink_records(&mut self, data: Vec<Arc<DataRecord>>) -> SinkResult<()> {
+        self.ensure_ticker();
+        let mut core = self.core.lock().await;
+        if core.policy.is_none() && core.deadline.is_none() {
+            return core.inner.sink_records(data).await;
+        }
+        core.write_retrying(data, "sink_records").await
+    }
+}
+
+#[async_trait]
+impl<S: AsyncRawDataSink + Send> AsyncRawDataSink for RetryingSink<S> {
+    async fn sink_str(&mut self, data: &str) -> SinkResult<()> {
+        self.core.lock().await.inner.sink_str(data).await
+    }
+
+    async fn sink_bytes(&mut self, data: &[u8]) -> SinkResult<()> {
+        self.core.lock().await.inner.sink_bytes(data).await
+    }
+
+    async fn sink_str_batch(&mut self, data: Vec<&str>) -> SinkResult<()> {
+        self.core.lock().await.inner.sink_str_batch(data).await
+    }
+
+    async fn sink_bytes_batch(&mut self, data: Vec<&[u8]>) -> SinkResult<()> {
+        self.core.lock().await.inner.sink_bytes_batch(data).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::sync::Mutex;
+    use wp_model_core::model::DataField;
+
+    /// 前 `failures` 次调用返回 `error`，之后成功；不缓存，失败时不接收记录。
+    struct FlakySink {
+        failures: usize,
+        error: fn(&'static str) -> SinkError,
+        message: &'static str,
+        calls: usize,
+        written: usize,
+    }
+
+    impl FlakySink {
+        fn new(
+            failures: usize,
+            error: fn(&'static str) -> SinkError,
+            message: &'static str,
+        ) -> Self {
+            Self {
+                failures,
+                error,
+                message,
+                calls: 0,
+                written: 0,
+            }
+        }
+
+        fn attempt(&mut self, records: usize) -> SinkResult<()> {
+            self.calls += 1;
+            if self.failures > 0 {
+                self.failures -= 1;
+                return Err((self.error)(self.message));
+            }
+            self.written += records;
+            Ok(())
+        }
+    }
+
+    fn permanent(msg: &'static str) -> SinkError {
+        SinkReason::Sink(msg.to_string()).into()
+    }
+
+    impl Reconfigure for FlakySink {
+        fn reconfigure(&mut self, params: &ParamMap) -> SinkResult<()> {
+            crate::common::reconfigure::check_keys(params, &["failures"], "flaky")?;
+            if let Some(n) = crate::common::reconfigure::opt_u64(params, &["failures"], "flaky")? {
+                self.failures = n as usize;
+            }
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl AsyncCtrl for FlakySink {
+        async fn stop(&mut self) -> SinkResult<()> {
+            self.attempt(0)
+        }
+
+        async fn reconnect(&mut self) -> SinkResult<()> {
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl AsyncRecordSink for FlakySink {
+        async fn sink_record(&mut self, _data: &DataRecord) -> SinkResult<()> {
+            self.attempt(1)
+        }
+
+        async fn sink_records(&mut self, data: Vec<Arc<DataRecord>>) -> SinkResult<()> {
+            self.attempt(data.len())
+        }
+    }
+
+    #[async_trait]
+    impl PendingFlush for FlakySink {
+        fn pending_len(&self) -> usize {
+            0
+        }
+
+        async fn flush_now(&mut self) -> SinkResult<()> {
+            Ok(())
+        }
+
+        fn discard_pending(&mut self) -> usize {
+            0
+        }
+
+        fn accepted(&self) -> u64 {
+            self.written as u64
+        }
+    }
+
+    /// 攒批写入慢速后端：缓存达到 `batch` 时 flush，每次 flush 耗时 `latency`；
+    /// `down` 时 flush 超时失败并保留缓存；前 `failing_flushes` 次 flush 连接失败。
+    struct SlowBufferSink {
+        batch: usize,
+        latency: Duration,
+        down: bool,
+        failing_flushes: usize,
+        buffer: Vec<DataRecord>,
+        accepted: u64,
+        written: usize,
+    }
+
+    impl SlowBufferSink {
+        fn new(batch: usize, latency: Duration, down: bool) -> Self {
+            Self {
+                batch,
+                latency,
+                down,
+                failing_flushes: 0,
+                buffer: Vec::new(),
+                accepted: 0,
+                written: 0,
+            }
+        }
+
+        fn failing(mut self, flushes: usize) -> Self {
+            self.failing_flushes = flushes;
+            self
+        }
+    }
+
+    #[async_trait]
+    impl PendingFlush for SlowBufferSink {
+        fn pending_len(&self) -> usize {
+            self.buffer.len()
+        }
+
+        async fn flush_now(&mut self) -> SinkResult<()> {
+            tokio::time::sleep(self.latency).await;
+            if self.down {
+                return Err(timeout_error("backend write timed out"));
+            }
+            if self.failing_flushes > 0 {
+                self.failing_flushes -= 1;
+                return Err(network_error("backend connection reset"));
+            }
+            self.written += self.buffer.len();
+            self.buffer.clear();
+            Ok(())
+        }
+
+        fn discard_pending(&mut self) -> usize {
+            std::mem::take(&mut self.buffer).len()
+        }
+
+        fn accepted(&self) -> u64 {
+            self.accepted
+        }
+    }
+
+    #[async_trait]
+    impl AsyncCtrl for SlowBufferSink {
+        async fn stop(&mut self) -> SinkResult<()> {
+            self.flush_now().await
+        }
+
+        async fn reconnect(&mut self) -> SinkResult<()> {
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl AsyncRecordSink for SlowBufferSink {
+        async fn sink_record(&mut self, data: &DataRecord) -> SinkResult<()> {
+            self.buffer.push(data.clone());
+            self.accepted += 1;
+            if self.buffer.len() >= self.batch {
+                self.flush_now().await?;
+            }
+            Ok(())
+        }
+
+        async fn sink_records(&mut self, data: Vec<Arc<DataRecord>>) -> SinkResult<()> {
+            for record in data {
+                self.sink_record(record.as_ref()).await?;
+            }
+            Ok(())
+        }
+    }
+
+    /// 只接收原始数据的 dead letter sink，写入内容共享给测试断言。
+    #[derive(Clone, Default)]
+    struct DeadLetters(Arc<Mutex<Vec<String>>>);
+
+    impl DeadLetters {
+        fn entries(&self) -> Vec<serde_json::Value> {
+            let lines = self.0.lock().unwrap();
+            lines
+                .iter()
+                .map(|l| serde_json::from_str(l).expect("json line"))
+                .collect()
+        }
+    }
+
+    #[async_trait]
+    impl AsyncCtrl for DeadLetters {
+        async fn stop(&mut self) -> SinkResult<()> {
+            Ok(())
+        }
+
+        async fn reconnect(&mut self) -> SinkResult<()> {
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl AsyncRecordSink for DeadLetters {
+        async fn sink_record(&mut self, _data: &DataRecord) -> SinkResult<()> {
+            Ok(())
+        }
+
+        async fn sink_records(&mut self, _data: Vec<Arc<DataRecord>>) -> SinkResult<()> {
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl AsyncRawDataSink for DeadLetters {
+        async fn sink_str(&mut self, data: &str) -> SinkResult<()> {
+            self.0.lock().unwrap().push(data.to_string());
+            Ok(())
+        }
+
+        async fn sink_bytes(&mut self, data: &[u8]) -> SinkResult<()> {
+            self.sink_str(&String::from_utf8_lossy(data)).await
+        }
+
+        async fn sink_str_batch(&mut self, data: Vec<&str>) -> SinkResult<()> {
+            for item in data {
+                self.sink_str(item).await?;
+            }
+            Ok(())
+        }
+
+        async fn sink_bytes_batch(&mut self, data: Vec<&[u8]>) -> SinkResult<()> {
+            for item in data {
+                self.sink_bytes(item).await?;
+            }
+            Ok(())
+        }
+    }
+
+    fn policy(max_attempts: u32) -> Option<ReconnectPolicy> {
+        Some(ReconnectPolicy {
+            base_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(4),
+            max_attempts,
+            jitter: true,
+        })
+    }
+
+    fn record() -> DataRecord {
+        let mut rec = DataRecord::default();
+        rec.append(DataField::from_chars("msg", "hello"));
+        rec
+    }
+
+    #[tokio::test]
+    async fn transient_failures_are_retried_until_success() {
+        let flaky = FlakySink::new(2, network_error, "es bulk send fail: connection refused");
+        let mut sink = RetryingSink::new(flaky, policy(3));
+        sink.sink_record(&record())
+            .await
+            .expect("third attempt succeeds");
+        assert_eq!(sink.inner().await.calls, 3);
+        assert_eq!(sink.inner().await.written, 1);
+        assert_eq!(sink.retries().await, 2);
+
+        let flaky = FlakySink::new(
+            1,
+            busy_error,
+            "mysql execute fail: Lock wait timeout exceeded",
+        );
+        let mut sink = RetryingSink::new(flaky, policy(3));
+        sink.sink_records(vec![Arc::new(record()); 4])
+            .await
+            .expect("batch retried");
+        assert_eq!(sink.inner().await.written, 4);
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_max_attempts() {
+        let flaky = FlakySink::new(10, timeout_error, "ck send fail: operation timed out");
+        let mut sink = RetryingSink::new(flaky, policy(4));
+        let err = sink.stop().await.expect_err("gives up");
+        assert!(err.to_string().contains("timed out"), "{err}");
+        assert_eq!(sink.inner().await.calls, 4);
+        assert_eq!(sink.retries().await, 3);
+    }
+
+    #[tokio::test]
+    async fn permanent_failures_and_unconfigured_policy_do_not_retry() {
+        let flaky = FlakySink::new(
+            1,
+            permanent,
+            "mysql column `n` value rejected: 'x' is not a valid integer",
+        );
+        let mut sink = RetryingSink::new(flaky, policy(5));
+        assert!(sink.sink_record(&record()).await.is_err());
+        assert_eq!(sink.inner().await.calls, 1);
+
+        let flaky = FlakySink::new(1, network_error, "connection reset by peer");
+        let mut sink = RetryingSink::new(flaky, None);
+        assert!(sink.sink_record(&record()).await.is_err());
+        assert_eq!(sink.inner().await.calls, 1);
+    }
+
+    #[test]
+    fn failures_are_classified_by_reason() {
+        let kind = |err: SinkError| FailureKind::of(&err);
+        assert_eq!(
+            kind(network_error("connection refused")),
+            FailureKind::Retryable
+        );
+        assert_eq!(
+            kind(timeout_error("request timed out")),
+            FailureKind::Retryable
+        );
+        assert_eq!(kind(busy_error("Deadlock found")), FailureKind::Retryable);
+        assert_eq!(
+            kind(status_error(503, "es bulk fail")),
+            FailureKind::Retryable
+        );
+        assert_eq!(
+            kind(status_error(400, "es bulk fail")),
+            FailureKind::Permanent
+        );
+        // 信息中带超时字样但原因不是暂时性错误时不重试
+        assert_eq!(
+            kind(permanent("doris.timeout_ms must be > 0")),
+            FailureKind::Permanent
+        );
+    }
+
+    #[tokio::test]
+    async fn failed_flush_is_retried_without_resending_buffered_records() {
+        let buffered = SlowBufferSink::new(2, Duration::ZERO, false).failing(1);
+        let mut sink = RetryingSink::new(buffered, policy(3));
+        sink.sink_records(vec![Arc::new(record()); 5])
+            .await
+            .expect("flush retried");
+        // 第二条触发的 flush 失败后只重试 flush，再补交其余三条
+        assert_eq!(sink.retries().await, 1);
+        assert_eq!(sink.inner().await.accepted(), 5);
+        assert_eq!(sink.inner().await.written, 4);
+        assert_eq!(sink.inner().await.pending_len(), 1);
+
+        let buffered = SlowBufferSink::new(1, Duration::ZERO, false).failing(2);
+        let mut sink = RetryingSink::new(buffered, policy(3));
+        sink.sink_record(&record()).await.expect("flush retried");
+        assert_eq!(sink.inner().await.accepted(), 1);
+        assert_eq!(sink.inner().await.written, 1);
+    }
+
+    #[test]
+    fn retry_block_is_parsed_and_validated() {
+        let mut params = ParamMap::new();
+        assert_eq!(policy_from_params(&params).unwrap(), None);
+
+        params.insert(
+            "retry".into(),
+            json!({"max_attempts": 5, "base_delay_ms": 50}),
+        );
+        let policy = policy_from_params(&params).unwrap().expect("enabled");
+        assert_eq!(policy.max_attempts, 5);
+        assert_eq!(policy.base_delay, Duration::from_millis(50));
+        assert!(policy.jitter);
+
+        params.insert("retry".into(), json!({}));
+        let policy = policy_from_params(&params)
+            .unwrap()
+            .expect("enabled with defaults");
+        assert_eq!(policy.max_attempts, DEFAULT_MAX_ATTEMPTS);
+
+        for bad in [
+            json!({"max_attempts": 0}),
+            json!({"base_delay_ms": 500, "max_delay_ms": 100}),
+            json!({"jitter": "yes"}),
+            json!({"backoff": 2}),
+            json!(3),
+        ] {
+            params.insert("retry".into(), bad.clone());
+            assert!(policy_from_params(&params).is_err(), "{bad}");
+        }
+    }
+
+    #[tokio::test]
+    async fn records_are_written_or_dead_lettered_within_deadline() {
+        let deadline = Duration::from_millis(80);
+        let latency = Duration::from_millis(20);
+
+        // 批未攒满且没有新写入：定时任务在最早的记录到期后 flush
+        let slow = SlowBufferSink::new(100, latency, false);
+        let mut sink = RetryingSink::new(slow, None).with_write_deadline(Some(deadline));
+        sink.sink_record(&record()).await.unwrap();
+        assert_eq!(sink.inner().await.written, 0);
+        tokio::time::sleep(deadline + latency * 3).await;
+        assert_eq!(sink.inner().await.written, 1);
+        assert_eq!(sink.expired().await, 0);
+
+        // 后端持续超时：重试不越过期限，到期记录转投 dead letter
+        let dead = DeadLetters::default();
+        let slow = SlowBufferSink::new(1, latency, true);
+        let retry = Some(ReconnectPolicy {
+            base_delay: Duration::from_millis(15),
+            max_delay: Duration::from_millis(30),
+            max_attempts: 100,
+            jitter: false,
+        });
+        let mut sink = RetryingSink::new(slow, retry)
+            .with_write_deadline(Some(deadline))
+            .with_dead_letter(
+                "mysql_out".into(),
+                Some(SinkHandle::new(Box::new(dead.clone()))),
+            );
+        let started = Instant::now();
+        sink.sink_record(&record())
+            .await
+            .expect("records left to the deadline are not an error");
+        assert!(
+            started.elapsed() < deadline + latency * 2,
+            "{:?}",
+            started.elapsed()
+        );
+        assert!(sink.retries().await > 0);
+        tokio::time::sleep(deadline + latency * 2).await;
+        assert_eq!(sink.expired().await, 1);
+        assert_eq!(sink.inner().await.pending_len(), 0);
+        let entries = dead.entries();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0]["source"], "mysql_out");
+        assert!(entries[0]["error"].as_str().unwrap().contains("timed out"));
+    }
+
+    #[tokio::test]
+    async fn only_records_past_deadline_are_dead_lettered() {
+        let deadline = Duration::from_millis(200);
+        let dead = DeadLetters::default();
+        let slow = SlowBufferSink::new(100, Duration::ZERO, true);
+        let mut sink = RetryingSink::new(slow, None)
+            .with_write_deadline(Some(deadline))
+            .with_dead_letter(
+                "ck_out".into(),
+                Some(SinkHandle::new(Box::new(dead.clone()))),
+            );
+        sink.sink_record(&record()).await.unwrap();
+        tokio::time::sleep(deadline / 2).await;
+        sink.sink_record(&record()).await.unwrap();
+
+        // 第一条到期时 flush 失败：只转投它，第二条留待重新写入
+        tokio::time::sleep(deadline * 7 / 8).await;
+        assert_eq!(sink.expired().await, 1);
+        assert_eq!(dead.entries().len(), 1);
+        sink.inner().await.down = false;
+
+        tokio::time::sleep(deadline).await;
+        assert_eq!(sink.expired().await, 1);
+        assert_eq!(sink.inner().await.written, 1);
+        assert_eq!(sink.inner().await.pending_len(), 0);
+    }
+
+    #[test]
+    fn deadline_params_are_validated() {
+        let mut params = ParamMap::new();
+        assert_eq!(deadline_from_params(&params).unwrap(), None);
+        params.insert("write_deadline_ms".into(), json!(250));
+        assert_eq!(
+            deadline_from_params(&params).unwrap(),
+            Some(Duration::from_millis(250))
+        );
+        assert!(validate_params(&params).is_ok());
+
+        for bad in [json!(0), json!(-5), json!("1s")] {
+            params.insert("write_deadline_ms".into(), bad.clone());
+            assert!(validate_params(&params).is_err(), "{bad}");
+        }
+    }
+
+    #[tokio::test]
+    async fn registered_handle_reconfigures_inner_sink_by_name() {
+        use crate::common::reconfigure;
+
+        let sink = RetryingSink::new(FlakySink::new(0, permanent, "x"), policy(3));
+        reconfigure::register("retry-reconfigure", sink.reconfigure_handle());
+        let mut params = ParamMap::new();
+        params.insert("failures".into(), json!(2));
+        reconfigure::reconfigure("retry-reconfigure", &params).expect("reconfigured");
+        assert_eq!(sink.inner().await.failures, 2);
+
+        {
+            let _flushing = sink.core.lock().await;
+            let err = reconfigure::reconfigure("retry-reconfigure", &params).unwrap_err();
+            assert_eq!(FailureKind::of(&err), FailureKind::Retryable);
+        }
+        drop(sink);
+        assert!(reconfigure::reconfigure("retry-reconfigure", &params).is_err());
+        assert!(reconfigure::reconfigure("unknown-sink", &params).is_err());
+    }
+}
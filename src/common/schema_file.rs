@@ -0,0 +1,198 @@
+//! DB sink 的列类型文件：无法（或不希望）在线 DESCRIBE 时，以文件声明列名到类型的映射，
+//! 构建期加载，用于按类型格式化与校验写入值。
+//!
+//! 文件为扁平映射，扩展名 `.json` 按 JSON 解析，其余按 TOML 解析：
+//! ```toml
+//! id = "BIGINT"
+//! score = "DECIMAL(10,2)"
+//! active = "BOOLEAN"
+//! message = "VARCHAR(255)"
+//! ```
+//!
+//! 列名大小写不敏感且须唯一；未在文件中出现的列按文本处理。
+
+use serde::Deserialize;
+use serde::de::{MapAccess, Visitor};
+use std::collections::HashSet;
+use std::fmt;
+use std::path::Path;
+
+/// 列值的格式化类别。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnType {
+    Integer,
+    /// 浮点与定点小数
+    Decimal,
+    Boolean,
+    /// 字符串、时间等以引号字面量写入的类型
+    Text,
+}
+
+impl ColumnType {
+    /// 由 SQL 类型名推断，忽略长度/精度与 `UNSIGNED` 等修饰。
+    pub fn from_sql(sql_type: &str) -> Self {
+        let base = sql_type
+            .split(|c: char| c == '(' || c.is_whitespace())
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        match base.as_str() {
+            "tinyint" | "smallint" | "mediumint" | "int" | "integer" | "bigint" | "largeint" => {
+                Self::Integer
+            }
+            "float" | "double" | "real" | "decimal" | "numeric" | "decimalv3" => Self::Decimal,
+            "bool" | "boolean" => Self::Boolean,
+            _ => Self::Text,
+        }
+    }
+
+    /// 按类型渲染 SQL 字面量：数值不加引号，布尔为 `TRUE`/`FALSE`，
+    /// 数值/布尔列的空值为 `NULL`，文本加单引号并转义。
+    ///
+    /// # return
+    /// * `Result<String, String>` - 值与列类型不符时返回描述。
+    pub fn sql_literal(&self, raw: &str) -> Result<String, String> {
+        let trimmed = raw.trim();
+        if *self != Self::Text && trimmed.is_empty() {
+            return Ok("NULL".to_string());
+        }
+        match self {
+            Self::Integer if trimmed.parse::<i128>().is_ok() => Ok(trimmed.to_string()),
+            Self::Decimal if trimmed.parse::<f64>().is_ok_and(f64::is_finite) => {
+                Ok(trimmed.to_string())
+            }
+            Self::Boolean => match trimmed.to_ascii_lowercase().as_str() {
+                "true" | "1" => Ok("TRUE".to_string()),
+                "false" | "0" => Ok("FALSE".to_string()),
+                _ => Err(format!("'{raw}' is not a boolean")),
+            },
+            Self::Text => Ok(format!("'{}'", raw.replace('\'', "''"))),
+            _ => Err(format!("'{raw}' is not a valid {self:?} value")),
+        }
+    }
+}
+
+/// 按文件声明的列类型。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ColumnSchema {
+    /// (列名, 原始类型, 类型类别)，保持文件中的顺序
+    columns: Vec<(String, String, ColumnType)>,
+}
+
+impl ColumnSchema {
+    /// 读取并校验列类型文件。
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        let raw = std::fs::read_to_string(path)
+            .map_err(|e| format!("read schema_file '{}' failed: {e}", path.display()))?;
+        let is_json = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+        Self::parse(&raw, is_json)
+            .map_err(|e| format!("invalid schema_file '{}': {e}", path.display()))
+    }
+
+    /// 解析文件内容：列名非空且（忽略大小写）唯一，类型为非空字符串。
+    pub fn parse(raw: &str, is_json: bool) -> Result<Self, String> {
+        let Entries(entries) = if is_json {
+            serde_json::from_str(raw).map_err(|e| e.to_string())?
+        } else {
+            toml::from_str(raw).map_err(|e| e.to_string())?
+        };
+        if entries.is_empty() {
+            return Err("no columns declared".into());
+        }
+        let mut seen = HashSet::new();
+        let mut columns = Vec::with_capacity(entries.len());
+        for (name, sql_type) in entries {
+            let name = name.trim().to_string();
+            let sql_type = sql_type.trim().to_string();
+            if name.is_empty() || sql_type.is_empty() {
+                return Err("column names and types must be non-empty".into());
+            }
+            if !seen.insert(name.to_ascii_lowercase()) {
+                return Err(format!("duplicate column '{name}'"));
+            }
+            let kind = ColumnType::from_sql(&sql_type);
+            columns.push((name, sql_type, kind));
+        }
+        Ok(Self { columns })
+    }
+
+    /// 列的类型类别；列名忽略大小写，未声明时返回 `None`。
+    pub fn column_type(&self, name: &str) -> Option<ColumnType> {
+        self.columns
+            .iter()
+            .find(|(col, _, _)| col.eq_ignore_ascii_case(name))
+            .map(|(_, _, kind)| *kind)
+    }
+
+    /// 按文件顺序返回列名。
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.columns.iter().map(|(name, _, _)| name.as_str())
+    }
+}
+
+/// 按出现顺序收集的 `列名 = 类型`，保留重复键以便报错（JSON 对象解析为 map 时会静默覆盖）。
+struct Entries(Vec<(String, String)>);
+
+impl<'de> Deserialize<'de> for Entries {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct EntriesVisitor;
+
+        impl<'de> Visitor<'de> for EntriesVisitor {
+            type Value = Entries;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a table of column name = type string")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Entries, A::Error> {
+                let mut entries = Vec::new();
+                while let Some(entry) = map.next_entry::<String, String>()? {
+                    entries.push(entry);
+                }
+                Ok(Entries(entries))
+            }
+        }
+
+        deserializer.deserialize_map(EntriesVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn column_types_follow_sql_names() {
+        assert_eq!(ColumnType::from_sql("BIGINT UNSIGNED"), ColumnType::Integer);
+        assert_eq!(ColumnType::from_sql("decimal(10,2)"), ColumnType::Decimal);
+        assert_eq!(ColumnType::from_sql("Boolean"), ColumnType::Boolean);
+        assert_eq!(ColumnType::from_sql("DATETIME(3)"), ColumnType::Text);
+
+        assert_eq!(ColumnType::Integer.sql_literal(" 42 ").unwrap(), "42");
+        assert_eq!(ColumnType::Decimal.sql_literal("1.50").unwrap(), "1.50");
+        assert_eq!(ColumnType::Decimal.sql_literal("").unwrap(), "NULL");
+        assert_eq!(ColumnType::Boolean.sql_literal("1").unwrap(), "TRUE");
+        assert_eq!(ColumnType::Text.sql_literal("it's").unwrap(), "'it''s'");
+        assert!(ColumnType::Integer.sql_literal("4x").is_err());
+        assert!(ColumnType::Decimal.sql_literal("NaN").is_err());
+    }
+
+    #[test]
+    fn schema_parses_and_rejects_duplicates() {
+        let schema = ColumnSchema::parse("id = \"BIGINT\"\nmsg = \"TEXT\"\n", false).unwrap();
+        assert_eq!(schema.names().collect::<Vec<_>>(), vec!["id", "msg"]);
+        assert_eq!(schema.column_type("ID"), Some(ColumnType::Integer));
+        assert_eq!(schema.column_type("other"), None);
+
+        let err = ColumnSchema::parse(r#"{"id": "INT", "Id": "BIGINT"}"#, true).unwrap_err();
+        assert!(err.contains("duplicate column 'Id'"), "{err}");
+        let err = ColumnSchema::parse(r#"{"id": "INT", "id": "BIGINT"}"#, true).unwrap_err();
+        assert!(err.contains("duplicate"), "{err}");
+        assert!(ColumnSchema::parse(r#"{"id": 1}"#, true).is_err());
+        assert!(ColumnSchema::parse("{}", true).is_err());
+        assert!(ColumnSchema::parse("id = ", false).is_err());
+    }
+}
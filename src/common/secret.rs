@@ -0,0 +1,259 @@
+//! 构建期解析 `secret_ref`：从 secret 存储读取密码、令牌、证书等参数并注入 spec，
+//! 使敏感值不必以明文出现在配置中。
+//!
+//! 配置示例：
+//! ```toml
+//! [params.secret_ref]
+//! provider = "file"                 # env | file | 通过 register_provider 注册的名称，默认 env
+//! path = "/run/secrets/wp.toml"     # file 必填：目录（每个 secret 一个文件）或 TOML 文件
+//! [params.secret_ref.keys]
+//! password = "doris_password"       # 参数名 = secret 名
+//! ```
+//!
+//! 优先级：`keys` 中列出的参数以 secret 为准（覆盖同名内联值）；未列出的参数沿用内联值。
+
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, OnceLock, PoisonError};
+use wp_connector_api::{
+    ParamMap, SinkReason, SinkResult, SinkSpec, SourceReason, SourceResult, SourceSpec,
+};
+
+const SECRET_REF: &str = "secret_ref";
+
+/// 按名称读取 secret 的后端。
+pub trait SecretProvider: Send + Sync {
+    /// # args
+    /// * `name` - secret 名称。
+    ///
+    /// # return
+    /// * `Result<String, String>` - secret 不存在或读取失败时返回描述。
+    fn get(&self, name: &str) -> Result<String, String>;
+}
+
+/// 从同名环境变量读取 secret。
+pub struct EnvSecretProvider;
+
+impl SecretProvider for EnvSecretProvider {
+    fn get(&self, name: &str) -> Result<String, String> {
+        std::env::var(name).map_err(|_| format!("environment variable '{name}' is not set"))
+    }
+}
+
+/// 从文件读取 secret：`path` 为目录时读取 `<path>/<name>`（去掉末尾换行），
+/// 为文件时按 TOML 解析并取 `name` 对应的字符串值。
+pub struct FileSecretProvider {
+    path: PathBuf,
+}
+
+impl FileSecretProvider {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+impl SecretProvider for FileSecretProvider {
+    fn get(&self, name: &str) -> Result<String, String> {
+        if self.path.is_dir() {
+            let file = self.path.join(name);
+            return std::fs::read_to_string(&file)
+                .map(|s| s.trim_end_matches(['\r', '\n']).to_string())
+                .map_err(|e| format!("read secret file '{}' failed: {e}", file.display()));
+        }
+        let raw = std::fs::read_to_string(&self.path)
+            .map_err(|e| format!("read secret file '{}' failed: {e}", self.path.display()))?;
+        let table: toml::Table = toml::from_str(&raw)
+            .map_err(|e| format!("parse secret file '{}' failed: {e}", self.path.display()))?;
+        match table.get(name) {
+            Some(toml::Value::String(s)) => Ok(s.clone()),
+            Some(_) => Err(format!("secret '{name}' must be a string")),
+            None => Err(format!(
+                "secret '{name}' not found in '{}'",
+                self.path.display()
+            )),
+        }
+    }
+}
+
+fn registry() -> &'static Mutex<HashMap<String, Arc<dyn SecretProvider>>> {
+    static REGISTRY: OnceLock<Mutex<HashMap<String, Arc<dyn SecretProvider>>>> = OnceLock::new();
+    REGISTRY.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// 注册自定义 provider（如 Vault 客户端），`secret_ref.provider` 以该名称引用。
+pub fn register_provider(name: &str, provider: Arc<dyn SecretProvider>) {
+    registry()
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .insert(name.to_string(), provider);
+}
+
+/// 解析后的 `secret_ref`。
+pub struct SecretRef {
+    provider: Arc<dyn SecretProvider>,
+    /// 参数名 -> secret 名
+    keys: BTreeMap<String, String>,
+}
+
+impl SecretRef {
+    /// 读取 `secret_ref` 参数；未配置时返回 `None`。
+    pub fn from_params(params: &ParamMap) -> Result<Option<Self>, String> {
+        let obj = match params.get(SECRET_REF) {
+            None | Some(Value::Null) => return Ok(None),
+            Some(Value::Object(obj)) => obj,
+            Some(_) => return Err("secret_ref must be a table".into()),
+        };
+        let provider_name = match obj.get("provider") {
+            None => "env",
+            Some(Value::String(s)) if !s.trim().is_empty() => s.trim(),
+            Some(_) => return Err("secret_ref.provider must be a non-empty string".into()),
+        };
+        let provider: Arc<dyn SecretProvider> = match provider_name {
+            "env" => Arc::new(EnvSecretProvider),
+            "file" => match obj.get("path").and_then(Value::as_str).map(str::trim) {
+                Some(path) if !path.is_empty() => Arc::new(FileSecretProvider::new(path)),
+                _ => return Err("secret_ref.path is required for provider 'file'".into()),
+            },
+            other => registry()
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .get(other)
+                .cloned()
+                .ok_or_else(|| format!("unknown secret_ref.provider '{other}'"))?,
+        };
+        let Some(Value::Object(entries)) = obj.get("keys") else {
+            return Err("secret_ref.keys must be a table of param = secret name".into());
+        };
+        let mut keys = BTreeMap::new();
+        for (param, secret) in entries {
+            match secret.as_str().map(str::trim) {
+                Some(name) if !name.is_empty() && param != SECRET_REF => {
+                    keys.insert(param.clone(), name.to_string());
+                }
+                _ => return Err(format!("secret_ref.keys.{param} must be a secret name")),
+            }
+        }
+        Ok(Some(Self { provider, keys }))
+    }
+
+    /// 读取全部 secret 并写入参数表，同时移除 `secret_ref` 本身。
+    pub fn apply(&self, params: &mut ParamMap) -> Result<(), String> {
+        for (param, name) in &self.keys {
+            let value = self
+                .provider
+                .get(name)
+                .map_err(|e| format!("resolve secret for '{param}' failed: {e}"))?;
+            params.insert(param.clone(), Value::String(value));
+        }
+        params.remove(SECRET_REF);
+        Ok(())
+    }
+}
+
+/// 返回注入 secret 后的参数表；未配置 `secret_ref` 时原样复制。
+pub fn resolve_params(params: &ParamMap) -> Result<ParamMap, String> {
+    let mut resolved = params.clone();
+    if let Some(secret_ref) = SecretRef::from_params(params)? {
+        secret_ref.apply(&mut resolved)?;
+    }
+    Ok(resolved)
+}
+
+/// 供 sink 工厂在 build 开头调用，后续照常从 `spec.params` 读取参数。
+pub fn resolve_sink_spec(spec: &SinkSpec) -> SinkResult<SinkSpec> {
+    let params = resolve_params(&spec.params).map_err(SinkReason::sink)?;
+    Ok(SinkSpec {
+        params,
+        ..spec.clone()
+    })
+}
+
+/// 供 source 工厂在 build 开头调用。
+pub fn resolve_source_spec(spec: &SourceSpec) -> SourceResult<SourceSpec> {
+    let params = resolve_params(&spec.params).map_err(SourceReason::Other)?;
+    Ok(SourceSpec {
+        params,
+        ..spec.clone()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn secret_ref(provider: Value, keys: Value) -> ParamMap {
+        let mut params = ParamMap::new();
+        params.insert("password".into(), json!("inline"));
+        params.insert("user".into(), json!("root"));
+        let mut obj = provider.as_object().cloned().unwrap();
+        obj.insert("keys".into(), keys);
+        params.insert(SECRET_REF.into(), Value::Object(obj));
+        params
+    }
+
+    #[test]
+    fn file_provider_resolves_password_over_inline_value() {
+        let dir = std::env::temp_dir().join(format!("wp_secret_test_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("secrets.toml");
+        std::fs::write(&file, "doris_password = \"s3cr3t\"\n").unwrap();
+
+        let params = secret_ref(
+            json!({ "provider": "file", "path": file.to_str().unwrap() }),
+            json!({ "password": "doris_password" }),
+        );
+        let resolved = resolve_params(&params).unwrap();
+        assert_eq!(resolved.get("password"), Some(&json!("s3cr3t")));
+        assert_eq!(
+            resolved.get("user"),
+            Some(&json!("root")),
+            "inline values kept"
+        );
+        assert!(!resolved.contains_key(SECRET_REF));
+        assert!(!serde_json::to_string(&params).unwrap().contains("s3cr3t"));
+
+        // 目录形式：每个 secret 一个文件
+        std::fs::write(dir.join("token"), "abc\n").unwrap();
+        let params = secret_ref(
+            json!({ "provider": "file", "path": dir.to_str().unwrap() }),
+            json!({ "password": "token" }),
+        );
+        assert_eq!(
+            resolve_params(&params).unwrap().get("password"),
+            Some(&json!("abc"))
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn missing_secrets_and_bad_refs_are_rejected() {
+        let params = secret_ref(
+            json!({ "provider": "env" }),
+            json!({ "password": "WP_SECRET_TEST_SURELY_UNSET" }),
+        );
+        let err = resolve_params(&params).unwrap_err();
+        assert!(err.contains("'password'"), "{err}");
+
+        let params = secret_ref(json!({ "provider": "file" }), json!({ "password": "x" }));
+        assert!(resolve_params(&params).is_err());
+        let params = secret_ref(json!({ "provider": "vault" }), json!({ "password": "x" }));
+        assert!(resolve_params(&params).unwrap_err().contains("vault"));
+    }
+
+    #[test]
+    fn registered_provider_is_used() {
+        struct Fixed;
+        impl SecretProvider for Fixed {
+            fn get(&self, name: &str) -> Result<String, String> {
+                Ok(format!("fixed-{name}"))
+            }
+        }
+        register_provider("fixed_test", Arc::new(Fixed));
+        let params = secret_ref(json!({ "provider": "fixed_test" }), json!({ "token": "t" }));
+        let resolved = resolve_params(&params).unwrap();
+        assert_eq!(resolved.get("token"), Some(&json!("fixed-t")));
+        assert_eq!(resolved.get("password"), Some(&json!("inline")));
+    }
+}
@@ -0,0 +1,311 @@
+//! 运行时自省：连接器登记一个 [`StatsHandle`] 并随处理进度更新，
+//! 通过 [`snapshot_all`] 取得所有连接器的当前状态（缓存条数、最近 flush、最近错误、在途请求、
+//! 累计投递条数、源端 lag），Prometheus 导出器的 HTTP 服务以 `/stats` 输出 JSON。
+//!
+//! 启用 `prometheus` 特性时，已登记的句柄还会在 flush 成功/失败时更新
+//! `sink_last_success_timestamp`/`sink_last_failure_timestamp`（Unix 秒，按 `sink`/`kind`/`destination`
+//! 区分，`destination` 为 sink 配置的写入目标，见 [`StatsHandle::with_destination`]），
+//! 无流量期间保持最近一次的值，便于按“N 分钟内无成功写入”告警；
+//! sink 在 flush 时经 [`StatsHandle::record_delivery`] 上报的条数累计到
+//! `sink_records_attempted_total`/`sink_records_succeeded_total`/`sink_records_failed_total`。
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex, OnceLock};
+
+/// 单个连接器的状态快照。
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ConnectorStats {
+    pub name: String,
+    pub kind: String,
+    /// 写入目标（表、索引、topic 或 endpoint）；source 与未设置时为空
+    pub destination: String,
+    /// 已缓存、尚未写出的记录数
+    pub buffered_records: u64,
+    /// 因过载被丢弃的记录累计数（Doris sink 的 `shed_policy`）
+    pub shed_records: u64,
+    /// 已发出、尚未确认的请求/消息数
+    pub in_flight: u64,
+    /// 累计尝试写出的记录数（成功 + 失败）
+    pub attempted_records: u64,
+    pub succeeded_records: u64,
+    pub failed_records: u64,
+    /// 最近一次成功 flush 的时间（RFC3339）
+    pub last_flush: Option<String>,
+    pub last_error: Option<String>,
+    /// 最近一次错误的时间（RFC3339）
+    pub last_error_at: Option<String>,
+    /// 源端落后于最新位置的消息数
+    pub source_lag: Option<i64>,
+}
+
+/// 连接器持有的状态句柄；克隆后共享同一份状态。
+#[derive(Debug, Clone, Default)]
+pub struct StatsHandle {
+    inner: Arc<Mutex<ConnectorStats>>,
+    /// 经 [`register`] 登记的句柄同时更新 Prometheus 指标
+    exported: bool,
+}
+
+impl StatsHandle {
+    /// 未登记到全局注册表的句柄（仅连接器自身可见）。
+    pub fn detached(name: &str, kind: &str) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(ConnectorStats {
+                name: name.to_string(),
+                kind: kind.to_string(),
+                ..Default::default()
+            })),
+            exported: false,
+        }
+    }
+
+    /// 设置写入目标，作为时间戳指标的 `destination` 标签。
+    pub fn with_destination(self, destination: impl Into<String>) -> Self {
+        let destination = destination.into();
+        self.update(|s| s.destination = destination);
+        self
+    }
+
+    fn update(&self, f: impl FnOnce(&mut ConnectorStats)) {
+        f(&mut self.inner.lock().unwrap_or_else(|e| e.into_inner()));
+    }
+
+    pub fn set_buffered(&self, records: usize) {
+        self.update(|s| s.buffered_records = records as u64);
+    }
+
+    pub fn set_shed(&self, records: u64) {
+        self.update(|s| s.shed_records = records);
+    }
+
+    pub fn set_in_flight(&self, requests: u64) {
+        self.update(|s| s.in_flight = requests);
+    }
+
+    /// 记录一次成功 flush（同时清空缓存计数）。
+    pub fn mark_flush(&self) {
+        let now = chrono::Utc::now();
+        self.update(|s| {
+            s.buffered_records = 0;
+            s.last_flush = Some(now.to_rfc3339());
+        });
+        #[cfg(feature = "prometheus")]
+        self.export_timestamp(true, now);
+    }
+
+    /// 记录一次失败（flush 失败时调用）。
+    pub fn record_error(&self, err: impl ToString) {
+        let err = err.to_string();
+        let now = chrono::Utc::now();
+        self.update(|s| {
+            s.last_error = Some(err);
+            s.last_error_at = Some(now.to_rfc3339());
+        });
+        #[cfg(feature = "prometheus")]
+        self.export_timestamp(false, now);
+    }
+
+    /// 按 flush 结果调用 [`Self::mark_flush`] 或 [`Self::record_error`]。
+    pub fn record_flush<T, E: ToString>(&self, result: &Result<T, E>) {
+        match result {
+            Ok(_) => self.mark_flush(),
+            Err(e) => self.record_error(e),
+        }
+    }
+
+    /// 累计一次 flush 的投递结果：写出成功与失败的记录数。
+    pub fn record_delivery(&self, succeeded: u64, failed: u64) {
+        if succeeded == 0 && failed == 0 {
+            return;
+        }
+        self.update(|s| {
+            s.attempted_records += succeeded + failed;
+            s.succeeded_records += succeeded;
+            s.failed_records += failed;
+        });
+        #[cfg(feature = "prometheus")]
+        if self.exported {
+            let s = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+            metrics::add_delivery(&s.name, &s.kind, succeeded, failed);
+        }
+    }
+
+    /// 更新最近成功/失败时间指标；未登记的句柄不导出。
+    #[cfg(feature = "prometheus")]
+    fn export_timestamp(&self, success: bool, at: chrono::DateTime<chrono::Utc>) {
+        if !self.exported {
+            return;
+        }
+        let s = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        metrics::set_timestamp(success, &s.name, &s.kind, &s.destination, at);
+    }
+
+    pub fn set_lag(&self, lag: i64) {
+        self.update(|s| s.source_lag = Some(lag));
+    }
+
+    pub fn snapshot(&self) -> ConnectorStats {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
+
+/// 按（类型, 名称）登记的句柄：不同类型的连接器可以同名
+type Registry = BTreeMap<(String, String), StatsHandle>;
+
+fn registry() -> &'static Mutex<Registry> {
+    static REGISTRY: OnceLock<Mutex<Registry>> = OnceLock::new();
+    REGISTRY.get_or_init(Default::default)
+}
+
+/// 以连接器类型与名称登记状态句柄；同类型同名重复登记时替换旧句柄（例如重建 sink）。
+pub fn register(name: &str, kind: &str) -> StatsHandle {
+    let handle = StatsHandle {
+        exported: true,
+        ..StatsHandle::detached(name, kind)
+    };
+    registry()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert((kind.to_string(), name.to_string()), handle.clone());
+    handle
+}
+
+/// 所有已登记连接器的快照，按类型、名称排序。
+pub fn snapshot_all() -> Vec<ConnectorStats> {
+    registry()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .values()
+        .map(StatsHandle::snapshot)
+        .collect()
+}
+
+#[cfg(feature = "prometheus")]
+mod metrics {
+    use chrono::{DateTime, Utc};
+    use lazy_static::lazy_static;
+    use prometheus::{GaugeVec, IntCounterVec, register_gauge_vec, register_int_counter_vec};
+
+    lazy_static! {
+        static ref LAST_SUCCESS: GaugeVec = register_gauge_vec!(
+            "sink_last_success_timestamp",
+            "Unix time in seconds of the last successful sink flush.",
+            &["sink", "kind", "destination"]
+        )
+        .expect("register sink_last_success_timestamp fail");
+        static ref LAST_FAILURE: GaugeVec = register_gauge_vec!(
+            "sink_last_failure_timestamp",
+            "Unix time in seconds of the last failed sink flush.",
+            &["sink", "kind", "destination"]
+        )
+        .expect("register sink_last_failure_timestamp fail");
+        static ref ATTEMPTED: IntCounterVec = register_int_counter_vec!(
+            "sink_records_attempted_total",
+            "Records a sink tried to write at flush.",
+            &["sink", "kind"]
+        )
+        .expect("register sink_records_attempted_total fail");
+        static ref SUCCEEDED: IntCounterVec = register_int_counter_vec!(
+            "sink_records_succeeded_total",
+            "Records a sink wrote successfully.",
+            &["sink", "kind"]
+        )
+        .expect("register sink_records_succeeded_total fail");
+        static ref FAILED: IntCounterVec = register_int_counter_vec!(
+            "sink_records_failed_total",
+            "Records a sink failed to write.",
+            &["sink", "kind"]
+        )
+        .expect("register sink_records_failed_total fail");
+    }
+
+    /// 毫秒精度的 Unix 秒
+    fn seconds(at: DateTime<Utc>) -> f64 {
+        at.timestamp_millis() as f64 / 1000.0
+    }
+
+    pub(super) fn set_timestamp(
+        success: bool,
+        sink: &str,
+        kind: &str,
+        destination: &str,
+        at: DateTime<Utc>,
+    ) {
+        let gauge: &GaugeVec = if success {
+            &LAST_SUCCESS
+        } else {
+            &LAST_FAILURE
+        };
+        gauge
+            .with_label_values(&[sink, kind, destination])
+            .set(seconds(at));
+    }
+
+    pub(super) fn add_delivery(sink: &str, kind: &str, succeeded: u64, failed: u64) {
+        ATTEMPTED
+            .with_label_values(&[sink, kind])
+            .inc_by(succeeded + failed);
+        SUCCEEDED.with_label_values(&[sink, kind]).inc_by(succeeded);
+        FAILED.with_label_values(&[sink, kind]).inc_by(failed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn registered_handles_appear_in_snapshot() {
+        let handle = register("stats_unit_sink", "doris");
+        handle.set_buffered(5);
+        handle.set_in_flight(2);
+        handle.record_error("connection reset");
+        let snap = snapshot_all()
+            .into_iter()
+            .find(|s| s.name == "stats_unit_sink")
+            .expect("registered");
+        assert_eq!(snap.kind, "doris");
+        assert_eq!(snap.buffered_records, 5);
+        assert_eq!(snap.in_flight, 2);
+        assert_eq!(snap.last_error.as_deref(), Some("connection reset"));
+        assert_eq!(snap.last_flush, None);
+
+        handle.mark_flush();
+        let snap = handle.snapshot();
+        assert_eq!(snap.buffered_records, 0);
+        assert!(snap.last_flush.is_some());
+    }
+
+    #[test]
+    fn same_name_with_different_kinds_is_kept_apart() {
+        register("stats_shared_name", "kafka").set_buffered(1);
+        register("stats_shared_name", "clickhouse").set_buffered(2);
+        let mut snaps = snapshot_all()
+            .into_iter()
+            .filter(|s| s.name == "stats_shared_name")
+            .map(|s| (s.kind, s.buffered_records))
+            .collect::<Vec<_>>();
+        snaps.sort();
+        assert_eq!(
+            snaps,
+            [("clickhouse".to_string(), 2), ("kafka".to_string(), 1)]
+        );
+    }
+
+    #[test]
+    fn delivery_counts_accumulate() {
+        let handle = StatsHandle::detached("stats_delivery_sink", "clickhouse");
+        handle.record_delivery(9, 1);
+        handle.record_delivery(0, 0);
+        handle.record_delivery(5, 0);
+        let snap = handle.snapshot();
+        assert_eq!(snap.attempted_records, 15);
+        assert_eq!(snap.succeeded_records, 14);
+        assert_eq!(snap.failed_records, 1);
+        assert_eq!(snap.last_error_at, None);
+
+        handle.record_error("timeout");
+        assert!(handle.snapshot().last_error_at.is_some());
+    }
+}
@@ -0,0 +1,148 @@
+//! Sink 侧按记录路由目标表：`table_template = "events_{dataset}"` 中的 `{field}`
+//! 以记录中同名字段的值替换；字段缺失或为空时回退到静态 `table`。
+//!
+//! 字段值中除字母、数字与 `_` 以外的字符一律替换为 `_`，渲染结果可直接作为表名使用。
+
+use serde_json::Value;
+use wp_connector_api::{ParamMap, SinkReason, SinkResult};
+use wp_model_core::model::{DataRecord, DataType};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    Literal(String),
+    Field(String),
+}
+
+/// 已解析的表名模板。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableTemplate {
+    parts: Vec<Part>,
+}
+
+impl TableTemplate {
+    /// 解析模板；花括号必须成对且占位符非空。
+    pub fn parse(raw: &str) -> Result<Self, String> {
+        let mut parts = Vec::new();
+        let mut rest = raw.trim();
+        if rest.is_empty() {
+            return Err("table_template must not be empty".into());
+        }
+        while let Some(start) = rest.find('{') {
+            let (literal, tail) = rest.split_at(start);
+            if literal.contains('}') {
+                return Err(format!("table_template '{raw}' has an unmatched '}}'"));
+            }
+            let end = tail
+                .find('}')
+                .ok_or_else(|| format!("table_template '{raw}' has an unmatched '{{'"))?;
+            let field = tail[1..end].trim();
+            if field.is_empty() || field.contains('{') {
+                return Err(format!("table_template '{raw}' has an empty placeholder"));
+            }
+            if !literal.is_empty() {
+                parts.push(Part::Literal(literal.to_string()));
+            }
+            parts.push(Part::Field(field.to_string()));
+            rest = &tail[end + 1..];
+        }
+        if rest.contains('}') {
+            return Err(format!("table_template '{raw}' has an unmatched '}}'"));
+        }
+        if !rest.is_empty() {
+            parts.push(Part::Literal(rest.to_string()));
+        }
+        Ok(Self { parts })
+    }
+
+    /// 读取 `table_template` 参数；`kind` 用于错误信息前缀。
+    pub fn from_params(params: &ParamMap, kind: &str) -> SinkResult<Option<Self>> {
+        match params.get("table_template") {
+            None | Some(Value::Null) => Ok(None),
+            Some(Value::String(raw)) => Self::parse(raw)
+                .map(Some)
+                .map_err(|e| SinkReason::sink(format!("{kind}.{e}")).into()),
+            Some(_) => {
+                Err(SinkReason::sink(format!("{kind}.table_template must be a string")).into())
+            }
+        }
+    }
+
+    /// 按记录渲染表名；任一占位字段缺失或为空时返回 `None`。
+    pub fn render(&self, record: &DataRecord) -> Option<String> {
+        let mut table = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Literal(text) => table.push_str(text),
+                Part::Field(name) => {
+                    let value = record
+                        .items
+                        .iter()
+                        .filter(|f| *f.get_meta() != DataType::Ignore)
+                        .find(|f| f.get_name() == name.as_str())
+                        .map(|f| f.get_value().to_string())
+                        .filter(|v| !v.trim().is_empty())?;
+                    table.extend(value.trim().chars().map(|c| {
+                        if c.is_ascii_alphanumeric() || c == '_' {
+                            c
+                        } else {
+                            '_'
+                        }
+                    }));
+                }
+            }
+        }
+        Some(table)
+    }
+}
+
+/// 目标表：配置了模板且可渲染时使用渲染结果，否则为 `fallback`。
+pub fn route_table(
+    template: Option<&TableTemplate>,
+    record: &DataRecord,
+    fallback: &str,
+) -> String {
+    template
+        .and_then(|t| t.render(record))
+        .unwrap_or_else(|| fallback.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wp_model_core::model::DataField;
+
+    fn record(dataset: Option<&str>) -> DataRecord {
+        let mut rec = DataRecord::default();
+        if let Some(dataset) = dataset {
+            rec.append(DataField::from_chars("dataset", dataset));
+        }
+        rec.append(DataField::from_chars("day", "2024-05-01"));
+        rec
+    }
+
+    #[test]
+    fn renders_fields_and_falls_back() {
+        let tpl = TableTemplate::parse("events_{dataset}_{ day }").unwrap();
+        assert_eq!(
+            route_table(Some(&tpl), &record(Some("audit")), "events"),
+            "events_audit_2024_05_01"
+        );
+        assert_eq!(
+            route_table(Some(&tpl), &record(Some("a`; DROP")), "events"),
+            "events_a___DROP_2024_05_01"
+        );
+        assert_eq!(route_table(Some(&tpl), &record(None), "events"), "events");
+        assert_eq!(
+            route_table(None, &record(Some("audit")), "events"),
+            "events"
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_templates() {
+        for raw in ["", "events_{", "events_}", "events_{}", "a_{b{c}}"] {
+            assert!(TableTemplate::parse(raw).is_err(), "{raw}");
+        }
+        assert!(TableTemplate::parse("static_table").is_ok());
+    }
+}
@@ -0,0 +1,158 @@
+//! 单元测试辅助：记录所有写入内容的内存 sink，以及按请求序号应答的简易 HTTP 服务端。
+
+use async_trait::async_trait;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use wp_connector_api::{AsyncCtrl, AsyncRawDataSink, AsyncRecordSink, SinkResult};
+use wp_data_fmt::{DataFormat, FormatType};
+use wp_model_core::model::{DataRecord, fmt_def::TextFmt};
+
+#[derive(Default)]
+pub(crate) struct CaptureSink {
+    pub records: Vec<DataRecord>,
+    pub raw: Vec<Vec<u8>>,
+    pub stopped: usize,
+}
+
+impl CaptureSink {
+    /// 以 JSON 格式渲染已接收的记录，便于断言字段。
+    pub fn json_lines(&self) -> Vec<serde_json::Value> {
+        let fmt = FormatType::from(&TextFmt::Json);
+        self.records
+            .iter()
+            .map(|r| serde_json::from_str(&fmt.format_record(r).to_string()).expect("json"))
+            .collect()
+    }
+}
+
+#[async_trait]
+impl AsyncCtrl for CaptureSink {
+    async fn stop(&mut self) -> SinkResult<()> {
+        self.stopped += 1;
+        Ok(())
+    }
+
+    async fn reconnect(&mut self) -> SinkResult<()> {
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl AsyncRecordSink for CaptureSink {
+    async fn sink_record(&mut self, data: &DataRecord) -> SinkResult<()> {
+        self.records.push(data.clone());
+        Ok(())
+    }
+
+    async fn sink_records(&mut self, data: Vec<Arc<DataRecord>>) -> SinkResult<()> {
+        for record in data {
+            self.records.push(record.as_ref().clone());
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl AsyncRawDataSink for CaptureSink {
+    async fn sink_str(&mut self, data: &str) -> SinkResult<()> {
+        self.raw.push(data.as_bytes().to_vec());
+        Ok(())
+    }
+
+    async fn sink_bytes(&mut self, data: &[u8]) -> SinkResult<()> {
+        self.raw.push(data.to_vec());
+        Ok(())
+    }
+
+    async fn sink_str_batch(&mut self, data: Vec<&str>) -> SinkResult<()> {
+        for item in data {
+            self.raw.push(item.as_bytes().to_vec());
+        }
+        Ok(())
+    }
+
+    async fn sink_bytes_batch(&mut self, data: Vec<&[u8]>) -> SinkResult<()> {
+        for item in data {
+            self.raw.push(item.to_vec());
+        }
+        Ok(())
+    }
+}
+
+/// 简易 HTTP/1.1 服务端：同一连接上依次应答多个请求（keep-alive），统计请求数与连接数。
+/// 用于 httpmock 无法表达的场景：按请求序号变化的应答、断言连接复用。
+pub(crate) struct TestServer {
+    pub url: String,
+    requests: Arc<AtomicUsize>,
+    connections: Arc<AtomicUsize>,
+}
+
+impl TestServer {
+    /// `respond` 按请求序号（从 0 开始、跨连接累计）返回状态码与响应体。
+    pub async fn start<F>(respond: F) -> Self
+    where
+        F: Fn(usize) -> (u16, String) + Send + Sync + 'static,
+    {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(AtomicUsize::new(0));
+        let connections = Arc::new(AtomicUsize::new(0));
+        let respond = Arc::new(respond);
+        let (req_counter, conn_counter) = (requests.clone(), connections.clone());
+        tokio::spawn(async move {
+            while let Ok((mut conn, _)) = listener.accept().await {
+                conn_counter.fetch_add(1, Ordering::SeqCst);
+                let (counter, respond) = (req_counter.clone(), respond.clone());
+                tokio::spawn(async move {
+                    let mut buf = Vec::new();
+                    let mut chunk = [0u8; 4096];
+                    loop {
+                        // 读完请求头与 body 后再应答
+                        if let Some(len) = request_len(&buf) {
+                            buf.drain(..len);
+                            let (status, body) = respond(counter.fetch_add(1, Ordering::SeqCst));
+                            let resp = format!(
+                                "HTTP/1.1 {status} -\r\ncontent-length: {}\r\n\r\n{body}",
+                                body.len()
+                            );
+                            if conn.write_all(resp.as_bytes()).await.is_err() {
+                                return;
+                            }
+                            continue;
+                        }
+                        match conn.read(&mut chunk).await {
+                            Ok(0) | Err(_) => return,
+                            Ok(n) => buf.extend_from_slice(&chunk[..n]),
+                        }
+                    }
+                });
+            }
+        });
+        Self {
+            url,
+            requests,
+            connections,
+        }
+    }
+
+    pub fn requests(&self) -> usize {
+        self.requests.load(Ordering::SeqCst)
+    }
+
+    pub fn connections(&self) -> usize {
+        self.connections.load(Ordering::SeqCst)
+    }
+}
+
+/// 缓冲区中第一个完整请求（请求头 + `content-length` 字节的 body）的长度。
+fn request_len(buf: &[u8]) -> Option<usize> {
+    let text = String::from_utf8_lossy(buf).to_ascii_lowercase();
+    let end = text.find("\r\n\r\n")?;
+    let body = text[..end]
+        .lines()
+        .find_map(|l| l.strip_prefix("content-length:"))
+        .and_then(|v| v.trim().parse::<usize>().ok())
+        .unwrap_or(0);
+    Some(end + 4 + body).filter(|len| buf.len() >= *len)
+}
@@ -0,0 +1,102 @@
+//! HTTP 类 sink 共用的 TLS 客户端配置：自定义 CA、客户端证书（PEM）与跳过证书校验，
+//! 以及按 `tls_insecure_hosts` 名单决定单个主机是否跳过校验。
+
+use reqwest::ClientBuilder;
+use wp_connector_api::{SinkError, SinkReason, SinkResult};
+
+/// TLS 配置；证书与私钥均为 PEM 文件路径。
+#[derive(Debug, Default, Clone, Copy)]
+pub struct TlsFiles<'a> {
+    pub ca_cert: Option<&'a str>,
+    pub client_cert: Option<&'a str>,
+    pub client_key: Option<&'a str>,
+}
+
+/// 按 TLS 配置设置客户端构建器；`insecure` 时不校验证书与主机名。
+///
+/// # args
+/// * `sink` - 错误信息前缀，如 `es`、`ck`。
+///
+/// # return
+/// * `SinkResult<ClientBuilder>` - 文件不可读、PEM 无效或客户端证书与私钥未成对配置时返回错误。
+pub fn configure(
+    mut builder: ClientBuilder,
+    files: &TlsFiles,
+    insecure: bool,
+    sink: &str,
+) -> SinkResult<ClientBuilder> {
+    if let Some(path) = files.ca_cert {
+        let pem = read_pem(path, "tls_ca_cert", sink)?;
+        let cert = reqwest::Certificate::from_pem(&pem).map_err(|e| {
+            SinkError::from(SinkReason::Sink(format!("{sink} tls_ca_cert invalid: {e}")))
+        })?;
+        builder = builder.add_root_certificate(cert);
+    }
+    match (files.client_cert, files.client_key) {
+        (Some(cert), Some(key)) => {
+            let cert = read_pem(cert, "tls_client_cert", sink)?;
+            let key = read_pem(key, "tls_client_key", sink)?;
+            let identity = reqwest::Identity::from_pkcs8_pem(&cert, &key).map_err(|e| {
+                SinkError::from(SinkReason::Sink(format!(
+                    "{sink} tls client identity invalid: {e}"
+                )))
+            })?;
+            builder = builder.identity(identity);
+        }
+        (None, None) => {}
+        _ => {
+            return Err(SinkError::from(SinkReason::Sink(format!(
+                "{sink} tls_client_cert and tls_client_key must be set together"
+            ))));
+        }
+    }
+    Ok(builder
+        .danger_accept_invalid_certs(insecure)
+        .danger_accept_invalid_hostnames(insecure))
+}
+
+/// 读取 PEM 格式的证书或私钥文件。
+fn read_pem(path: &str, key: &str, sink: &str) -> SinkResult<Vec<u8>> {
+    std::fs::read(path).map_err(|e| {
+        SinkError::from(SinkReason::Sink(format!(
+            "{sink} {key} `{path}` read fail: {e}"
+        )))
+    })
+}
+
+/// url 的主机名是否在跳过证书校验的名单中：忽略大小写与端口，主机名需完全匹配。
+pub fn is_insecure_host(hosts: &[String], url: &str) -> bool {
+    let Some(host) = reqwest::Url::parse(url)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_ascii_lowercase))
+    else {
+        return false;
+    };
+    hosts.iter().any(|h| h.trim().eq_ignore_ascii_case(&host))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn insecure_hosts_match_hostname_ignoring_case_and_port() {
+        let hosts = vec!["Internal.Local".to_string()];
+        for url in [
+            "https://internal.local",
+            "https://INTERNAL.local:8443",
+            "https://internal.local:9200/_bulk",
+        ] {
+            assert!(is_insecure_host(&hosts, url), "{url}");
+        }
+        for url in [
+            "https://public.example.com:8443",
+            "https://internal.local.evil.com",
+            "https://evil.com/internal.local",
+            "not a url",
+        ] {
+            assert!(!is_insecure_host(&hosts, url), "{url}");
+        }
+        assert!(!is_insecure_host(&[], "https://internal.local"));
+    }
+}
@@ -0,0 +1,182 @@
+//! HTTP sink 的 W3C Trace Context 透传：为每个请求附加 `traceparent`（及可选 `tracestate`），
+//! 使后端访问日志能与管道 trace 关联。
+//!
+//! 配置示例：
+//! ```toml
+//! trace_context = true          # 开启后每个请求携带 traceparent
+//! trace_id_field = "trace_id"   # 可选：记录中携带 trace id（32 位十六进制）的字段
+//! tracestate = "wp=pipeline"    # 可选：原样附加的 tracestate
+//! ```
+//!
+//! trace id 取批内第一条带合法 trace id 的记录，否则按批随机生成；span id 每个请求新生成。
+
+use serde_json::Value;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use wp_connector_api::{ParamMap, SinkReason, SinkResult};
+use wp_model_core::model::DataRecord;
+
+pub const TRACEPARENT: &str = "traceparent";
+pub const TRACESTATE: &str = "tracestate";
+
+/// trace 上下文配置。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TraceContext {
+    pub trace_id_field: Option<String>,
+    pub tracestate: Option<String>,
+}
+
+impl TraceContext {
+    /// 读取 `trace_context`/`trace_id_field`/`tracestate`；未开启时返回 `None`。
+    pub fn from_params(params: &ParamMap) -> SinkResult<Option<Self>> {
+        let enabled = match params.get("trace_context") {
+            None | Some(Value::Null) => false,
+            Some(Value::Bool(b)) => *b,
+            Some(_) => return Err(SinkReason::sink("trace_context must be a boolean").into()),
+        };
+        let trace_id_field = optional_string(params, "trace_id_field")?;
+        let tracestate = optional_string(params, "tracestate")?;
+        if !enabled {
+            if trace_id_field.is_some() || tracestate.is_some() {
+                return Err(SinkReason::sink(
+                    "trace_id_field/tracestate require trace_context = true",
+                )
+                .into());
+            }
+            return Ok(None);
+        }
+        Ok(Some(Self {
+            trace_id_field,
+            tracestate,
+        }))
+    }
+
+    /// 生成一个请求的 trace 头。
+    ///
+    /// # args
+    /// * `records` - 本次请求包含的记录，用于查找 `trace_id_field`。
+    ///
+    /// # return
+    /// * `Vec<(&'static str, String)>` - `traceparent`，以及配置了时的 `tracestate`。
+    pub fn headers<'a>(
+        &self,
+        records: impl IntoIterator<Item = &'a DataRecord>,
+    ) -> Vec<(&'static str, String)> {
+        let trace_id = records
+            .into_iter()
+            .find_map(|record| self.record_trace_id(record));
+        self.headers_with_id(trace_id)
+    }
+
+    /// 读取记录中 `trace_id_field` 的合法 trace id（已转为小写）；供缓冲批量的 sink 提前提取。
+    pub fn record_trace_id(&self, record: &DataRecord) -> Option<String> {
+        let field = self.trace_id_field.as_deref()?;
+        record
+            .get2(field)
+            .and_then(|f| normalize_trace_id(&f.get_value().to_string()))
+    }
+
+    /// 以给定 trace id 生成 trace 头；为 `None` 时随机生成。
+    pub fn headers_with_id(&self, trace_id: Option<String>) -> Vec<(&'static str, String)> {
+        let trace_id =
+            trace_id.unwrap_or_else(|| format!("{:016x}{:016x}", random_u64(), random_u64()));
+        let mut headers = vec![(TRACEPARENT, traceparent(&trace_id, random_u64()))];
+        if let Some(state) = &self.tracestate {
+            headers.push((TRACESTATE, state.clone()));
+        }
+        headers
+    }
+}
+
+fn optional_string(params: &ParamMap, key: &str) -> SinkResult<Option<String>> {
+    match params.get(key) {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::String(s)) if !s.trim().is_empty() => Ok(Some(s.trim().to_string())),
+        Some(_) => Err(SinkReason::sink(format!("{key} must be a non-empty string")).into()),
+    }
+}
+
+/// `version-traceid-spanid-flags`，flags 固定为 sampled。
+fn traceparent(trace_id: &str, span_id: u64) -> String {
+    // span id 全零非法
+    format!("00-{}-{:016x}-01", trace_id, span_id.max(1))
+}
+
+/// 合法 trace id：32 位十六进制且不全为 0，统一转为小写；否则返回 None。
+fn normalize_trace_id(raw: &str) -> Option<String> {
+    let id = raw.trim().to_ascii_lowercase();
+    let valid = id.len() == 32
+        && id.bytes().all(|b| b.is_ascii_hexdigit())
+        && id.bytes().any(|b| b != b'0');
+    valid.then_some(id)
+}
+
+/// 非密码学随机数：每次调用使用新的随机种子哈希器，并混入计数器避免重复。
+fn random_u64() -> u64 {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u64(COUNTER.fetch_add(1, Ordering::Relaxed));
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use wp_model_core::model::DataField;
+
+    fn is_valid_traceparent(value: &str) -> bool {
+        let parts = value.split('-').collect::<Vec<_>>();
+        parts.len() == 4
+            && parts[0] == "00"
+            && normalize_trace_id(parts[1]).as_deref() == Some(parts[1])
+            && parts[2].len() == 16
+            && parts[2].bytes().all(|b| b.is_ascii_hexdigit())
+            && parts[2] != "0000000000000000"
+            && parts[3] == "01"
+    }
+
+    #[test]
+    fn record_trace_id_is_used_when_valid() {
+        let ctx = TraceContext {
+            trace_id_field: Some("trace_id".into()),
+            tracestate: Some("wp=pipeline".into()),
+        };
+        let mut rec = DataRecord::default();
+        rec.append(DataField::from_chars(
+            "trace_id",
+            "4BF92F3577B34DA6A3CE929D0E0E4736",
+        ));
+        let headers = ctx.headers([&rec]);
+        assert!(is_valid_traceparent(&headers[0].1), "{}", headers[0].1);
+        assert!(
+            headers[0]
+                .1
+                .starts_with("00-4bf92f3577b34da6a3ce929d0e0e4736-")
+        );
+        assert_eq!(headers[1], (TRACESTATE, "wp=pipeline".to_string()));
+
+        // 非法 trace id 回退为随机生成
+        let mut bad = DataRecord::default();
+        bad.append(DataField::from_chars("trace_id", "not-a-trace"));
+        let generated = ctx.headers([&bad]);
+        assert!(is_valid_traceparent(&generated[0].1), "{}", generated[0].1);
+        assert_ne!(
+            generated[0].1,
+            ctx.headers([&bad])[0].1,
+            "fresh ids per request"
+        );
+    }
+
+    #[test]
+    fn from_params_requires_explicit_enable() {
+        let mut params = ParamMap::new();
+        assert_eq!(TraceContext::from_params(&params).unwrap(), None);
+        params.insert("trace_id_field".into(), json!("trace_id"));
+        assert!(TraceContext::from_params(&params).is_err());
+        params.insert("trace_context".into(), json!(true));
+        let ctx = TraceContext::from_params(&params).unwrap().unwrap();
+        assert_eq!(ctx.trace_id_field.as_deref(), Some("trace_id"));
+    }
+}
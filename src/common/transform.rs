@@ -0,0 +1,166 @@
+//! Sink 侧字段值变换：在下发前对指定字段做轻量处理（如脱敏 PII）。
+//!
+//! 配置示例：
+//! ```toml
+//! field_transforms = { email = "lowercase", phone = "mask", user_id = "sha256" }
+//! ```
+//! 变换作用于字段值的文本形式，结果以字符串写回原位置；记录中不存在的字段忽略。
+
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use wp_connector_api::{ParamMap, SinkReason, SinkResult};
+use wp_model_core::model::{DataField, DataRecord};
+
+/// `mask` 保留的末尾字符数
+const MASK_KEEP: usize = 4;
+
+/// 单个字段的变换操作。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransformOp {
+    Lowercase,
+    Uppercase,
+    Trim,
+    /// 十六进制小写 SHA-256 摘要
+    Sha256,
+    /// 仅保留末尾 4 个字符，其余替换为 `*`
+    Mask,
+}
+
+impl TransformOp {
+    fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "lowercase" => Some(Self::Lowercase),
+            "uppercase" => Some(Self::Uppercase),
+            "trim" => Some(Self::Trim),
+            "sha256" => Some(Self::Sha256),
+            "mask" => Some(Self::Mask),
+            _ => None,
+        }
+    }
+
+    pub fn apply(&self, value: &str) -> String {
+        match self {
+            Self::Lowercase => value.to_lowercase(),
+            Self::Uppercase => value.to_uppercase(),
+            Self::Trim => value.trim().to_string(),
+            Self::Sha256 => {
+                let digest = Sha256::digest(value.as_bytes());
+                let mut out = String::with_capacity(digest.len() * 2);
+                for b in digest {
+                    let _ = write!(out, "{b:02x}");
+                }
+                out
+            }
+            Self::Mask => {
+                let total = value.chars().count();
+                let keep = if total > MASK_KEEP { MASK_KEEP } else { 0 };
+                value
+                    .chars()
+                    .enumerate()
+                    .map(|(i, c)| if i < total - keep { '*' } else { c })
+                    .collect()
+            }
+        }
+    }
+}
+
+/// 字段 -> 变换操作。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FieldTransforms {
+    pub ops: BTreeMap<String, TransformOp>,
+}
+
+impl FieldTransforms {
+    /// 从 sink 参数中读取 `field_transforms`（对象，值为操作名）。
+    pub fn from_params(params: &ParamMap) -> SinkResult<Self> {
+        let mut conf = Self::default();
+        let map = match params.get("field_transforms") {
+            None | Some(Value::Null) => return Ok(conf),
+            Some(Value::Object(map)) => map,
+            Some(_) => return Err(SinkReason::sink("field_transforms must be a table").into()),
+        };
+        for (field, op) in map {
+            let field = field.trim();
+            if field.is_empty() {
+                return Err(SinkReason::sink("field_transforms keys must not be empty").into());
+            }
+            let Some(op) = op.as_str().and_then(TransformOp::parse) else {
+                return Err(SinkReason::sink(format!(
+                    "invalid field_transforms.{field}; allowed: lowercase,uppercase,trim,sha256,mask"
+                ))
+                .into());
+            };
+            conf.ops.insert(field.to_string(), op);
+        }
+        Ok(conf)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ops.is_empty()
+    }
+
+    /// 原位替换命中字段的值，其余字段保持不变。
+    pub fn apply(&self, record: &mut DataRecord) {
+        for field in record.items.iter_mut() {
+            if let Some(op) = self.ops.get(field.get_name()) {
+                let name = field.get_name().to_string();
+                let value = op.apply(&field.get_value().to_string());
+                *field = DataField::from_chars(name.as_str(), value.as_str());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn each_op_transforms_value() {
+        assert_eq!(
+            TransformOp::Lowercase.apply("Alice@Example.COM"),
+            "alice@example.com"
+        );
+        assert_eq!(TransformOp::Uppercase.apply("eu-west"), "EU-WEST");
+        assert_eq!(TransformOp::Trim.apply("  padded \t"), "padded");
+        assert_eq!(
+            TransformOp::Sha256.apply("abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(TransformOp::Mask.apply("13800138000"), "*******8000");
+        assert_eq!(TransformOp::Mask.apply("abc"), "***");
+    }
+
+    #[test]
+    fn apply_touches_only_configured_fields() {
+        let mut params = ParamMap::new();
+        params.insert(
+            "field_transforms".into(),
+            json!({"email": "lowercase", "phone": "mask", "missing": "trim"}),
+        );
+        let transforms = FieldTransforms::from_params(&params).expect("valid transforms");
+
+        let mut rec = DataRecord::default();
+        rec.append(DataField::from_chars("email", "Bob@Example.com"));
+        rec.append(DataField::from_chars("phone", "5551234567"));
+        rec.append(DataField::from_chars("msg", "Keep Me"));
+        transforms.apply(&mut rec);
+
+        let value = |name: &str| rec.get2(name).map(|f| f.get_value().to_string());
+        assert_eq!(value("email").as_deref(), Some("bob@example.com"));
+        assert_eq!(value("phone").as_deref(), Some("******4567"));
+        assert_eq!(value("msg").as_deref(), Some("Keep Me"));
+        assert_eq!(rec.items.len(), 3);
+    }
+
+    #[test]
+    fn rejects_unknown_op() {
+        let mut params = ParamMap::new();
+        params.insert("field_transforms".into(), json!({"email": "rot13"}));
+        let err = FieldTransforms::from_params(&params).expect_err("unknown op");
+        assert!(format!("{err}").contains("field_transforms.email"));
+    }
+}
@@ -0,0 +1,120 @@
+//! 字段类型到 SQL 列类型的映射：DB sink 按首条记录自动建表时，
+//! 由 [`sql_type_for`] 给出各方言下的列类型，避免各 sink 各自硬编码。
+//!
+//! 无法映射的字段类型（以及记录中缺失的列）按方言的文本类型建列，见 [`SqlDialect::text_type`]。
+
+use wp_model_core::model::DataType;
+
+/// 建表语句的 SQL 方言。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SqlDialect {
+    Mysql,
+    Doris,
+    Postgres,
+    ClickHouse,
+}
+
+impl SqlDialect {
+    /// 兜底的文本列类型。
+    pub fn text_type(&self) -> &'static str {
+        match self {
+            Self::Mysql | Self::Postgres => "TEXT",
+            Self::Doris => "STRING",
+            Self::ClickHouse => "String",
+        }
+    }
+}
+
+/// 字段类型在指定方言下的列类型；时间列保留微秒精度。
+pub fn sql_type_for(meta: &DataType, dialect: SqlDialect) -> &'static str {
+    use SqlDialect::*;
+    match (meta, dialect) {
+        (DataType::Chars, Mysql | Doris) => "VARCHAR(255)",
+        (DataType::Digit, Mysql | Doris | Postgres) => "BIGINT",
+        (DataType::Digit, ClickHouse) => "Int64",
+        (DataType::Float, Mysql | Doris) => "DOUBLE",
+        (DataType::Float, Postgres) => "DOUBLE PRECISION",
+        (DataType::Float, ClickHouse) => "Float64",
+        (DataType::Bool, Mysql | Doris | Postgres) => "BOOLEAN",
+        (DataType::Bool, ClickHouse) => "Bool",
+        (DataType::Time, Mysql | Doris) => "DATETIME(6)",
+        (DataType::Time, Postgres) => "TIMESTAMP(6)",
+        (DataType::Time, ClickHouse) => "DateTime64(6)",
+        (_, dialect) => dialect.text_type(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mapped(dialect: SqlDialect) -> Vec<&'static str> {
+        [
+            DataType::Chars,
+            DataType::Digit,
+            DataType::Float,
+            DataType::Bool,
+            DataType::Time,
+            DataType::Ignore,
+        ]
+        .iter()
+        .map(|meta| sql_type_for(meta, dialect))
+        .collect()
+    }
+
+    #[test]
+    fn mysql_and_doris_types() {
+        assert_eq!(
+            mapped(SqlDialect::Mysql),
+            [
+                "VARCHAR(255)",
+                "BIGINT",
+                "DOUBLE",
+                "BOOLEAN",
+                "DATETIME(6)",
+                "TEXT"
+            ]
+        );
+        assert_eq!(
+            mapped(SqlDialect::Doris),
+            [
+                "VARCHAR(255)",
+                "BIGINT",
+                "DOUBLE",
+                "BOOLEAN",
+                "DATETIME(6)",
+                "STRING"
+            ]
+        );
+    }
+
+    #[test]
+    fn postgres_types() {
+        assert_eq!(
+            mapped(SqlDialect::Postgres),
+            [
+                "TEXT",
+                "BIGINT",
+                "DOUBLE PRECISION",
+                "BOOLEAN",
+                "TIMESTAMP(6)",
+                "TEXT"
+            ]
+        );
+    }
+
+    #[test]
+    fn clickhouse_types() {
+        assert_eq!(
+            mapped(SqlDialect::ClickHouse),
+            [
+                "String",
+                "Int64",
+                "Float64",
+                "Bool",
+                "DateTime64(6)",
+                "String"
+            ]
+        );
+    }
+}
@@ -0,0 +1,131 @@
+//! dev 适配器连接串的查询参数：`scheme://...?k1=v1&k2=v2`。
+//!
+//! 键值按百分号编码解码（`+` 视为空格）；没有 `=` 的键取空值，空键被忽略。
+//! 转为 `ParamMap` 时由 [`query_value`] 按适配器声明的类型解析布尔与整数参数，使其与工厂参数的
+//! 类型一致；未声明的键保持字符串。
+
+use serde_json::Value;
+
+/// 拆分 `?` 之后的查询串为键值对，保持出现顺序。
+pub fn parse_query(query: &str) -> Vec<(String, String)> {
+    query
+        .split('&')
+        .filter_map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            let key = decode(key);
+            (!key.is_empty()).then(|| (key, decode(value)))
+        })
+        .collect()
+}
+
+/// 查询参数值的类型；未声明的键一律按字符串传递。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueryType {
+    Bool,
+    Int,
+}
+
+/// 各 sink 共有的非字符串参数（写入期限、富化、trace 头）。
+const COMMON_TYPES: [(&str, QueryType); 3] = [
+    ("write_deadline_ms", QueryType::Int),
+    ("enrich_overwrite", QueryType::Bool),
+    ("trace_context", QueryType::Bool),
+];
+
+/// 查询参数值转为 JSON：`types` 或各 sink 共有参数中声明为布尔/整数的键按类型解析，
+/// 其余保持字符串，避免 `id_field=123`、`index=2024` 之类的参数被转成数字。
+/// 声明了类型但无法解析的值保留为字符串，由工厂校验报错。
+pub fn query_value(key: &str, raw: &str, types: &[(&str, QueryType)]) -> Value {
+    let ty = types
+        .iter()
+        .chain(COMMON_TYPES.iter())
+        .find(|(k, _)| *k == key)
+        .map(|(_, ty)| *ty);
+    let typed = match ty {
+        Some(QueryType::Bool) => query_bool(raw).map(Value::Bool),
+        Some(QueryType::Int) => raw.parse::<i64>().ok().map(Value::from),
+        None => None,
+    };
+    typed.unwrap_or_else(|| Value::String(raw.to_string()))
+}
+
+/// 布尔型查询参数：`true`/`false`/`1`/`0`（不区分大小写），其余返回 `None`。
+pub fn query_bool(raw: &str) -> Option<bool> {
+    match raw.to_ascii_lowercase().as_str() {
+        "true" | "1" => Some(true),
+        "false" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+/// 百分号解码；非法的 `%xx` 原样保留。
+fn decode(raw: &str) -> String {
+    let bytes = raw.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => out.push(b' '),
+            b'%' if i + 2 < bytes.len() => match (hex(bytes[i + 1]), hex(bytes[i + 2])) {
+                (Some(hi), Some(lo)) => {
+                    out.push(hi << 4 | lo);
+                    i += 3;
+                    continue;
+                }
+                _ => out.push(b'%'),
+            },
+            b => out.push(b),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+fn hex(b: u8) -> Option<u8> {
+    (b as char).to_digit(16).map(|d| d as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pairs_are_decoded_in_order() {
+        assert_eq!(
+            parse_query("table=app%20events&batch=500&flag&=x&q=a+b&bad=%zz"),
+            [
+                ("table".to_string(), "app events".to_string()),
+                ("batch".to_string(), "500".to_string()),
+                ("flag".to_string(), String::new()),
+                ("q".to_string(), "a b".to_string()),
+                ("bad".to_string(), "%zz".to_string()),
+            ]
+        );
+        assert!(parse_query("").is_empty());
+    }
+
+    #[test]
+    fn values_are_typed_only_for_declared_keys() {
+        let types = [("flag", QueryType::Bool), ("size", QueryType::Int)];
+        assert_eq!(query_value("flag", "TRUE", &types), Value::Bool(true));
+        assert_eq!(query_value("flag", "0", &types), Value::Bool(false));
+        assert_eq!(query_value("size", "500", &types), Value::from(500));
+        assert_eq!(
+            query_value("size", "1.5", &types),
+            Value::String("1.5".into())
+        );
+        assert_eq!(query_value("trace_context", "true", &[]), Value::Bool(true));
+        // 未声明的键保持字符串
+        assert_eq!(
+            query_value("id_field", "123", &types),
+            Value::String("123".into())
+        );
+        assert_eq!(
+            query_value("name", "true", &types),
+            Value::String("true".into())
+        );
+        assert_eq!(query_bool("1"), Some(true));
+        assert_eq!(query_bool("False"), Some(false));
+        assert_eq!(query_bool("yes"), None);
+    }
+}
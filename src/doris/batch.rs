@@ -0,0 +1,240 @@
+//! Doris sink 的批量缓冲：按条数攒批，持续过载（如下游长时间写入失败）时按策略丢弃记录，
+//! 使缓冲保持有界，而不是无限增长直至 OOM。
+//!
+//! 配置示例：
+//! ```toml
+//! shed_policy = "drop_oldest"   # none | drop_oldest | drop_newest，默认 none
+//! shed_threshold = 10000        # 缓冲达到该条数后开始丢弃
+//! ```
+
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::str::FromStr;
+use wp_connector_api::{ParamMap, SinkReason, SinkResult};
+
+/// 丢弃日志的间隔：首条及此后每累计该数量时输出一次告警。
+const SHED_LOG_EVERY: u64 = 1000;
+
+/// 超过阈值时的丢弃策略。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ShedPolicy {
+    /// 不丢弃，缓冲不设上限
+    #[default]
+    None,
+    /// 丢弃最早缓存的记录，保留最新数据
+    DropOldest,
+    /// 丢弃新到达的记录，保留已缓存数据
+    DropNewest,
+}
+
+impl FromStr for ShedPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "none" => Ok(Self::None),
+            "drop_oldest" => Ok(Self::DropOldest),
+            "drop_newest" => Ok(Self::DropNewest),
+            other => Err(format!(
+                "invalid shed_policy '{other}'; allowed: none,drop_oldest,drop_newest"
+            )),
+        }
+    }
+}
+
+/// 丢弃配置：策略与触发阈值（条数）。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ShedConf {
+    pub policy: ShedPolicy,
+    pub threshold: Option<usize>,
+}
+
+impl ShedConf {
+    /// 从 sink 参数中读取 `shed_policy` 与 `shed_threshold`；启用丢弃时阈值必填且大于 0。
+    pub fn from_params(params: &ParamMap) -> SinkResult<Self> {
+        let policy = match params.get("shed_policy") {
+            None | Some(Value::Null) => ShedPolicy::None,
+            Some(Value::String(s)) => s.parse().map_err(SinkReason::sink)?,
+            Some(_) => return Err(SinkReason::sink("shed_policy must be a string").into()),
+        };
+        let threshold = match params.get("shed_threshold") {
+            None | Some(Value::Null) => None,
+            Some(v) => match v.as_u64() {
+                Some(n) if n > 0 => Some(n as usize),
+                _ => return Err(SinkReason::sink("shed_threshold must be > 0").into()),
+            },
+        };
+        if policy != ShedPolicy::None && threshold.is_none() {
+            return Err(
+                SinkReason::sink("shed_threshold is required when shed_policy is set").into(),
+            );
+        }
+        Ok(Self { policy, threshold })
+    }
+
+    /// 生效的阈值；策略为 `none` 时不限。
+    pub fn limit(&self) -> Option<usize> {
+        match self.policy {
+            ShedPolicy::None => None,
+            _ => self.threshold,
+        }
+    }
+}
+
+/// 有界批量缓冲。
+#[derive(Debug)]
+pub(crate) struct BatchBuffer<T> {
+    name: String,
+    items: VecDeque<T>,
+    shed: ShedConf,
+    dropped: u64,
+}
+
+impl<T> BatchBuffer<T> {
+    /// # args
+    /// * `name` - 用于告警日志的 sink 名称。
+    /// * `shed` - 丢弃配置。
+    pub fn new(name: impl Into<String>, shed: ShedConf) -> Self {
+        Self {
+            name: name.into(),
+            items: VecDeque::new(),
+            shed,
+            dropped: 0,
+        }
+    }
+
+    /// 追加一条记录；达到阈值时按策略丢弃一条。
+    ///
+    /// # return
+    /// * `bool` - 本次是否发生了丢弃。
+    pub fn push(&mut self, item: T) -> bool {
+        let Some(limit) = self.shed.limit() else {
+            self.items.push_back(item);
+            return false;
+        };
+        if self.items.len() < limit {
+            self.items.push_back(item);
+            return false;
+        }
+        match self.shed.policy {
+            ShedPolicy::DropOldest => {
+                self.items.pop_front();
+                self.items.push_back(item);
+            }
+            ShedPolicy::DropNewest | ShedPolicy::None => {}
+        }
+        self.dropped += 1;
+        if self.dropped % SHED_LOG_EVERY == 1 {
+            wp_log::warn_data!(
+                "[{}] buffer reached shed_threshold {}, shedding records ({:?}); dropped so far: {}",
+                self.name,
+                limit,
+                self.shed.policy,
+                self.dropped
+            );
+        }
+        true
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.items.iter()
+    }
+
+    pub fn clear(&mut self) {
+        self.items.clear();
+    }
+
+    /// 取出全部记录（按到达顺序）。
+    pub fn drain(&mut self) -> Vec<T> {
+        self.items.drain(..).collect()
+    }
+
+    /// 自创建以来因过载丢弃的记录数。
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+}
+
+impl<T> From<Vec<T>> for BatchBuffer<T> {
+    fn from(items: Vec<T>) -> Self {
+        Self {
+            name: String::new(),
+            items: items.into(),
+            shed: ShedConf::default(),
+            dropped: 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn shed(policy: ShedPolicy, threshold: usize) -> ShedConf {
+        ShedConf {
+            policy,
+            threshold: Some(threshold),
+        }
+    }
+
+    #[test]
+    fn drop_oldest_keeps_newest_records() {
+        let mut buf = BatchBuffer::new("test", shed(ShedPolicy::DropOldest, 3));
+        for i in 0..10 {
+            buf.push(i);
+            assert!(buf.len() <= 3);
+        }
+        assert_eq!(buf.drain(), vec![7, 8, 9]);
+        assert_eq!(buf.dropped(), 7);
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn drop_newest_keeps_buffered_records() {
+        let mut buf = BatchBuffer::new("test", shed(ShedPolicy::DropNewest, 3));
+        let shed_flags = (0..5).map(|i| buf.push(i)).collect::<Vec<_>>();
+        assert_eq!(shed_flags, vec![false, false, false, true, true]);
+        assert_eq!(buf.drain(), vec![0, 1, 2]);
+        assert_eq!(buf.dropped(), 2);
+    }
+
+    #[test]
+    fn policy_none_is_unbounded() {
+        let mut buf = BatchBuffer::new("test", shed(ShedPolicy::None, 3));
+        for i in 0..10 {
+            assert!(!buf.push(i));
+        }
+        assert_eq!(buf.len(), 10);
+        assert_eq!(buf.dropped(), 0);
+    }
+
+    #[test]
+    fn shed_conf_from_params() {
+        let mut params = ParamMap::new();
+        assert_eq!(ShedConf::from_params(&params).unwrap(), ShedConf::default());
+
+        params.insert("shed_policy".into(), json!("drop_oldest"));
+        assert!(ShedConf::from_params(&params).is_err());
+
+        params.insert("shed_threshold".into(), json!(500));
+        assert_eq!(
+            ShedConf::from_params(&params).unwrap(),
+            shed(ShedPolicy::DropOldest, 500)
+        );
+
+        params.insert("shed_policy".into(), json!("drop_random"));
+        assert!(ShedConf::from_params(&params).is_err());
+        params.insert("shed_policy".into(), json!("none"));
+        params.insert("shed_threshold".into(), json!(0));
+        assert!(ShedConf::from_params(&params).is_err());
+    }
+}
@@ -1,6 +1,22 @@
-use crate::doris::{DorisSink, config::DorisSinkConfig};
+use crate::common::enrich::{EnrichConf, EnrichSink};
+use crate::common::flush_notify::FlushNotifier;
+use crate::common::health::{CHECK_TIMEOUT, CheckConnection, Readiness, probe};
+use crate::common::reconfigure;
+use crate::common::retry::{self, RetryingSink};
+use crate::common::secret;
+use crate::common::stats;
+use crate::common::table_route::TableTemplate;
+use crate::common::transform::FieldTransforms;
+use crate::doris::batch::ShedConf;
+use crate::doris::{
+    DorisSink,
+    config::{DorisSinkConfig, LoadMode, WriteMode},
+    sanitize_options,
+};
 use async_trait::async_trait;
 use serde_json::{Value, json};
+use sqlx::mysql::{MySqlConnectOptions, MySqlPoolOptions};
+use std::collections::BTreeMap;
 use wp_connector_api::{
     ConnectorDef, ConnectorScope, ParamMap, SinkBuildCtx, SinkDefProvider, SinkError, SinkFactory,
     SinkHandle, SinkReason, SinkResult, SinkSpec,
@@ -15,6 +31,7 @@ impl SinkFactory for DorisSinkFactory {
     }
 
     fn validate_spec(&self, spec: &SinkSpec) -> SinkResult<()> {
+        let spec = &secret::resolve_sink_spec(spec)?;
         ensure_not_empty(spec, "endpoint")?;
         ensure_not_empty(spec, "user")?;
         ensure_not_empty(spec, "table")?;
@@ -29,10 +46,42 @@ impl SinkFactory for DorisSinkFactory {
         {
             return Err(SinkReason::sink("doris.batch must be > 0").into());
         }
+        let load_mode = parse_load_mode(spec)?;
+        let write_mode = parse_write_mode(spec)?;
+        parse_key_columns(spec)?;
+        parse_max_retries(spec)?;
+        parse_column_map(spec)?;
+        parse_string_list(spec, "columns")?;
+        if optional_string(spec, "delete_field").is_some() {
+            if write_mode != WriteMode::Upsert {
+                return Err(
+                    SinkReason::sink("doris.delete_field requires write_mode=upsert").into(),
+                );
+            }
+            if load_mode != LoadMode::Insert {
+                return Err(
+                    SinkReason::sink("doris.delete_field requires load_mode=insert").into(),
+                );
+            }
+        }
+        let shed = ShedConf::from_params(&spec.params)?;
+        let batch = parse_usize_param(spec, &["batch", "batch_size"])?
+            .unwrap_or(DorisSinkConfig::default_batch_size());
+        if let Some(limit) = shed.limit()
+            && limit < batch
+        {
+            return Err(SinkReason::sink("doris.shed_threshold must be >= batch").into());
+        }
+        TableTemplate::from_params(&spec.params, "doris")?;
+        EnrichConf::from_params(&spec.params)?;
+        retry::validate_params(&spec.params)?;
+        FieldTransforms::from_params(&spec.params)?;
+        FlushNotifier::from_params(&spec.params)?;
         Ok(())
     }
 
     async fn build(&self, spec: &SinkSpec, _ctx: &SinkBuildCtx) -> SinkResult<SinkHandle> {
+        let spec = &secret::resolve_sink_spec(spec)?;
         let endpoint = required_param(spec, "endpoint")?;
         let user = required_param(spec, "user")?;
         let password = optional_string(spec, "password").unwrap_or_default();
@@ -50,14 +99,68 @@ impl SinkFactory for DorisSinkFactory {
             create_stmt,
             pool_size,
             batch_size,
-        );
-        let sink = DorisSink::new(cfg).await.map_err(|err| {
-            SinkError::from(SinkReason::sink(format!("init doris sink failed: {err}")))
-        })?;
+        )
+        .with_load_mode(parse_load_mode(spec)?)
+        .with_http_endpoint(optional_string(spec, "http_endpoint"))
+        .with_write_mode(parse_write_mode(spec)?)
+        .with_key_columns(parse_key_columns(spec)?)
+        .with_delete_field(optional_string(spec, "delete_field"))
+        .with_retry(parse_max_retries(spec)?, get_u64(spec, "retry_backoff_ms"))
+        .with_column_map(parse_column_map(spec)?)
+        .with_columns(parse_string_list(spec, "columns")?);
+        let enrich = EnrichConf::from_params(&spec.params)?;
+        let transforms = FieldTransforms::from_params(&spec.params)?;
+        let sink = DorisSink::new(cfg)
+            .await
+            .map_err(|err| {
+                SinkError::from(SinkReason::sink(format!("init doris sink failed: {err}")))
+            })?
+            .with_stats(stats::register(&spec.name, self.kind()))
+            .with_shed(ShedConf::from_params(&spec.params)?)
+            .with_table_template(TableTemplate::from_params(&spec.params, "doris")?)
+            .with_flush_notifier(FlushNotifier::from_params(&spec.params)?);
+        let sink = RetryingSink::from_spec(sink, spec).await?;
+        reconfigure::register(&spec.name, sink.reconfigure_handle());
+        let sink = EnrichSink::new(sink, enrich).with_transforms(transforms);
         Ok(SinkHandle::new(Box::new(sink)))
     }
 }
 
+#[async_trait]
+impl CheckConnection for DorisSinkFactory {
+    /// 连接 FE 的 MySQL 端口执行 `SELECT 1`；不指定库，目标库可由 sink 构建时创建。
+    async fn check_connection(&self, spec: &SinkSpec) -> Readiness {
+        let params = secret::resolve_sink_spec(spec).and_then(|spec| {
+            let endpoint = required_param(&spec, "endpoint")?;
+            let user = required_param(&spec, "user")?;
+            let password = optional_string(&spec, "password").unwrap_or_default();
+            Ok((endpoint, user, password))
+        });
+        let (endpoint, user, password) = match params {
+            Ok(params) => params,
+            Err(err) => return Readiness::not_ready(self.kind(), err),
+        };
+        probe(self.kind(), &endpoint, async {
+            let opts = endpoint
+                .parse::<MySqlConnectOptions>()
+                .map_err(|e| format!("invalid doris.endpoint: {e}"))?;
+            let pool = MySqlPoolOptions::new()
+                .max_connections(1)
+                .acquire_timeout(CHECK_TIMEOUT)
+                .connect_with(sanitize_options(opts, &user, &password))
+                .await
+                .map_err(|e| format!("connect doris fail: {e}"))?;
+            let selected = sqlx::query("SELECT 1")
+                .execute(&pool)
+                .await
+                .map_err(|e| format!("query doris fail: {e}"));
+            pool.close().await;
+            selected.map(|_| None)
+        })
+        .await
+    }
+}
+
 impl SinkDefProvider for DorisSinkFactory {
     fn sink_def(&self) -> ConnectorDef {
         ConnectorDef {
@@ -69,12 +172,32 @@ impl SinkDefProvider for DorisSinkFactory {
                 "database",
                 "user",
                 "password",
+                "secret_ref",
                 "table",
+                "table_template",
                 "create_table",
                 "pool",
                 "pool_size",
                 "batch",
                 "batch_size",
+                "load_mode",
+                "http_endpoint",
+                "write_mode",
+                "key_columns",
+                "delete_field",
+                "max_retries",
+                "retry_backoff_ms",
+                "shed_policy",
+                "shed_threshold",
+                "column_map",
+                "columns",
+                "enrich",
+                "enrich_overwrite",
+                "field_transforms",
+                "retry",
+                "write_deadline_ms",
+                "quarantine",
+                "flush_notify",
             ]
             .into_iter()
             .map(str::to_string)
@@ -196,6 +319,120 @@ fn parse_usize_param(spec: &SinkSpec, keys: &[&str]) -> SinkResult<Option<usize>
     Ok(None)
 }
 
+/// 解析批次写入重试次数 `max_retries`（允许为 0 表示不重试）。
+///
+/// # 参数
+/// * `spec` - Sink 定义。
+///
+/// # 返回
+/// * `SinkResult<Option<u32>>` - 未配置时为 `None`。
+fn parse_max_retries(spec: &SinkSpec) -> SinkResult<Option<u32>> {
+    get_u64(spec, "max_retries")
+        .map(|value| {
+            u32::try_from(value)
+                .map_err(|_| SinkReason::sink("doris.max_retries exceeds u32 range").into())
+        })
+        .transpose()
+}
+
+/// 解析写入方式 `load_mode`（`insert` | `stream_load`），缺省为 `insert`。
+///
+/// # 参数
+/// * `spec` - Sink 定义。
+///
+/// # 返回
+/// * `SinkResult<LoadMode>` - 解析结果。
+fn parse_load_mode(spec: &SinkSpec) -> SinkResult<LoadMode> {
+    match optional_string(spec, "load_mode") {
+        None => Ok(LoadMode::default()),
+        Some(mode) => mode.parse().map_err(|e: String| SinkReason::sink(e).into()),
+    }
+}
+
+/// 解析写入语义 `write_mode`（`append` | `upsert`），缺省为 `append`。
+///
+/// # 参数
+/// * `spec` - Sink 定义。
+///
+/// # 返回
+/// * `SinkResult<WriteMode>` - 解析结果。
+fn parse_write_mode(spec: &SinkSpec) -> SinkResult<WriteMode> {
+    match optional_string(spec, "write_mode") {
+        None => Ok(WriteMode::default()),
+        Some(mode) => mode.parse().map_err(|e: String| SinkReason::sink(e).into()),
+    }
+}
+
+/// 解析 upsert 唯一键列 `key_columns`，缺省为空（沿用默认键）。
+///
+/// # 参数
+/// * `spec` - Sink 定义。
+///
+/// # 返回
+/// * `SinkResult<Vec<String>>` - 去除空白后的列名。
+fn parse_key_columns(spec: &SinkSpec) -> SinkResult<Vec<String>> {
+    Ok(parse_string_list(spec, "key_columns")?.unwrap_or_default())
+}
+
+/// 解析字符串数组或逗号分隔字符串形式的列名列表。
+///
+/// # 参数
+/// * `spec` - Sink 定义。
+/// * `key` - 参数名称。
+///
+/// # 返回
+/// * `SinkResult<Option<Vec<String>>>` - 未配置时为 `None`；配置为空列表时报错。
+fn parse_string_list(spec: &SinkSpec, key: &str) -> SinkResult<Option<Vec<String>>> {
+    let items = match spec.params.get(key) {
+        None | Some(Value::Null) => return Ok(None),
+        Some(Value::String(s)) => s.split(',').map(str::to_string).collect::<Vec<_>>(),
+        Some(Value::Array(items)) => items
+            .iter()
+            .map(|v| v.as_str().map(str::to_string))
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(|| SinkReason::sink(format!("doris.{key} must be strings")))?,
+        Some(_) => {
+            return Err(SinkReason::sink(format!("doris.{key} must be a string array")).into());
+        }
+    };
+    let items = items
+        .iter()
+        .map(|c| c.trim().to_string())
+        .filter(|c| !c.is_empty())
+        .collect::<Vec<_>>();
+    if items.is_empty() {
+        return Err(SinkReason::sink(format!("doris.{key} must not be empty")).into());
+    }
+    Ok(Some(items))
+}
+
+/// 解析字段改名映射 `column_map`（记录字段名 -> 目标列名）。
+///
+/// # 参数
+/// * `spec` - Sink 定义。
+///
+/// # 返回
+/// * `SinkResult<BTreeMap<String, String>>` - 未配置时为空。
+fn parse_column_map(spec: &SinkSpec) -> SinkResult<BTreeMap<String, String>> {
+    let map = match spec.params.get("column_map") {
+        None | Some(Value::Null) => return Ok(BTreeMap::new()),
+        Some(Value::Object(map)) => map,
+        Some(_) => return Err(SinkReason::sink("doris.column_map must be a table").into()),
+    };
+    let mut column_map = BTreeMap::new();
+    for (field, column) in map {
+        let column = column.as_str().map(str::trim).unwrap_or_default();
+        if field.trim().is_empty() || column.is_empty() {
+            return Err(SinkReason::sink(format!(
+                "doris.column_map.{field} must map to a non-empty column name"
+            ))
+            .into());
+        }
+        column_map.insert(field.trim().to_string(), column.to_string());
+    }
+    Ok(column_map)
+}
+
 fn doris_defaults() -> ParamMap {
     let mut params = ParamMap::new();
     params.insert("endpoint".into(), json!("mysql://localhost:9030"));
@@ -242,6 +479,86 @@ mod tests {
         assert!(factory.validate_spec(&spec).is_err());
     }
 
+    #[test]
+    fn validate_checks_load_mode() {
+        let mut spec = base_spec();
+        spec.params
+            .insert("load_mode".into(), Value::String("stream_load".into()));
+        let factory = DorisSinkFactory;
+        assert!(factory.validate_spec(&spec).is_ok());
+        assert_eq!(parse_load_mode(&spec).unwrap(), LoadMode::StreamLoad);
+
+        spec.params
+            .insert("load_mode".into(), Value::String("copy".into()));
+        assert!(factory.validate_spec(&spec).is_err());
+    }
+
+    #[test]
+    fn validate_checks_upsert_params() {
+        let mut spec = base_spec();
+        let factory = DorisSinkFactory;
+        spec.params
+            .insert("delete_field".into(), Value::String("deleted".into()));
+        assert!(factory.validate_spec(&spec).is_err());
+
+        spec.params
+            .insert("write_mode".into(), Value::String("upsert".into()));
+        spec.params
+            .insert("key_columns".into(), json!(["id", " day "]));
+        assert!(factory.validate_spec(&spec).is_ok());
+        assert_eq!(parse_write_mode(&spec).unwrap(), WriteMode::Upsert);
+        assert_eq!(parse_key_columns(&spec).unwrap(), vec!["id", "day"]);
+
+        spec.params
+            .insert("load_mode".into(), Value::String("stream_load".into()));
+        assert!(factory.validate_spec(&spec).is_err());
+
+        spec.params.insert("key_columns".into(), json!([]));
+        assert!(parse_key_columns(&spec).is_err());
+    }
+
+    #[test]
+    fn validate_checks_shed_threshold_against_batch() {
+        let mut spec = base_spec();
+        let factory = DorisSinkFactory;
+        spec.params.insert("batch".into(), json!(100));
+        spec.params
+            .insert("shed_policy".into(), json!("drop_oldest"));
+        spec.params.insert("shed_threshold".into(), json!(50));
+        assert!(factory.validate_spec(&spec).is_err());
+        spec.params.insert("shed_threshold".into(), json!(1000));
+        assert!(factory.validate_spec(&spec).is_ok());
+    }
+
+    #[test]
+    fn parse_column_map_and_whitelist() {
+        let mut spec = base_spec();
+        assert!(parse_column_map(&spec).unwrap().is_empty());
+        assert_eq!(parse_string_list(&spec, "columns").unwrap(), None);
+
+        spec.params.insert(
+            "column_map".into(),
+            json!({"name": "user_name", "ip": " src_ip "}),
+        );
+        spec.params.insert("columns".into(), json!("id, user_name"));
+        let factory = DorisSinkFactory;
+        assert!(factory.validate_spec(&spec).is_ok());
+        assert_eq!(
+            parse_column_map(&spec).unwrap(),
+            BTreeMap::from([
+                ("ip".to_string(), "src_ip".to_string()),
+                ("name".to_string(), "user_name".to_string())
+            ])
+        );
+        assert_eq!(
+            parse_string_list(&spec, "columns").unwrap(),
+            Some(vec!["id".to_string(), "user_name".to_string()])
+        );
+
+        spec.params.insert("column_map".into(), json!({"name": 1}));
+        assert!(factory.validate_spec(&spec).is_err());
+    }
+
     #[test]
     fn validate_accepts_minimal_spec() {
         let spec = base_spec();
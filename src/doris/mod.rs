@@ -1,11 +1,20 @@
 //! Doris sink implementation for wp-connectors
 //!
-//! 当前仅提供 Sink 实现，负责连接 Doris（MySQL 协议）、建表与批量写入。
+//! 当前仅提供 Sink 实现，负责连接 Doris（MySQL 协议）、建表与批量写入；
+//! 写入方式可选批量 INSERT 或 HTTP Stream Load。
 
+mod batch;
 mod config;
 mod factory;
 mod sink;
+pub(crate) mod stream_load;
 
-pub use config::DorisSinkConfig;
+pub use batch::{ShedConf, ShedPolicy};
+pub use config::{DorisSinkConfig, LoadMode, WriteMode};
 pub use factory::DorisSinkFactory;
 pub use sink::DorisSink;
+// 供 StarRocks sink 复用的建表与表结构读取
+#[cfg(feature = "starrocks")]
+pub(crate) use sink::{
+    ensure_table_exists, load_table_columns, quote_identifier, sanitize_options,
+};
@@ -1,25 +1,220 @@
-use crate::doris::config::DorisSinkConfig;
+use crate::common::flush_notify::FlushNotifier;
+use crate::common::reconfigure::{self, Reconfigure};
+use crate::common::reconnect::{ReconnectCoordinator, ReconnectPolicy, backend_key};
+use crate::common::retry::{self, FailureKind, PendingFlush};
+use crate::common::stats::{ConnectorStats, StatsHandle};
+use crate::common::table_route::{TableTemplate, route_table};
+use crate::doris::batch::{BatchBuffer, ShedConf};
+use crate::doris::config::{DorisSinkConfig, LoadMode, WriteMode};
+use crate::doris::stream_load::{StreamLoader, next_label};
 use async_trait::async_trait;
 use sqlx::{
-    Row,
-    mysql::{MySqlConnectOptions, MySqlPool, MySqlPoolOptions},
+    MySql, QueryBuilder, Row,
+    mysql::{MySqlConnectOptions, MySqlDatabaseError, MySqlPool, MySqlPoolOptions},
     raw_sql,
 };
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
+use std::time::Duration;
 use wp_connector_api::{
-    AsyncCtrl, AsyncRawDataSink, AsyncRecordSink, SinkError, SinkReason, SinkResult,
+    AsyncCtrl, AsyncRawDataSink, AsyncRecordSink, ParamMap, SinkError, SinkReason, SinkResult,
 };
 use wp_model_core::model::{DataRecord, DataType};
 
+/// 单条 INSERT 语句允许的占位符上限（MySQL 协议限制）。
+const MAX_BIND_PARAMS: usize = 65_535;
+/// UNIQUE KEY 表的隐藏删除标记列
+const DELETE_SIGN_COLUMN: &str = "__DORIS_DELETE_SIGN__";
+
+/// MySQL 错误码中表示连接中断或服务端繁忙、可重试的部分：
+/// 1040 连接数过多、1053 服务端关闭中、1205 锁等待超时、1213 死锁、2006 连接已断开、2013 查询中断开。
+const RETRYABLE_MYSQL_CODES: [u16; 6] = [1040, 1053, 1205, 1213, 2006, 2013];
+/// 其中需要重建连接池的错误码
+const CONNECTION_MYSQL_CODES: [u16; 3] = [1053, 2006, 2013];
+
+/// 已冻结的 Stream Load 批次：label 与请求体在首次导入前确定，成功前的重试原样重发；
+/// 期间新到的记录进入表缓存，留待下一批次，不会并入同一 label 而被服务端去重丢弃。
+struct InFlightLoad {
+    label: String,
+    /// 无可写记录时为 None
+    body: Option<Vec<u8>>,
+    records: BatchBuffer<Buffered>,
+}
+
+/// 缓存中的记录及其接收序号（见 [`PendingFlush::pending_seqs`]）。
+type Buffered = (u64, DataRecord);
+
+/// 一次写出尝试的失败及其处理方式。
+#[derive(Debug)]
+struct FlushFailure {
+    msg: String,
+    /// 是否值得重试（瞬时错误）；SQL 语法、类型等错误重试无意义
+    retryable: bool,
+    /// 重试前是否重建连接池
+    reconnect: bool,
+}
+
+impl FlushFailure {
+    fn fatal(msg: String) -> Self {
+        Self {
+            msg,
+            retryable: false,
+            reconnect: false,
+        }
+    }
+
+    /// 转为上报的错误：断连为网络错误，其余瞬时错误为过载错误，供上层重试判断。
+    fn into_error(self) -> SinkError {
+        match (self.retryable, self.reconnect) {
+            (true, true) => retry::network_error(self.msg),
+            (true, false) => retry::busy_error(self.msg),
+            _ => sink_error(self.msg),
+        }
+    }
+
+    /// 按 sqlx 错误类型分类。
+    ///
+    /// # args
+    /// * `context` - 错误描述前缀。
+    /// * `err` - 写入时返回的错误。
+    fn from_sqlx(context: &str, err: &sqlx::Error) -> Self {
+        let (retryable, reconnect) = match err {
+            sqlx::Error::Io(_)
+            | sqlx::Error::Tls(_)
+            | sqlx::Error::Protocol(_)
+            | sqlx::Error::PoolClosed
+            | sqlx::Error::WorkerCrashed => (true, true),
+            sqlx::Error::PoolTimedOut => (true, false),
+            sqlx::Error::Database(db) => {
+                match db
+                    .try_downcast_ref::<MySqlDatabaseError>()
+                    .map(|e| e.number())
+                {
+                    Some(code) => (
+                        RETRYABLE_MYSQL_CODES.contains(&code),
+                        CONNECTION_MYSQL_CODES.contains(&code),
+                    ),
+                    None => (false, false),
+                }
+            }
+            _ => (false, false),
+        };
+        Self {
+            msg: format!("{}: {}", context, err),
+            retryable,
+            reconnect,
+        }
+    }
+}
+
+/// 列的绑定类型，由 information_schema.COLUMNS.DATA_TYPE 推断。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ColumnKind {
+    Int,
+    Float,
+    Bool,
+    /// 字符串、时间以及 DECIMAL/LARGEINT 等需服务端精确转换的类型
+    Text,
+}
+
+impl ColumnKind {
+    fn from_data_type(data_type: &str) -> Self {
+        match data_type.to_ascii_lowercase().as_str() {
+            "tinyint" | "smallint" | "int" | "integer" | "bigint" => Self::Int,
+            "float" | "double" => Self::Float,
+            "boolean" | "bool" => Self::Bool,
+            _ => Self::Text,
+        }
+    }
+}
+
+/// 一个待绑定的参数值。
+#[derive(Debug, Clone, PartialEq)]
+enum BindValue {
+    Null,
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    Text(String),
+}
+
+impl BindValue {
+    /// 按列类型转换字段值：数值/布尔列空值绑定为 NULL，无法解析时按原文本绑定交由服务端校验。
+    ///
+    /// # args
+    /// * `kind` - 目标列的绑定类型。
+    /// * `raw` - 字段的字符串形式。
+    fn parse(kind: ColumnKind, raw: &str) -> Self {
+        let trimmed = raw.trim();
+        if kind != ColumnKind::Text && trimmed.is_empty() {
+            return Self::Null;
+        }
+        let parsed = match kind {
+            ColumnKind::Int => trimmed.parse().ok().map(Self::Int),
+            ColumnKind::Float => trimmed
+                .parse::<f64>()
+                .ok()
+                .filter(|v| v.is_finite())
+                .map(Self::Float),
+            ColumnKind::Bool => match trimmed.to_ascii_lowercase().as_str() {
+                "true" | "1" => Some(Self::Bool(true)),
+                "false" | "0" => Some(Self::Bool(false)),
+                _ => None,
+            },
+            ColumnKind::Text => None,
+        };
+        parsed.unwrap_or_else(|| Self::Text(raw.to_string()))
+    }
+}
+
 pub struct DorisSink {
     pub pool: MySqlPool,
-    quoted_table: String,
+    /// 重建连接池所需的连接参数
+    connect_options: MySqlConnectOptions,
+    pool_size: u32,
+    /// 批次写入的重试退避：`max_attempts` 即 `max_retries`
+    retry: ReconnectPolicy,
+    database: String,
+    /// 默认目标表；未配置 `table_template` 或渲染失败时写入此表
+    table: String,
     column_order: Vec<String>,
     quoted_columns: Vec<String>,
     column_set: HashSet<String>,
+    /// 与 `column_order` 一一对应的绑定类型
+    column_kinds: Vec<ColumnKind>,
+    /// 记录字段名 -> 目标列名
+    column_map: HashMap<String, String>,
+    /// 目标列白名单；`column_order` 只保留其中的列
+    whitelist: Option<HashSet<String>>,
     batch_size: usize,
-    pending_values: Vec<String>,
+    /// 按目标表分组的待写入记录；在 flush 时按当前列序生成 VALUES，便于表结构变更后重试
+    pending: HashMap<String, BatchBuffer<Buffered>>,
+    /// 新建各表缓存时使用的丢弃配置（阈值按表生效）
+    shed: ShedConf,
+    /// 已丢弃的记录总数（含已写出并移除的表缓存）
+    shed_records: u64,
+    /// 累计接收的记录数，供重试时判断哪些记录已进入缓存
+    accepted: u64,
+    /// 与同一 Doris FE 的其他 sink 共享的重连退避
+    reconnect: ReconnectCoordinator,
+    /// `load_mode = stream_load` 时的 HTTP 导入客户端；为 None 时走批量 INSERT
+    stream_load: Option<StreamLoader>,
+    /// 各表已冻结、导入成功前重试沿用的 Stream Load 批次
+    in_flight: HashMap<String, InFlightLoad>,
+    stats: StatsHandle,
+    write_mode: WriteMode,
+    /// upsert 模式下的唯一键列，用于批内去重
+    key_columns: Vec<String>,
+    /// upsert 模式下的删除标记字段
+    delete_field: Option<String>,
+    /// 按记录字段路由目标表；路由到的表与默认表共用列信息
+    table_template: Option<TableTemplate>,
+    /// 路由到不存在的表时使用的建表模板
+    create_table: Option<String>,
+    /// 已确认存在的表
+    known_tables: HashSet<String>,
+    /// 每批成功写出后通知外部协调方
+    flush_notifier: FlushNotifier,
 }
 
 impl DorisSink {
@@ -38,7 +233,7 @@ impl DorisSink {
         );
         let pool = MySqlPoolOptions::new()
             .max_connections(config.pool_size.max(1))
-            .connect_with(db_opts)
+            .connect_with(db_opts.clone())
             .await?;
 
         ensure_table_exists(
@@ -53,92 +248,580 @@ impl DorisSink {
         if column_order.is_empty() {
             anyhow::bail!("table `{}` has no columns", config.table);
         }
-        let column_set = column_order.iter().cloned().collect::<HashSet<_>>();
-        let quoted_columns = column_order
-            .iter()
-            .map(|name| quote_identifier(name))
-            .collect::<Vec<_>>();
-        let quoted_table = quote_identifier(&format!("{}.{}", config.database, config.table));
+        check_column_targets(&config, &column_order)?;
+        let whitelist = config
+            .columns
+            .as_ref()
+            .map(|columns| columns.iter().cloned().collect::<HashSet<_>>());
+        let column_order = select_columns(column_order, whitelist.as_ref());
+        if config.write_mode == WriteMode::Upsert {
+            check_key_columns(&config.key_columns, &column_order)?;
+            if config.delete_field.is_some() && config.load_mode == LoadMode::StreamLoad {
+                anyhow::bail!("doris.delete_field is only supported with load_mode=insert");
+            }
+        }
+        let stream_load = match config.load_mode {
+            LoadMode::Insert => None,
+            LoadMode::StreamLoad => Some(StreamLoader::new(
+                &config.stream_load_base(),
+                &config.database,
+                &config.user,
+                &config.password,
+            )?),
+        };
 
-        Ok(Self {
+        let mut sink = Self {
             pool,
-            quoted_table,
-            column_order,
-            quoted_columns,
-            column_set,
+            connect_options: db_opts,
+            pool_size: config.pool_size.max(1),
+            retry: retry_policy(config.max_retries, config.retry_backoff_ms),
+            database: config.database.clone(),
+            table: config.table.clone(),
+            column_order: Vec::new(),
+            quoted_columns: Vec::new(),
+            column_set: HashSet::new(),
+            column_kinds: Vec::new(),
+            column_map: config.column_map.clone().into_iter().collect(),
+            whitelist,
             batch_size: config.batch_size,
-            pending_values: Vec::with_capacity(config.batch_size),
-        })
+            pending: HashMap::new(),
+            shed: ShedConf::default(),
+            shed_records: 0,
+            accepted: 0,
+            reconnect: ReconnectCoordinator::shared(
+                backend_key("doris", &config.endpoint),
+                Default::default(),
+            ),
+            stream_load,
+            in_flight: HashMap::new(),
+            stats: StatsHandle::detached(&config.table, "doris"),
+            write_mode: config.write_mode,
+            key_columns: config.key_columns.clone(),
+            delete_field: config.delete_field.clone(),
+            table_template: None,
+            create_table: config.create_table.clone(),
+            known_tables: HashSet::from([config.table.clone()]),
+            flush_notifier: FlushNotifier::default(),
+        };
+        sink.apply_columns(column_order);
+        Ok(sink)
+    }
+
+    /// 使用外部登记的状态句柄（见 [`crate::common::stats::register`]），
+    /// 以配置的默认表作为指标的写入目标。
+    pub fn with_stats(mut self, stats: StatsHandle) -> Self {
+        self.stats = stats.with_destination(self.table.as_str());
+        self
     }
 
-    /// 生成固定的 INSERT 语句前缀（含表名和列名）。
+    /// 缓存过载时的丢弃策略（见 [`ShedConf`]），阈值按目标表分别生效。
+    pub fn with_shed(mut self, shed: ShedConf) -> Self {
+        self.shed = shed;
+        self
+    }
+
+    /// 按记录字段路由目标表（见 [`crate::common::table_route`]）。
+    pub fn with_table_template(mut self, table_template: Option<TableTemplate>) -> Self {
+        self.table_template = table_template;
+        self
+    }
+
+    /// 每批成功写出后的通知（见 [`crate::common::flush_notify`]）。
+    pub fn with_flush_notifier(mut self, flush_notifier: FlushNotifier) -> Self {
+        self.flush_notifier = flush_notifier;
+        self
+    }
+
+    /// 当前缓存与最近 flush/错误状态。
+    pub fn stats(&self) -> ConnectorStats {
+        self.stats.snapshot()
+    }
+
+    /// 以新的列信息刷新 `column_order`/`column_set`/`quoted_columns`/`column_kinds`。
+    ///
+    /// # args
+    /// * `columns` - 按 ordinal_position 排序的 `(列名, DATA_TYPE)`。
+    fn apply_columns(&mut self, columns: Vec<(String, String)>) {
+        let (column_order, kinds): (Vec<String>, Vec<ColumnKind>) = columns
+            .into_iter()
+            .map(|(name, data_type)| (name, ColumnKind::from_data_type(&data_type)))
+            .unzip();
+        self.column_kinds = kinds;
+        self.column_set = column_order.iter().cloned().collect();
+        self.quoted_columns = column_order
+            .iter()
+            .map(|name| quote_identifier(name))
+            .collect();
+        self.column_order = column_order;
+    }
+
+    /// 重新读取表结构（ALTER 后列序或列集合可能变化）。
     ///
     /// # return
-    /// * `String` - 形如 `INSERT INTO db.table (col1,...) VALUES ` 的片段。
-    fn base_insert_prefix(&self) -> String {
-        format!(
-            "INSERT INTO {} ({}) VALUES ",
-            self.quoted_table,
-            self.quoted_columns.join(", ")
-        )
+    /// * `SinkResult<()>` - 成功表示列信息已刷新。
+    async fn reload_columns(&mut self) -> SinkResult<()> {
+        let columns = load_table_columns(&self.pool, &self.database, &self.table)
+            .await
+            .map_err(|e| sink_error(format!("doris reload columns fail: {}", e)))?;
+        let columns = select_columns(columns, self.whitelist.as_ref());
+        if columns.is_empty() {
+            return Err(sink_error(format!("table `{}` has no columns", self.table)));
+        }
+        wp_log::info_data!(
+            "[doris] reloaded columns for {}: {:?} -> {:?}",
+            self.quoted_table(&self.table),
+            self.column_order,
+            columns.iter().map(|(name, _)| name).collect::<Vec<_>>()
+        );
+        if self.write_mode == WriteMode::Upsert {
+            check_key_columns(&self.key_columns, &columns)
+                .map_err(|e| sink_error(format!("doris upsert disabled: {}", e)))?;
+        }
+        self.apply_columns(columns);
+        Ok(())
     }
 
-    /// 将一条 [`DataRecord`] 转换成 `(v1, v2, ..)` 形式的 VALUES 片段。
+    /// 是否在每行末尾写入 `__DORIS_DELETE_SIGN__`（upsert 且配置了删除标记字段）。
+    fn writes_delete_sign(&self) -> bool {
+        self.write_mode == WriteMode::Upsert && self.delete_field.is_some()
+    }
+
+    /// 以反引号引用的 `db.table`。
+    fn quoted_table(&self, table: &str) -> String {
+        quote_identifier(&format!("{}.{}", self.database, table))
+    }
+
+    /// 生成固定的 INSERT 语句前缀（含表名和列名），VALUES 部分由绑定参数构成。
+    ///
+    /// # args
+    /// * `table` - 目标表名。
+    ///
+    /// # return
+    /// * `String` - 形如 `INSERT INTO db.table (col1,...) ` 的片段；写删除标记时追加该列。
+    fn base_insert_prefix(&self, table: &str) -> String {
+        let mut columns = self.quoted_columns.join(", ");
+        if self.writes_delete_sign() {
+            columns.push_str(", ");
+            columns.push_str(&quote_identifier(DELETE_SIGN_COLUMN));
+        }
+        format!("INSERT INTO {} ({}) ", self.quoted_table(table), columns)
+    }
+
+    /// 指定表的缓存记录；表无缓存时为空。
+    fn pending_records(&self, table: &str) -> impl Iterator<Item = &DataRecord> {
+        self.pending
+            .get(table)
+            .into_iter()
+            .flat_map(|buf| buf.iter())
+            .map(|(_, record)| record)
+    }
+
+    /// 所有表的缓存记录总数（含已冻结的 Stream Load 批次）。
+    fn buffered(&self) -> usize {
+        let frozen: usize = self.in_flight.values().map(|load| load.records.len()).sum();
+        self.pending.values().map(BatchBuffer::len).sum::<usize>() + frozen
+    }
+
+    /// 待写出批次的记录数：Stream Load 为已冻结批次，批量 INSERT 为表缓存。
+    fn batch_len(&self, table: &str) -> usize {
+        match self.stream_load {
+            Some(_) => self
+                .in_flight
+                .get(table)
+                .map_or(0, |load| load.records.len()),
+            None => self.pending.get(table).map_or(0, BatchBuffer::len),
+        }
+    }
+
+    /// 取出已写出的批次。
+    fn take_batch(&mut self, table: &str) -> Option<BatchBuffer<Buffered>> {
+        match self.stream_load {
+            Some(_) => self.in_flight.remove(table).map(|load| load.records),
+            None => self.pending.remove(table),
+        }
+    }
+
+    /// 将表缓存冻结为待导入批次，确定其 label 与请求体；缓存为空时不做处理。
+    fn freeze_batch(&mut self, table: &str) {
+        if self.pending.get(table).is_none_or(BatchBuffer::is_empty) {
+            return;
+        }
+        let body = self.stream_load_body(table);
+        if let Some(records) = self.pending.remove(table) {
+            let label = next_label(&self.database, table);
+            self.in_flight.insert(
+                table.to_string(),
+                InFlightLoad {
+                    label,
+                    body,
+                    records,
+                },
+            );
+        }
+    }
+
+    /// 将一条 [`DataRecord`] 按当前列序转换为绑定参数；缺失字段绑定为 NULL。
     ///
     /// # args
     /// * `record` - 上层传入的数据记录。
     ///
     /// # return
-    /// * `Option<String>` - 若存在可写字段则返回 VALUES 字符串，否则为 `None`。
-    fn format_values_tuple(&self, record: &DataRecord) -> Option<String> {
-        let mut field_map: HashMap<&str, String> = HashMap::new();
+    /// * `Option<Vec<BindValue>>` - 若存在可写字段则返回整行参数，否则为 `None`。
+    fn record_values(&self, record: &DataRecord) -> Option<Vec<BindValue>> {
+        let field_map = self.writable_fields(record);
+        if field_map.is_empty() {
+            return None;
+        }
+
+        let mut values = self
+            .column_order
+            .iter()
+            .zip(&self.column_kinds)
+            .map(|(column, kind)| match field_map.get(column.as_str()) {
+                Some(value) => BindValue::parse(*kind, value),
+                None => BindValue::Null,
+            })
+            .collect::<Vec<_>>();
+        if self.writes_delete_sign() {
+            values.push(BindValue::Int(i64::from(self.is_delete(record))));
+        }
+        Some(values)
+    }
+
+    /// 记录是否携带删除标记（`delete_field` 的值为 true/1/yes）。
+    fn is_delete(&self, record: &DataRecord) -> bool {
+        let Some(marker) = &self.delete_field else {
+            return false;
+        };
+        record
+            .items
+            .iter()
+            .find(|field| field.get_name() == marker.as_str())
+            .map(|field| field.get_value().to_string())
+            .is_some_and(|value| {
+                matches!(
+                    value.trim().to_ascii_lowercase().as_str(),
+                    "true" | "1" | "yes"
+                )
+            })
+    }
+
+    /// 记录的唯一键取值，按 `key_columns` 顺序；缺少任一键列时为 `None`，不参与批内去重。
+    fn record_key(&self, record: &DataRecord) -> Option<Vec<String>> {
+        let field_map = self.writable_fields(record);
+        self.key_columns
+            .iter()
+            .map(|column| field_map.get(column.as_str()).cloned())
+            .collect()
+    }
+
+    /// 取出记录中可写入目标列的字段（忽略 `Ignore` 类型），键为目标列名。
+    /// 字段按 `column_map` 改名后匹配列；显式映射优先于同名字段。
+    ///
+    /// # args
+    /// * `record` - 上层传入的数据记录。
+    fn writable_fields(&self, record: &DataRecord) -> HashMap<String, String> {
+        let mut field_map = HashMap::new();
         for field in &record.items {
             if *field.get_meta() == DataType::Ignore {
                 continue;
             }
             let name = field.get_name();
-            if self.column_set.contains(name) {
-                field_map.insert(name, field.get_value().to_string());
+            let mapped = self.column_map.get(name);
+            let column = mapped.map_or(name, String::as_str);
+            if !self.column_set.contains(column) {
+                continue;
+            }
+            let value = field.get_value().to_string();
+            if mapped.is_some() {
+                field_map.insert(column.to_string(), value);
+            } else {
+                field_map.entry(column.to_string()).or_insert(value);
             }
         }
-        if field_map.is_empty() {
+        field_map
+    }
+
+    /// 将指定表的缓存记录编码为 Stream Load 的 JSON 数组（每条记录一个对象，缺失列由服务端置 NULL）。
+    ///
+    /// # return
+    /// * `Option<Vec<u8>>` - 无可写记录时为 `None`。
+    fn stream_load_body(&self, table: &str) -> Option<Vec<u8>> {
+        let rows = self
+            .pending_records(table)
+            .map(|record| self.writable_fields(record))
+            .filter(|fields| !fields.is_empty())
+            .map(|fields| {
+                fields
+                    .into_iter()
+                    .map(|(name, value)| (name, serde_json::Value::String(value)))
+                    .collect::<serde_json::Map<_, _>>()
+            })
+            .collect::<Vec<_>>();
+        if rows.is_empty() {
             return None;
         }
+        serde_json::to_vec(&rows).ok()
+    }
 
-        let values: Vec<String> = self
-            .column_order
-            .iter()
-            .map(|column| match field_map.get(column.as_str()) {
-                Some(value) => format!("'{}'", escape_single_quotes(value)),
-                None => "NULL".to_string(),
+    /// 按当前列序将指定表的缓存记录转换为待绑定的行；无可写字段的记录被跳过。
+    /// upsert 模式下同一批次内相同唯一键只保留最后一条（同一次导入内的合并顺序不确定）。
+    fn build_insert_rows(&self, table: &str) -> Vec<Vec<BindValue>> {
+        if self.write_mode == WriteMode::Append {
+            return self
+                .pending_records(table)
+                .filter_map(|record| self.record_values(record))
+                .collect();
+        }
+        let mut rows: Vec<Vec<BindValue>> = Vec::new();
+        let mut positions = HashMap::new();
+        for record in self.pending_records(table) {
+            let Some(values) = self.record_values(record) else {
+                continue;
+            };
+            let Some(key) = self.record_key(record) else {
+                rows.push(values);
+                continue;
+            };
+            match positions.get(&key) {
+                Some(&pos) => rows[pos] = values,
+                None => {
+                    positions.insert(key, rows.len());
+                    rows.push(values);
+                }
+            }
+        }
+        rows
+    }
+
+    /// 将行按占位符上限切分为若干参数化 INSERT 语句。
+    ///
+    /// # args
+    /// * `table` - 目标表名。
+    /// * `rows` - 与当前列序对齐的参数行。
+    fn build_insert_queries<'a>(
+        &self,
+        table: &str,
+        rows: &'a [Vec<BindValue>],
+    ) -> Vec<QueryBuilder<'a, MySql>> {
+        let width = self.column_order.len() + usize::from(self.writes_delete_sign());
+        let rows_per_stmt = (MAX_BIND_PARAMS / width.max(1)).max(1);
+        rows.chunks(rows_per_stmt)
+            .map(|chunk| {
+                let mut builder = QueryBuilder::new(self.base_insert_prefix(table));
+                builder.push_values(chunk, |mut row, values| {
+                    for value in values {
+                        match value {
+                            BindValue::Null => row.push_bind(None::<String>),
+                            BindValue::Int(v) => row.push_bind(*v),
+                            BindValue::Float(v) => row.push_bind(*v),
+                            BindValue::Bool(v) => row.push_bind(*v),
+                            BindValue::Text(v) => row.push_bind(v.as_str()),
+                        };
+                    }
+                });
+                builder
             })
-            .collect();
-        Some(format!("({})", values.join(", ")))
+            .collect()
     }
 
-    /// 将缓存的 VALUES 组成批量 INSERT 并写入 Doris。
+    /// 以绑定参数写入指定表的缓存记录；无可写记录时直接返回。
+    async fn execute_insert(&self, table: &str) -> Result<(), sqlx::Error> {
+        let rows = self.build_insert_rows(table);
+        for mut query in self.build_insert_queries(table, &rows) {
+            query.build().execute(&self.pool).await?;
+        }
+        Ok(())
+    }
+
+    /// 写出所有表的缓存；某张表失败时继续写其余表，最终返回首个错误。
     ///
     /// # return
-    /// * `SinkResult<()>` - 成功表示缓存已清空。
+    /// * `SinkResult<()>` - 成功表示所有缓存已清空。
     async fn flush_pending(&mut self) -> SinkResult<()> {
-        if self.pending_values.is_empty() {
+        let tables = self
+            .pending
+            .keys()
+            .chain(self.in_flight.keys())
+            .cloned()
+            .collect::<HashSet<_>>();
+        let mut first_err = None;
+        for table in tables {
+            if let Err(e) = self.flush_table(&table).await {
+                first_err.get_or_insert(e);
+            }
+        }
+        first_err.map_or(Ok(()), Err)
+    }
+
+    /// 按 `load_mode` 写出指定表的缓存记录，成功后移除该表缓存；最终失败时缓存保留以便上层重试。
+    /// Stream Load 先导入已冻结的批次，再冻结并导入之后缓存的记录。
+    ///
+    /// # return
+    /// * `SinkResult<()>` - 成功表示该表缓存已清空。
+    async fn flush_table(&mut self, table: &str) -> SinkResult<()> {
+        if self.stream_load.is_some() {
+            if self.in_flight.contains_key(table) {
+                self.flush_batch(table).await?;
+            }
+            self.freeze_batch(table);
+        }
+        self.flush_batch(table).await
+    }
+
+    /// 写出指定表的当前批次，瞬时错误按 `max_retries`/`retry_backoff_ms` 退避重试。
+    async fn flush_batch(&mut self, table: &str) -> SinkResult<()> {
+        if self.batch_len(table) == 0 {
             return Ok(());
         }
-        //"INSERT INTO `wp_test`.`events_parsed` (`occur_time`, `src_ip`, `wp_event_id`, `time`, `digit`, `chars`, `wp_src_key`) VALUES (NULL, NULL, '1766973779209849128', NULL, NULL, NULL, 'file_1'), (NULL, NULL, '1766973779209849129', NULL, NULL, NULL, 'file_1'), (NULL, NULL, '1766973779209849130', NULL, NULL, NULL, 'file_1'), (NULL, NULL, '1766973779209849131', NULL, NULL, NULL, 'file_1'), (NULL, NULL, '1766973779209849132', NULL, NULL, NULL, 'file_1'), (NULL, NULL, '1766973779209849133', NULL, NULL, NULL, 'file_1'), (NULL, NULL, '1766973779209849134', NULL, NULL, NULL, 'file_1'), (NULL, NULL, '1766973779209849135', NULL, NULL, NULL, 'file_1'), (NULL, NULL, '1766973779209849136', NULL, NULL, NULL, 'file_1'), (NULL, NULL, '1766973779209849137', NULL, NULL, NULL, 'file_1'), (NULL, NULL, '1766973779209849138', NULL, NULL, NULL, 'file_1'), (NULL, NULL, '1766973779209849139', NULL, NULL, NULL, 'file_1'), (NULL, NULL, '1766973779209849140', NULL, NULL, NULL, 'file_1'), (NULL, NULL, '1766973779209849141', NULL, NULL, NULL, 'file_1'), (NULL, NULL, '1766973779209849142', NULL, NULL, NULL, 'file_1'), (NULL, NULL, '1766973779209849143', NULL, NULL, NULL, 'file_1'), (NULL, NULL, '1766973779209849144', NULL, NULL, NULL, 'file_1'), (NULL, NULL, '1766973779209849145', NULL, NULL, NULL, 'file_1'), (NULL, NULL, '1766973779209849146', NULL, NULL, NULL, 'file_1'), (NULL, NULL, '1766973779209849147', NULL, NULL, NULL, 'file_1'), (NULL, NULL, '1766973779209849148', NULL, NULL, NULL, 'file_1'), (NULL, NULL, '1766973779209849149', NULL, NULL, NULL, 'file_1'), (NULL, NULL, '1766973779209849150', NULL, NULL, NULL, 'file_1'), (NULL, NULL, '1766973779209849151', NULL, NULL, NULL, 'file_1'), (NULL, NULL, '1766973779209849152', NULL, NULL, NULL, 'file_1'), (NULL, NULL, '1766973779209849153', NULL, NULL, NULL, 'file_1'), (NULL, NULL, '1766973779209849154', NULL, NULL, NULL, 'file_1'), (NULL, NULL, '1766973779209849155', NULL, NULL, NULL, 'file_1'), (NULL, NULL, '1766973779209849156', NULL, NULL, NULL, 'file_1'), (NULL, NULL, '1766973779209849157', NULL, NULL, NULL, 'file_1'), (NULL, NULL, '1766973779209849158', NULL, NULL, NULL, 'file_1'), (NULL, NULL, '1766973779209849159', NULL, NULL, NULL, 'file_1'), (NULL, NULL, '1766973779209849160', NULL, NULL, NULL, 'file_1'), (NULL, NULL, '1766973779209849161', NULL, NULL, NULL, 'file_1'), (NULL, NULL, '1766973779209849162', NULL, NULL, NULL, 'file_1'), (NULL, NULL, '1766973779209849163', NULL, NULL, NULL, 'file_1'), (NULL, NULL, '1766973779209849164', NULL, NULL, NULL, 'file_1'), (NULL, NULL, '1766973779209849165', NULL, NULL, NULL, 'file_1'), (NULL, NULL, '1766973779209849166', NULL, NULL, NULL, 'file_1'), (NULL, NULL, '1766973779209849167', NULL, NULL, NULL, 'file_1'), (NULL, NULL, '1766973779209849168', NULL, NULL, NULL, 'file_1'), (NULL, NULL, '1766973779209849169', NULL, NULL, NULL, 'file_1'), (NULL, NULL, '1766973779209849170', NULL, NULL, NULL, 'file_1'), (NULL, NULL, '1766973779209849171', NULL, NULL, NULL, 'file_1'), (NULL, NULL, '1766973779209849172', NULL, NULL, NULL, 'file_1'), (NULL, NULL, '1766973779209849173', NULL, NULL, NULL, 'file_1'), (NULL, NULL, '1766973779209849174', NULL, NULL, NULL, 'file_1'), (NULL, NULL, '1766973779209849175', NULL, NULL, NULL, 'file_1'), (NULL, NULL, '1766973779209849176', NULL, NULL, NULL, 'file_1'), (NULL, NULL, '1766973779209849177', NULL, NULL, NULL, 'file_1'), (NULL, NULL, '1766973779209849178', NULL, NULL, NULL, 'file_1'), (NULL, NULL, '1766973779209849179', NULL, NULL, NULL, 'file_1'), (NULL, NULL, '1766973779209849180', NULL, NULL, NULL, 'file_1'), (NULL, NULL, '1766973779209849181', NULL, NULL, NULL, 'file_1'), (NULL, NULL, '1766973779209849182', NULL, NULL, NULL, 'file_1'), (NULL, NULL, '1766973779209849183', NULL, NULL, NULL, 'file_1'), (NULL, NULL, '1766973779209849184', NULL, NULL, NULL, 'file_1'), (NULL, NULL, '1766973779209849185', NULL, NULL, NULL, 'file_1'), (NULL, NULL, '1766973779209849186', NULL, NULL, NULL, 'file_1'), (NULL, NULL, '1766973779209849187', NULL, NULL, NULL, 'file_1'), (NULL, NULL, '1766973779209849188', NULL, NULL, NULL, 'file_1'), (NULL, NULL, '1766973779209849189', NULL, NULL, NULL, 'file_1'), (NULL, NULL, '1766973779209849190', NULL, NULL, NULL, 'file_1'), (NULL, NULL, '1766973779209849191', NULL, NULL, NULL, 'file_1')"
-        let sql = format!(
-            "{}{}",
-            self.base_insert_prefix(),
-            self.pending_values.join(", ")
-        );
-        raw_sql(&sql)
-            .execute(&self.pool)
+        let mut retry = 0;
+        loop {
+            let result = if self.stream_load.is_some() {
+                self.flush_stream_load(table).await
+            } else {
+                self.flush_insert(table).await
+            };
+            match result {
+                Ok(()) => break,
+                Err(failure) if failure.retryable && retry < self.retry.max_attempts => {
+                    retry += 1;
+                    let delay = self.retry.backoff(retry);
+                    wp_log::warn_data!(
+                        "[doris] flush to {} failed (retry {}/{} in {:?}): {}",
+                        self.quoted_table(table),
+                        retry,
+                        self.retry.max_attempts,
+                        delay,
+                        failure.msg
+                    );
+                    if failure.reconnect {
+                        self.reset_pool();
+                    }
+                    tokio::time::sleep(delay).await;
+                }
+                Err(failure) => {
+                    let failed = self.batch_len(table);
+                    self.stats.record_delivery(0, failed as u64);
+                    self.stats.record_error(&failure.msg);
+                    return Err(failure.into_error());
+                }
+            }
+        }
+        if let Some(flushed) = self.take_batch(table) {
+            self.stats.record_delivery(flushed.len() as u64, 0);
+            let event_ids = flushed
+                .iter()
+                .filter_map(|(_, record)| record.get2("wp_event_id"))
+                .map(|field| field.get_value().to_string())
+                .collect();
+            self.flush_notifier.notify(table, flushed.len(), event_ids);
+        }
+        self.stats.set_buffered(self.buffered());
+        self.stats.mark_flush();
+        Ok(())
+    }
+
+    /// 以保存的连接参数重建连接池（惰性建连），丢弃可能已失效的连接。
+    fn reset_pool(&mut self) {
+        self.pool = MySqlPoolOptions::new()
+            .max_connections(self.pool_size)
+            .connect_lazy_with(self.connect_options.clone());
+    }
+
+    /// 以 Stream Load 导入已冻结的批次；重试沿用其 label 与请求体，由服务端按 label 去重。
+    /// 导入被拒绝（如过滤行过多）时不重试。
+    async fn flush_stream_load(&self, table: &str) -> Result<(), FlushFailure> {
+        let (Some(loader), Some(load)) = (&self.stream_load, self.in_flight.get(table)) else {
+            return Ok(());
+        };
+        let Some(body) = &load.body else {
+            return Ok(());
+        };
+        loader
+            .load(table, &load.label, body.clone())
             .await
-            .map_err(|e| sink_error(format!("doris insert fail: {}", e)))?;
-        self.pending_values.clear();
+            .map_err(|e| FlushFailure {
+                msg: format!("doris stream load fail (label {}): {}", load.label, e),
+                retryable: FailureKind::of(&e) == FailureKind::Retryable,
+                reconnect: false,
+            })
+    }
+
+    /// 将指定表的缓存记录组成批量 INSERT 并写入 Doris；遇到列不匹配错误时重新加载表结构并重试一次。
+    async fn flush_insert(&mut self, table: &str) -> Result<(), FlushFailure> {
+        if let Err(e) = self.execute_insert(table).await {
+            let msg = e.to_string();
+            if !is_schema_mismatch(&msg) {
+                return Err(FlushFailure::from_sqlx("doris insert fail", &e));
+            }
+            wp_log::warn_data!("[doris] column mismatch, reloading schema: {}", msg);
+            self.reload_columns()
+                .await
+                .map_err(|e| FlushFailure::fatal(e.to_string()))?;
+            self.execute_insert(table)
+                .await
+                .map_err(|e| FlushFailure::from_sqlx("doris insert fail", &e))?;
+        }
+        Ok(())
+    }
+}
+
+/// 可热更新：`batch`/`batch_size`、`max_retries`、`retry_backoff_ms`。
+impl Reconfigure for DorisSink {
+    fn reconfigure(&mut self, params: &ParamMap) -> SinkResult<()> {
+        const KEYS: [&str; 4] = ["batch", "batch_size", "max_retries", "retry_backoff_ms"];
+        reconfigure::check_keys(params, &KEYS, "doris")?;
+        let batch = reconfigure::opt_batch(params, &["batch", "batch_size"], "doris")?;
+        let max_retries = reconfigure::opt_u64(params, &["max_retries"], "doris")?
+            .map(|n| {
+                u32::try_from(n).map_err(|_| sink_error("doris.max_retries exceeds u32 range"))
+            })
+            .transpose()?;
+        let backoff_ms = reconfigure::opt_u64(params, &["retry_backoff_ms"], "doris")?;
+        if let Some(batch) = batch {
+            self.batch_size = batch;
+        }
+        if let Some(max_retries) = max_retries {
+            self.retry.max_attempts = max_retries;
+        }
+        if let Some(backoff_ms) = backoff_ms {
+            self.retry.base_delay = Duration::from_millis(backoff_ms);
+        }
         Ok(())
     }
 }
 
+#[async_trait]
+impl PendingFlush for DorisSink {
+    fn pending_len(&self) -> usize {
+        self.buffered()
+    }
+
+    async fn flush_now(&mut self) -> SinkResult<()> {
+        self.flush_pending().await
+    }
+
+    fn discard_pending(&mut self) -> usize {
+        let discarded = self.buffered();
+        self.pending.clear();
+        self.in_flight.clear();
+        self.stats.set_buffered(0);
+        discarded
+    }
+
+    fn accepted(&self) -> u64 {
+        self.accepted
+    }
+
+    fn pending_seqs(&self) -> Vec<u64> {
+        self.pending
+            .values()
+            .chain(self.in_flight.values().map(|load| &load.records))
+            .flat_map(|buf| buf.iter().map(|(seq, _)| *seq))
+            .collect()
+    }
+}
+
 #[async_trait]
 impl AsyncCtrl for DorisSink {
     async fn stop(&mut self) -> SinkResult<()> {
@@ -146,23 +829,47 @@ impl AsyncCtrl for DorisSink {
     }
 
     async fn reconnect(&mut self) -> SinkResult<()> {
-        sqlx::query("SELECT 1")
-            .execute(&self.pool)
+        let pool = &self.pool;
+        self.reconnect
+            .run(|| async move {
+                sqlx::query("SELECT 1")
+                    .execute(pool)
+                    .await
+                    .map(|_| ())
+                    .map_err(|e| sink_error(format!("doris reconnect fail: {}", e)))
+            })
             .await
-            .map_err(|e| sink_error(format!("doris reconnect fail: {}", e)))?
-            .rows_affected();
-        Ok(())
     }
 }
 
 #[async_trait]
 impl AsyncRecordSink for DorisSink {
     async fn sink_record(&mut self, data: &DataRecord) -> SinkResult<()> {
-        if let Some(raw) = self.format_values_tuple(data) {
-            self.pending_values.push(raw);
-            if self.pending_values.len() >= self.batch_size {
-                self.flush_pending().await?;
-            }
+        let table = route_table(self.table_template.as_ref(), data, &self.table);
+        if !self.known_tables.contains(&table) {
+            ensure_table_exists(
+                &self.pool,
+                &self.database,
+                &table,
+                self.create_table.as_deref(),
+            )
+            .await
+            .map_err(|e| sink_error(format!("doris prepare table `{}` fail: {}", table, e)))?;
+            self.known_tables.insert(table.clone());
+        }
+        let buffer = self
+            .pending
+            .entry(table.clone())
+            .or_insert_with(|| BatchBuffer::new(format!("doris {}", table), self.shed));
+        if buffer.push((self.accepted, data.clone())) {
+            self.shed_records += 1;
+            self.stats.set_shed(self.shed_records);
+        }
+        self.accepted += 1;
+        let len = buffer.len();
+        self.stats.set_buffered(self.buffered());
+        if len >= self.batch_size {
+            self.flush_table(&table).await?;
         }
         Ok(())
     }
@@ -203,7 +910,7 @@ impl AsyncRawDataSink for DorisSink {
 ///
 /// # return
 /// * `String` - 每段都以反引号包裹的标识符。
-fn quote_identifier(input: &str) -> String {
+pub(crate) fn quote_identifier(input: &str) -> String {
     input
         .split('.')
         .map(|segment| format!("`{}`", segment.replace('`', "``")))
@@ -211,6 +918,102 @@ fn quote_identifier(input: &str) -> String {
         .join(".")
 }
 
+/// 按白名单筛选表列（保持原列序）；未配置白名单时原样返回。
+///
+/// # args
+/// * `columns` - 表的 `(列名, DATA_TYPE)` 列表。
+/// * `whitelist` - 目标列白名单。
+fn select_columns(
+    columns: Vec<(String, String)>,
+    whitelist: Option<&HashSet<String>>,
+) -> Vec<(String, String)> {
+    match whitelist {
+        Some(allowed) => columns
+            .into_iter()
+            .filter(|(name, _)| allowed.contains(name))
+            .collect(),
+        None => columns,
+    }
+}
+
+/// 校验 `columns` 白名单与 `column_map` 的目标列均存在于表中，避免配置笔误导致字段被静默丢弃。
+///
+/// # args
+/// * `config` - sink 配置。
+/// * `columns` - 表的 `(列名, DATA_TYPE)` 列表。
+fn check_column_targets(
+    config: &DorisSinkConfig,
+    columns: &[(String, String)],
+) -> anyhow::Result<()> {
+    let unknown = config
+        .columns
+        .iter()
+        .flatten()
+        .chain(config.column_map.values())
+        .filter(|target| !columns.iter().any(|(name, _)| name == *target))
+        .map(String::as_str)
+        .collect::<Vec<_>>();
+    if !unknown.is_empty() {
+        anyhow::bail!(
+            "doris.columns/column_map reference unknown column(s) [{}] in `{}`",
+            unknown.join(", "),
+            config.table
+        );
+    }
+    Ok(())
+}
+
+/// 批次写入的重试策略（不加抖动，单个 sink 内顺序重试）。
+///
+/// # args
+/// * `max_retries` - 最大重试次数。
+/// * `backoff_ms` - 首次重试前的等待。
+fn retry_policy(max_retries: u32, backoff_ms: u64) -> ReconnectPolicy {
+    ReconnectPolicy {
+        base_delay: Duration::from_millis(backoff_ms),
+        max_attempts: max_retries,
+        jitter: false,
+        ..Default::default()
+    }
+}
+
+/// 校验 upsert 所需的唯一键列均存在于表结构中。
+///
+/// # args
+/// * `keys` - 配置的唯一键列。
+/// * `columns` - 表的 `(列名, DATA_TYPE)` 列表。
+///
+/// # return
+/// * `anyhow::Result<()>` - 缺少键列时返回错误。
+fn check_key_columns(keys: &[String], columns: &[(String, String)]) -> anyhow::Result<()> {
+    let missing = keys
+        .iter()
+        .filter(|key| !columns.iter().any(|(name, _)| name == *key))
+        .map(String::as_str)
+        .collect::<Vec<_>>();
+    if !missing.is_empty() {
+        anyhow::bail!(
+            "doris.write_mode=upsert requires key column(s) [{}] in table columns",
+            missing.join(", ")
+        );
+    }
+    Ok(())
+}
+
+/// 判断写入错误是否由表结构变化（列增删/列数不符）引起。
+///
+/// # args
+/// * `msg` - 数据库返回的错误信息。
+///
+/// # return
+/// * `bool` - 是否应重新加载列信息后重试。
+fn is_schema_mismatch(msg: &str) -> bool {
+    let msg = msg.to_ascii_lowercase();
+    msg.contains("unknown column")
+        || msg.contains("column count")
+        || msg.contains("doesn't have a default value")
+}
+
 /// 将值中的单引号替换成 SQL 可接受的形式。
 ///
 /// # args
@@ -241,7 +1044,7 @@ fn sink_error(msg: impl Into<String>) -> SinkError {
 ///
 /// # return
 /// * `MySqlConnectOptions` - 可直接用于 sqlx 的配置。
-fn sanitize_options(
+pub(crate) fn sanitize_options(
     mut opts: MySqlConnectOptions,
     user: &str,
     password: &str,
@@ -292,7 +1095,7 @@ async fn create_database_if_missing(cfg: &DorisSinkConfig) -> anyhow::Result<()>
 ///
 /// # return
 /// * `anyhow::Result<()>` - 表存在或建表成功。
-async fn ensure_table_exists(
+pub(crate) async fn ensure_table_exists(
     pool: &MySqlPool,
     database: &str,
     table: &str,
@@ -317,21 +1120,21 @@ async fn ensure_table_exists(
     Ok(())
 }
 
-/// 从 information_schema 读取列顺序，作为批量写入的列序。
+/// 从 information_schema 读取列顺序与类型，作为批量写入的列序与参数绑定类型。
 ///
 /// # args
 /// * `pool` - 连接池。
 /// * `database`/`table` - 目标表。
 ///
 /// # return
-/// * `Vec<String>` - 按 ordinal_position 排序的列名列表。
-async fn load_table_columns(
+/// * `Vec<(String, String)>` - 按 ordinal_position 排序的 `(列名, DATA_TYPE)` 列表。
+pub(crate) async fn load_table_columns(
     pool: &MySqlPool,
     database: &str,
     table: &str,
-) -> anyhow::Result<Vec<String>> {
+) -> anyhow::Result<Vec<(String, String)>> {
     let sql = format!(
-        "SELECT COLUMN_NAME FROM information_schema.COLUMNS WHERE TABLE_SCHEMA='{}' AND TABLE_NAME='{}' ORDER BY ORDINAL_POSITION",
+        "SELECT COLUMN_NAME, DATA_TYPE FROM information_schema.COLUMNS WHERE TABLE_SCHEMA='{}' AND TABLE_NAME='{}' ORDER BY ORDINAL_POSITION",
         escape_single_quotes(database),
         escape_single_quotes(table)
     );
@@ -340,14 +1143,17 @@ async fn load_table_columns(
     let mut cols = Vec::with_capacity(rows.len());
     for row in rows {
         let name: String = row.try_get("COLUMN_NAME")?;
-        cols.push(name);
+        let data_type: String = row.try_get("DATA_TYPE")?;
+        cols.push((name, data_type));
     }
     Ok(cols)
 }
 
 #[cfg(test)]
 mod tests {
-    use super::quote_identifier;
+    use super::*;
+    use crate::common::testing::TestServer;
+    use wp_model_core::model::DataField;
 
     #[test]
     fn quote_identifier_handles_segments() {
@@ -355,6 +1161,615 @@ mod tests {
         assert_eq!(quote_identifier("demo.events"), "`demo`.`events`");
     }
 
+    #[test]
+    fn schema_mismatch_errors_are_detected() {
+        assert!(is_schema_mismatch(
+            "error returned from database: 1054 (42S22): Unknown column 'score' in 'field list'"
+        ));
+        assert!(is_schema_mismatch(
+            "1136 (21S01): Column count doesn't match value count at row 1"
+        ));
+        assert!(!is_schema_mismatch(
+            "1045 (28000): Access denied for user 'root'"
+        ));
+    }
+
+    fn columns(items: &[(&str, &str)]) -> Vec<(String, String)> {
+        items
+            .iter()
+            .map(|(name, ty)| (name.to_string(), ty.to_string()))
+            .collect()
+    }
+
+    fn lazy_sink() -> DorisSink {
+        let pool = MySqlPoolOptions::new()
+            .connect_lazy("mysql://localhost:9030/wp_test")
+            .expect("lazy pool");
+        DorisSink {
+            pool,
+            connect_options: "mysql://localhost:9030/wp_test".parse().expect("options"),
+            pool_size: 1,
+            retry: retry_policy(0, 0),
+            database: "wp_test".into(),
+            table: "events".into(),
+            column_order: Vec::new(),
+            quoted_columns: Vec::new(),
+            column_set: HashSet::new(),
+            column_kinds: Vec::new(),
+            column_map: HashMap::new(),
+            whitelist: None,
+            batch_size: 10,
+            pending: HashMap::new(),
+            shed: ShedConf::default(),
+            shed_records: 0,
+            accepted: 0,
+            reconnect: ReconnectCoordinator::standalone("doris:test", Default::default()),
+            stream_load: None,
+            in_flight: HashMap::new(),
+            stats: StatsHandle::detached("events", "doris"),
+            write_mode: WriteMode::Append,
+            key_columns: DorisSinkConfig::default_key_columns(),
+            delete_field: None,
+            table_template: None,
+            create_table: None,
+            known_tables: HashSet::from(["events".to_string()]),
+            flush_notifier: FlushNotifier::default(),
+        }
+    }
+
+    /// 以给定记录替换默认表 `events` 的缓存。
+    fn set_pending(sink: &mut DorisSink, records: Vec<DataRecord>) {
+        let records: Vec<Buffered> = records
+            .into_iter()
+            .enumerate()
+            .map(|(i, r)| (i as u64, r))
+            .collect();
+        sink.pending.insert("events".into(), records.into());
+    }
+
+    #[tokio::test]
+    async fn pending_records_reformat_after_column_reload() {
+        let mut sink = lazy_sink();
+        sink.apply_columns(columns(&[
+            ("id", "bigint"),
+            ("name", "varchar"),
+            ("score", "double"),
+        ]));
+
+        let mut rec = DataRecord::default();
+        rec.append(DataField::from_chars("name", "Alice"));
+        rec.append(DataField::from_chars("score", "98.5"));
+        set_pending(&mut sink, vec![rec]);
+        assert_eq!(
+            sink.build_insert_rows("events"),
+            vec![vec![
+                BindValue::Null,
+                BindValue::Text("Alice".into()),
+                BindValue::Float(98.5)
+            ]]
+        );
+        let rows = sink.build_insert_rows("events");
+        let mut queries = sink.build_insert_queries("events", &rows);
+        assert_eq!(
+            queries[0].sql(),
+            "INSERT INTO `wp_test`.`events` (`id`, `name`, `score`) VALUES (?, ?, ?)"
+        );
+
+        // 模拟 ALTER TABLE DROP COLUMN score 后重新加载的列信息
+        sink.apply_columns(columns(&[("name", "varchar"), ("id", "bigint")]));
+        assert_eq!(
+            sink.build_insert_rows("events"),
+            vec![vec![BindValue::Text("Alice".into()), BindValue::Null]]
+        );
+        let rows = sink.build_insert_rows("events");
+        queries = sink.build_insert_queries("events", &rows);
+        assert_eq!(
+            queries[0].sql(),
+            "INSERT INTO `wp_test`.`events` (`name`, `id`) VALUES (?, ?)"
+        );
+    }
+
+    #[test]
+    fn values_bind_by_column_type_without_escaping() {
+        let payload = "O'Brien \\' OR 1=1; --\nDROP TABLE events";
+        assert_eq!(
+            BindValue::parse(ColumnKind::Text, payload),
+            BindValue::Text(payload.to_string())
+        );
+        assert_eq!(
+            BindValue::parse(ColumnKind::Int, " 42 "),
+            BindValue::Int(42)
+        );
+        assert_eq!(
+            BindValue::parse(ColumnKind::Float, "98.5"),
+            BindValue::Float(98.5)
+        );
+        assert_eq!(
+            BindValue::parse(ColumnKind::Bool, "TRUE"),
+            BindValue::Bool(true)
+        );
+        assert_eq!(BindValue::parse(ColumnKind::Int, ""), BindValue::Null);
+        assert_eq!(
+            BindValue::parse(ColumnKind::Float, "NaN"),
+            BindValue::Text("NaN".into())
+        );
+        assert_eq!(ColumnKind::from_data_type("DECIMAL"), ColumnKind::Text);
+        assert_eq!(ColumnKind::from_data_type("BIGINT"), ColumnKind::Int);
+    }
+
+    #[tokio::test]
+    async fn large_batches_split_by_placeholder_limit() {
+        let mut sink = lazy_sink();
+        sink.apply_columns(columns(&[("a", "int"), ("b", "int"), ("c", "int")]));
+        let rows = vec![vec![BindValue::Int(1); 3]; MAX_BIND_PARAMS / 3 + 1];
+        assert_eq!(sink.build_insert_queries("events", &rows).len(), 2);
+    }
+
+    #[tokio::test]
+    async fn stream_load_body_keeps_label_until_batch_succeeds() {
+        use httpmock::prelude::*;
+
+        let server = MockServer::start_async().await;
+        let mut sink = lazy_sink();
+        sink.stream_load =
+            Some(StreamLoader::new(&server.base_url(), "wp_test", "root", "").unwrap());
+        sink.apply_columns(columns(&[("name", "varchar"), ("score", "double")]));
+        let mut alice = DataRecord::default();
+        alice.append(DataField::from_chars("name", "O'Brien \\ \"x\""));
+        alice.append(DataField::from_chars("score", "98.5"));
+        alice.append(DataField::from_chars("unknown", "dropped"));
+        let mut bob = DataRecord::default();
+        bob.append(DataField::from_chars("name", "Bob"));
+        set_pending(&mut sink, vec![alice, bob]);
+
+        let body = String::from_utf8(sink.stream_load_body("events").unwrap()).unwrap();
+        assert_eq!(
+            body,
+            r#"[{"name":"O'Brien \\ \"x\"","score":"98.5"},{"name":"Bob"}]"#
+        );
+
+        let mut rejected = server.mock(|when, then| {
+            when.method(PUT).path("/api/wp_test/events/_stream_load");
+            then.status(200)
+                .body(r#"{"Status":"Fail","Message":"tablet writer error"}"#);
+        });
+        let err = sink.flush_pending().await.expect_err("rejected");
+        assert_eq!(FailureKind::of(&err), FailureKind::Permanent);
+        let label = sink.in_flight["events"].label.clone();
+        assert_eq!(sink.buffered(), 2);
+        rejected.delete();
+
+        // 失败后到达的记录不并入已冻结的批次
+        let mut carol = DataRecord::default();
+        carol.append(DataField::from_chars("name", "Carol"));
+        set_pending(&mut sink, vec![carol]);
+        assert_eq!(sink.buffered(), 3);
+
+        let accepted = server.mock(|when, then| {
+            when.method(PUT)
+                .path("/api/wp_test/events/_stream_load")
+                .header("label", label.as_str())
+                .body(body.as_str());
+            then.status(200)
+                .body(r#"{"Status":"Success","Message":"OK"}"#);
+        });
+        let next = server.mock(|when, then| {
+            when.method(PUT)
+                .path("/api/wp_test/events/_stream_load")
+                .body(r#"[{"name":"Carol"}]"#);
+            then.status(200)
+                .body(r#"{"Status":"Success","Message":"OK"}"#);
+        });
+        sink.flush_pending().await.expect("retry with same label");
+        accepted.assert_hits(1);
+        next.assert_hits(1);
+        assert!(sink.pending.is_empty());
+        assert!(sink.in_flight.is_empty());
+    }
+
+    fn event(id: &str, name: &str) -> DataRecord {
+        let mut rec = DataRecord::default();
+        rec.append(DataField::from_chars("wp_event_id", id));
+        rec.append(DataField::from_chars("name", name));
+        rec
+    }
+
+    #[tokio::test]
+    async fn append_mode_keeps_duplicate_keys() {
+        let mut sink = lazy_sink();
+        sink.apply_columns(columns(&[("wp_event_id", "bigint"), ("name", "varchar")]));
+        set_pending(&mut sink, vec![event("1", "a"), event("1", "b")]);
+        let rows = sink.build_insert_rows("events");
+        assert_eq!(rows.len(), 2);
+        let mut queries = sink.build_insert_queries("events", &rows);
+        assert_eq!(
+            queries[0].sql(),
+            "INSERT INTO `wp_test`.`events` (`wp_event_id`, `name`) VALUES (?, ?), (?, ?)"
+        );
+    }
+
+    #[tokio::test]
+    async fn upsert_mode_keeps_records_missing_key_columns() {
+        let mut sink = lazy_sink();
+        sink.write_mode = WriteMode::Upsert;
+        sink.apply_columns(columns(&[("wp_event_id", "bigint"), ("name", "varchar")]));
+        let keyless = |name: &str| {
+            let mut rec = DataRecord::default();
+            rec.append(DataField::from_chars("name", name));
+            rec
+        };
+        set_pending(&mut sink, vec![keyless("a"), keyless("b"), event("1", "c")]);
+        assert_eq!(sink.build_insert_rows("events").len(), 3);
+    }
+
+    #[tokio::test]
+    async fn upsert_mode_dedups_keys_and_writes_delete_sign() {
+        let mut sink = lazy_sink();
+        sink.write_mode = WriteMode::Upsert;
+        sink.delete_field = Some("deleted".into());
+        sink.apply_columns(columns(&[("wp_event_id", "bigint"), ("name", "varchar")]));
+        let mut removed = event("2", "c");
+        removed.append(DataField::from_chars("deleted", "true"));
+        set_pending(&mut sink, vec![event("1", "a"), removed, event("1", "b")]);
+
+        let rows = sink.build_insert_rows("events");
+        assert_eq!(
+            rows,
+            vec![
+                vec![
+                    BindValue::Int(1),
+                    BindValue::Text("b".into()),
+                    BindValue::Int(0)
+                ],
+                vec![
+                    BindValue::Int(2),
+                    BindValue::Text("c".into()),
+                    BindValue::Int(1)
+                ],
+            ]
+        );
+        let mut queries = sink.build_insert_queries("events", &rows);
+        assert_eq!(
+            queries[0].sql(),
+            "INSERT INTO `wp_test`.`events` (`wp_event_id`, `name`, `__DORIS_DELETE_SIGN__`) \
+             VALUES (?, ?, ?), (?, ?, ?)"
+        );
+
+        // 未配置删除标记时不写隐藏列
+        sink.delete_field = None;
+        let rows = sink.build_insert_rows("events");
+        assert_eq!(
+            rows[0],
+            vec![BindValue::Int(1), BindValue::Text("b".into())]
+        );
+    }
+
+    #[tokio::test]
+    async fn routed_tables_are_batched_and_flushed_independently() {
+        use httpmock::prelude::*;
+
+        let server = MockServer::start_async().await;
+        let table_a = server.mock(|when, then| {
+            when.method(PUT).path("/api/wp_test/events_a/_stream_load");
+            then.status(200)
+                .body(r#"{"Status":"Success","Message":"OK"}"#);
+        });
+        let table_b = server.mock(|when, then| {
+            when.method(PUT)
+                .path("/api/wp_test/events_b/_stream_load")
+                .body(r#"[{"name":"c","wp_event_id":"3"}]"#);
+            then.status(200)
+                .body(r#"{"Status":"Success","Message":"OK"}"#);
+        });
+        let mut sink = lazy_sink();
+        sink.batch_size = 2;
+        sink.stream_load =
+            Some(StreamLoader::new(&server.base_url(), "wp_test", "root", "").unwrap());
+        sink.table_template = Some(TableTemplate::parse("events_{dataset}").unwrap());
+        sink.known_tables
+            .extend(["events_a".to_string(), "events_b".to_string()]);
+        sink.apply_columns(columns(&[("wp_event_id", "bigint"), ("name", "varchar")]));
+        let routed = |id: &str, name: &str, dataset: &str| {
+            let mut rec = event(id, name);
+            rec.append(DataField::from_chars("dataset", dataset));
+            rec
+        };
+
+        sink.sink_record(&routed("1", "a", "a")).await.unwrap();
+        sink.sink_record(&routed("3", "c", "b")).await.unwrap();
+        table_a.assert_hits(0);
+        sink.sink_record(&routed("2", "b", "a")).await.unwrap();
+        table_a.assert_hits(1);
+        table_b.assert_hits(0);
+        assert_eq!(sink.buffered(), 1);
+
+        sink.stop().await.unwrap();
+        table_b.assert_hits(1);
+        assert!(sink.pending.is_empty());
+    }
+
+    #[tokio::test]
+    async fn reconfigured_batch_size_applies_to_next_flush() {
+        use httpmock::prelude::*;
+        use serde_json::json;
+
+        let server = MockServer::start_async().await;
+        let load = server.mock(|when, then| {
+            when.method(PUT).path("/api/wp_test/events/_stream_load");
+            then.status(200)
+                .body(r#"{"Status":"Success","Message":"OK"}"#);
+        });
+        let mut sink = lazy_sink();
+        sink.stream_load =
+            Some(StreamLoader::new(&server.base_url(), "wp_test", "root", "").unwrap());
+        sink.known_tables.insert("events".to_string());
+        sink.apply_columns(columns(&[("wp_event_id", "bigint"), ("name", "varchar")]));
+
+        sink.sink_record(&event("1", "a")).await.unwrap();
+        sink.sink_record(&event("2", "b")).await.unwrap();
+        load.assert_hits(0);
+
+        let mut params = ParamMap::new();
+        params.insert("batch_size".into(), json!(0));
+        assert!(sink.reconfigure(&params).is_err());
+        params.insert("batch_size".into(), json!(3));
+        params.insert("pool_size".into(), json!(8));
+        assert!(
+            sink.reconfigure(&params).is_err(),
+            "pool_size needs a rebuild"
+        );
+        assert_eq!(
+            sink.batch_size, 10,
+            "rejected update leaves config unchanged"
+        );
+
+        params.remove("pool_size");
+        params.insert("max_retries".into(), json!(5));
+        sink.reconfigure(&params).unwrap();
+        assert_eq!(sink.retry.max_attempts, 5);
+        sink.sink_record(&event("3", "c")).await.unwrap();
+        load.assert_hits(1);
+        assert_eq!(sink.buffered(), 0);
+    }
+
+    #[test]
+    fn upsert_requires_key_columns_in_table() {
+        let table = columns(&[("wp_event_id", "bigint"), ("name", "varchar")]);
+        assert!(check_key_columns(&["wp_event_id".into()], &table).is_ok());
+        let err = check_key_columns(&["id".into(), "wp_event_id".into()], &table).unwrap_err();
+        assert!(err.to_string().contains("[id]"));
+    }
+
+    #[tokio::test]
+    async fn column_map_renames_and_whitelist_limits_columns() {
+        let table = columns(&[
+            ("id", "bigint"),
+            ("user_name", "varchar"),
+            ("note", "varchar"),
+        ]);
+        let mut rec = DataRecord::default();
+        rec.append(DataField::from_chars("id", "7"));
+        rec.append(DataField::from_chars("name", "Alice"));
+        rec.append(DataField::from_chars("user_name", "shadowed"));
+        rec.append(DataField::from_chars("note", "n"));
+        rec.append(DataField::from_chars("extra", "dropped"));
+
+        // 默认：同名匹配，未知字段忽略
+        let mut sink = lazy_sink();
+        sink.apply_columns(table.clone());
+        set_pending(&mut sink, vec![rec.clone()]);
+        assert_eq!(
+            sink.build_insert_rows("events"),
+            vec![vec![
+                BindValue::Int(7),
+                BindValue::Text("shadowed".into()),
+                BindValue::Text("n".into())
+            ]]
+        );
+
+        // 改名：映射字段优先于同名字段
+        sink.column_map = HashMap::from([("name".to_string(), "user_name".to_string())]);
+        assert_eq!(
+            sink.build_insert_rows("events")[0][1],
+            BindValue::Text("Alice".into())
+        );
+
+        // 白名单：只写入选定列，INSERT 列表随之收窄
+        sink.whitelist = Some(HashSet::from(["id".to_string(), "user_name".to_string()]));
+        sink.apply_columns(select_columns(table, sink.whitelist.as_ref()));
+        let rows = sink.build_insert_rows("events");
+        assert_eq!(
+            rows,
+            vec![vec![BindValue::Int(7), BindValue::Text("Alice".into())]]
+        );
+        let mut queries = sink.build_insert_queries("events", &rows);
+        assert_eq!(
+            queries[0].sql(),
+            "INSERT INTO `wp_test`.`events` (`id`, `user_name`) VALUES (?, ?)"
+        );
+    }
+
+    #[test]
+    fn unknown_mapping_targets_are_rejected() {
+        let table = columns(&[("id", "bigint"), ("user_name", "varchar")]);
+        let cfg = DorisSinkConfig::new(
+            "mysql://localhost:9030".into(),
+            "wp_test".into(),
+            "root".into(),
+            "".into(),
+            "events".into(),
+            None,
+            None,
+            None,
+        );
+        assert!(check_column_targets(&cfg, &table).is_ok());
+        let cfg = cfg
+            .with_column_map([("name".to_string(), "username".to_string())].into())
+            .with_columns(Some(vec!["id".into()]));
+        let err = check_column_targets(&cfg, &table).unwrap_err();
+        assert!(err.to_string().contains("[username]"), "{err}");
+    }
+
+    /// Stream Load 服务端：前 `failures` 个请求返回 503，之后返回成功。
+    async fn flaky_stream_load(failures: usize) -> TestServer {
+        TestServer::start(move |n| {
+            if n < failures {
+                (503, String::new())
+            } else {
+                (200, r#"{"Status":"Success","Message":"OK"}"#.to_string())
+            }
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn transient_flush_failures_are_retried() {
+        let server = flaky_stream_load(2).await;
+        let mut sink = lazy_sink();
+        sink.retry = retry_policy(3, 1);
+        sink.stream_load = Some(StreamLoader::new(&server.url, "wp_test", "root", "").unwrap());
+        sink.apply_columns(columns(&[("wp_event_id", "bigint"), ("name", "varchar")]));
+        set_pending(&mut sink, vec![event("1", "a")]);
+
+        sink.flush_pending()
+            .await
+            .expect("succeeds on third attempt");
+        assert_eq!(server.requests(), 3);
+        assert!(sink.pending.is_empty());
+
+        // 重试次数用尽时保留缓存并报错
+        let server = flaky_stream_load(usize::MAX).await;
+        sink.retry = retry_policy(1, 1);
+        sink.stream_load = Some(StreamLoader::new(&server.url, "wp_test", "root", "").unwrap());
+        set_pending(&mut sink, vec![event("2", "b")]);
+        assert!(sink.flush_pending().await.is_err());
+        assert_eq!(server.requests(), 2);
+        assert_eq!(sink.buffered(), 1);
+    }
+
+    #[tokio::test]
+    async fn flush_notifier_fires_after_successful_flush() {
+        use crate::common::flush_notify::FlushEvent;
+        use std::sync::Mutex;
+
+        let server = flaky_stream_load(1).await;
+        let events = Arc::new(Mutex::new(Vec::<FlushEvent>::new()));
+        let seen = events.clone();
+        let mut sink = lazy_sink().with_flush_notifier(FlushNotifier::from_fn(move |event| {
+            seen.lock().unwrap().push(event.clone());
+        }));
+        sink.retry = retry_policy(1, 1);
+        sink.stream_load = Some(StreamLoader::new(&server.url, "wp_test", "root", "").unwrap());
+        sink.apply_columns(columns(&[("wp_event_id", "bigint"), ("name", "varchar")]));
+        set_pending(&mut sink, vec![event("1", "a"), event("2", "b")]);
+
+        // 首次请求失败并重试：通知只在写出成功后发出一次
+        sink.flush_pending().await.expect("flushed after retry");
+        set_pending(&mut sink, vec![event("3", "c")]);
+        sink.flush_pending().await.expect("flushed");
+        let events = events.lock().unwrap();
+        assert_eq!(
+            *events,
+            vec![
+                FlushEvent {
+                    target: "events".into(),
+                    records: 2,
+                    total_records: 2,
+                    event_ids: vec!["1".into(), "2".into()],
+                    last_event_id: Some("2".into()),
+                },
+                FlushEvent {
+                    target: "events".into(),
+                    records: 1,
+                    total_records: 3,
+                    event_ids: vec!["3".into()],
+                    last_event_id: Some("3".into()),
+                },
+            ]
+        );
+    }
+
+    #[cfg(feature = "prometheus")]
+    #[tokio::test]
+    async fn flush_updates_last_success_and_failure_gauges() {
+        use prometheus::Encoder;
+
+        let gauge = |name: &str| {
+            let mut buf = Vec::new();
+            prometheus::TextEncoder::new()
+                .encode(&prometheus::gather(), &mut buf)
+                .expect("encode metrics");
+            String::from_utf8(buf)
+                .expect("utf8 metrics")
+                .lines()
+                .find(|l| {
+                    l.starts_with(name)
+                        && l.contains("sink=\"doris_gauge_sink\"")
+                        && l.contains("destination=\"events\"")
+                })
+                .and_then(|l| l.rsplit(' ').next()?.parse::<f64>().ok())
+        };
+        let stats = crate::common::stats::register("doris_gauge_sink", "doris");
+        let mut sink = lazy_sink().with_stats(stats);
+        sink.retry = retry_policy(0, 1);
+        sink.apply_columns(columns(&[("wp_event_id", "bigint"), ("name", "varchar")]));
+
+        let before = chrono::Utc::now().timestamp() as f64;
+        let server = flaky_stream_load(0).await;
+        sink.stream_load = Some(StreamLoader::new(&server.url, "wp_test", "root", "").unwrap());
+        set_pending(&mut sink, vec![event("1", "a")]);
+        sink.flush_pending().await.expect("flushed");
+        let success = gauge("sink_last_success_timestamp").expect("success gauge");
+        assert!(success >= before, "{success} < {before}");
+        assert_eq!(gauge("sink_last_failure_timestamp"), None);
+
+        let server = flaky_stream_load(usize::MAX).await;
+        sink.stream_load = Some(StreamLoader::new(&server.url, "wp_test", "root", "").unwrap());
+        set_pending(&mut sink, vec![event("2", "b")]);
+        assert!(sink.flush_pending().await.is_err());
+        let failure = gauge("sink_last_failure_timestamp").expect("failure gauge");
+        assert!(failure >= success);
+        // 失败不影响最近成功时间
+        assert_eq!(gauge("sink_last_success_timestamp"), Some(success));
+    }
+
+    #[tokio::test]
+    async fn flush_counts_delivered_and_failed_records() {
+        let mut sink = lazy_sink();
+        sink.retry = retry_policy(0, 1);
+        sink.apply_columns(columns(&[("wp_event_id", "bigint"), ("name", "varchar")]));
+
+        let server = flaky_stream_load(0).await;
+        sink.stream_load = Some(StreamLoader::new(&server.url, "wp_test", "root", "").unwrap());
+        set_pending(&mut sink, vec![event("1", "a"), event("2", "b")]);
+        sink.flush_pending().await.expect("flushed");
+
+        let server = flaky_stream_load(usize::MAX).await;
+        sink.stream_load = Some(StreamLoader::new(&server.url, "wp_test", "root", "").unwrap());
+        set_pending(&mut sink, vec![event("3", "c")]);
+        assert!(sink.flush_pending().await.is_err());
+
+        let stats = sink.stats();
+        assert_eq!(stats.attempted_records, 3);
+        assert_eq!(stats.succeeded_records, 2);
+        assert_eq!(stats.failed_records, 1);
+        assert!(stats.last_error_at.is_some());
+    }
+
+    #[test]
+    fn sqlx_errors_are_classified_for_retry() {
+        let io = sqlx::Error::Io(std::io::Error::from(std::io::ErrorKind::ConnectionReset));
+        let failure = FlushFailure::from_sqlx("doris insert fail", &io);
+        assert!(failure.retryable && failure.reconnect);
+        assert!(failure.msg.starts_with("doris insert fail: "));
+
+        let timeout = FlushFailure::from_sqlx("doris insert fail", &sqlx::Error::PoolTimedOut);
+        assert!(timeout.retryable && !timeout.reconnect);
+
+        let column = sqlx::Error::ColumnNotFound("score".into());
+        assert!(!FlushFailure::from_sqlx("doris insert fail", &column).retryable);
+    }
+
     // #[test]
     // fn test_new() {
     //     DorisSinkConfig{
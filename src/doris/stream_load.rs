@@ -0,0 +1,345 @@
+//! Doris Stream Load：将一批记录以 JSON 数组 PUT 到 `/api/{db}/{table}/_stream_load`。
+//!
+//! 每个批次携带唯一 label；批次写入失败重试时以同一 label 重发同一请求体，服务端据此去重，
+//! 保证不重复导入。
+//! StarRocks 沿用同一协议，通过 [`StreamLoadFormat`] 与附加请求头适配。
+
+use reqwest::{StatusCode, header, redirect};
+use serde::Deserialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use wp_connector_api::{SinkError, SinkReason, SinkResult};
+
+use crate::common::retry;
+
+static LABEL_SEQ: AtomicU64 = AtomicU64::new(0);
+
+/// 生成批次 label：`wp_<db>_<table>_<毫秒时间戳>_<pid>_<序号>`，非法字符替换为 `_`。
+///
+/// # args
+/// * `database`/`table` - 目标表。
+pub(crate) fn next_label(database: &str, table: &str) -> String {
+    let millis = chrono::Utc::now().timestamp_millis();
+    let seq = LABEL_SEQ.fetch_add(1, Ordering::Relaxed);
+    let raw = format!(
+        "wp_{}_{}_{}_{}_{}",
+        database,
+        table,
+        millis,
+        std::process::id(),
+        seq
+    );
+    raw.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+#[derive(Debug, Deserialize)]
+struct StreamLoadResponse {
+    #[serde(rename = "Status")]
+    status: String,
+    #[serde(rename = "Message", default)]
+    message: String,
+    #[serde(rename = "ExistingJobStatus", default)]
+    existing_job_status: Option<String>,
+}
+
+impl StreamLoadResponse {
+    /// 已提交即视为成功；label 已存在且对应作业已完成说明此前重试已导入成功，
+    /// 作业仍在进行时按过载错误稍后重试。其余状态（`Fail`，如过滤行过多）为服务端拒绝本批数据，
+    /// 原样重试无意义。
+    fn into_result(self) -> SinkResult<()> {
+        let msg = format!("{}: {}", self.status, self.message);
+        match self.status.as_str() {
+            "Success" | "Publish Timeout" => Ok(()),
+            "Label Already Exists" if self.existing_job_status.as_deref() == Some("FINISHED") => {
+                Ok(())
+            }
+            "Label Already Exists" => Err(retry::busy_error(msg)),
+            _ => Err(SinkError::from(SinkReason::Sink(msg))),
+        }
+    }
+}
+
+/// 请求体格式及对应的 Stream Load 请求头。
+#[cfg_attr(not(feature = "starrocks"), allow(dead_code))]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) enum StreamLoadFormat {
+    /// JSON 数组（`format: json` + `strip_outer_array: true`）
+    #[default]
+    Json,
+    /// 按 `columns` 列序逐行拼接；含分隔符、换行或引号的值以 `"` 包裹、`\` 转义
+    Csv {
+        column_separator: String,
+        columns: Vec<String>,
+    },
+}
+
+impl StreamLoadFormat {
+    fn headers(&self) -> Vec<(&'static str, String)> {
+        match self {
+            Self::Json => vec![
+                ("format", "json".to_string()),
+                ("strip_outer_array", "true".to_string()),
+            ],
+            Self::Csv {
+                column_separator,
+                columns,
+            } => vec![
+                ("format", "csv".to_string()),
+                ("column_separator", column_separator.clone()),
+                ("columns", columns.join(",")),
+                ("enclose", "\"".to_string()),
+                ("escape", "\\".to_string()),
+            ],
+        }
+    }
+}
+
+pub(crate) struct StreamLoader {
+    client: reqwest::Client,
+    base: String,
+    database: String,
+    user: String,
+    password: String,
+    format: StreamLoadFormat,
+    /// 附加请求头，如 StarRocks FE 要求的 `Expect: 100-continue`
+    extra_headers: Vec<(String, String)>,
+}
+
+impl StreamLoader {
+    /// # args
+    /// * `base` - FE（或 BE）HTTP 地址，如 `http://fe:8030`。
+    /// * `database` - 目标库；表名在每次导入时指定。
+    /// * `user`/`password` - 认证信息。
+    pub(crate) fn new(
+        base: &str,
+        database: &str,
+        user: &str,
+        password: &str,
+    ) -> anyhow::Result<Self> {
+        // FE 以 307 转发到 BE；自动跟随会丢失认证头，因此手动处理跳转
+        let client = reqwest::Client::builder()
+            .redirect(redirect::Policy::none())
+            .build()?;
+        Ok(Self {
+            client,
+            base: base.trim_end_matches('/').to_string(),
+            database: database.to_string(),
+            user: user.to_string(),
+            password: password.to_string(),
+            format: StreamLoadFormat::default(),
+            extra_headers: Vec::new(),
+        })
+    }
+
+    #[cfg_attr(not(feature = "starrocks"), allow(dead_code))]
+    pub(crate) fn with_format(mut self, format: StreamLoadFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    #[cfg_attr(not(feature = "starrocks"), allow(dead_code))]
+    pub(crate) fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.extra_headers
+            .push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// 以给定 label 向 `table` 导入一批数据，请求体须与 [`StreamLoadFormat`] 一致。
+    ///
+    /// # return
+    /// * `SinkResult<()>` - 传输失败与 408/429/5xx 为可重试错误；导入被拒绝为永久性错误，
+    ///   均携带服务端信息。
+    pub(crate) async fn load(&self, table: &str, label: &str, body: Vec<u8>) -> SinkResult<()> {
+        self.load_with_headers(table, label, body, &[]).await
+    }
+
+    /// 同 [`StreamLoader::load`]，请求（含跳转后的请求）另附 `headers`，如 trace 头。
+    pub(crate) async fn load_with_headers(
+        &self,
+        table: &str,
+        label: &str,
+        body: Vec<u8>,
+        headers: &[(&'static str, String)],
+    ) -> SinkResult<()> {
+        let url = format!("{}/api/{}/{}/_stream_load", self.base, self.database, table);
+        let mut resp = self.put(&url, label, body.clone(), headers).await?;
+        if resp.status() == StatusCode::TEMPORARY_REDIRECT {
+            let url = resp
+                .headers()
+                .get(header::LOCATION)
+                .and_then(|v| v.to_str().ok())
+                .map(str::to_string)
+                .ok_or_else(|| {
+                    SinkError::from(SinkReason::Sink(
+                        "stream load redirect without location".into(),
+                    ))
+                })?;
+            resp = self.put(&url, label, body, headers).await?;
+        }
+        let status = resp.status();
+        let text = resp
+            .text()
+            .await
+            .map_err(|e| retry::transport_error(e.to_string(), e.is_timeout()))?;
+        if !status.is_success() {
+            return Err(retry::status_error(
+                status.as_u16(),
+                format!("http {}: {}", status, text),
+            ));
+        }
+        let parsed: StreamLoadResponse = serde_json::from_str(&text).map_err(|e| {
+            SinkError::from(SinkReason::Sink(format!(
+                "unexpected stream load response ({e}): {text}"
+            )))
+        })?;
+        parsed.into_result()
+    }
+
+    async fn put(
+        &self,
+        url: &str,
+        label: &str,
+        body: Vec<u8>,
+        headers: &[(&'static str, String)],
+    ) -> SinkResult<reqwest::Response> {
+        let mut req = self
+            .client
+            .put(url)
+            .basic_auth(&self.user, Some(&self.password))
+            .header("label", label);
+        for (name, value) in self.format.headers() {
+            req = req.header(name, value);
+        }
+        for (name, value) in &self.extra_headers {
+            req = req.header(name.as_str(), value.as_str());
+        }
+        for (name, value) in headers {
+            req = req.header(*name, value.as_str());
+        }
+        req.body(body)
+            .send()
+            .await
+            .map_err(|e| retry::transport_error(e.to_string(), e.is_timeout()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::retry::FailureKind;
+    use httpmock::prelude::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn labels_are_unique_and_well_formed() {
+        let labels = (0..1000)
+            .map(|_| next_label("wp data", "events.v2"))
+            .collect::<HashSet<_>>();
+        assert_eq!(labels.len(), 1000);
+        for label in &labels {
+            assert!(label.starts_with("wp_wp_data_events_v2_"));
+            assert!(
+                label
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn load_puts_json_with_label_and_accepts_finished_duplicate() {
+        let server = MockServer::start_async().await;
+        let first = server.mock(|when, then| {
+            when.method(PUT)
+                .path("/api/demo/events/_stream_load")
+                .header("label", "batch-1")
+                .header("format", "json")
+                .header("strip_outer_array", "true")
+                .header_exists("authorization")
+                .body("[{\"name\":\"a\"}]");
+            then.status(200)
+                .body("{\"Status\":\"Success\",\"Message\":\"OK\",\"NumberLoadedRows\":1}");
+        });
+        let retry = server.mock(|when, then| {
+            when.method(PUT).header("label", "batch-2");
+            then.status(200)
+                .body("{\"Status\":\"Label Already Exists\",\"ExistingJobStatus\":\"FINISHED\"}");
+        });
+        let failed = server.mock(|when, then| {
+            when.method(PUT).header("label", "batch-3");
+            then.status(200)
+                .body("{\"Status\":\"Fail\",\"Message\":\"too many filtered rows\"}");
+        });
+        let running = server.mock(|when, then| {
+            when.method(PUT).header("label", "batch-4");
+            then.status(200)
+                .body("{\"Status\":\"Label Already Exists\",\"ExistingJobStatus\":\"RUNNING\"}");
+        });
+        let unavailable = server.mock(|when, then| {
+            when.method(PUT).header("label", "batch-5");
+            then.status(503).body("be unavailable");
+        });
+
+        let loader = StreamLoader::new(&server.base_url(), "demo", "root", "").expect("client");
+        loader
+            .load("events", "batch-1", b"[{\"name\":\"a\"}]".to_vec())
+            .await
+            .expect("loaded");
+        loader
+            .load("events", "batch-2", b"[]".to_vec())
+            .await
+            .expect("deduped");
+        let err = loader
+            .load("events", "batch-3", b"[]".to_vec())
+            .await
+            .expect_err("fail");
+        assert!(format!("{err}").contains("too many filtered rows"), "{err}");
+        assert_eq!(FailureKind::of(&err), FailureKind::Permanent);
+        for label in ["batch-4", "batch-5"] {
+            let err = loader
+                .load("events", label, b"[]".to_vec())
+                .await
+                .expect_err("not committed");
+            assert_eq!(FailureKind::of(&err), FailureKind::Retryable, "{err}");
+        }
+        first.assert_hits(1);
+        retry.assert_hits(1);
+        failed.assert_hits(1);
+        running.assert_hits(1);
+        unavailable.assert_hits(1);
+    }
+
+    #[tokio::test]
+    async fn csv_format_sends_column_headers() {
+        let server = MockServer::start_async().await;
+        let load = server.mock(|when, then| {
+            when.method(PUT)
+                .path("/api/demo/events/_stream_load")
+                .header("format", "csv")
+                .header("column_separator", "|")
+                .header("columns", "name,score")
+                .header("expect", "100-continue")
+                .body("a|1\n");
+            then.status(200).body("{\"Status\":\"Success\"}");
+        });
+        let loader = StreamLoader::new(&server.base_url(), "demo", "root", "")
+            .expect("client")
+            .with_format(StreamLoadFormat::Csv {
+                column_separator: "|".into(),
+                columns: vec!["name".into(), "score".into()],
+            })
+            .with_header("Expect", "100-continue");
+        loader
+            .load("events", "batch-1", b"a|1\n".to_vec())
+            .await
+            .expect("loaded");
+        load.assert_hits(1);
+    }
+}
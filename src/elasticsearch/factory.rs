@@ -1,28 +1,32 @@
 use async_trait::async_trait;
+use chrono::format::{Item, StrftimeItems};
 use serde_json::json;
 use wp_connector_api::{
-    register_sink_factory, ConnectorDef, ConnectorDefProvider, ConnectorScope, ParamMap,
-    SinkBuildCtx, SinkFactory, SinkHandle, SinkReason, SinkResult, SinkSpec,
+    ConnectorDef, ConnectorScope, ParamMap, SinkBuildCtx, SinkDefProvider, SinkError, SinkFactory,
+    SinkHandle, SinkReason, SinkResult, SinkSpec,
 };
 
-use super::sink::ElasticsearchSink;
+use super::config::Elasticsearch;
+use super::sink::{ElasticsearchSink, bulk_client};
+use crate::common::enrich::{EnrichConf, EnrichSink};
+use crate::common::flush_notify::FlushNotifier;
+use crate::common::health::{CHECK_TIMEOUT, CheckConnection, Readiness, probe};
+use crate::common::params::string_list;
+use crate::common::retry::{self, RetryingSink};
+use crate::common::secret;
+use crate::common::stats;
+use crate::common::trace_context::TraceContext;
+use crate::common::transform::FieldTransforms;
 
-pub fn register_builder() {
-    register_sink_factory(ElasticsearchFactory);
-}
-
-pub fn register_factory_only() {
-    register_sink_factory(ElasticsearchFactory);
-}
-
-struct ElasticsearchFactory;
+pub struct ElasticsearchSinkFactory;
 
 #[async_trait]
-impl SinkFactory for ElasticsearchFactory {
+impl SinkFactory for ElasticsearchSinkFactory {
     fn kind(&self) -> &'static str {
         "elasticsearch"
     }
     fn validate_spec(&self, spec: &SinkSpec) -> SinkResult<()> {
+        let spec = &secret::resolve_sink_spec(spec)?;
         let endpoint = spec
             .params
             .get("endpoint")
@@ -31,57 +35,366 @@ impl SinkFactory for ElasticsearchFactory {
         if endpoint.trim().is_empty() {
             return Err(SinkReason::sink("elasticsearch.endpoint must not be empty").into());
         }
-        if let Some(i) = spec.params.get("batch").and_then(|v| v.as_i64()) {
-            if i <= 0 {
-                return Err(SinkReason::sink("elasticsearch.batch must be > 0").into());
+        if let Some(i) = spec.params.get("batch").and_then(|v| v.as_i64())
+            && i <= 0
+        {
+            return Err(SinkReason::sink("elasticsearch.batch must be > 0").into());
+        }
+        for key in ["max_batch_bytes", "bulk_concurrency", "api_version"] {
+            if let Some(i) = spec.params.get(key).and_then(|v| v.as_i64())
+                && i <= 0
+            {
+                return Err(SinkReason::sink(format!("elasticsearch.{key} must be > 0")).into());
             }
         }
-        Ok(())
-    }
-    async fn build(&self, spec: &SinkSpec, _ctx: &SinkBuildCtx) -> SinkResult<SinkHandle> {
-        let mut tbl = toml::map::Map::new();
-        if let Some(s) = spec.params.get("endpoint").and_then(|v| v.as_str()) {
-            tbl.insert("endpoint".to_string(), toml::Value::String(s.to_string()));
+        EnrichConf::from_params(&spec.params)?;
+        retry::validate_params(&spec.params)?;
+        FieldTransforms::from_params(&spec.params)?;
+        FlushNotifier::from_params(&spec.params)?;
+        TraceContext::from_params(&spec.params)?;
+        parse_id_from_fields(&spec.params)?;
+        string_list(&spec.params, "tls_insecure_hosts", "elasticsearch")?;
+        for key in ["tls_ca_cert", "tls_client_cert", "tls_client_key"] {
+            if let Some(v) = spec.params.get(key)
+                && v.as_str().is_none_or(|s| s.trim().is_empty())
+            {
+                return Err(SinkReason::sink(format!(
+                    "elasticsearch.{key} must be a non-empty string"
+                ))
+                .into());
+            }
+        }
+        if spec.params.contains_key("tls_client_cert") != spec.params.contains_key("tls_client_key")
+        {
+            return Err(SinkReason::sink(
+                "elasticsearch.tls_client_cert and tls_client_key must be set together",
+            )
+            .into());
         }
-        if let Some(s) = spec.params.get("username").and_then(|v| v.as_str()) {
-            tbl.insert("username".to_string(), toml::Value::String(s.to_string()));
+        if let Some(v) = spec.params.get("tls_insecure_skip_verify")
+            && !v.is_boolean()
+        {
+            return Err(SinkReason::sink(
+                "elasticsearch.tls_insecure_skip_verify must be a boolean",
+            )
+            .into());
         }
-        if let Some(s) = spec.params.get("password").and_then(|v| v.as_str()) {
-            tbl.insert("password".to_string(), toml::Value::String(s.to_string()));
+        if spec.params.contains_key("id_field") && spec.params.contains_key("id_from_fields") {
+            return Err(SinkReason::sink(
+                "elasticsearch.id_field and elasticsearch.id_from_fields are mutually exclusive",
+            )
+            .into());
         }
-        if let Some(i) = spec.params.get("batch").and_then(|v| v.as_i64()) {
-            tbl.insert("batch".to_string(), toml::Value::Integer(i));
+        if let Some(hash) = spec.params.get("id_hash")
+            && !matches!(hash.as_str(), Some("sha1" | "sha256"))
+        {
+            return Err(SinkReason::sink("elasticsearch.id_hash must be sha1 or sha256").into());
         }
-        if let Some(s) = spec.params.get("table").and_then(|v| v.as_str()) {
-            tbl.insert("table".to_string(), toml::Value::String(s.to_string()));
+        if let Some(pattern) = spec.params.get("index_pattern")
+            && !pattern.as_str().is_some_and(is_valid_index_pattern)
+        {
+            return Err(SinkReason::sink(
+                "elasticsearch.index_pattern must be a valid strftime pattern",
+            )
+            .into());
         }
-        let value = toml::Value::Table(tbl);
-        let serialized = toml::to_string(&value)
-            .map_err(|err| SinkReason::sink(format!("encode elasticsearch conf failed: {err}")).into())?;
-        let conf: wp_config::structure::io::Elasticsearch = toml::from_str(&serialized)
-            .map_err(|err| SinkReason::sink(format!("parse elasticsearch conf failed: {err}")).into())?;
+        match spec.params.get("auth_mode").map(|v| v.as_str()) {
+            None | Some(Some("basic")) => {}
+            Some(Some(mode @ ("api_key" | "bearer"))) => {
+                let key = if mode == "api_key" {
+                    "api_key"
+                } else {
+                    "token"
+                };
+                if spec
+                    .params
+                    .get(key)
+                    .and_then(|v| v.as_str())
+                    .is_none_or(str::is_empty)
+                {
+                    return Err(SinkReason::sink(format!(
+                        "elasticsearch.auth_mode={mode} requires elasticsearch.{key}"
+                    ))
+                    .into());
+                }
+            }
+            Some(_) => {
+                return Err(SinkReason::sink(
+                    "elasticsearch.auth_mode must be basic, api_key or bearer",
+                )
+                .into());
+            }
+        }
+        validate_versioning(&spec.params)?;
+        if let Some(mode) = spec.params.get("on_item_error")
+            && !matches!(mode.as_str(), Some("fail" | "skip"))
+        {
+            return Err(
+                SinkReason::sink("elasticsearch.on_item_error must be fail or skip").into(),
+            );
+        }
+        Ok(())
+    }
+    async fn build(&self, spec: &SinkSpec, _ctx: &SinkBuildCtx) -> SinkResult<SinkHandle> {
+        let spec = &secret::resolve_sink_spec(spec)?;
+        let conf = build_conf(spec)?;
+        // 证书文件有误时在构建阶段报错
+        let client = bulk_client(&conf)?;
         let table = conf.table.clone().unwrap_or_else(|| spec.name.clone());
-        let sink = ElasticsearchSink::new(conf, table);
+        let sink = ElasticsearchSink::new(conf, table)
+            .with_client(client)
+            .with_name(spec.name.clone())
+            .with_stats(stats::register(&spec.name, self.kind()))
+            .with_flush_notifier(FlushNotifier::from_params(&spec.params)?)
+            .with_trace_context(TraceContext::from_params(&spec.params)?);
+        let enrich = EnrichConf::from_params(&spec.params)?;
+        let transforms = FieldTransforms::from_params(&spec.params)?;
+        let sink = RetryingSink::from_spec(sink, spec).await?;
+        let sink = EnrichSink::new(sink, enrich).with_transforms(transforms);
         Ok(SinkHandle::new(Box::new(sink)))
     }
 }
 
-impl ConnectorDefProvider for ElasticsearchFactory {
+#[async_trait]
+impl CheckConnection for ElasticsearchSinkFactory {
+    /// 请求 `_cluster/health`：green/yellow 视为就绪，red 或请求失败为未就绪。
+    async fn check_connection(&self, spec: &SinkSpec) -> Readiness {
+        let conf = match secret::resolve_sink_spec(spec).and_then(|spec| build_conf(&spec)) {
+            Ok(conf) => conf,
+            Err(err) => return Readiness::not_ready(self.kind(), err),
+        };
+        let endpoint = conf.get_endpoint();
+        probe(self.kind(), &endpoint, cluster_health(&conf)).await
+    }
+}
+
+async fn cluster_health(conf: &Elasticsearch) -> Result<Option<String>, String> {
+    let uri = format!("{}/_cluster/health", conf.get_endpoint());
+    // 与 bulk 请求同一 endpoint，沿用其 `tls_insecure_hosts` 判定
+    let client = bulk_client(conf).map_err(|e| e.to_string())?;
+    let req = ElasticsearchSink::with_auth(conf, client.get(&uri).timeout(CHECK_TIMEOUT))
+        .map_err(|e| e.to_string())?;
+    let resp = req.send().await.map_err(|e| format!("request fail: {e}"))?;
+    let status = resp.status();
+    let text = resp.text().await.unwrap_or_default();
+    if !status.is_success() {
+        return Err(format!("status {}: {}", status, text.trim()));
+    }
+    let health = serde_json::from_str::<serde_json::Value>(&text)
+        .ok()
+        .and_then(|v| v.get("status")?.as_str().map(str::to_string))
+        .ok_or_else(|| format!("unexpected response: {}", text.trim()))?;
+    match health.as_str() {
+        "green" | "yellow" => Ok(Some(format!("cluster status {health}"))),
+        _ => Err(format!("cluster status {health}")),
+    }
+}
+
+/// 由参数组装 [`Elasticsearch`] 配置（经 TOML 反序列化，沿用其默认值）。
+fn build_conf(spec: &SinkSpec) -> SinkResult<Elasticsearch> {
+    let mut tbl = toml::map::Map::new();
+    if let Some(s) = spec.params.get("endpoint").and_then(|v| v.as_str()) {
+        tbl.insert("endpoint".to_string(), toml::Value::String(s.to_string()));
+    }
+    if let Some(s) = spec.params.get("username").and_then(|v| v.as_str()) {
+        tbl.insert("username".to_string(), toml::Value::String(s.to_string()));
+    }
+    if let Some(s) = spec.params.get("password").and_then(|v| v.as_str()) {
+        tbl.insert("password".to_string(), toml::Value::String(s.to_string()));
+    }
+    if let Some(i) = spec.params.get("batch").and_then(|v| v.as_i64()) {
+        tbl.insert("batch".to_string(), toml::Value::Integer(i));
+    }
+    for key in [
+        "table",
+        "id_field",
+        "id_hash",
+        "on_item_error",
+        "index_pattern",
+        "time_field",
+        "auth_mode",
+        "api_key",
+        "token",
+        "version_field",
+        "version_type",
+        "ingest_id_field",
+        "tls_ca_cert",
+        "tls_client_cert",
+        "tls_client_key",
+    ] {
+        if let Some(s) = spec.params.get(key).and_then(|v| v.as_str()) {
+            tbl.insert(key.to_string(), toml::Value::String(s.to_string()));
+        }
+    }
+    if let Some(fields) = parse_id_from_fields(&spec.params)? {
+        let fields = fields.into_iter().map(toml::Value::String).collect();
+        tbl.insert("id_from_fields".to_string(), toml::Value::Array(fields));
+    }
+    let insecure_hosts = string_list(&spec.params, "tls_insecure_hosts", "elasticsearch")?;
+    if !insecure_hosts.is_empty() {
+        let hosts = insecure_hosts
+            .into_iter()
+            .map(toml::Value::String)
+            .collect();
+        tbl.insert("tls_insecure_hosts".to_string(), toml::Value::Array(hosts));
+    }
+    for key in ["max_batch_bytes", "bulk_concurrency", "api_version"] {
+        if let Some(i) = spec.params.get(key).and_then(|v| v.as_i64()) {
+            tbl.insert(key.to_string(), toml::Value::Integer(i));
+        }
+    }
+    for key in ["ignore_conflicts", "tls_insecure_skip_verify"] {
+        if let Some(b) = spec.params.get(key).and_then(|v| v.as_bool()) {
+            tbl.insert(key.to_string(), toml::Value::Boolean(b));
+        }
+    }
+    let value = toml::Value::Table(tbl);
+    let serialized = toml::to_string(&value).map_err(|err| {
+        SinkError::from(SinkReason::sink(format!(
+            "encode elasticsearch conf failed: {err}"
+        )))
+    })?;
+    toml::from_str(&serialized).map_err(|err| {
+        SinkError::from(SinkReason::sink(format!(
+            "parse elasticsearch conf failed: {err}"
+        )))
+    })
+}
+
+impl SinkDefProvider for ElasticsearchSinkFactory {
     fn sink_def(&self) -> ConnectorDef {
         ConnectorDef {
             id: "elasticsearch_sink".into(),
             kind: self.kind().into(),
             scope: ConnectorScope::Sink,
-            allow_override: vec!["endpoint", "username", "password", "table", "batch"]
-                .into_iter()
-                .map(str::to_string)
-                .collect(),
+            allow_override: vec![
+                "endpoint",
+                "username",
+                "password",
+                "secret_ref",
+                "table",
+                "batch",
+                "max_batch_bytes",
+                "bulk_concurrency",
+                "enrich",
+                "enrich_overwrite",
+                "field_transforms",
+                "retry",
+                "write_deadline_ms",
+                "quarantine",
+                "flush_notify",
+                "trace_context",
+                "trace_id_field",
+                "tracestate",
+                "id_field",
+                "id_from_fields",
+                "id_hash",
+                "tls_insecure_hosts",
+                "api_version",
+                "tls_ca_cert",
+                "tls_client_cert",
+                "tls_client_key",
+                "tls_insecure_skip_verify",
+                "on_item_error",
+                "index_pattern",
+                "time_field",
+                "auth_mode",
+                "api_key",
+                "token",
+                "version_field",
+                "version_type",
+                "ignore_conflicts",
+                "ingest_id_field",
+            ]
+            .into_iter()
+            .map(str::to_string)
+            .collect(),
             default_params: elasticsearch_defaults(),
             origin: Some("wp-connectors:elasticsearch_sink".into()),
         }
     }
 }
 
+/// `index_pattern`：非空且 strftime 占位符均可识别。
+fn is_valid_index_pattern(pattern: &str) -> bool {
+    !pattern.trim().is_empty() && !StrftimeItems::new(pattern).any(|item| item == Item::Error)
+}
+
+/// 外部版本：`version_field` 需配合确定的 `_id`；`version_type` 与 `ignore_conflicts`
+/// 仅在配置 `version_field` 时有意义。
+fn validate_versioning(params: &ParamMap) -> SinkResult<()> {
+    let field = match params.get("version_field") {
+        None => None,
+        Some(v) => match v.as_str().map(str::trim) {
+            Some(s) if !s.is_empty() => Some(s),
+            _ => {
+                return Err(SinkReason::sink(
+                    "elasticsearch.version_field must be a non-empty string",
+                )
+                .into());
+            }
+        },
+    };
+    if let Some(ty) = params.get("version_type")
+        && !matches!(ty.as_str(), Some("external" | "external_gte"))
+    {
+        return Err(SinkReason::sink(
+            "elasticsearch.version_type must be external or external_gte",
+        )
+        .into());
+    }
+    if let Some(flag) = params.get("ignore_conflicts")
+        && !flag.is_boolean()
+    {
+        return Err(SinkReason::sink("elasticsearch.ignore_conflicts must be a boolean").into());
+    }
+    if field.is_none() {
+        if params.contains_key("version_type") || params.contains_key("ignore_conflicts") {
+            return Err(SinkReason::sink(
+                "elasticsearch.version_type/ignore_conflicts require elasticsearch.version_field",
+            )
+            .into());
+        }
+        return Ok(());
+    }
+    if !params.contains_key("id_field") && !params.contains_key("id_from_fields") {
+        return Err(SinkReason::sink(
+            "elasticsearch.version_field requires elasticsearch.id_field or id_from_fields",
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// `id_from_fields`：非空的字段名数组。
+fn parse_id_from_fields(params: &ParamMap) -> SinkResult<Option<Vec<String>>> {
+    let Some(value) = params.get("id_from_fields") else {
+        return Ok(None);
+    };
+    let fields = value
+        .as_array()
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(|v| v.as_str().map(str::trim))
+                .filter(|s| !s.is_empty())
+                .map(str::to_string)
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    if fields.is_empty()
+        || value
+            .as_array()
+            .is_some_and(|items| items.len() != fields.len())
+    {
+        return Err(SinkReason::sink(
+            "elasticsearch.id_from_fields must be a non-empty string array",
+        )
+        .into());
+    }
+    Ok(Some(fields))
+}
+
 fn elasticsearch_defaults() -> ParamMap {
     let mut params = ParamMap::new();
     params.insert("endpoint".into(), json!("http://localhost:9200"));
@@ -90,3 +403,184 @@ fn elasticsearch_defaults() -> ParamMap {
     params.insert("batch".into(), json!(500));
     params
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec(params: ParamMap) -> SinkSpec {
+        SinkSpec {
+            name: "es".into(),
+            kind: "elasticsearch".into(),
+            connector_id: String::new(),
+            group: "test".into(),
+            params,
+            filter: None,
+        }
+    }
+
+    #[test]
+    fn id_field_and_id_from_fields_are_exclusive() {
+        let mut params = elasticsearch_defaults();
+        params.insert("id_from_fields".into(), json!(["host", "ts"]));
+        assert!(
+            ElasticsearchSinkFactory
+                .validate_spec(&spec(params.clone()))
+                .is_ok()
+        );
+
+        params.insert("id_field".into(), json!("event_id"));
+        let err = ElasticsearchSinkFactory
+            .validate_spec(&spec(params.clone()))
+            .expect_err("exclusive");
+        assert!(format!("{err}").contains("mutually exclusive"));
+
+        params.remove("id_field");
+        params.insert("id_hash".into(), json!("md5"));
+        assert!(
+            ElasticsearchSinkFactory
+                .validate_spec(&spec(params.clone()))
+                .is_err()
+        );
+        params.insert("id_hash".into(), json!("sha1"));
+        params.insert("id_from_fields".into(), json!([]));
+        assert!(
+            ElasticsearchSinkFactory
+                .validate_spec(&spec(params))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn auth_mode_requires_matching_credential() {
+        let mut params = elasticsearch_defaults();
+        params.insert("auth_mode".into(), json!("api_key"));
+        assert!(
+            ElasticsearchSinkFactory
+                .validate_spec(&spec(params.clone()))
+                .is_err()
+        );
+        params.insert("api_key".into(), json!("aWQ6c2VjcmV0"));
+        assert!(
+            ElasticsearchSinkFactory
+                .validate_spec(&spec(params.clone()))
+                .is_ok()
+        );
+        params.insert("auth_mode".into(), json!("bearer"));
+        assert!(
+            ElasticsearchSinkFactory
+                .validate_spec(&spec(params.clone()))
+                .is_err()
+        );
+        params.insert("auth_mode".into(), json!("digest"));
+        assert!(
+            ElasticsearchSinkFactory
+                .validate_spec(&spec(params))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn version_field_requires_document_id_and_known_type() {
+        let mut params = elasticsearch_defaults();
+        params.insert("version_field".into(), json!("rev"));
+        let err = ElasticsearchSinkFactory
+            .validate_spec(&spec(params.clone()))
+            .expect_err("needs _id");
+        assert!(format!("{err}").contains("id_field"));
+
+        params.insert("id_field".into(), json!("event_id"));
+        params.insert("version_type".into(), json!("external_gte"));
+        params.insert("ignore_conflicts".into(), json!(true));
+        assert!(
+            ElasticsearchSinkFactory
+                .validate_spec(&spec(params.clone()))
+                .is_ok()
+        );
+
+        params.insert("version_type".into(), json!("internal"));
+        assert!(
+            ElasticsearchSinkFactory
+                .validate_spec(&spec(params.clone()))
+                .is_err()
+        );
+        params.remove("version_type");
+        params.insert("ignore_conflicts".into(), json!("yes"));
+        assert!(
+            ElasticsearchSinkFactory
+                .validate_spec(&spec(params.clone()))
+                .is_err()
+        );
+
+        params.remove("version_field");
+        params.insert("ignore_conflicts".into(), json!(true));
+        assert!(
+            ElasticsearchSinkFactory
+                .validate_spec(&spec(params))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn index_pattern_must_be_valid_strftime() {
+        let mut params = elasticsearch_defaults();
+        params.insert("index_pattern".into(), json!("logs-%Y.%m.%d"));
+        assert!(
+            ElasticsearchSinkFactory
+                .validate_spec(&spec(params.clone()))
+                .is_ok()
+        );
+        params.insert("index_pattern".into(), json!("logs-%Q"));
+        assert!(
+            ElasticsearchSinkFactory
+                .validate_spec(&spec(params))
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn check_connection_reads_cluster_health() {
+        use crate::common::health::ReadinessState;
+        use httpmock::prelude::*;
+
+        let server = MockServer::start_async().await;
+        let mut health = server.mock(|when, then| {
+            when.method(GET)
+                .path("/_cluster/health")
+                .header_exists("authorization");
+            then.status(200)
+                .body(r#"{"cluster_name":"wp","status":"yellow","number_of_nodes":1}"#);
+        });
+        let mut params = elasticsearch_defaults();
+        params.insert("endpoint".into(), json!(server.base_url()));
+        let readiness = ElasticsearchSinkFactory
+            .check_connection(&spec(params.clone()))
+            .await;
+        assert!(readiness.is_ready(), "{readiness:?}");
+        assert_eq!(
+            readiness.target.as_deref(),
+            Some(server.base_url().as_str())
+        );
+        assert_eq!(readiness.detail.as_deref(), Some("cluster status yellow"));
+        health.assert_hits(1);
+        health.delete();
+
+        let red = server.mock(|when, then| {
+            when.method(GET).path("/_cluster/health");
+            then.status(200).body(r#"{"status":"red"}"#);
+        });
+        let readiness = ElasticsearchSinkFactory
+            .check_connection(&spec(params.clone()))
+            .await;
+        assert_eq!(readiness.state, ReadinessState::NotReady);
+        assert_eq!(readiness.detail.as_deref(), Some("cluster status red"));
+        red.assert_hits(1);
+
+        params.insert("endpoint".into(), json!("http://127.0.0.1:1"));
+        let readiness = ElasticsearchSinkFactory
+            .check_connection(&spec(params))
+            .await;
+        assert_eq!(readiness.state, ReadinessState::NotReady);
+        assert!(readiness.detail.is_some_and(|d| d.contains("request fail")));
+    }
+}
@@ -1,6 +1,16 @@
+//! Elasticsearch sink：经 `_bulk` 接口批量写入索引。
+//!
+//! 模块划分：
+//! - config：Elasticsearch 配置与 `elasticsearch://` 连接串解析
+//! - sink：ElasticsearchSink（攒批写入）
+//! - factory：Sink 工厂
+//! - adapter：dev 适配器（连接串转参数）
+
+mod adapter;
 pub mod config;
-mod sink;
 mod factory;
+mod sink;
 
 pub use config::Elasticsearch;
-pub use factory::{register_builder, register_factory_only};
+pub use factory::ElasticsearchSinkFactory;
+pub use sink::ElasticsearchSink;
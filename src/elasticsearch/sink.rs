@@ -1,141 +1,1334 @@
 use async_trait::async_trait;
-use reqwest::{StatusCode, header::CONTENT_TYPE};
+use chrono::format::{Fixed, Item, Numeric, StrftimeItems};
+use chrono::{DateTime, NaiveDate, Utc};
+use reqwest::{
+    RequestBuilder, StatusCode,
+    header::{AUTHORIZATION, CONTENT_TYPE},
+};
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
 use std::collections::VecDeque;
+use std::fmt::Write;
 use std::sync::Arc;
-use wp_connector_api::{AsyncCtrl, AsyncRawDataSink, AsyncRecordSink};
-use wp_error::dist_error::{SinkError, SinkReason, SinkResult};
-use wp_model_core::format::{DataFormat, Json};
-use wp_model_core::model::DataRecord;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use wp_connector_api::{
+    AsyncCtrl, AsyncRawDataSink, AsyncRecordSink, SinkError, SinkReason, SinkResult,
+};
+use wp_data_fmt::{DataFormat, FormatType};
+use wp_log::warn_data;
+use wp_model_core::model::{DataField, DataRecord, Value, fmt_def::TextFmt};
+
+use super::config::Elasticsearch;
+use crate::common::flush_notify::FlushNotifier;
+use crate::common::ingest_id::ingest_id;
+use crate::common::reconnect::{ReconnectCoordinator, backend_key};
+use crate::common::retry::{self, PendingFlush};
+use crate::common::stats::{ConnectorStats, StatsHandle};
+use crate::common::tls::{self, TlsFiles, is_insecure_host};
+use crate::common::trace_context::TraceContext;
 
 const DEFAULT_BATCH: usize = 100;
+const DEFAULT_BULK_CONCURRENCY: usize = 4;
+/// bulk 失败汇总中最多列出的文档数
+const MAX_REPORTED_ITEM_ERRORS: usize = 5;
+/// id_from_fields 拼接字段值时使用的分隔符（单元分隔符，避免 "a"+"bc" 与 "ab"+"c" 冲突）
+const ID_FIELD_SEPARATOR: char = '\u{1f}';
+/// 携带批次标识的请求头，ES 会将其记入慢日志与任务列表
+const OPAQUE_ID_HEADER: &str = "X-Opaque-Id";
+
+/// 待发送文档：(table, _id, 外部版本号, json)
+pub(crate) type BulkDoc = (String, Option<String>, Option<u64>, String);
+/// 一个 bulk 请求的 body 及其 trace 头
+type BulkRequest = (Vec<u8>, Vec<(&'static str, String)>);
 
 pub struct ElasticsearchSink {
-    pub(crate) conf: wp_config::structure::io::Elasticsearch,
+    pub(crate) conf: Elasticsearch,
     pub(crate) table: String,
     pub(crate) batch: usize,
     pub(crate) proc_cnt: usize,
-    pub(crate) values: VecDeque<(String, String)>, // (table, json)
+    pub(crate) values: VecDeque<BulkDoc>,
+    pub(crate) pending_bytes: usize,
+    // ignore_conflicts 下被 ES 以版本冲突拒绝的文档累计数
+    pub(crate) version_conflicts: u64,
+    // index_pattern 只含日期占位符时的格式化缓存：(日期, 索引名)，同一天内不重复格式化
+    index_cache: Option<(NaiveDate, String)>,
+    // index_pattern 只含日期占位符（不含时分秒等），可按日期缓存
+    index_by_date: bool,
+    // sink 名称，作为摄入 id 与 X-Opaque-Id 的前缀；默认为 table
+    pub(crate) name: String,
+    // 当前批次序号，每次 flush 后递增
+    pub(crate) batch_seq: u64,
+    pub(crate) stats: StatsHandle,
+    // 缓存中记录的 wp_event_id（按到达顺序），随 flush 通知带出
+    event_ids: Vec<String>,
+    pub(crate) flush_notifier: FlushNotifier,
+    // 为每个 bulk 请求附加 W3C trace 头；为 None 时不附加
+    trace_context: Option<TraceContext>,
+    // 缓存批内第一条记录携带的 trace id
+    buffer_trace_id: Option<String>,
+    // bulk 请求共用的客户端，首次发送时构建（证书只读取一次）
+    client: Option<reqwest::Client>,
 }
 
 impl ElasticsearchSink {
-    pub fn new(conf: wp_config::structure::io::Elasticsearch, table: String) -> Self {
+    pub fn new(conf: Elasticsearch, table: String) -> Self {
+        if conf.tls_insecure_skip_verify {
+            warn_data!("es tls_insecure_skip_verify enabled: certificates are not verified");
+        }
         Self {
             batch: conf.batch.unwrap_or(DEFAULT_BATCH),
             conf,
-            table,
+            name: table.clone(),
             proc_cnt: 0,
             values: Default::default(),
+            pending_bytes: 0,
+            version_conflicts: 0,
+            index_cache: None,
+            index_by_date: conf.index_pattern.as_deref().is_some_and(date_only_pattern),
+            batch_seq: 0,
+            stats: StatsHandle::detached(&table, "elasticsearch"),
+            event_ids: Vec::new(),
+            flush_notifier: FlushNotifier::default(),
+            trace_context: None,
+            buffer_trace_id: None,
+            client: None,
+            table,
         }
     }
 
+    /// 使用已构建的 bulk 客户端（见 [`bulk_client`]）。
+    pub fn with_client(mut self, client: reqwest::Client) -> Self {
+        self.client = Some(client);
+        self
+    }
+
+    /// bulk 客户端；未设置时按配置构建并缓存。
+    fn client(&mut self) -> SinkResult<reqwest::Client> {
+        if let Some(client) = &self.client {
+            return Ok(client.clone());
+        }
+        let client = bulk_client(&self.conf)?;
+        self.client = Some(client.clone());
+        Ok(client)
+    }
+
+    /// 设置 sink 名称（摄入 id 与 X-Opaque-Id 的前缀）。
+    pub fn with_name(mut self, name: String) -> Self {
+        self.name = name;
+        self
+    }
+
+    /// 使用外部登记的状态句柄（见 [`crate::common::stats::register`]），
+    /// 以配置的索引作为指标的写入目标。
+    pub fn with_stats(mut self, stats: StatsHandle) -> Self {
+        self.stats = stats.with_destination(self.table.as_str());
+        self
+    }
+
+    /// 每批成功写出后的通知（见 [`crate::common::flush_notify`]）。
+    pub fn with_flush_notifier(mut self, flush_notifier: FlushNotifier) -> Self {
+        self.flush_notifier = flush_notifier;
+        self
+    }
+
+    /// 为每个 bulk 请求附加 W3C trace 头（见 [`crate::common::trace_context`]）。
+    pub fn with_trace_context(mut self, trace_context: Option<TraceContext>) -> Self {
+        self.trace_context = trace_context;
+        self
+    }
+
+    /// 缓存条数、累计投递条数与最近错误的快照。
+    pub fn stats(&self) -> ConnectorStats {
+        self.stats.snapshot()
+    }
+
+    /// 序列化记录；配置 `ingest_id_field` 时附加按批次序号与批内位置生成的摄入 id。
+    fn format_doc(&self, data: &DataRecord) -> String {
+        let Some(field) = self.conf.ingest_id_field.as_deref() else {
+            return FormatType::from(&TextFmt::Json).format_record(data);
+        };
+        let id = ingest_id(&self.name, self.batch_seq, self.values.len());
+        let mut stamped = data.clone();
+        stamped.append(DataField::from_chars(field, id.as_str()));
+        FormatType::from(&TextFmt::Json).format_record(&stamped)
+    }
+
+    /// 记录写入的索引：配置 `index_pattern` 时按记录时间（`time_field`，缺省为 `now`）格式化并转为小写
+    /// （ES 索引名不允许大写，如 `%b` 渲染出的月份名），否则为 `table`；格式化失败时同样回退到 `table`。
+    fn resolve_index(&mut self, data: &DataRecord, now: DateTime<Utc>) -> String {
+        let Some(pattern) = self.conf.index_pattern.as_deref() else {
+            return self.table.clone();
+        };
+        let ts = self
+            .conf
+            .time_field
+            .as_deref()
+            .and_then(|field| data.get2(field))
+            .and_then(|field| match &field.value {
+                Value::Time(dt) => Some(dt.and_utc()),
+                other => DateTime::parse_from_rfc3339(other.to_string().trim())
+                    .ok()
+                    .map(|dt| dt.with_timezone(&Utc)),
+            })
+            .unwrap_or(now);
+        let date = ts.date_naive();
+        if let Some((cached_date, index)) = &self.index_cache
+            && *cached_date == date
+        {
+            return index.clone();
+        }
+        let mut index = String::new();
+        if write!(index, "{}", ts.format(pattern)).is_err() {
+            return self.table.clone();
+        }
+        index.make_ascii_lowercase();
+        if self.index_by_date {
+            self.index_cache = Some((date, index.clone()));
+        }
+        index
+    }
+
+    /// 文档 `_id`：`id_field` 取字段值，`id_from_fields` 取各字段值拼接后的哈希；
+    /// 所选字段均缺失时返回 None，由 ES 自动生成。
+    fn doc_id(&self, data: &DataRecord) -> Option<String> {
+        if let Some(field) = self.conf.id_field.as_deref() {
+            return data.get2(field).map(|f| f.get_value().to_string());
+        }
+        let fields = self.conf.id_from_fields.as_ref()?;
+        let mut present = false;
+        let mut joined = String::new();
+        for (idx, name) in fields.iter().enumerate() {
+            if idx > 0 {
+                joined.push(ID_FIELD_SEPARATOR);
+            }
+            if let Some(field) = data.get2(name) {
+                present = true;
+                joined.push_str(&field.get_value().to_string());
+            }
+        }
+        present.then(|| hash_id(self.conf.id_hash.as_deref(), &joined))
+    }
+
+    /// 外部版本号：`version_field` 的值须为非负整数；字段缺失时返回 None（不带版本写入）。
+    fn doc_version(&self, data: &DataRecord) -> SinkResult<Option<u64>> {
+        let Some(field) = self.conf.version_field.as_deref() else {
+            return Ok(None);
+        };
+        let Some(value) = data.get2(field).map(|f| f.get_value().to_string()) else {
+            return Ok(None);
+        };
+        value.trim().parse::<u64>().map(Some).map_err(|_| {
+            SinkError::from(SinkReason::Sink(format!(
+                "es version_field '{}' is not a non-negative integer: {}",
+                field, value
+            )))
+        })
+    }
+
+    /// 是否在 bulk action 行中携带 `_type`：仅 `api_version` 低于 8 时需要，8+ 会拒绝该字段。
+    fn include_type(&self) -> bool {
+        self.conf.api_version.is_some_and(|v| v < 8)
+    }
+
+    /// 单个文档对应的 bulk 片段（action 行 + 文档行）；`version` 为 `(版本号, version_type)`。
+    fn bulk_entry(
+        table: &str,
+        id: Option<&str>,
+        version: Option<(u64, &str)>,
+        json: &str,
+        include_type: bool,
+    ) -> Vec<u8> {
+        let ty = if include_type {
+            ",\"_type\":\"_doc\""
+        } else {
+            ""
+        };
+        let id = id
+            .map(|id| format!(",\"_id\":{}", serde_json::Value::from(id)))
+            .unwrap_or_default();
+        let version = version
+            .map(|(v, ty)| format!(",\"version\":{},\"version_type\":\"{}\"", v, ty))
+            .unwrap_or_default();
+        let header = format!(
+            "{{\"index\":{{\"_index\":\"{}\"{}{}{}}}}}\n",
+            table, ty, id, version
+        );
+        let mut entry = Vec::with_capacity(header.len() + json.len() + 1);
+        entry.extend_from_slice(header.as_bytes());
+        entry.extend_from_slice(json.as_bytes());
+        entry.push(b'\n');
+        entry
+    }
+
+    /// 记录序列化后放入缓存。超过 `max_batch_bytes` 的文档不入缓存，以 `Ok(Some(err))` 返回拒绝原因；
+    /// 它仍计入已接收，重试装饰器不会再次交给本 sink。
+    fn buffer_record(&mut self, data: &DataRecord) -> SinkResult<Option<SinkError>> {
+        let val = self.format_doc(data);
+        let id = self.doc_id(data);
+        let version = self.doc_version(data)?;
+        let index = self.resolve_index(data, Utc::now());
+        self.proc_cnt += 1;
+        if let Err(e) = self.check_doc_size(&index, id.as_deref(), version, &val) {
+            self.stats.record_delivery(0, 1);
+            self.stats.record_error(&e);
+            return Ok(Some(e));
+        }
+        self.pending_bytes += val.len();
+        self.values.push_back((index, id, version, val));
+        if let Some(field) = data.get2("wp_event_id") {
+            self.event_ids.push(field.get_value().to_string());
+        }
+        if self.buffer_trace_id.is_none() {
+            self.buffer_trace_id = self
+                .trace_context
+                .as_ref()
+                .and_then(|trace| trace.record_trace_id(data));
+        }
+        Ok(None)
+    }
+
+    /// 攒满 `batch` 条或缓存字节数达到 `max_batch_bytes` 时 flush。
+    async fn flush_if_due(&mut self) -> SinkResult<()> {
+        let over_bytes = self
+            .conf
+            .max_batch_bytes
+            .is_some_and(|max| self.pending_bytes >= max);
+        if self.proc_cnt.is_multiple_of(self.batch) || over_bytes {
+            self.flush().await?;
+        }
+        Ok(())
+    }
+
+    /// 单个文档的 bulk 片段超过 `max_batch_bytes` 时返回错误；这样的文档在进入缓存前即被拒绝，
+    /// 否则它会留在缓存中使之后的每次 flush 失败。
+    fn check_doc_size(
+        &self,
+        index: &str,
+        id: Option<&str>,
+        version: Option<u64>,
+        json: &str,
+    ) -> SinkResult<()> {
+        let Some(max_bytes) = self.conf.max_batch_bytes else {
+            return Ok(());
+        };
+        let version_type = self.conf.version_type.as_deref().unwrap_or("external");
+        let version = version.map(|v| (v, version_type));
+        let len = Self::bulk_entry(index, id, version, json, self.include_type()).len();
+        if len > max_bytes {
+            return Err(SinkError::from(SinkReason::Sink(format!(
+                "es document of {} bytes exceeds max_batch_bytes {}",
+                len, max_bytes
+            ))));
+        }
+        Ok(())
+    }
+
+    /// 按 `max_batch_bytes` 将缓存切分为多个 bulk body，保持提交顺序；缓存在写入成功后才清空。
+    fn bulk_bodies(&self) -> SinkResult<Vec<Vec<u8>>> {
+        let include_type = self.include_type();
+        let version_type = self.conf.version_type.as_deref().unwrap_or("external");
+        let entries = self
+            .values
+            .iter()
+            .map(|(table, id, version, json)| {
+                let version = version.map(|v| (v, version_type));
+                Self::bulk_entry(table, id.as_deref(), version, json, include_type)
+            })
+            .collect::<Vec<_>>();
+        split_bulk_bodies(entries, self.conf.max_batch_bytes)
+    }
+
+    /// 发送缓存的文档；成功后清空并计入投递结果，失败时保留整批由外层重试
+    /// （摄入 id 已写入文档，重试时不变）。
+    async fn flush(&mut self) -> SinkResult<()> {
+        if self.values.is_empty() {
+            return Ok(());
+        }
+        let requests = self
+            .bulk_bodies()?
+            .into_iter()
+            .map(|body| {
+                let trace = self
+                    .trace_context
+                    .as_ref()
+                    .map(|t| t.headers_with_id(self.buffer_trace_id.clone()))
+                    .unwrap_or_default();
+                (body, trace)
+            })
+            .collect();
+        let opaque_id = self
+            .conf
+            .ingest_id_field
+            .as_ref()
+            .map(|_| format!("{}-{}", self.name, self.batch_seq));
+        self.batch_seq += 1;
+        let concurrency = self
+            .conf
+            .bulk_concurrency
+            .unwrap_or(DEFAULT_BULK_CONCURRENCY)
+            .max(1);
+        let client = self.client()?;
+        let result =
+            Self::insert_bodies(&client, &self.conf, requests, concurrency, opaque_id).await;
+        match result {
+            Ok((conflicts, skipped)) => {
+                let written = self.values.len().saturating_sub(skipped);
+                self.stats.record_delivery(written as u64, skipped as u64);
+                let event_ids = std::mem::take(&mut self.event_ids);
+                self.flush_notifier
+                    .notify(&self.table, self.values.len(), event_ids);
+                self.values.clear();
+                self.pending_bytes = 0;
+                self.buffer_trace_id = None;
+                self.stats.set_buffered(0);
+                self.version_conflicts += conflicts as u64;
+                self.stats.mark_flush();
+                Ok(())
+            }
+            // 缓存保留待重试，投递结果在最终写出或丢弃时再计入
+            Err(e) => {
+                self.stats.set_buffered(self.values.len());
+                self.stats.record_error(&e);
+                Err(e)
+            }
+        }
+    }
+
+    /// 并发（有上限）发送多个 bulk 请求，任一失败即返回错误；
+    /// 成功时返回（被忽略的版本冲突数, 按 `on_item_error = skip` 跳过的失败文档数）。
+    async fn insert_bodies(
+        client: &reqwest::Client,
+        conf: &Elasticsearch,
+        requests: Vec<BulkRequest>,
+        concurrency: usize,
+        opaque_id: Option<String>,
+    ) -> SinkResult<(usize, usize)> {
+        if requests.len() == 1 {
+            let (body, trace) = requests.into_iter().next().unwrap_or_default();
+            return Self::insert_values(client, conf, body, opaque_id.as_deref(), &trace).await;
+        }
+        let permits = Arc::new(Semaphore::new(concurrency));
+        let mut tasks = JoinSet::new();
+        for (body, trace) in requests {
+            let client = client.clone();
+            let conf = conf.clone();
+            let permits = permits.clone();
+            let opaque_id = opaque_id.clone();
+            tasks.spawn(async move {
+                let _permit = permits.acquire_owned().await.map_err(|e| {
+                    SinkError::from(SinkReason::Sink(format!("es bulk semaphore closed: {}", e)))
+                })?;
+                Self::insert_values(&client, &conf, body, opaque_id.as_deref(), &trace).await
+            });
+        }
+        let mut first_err = None;
+        let (mut conflicts, mut skipped) = (0, 0);
+        while let Some(joined) = tasks.join_next().await {
+            let result = joined.map_err(|e| {
+                SinkError::from(SinkReason::Sink(format!("es bulk join error: {}", e)))
+            })?;
+            match result {
+                Ok((c, s)) => {
+                    conflicts += c;
+                    skipped += s;
+                }
+                Err(e) if first_err.is_none() => first_err = Some(e),
+                Err(_) => {}
+            }
+        }
+        match first_err {
+            Some(e) => Err(e),
+            None => Ok((conflicts, skipped)),
+        }
+    }
+
+    /// 按 `auth_mode` 设置 `Authorization`：basic 使用 username/password，
+    /// api_key 为 `ApiKey <api_key>`，bearer 为 `Bearer <token>`。
+    pub(crate) fn with_auth(
+        conf: &Elasticsearch,
+        req: RequestBuilder,
+    ) -> SinkResult<RequestBuilder> {
+        let credential = |value: &Option<String>, name: &str| {
+            value.clone().filter(|v| !v.is_empty()).ok_or_else(|| {
+                SinkError::from(SinkReason::Sink(format!("es auth_mode requires {}", name)))
+            })
+        };
+        match conf.auth_mode.as_deref().unwrap_or("basic") {
+            "basic" => Ok(req.basic_auth(&conf.username, Some(&conf.password))),
+            "api_key" => {
+                let key = credential(&conf.api_key, "api_key")?;
+                Ok(req.header(AUTHORIZATION, format!("ApiKey {}", key)))
+            }
+            "bearer" => Ok(req.bearer_auth(credential(&conf.token, "token")?)),
+            other => Err(SinkError::from(SinkReason::Sink(format!(
+                "es unknown auth_mode: {}",
+                other
+            )))),
+        }
+    }
+
+    /// 发送一个 bulk 请求；成功时返回（被忽略的版本冲突数, 跳过的失败文档数）。
     async fn insert_values(
-        conf: &wp_config::structure::io::Elasticsearch,
+        client: &reqwest::Client,
+        conf: &Elasticsearch,
         body: Vec<u8>,
-    ) -> SinkResult<()> {
+        opaque_id: Option<&str>,
+        trace: &[(&'static str, String)],
+    ) -> SinkResult<(usize, usize)> {
+        let (conflicts, failed, failures) =
+            Self::send_bulk(client, conf, body, opaque_id, trace).await?;
+        if conflicts > 0 {
+            warn_data!("es bulk ignored {} version conflicts", conflicts);
+        }
+        if let Some(summary) = failures {
+            if conf.on_item_error.as_deref() == Some("skip") {
+                warn_data!("es bulk partial failure skipped: {}", summary);
+            } else {
+                return Err(SinkError::from(SinkReason::Sink(format!(
+                    "es bulk partial failure: {}",
+                    summary
+                ))));
+            }
+        }
+        Ok((conflicts, failed))
+    }
+
+    /// 发送 bulk 请求并解析逐项结果，返回值同 [`bulk_item_errors`]。
+    async fn send_bulk(
+        client: &reqwest::Client,
+        conf: &Elasticsearch,
+        body: Vec<u8>,
+        opaque_id: Option<&str>,
+        trace: &[(&'static str, String)],
+    ) -> SinkResult<(usize, usize, Option<String>)> {
         let uri = format!("{}/_bulk", conf.get_endpoint());
-        let client = reqwest::Client::builder().build().map_err(|e| {
-            SinkError::from(SinkReason::SinkError(format!(
-                "es client build fail: {}",
-                e
-            )))
-        })?;
-        let resp = client
-            .put(&uri)
-            .basic_auth(&conf.username, Some(&conf.password))
+        let mut req = Self::with_auth(conf, client.put(&uri))?;
+        if let Some(id) = opaque_id {
+            req = req.header(OPAQUE_ID_HEADER, id);
+        }
+        for (name, value) in trace {
+            req = req.header(*name, value);
+        }
+        let resp = req
             .header(CONTENT_TYPE, "application/x-ndjson")
             .body(body)
             .send()
             .await
             .map_err(|e| {
-                SinkError::from(SinkReason::SinkError(format!("es bulk send fail: {}", e)))
+                retry::transport_error(format!("es bulk send fail: {}", e), e.is_timeout())
             })?;
-        if resp.status() != StatusCode::OK {
+        let status = resp.status();
+        if status != StatusCode::OK {
             let t = resp.text().await.unwrap_or_default();
-            return Err(SinkError::from(SinkReason::SinkError(format!(
-                "es bulk fail: {}",
-                t
+            return Err(retry::status_error(
+                status.as_u16(),
+                format!("es bulk fail: {}", t),
+            ));
+        }
+        // 整体 200 时单个文档仍可能失败（mapping 冲突、版本冲突等），需逐项检查
+        let text = resp.text().await.map_err(|e| {
+            retry::transport_error(format!("es bulk read response fail: {}", e), e.is_timeout())
+        })?;
+        Ok(bulk_item_errors(&text, conf.ignore_conflicts))
+    }
+}
+
+/// 解析 bulk 响应，`errors` 为 true 时汇总失败文档（索引、状态码、原因）；
+/// 无失败或响应无法解析时汇总为 None。`ignore_conflicts` 时版本冲突只计数，不计入失败。
+///
+/// # return
+/// * `(usize, usize, Option<String>)` - (被忽略的版本冲突数, 失败文档数, 失败汇总)。
+fn bulk_item_errors(body: &str, ignore_conflicts: bool) -> (usize, usize, Option<String>) {
+    let Ok(resp) = serde_json::from_str::<serde_json::Value>(body) else {
+        return (0, 0, None);
+    };
+    if resp.get("errors").and_then(|v| v.as_bool()) != Some(true) {
+        return (0, 0, None);
+    }
+    let Some(items) = resp.get("items").and_then(|v| v.as_array()) else {
+        return (0, 0, None);
+    };
+    let mut conflicts = 0;
+    let mut failed = Vec::new();
+    for item in items {
+        // 每项形如 {"index": {...}}，键为 action 名
+        let Some(result) = item.as_object().and_then(|o| o.values().next()) else {
+            continue;
+        };
+        let status = result.get("status").and_then(|v| v.as_u64()).unwrap_or(0);
+        if (200..300).contains(&status) {
+            continue;
+        }
+        let index = result.get("_index").and_then(|v| v.as_str()).unwrap_or("?");
+        let error = result.get("error");
+        let kind = error
+            .and_then(|e| e.get("type"))
+            .and_then(|v| v.as_str())
+            .unwrap_or("unknown");
+        let reason = error
+            .and_then(|e| e.get("reason"))
+            .and_then(|v| v.as_str())
+            .unwrap_or("");
+        if ignore_conflicts && status == 409 && kind == "version_conflict_engine_exception" {
+            conflicts += 1;
+            continue;
+        }
+        failed.push(format!("[{} {}] {}: {}", index, status, kind, reason));
+    }
+    if failed.is_empty() {
+        return (conflicts, 0, None);
+    }
+    let mut summary = format!("{} of {} items failed: ", failed.len(), items.len());
+    summary.push_str(&failed[..failed.len().min(MAX_REPORTED_ITEM_ERRORS)].join("; "));
+    if failed.len() > MAX_REPORTED_ITEM_ERRORS {
+        let _ = write!(
+            summary,
+            "; ... {} more",
+            failed.len() - MAX_REPORTED_ITEM_ERRORS
+        );
+    }
+    (conflicts, failed.len(), Some(summary))
+}
+
+/// 按 TLS 配置构建 HTTP 客户端；`insecure` 或开启 `tls_insecure_skip_verify` 时
+/// 不校验证书与主机名。
+fn build_client(conf: &Elasticsearch, insecure: bool) -> SinkResult<reqwest::Client> {
+    let files = TlsFiles {
+        ca_cert: conf.tls_ca_cert.as_deref(),
+        client_cert: conf.tls_client_cert.as_deref(),
+        client_key: conf.tls_client_key.as_deref(),
+    };
+    let insecure = insecure || conf.tls_insecure_skip_verify;
+    tls::configure(reqwest::Client::builder(), &files, insecure, "es")?
+        .build()
+        .map_err(|e| SinkError::from(SinkReason::Sink(format!("es client build fail: {}", e))))
+}
+
+/// bulk 请求使用的客户端：endpoint 主机在 `tls_insecure_hosts` 中时跳过证书校验。
+pub(crate) fn bulk_client(conf: &Elasticsearch) -> SinkResult<reqwest::Client> {
+    let insecure = is_insecure_host(&conf.tls_insecure_hosts, &conf.get_endpoint());
+    build_client(conf, insecure)
+}
+
+/// 按 `id_hash` 计算十六进制摘要，默认 sha256。
+fn hash_id(algo: Option<&str>, joined: &str) -> String {
+    let digest = match algo {
+        Some("sha1") => Sha1::digest(joined.as_bytes()).to_vec(),
+        _ => Sha256::digest(joined.as_bytes()).to_vec(),
+    };
+    let mut out = String::with_capacity(digest.len() * 2);
+    for b in digest {
+        let _ = write!(out, "{b:02x}");
+    }
+    out
+}
+
+/// 按字节上限切分 bulk 片段；未设置上限时合并为一个 body。
+/// 单个文档超过上限时直接报错，避免发送超限请求。
+fn split_bulk_bodies(entries: Vec<Vec<u8>>, max_bytes: Option<usize>) -> SinkResult<Vec<Vec<u8>>> {
+    let Some(max_bytes) = max_bytes else {
+        return Ok(vec![entries.concat()]);
+    };
+    let mut bodies = Vec::new();
+    let mut current: Vec<u8> = Vec::new();
+    for entry in entries {
+        if entry.len() > max_bytes {
+            return Err(SinkError::from(SinkReason::Sink(format!(
+                "es document of {} bytes exceeds max_batch_bytes {}",
+                entry.len(),
+                max_bytes
             ))));
         }
-        Ok(())
+        if !current.is_empty() && current.len() + entry.len() > max_bytes {
+            bodies.push(std::mem::take(&mut current));
+        }
+        current.extend_from_slice(&entry);
+    }
+    if !current.is_empty() {
+        bodies.push(current);
+    }
+    Ok(bodies)
+}
+
+#[async_trait]
+impl PendingFlush for ElasticsearchSink {
+    fn pending_len(&self) -> usize {
+        self.values.len()
+    }
+
+    async fn flush_now(&mut self) -> SinkResult<()> {
+        self.flush().await
+    }
+
+    fn discard_pending(&mut self) -> usize {
+        let discarded = self.values.len();
+        self.stats.record_delivery(0, discarded as u64);
+        self.values.clear();
+        self.pending_bytes = 0;
+        self.event_ids.clear();
+        self.buffer_trace_id = None;
+        self.stats.set_buffered(0);
+        discarded
+    }
+
+    fn accepted(&self) -> u64 {
+        self.proc_cnt as u64
     }
 }
 
 #[async_trait]
 impl AsyncCtrl for ElasticsearchSink {
     async fn stop(&mut self) -> SinkResult<()> {
-        if self.values.is_empty() {
-            return Ok(());
-        }
-        let mut buf = Vec::new();
-        while let Some((table, json)) = self.values.pop_front() {
-            let header = format!(
-                "{{\"index\":{{\"_index\":\"{}\",\"_type\":\"_doc\"}}}}\n",
-                table
-            );
-            buf.extend_from_slice(header.as_bytes());
-            buf.extend_from_slice(json.as_bytes());
-            buf.extend_from_slice(b"\n");
-        }
-        Self::insert_values(&self.conf, buf).await
+        self.flush().await
     }
+    /// 以认证后的 `GET /` 确认集群可达且凭据有效，按后端共享退避。
     async fn reconnect(&mut self) -> SinkResult<()> {
-        Ok(())
+        let client = self.client()?;
+        let endpoint = self.conf.get_endpoint();
+        let conf = &self.conf;
+        ReconnectCoordinator::shared(backend_key("elasticsearch", &endpoint), Default::default())
+            .run(|| {
+                let req = Self::with_auth(conf, client.get(format!("{endpoint}/")));
+                async move {
+                    let resp = req?.send().await.map_err(|e| {
+                        SinkError::from(SinkReason::Sink(format!("es reconnect fail: {}", e)))
+                    })?;
+                    if !resp.status().is_success() {
+                        return Err(SinkError::from(SinkReason::Sink(format!(
+                            "es reconnect status: {}",
+                            resp.status()
+                        ))));
+                    }
+                    Ok(())
+                }
+            })
+            .await
     }
 }
 
 #[async_trait]
 impl AsyncRecordSink for ElasticsearchSink {
     async fn sink_record(&mut self, data: &DataRecord) -> SinkResult<()> {
-        let j = Json;
-        let val = j.format_record(data).to_string();
-        self.proc_cnt += 1;
-        self.values.push_back((self.table.clone(), val));
-        if self.proc_cnt.is_multiple_of(self.batch) {
-            let mut buf = Vec::new();
-            while let Some((table, json)) = self.values.pop_front() {
-                let header = format!(
-                    "{{\"index\":{{\"_index\":\"{}\",\"_type\":\"_doc\"}}}}\n",
-                    table
-                );
-                buf.extend_from_slice(header.as_bytes());
-                buf.extend_from_slice(json.as_bytes());
-                buf.extend_from_slice(b"\n");
-            }
-            Self::insert_values(&self.conf, buf).await?;
+        if let Some(rejected) = self.buffer_record(data)? {
+            return Err(rejected);
         }
-        Ok(())
+        self.flush_if_due().await
     }
 
+    /// 超过 `max_batch_bytes` 的文档被拒绝，其余记录照常写入，结束后返回首个拒绝错误。
     async fn sink_records(&mut self, data: Vec<Arc<DataRecord>>) -> SinkResult<()> {
+        let mut rejected = None;
         for record in data {
-            self.sink_record(record.as_ref()).await?;
+            match self.buffer_record(record.as_ref())? {
+                Some(e) => {
+                    rejected.get_or_insert(e);
+                }
+                None => self.flush_if_due().await?,
+            }
         }
-        Ok(())
+        rejected.map_or(Ok(()), Err)
     }
 }
 
 #[async_trait]
 impl AsyncRawDataSink for ElasticsearchSink {
     async fn sink_str(&mut self, _data: &str) -> SinkResult<()> {
-        Err(SinkError::from(SinkReason::SinkError(
+        Err(SinkError::from(SinkReason::Sink(
             "Elasticsearch sink does not support raw input; route TDC records".into(),
         )))
     }
     async fn sink_bytes(&mut self, _data: &[u8]) -> SinkResult<()> {
-        Err(SinkError::from(SinkReason::SinkError(
+        Err(SinkError::from(SinkReason::Sink(
             "Elasticsearch sink does not support raw bytes input; route TDC records".into(),
         )))
     }
 
     async fn sink_str_batch(&mut self, _data: Vec<&str>) -> SinkResult<()> {
-        Err(SinkError::from(SinkReason::SinkError(
+        Err(SinkError::from(SinkReason::Sink(
             "Elasticsearch sink does not support raw batch input; route TDC records".into(),
         )))
     }
 
     async fn sink_bytes_batch(&mut self, _data: Vec<&[u8]>) -> SinkResult<()> {
-        Err(SinkError::from(SinkReason::SinkError(
+        Err(SinkError::from(SinkReason::Sink(
             "Elasticsearch sink does not support raw batch input; route TDC records".into(),
         )))
     }
 }
+
+/// strftime 模式是否只含日期占位符；含时分秒等更细粒度的占位符时不能按日期缓存。
+fn date_only_pattern(pattern: &str) -> bool {
+    StrftimeItems::new(pattern).all(|item| match item {
+        Item::Literal(_) | Item::OwnedLiteral(_) | Item::Space(_) | Item::OwnedSpace(_) => true,
+        Item::Numeric(numeric, _) => matches!(
+            numeric,
+            Numeric::Year
+                | Numeric::YearDiv100
+                | Numeric::YearMod100
+                | Numeric::IsoYear
+                | Numeric::IsoYearDiv100
+                | Numeric::IsoYearMod100
+                | Numeric::Month
+                | Numeric::Day
+                | Numeric::WeekFromSun
+                | Numeric::WeekFromMon
+                | Numeric::IsoWeek
+                | Numeric::NumDaysFromSun
+                | Numeric::WeekdayFromMon
+                | Numeric::Ordinal
+        ),
+        Item::Fixed(fixed) => matches!(
+            fixed,
+            Fixed::ShortMonthName
+                | Fixed::LongMonthName
+                | Fixed::ShortWeekdayName
+                | Fixed::LongWeekdayName
+        ),
+        _ => false,
+    })
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use httpmock::prelude::*;
+    use wp_model_core::model::DataField;
+
+    fn big_record(idx: usize) -> DataRecord {
+        let mut rec = DataRecord::default();
+        rec.append(DataField::from_chars("seq", idx.to_string().as_str()));
+        rec.append(DataField::from_chars("blob", "x".repeat(400).as_str()));
+        rec
+    }
+
+    #[test]
+    fn split_keeps_order_and_limit() {
+        let entries = (0..5)
+            .map(|i| {
+                let json = format!("{{\"seq\":{}}}", i);
+                ElasticsearchSink::bulk_entry("idx", None, None, &json, false)
+            })
+            .collect::<Vec<_>>();
+        let one = entries[0].len();
+        let bodies = split_bulk_bodies(entries.clone(), Some(one * 2)).unwrap();
+        assert_eq!(bodies.len(), 3);
+        assert!(bodies.iter().all(|b| b.len() <= one * 2));
+        assert_eq!(bodies.concat(), entries.concat(), "submission order kept");
+
+        let err = split_bulk_bodies(entries, Some(one - 1)).expect_err("oversized doc");
+        assert!(format!("{err}").contains("max_batch_bytes"));
+    }
+
+    #[test]
+    fn tls_client_builds_for_each_combination() {
+        let fixture = |name: &str| {
+            Some(format!(
+                "{}/tests/fixtures/tls/{}",
+                env!("CARGO_MANIFEST_DIR"),
+                name
+            ))
+        };
+        let tls = |ca: bool, identity: bool, skip_verify: bool| Elasticsearch {
+            tls_ca_cert: if ca { fixture("ca.pem") } else { None },
+            tls_client_cert: if identity {
+                fixture("client.pem")
+            } else {
+                None
+            },
+            tls_client_key: if identity {
+                fixture("client.key")
+            } else {
+                None
+            },
+            tls_insecure_skip_verify: skip_verify,
+            ..Default::default()
+        };
+        for ca in [false, true] {
+            for identity in [false, true] {
+                for skip_verify in [false, true] {
+                    build_client(&tls(ca, identity, skip_verify), false)
+                        .unwrap_or_else(|e| panic!("{ca}/{identity}/{skip_verify}: {e}"));
+                }
+            }
+        }
+
+        let key_only = Elasticsearch {
+            tls_client_key: fixture("client.key"),
+            ..Default::default()
+        };
+        let err = build_client(&key_only, false).expect_err("cert missing");
+        assert!(format!("{err}").contains("set together"));
+        let not_a_cert = Elasticsearch {
+            tls_ca_cert: fixture("client.key"),
+            ..Default::default()
+        };
+        assert!(
+            build_client(&not_a_cert, false).is_err(),
+            "key is not a ca cert"
+        );
+    }
+
+    #[test]
+    fn bulk_header_includes_type_only_for_legacy_versions() {
+        let header = |api_version: Option<u32>| {
+            let sink = ElasticsearchSink::new(
+                Elasticsearch {
+                    api_version,
+                    ..Default::default()
+                },
+                "logs".into(),
+            );
+            let include_type = sink.include_type();
+            let entry = ElasticsearchSink::bulk_entry("logs", Some("a1"), None, "{}", include_type);
+            String::from_utf8(entry)
+                .unwrap()
+                .lines()
+                .next()
+                .unwrap()
+                .to_string()
+        };
+        let modern = r#"{"index":{"_index":"logs","_id":"a1"}}"#;
+        assert_eq!(header(None), modern);
+        assert_eq!(header(Some(8)), modern);
+        assert_eq!(
+            header(Some(7)),
+            r#"{"index":{"_index":"logs","_type":"_doc","_id":"a1"}}"#
+        );
+    }
+
+    #[test]
+    fn index_pattern_resolves_from_record_time_or_clock() {
+        let conf = Elasticsearch {
+            index_pattern: Some("logs-%Y.%m.%d".into()),
+            time_field: Some("ts".into()),
+            ..Default::default()
+        };
+        let mut sink = ElasticsearchSink::new(conf, "fallback".into());
+        let clock = DateTime::parse_from_rfc3339("2024-06-01T23:59:30Z")
+            .unwrap()
+            .with_timezone(&Utc);
+
+        assert_eq!(
+            sink.resolve_index(&DataRecord::default(), clock),
+            "logs-2024.06.01"
+        );
+        let mut rec = DataRecord::default();
+        rec.append(DataField::from_chars("ts", "2024-06-02T00:00:05+00:00"));
+        assert_eq!(sink.resolve_index(&rec, clock), "logs-2024.06.02");
+        // 同一天命中缓存
+        assert_eq!(
+            sink.index_cache.as_ref().map(|(_, i)| i.as_str()),
+            Some("logs-2024.06.02")
+        );
+        assert_eq!(sink.resolve_index(&rec, clock), "logs-2024.06.02");
+
+        // 含小时占位符时不按日期缓存；月份名转为小写
+        let conf = Elasticsearch {
+            index_pattern: Some("logs-%b-%d-%H".into()),
+            ..Default::default()
+        };
+        let mut hourly = ElasticsearchSink::new(conf, "fallback".into());
+        let record = DataRecord::default();
+        assert_eq!(hourly.resolve_index(&record, clock), "logs-jun-01-23");
+        let next_hour = clock + chrono::Duration::minutes(45);
+        assert_eq!(hourly.resolve_index(&record, next_hour), "logs-jun-02-00");
+        let same_day = clock - chrono::Duration::hours(2);
+        assert_eq!(hourly.resolve_index(&record, same_day), "logs-jun-01-21");
+        assert!(hourly.index_cache.is_none());
+
+        let mut plain = ElasticsearchSink::new(Elasticsearch::default(), "fallback".into());
+        assert_eq!(plain.resolve_index(&rec, clock), "fallback");
+    }
+
+    #[tokio::test]
+    async fn authorization_header_follows_auth_mode() {
+        let server = MockServer::start_async().await;
+        let cases = [
+            (None, "Basic ZWxhc3RpYzp3cGFyc2U="),
+            (Some("api_key"), "ApiKey aWQ6c2VjcmV0"),
+            (Some("bearer"), "Bearer tok-123"),
+        ];
+        for (auth_mode, expected) in cases {
+            let bulk = server.mock(|when, then| {
+                when.method(PUT)
+                    .path("/_bulk")
+                    .header("authorization", expected);
+                then.status(200).body("{\"errors\":false,\"items\":[]}");
+            });
+            let conf = Elasticsearch {
+                endpoint: server.base_url(),
+                batch: Some(1),
+                auth_mode: auth_mode.map(str::to_string),
+                api_key: Some("aWQ6c2VjcmV0".into()),
+                token: Some("tok-123".into()),
+                ..Default::default()
+            };
+            let mut sink = ElasticsearchSink::new(conf, "logs".into());
+            sink.sink_record(&big_record(1))
+                .await
+                .expect("authorized bulk");
+            bulk.assert_hits(1);
+            bulk.delete();
+        }
+
+        let conf = Elasticsearch {
+            endpoint: server.base_url(),
+            auth_mode: Some("bearer".into()),
+            ..Default::default()
+        };
+        let err =
+            ElasticsearchSink::insert_values(&reqwest::Client::new(), &conf, Vec::new(), None, &[])
+                .await
+                .expect_err("missing token");
+        assert!(format!("{err}").contains("token"));
+    }
+
+    #[tokio::test]
+    async fn trace_context_headers_attached_to_bulk_requests() {
+        let server = MockServer::start_async().await;
+        let bulk = server.mock(|when, then| {
+            when.method(PUT)
+                .path("/_bulk")
+                .header("tracestate", "wp=pipeline")
+                .matches(|req| {
+                    req.headers.iter().flatten().any(|(name, value)| {
+                        name.eq_ignore_ascii_case("traceparent")
+                            && value.starts_with("00-4bf92f3577b34da6a3ce929d0e0e4736-")
+                    })
+                });
+            then.status(200).body("{\"errors\":false,\"items\":[]}");
+        });
+        let conf = Elasticsearch {
+            endpoint: server.base_url(),
+            batch: Some(2),
+            ..Default::default()
+        };
+        let trace = TraceContext {
+            trace_id_field: Some("trace_id".into()),
+            tracestate: Some("wp=pipeline".into()),
+        };
+        let mut sink = ElasticsearchSink::new(conf, "logs".into()).with_trace_context(Some(trace));
+        let mut traced = big_record(2);
+        traced.append(DataField::from_chars(
+            "trace_id",
+            "4bf92f3577b34da6a3ce929d0e0e4736",
+        ));
+        sink.sink_record(&big_record(1)).await.expect("buffer ok");
+        sink.sink_record(&traced).await.expect("traced bulk");
+        bulk.assert_hits(1);
+        assert!(sink.buffer_trace_id.is_none());
+    }
+
+    #[tokio::test]
+    async fn bulk_item_failures_fail_or_skip() {
+        let server = MockServer::start_async().await;
+        let bulk = server.mock(|when, then| {
+            when.method(PUT).path("/_bulk");
+            then.status(200).body(
+                r#"{"took":3,"errors":true,"items":[
+                    {"index":{"_index":"logs","_id":"a","status":201}},
+                    {"index":{"_index":"logs","_id":"b","status":409,"error":{
+                        "type":"version_conflict_engine_exception",
+                        "reason":"[b]: version conflict"}}}
+                ]}"#,
+            );
+        });
+        let sink_with = |on_item_error: Option<&str>| {
+            let conf = Elasticsearch {
+                endpoint: server.base_url(),
+                batch: Some(10),
+                on_item_error: on_item_error.map(str::to_string),
+                ..Default::default()
+            };
+            ElasticsearchSink::new(conf, "logs".into())
+        };
+
+        let mut sink = sink_with(None);
+        sink.sink_record(&big_record(1)).await.expect("buffer ok");
+        sink.sink_record(&big_record(2)).await.expect("buffer ok");
+        let err = sink
+            .stop()
+            .await
+            .expect_err("item failure aborts by default");
+        let msg = format!("{err}");
+        assert!(msg.contains("1 of 2 items failed"), "{msg}");
+        assert!(
+            msg.contains("[logs 409] version_conflict_engine_exception"),
+            "{msg}"
+        );
+
+        // 整批保留待重试，尚未计入投递结果
+        let stats = sink.stats();
+        assert_eq!(stats.attempted_records, 0);
+        assert!(
+            stats
+                .last_error
+                .is_some_and(|e| e.contains("1 of 2 items failed"))
+        );
+        sink.stop().await.expect_err("retry fails again");
+        assert_eq!(sink.stats().attempted_records, 0);
+        assert_eq!(sink.discard_pending(), 2);
+        let stats = sink.stats();
+        assert_eq!((stats.attempted_records, stats.failed_records), (2, 2));
+
+        let mut sink = sink_with(Some("skip"));
+        sink.sink_record(&big_record(1)).await.expect("buffer ok");
+        sink.sink_record(&big_record(2)).await.expect("buffer ok");
+        sink.stop().await.expect("item failure skipped");
+        bulk.assert_hits(3);
+        // 跳过的失败文档仍计入失败数
+        let stats = sink.stats();
+        assert_eq!((stats.succeeded_records, stats.failed_records), (1, 1));
+        assert!(stats.last_flush.is_some());
+    }
+
+    #[tokio::test]
+    async fn older_external_version_is_counted_as_conflict() {
+        let server = MockServer::start_async().await;
+        let bulk = server.mock(|when, then| {
+            when.method(PUT)
+                .path("/_bulk")
+                .body_contains(r#""_id":"evt-1","version":5,"version_type":"external"}"#)
+                .body_contains(r#""_id":"evt-1","version":3,"version_type":"external"}"#);
+            then.status(200).body(
+                r#"{"took":2,"errors":true,"items":[
+                    {"index":{"_index":"logs","_id":"evt-1","_version":5,"status":201}},
+                    {"index":{"_index":"logs","_id":"evt-1","status":409,"error":{
+                        "type":"version_conflict_engine_exception",
+                        "reason":"[evt-1]: version conflict, current version [5] is higher"}}}
+                ]}"#,
+            );
+        });
+        let sink_with = |ignore_conflicts: bool| {
+            let conf = Elasticsearch {
+                endpoint: server.base_url(),
+                batch: Some(10),
+                id_field: Some("event_id".into()),
+                version_field: Some("rev".into()),
+                ignore_conflicts,
+                ..Default::default()
+            };
+            ElasticsearchSink::new(conf, "logs".into())
+        };
+        let update = |rev: &str| {
+            let mut rec = DataRecord::default();
+            rec.append(DataField::from_chars("event_id", "evt-1"));
+            rec.append(DataField::from_chars("rev", rev));
+            rec
+        };
+
+        let mut sink = sink_with(true);
+        sink.sink_record(&update("5")).await.expect("buffer ok");
+        sink.sink_record(&update("3")).await.expect("buffer ok");
+        sink.stop().await.expect("conflict ignored");
+        assert_eq!(sink.version_conflicts, 1);
+
+        let mut sink = sink_with(false);
+        sink.sink_record(&update("5")).await.expect("buffer ok");
+        sink.sink_record(&update("3")).await.expect("buffer ok");
+        let err = sink
+            .stop()
+            .await
+            .expect_err("conflict fails without ignore_conflicts");
+        assert!(format!("{err}").contains("version_conflict_engine_exception"));
+        assert_eq!(sink.version_conflicts, 0);
+        bulk.assert_hits(2);
+
+        let err = sink
+            .sink_record(&update("v2"))
+            .await
+            .expect_err("non-numeric version");
+        assert!(format!("{err}").contains("version_field 'rev'"));
+    }
+
+    #[tokio::test]
+    async fn large_documents_split_into_multiple_bulk_requests() {
+        let server = MockServer::start_async().await;
+        let bulk = server.mock(|when, then| {
+            when.method(PUT).path("/_bulk");
+            then.status(200).body("{\"errors\":false,\"items\":[]}");
+        });
+
+        let conf = Elasticsearch {
+            endpoint: server.base_url(),
+            batch: Some(10),
+            max_batch_bytes: Some(1024),
+            ..Default::default()
+        };
+        let mut sink = ElasticsearchSink::new(conf, "logs".into());
+        for i in 0..10 {
+            sink.sink_record(&big_record(i)).await.expect("bulk ok");
+        }
+        sink.stop().await.expect("flush ok");
+        assert!(sink.values.is_empty());
+        assert!(bulk.hits() >= 4, "expected split bulk requests");
+    }
+
+    #[tokio::test]
+    async fn oversized_document_is_rejected_before_buffering() {
+        let server = MockServer::start_async().await;
+        let bulk = server.mock(|when, then| {
+            when.method(PUT).path("/_bulk");
+            then.status(200).body("{\"errors\":false,\"items\":[]}");
+        });
+        let conf = Elasticsearch {
+            endpoint: server.base_url(),
+            batch: Some(10),
+            max_batch_bytes: Some(256),
+            ..Default::default()
+        };
+        let mut sink = ElasticsearchSink::new(conf, "logs".into());
+        let mut small = DataRecord::default();
+        small.append(DataField::from_chars("msg", "ok"));
+        let batch = vec![
+            Arc::new(small.clone()),
+            Arc::new(big_record(1)),
+            Arc::new(small),
+        ];
+        let err = sink
+            .sink_records(batch)
+            .await
+            .expect_err("oversized doc rejected");
+        assert!(format!("{err}").contains("max_batch_bytes"), "{err}");
+        assert_eq!(sink.values.len(), 2);
+        assert_eq!(sink.accepted(), 3);
+
+        // 被拒绝的文档不留在缓存中，之后的 flush 不受影响
+        sink.stop().await.expect("flush ok");
+        bulk.assert_hits(1);
+        assert_eq!(sink.stats().failed_records, 1);
+    }
+
+    #[tokio::test]
+    async fn id_field_sets_bulk_document_id_with_auto_id_fallback() {
+        let server = MockServer::start_async().await;
+        let bulk = server.mock(|when, then| {
+            when.method(PUT)
+                .path("/_bulk")
+                .body_contains(r#"{"index":{"_index":"logs","_id":"evt-42"}}"#)
+                .body_contains(r#"{"index":{"_index":"logs"}}"#);
+            then.status(200).body("{\"errors\":false,\"items\":[]}");
+        });
+        let conf = Elasticsearch {
+            endpoint: server.base_url(),
+            batch: Some(10),
+            id_field: Some("event_id".into()),
+            ..Default::default()
+        };
+        let mut sink = ElasticsearchSink::new(conf, "logs".into());
+        let mut rec = DataRecord::default();
+        rec.append(DataField::from_chars("event_id", "evt-42"));
+        sink.sink_record(&rec).await.expect("buffer ok");
+        // 缺少 id 字段：不带 `_id`，由 ES 自动生成
+        let mut missing = DataRecord::default();
+        missing.append(DataField::from_chars("msg", "no id"));
+        sink.sink_record(&missing).await.expect("buffer ok");
+
+        sink.stop().await.expect("flush ok");
+        bulk.assert_hits(1);
+    }
+
+    #[tokio::test]
+    async fn identical_selected_fields_share_one_document_id() {
+        let server = MockServer::start_async().await;
+        let conf = Elasticsearch {
+            endpoint: server.base_url(),
+            batch: Some(10),
+            id_from_fields: Some(vec!["host".into(), "ts".into()]),
+            ..Default::default()
+        };
+        let mut sink = ElasticsearchSink::new(conf, "logs".into());
+        for msg in ["first", "retry"] {
+            let mut rec = DataRecord::default();
+            rec.append(DataField::from_chars("host", "web-1"));
+            rec.append(DataField::from_chars("ts", "2026-01-01T00:00:00Z"));
+            rec.append(DataField::from_chars("msg", msg));
+            sink.sink_record(&rec).await.expect("buffer ok");
+        }
+        let mut other = DataRecord::default();
+        other.append(DataField::from_chars("host", "web-2"));
+        other.append(DataField::from_chars("ts", "2026-01-01T00:00:00Z"));
+        sink.sink_record(&other).await.expect("buffer ok");
+
+        let ids = sink
+            .values
+            .iter()
+            .map(|(_, id, _, _)| id.clone().expect("hashed id"))
+            .collect::<Vec<_>>();
+        assert_eq!(ids[0], ids[1], "same selected values dedupe to one _id");
+        assert_ne!(ids[0], ids[2]);
+        assert_eq!(ids[0].len(), 64, "sha256 hex");
+
+        let action = format!("\"_id\":\"{}\"", ids[0]);
+        let bulk = server.mock(|when, then| {
+            when.method(PUT)
+                .path("/_bulk")
+                .body_contains(action.as_str());
+            then.status(200).body("{\"errors\":false,\"items\":[]}");
+        });
+        sink.stop().await.expect("flush ok");
+        bulk.assert_hits(1);
+    }
+
+    fn ingest_sink(endpoint: String) -> ElasticsearchSink {
+        let conf = Elasticsearch {
+            endpoint,
+            batch: Some(3),
+            ingest_id_field: Some("_ingest_id".into()),
+            ..Default::default()
+        };
+        ElasticsearchSink::new(conf, "logs".into()).with_name("es_main".into())
+    }
+
+    fn buffered_ingest_ids(sink: &ElasticsearchSink) -> Vec<String> {
+        sink.values
+            .iter()
+            .map(|(_, _, _, json)| {
+                let doc: serde_json::Value = serde_json::from_str(json).expect("json doc");
+                doc["_ingest_id"].as_str().expect("stamped").to_string()
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn ingest_ids_are_reconstructable_and_reproducible() {
+        let server = MockServer::start_async().await;
+        let first = server.mock(|when, then| {
+            when.method(PUT)
+                .path("/_bulk")
+                .header("x-opaque-id", "es_main-0")
+                .body_contains("es_main-0-2");
+            then.status(200).body("{\"errors\":false,\"items\":[]}");
+        });
+        let second = server.mock(|when, then| {
+            when.method(PUT)
+                .path("/_bulk")
+                .header("x-opaque-id", "es_main-1")
+                .body_contains("es_main-1-0");
+            then.status(200).body("{\"errors\":false,\"items\":[]}");
+        });
+
+        let mut sink = ingest_sink(server.base_url());
+        let mut replay = ingest_sink(server.base_url());
+        for i in 0..2 {
+            sink.sink_record(&big_record(i)).await.expect("buffer ok");
+            replay.sink_record(&big_record(i)).await.expect("buffer ok");
+        }
+        let ids = buffered_ingest_ids(&sink);
+        let expected = (0..2)
+            .map(|i| ingest_id("es_main", 0, i))
+            .collect::<Vec<_>>();
+        assert_eq!(ids, expected);
+        assert_ne!(ids[0], ids[1]);
+        // 同样的输入重新跑一遍得到相同的 id
+        assert_eq!(buffered_ingest_ids(&replay), ids);
+
+        // 第三条记录凑满批次触发 flush，之后的记录进入下一批
+        sink.sink_record(&big_record(2)).await.expect("flush ok");
+        first.assert_hits(1);
+        sink.sink_record(&big_record(3)).await.expect("buffer ok");
+        assert_eq!(buffered_ingest_ids(&sink), vec![ingest_id("es_main", 1, 0)]);
+        sink.stop().await.expect("flush ok");
+        second.assert_hits(1);
+    }
+}
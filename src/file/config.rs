@@ -0,0 +1,111 @@
+use chrono::NaiveDateTime;
+use educe::Educe;
+use serde::Deserialize;
+use serde::Serialize;
+
+/// 路径模板支持的日期占位符（本地时间）
+pub const PATH_TOKENS: [&str; 5] = ["yyyy", "MM", "dd", "HH", "mm"];
+
+/// 轮转出的历史文件的压缩方式。
+#[derive(Debug, Default, Deserialize, Serialize, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum FileCompression {
+    #[default]
+    None,
+    /// 轮转后压缩为 `<path>.N.gz`
+    Gzip,
+}
+
+impl FileCompression {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::Gzip => "gzip",
+        }
+    }
+
+    /// 历史文件的附加扩展名
+    pub fn suffix(&self) -> &'static str {
+        match self {
+            Self::None => "",
+            Self::Gzip => ".gz",
+        }
+    }
+}
+
+#[derive(Educe, Deserialize, Serialize, PartialEq, Clone)]
+#[educe(Debug, Default)]
+pub struct FileSinkConfig {
+    /// 输出路径模板；渲染结果变化（如跨天）时切换到新文件
+    #[educe(Default = "./data/out/sink-{yyyy}-{MM}-{dd}.log")]
+    pub path: String,
+    /// 记录的渲染格式，每条一行
+    #[educe(Default = "json")]
+    pub fmt: String,
+    /// 当前文件达到该字节数时轮转为 `<path>.1`；0 表示不按大小轮转
+    #[educe(Default = 104857600)]
+    pub rotate_size_bytes: u64,
+    /// 每个路径保留的历史文件数（至少 1），超出的最旧文件被删除
+    #[educe(Default = 5)]
+    pub max_files: usize,
+    #[serde(default)]
+    pub compression: FileCompression,
+}
+
+/// 校验路径模板：非空，占位符须为 [`PATH_TOKENS`] 之一。
+pub fn validate_path_template(template: &str) -> Result<(), String> {
+    if template.trim().is_empty() {
+        return Err("file.path must not be empty".into());
+    }
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let end = rest[start..]
+            .find('}')
+            .ok_or_else(|| format!("file.path has an unclosed '{{' in '{template}'"))?;
+        let token = &rest[start + 1..start + end];
+        if !PATH_TOKENS.contains(&token) {
+            return Err(format!("file.path has unknown token '{{{token}}}'"));
+        }
+        rest = &rest[start + end + 1..];
+    }
+    Ok(())
+}
+
+/// 按给定时间渲染路径模板。
+pub fn render_path(template: &str, at: NaiveDateTime) -> String {
+    let mut path = template.to_string();
+    for (token, pattern) in [
+        ("yyyy", "%Y"),
+        ("MM", "%m"),
+        ("dd", "%d"),
+        ("HH", "%H"),
+        ("mm", "%M"),
+    ] {
+        path = path.replace(&format!("{{{token}}}"), &at.format(pattern).to_string());
+    }
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn path_template_renders_date_tokens() {
+        let at = NaiveDate::from_ymd_opt(2024, 3, 9)
+            .unwrap()
+            .and_hms_opt(7, 5, 0)
+            .unwrap();
+        assert_eq!(
+            render_path("/var/log/wp/{yyyy}/{MM}/{dd}/out-{HH}{mm}.log", at),
+            "/var/log/wp/2024/03/09/out-0705.log"
+        );
+        assert_eq!(render_path("./out.log", at), "./out.log");
+
+        assert!(validate_path_template(&FileSinkConfig::default().path).is_ok());
+        assert!(validate_path_template("./out-{date}.log").is_err());
+        assert!(validate_path_template("./out-{yyyy.log").is_err());
+        assert!(validate_path_template(" ").is_err());
+    }
+}
@@ -0,0 +1,160 @@
+use async_trait::async_trait;
+use serde_json::{Value, json};
+use wp_connector_api::{
+    ConnectorDef, ConnectorScope, ParamMap, SinkBuildCtx, SinkDefProvider, SinkFactory, SinkHandle,
+    SinkReason, SinkResult, SinkSpec,
+};
+use wp_model_core::model::fmt_def::TextFmt;
+
+use super::config::{FileCompression, FileSinkConfig, validate_path_template};
+use super::sink::FileSink;
+use crate::common::enrich::{EnrichConf, EnrichSink};
+use crate::common::partition::PartitionTemplate;
+use crate::common::stats;
+use crate::common::transform::FieldTransforms;
+
+pub struct FileSinkFactory;
+
+#[async_trait]
+impl SinkFactory for FileSinkFactory {
+    fn kind(&self) -> &'static str {
+        "file"
+    }
+
+    fn validate_spec(&self, spec: &SinkSpec) -> SinkResult<()> {
+        build_conf(&spec.params)?;
+        PartitionTemplate::from_params(&spec.params, "file")?;
+        EnrichConf::from_params(&spec.params)?;
+        FieldTransforms::from_params(&spec.params)?;
+        Ok(())
+    }
+
+    async fn build(&self, spec: &SinkSpec, _ctx: &SinkBuildCtx) -> SinkResult<SinkHandle> {
+        let conf = build_conf(&spec.params)?;
+        let partition = PartitionTemplate::from_params(&spec.params, "file")?;
+        let sink = FileSink::new(&conf, TextFmt::from(conf.fmt.as_str()))
+            .with_partition(partition)
+            .with_stats(stats::register(&spec.name, self.kind()));
+        let enrich = EnrichConf::from_params(&spec.params)?;
+        let transforms = FieldTransforms::from_params(&spec.params)?;
+        let sink = EnrichSink::new(sink, enrich).with_transforms(transforms);
+        Ok(SinkHandle::new(Box::new(sink)))
+    }
+}
+
+impl SinkDefProvider for FileSinkFactory {
+    fn sink_def(&self) -> ConnectorDef {
+        ConnectorDef {
+            id: "file_sink".into(),
+            kind: self.kind().into(),
+            scope: ConnectorScope::Sink,
+            allow_override: vec![
+                "path",
+                "fmt",
+                "rotate_size_bytes",
+                "max_files",
+                "compression",
+                "partition_template",
+                "partition_time_field",
+                "enrich",
+                "enrich_overwrite",
+                "field_transforms",
+            ]
+            .into_iter()
+            .map(str::to_string)
+            .collect(),
+            default_params: file_defaults(),
+            origin: Some("wp-connectors:file_sink".into()),
+        }
+    }
+}
+
+fn file_defaults() -> ParamMap {
+    let defaults = FileSinkConfig::default();
+    let mut params = ParamMap::new();
+    params.insert("path".into(), json!(defaults.path));
+    params.insert("fmt".into(), json!(defaults.fmt));
+    params.insert(
+        "rotate_size_bytes".into(),
+        json!(defaults.rotate_size_bytes),
+    );
+    params.insert("max_files".into(), json!(defaults.max_files));
+    params.insert("compression".into(), json!(defaults.compression.as_str()));
+    params
+}
+
+fn build_conf(params: &ParamMap) -> SinkResult<FileSinkConfig> {
+    let mut conf = FileSinkConfig::default();
+    if let Some(v) = params.get("path").filter(|v| !v.is_null()) {
+        conf.path = v
+            .as_str()
+            .ok_or_else(|| SinkReason::sink("file.path must be a string"))?
+            .trim()
+            .to_string();
+    }
+    validate_path_template(&conf.path).map_err(SinkReason::sink)?;
+    if let Some(s) = params.get("fmt").and_then(|v| v.as_str()) {
+        conf.fmt = s.to_string();
+    }
+    if let Some(n) = opt_u64(params, "rotate_size_bytes")? {
+        conf.rotate_size_bytes = n;
+    }
+    if let Some(n) = opt_u64(params, "max_files")? {
+        if n == 0 {
+            return Err(SinkReason::sink("file.max_files must be > 0").into());
+        }
+        conf.max_files = n as usize;
+    }
+    if let Some(v) = params.get("compression").filter(|v| !v.is_null()) {
+        conf.compression = match v.as_str() {
+            Some("none") => FileCompression::None,
+            Some("gzip") => FileCompression::Gzip,
+            _ => {
+                return Err(SinkReason::sink("file.compression must be one of: none, gzip").into());
+            }
+        };
+    }
+    Ok(conf)
+}
+
+/// 可选的非负整数参数。
+fn opt_u64(params: &ParamMap, key: &str) -> SinkResult<Option<u64>> {
+    match params.get(key) {
+        None | Some(Value::Null) => Ok(None),
+        Some(v) => v.as_u64().map(Some).ok_or_else(|| {
+            SinkReason::sink(format!("file.{key} must be a non-negative integer")).into()
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn build_conf_validates_params() {
+        let conf = build_conf(&file_defaults()).expect("defaults");
+        assert_eq!(conf, FileSinkConfig::default());
+
+        let mut p = file_defaults();
+        p.insert("path".into(), json!("/tmp/wp/{yyyy}{MM}{dd}.log"));
+        p.insert("compression".into(), json!("gzip"));
+        p.insert("max_files".into(), json!(1));
+        let conf = build_conf(&p).expect("gzip");
+        assert_eq!(conf.compression, FileCompression::Gzip);
+        assert_eq!(conf.max_files, 1);
+
+        p.insert("max_files".into(), json!(0));
+        assert!(build_conf(&p).is_err());
+        p.insert("max_files".into(), json!(1));
+
+        p.insert("compression".into(), json!("zstd"));
+        assert!(build_conf(&p).is_err());
+        p.insert("compression".into(), json!("none"));
+        p.insert("rotate_size_bytes".into(), json!(-1));
+        assert!(build_conf(&p).is_err());
+        p.insert("rotate_size_bytes".into(), json!(0));
+        p.insert("path".into(), json!("/tmp/{host}.log"));
+        assert!(build_conf(&p).is_err());
+    }
+}
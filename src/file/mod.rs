@@ -0,0 +1,14 @@
+//! 本地文件 sink：按行写入格式化记录，支持按大小/日期轮转与 gzip 压缩，便于离线环境排查。
+//!
+//! 模块划分：
+//! - config：FileSinkConfig 与路径模板
+//! - sink：FileSink（打开/轮转/压缩）
+//! - factory：Sink 工厂
+
+pub mod config;
+mod factory;
+mod sink;
+
+pub use config::{FileCompression, FileSinkConfig};
+pub use factory::FileSinkFactory;
+pub use sink::FileSink;
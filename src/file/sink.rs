@@ -0,0 +1,671 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, PoisonError};
+
+use async_trait::async_trait;
+use chrono::{Local, NaiveDateTime, Utc};
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use wp_connector_api::{
+    AsyncCtrl, AsyncRawDataSink, AsyncRecordSink, SinkError, SinkReason, SinkResult,
+};
+use wp_data_fmt::{DataFormat, FormatType};
+use wp_model_core::model::{DataRecord, fmt_def::TextFmt};
+
+use crate::common::csv::CsvColumns;
+use crate::common::partition::PartitionTemplate;
+use crate::common::stats::StatsHandle;
+use crate::file::config::{FileCompression, FileSinkConfig, render_path};
+
+/// 待写入的一行；`dir` 为分区目录，位于路径模板所在目录之下。
+struct Line {
+    dir: Option<String>,
+    bytes: Vec<u8>,
+}
+
+struct OpenFile {
+    path: PathBuf,
+    writer: BufWriter<File>,
+    size: u64,
+    /// 文件首行的 CSV 表头；非 CSV 写入或空文件时为 `None`
+    header: Option<String>,
+}
+
+/// 当前文件与轮转状态；文件读写、轮转与压缩均为同步操作，由 [`FileSink`] 放到阻塞线程池执行。
+struct FileWriter {
+    template: String,
+    rotate_size: u64,
+    max_files: usize,
+    compression: FileCompression,
+    current: Option<OpenFile>,
+}
+
+impl FileWriter {
+    /// 逐行写入后 flush；`header` 为 CSV 表头，写入每个新文件的开头。
+    fn write_lines(
+        &mut self,
+        lines: &[Line],
+        header: Option<&str>,
+        now: NaiveDateTime,
+    ) -> io::Result<()> {
+        for line in lines {
+            self.write_line_at(&line.bytes, line.dir.as_deref(), header, now)?;
+        }
+        self.flush()
+    }
+
+    /// 一行的最终路径：路径模板渲染结果，配置分区时位于分区目录下。
+    fn target_path(&self, dir: Option<&str>, now: NaiveDateTime) -> PathBuf {
+        let path = PathBuf::from(render_path(&self.template, now));
+        match dir {
+            Some(dir) => partition_path(&path, dir),
+            None => path,
+        }
+    }
+
+    /// 目标文件已有的 CSV 表头；文件不存在或为空时为 `None`。
+    fn existing_header(&self, dir: Option<&str>, now: NaiveDateTime) -> io::Result<Option<String>> {
+        let path = self.target_path(dir, now);
+        if let Some(file) = self.current.as_ref().filter(|f| f.path == path) {
+            return Ok(file.header.clone());
+        }
+        match File::open(&path) {
+            Ok(file) => read_header(file),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// 写入一行；轮转按最终路径进行，各分区目录下的文件各自轮转。
+    /// 文件已有内容而表头与 `header` 不同（CSV 列增加）时：开启轮转则轮转，新文件以新表头开头；
+    /// 未开启轮转（`rotate_size_bytes = 0`）时不轮转，在当前文件中写入新表头开始新的一段。
+    fn write_line_at(
+        &mut self,
+        line: &[u8],
+        dir: Option<&str>,
+        header: Option<&str>,
+        now: NaiveDateTime,
+    ) -> io::Result<()> {
+        let path = self.target_path(dir, now);
+        let newline = !line.ends_with(b"\n");
+        let len = line.len() as u64 + u64::from(newline);
+        if self.current.as_ref().is_some_and(|f| f.path != path) {
+            self.close()?;
+        }
+        let file = match self.current.take() {
+            Some(file) => file,
+            None => open_append(path.clone(), header.is_some())?,
+        };
+        let full = self.rotate_size > 0 && file.size + len > self.rotate_size;
+        let stale = self.rotate_size > 0 && header.is_some() && file.header.as_deref() != header;
+        let file = if file.size > 0 && (full || stale) {
+            self.current = Some(file);
+            self.close()?;
+            self.rotate(&path)?;
+            open_append(path, false)?
+        } else {
+            file
+        };
+        let file = self.current.insert(file);
+        if let Some(header) = header.filter(|h| file.header.as_deref() != Some(*h)) {
+            file.writer.write_all(header.as_bytes())?;
+            file.writer.write_all(b"\n")?;
+            file.size += header.len() as u64 + 1;
+            file.header = Some(header.to_string());
+        }
+        file.writer.write_all(line)?;
+        if newline {
+            file.writer.write_all(b"\n")?;
+        }
+        file.size += len;
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match &mut self.current {
+            Some(file) => file.writer.flush(),
+            None => Ok(()),
+        }
+    }
+
+    fn close(&mut self) -> io::Result<()> {
+        match self.current.take() {
+            Some(mut file) => file.writer.flush(),
+            None => Ok(()),
+        }
+    }
+
+    /// `<path>.N` 依次后移一位并删除超出 `max_files` 的最旧文件，当前文件改名为 `<path>.1`。
+    fn rotate(&self, path: &Path) -> io::Result<()> {
+        let suffix = self.compression.suffix();
+        remove_if_exists(&backup_path(path, self.max_files, suffix))?;
+        for idx in (1..self.max_files).rev() {
+            let from = backup_path(path, idx, suffix);
+            if from.exists() {
+                fs::rename(&from, backup_path(path, idx + 1, suffix))?;
+            }
+        }
+        let first = backup_path(path, 1, "");
+        fs::rename(path, &first)?;
+        if self.compression == FileCompression::Gzip {
+            gzip_file(&first, &backup_path(path, 1, suffix))?;
+        }
+        Ok(())
+    }
+}
+
+/// 将记录逐行写入本地文件：路径模板渲染结果变化时切换文件，超过
+/// `rotate_size_bytes` 时轮转为 `<path>.1`（依次后移，保留 `max_files` 个）。
+/// 每次写入调用结束时 flush，`stop` 时关闭当前文件。
+///
+/// 配置 `partition_template` 时，每条记录写入路径模板所在目录下的分区目录
+/// （如 `dt=2024-03-09/hour=07/`），目录按需创建，轮转在各分区内独立进行；
+/// 原始数据没有字段，按当前时间与默认分区名渲染。
+///
+/// `fmt = csv` 时列序取自首批记录的字段并集（目标文件已存在时先取其表头，重启后追加的行
+/// 与已有内容对齐），之后每批新出现的字段按出现顺序追加到列尾，批内各行按并入后的列输出。
+/// 每个新文件（含轮转、切换后）先写表头；列增加时开启轮转则当前文件轮转、新表头写入新文件，
+/// 否则在当前文件中写入新表头，其后各行按新列输出，已有数据不会因列变化被轮转删除。
+pub struct FileSink {
+    fmt: TextFmt,
+    csv: Option<CsvColumns>,
+    partition: Option<PartitionTemplate>,
+    writer: Arc<Mutex<FileWriter>>,
+    /// `/stats` 自省状态（最近写入与错误）
+    stats: StatsHandle,
+}
+
+impl FileSink {
+    pub fn new(conf: &FileSinkConfig, fmt: TextFmt) -> Self {
+        Self {
+            csv: (fmt == TextFmt::Csv).then(CsvColumns::default),
+            fmt,
+            partition: None,
+            writer: Arc::new(Mutex::new(FileWriter {
+                template: conf.path.clone(),
+                rotate_size: conf.rotate_size_bytes,
+                max_files: conf.max_files,
+                compression: conf.compression,
+                current: None,
+            })),
+            stats: StatsHandle::detached(&conf.path, "file"),
+        }
+    }
+
+    pub fn with_partition(mut self, partition: Option<PartitionTemplate>) -> Self {
+        self.partition = partition;
+        self
+    }
+
+    /// 使用外部登记的状态句柄（见 [`crate::common::stats::register`]），
+    /// 以路径模板作为指标的写入目标。
+    pub fn with_stats(mut self, stats: StatsHandle) -> Self {
+        let template = self
+            .writer
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .template
+            .clone();
+        self.stats = stats.with_destination(template);
+        self
+    }
+
+    /// 首次写 CSV 时以目标文件已有的表头确定列序（如重启后继续追加）。
+    async fn seed_csv(&mut self, first: Option<&DataRecord>, now: NaiveDateTime) -> SinkResult<()> {
+        let Some(first) = first.filter(|_| self.csv.as_ref().is_some_and(CsvColumns::is_empty))
+        else {
+            return Ok(());
+        };
+        let dir = self.partition_dir(first);
+        let header = self
+            .blocking(move |writer| writer.existing_header(dir.as_deref(), now))
+            .await?;
+        if let Some(header) = header {
+            self.csv = Some(CsvColumns::from_header(&header));
+        }
+        Ok(())
+    }
+
+    async fn write_lines(&mut self, lines: Vec<Line>, now: NaiveDateTime) -> SinkResult<()> {
+        let header = self
+            .csv
+            .as_ref()
+            .filter(|c| !c.is_empty())
+            .map(CsvColumns::header);
+        let result = self
+            .blocking(move |writer| writer.write_lines(&lines, header.as_deref(), now))
+            .await;
+        self.stats.record_flush(&result);
+        result
+    }
+
+    /// 在阻塞线程池中操作文件，避免同步 I/O 与 gzip 压缩占住异步工作线程。
+    async fn blocking<F, T>(&self, op: F) -> SinkResult<T>
+    where
+        F: FnOnce(&mut FileWriter) -> io::Result<T> + Send + 'static,
+        T: Send + 'static,
+    {
+        let writer = self.writer.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut guard = writer.lock().unwrap_or_else(PoisonError::into_inner);
+            op(&mut *guard)
+        })
+        .await
+        .map_err(|e| SinkError::from(SinkReason::sink(format!("file sink join error: {e}"))))?
+        .map_err(file_error)
+    }
+
+    /// 记录所属的分区目录；与 S3 sink 一致，缺少记录时间时按当前 UTC 时间分区。
+    fn partition_dir(&self, record: &DataRecord) -> Option<String> {
+        self.partition
+            .as_ref()
+            .map(|p| p.render(record, Utc::now().naive_utc()))
+    }
+
+    /// 按 `fmt` 渲染记录并确定分区目录；CSV 列先并入本批记录的全部字段。
+    fn render_records<'a>(
+        &mut self,
+        records: impl IntoIterator<Item = &'a DataRecord> + Clone,
+    ) -> Vec<Line> {
+        if let Some(columns) = &mut self.csv {
+            records.clone().into_iter().for_each(|r| columns.extend(r));
+        }
+        let fmt = FormatType::from(&self.fmt);
+        records
+            .into_iter()
+            .map(|r| Line {
+                dir: self.partition_dir(r),
+                bytes: match &self.csv {
+                    Some(columns) => columns.row(r).into_bytes(),
+                    None => fmt.format_record(r).into_bytes(),
+                },
+            })
+            .collect()
+    }
+
+    async fn write_raw<'a>(&mut self, data: impl IntoIterator<Item = &'a [u8]>) -> SinkResult<()> {
+        let now = Local::now().naive_local();
+        let dir = self.partition_dir(&DataRecord::default());
+        let lines = data
+            .into_iter()
+            .map(|bytes| Line {
+                dir: dir.clone(),
+                bytes: bytes.to_vec(),
+            })
+            .collect();
+        self.write_lines(lines, now).await
+    }
+}
+
+/// 以追加方式打开文件；`read_header` 时读出已有内容的首行作为表头。
+fn open_append(path: PathBuf, read_header: bool) -> io::Result<OpenFile> {
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)?;
+    }
+    let file = OpenOptions::new().create(true).append(true).open(&path)?;
+    let size = file.metadata()?.len();
+    let header = if read_header && size > 0 {
+        read_header(File::open(&path)?)?
+    } else {
+        None
+    };
+    Ok(OpenFile {
+        path,
+        writer: BufWriter::new(file),
+        size,
+        header,
+    })
+}
+
+/// 读出首行作为 CSV 表头；空文件为 `None`。
+fn read_header(file: File) -> io::Result<Option<String>> {
+    let mut line = String::new();
+    BufReader::new(file).read_line(&mut line)?;
+    let line = line.trim_end_matches(['\r', '\n']);
+    Ok((!line.is_empty()).then(|| line.to_string()))
+}
+
+/// 分区文件路径：`<模板目录>/<dir>/<文件名>`。
+fn partition_path(path: &Path, dir: &str) -> PathBuf {
+    let parent = path.parent().unwrap_or_else(|| Path::new(""));
+    match path.file_name() {
+        Some(name) => parent.join(dir).join(name),
+        None => parent.join(dir),
+    }
+}
+
+fn backup_path(path: &Path, idx: usize, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(format!(".{idx}{suffix}"));
+    PathBuf::from(name)
+}
+
+fn remove_if_exists(path: &Path) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+        other => other,
+    }
+}
+
+/// 压缩为 `dst` 后删除 `src`。
+fn gzip_file(src: &Path, dst: &Path) -> io::Result<()> {
+    let mut input = File::open(src)?;
+    let mut encoder = GzEncoder::new(File::create(dst)?, Compression::default());
+    io::copy(&mut input, &mut encoder)?;
+    encoder.finish()?;
+    fs::remove_file(src)
+}
+
+fn file_error(err: io::Error) -> SinkError {
+    SinkError::from(SinkReason::sink(format!("file sink write failed: {err}")))
+}
+
+#[async_trait]
+impl AsyncCtrl for FileSink {
+    async fn stop(&mut self) -> SinkResult<()> {
+        self.blocking(FileWriter::close).await
+    }
+
+    async fn reconnect(&mut self) -> SinkResult<()> {
+        self.blocking(FileWriter::close).await
+    }
+}
+
+#[async_trait]
+impl AsyncRecordSink for FileSink {
+    async fn sink_record(&mut self, data: &DataRecord) -> SinkResult<()> {
+        let now = Local::now().naive_local();
+        self.seed_csv(Some(data), now).await?;
+        let lines = self.render_records([data]);
+        self.write_lines(lines, now).await
+    }
+
+    async fn sink_records(&mut self, data: Vec<Arc<DataRecord>>) -> SinkResult<()> {
+        let now = Local::now().naive_local();
+        self.seed_csv(data.first().map(|r| r.as_ref()), now).await?;
+        let lines = self.render_records(data.iter().map(|r| r.as_ref()));
+        self.write_lines(lines, now).await
+    }
+}
+
+#[async_trait]
+impl AsyncRawDataSink for FileSink {
+    async fn sink_str(&mut self, data: &str) -> SinkResult<()> {
+        self.write_raw([data.as_bytes()]).await
+    }
+
+    async fn sink_bytes(&mut self, data: &[u8]) -> SinkResult<()> {
+        self.write_raw([data]).await
+    }
+
+    async fn sink_str_batch(&mut self, data: Vec<&str>) -> SinkResult<()> {
+        self.write_raw(data.into_iter().map(str::as_bytes)).await
+    }
+
+    async fn sink_bytes_batch(&mut self, data: Vec<&[u8]>) -> SinkResult<()> {
+        self.write_raw(data).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("wp_file_sink_{name}_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn sink(dir: &Path, template: &str, rotate_size: u64, max_files: usize) -> FileSink {
+        let conf = FileSinkConfig {
+            path: dir.join(template).to_string_lossy().into_owned(),
+            rotate_size_bytes: rotate_size,
+            max_files,
+            ..Default::default()
+        };
+        FileSink::new(&conf, TextFmt::Json)
+    }
+
+    fn writer(dir: &Path, template: &str, rotate_size: u64, max_files: usize) -> FileWriter {
+        FileWriter {
+            template: dir.join(template).to_string_lossy().into_owned(),
+            rotate_size,
+            max_files,
+            compression: FileCompression::None,
+            current: None,
+        }
+    }
+
+    fn at(day: u32, hour: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 3, day)
+            .unwrap()
+            .and_hms_opt(hour, 0, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn rotates_by_size_and_keeps_max_files() {
+        let dir = temp_dir("size");
+        let mut writer = writer(&dir, "out.log", 10, 2);
+        for line in ["aaaa", "bbbb", "cccc", "dddd", "eeee"] {
+            writer
+                .write_line_at(line.as_bytes(), None, None, at(9, 0))
+                .unwrap();
+        }
+        writer.close().unwrap();
+
+        let read = |name: &str| fs::read_to_string(dir.join(name)).unwrap();
+        assert_eq!(read("out.log"), "eeee\n");
+        assert_eq!(read("out.log.1"), "cccc\ndddd\n");
+        assert_eq!(read("out.log.2"), "aaaa\nbbbb\n");
+        assert!(
+            !dir.join("out.log.3").exists(),
+            "only max_files backups kept"
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn switches_file_when_date_changes() {
+        let dir = temp_dir("date");
+        let mut writer = writer(&dir, "{yyyy}{MM}{dd}/out-{HH}.log", 0, 2);
+        writer
+            .write_line_at(b"first", None, None, at(9, 23))
+            .unwrap();
+        writer
+            .write_line_at(b"second\n", None, None, at(10, 0))
+            .unwrap();
+        writer.close().unwrap();
+
+        assert_eq!(
+            fs::read_to_string(dir.join("20240309/out-23.log")).unwrap(),
+            "first\n"
+        );
+        assert_eq!(
+            fs::read_to_string(dir.join("20240310/out-00.log")).unwrap(),
+            "second\n"
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn gzip_compresses_rotated_files() {
+        let dir = temp_dir("gzip");
+        let mut writer = writer(&dir, "out.log", 4, 3);
+        writer.compression = FileCompression::Gzip;
+        writer.write_line_at(b"abc", None, None, at(9, 0)).unwrap();
+        writer.write_line_at(b"def", None, None, at(9, 0)).unwrap();
+        writer.close().unwrap();
+
+        assert!(!dir.join("out.log.1").exists());
+        let mut text = String::new();
+        GzDecoder::new(File::open(dir.join("out.log.1.gz")).unwrap())
+            .read_to_string(&mut text)
+            .unwrap();
+        assert_eq!(text, "abc\n");
+        assert_eq!(fs::read_to_string(dir.join("out.log")).unwrap(), "def\n");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    fn csv_sink(dir: &Path, rotate_size: u64, max_files: usize) -> FileSink {
+        let mut sink = sink(dir, "out.csv", rotate_size, max_files);
+        sink.fmt = TextFmt::Csv;
+        sink.csv = Some(CsvColumns::default());
+        sink
+    }
+
+    fn csv_record(fields: &[(&str, &str)]) -> Arc<DataRecord> {
+        let mut record = DataRecord::default();
+        for (name, value) in fields {
+            record.append(wp_model_core::model::DataField::from_chars(*name, *value));
+        }
+        Arc::new(record)
+    }
+
+    #[tokio::test]
+    async fn csv_writes_header_per_file() {
+        let dir = temp_dir("csv");
+        let mut sink = csv_sink(&dir, 30, 2);
+        sink.sink_records(vec![
+            csv_record(&[("host", "a"), ("msg", "x,y")]),
+            csv_record(&[("host", "b"), ("code", "1")]),
+        ])
+        .await
+        .unwrap();
+        // 超过轮转大小后新文件重新写表头
+        sink.sink_record(&csv_record(&[("host", "c"), ("code", "3")]))
+            .await
+            .unwrap();
+        sink.stop().await.unwrap();
+
+        assert_eq!(
+            fs::read_to_string(dir.join("out.csv.1")).unwrap(),
+            "host,msg,code\na,\"x,y\",\nb,,1\n"
+        );
+        assert_eq!(
+            fs::read_to_string(dir.join("out.csv")).unwrap(),
+            "host,msg,code\nc,,3\n"
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn csv_new_columns_write_a_header_segment_without_rotation() {
+        let dir = temp_dir("csv_union");
+        let mut sink = csv_sink(&dir, 0, 1);
+        sink.sink_records(vec![
+            csv_record(&[("host", "a")]),
+            csv_record(&[("host", "b"), ("code", "1")]),
+        ])
+        .await
+        .unwrap();
+        // 列未增加时追加到同一文件
+        sink.sink_record(&csv_record(&[("host", "c")]))
+            .await
+            .unwrap();
+        // 批内新字段并入列尾；未开启轮转时在同一文件中写入新表头
+        sink.sink_records(vec![
+            csv_record(&[("code", "2")]),
+            csv_record(&[("extra", "z")]),
+        ])
+        .await
+        .unwrap();
+        sink.stop().await.unwrap();
+
+        let read = |name: &str| fs::read_to_string(dir.join(name)).unwrap();
+        let written = "host,code\na,\nb,1\nc,\nhost,code,extra\n,2,\n,,z\n";
+        assert_eq!(read("out.csv"), written);
+        assert!(!dir.join("out.csv.1").exists(), "rotation disabled");
+
+        // 重启后列序取自已有文件的表头，追加的行与之对齐
+        let mut sink = csv_sink(&dir, 0, 1);
+        sink.sink_record(&csv_record(&[("host", "d")]))
+            .await
+            .unwrap();
+        sink.stop().await.unwrap();
+        assert_eq!(read("out.csv"), format!("{written}d,,\n"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn csv_new_columns_rotate_when_rotation_enabled() {
+        let dir = temp_dir("csv_rotate");
+        let mut sink = csv_sink(&dir, 1 << 20, 2);
+        sink.sink_record(&csv_record(&[("host", "a")]))
+            .await
+            .unwrap();
+        sink.sink_record(&csv_record(&[("host", "b"), ("code", "1")]))
+            .await
+            .unwrap();
+        sink.stop().await.unwrap();
+
+        let read = |name: &str| fs::read_to_string(dir.join(name)).unwrap();
+        assert_eq!(read("out.csv.1"), "host\na\n");
+        assert_eq!(read("out.csv"), "host,code\nb,1\n");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn partition_template_splits_records_by_hour() {
+        let dir = temp_dir("partition");
+        let template =
+            PartitionTemplate::parse("{table}/dt=%Y-%m-%d/hour=%H", Some("ts".into())).unwrap();
+        let mut sink = sink(&dir, "part.log", 0, 1).with_partition(Some(template));
+        let record = |msg: &str, ts: &str| {
+            let mut record = DataRecord::default();
+            record.append(wp_model_core::model::DataField::from_chars(
+                "table", "events",
+            ));
+            record.append(wp_model_core::model::DataField::from_chars("ts", ts));
+            record.append(wp_model_core::model::DataField::from_chars("msg", msg));
+            Arc::new(record)
+        };
+        sink.sink_records(vec![
+            record("a", "2024-03-09T07:10:00Z"),
+            record("b", "2024-03-09T08:00:00Z"),
+            record("c", "2024-03-09T07:59:59Z"),
+        ])
+        .await
+        .unwrap();
+        sink.stop().await.unwrap();
+
+        let read = |hour: &str| {
+            let path = dir.join(format!("events/dt=2024-03-09/hour={hour}/part.log"));
+            let text = fs::read_to_string(path).unwrap();
+            text.lines()
+                .map(|line| {
+                    let value: serde_json::Value = serde_json::from_str(line).unwrap();
+                    value["msg"].as_str().unwrap().to_string()
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(read("07"), ["a", "c"]);
+        assert_eq!(read("08"), ["b"]);
+        assert!(!dir.join("part.log").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn stop_flushes_records() {
+        let dir = temp_dir("stop");
+        let mut sink = sink(&dir, "out.log", 0, 1);
+        let mut record = DataRecord::default();
+        record.append(wp_model_core::model::DataField::from_chars("msg", "hello"));
+        sink.sink_record(&record).await.unwrap();
+        sink.sink_str_batch(vec!["raw-1", "raw-2"]).await.unwrap();
+        sink.stop().await.unwrap();
+        assert!(sink.writer.lock().unwrap().current.is_none());
+
+        let text = fs::read_to_string(dir.join("out.log")).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].contains("hello"), "{text}");
+        assert_eq!(&lines[1..], ["raw-1", "raw-2"]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
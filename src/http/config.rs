@@ -0,0 +1,159 @@
+use std::collections::BTreeMap;
+
+use educe::Educe;
+use serde::Deserialize;
+use serde::Serialize;
+
+/// 批量请求体的组装方式。
+#[derive(Debug, Default, Deserialize, Serialize, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum HttpBodyFormat {
+    /// 每条一行，`Content-Type: application/x-ndjson`
+    #[default]
+    Ndjson,
+    /// `[item,item,...]`，`Content-Type: application/json`
+    JsonArray,
+}
+
+impl HttpBodyFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Ndjson => "ndjson",
+            Self::JsonArray => "json_array",
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Ndjson => "application/x-ndjson",
+            Self::JsonArray => "application/json",
+        }
+    }
+
+    /// 将各条目组装为请求体。
+    pub fn assemble(&self, items: &[String]) -> String {
+        match self {
+            Self::Ndjson => {
+                let mut body = items.join("\n");
+                body.push('\n');
+                body
+            }
+            Self::JsonArray => format!("[{}]", items.join(",")),
+        }
+    }
+}
+
+#[derive(Educe, Deserialize, Serialize, PartialEq, Clone)]
+#[educe(Debug, Default)]
+pub struct HttpSinkConfig {
+    pub url: String,
+    /// `POST`、`PUT` 或 `PATCH`
+    #[educe(Default = "POST")]
+    pub method: String,
+    /// 附加请求头；可覆盖默认的 `Content-Type`
+    #[educe(Debug(ignore))]
+    pub headers: BTreeMap<String, String>,
+    /// 每条记录的请求体模板，见 [`BodyTemplate`]；未配置时为记录的 JSON
+    pub body_template: Option<String>,
+    /// 累积到该条数时合并为一个请求发送
+    #[educe(Default = 1)]
+    pub batch: usize,
+    #[serde(default)]
+    pub format: HttpBodyFormat,
+    #[educe(Default = 5000)]
+    pub timeout_ms: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum TemplatePart {
+    Text(String),
+    Field(String),
+    Record,
+}
+
+/// 请求体模板：`{{name}}` 替换为字段值（按 JSON 字符串内容转义，不含引号，缺失时为空），
+/// `{{_record}}` 替换为整条记录的 JSON 对象。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BodyTemplate {
+    parts: Vec<TemplatePart>,
+}
+
+impl BodyTemplate {
+    pub fn parse(template: &str) -> Result<Self, String> {
+        let mut parts = Vec::new();
+        let mut rest = template;
+        while let Some(start) = rest.find("{{") {
+            if start > 0 {
+                parts.push(TemplatePart::Text(rest[..start].to_string()));
+            }
+            let end = rest[start..].find("}}").ok_or_else(|| {
+                format!("http.body_template has an unclosed '{{{{' in '{template}'")
+            })?;
+            let name = rest[start + 2..start + end].trim();
+            if name.is_empty() {
+                return Err("http.body_template has an empty placeholder".into());
+            }
+            parts.push(if name == "_record" {
+                TemplatePart::Record
+            } else {
+                TemplatePart::Field(name.to_string())
+            });
+            rest = &rest[start + end + 2..];
+        }
+        if !rest.is_empty() {
+            parts.push(TemplatePart::Text(rest.to_string()));
+        }
+        Ok(Self { parts })
+    }
+
+    /// `field` 按名取字段值，`record` 为整条记录的 JSON。
+    pub fn render(&self, field: impl Fn(&str) -> Option<String>, record: &str) -> String {
+        let mut out = String::new();
+        for part in &self.parts {
+            match part {
+                TemplatePart::Text(text) => out.push_str(text),
+                TemplatePart::Record => out.push_str(record),
+                TemplatePart::Field(name) => {
+                    let value = field(name).unwrap_or_default();
+                    let quoted = serde_json::Value::String(value).to_string();
+                    out.push_str(&quoted[1..quoted.len() - 1]);
+                }
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn template_escapes_fields_and_embeds_record() {
+        let tpl =
+            BodyTemplate::parse(r#"{"text":"{{ msg }} on {{host}}","raw":{{_record}}}"#).unwrap();
+        let body = tpl.render(
+            |name| (name == "msg").then(|| "say \"hi\"\n".to_string()),
+            r#"{"a":1}"#,
+        );
+        assert_eq!(body, r#"{"text":"say \"hi\"\n on ","raw":{"a":1}}"#);
+        let value: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(value["text"], "say \"hi\"\n on ");
+
+        assert!(BodyTemplate::parse("{{msg").is_err());
+        assert!(BodyTemplate::parse("{{ }}").is_err());
+    }
+
+    #[test]
+    fn body_formats_assemble_items() {
+        let items = vec![r#"{"a":1}"#.to_string(), r#"{"a":2}"#.to_string()];
+        assert_eq!(
+            HttpBodyFormat::Ndjson.assemble(&items),
+            "{\"a\":1}\n{\"a\":2}\n"
+        );
+        assert_eq!(
+            HttpBodyFormat::JsonArray.assemble(&items),
+            r#"[{"a":1},{"a":2}]"#
+        );
+    }
+}
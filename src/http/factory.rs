@@ -0,0 +1,234 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use reqwest::Method;
+use serde_json::{Value, json};
+use wp_connector_api::{
+    ConnectorDef, ConnectorScope, ParamMap, SinkBuildCtx, SinkDefProvider, SinkError, SinkFactory,
+    SinkHandle, SinkReason, SinkResult, SinkSpec,
+};
+
+use super::config::{BodyTemplate, HttpBodyFormat, HttpSinkConfig};
+use super::sink::{HttpSink, header_map};
+use crate::common::enrich::{EnrichConf, EnrichSink};
+use crate::common::retry::{self, RetryingSink};
+use crate::common::secret;
+use crate::common::stats;
+use crate::common::trace_context::TraceContext;
+use crate::common::transform::FieldTransforms;
+
+pub struct HttpSinkFactory;
+
+#[async_trait]
+impl SinkFactory for HttpSinkFactory {
+    fn kind(&self) -> &'static str {
+        "http"
+    }
+
+    fn validate_spec(&self, spec: &SinkSpec) -> SinkResult<()> {
+        let spec = &secret::resolve_sink_spec(spec)?;
+        let conf = build_conf(&spec.params)?;
+        header_map(&conf.headers).map_err(SinkReason::sink)?;
+        EnrichConf::from_params(&spec.params)?;
+        retry::validate_params(&spec.params)?;
+        FieldTransforms::from_params(&spec.params)?;
+        TraceContext::from_params(&spec.params)?;
+        Ok(())
+    }
+
+    async fn build(&self, spec: &SinkSpec, _ctx: &SinkBuildCtx) -> SinkResult<SinkHandle> {
+        let spec = &secret::resolve_sink_spec(spec)?;
+        let conf = build_conf(&spec.params)?;
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_millis(conf.timeout_ms))
+            .build()
+            .map_err(|err| {
+                SinkError::from(SinkReason::sink(format!("build http client failed: {err}")))
+            })?;
+        let method = parse_method(&conf.method)?;
+        let template = conf
+            .body_template
+            .as_deref()
+            .map(BodyTemplate::parse)
+            .transpose()
+            .map_err(SinkReason::sink)?;
+        let sink = HttpSink::new(client, conf.url.clone(), method)
+            .with_headers(header_map(&conf.headers).map_err(SinkReason::sink)?)
+            .with_template(template)
+            .with_format(conf.format)
+            .with_batch(conf.batch)
+            .with_trace_context(TraceContext::from_params(&spec.params)?)
+            .with_stats(stats::register(&spec.name, self.kind()));
+        let enrich = EnrichConf::from_params(&spec.params)?;
+        let transforms = FieldTransforms::from_params(&spec.params)?;
+        let sink = RetryingSink::from_spec(sink, spec).await?;
+        let sink = EnrichSink::new(sink, enrich).with_transforms(transforms);
+        Ok(SinkHandle::new(Box::new(sink)))
+    }
+}
+
+impl SinkDefProvider for HttpSinkFactory {
+    fn sink_def(&self) -> ConnectorDef {
+        ConnectorDef {
+            id: "http_sink".into(),
+            kind: self.kind().into(),
+            scope: ConnectorScope::Sink,
+            allow_override: vec![
+                "url",
+                "method",
+                "headers",
+                "body_template",
+                "batch",
+                "format",
+                "timeout_ms",
+                "trace_context",
+                "trace_id_field",
+                "tracestate",
+                "enrich",
+                "enrich_overwrite",
+                "field_transforms",
+                "retry",
+                "write_deadline_ms",
+                "quarantine",
+                "secret_ref",
+            ]
+            .into_iter()
+            .map(str::to_string)
+            .collect(),
+            default_params: http_defaults(),
+            origin: Some("wp-connectors:http_sink".into()),
+        }
+    }
+}
+
+fn http_defaults() -> ParamMap {
+    let defaults = HttpSinkConfig::default();
+    let mut params = ParamMap::new();
+    params.insert("method".into(), json!(defaults.method));
+    params.insert("batch".into(), json!(defaults.batch));
+    params.insert("format".into(), json!(defaults.format.as_str()));
+    params.insert("timeout_ms".into(), json!(defaults.timeout_ms));
+    params
+}
+
+fn parse_method(method: &str) -> SinkResult<Method> {
+    match method.to_ascii_uppercase().as_str() {
+        "POST" => Ok(Method::POST),
+        "PUT" => Ok(Method::PUT),
+        "PATCH" => Ok(Method::PATCH),
+        _ => Err(SinkReason::sink("http.method must be one of: POST, PUT, PATCH").into()),
+    }
+}
+
+/// 由参数构建配置：`url` 必填且为 http(s)，`headers` 为字符串值的表。
+fn build_conf(params: &ParamMap) -> SinkResult<HttpSinkConfig> {
+    let url = params
+        .get("url")
+        .and_then(|v| v.as_str())
+        .map(str::trim)
+        .unwrap_or("");
+    if !url.starts_with("http://") && !url.starts_with("https://") {
+        return Err(SinkReason::sink("http.url must start with http:// or https://").into());
+    }
+    let mut conf = HttpSinkConfig {
+        url: url.to_string(),
+        ..Default::default()
+    };
+    if let Some(method) = params.get("method").and_then(|v| v.as_str()) {
+        parse_method(method)?;
+        conf.method = method.to_ascii_uppercase();
+    }
+    match params.get("headers") {
+        None | Some(Value::Null) => {}
+        Some(Value::Object(map)) => {
+            let mut headers = BTreeMap::new();
+            for (name, value) in map {
+                let value = value.as_str().ok_or_else(|| {
+                    SinkReason::sink(format!("http.headers.{name} must be a string"))
+                })?;
+                headers.insert(name.clone(), value.to_string());
+            }
+            conf.headers = headers;
+        }
+        Some(_) => return Err(SinkReason::sink("http.headers must be a table").into()),
+    }
+    if let Some(template) = params.get("body_template").and_then(|v| v.as_str()) {
+        BodyTemplate::parse(template).map_err(SinkReason::sink)?;
+        conf.body_template = Some(template.to_string());
+    }
+    if let Some(v) = params.get("batch").filter(|v| !v.is_null()) {
+        conf.batch = match v.as_u64() {
+            Some(n) if n > 0 => n as usize,
+            _ => return Err(SinkReason::sink("http.batch must be a positive integer").into()),
+        };
+    }
+    if let Some(v) = params.get("format").filter(|v| !v.is_null()) {
+        conf.format = match v.as_str() {
+            Some("ndjson") => HttpBodyFormat::Ndjson,
+            Some("json_array") => HttpBodyFormat::JsonArray,
+            _ => {
+                return Err(
+                    SinkReason::sink("http.format must be one of: ndjson, json_array").into(),
+                );
+            }
+        };
+    }
+    if let Some(v) = params.get("timeout_ms").filter(|v| !v.is_null()) {
+        conf.timeout_ms = match v.as_u64() {
+            Some(n) if n > 0 => n,
+            _ => return Err(SinkReason::sink("http.timeout_ms must be a positive integer").into()),
+        };
+    }
+    Ok(conf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params() -> ParamMap {
+        let mut params = http_defaults();
+        params.insert("url".into(), json!("https://hooks.example.com/in"));
+        params
+    }
+
+    #[test]
+    fn build_conf_parses_params() {
+        let conf = build_conf(&params()).expect("defaults");
+        assert_eq!(conf.method, "POST");
+        assert_eq!(conf.batch, 1);
+        assert_eq!(conf.format, HttpBodyFormat::Ndjson);
+
+        let mut p = params();
+        p.insert("method".into(), json!("put"));
+        p.insert("headers".into(), json!({"Authorization": "Bearer t"}));
+        p.insert("format".into(), json!("json_array"));
+        p.insert("batch".into(), json!(50));
+        p.insert("body_template".into(), json!("{\"text\":\"{{msg}}\"}"));
+        let conf = build_conf(&p).expect("custom");
+        assert_eq!(conf.method, "PUT");
+        assert_eq!(conf.headers["Authorization"], "Bearer t");
+        assert_eq!(conf.format, HttpBodyFormat::JsonArray);
+        assert_eq!(conf.batch, 50);
+    }
+
+    #[test]
+    fn build_conf_rejects_invalid_params() {
+        let cases = [
+            ("url", json!("hooks.example.com")),
+            ("method", json!("GET")),
+            ("headers", json!({"X-Count": 1})),
+            ("headers", json!("X-A: 1")),
+            ("batch", json!(0)),
+            ("format", json!("xml")),
+            ("body_template", json!("{{msg")),
+            ("timeout_ms", json!(0)),
+        ];
+        for (key, value) in cases {
+            let mut p = params();
+            p.insert(key.into(), value.clone());
+            assert!(build_conf(&p).is_err(), "{key} = {value}");
+        }
+    }
+}
@@ -0,0 +1,13 @@
+//! HTTP webhook sink：把记录（可按批合并）以 NDJSON 或 JSON 数组发送到任意 HTTP 端点。
+//!
+//! 模块划分：
+//! - config：HttpSinkConfig、请求体格式与模板
+//! - sink：HttpSink（攒批与发送）
+//! - factory：Sink 工厂
+
+pub mod config;
+mod factory;
+mod sink;
+
+pub use config::{BodyTemplate, HttpBodyFormat, HttpSinkConfig};
+pub use factory::HttpSinkFactory;
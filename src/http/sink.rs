@@ -0,0 +1,439 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use reqwest::Method;
+use reqwest::header::{CONTENT_TYPE, HeaderMap, HeaderName, HeaderValue};
+use wp_connector_api::{AsyncCtrl, AsyncRawDataSink, AsyncRecordSink, SinkResult};
+use wp_data_fmt::{DataFormat, FormatType};
+use wp_model_core::model::{DataRecord, fmt_def::TextFmt};
+
+use crate::common::reconnect::{ReconnectCoordinator, backend_key, probe_endpoint};
+use crate::common::retry::{self, PendingFlush};
+use crate::common::stats::StatsHandle;
+use crate::common::trace_context::TraceContext;
+use crate::http::config::{BodyTemplate, HttpBodyFormat};
+
+/// 把记录渲染为 JSON（或按 `body_template` 渲染）后攒批，达到 `batch` 条时合并为一个请求发送。
+/// 原始数据按原样作为条目；`json_array` 格式下不是合法 JSON 的原始数据编码为 JSON 字符串。
+/// 非 2xx 响应返回错误并保留缓存。
+pub(crate) struct HttpSink {
+    client: reqwest::Client,
+    url: String,
+    method: Method,
+    headers: HeaderMap,
+    template: Option<BodyTemplate>,
+    format: HttpBodyFormat,
+    batch: usize,
+    buffer: Vec<String>,
+    /// 与 `buffer` 一一对应的记录 trace id；原始数据为 None
+    trace_ids: Vec<Option<String>>,
+    /// 为每个请求附加 W3C trace 头；为 None 时不附加
+    trace_context: Option<TraceContext>,
+    /// 累计进入缓存的条目数
+    accepted: u64,
+    /// `/stats` 自省状态（最近请求结果）
+    stats: StatsHandle,
+}
+
+impl HttpSink {
+    pub(crate) fn new(client: reqwest::Client, url: String, method: Method) -> Self {
+        Self {
+            stats: StatsHandle::detached(&url, "http"),
+            client,
+            url,
+            method,
+            headers: HeaderMap::new(),
+            template: None,
+            format: HttpBodyFormat::default(),
+            batch: 1,
+            buffer: Vec::new(),
+            trace_ids: Vec::new(),
+            trace_context: None,
+            accepted: 0,
+        }
+    }
+
+    /// 设置请求头；未指定 `Content-Type` 时按 `format` 补齐。
+    pub(crate) fn with_headers(mut self, headers: HeaderMap) -> Self {
+        self.headers = headers;
+        self
+    }
+
+    pub(crate) fn with_template(mut self, template: Option<BodyTemplate>) -> Self {
+        self.template = template;
+        self
+    }
+
+    pub(crate) fn with_format(mut self, format: HttpBodyFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    pub(crate) fn with_batch(mut self, batch: usize) -> Self {
+        self.batch = batch.max(1);
+        self
+    }
+
+    pub(crate) fn with_trace_context(mut self, trace_context: Option<TraceContext>) -> Self {
+        self.trace_context = trace_context;
+        self
+    }
+
+    /// 使用外部登记的状态句柄，以去掉查询串的 url 作为指标的写入目标。
+    pub(crate) fn with_stats(mut self, stats: StatsHandle) -> Self {
+        let target = self.url.split(['?', '#']).next().unwrap_or_default();
+        self.stats = stats.with_destination(target);
+        self
+    }
+
+    /// 渲染记录，并在配置了 trace 上下文时取出记录携带的 trace id。
+    fn item(&self, record: &DataRecord) -> (String, Option<String>) {
+        let trace_id = self
+            .trace_context
+            .as_ref()
+            .and_then(|trace| trace.record_trace_id(record));
+        (self.render(record), trace_id)
+    }
+
+    fn render(&self, record: &DataRecord) -> String {
+        let json = FormatType::from(&TextFmt::Json).format_record(record);
+        match &self.template {
+            Some(template) => template.render(
+                |name| record.get2(name).map(|f| f.get_value().to_string()),
+                &json,
+            ),
+            None => json,
+        }
+    }
+
+    /// 原始数据条目：`json_array` 格式下非 JSON 文本编码为 JSON 字符串，保证请求体是合法 JSON。
+    fn raw_item(&self, data: &str) -> (String, Option<String>) {
+        let item = match self.format {
+            HttpBodyFormat::JsonArray
+                if serde_json::from_str::<serde::de::IgnoredAny>(data).is_err() =>
+            {
+                serde_json::Value::from(data).to_string()
+            }
+            _ => data.to_string(),
+        };
+        (item, None)
+    }
+
+    async fn push(
+        &mut self,
+        items: impl IntoIterator<Item = (String, Option<String>)>,
+    ) -> SinkResult<()> {
+        let before = self.buffer.len();
+        for (item, trace_id) in items {
+            self.buffer.push(item);
+            self.trace_ids.push(trace_id);
+        }
+        self.accepted += (self.buffer.len() - before) as u64;
+        if self.buffer.len() >= self.batch {
+            self.flush().await?;
+        }
+        Ok(())
+    }
+
+    /// 发送缓存中的全部条目，每 `batch` 条一个请求；失败时保留未发送的条目。
+    /// 请求的 trace id 取其中第一条带 trace id 的记录。
+    async fn flush(&mut self) -> SinkResult<()> {
+        while !self.buffer.is_empty() {
+            let n = self.buffer.len().min(self.batch);
+            let trace_id = self.trace_ids[..n].iter().flatten().next().cloned();
+            self.send(self.format.assemble(&self.buffer[..n]), trace_id)
+                .await?;
+            self.buffer.drain(..n);
+            self.trace_ids.drain(..n);
+        }
+        Ok(())
+    }
+
+    async fn send(&self, body: String, trace_id: Option<String>) -> SinkResult<()> {
+        let result = self.request(body, trace_id).await;
+        self.stats.record_flush(&result);
+        result
+    }
+
+    async fn request(&self, body: String, trace_id: Option<String>) -> SinkResult<()> {
+        let mut req = self
+            .client
+            .request(self.method.clone(), &self.url)
+            .headers(self.headers.clone());
+        if !self.headers.contains_key(CONTENT_TYPE) {
+            req = req.header(CONTENT_TYPE, self.format.content_type());
+        }
+        if let Some(trace) = &self.trace_context {
+            for (name, value) in trace.headers_with_id(trace_id) {
+                req = req.header(name, value);
+            }
+        }
+        let resp = req.body(body).send().await.map_err(|e| {
+            retry::transport_error(
+                format!("http {} {} fail: {e}", self.method, self.url),
+                e.is_timeout(),
+            )
+        })?;
+        let status = resp.status();
+        if !status.is_success() {
+            let text = resp.text().await.unwrap_or_default();
+            return Err(retry::status_error(
+                status.as_u16(),
+                format!(
+                    "http {} {} returned {status}: {text}",
+                    self.method, self.url
+                ),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// 由配置的键值对构造请求头。
+pub(crate) fn header_map<'a>(
+    headers: impl IntoIterator<Item = (&'a String, &'a String)>,
+) -> Result<HeaderMap, String> {
+    let mut map = HeaderMap::new();
+    for (name, value) in headers {
+        let name = HeaderName::from_bytes(name.as_bytes())
+            .map_err(|_| format!("http.headers has invalid header name '{name}'"))?;
+        let value = HeaderValue::from_str(value)
+            .map_err(|_| format!("http.headers.{name} has an invalid value"))?;
+        map.insert(name, value);
+    }
+    Ok(map)
+}
+
+#[async_trait]
+impl PendingFlush for HttpSink {
+    fn pending_len(&self) -> usize {
+        self.buffer.len()
+    }
+
+    async fn flush_now(&mut self) -> SinkResult<()> {
+        self.flush().await
+    }
+
+    fn discard_pending(&mut self) -> usize {
+        let discarded = self.buffer.len();
+        self.buffer.clear();
+        self.trace_ids.clear();
+        discarded
+    }
+
+    fn accepted(&self) -> u64 {
+        self.accepted
+    }
+}
+
+#[async_trait]
+impl AsyncCtrl for HttpSink {
+    async fn stop(&mut self) -> SinkResult<()> {
+        self.flush().await
+    }
+
+    /// 不发请求，只确认目标主机可连通，按后端共享退避。
+    async fn reconnect(&mut self) -> SinkResult<()> {
+        ReconnectCoordinator::shared(backend_key("http", &self.url), Default::default())
+            .run(|| probe_endpoint(&self.url))
+            .await
+    }
+}
+
+#[async_trait]
+impl AsyncRecordSink for HttpSink {
+    async fn sink_record(&mut self, data: &DataRecord) -> SinkResult<()> {
+        let item = self.item(data);
+        self.push([item]).await
+    }
+
+    async fn sink_records(&mut self, data: Vec<Arc<DataRecord>>) -> SinkResult<()> {
+        let items: Vec<_> = data.iter().map(|r| self.item(r.as_ref())).collect();
+        self.push(items).await
+    }
+}
+
+#[async_trait]
+impl AsyncRawDataSink for HttpSink {
+    async fn sink_str(&mut self, data: &str) -> SinkResult<()> {
+        let item = self.raw_item(data);
+        self.push([item]).await
+    }
+
+    async fn sink_bytes(&mut self, data: &[u8]) -> SinkResult<()> {
+        self.sink_str(&String::from_utf8_lossy(data)).await
+    }
+
+    async fn sink_str_batch(&mut self, data: Vec<&str>) -> SinkResult<()> {
+        let items: Vec<_> = data.into_iter().map(|s| self.raw_item(s)).collect();
+        self.push(items).await
+    }
+
+    async fn sink_bytes_batch(&mut self, data: Vec<&[u8]>) -> SinkResult<()> {
+        let items: Vec<_> = data
+            .into_iter()
+            .map(|b| self.raw_item(&String::from_utf8_lossy(b)))
+            .collect();
+        self.push(items).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::retry::FailureKind;
+    use httpmock::prelude::*;
+    use std::collections::BTreeMap;
+    use wp_model_core::model::DataField;
+
+    fn client() -> reqwest::Client {
+        reqwest::Client::builder().no_proxy().build().unwrap()
+    }
+
+    fn record(msg: &str) -> DataRecord {
+        let mut record = DataRecord::default();
+        record.append(DataField::from_chars("msg", msg));
+        record
+    }
+
+    #[tokio::test]
+    async fn sends_custom_headers_and_templated_body() {
+        let server = MockServer::start_async().await;
+        let hook = server.mock(|when, then| {
+            when.method(PUT)
+                .path("/hook")
+                .header("x-api-key", "secret")
+                .header("content-type", "application/x-ndjson")
+                .body("{\"text\":\"disk \\\"full\\\"\"}\n");
+            then.status(204);
+        });
+        let headers = BTreeMap::from([("X-Api-Key".to_string(), "secret".to_string())]);
+        let mut sink = HttpSink::new(client(), server.url("/hook"), Method::PUT)
+            .with_headers(header_map(&headers).unwrap())
+            .with_template(Some(BodyTemplate::parse(r#"{"text":"{{msg}}"}"#).unwrap()));
+        sink.sink_record(&record("disk \"full\"")).await.unwrap();
+        hook.assert_hits(1);
+        assert_eq!(sink.pending_len(), 0);
+    }
+
+    #[tokio::test]
+    async fn batches_records_into_json_array() {
+        let server = MockServer::start_async().await;
+        let hook = server.mock(|when, then| {
+            when.method(POST)
+                .path("/batch")
+                .header("content-type", "application/json")
+                .body_contains("[{")
+                .body_contains("\"msg\":\"a\"")
+                .body_contains("\"msg\":\"c\"");
+            then.status(200);
+        });
+        let mut sink = HttpSink::new(client(), server.url("/batch"), Method::POST)
+            .with_format(HttpBodyFormat::JsonArray)
+            .with_batch(3);
+        sink.sink_records(vec![Arc::new(record("a")), Arc::new(record("b"))])
+            .await
+            .unwrap();
+        hook.assert_hits(0);
+        sink.sink_record(&record("c")).await.unwrap();
+        hook.assert_hits(1);
+
+        // stop 时发送不足一批的剩余条目；非 JSON 的原始数据编码为字符串，JSON 原样保留
+        let tail = server.mock(|when, then| {
+            when.method(POST)
+                .path("/batch")
+                .body(r#"["raw \"1\"",{"n":2}]"#);
+            then.status(200);
+        });
+        sink.sink_str_batch(vec!["raw \"1\"", r#"{"n":2}"#])
+            .await
+            .unwrap();
+        assert_eq!(sink.pending_len(), 2);
+        sink.stop().await.unwrap();
+        tail.assert_hits(1);
+    }
+
+    #[tokio::test]
+    async fn trace_context_uses_record_trace_id_per_request() {
+        let server = MockServer::start_async().await;
+        let traced = server.mock(|when, then| {
+            when.method(POST)
+                .path("/hook")
+                .header("tracestate", "wp=pipeline")
+                .matches(|req| {
+                    req.headers.iter().flatten().any(|(name, value)| {
+                        name.eq_ignore_ascii_case("traceparent")
+                            && value.starts_with("00-4bf92f3577b34da6a3ce929d0e0e4736-")
+                            && value.ends_with("-01")
+                    })
+                });
+            then.status(200);
+        });
+        let generated = server.mock(|when, then| {
+            when.method(POST).path("/hook").matches(|req| {
+                req.headers.iter().flatten().any(|(name, value)| {
+                    name.eq_ignore_ascii_case("traceparent")
+                        && value.len() == 55
+                        && !value.contains("4bf92f3577b34da6a3ce929d0e0e4736")
+                })
+            });
+            then.status(200);
+        });
+        let trace = TraceContext {
+            trace_id_field: Some("trace_id".into()),
+            tracestate: Some("wp=pipeline".into()),
+        };
+        let mut sink = HttpSink::new(client(), server.url("/hook"), Method::POST)
+            .with_batch(2)
+            .with_trace_context(Some(trace));
+        let mut with_id = record("b");
+        with_id.append(DataField::from_chars(
+            "trace_id",
+            "4BF92F3577B34DA6A3CE929D0E0E4736",
+        ));
+        // 批内第一条带 trace id 的记录决定请求的 trace id
+        sink.sink_records(vec![Arc::new(record("a")), Arc::new(with_id)])
+            .await
+            .unwrap();
+        traced.assert_hits(1);
+        // 没有 trace id 时按请求随机生成
+        sink.sink_str_batch(vec!["raw-1", "raw-2"]).await.unwrap();
+        generated.assert_hits(1);
+    }
+
+    #[tokio::test]
+    async fn non_2xx_response_is_an_error_and_keeps_buffer() {
+        let server = MockServer::start_async().await;
+        let hook = server.mock(|when, then| {
+            when.method(POST).path("/hook");
+            then.status(500).body("boom");
+        });
+        let mut sink = HttpSink::new(client(), server.url("/hook"), Method::POST);
+        let err = sink.sink_str("line").await.unwrap_err();
+        let msg = format!("{err}");
+        assert!(msg.contains("500") && msg.contains("boom"), "{msg}");
+        assert_eq!(FailureKind::of(&err), FailureKind::Retryable);
+        hook.assert_hits(1);
+        assert_eq!(sink.pending_len(), 1);
+        assert_eq!(sink.accepted(), 1);
+        assert_eq!(sink.discard_pending(), 1);
+    }
+
+    #[tokio::test]
+    async fn client_errors_are_not_retried() {
+        let server = MockServer::start_async().await;
+        server.mock(|when, then| {
+            when.method(POST).path("/hook");
+            then.status(400).body("bad payload");
+        });
+        let mut sink = HttpSink::new(client(), server.url("/hook"), Method::POST);
+        let err = sink.sink_str("line").await.unwrap_err();
+        assert_eq!(FailureKind::of(&err), FailureKind::Permanent);
+    }
+
+    #[test]
+    fn header_map_rejects_invalid_names() {
+        let headers = BTreeMap::from([("bad header".to_string(), "v".to_string())]);
+        assert!(header_map(&headers).is_err());
+    }
+}
@@ -0,0 +1,487 @@
+//! Kafka 的 Confluent Avro 编解码：经 Schema Registry 注册/获取 schema，
+//! 消息值为 `0x00 | schema id（4 字节大端）| Avro datum`。
+//!
+//! sink：记录字段按 schema 的 record 字段逐一转换；缺失字段仅在 schema 允许 null 或带默认值时接受，
+//! 其余不匹配以错误返回，由 sink 按 DLQ 配置处理。
+//!
+//! source：按前缀中的 schema id 从 Registry 获取写入方 schema（按 id 缓存），
+//! 将 record 解码为 JSON 对象交给下游解析。Registry 暂时不可用时返回
+//! [`DecodeError::Unavailable`]，由 source 重新投递该消息，而不是隔离或透传原文。
+
+use apache_avro::schema::{RecordField, Schema};
+use apache_avro::types::Value as AvroValue;
+use apache_avro::{from_avro_datum, to_avro_datum};
+use reqwest::StatusCode;
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::time::Duration;
+use wp_model_core::model::{DataRecord, DataType};
+
+use crate::kafka::config::KafkaSinkConf;
+
+/// Confluent wire format 的魔数字节
+const MAGIC_BYTE: u8 = 0;
+const REGISTRY_CONTENT_TYPE: &str = "application/vnd.schemaregistry.v1+json";
+/// 魔数字节加 4 字节 schema id
+const HEADER_LEN: usize = 5;
+const REGISTRY_CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+const REGISTRY_REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Schema Registry 客户端：带连接与请求超时，避免 Registry 无响应时阻塞收发。
+fn registry_client() -> reqwest::Result<reqwest::Client> {
+    reqwest::Client::builder()
+        .connect_timeout(REGISTRY_CONNECT_TIMEOUT)
+        .timeout(REGISTRY_REQUEST_TIMEOUT)
+        .build()
+}
+
+#[derive(Debug, Deserialize)]
+struct RegisteredSchema {
+    id: u32,
+    #[serde(default)]
+    schema: Option<String>,
+}
+
+pub(crate) struct AvroEncoder {
+    schema: Schema,
+    schema_id: u32,
+}
+
+impl AvroEncoder {
+    /// 按配置解析 schema：配置了 `value_schema` 时注册到 subject（已存在则返回原 id），
+    /// 否则取 subject 的最新版本。
+    pub(crate) async fn resolve(conf: &KafkaSinkConf) -> anyhow::Result<Self> {
+        let base = conf
+            .schema_registry_url
+            .as_deref()
+            .ok_or_else(|| anyhow::anyhow!("kafka.schema_registry_url is required for avro"))?
+            .trim_end_matches('/');
+        let subject = conf.effective_value_subject();
+        let client = registry_client()?;
+        let (id, raw) = match &conf.value_schema {
+            Some(raw) => {
+                let resp = client
+                    .post(format!("{base}/subjects/{subject}/versions"))
+                    .header(reqwest::header::CONTENT_TYPE, REGISTRY_CONTENT_TYPE)
+                    .json(&json!({ "schema": raw }))
+                    .send()
+                    .await?
+                    .error_for_status()?;
+                (resp.json::<RegisteredSchema>().await?.id, raw.clone())
+            }
+            None => {
+                let registered = client
+                    .get(format!("{base}/subjects/{subject}/versions/latest"))
+                    .send()
+                    .await?
+                    .error_for_status()?
+                    .json::<RegisteredSchema>()
+                    .await?;
+                let raw = registered
+                    .schema
+                    .ok_or_else(|| anyhow::anyhow!("registry returned no schema for {subject}"))?;
+                (registered.id, raw)
+            }
+        };
+        Self::new(&raw, id)
+    }
+
+    /// # args
+    /// * `raw` - Avro schema JSON，顶层须为 record。
+    /// * `schema_id` - Registry 分配的 schema id。
+    pub(crate) fn new(raw: &str, schema_id: u32) -> anyhow::Result<Self> {
+        let schema = Schema::parse_str(raw)?;
+        if !matches!(schema, Schema::Record(_)) {
+            anyhow::bail!("kafka avro value_schema must be a record schema");
+        }
+        Ok(Self { schema, schema_id })
+    }
+
+    pub(crate) fn schema_id(&self) -> u32 {
+        self.schema_id
+    }
+
+    /// 将记录编码为带 schema id 前缀的 Avro 消息值。
+    ///
+    /// # return
+    /// * `Result<Vec<u8>, String>` - 字段与 schema 不匹配时返回描述。
+    pub(crate) fn encode(&self, data: &DataRecord) -> Result<Vec<u8>, String> {
+        let Schema::Record(record) = &self.schema else {
+            return Err("avro schema is not a record".to_string());
+        };
+        let fields = data
+            .items
+            .iter()
+            .filter(|f| *f.get_meta() != DataType::Ignore)
+            .map(|f| (f.get_name(), f.get_value().to_string()))
+            .collect::<HashMap<_, _>>();
+        let values = record
+            .fields
+            .iter()
+            .map(|field| {
+                field_value(field, fields.get(field.name.as_str()))
+                    .map(|value| (field.name.clone(), value))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let datum = to_avro_datum(&self.schema, AvroValue::Record(values))
+            .map_err(|e| format!("avro encode failed: {e}"))?;
+        Ok(frame(self.schema_id, &datum))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct SchemaById {
+    schema: String,
+}
+
+/// source 侧解码失败。
+#[derive(Debug)]
+pub(crate) enum DecodeError {
+    /// Registry 暂时不可用（连接失败、超时、408/429/5xx），消息应重新投递
+    Unavailable(String),
+    /// 前缀非法、schema 不存在或无效、datum 与 schema 不符
+    Invalid(String),
+}
+
+impl Display for DecodeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DecodeError::Unavailable(msg) | DecodeError::Invalid(msg) => f.write_str(msg),
+        }
+    }
+}
+
+impl From<String> for DecodeError {
+    fn from(msg: String) -> Self {
+        DecodeError::Invalid(msg)
+    }
+}
+
+/// Registry 请求失败的归类：传输错误与 408/429/5xx 视为暂时不可用，其余（如 404）视为消息无效。
+fn registry_error(id: u32, err: reqwest::Error) -> DecodeError {
+    let msg = format!("fetch avro schema {id} failed: {err}");
+    let transient = match err.status() {
+        Some(status) => {
+            status == StatusCode::REQUEST_TIMEOUT
+                || status == StatusCode::TOO_MANY_REQUESTS
+                || status.is_server_error()
+        }
+        None => !err.is_decode(),
+    };
+    match transient {
+        true => DecodeError::Unavailable(msg),
+        false => DecodeError::Invalid(msg),
+    }
+}
+
+/// source 侧解码器：按消息携带的 schema id 获取并缓存写入方 schema。
+pub(crate) struct AvroDecoder {
+    base: String,
+    client: reqwest::Client,
+    schemas: HashMap<u32, Schema>,
+}
+
+impl AvroDecoder {
+    /// # args
+    /// * `registry_url` - Schema Registry 地址。
+    pub(crate) fn new(registry_url: &str) -> anyhow::Result<Self> {
+        Ok(Self {
+            base: registry_url.trim_end_matches('/').to_string(),
+            client: registry_client()?,
+            schemas: HashMap::new(),
+        })
+    }
+
+    /// 解码一条消息值为 JSON 对象。
+    ///
+    /// # return
+    /// * `Result<Vec<u8>, DecodeError>` - Registry 暂时不可用时为 `Unavailable`，其余失败为 `Invalid`。
+    pub(crate) async fn decode(&mut self, payload: &[u8]) -> Result<Vec<u8>, DecodeError> {
+        let (id, mut datum) = deframe(payload)?;
+        let schema = self.schema(id).await?;
+        let value = from_avro_datum(schema, &mut datum, None)
+            .map_err(|e| format!("avro decode with schema {id} failed: {e}"))?;
+        if !matches!(value, AvroValue::Record(_)) {
+            return Err(format!("avro schema {id} is not a record").into());
+        }
+        let json = serde_json::Value::try_from(value)
+            .map_err(|e| format!("avro value of schema {id} is not representable as JSON: {e}"))?;
+        Ok(json.to_string().into_bytes())
+    }
+
+    /// 按 id 取 schema：命中缓存直接返回，否则请求 `/schemas/ids/{id}` 并缓存。
+    async fn schema(&mut self, id: u32) -> Result<&Schema, DecodeError> {
+        if !self.schemas.contains_key(&id) {
+            let schema = self.fetch(id).await?;
+            self.schemas.insert(id, schema);
+        }
+        Ok(&self.schemas[&id])
+    }
+
+    async fn fetch(&self, id: u32) -> Result<Schema, DecodeError> {
+        let raw = self
+            .client
+            .get(format!("{}/schemas/ids/{id}", self.base))
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .map_err(|e| registry_error(id, e))?
+            .json::<SchemaById>()
+            .await
+            .map_err(|e| registry_error(id, e))?
+            .schema;
+        Schema::parse_str(&raw).map_err(|e| {
+            DecodeError::Invalid(format!("avro schema {id} from registry is invalid: {e}"))
+        })
+    }
+}
+
+/// 加上 wire format 前缀：魔数字节与大端 schema id。
+fn frame(schema_id: u32, datum: &[u8]) -> Vec<u8> {
+    let mut payload = Vec::with_capacity(datum.len() + HEADER_LEN);
+    payload.push(MAGIC_BYTE);
+    payload.extend_from_slice(&schema_id.to_be_bytes());
+    payload.extend_from_slice(datum);
+    payload
+}
+
+/// 拆出 wire format 前缀，返回 schema id 与 datum。
+fn deframe(payload: &[u8]) -> Result<(u32, &[u8]), String> {
+    if payload.len() < HEADER_LEN {
+        return Err(format!(
+            "avro payload too short: {} bytes, expected at least {HEADER_LEN}",
+            payload.len()
+        ));
+    }
+    if payload[0] != MAGIC_BYTE {
+        return Err(format!(
+            "avro payload has unknown magic byte {:#04x}",
+            payload[0]
+        ));
+    }
+    let id = u32::from_be_bytes([payload[1], payload[2], payload[3], payload[4]]);
+    Ok((id, &payload[HEADER_LEN..]))
+}
+
+/// 按 schema 字段转换单个记录值；缺失时依次尝试 null 分支与默认值。
+fn field_value(field: &RecordField, raw: Option<&String>) -> Result<AvroValue, String> {
+    match raw {
+        Some(raw) => convert(&field.schema, raw).ok_or_else(|| {
+            format!(
+                "field '{}' value '{}' does not match schema",
+                field.name, raw
+            )
+        }),
+        None => {
+            if let Some(value) = null_branch(&field.schema) {
+                return Ok(value);
+            }
+            let default = match &field.default {
+                Some(serde_json::Value::String(s)) => Some(s.clone()),
+                Some(serde_json::Value::Null) | None => None,
+                Some(other) => Some(other.to_string()),
+            };
+            default
+                .and_then(|raw| convert(&field.schema, &raw))
+                .ok_or_else(|| format!("required field '{}' is missing", field.name))
+        }
+    }
+}
+
+/// 联合类型中的 null 分支。
+fn null_branch(schema: &Schema) -> Option<AvroValue> {
+    let Schema::Union(union) = schema else {
+        return None;
+    };
+    union
+        .variants()
+        .iter()
+        .position(|s| matches!(s, Schema::Null))
+        .map(|idx| AvroValue::Union(idx as u32, Box::new(AvroValue::Null)))
+}
+
+/// 将字段的字符串形式转换为 schema 对应的 Avro 值；联合类型取第一个可转换的非 null 分支。
+fn convert(schema: &Schema, raw: &str) -> Option<AvroValue> {
+    let trimmed = raw.trim();
+    match schema {
+        Schema::String => Some(AvroValue::String(raw.to_string())),
+        Schema::Boolean => match trimmed.to_ascii_lowercase().as_str() {
+            "true" | "1" => Some(AvroValue::Boolean(true)),
+            "false" | "0" => Some(AvroValue::Boolean(false)),
+            _ => None,
+        },
+        Schema::Int => trimmed.parse().ok().map(AvroValue::Int),
+        Schema::Long => trimmed.parse().ok().map(AvroValue::Long),
+        Schema::Float => trimmed.parse().ok().map(AvroValue::Float),
+        Schema::Double => trimmed.parse().ok().map(AvroValue::Double),
+        Schema::Bytes => Some(AvroValue::Bytes(raw.as_bytes().to_vec())),
+        Schema::Union(union) => union.variants().iter().enumerate().find_map(|(idx, s)| {
+            if matches!(s, Schema::Null) {
+                return None;
+            }
+            convert(s, raw).map(|v| AvroValue::Union(idx as u32, Box::new(v)))
+        }),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use httpmock::prelude::*;
+    use wp_model_core::model::DataField;
+
+    const SCHEMA: &str = r#"{
+        "type": "record",
+        "name": "Event",
+        "fields": [
+            {"name": "msg", "type": "string"},
+            {"name": "code", "type": "long"},
+            {"name": "score", "type": ["null", "double"], "default": null}
+        ]
+    }"#;
+
+    fn event(code: &str) -> DataRecord {
+        let mut rec = DataRecord::default();
+        rec.append(DataField::from_chars("msg", "hello"));
+        rec.append(DataField::from_chars("code", code));
+        rec.append(DataField::from_chars("extra", "ignored"));
+        rec
+    }
+
+    #[tokio::test]
+    async fn registered_schema_id_prefixes_payload_and_decodes_back() {
+        let server = MockServer::start_async().await;
+        let register = server.mock(|when, then| {
+            when.method(POST)
+                .path("/subjects/events-value/versions")
+                .header("content-type", REGISTRY_CONTENT_TYPE)
+                .body_contains("\"schema\"");
+            then.status(200).json_body(json!({ "id": 42 }));
+        });
+        let conf = KafkaSinkConf {
+            topic: "events".into(),
+            value_format: crate::kafka::config::ValueFormat::Avro,
+            schema_registry_url: Some(server.base_url()),
+            value_schema: Some(SCHEMA.to_string()),
+            ..Default::default()
+        };
+        let encoder = AvroEncoder::resolve(&conf).await.expect("resolve");
+        register.assert_hits(1);
+        assert_eq!(encoder.schema_id(), 42);
+
+        let payload = encoder.encode(&event("7")).expect("encode");
+        assert_eq!(payload[0], MAGIC_BYTE);
+        assert_eq!(&payload[1..5], &42u32.to_be_bytes());
+        let decoded = from_avro_datum(&encoder.schema, &mut &payload[5..], None).expect("decode");
+        assert_eq!(
+            decoded,
+            AvroValue::Record(vec![
+                ("msg".into(), AvroValue::String("hello".into())),
+                ("code".into(), AvroValue::Long(7)),
+                (
+                    "score".into(),
+                    AvroValue::Union(0, Box::new(AvroValue::Null))
+                ),
+            ])
+        );
+    }
+
+    #[tokio::test]
+    async fn latest_schema_is_fetched_when_not_configured() {
+        let server = MockServer::start_async().await;
+        server.mock(|when, then| {
+            when.method(GET).path("/subjects/audit/versions/latest");
+            then.status(200)
+                .json_body(json!({ "id": 9, "version": 3, "schema": SCHEMA }));
+        });
+        let conf = KafkaSinkConf {
+            topic: "events".into(),
+            schema_registry_url: Some(format!("{}/", server.base_url())),
+            value_subject: Some("audit".into()),
+            ..Default::default()
+        };
+        let encoder = AvroEncoder::resolve(&conf).await.expect("resolve");
+        assert_eq!(encoder.schema_id(), 9);
+    }
+
+    #[test]
+    fn wire_format_framing_roundtrips() {
+        let payload = frame(0x0102_0304, b"datum");
+        assert_eq!(payload, b"\x00\x01\x02\x03\x04datum");
+        assert_eq!(deframe(&payload), Ok((0x0102_0304, b"datum".as_slice())));
+        assert_eq!(deframe(&frame(7, b"")), Ok((7, b"".as_slice())));
+
+        let err = deframe(b"\x00\x01\x02").unwrap_err();
+        assert!(err.contains("too short"), "{err}");
+        let err = deframe(b"{\"msg\":1}").unwrap_err();
+        assert!(err.contains("magic byte 0x7b"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn decoder_fetches_schema_once_per_id() {
+        let server = MockServer::start_async().await;
+        let by_id = server.mock(|when, then| {
+            when.method(GET).path("/schemas/ids/42");
+            then.status(200).json_body(json!({ "schema": SCHEMA }));
+        });
+        let encoder = AvroEncoder::new(SCHEMA, 42).unwrap();
+        let mut decoder = AvroDecoder::new(&format!("{}/", server.base_url())).unwrap();
+        for code in ["7", "8"] {
+            let payload = encoder.encode(&event(code)).expect("encode");
+            let decoded = decoder.decode(&payload).await.expect("decode");
+            let value: serde_json::Value = serde_json::from_slice(&decoded).unwrap();
+            assert_eq!(value["msg"], "hello");
+            assert_eq!(value["code"].to_string(), code);
+            assert!(value["score"].is_null(), "{value}");
+        }
+        by_id.assert_hits(1);
+
+        let err = decoder.decode(&frame(42, b"\x02")).await.unwrap_err();
+        assert!(err.to_string().contains("schema 42"), "{err}");
+        let err = decoder.decode(b"plain text").await.unwrap_err();
+        assert!(err.to_string().contains("magic byte"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn unavailable_registry_is_reported_for_redelivery() {
+        let server = MockServer::start_async().await;
+        let mut down = server.mock(|when, then| {
+            when.method(GET).path("/schemas/ids/42");
+            then.status(503);
+        });
+        server.mock(|when, then| {
+            when.method(GET).path("/schemas/ids/7");
+            then.status(404);
+        });
+        let encoder = AvroEncoder::new(SCHEMA, 42).unwrap();
+        let payload = encoder.encode(&event("1")).expect("encode");
+        let mut decoder = AvroDecoder::new(&server.base_url()).unwrap();
+
+        let err = decoder.decode(&payload).await.unwrap_err();
+        assert!(matches!(err, DecodeError::Unavailable(_)), "{err}");
+        let err = decoder.decode(&frame(7, b"")).await.unwrap_err();
+        assert!(matches!(err, DecodeError::Invalid(_)), "{err}");
+
+        down.delete();
+        server.mock(|when, then| {
+            when.method(GET).path("/schemas/ids/42");
+            then.status(200).json_body(json!({ "schema": SCHEMA }));
+        });
+        assert!(decoder.decode(&payload).await.is_ok());
+    }
+
+    #[test]
+    fn mismatched_records_are_rejected() {
+        let encoder = AvroEncoder::new(SCHEMA, 1).unwrap();
+        let err = encoder.encode(&event("seven")).unwrap_err();
+        assert!(err.contains("'code'"), "{err}");
+        let mut missing = DataRecord::default();
+        missing.append(DataField::from_chars("code", "1"));
+        let err = encoder.encode(&missing).unwrap_err();
+        assert!(err.contains("required field 'msg'"), "{err}");
+        assert!(AvroEncoder::new(r#""string""#, 1).is_err());
+    }
+}
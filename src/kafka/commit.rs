@@ -0,0 +1,234 @@
+//! Kafka source 批量提交：按分区跟踪已下发但未确认的 offset，
+//! 周期性提交每个分区"最小未确认 offset"（即可安全提交的位置），保证 at-least-once。
+//!
+//! 确认来自下游 sink 的 flush 通知（见 [`crate::common::flush_notify`]）：通知携带该批写出记录的
+//! `wp_event_id`，即本 source 下发的事件 id；只确认这些事件。下游按表分别缓存、部分表写入失败时，
+//! 仍缓存的事件不会因其他表的 flush 被确认。
+
+use std::collections::{BTreeSet, HashMap};
+use std::time::{Duration, Instant};
+
+use crate::common::flush_notify::FlushEvent;
+
+/// 一个待提交的分区位置（Kafka 语义：下一条要读取的 offset）。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommitPosition {
+    pub topic: String,
+    pub partition: i32,
+    pub offset: i64,
+}
+
+#[derive(Debug, Default)]
+struct PartitionState {
+    /// 已下发、尚未确认的 offset
+    inflight: BTreeSet<i64>,
+    /// 已下发的最大 offset
+    high: Option<i64>,
+    /// 最近一次提交的位置
+    committed: Option<i64>,
+}
+
+impl PartitionState {
+    /// 可提交位置：存在未确认 offset 时停在最小的那个（不越过缺口），否则为最大 offset + 1。
+    fn position(&self) -> Option<i64> {
+        match self.inflight.first() {
+            Some(first) => Some(*first),
+            None => self.high.map(|h| h + 1),
+        }
+    }
+}
+
+/// offset 跟踪器：`track` 记录下发，`ack` 记录确认，`take_due` 按间隔产出待提交位置。
+#[derive(Debug)]
+pub struct OffsetTracker {
+    interval: Duration,
+    last_commit: Instant,
+    partitions: HashMap<(String, i32), PartitionState>,
+    events: HashMap<u64, (String, i32, i64)>,
+}
+
+impl OffsetTracker {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            last_commit: Instant::now(),
+            partitions: HashMap::new(),
+            events: HashMap::new(),
+        }
+    }
+
+    /// 记录一条已下发的消息，`event_id` 用于之后按事件确认。
+    pub fn track(&mut self, event_id: u64, topic: &str, partition: i32, offset: i64) {
+        let state = self
+            .partitions
+            .entry((topic.to_string(), partition))
+            .or_default();
+        state.inflight.insert(offset);
+        state.high = Some(state.high.map_or(offset, |h| h.max(offset)));
+        self.events
+            .insert(event_id, (topic.to_string(), partition, offset));
+    }
+
+    /// 确认一个事件已被下游持久化；未知事件返回 false。
+    pub fn ack(&mut self, event_id: u64) -> bool {
+        let Some((topic, partition, offset)) = self.events.remove(&event_id) else {
+            return false;
+        };
+        if let Some(state) = self.partitions.get_mut(&(topic, partition)) {
+            state.inflight.remove(&offset);
+        }
+        true
+    }
+
+    /// 按下游 flush 通知确认其 `event_ids`，返回新确认的条数；不是本 source 的事件 id 忽略。
+    pub fn apply_flush(&mut self, event: &FlushEvent) -> usize {
+        event
+            .event_ids
+            .iter()
+            .filter_map(|id| id.trim().parse::<u64>().ok())
+            .filter(|id| self.ack(*id))
+            .count()
+    }
+
+    /// 尚未确认的事件数。
+    pub fn pending(&self) -> usize {
+        self.events.len()
+    }
+
+    /// 相比上次提交有推进的分区位置。
+    pub fn committable(&self) -> Vec<CommitPosition> {
+        let mut out: Vec<CommitPosition> = self
+            .partitions
+            .iter()
+            .filter_map(|((topic, partition), state)| {
+                let pos = state.position()?;
+                (state.committed < Some(pos)).then(|| CommitPosition {
+                    topic: topic.clone(),
+                    partition: *partition,
+                    offset: pos,
+                })
+            })
+            .collect();
+        out.sort_by(|a, b| (&a.topic, a.partition).cmp(&(&b.topic, b.partition)));
+        out
+    }
+
+    /// 提交成功后登记位置。
+    pub fn mark_committed(&mut self, positions: &[CommitPosition]) {
+        for pos in positions {
+            if let Some(state) = self.partitions.get_mut(&(pos.topic.clone(), pos.partition)) {
+                state.committed = Some(pos.offset);
+            }
+        }
+    }
+
+    /// 到达提交间隔时返回待提交位置（可能为空），并重置计时。
+    pub fn take_due(&mut self, now: Instant) -> Option<Vec<CommitPosition>> {
+        if now.duration_since(self.last_commit) < self.interval {
+            return None;
+        }
+        self.last_commit = now;
+        Some(self.committable())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn positions(tracker: &OffsetTracker) -> Vec<(i32, i64)> {
+        tracker
+            .committable()
+            .into_iter()
+            .map(|p| (p.partition, p.offset))
+            .collect()
+    }
+
+    #[test]
+    fn commit_does_not_pass_unacked_gap() {
+        let mut tracker = OffsetTracker::new(Duration::from_millis(100));
+        for (event, offset) in [(1, 10), (2, 11), (3, 12), (4, 13)] {
+            tracker.track(event, "events", 0, offset);
+        }
+        // 尚无确认：位置停在第一条
+        assert_eq!(positions(&tracker), vec![(0, 10)]);
+
+        // 11 未确认：只能提交到 11
+        tracker.ack(1);
+        tracker.ack(3);
+        tracker.ack(4);
+        assert_eq!(positions(&tracker), vec![(0, 11)]);
+        let due = tracker.committable();
+        tracker.mark_committed(&due);
+        assert!(positions(&tracker).is_empty());
+
+        // 缺口补上后推进到最大 offset + 1
+        assert!(tracker.ack(2));
+        assert_eq!(positions(&tracker), vec![(0, 14)]);
+        assert_eq!(tracker.pending(), 0);
+        assert!(!tracker.ack(2));
+    }
+
+    #[test]
+    fn commit_follows_downstream_flush_notifications() {
+        use crate::common::flush_notify::{FlushNotifier, subscribe};
+
+        let mut acks = subscribe("commit-test-ckpt");
+        let mut notifier = FlushNotifier::named("commit-test-ckpt");
+        let mut tracker = OffsetTracker::new(Duration::from_millis(100));
+        // consume：下发 4 条
+        for (event, offset) in [(1, 20), (2, 21), (3, 22), (4, 23)] {
+            tracker.track(event, "events", 0, offset);
+        }
+
+        // flush：下游按表缓存，table_b 先写出 2、4；table_a 的 1、3 仍在缓存
+        notifier.notify("table_b", 2, vec!["2".into(), "4".into()]);
+        let event = acks.try_recv().expect("flush event");
+        assert_eq!(tracker.apply_flush(&event), 2);
+
+        // commit：不越过仍在缓存的首条消息
+        let due = tracker
+            .take_due(Instant::now() + Duration::from_secs(1))
+            .expect("interval elapsed");
+        assert_eq!(due.iter().map(|p| p.offset).collect::<Vec<_>>(), [20]);
+        tracker.mark_committed(&due);
+
+        notifier.notify("table_a", 2, vec!["1".into(), "3".into()]);
+        let event = acks.try_recv().expect("flush event");
+        assert_eq!(tracker.apply_flush(&event), 2);
+        assert_eq!(positions(&tracker), vec![(0, 24)]);
+
+        // 无 id 的通知不推进
+        notifier.notify("table_a", 0, Vec::new());
+        assert_eq!(tracker.apply_flush(&acks.try_recv().expect("event")), 0);
+    }
+
+    #[test]
+    fn partitions_advance_independently_on_interval() {
+        let mut tracker = OffsetTracker::new(Duration::from_secs(10));
+        tracker.track(1, "events", 0, 5);
+        tracker.track(2, "events", 1, 7);
+        tracker.ack(2);
+
+        let start = Instant::now();
+        assert!(tracker.take_due(start).is_none());
+        let due = tracker
+            .take_due(start + Duration::from_secs(11))
+            .expect("interval elapsed");
+        assert_eq!(
+            due,
+            vec![
+                CommitPosition {
+                    topic: "events".into(),
+                    partition: 0,
+                    offset: 5,
+                },
+                CommitPosition {
+                    topic: "events".into(),
+                    partition: 1,
+                    offset: 8,
+                },
+            ]
+        );
+    }
+}
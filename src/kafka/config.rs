@@ -1,6 +1,9 @@
 use orion_conf::error::{ConfIOReason, OrionConfResult};
 use orion_error::{ToStructError, UvsValidationFrom};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
 use wp_conf_base::structure::Validate;
 
 #[derive(Debug, Deserialize, Serialize, PartialEq, Clone)]
@@ -10,10 +13,164 @@ pub struct KafkaSourceConf {
     pub topic: Vec<String>,
     pub config: Option<Vec<String>>,
     pub enable: bool,
+    /// 消费组；未配置时回退为 `{key}_group`
+    #[serde(default)]
+    pub group_id: Option<String>,
+    /// 源端 JSON 顶层字段白名单：仅解析并保留这些字段
+    #[serde(default)]
+    pub fields: Option<Vec<String>>,
+    /// 批量提交间隔（毫秒）：配置后关闭自动提交，仅周期性提交已确认的连续 offset
+    #[serde(default)]
+    pub commit_interval_ms: Option<u64>,
+    /// 下游 sink 的 flush 通知通道（与 sink 参数 `flush_notify` 同名）：
+    /// 收到通知后确认其中列出的事件（`event_ids`）；需配合 `commit_interval_ms`
+    #[serde(default)]
+    pub ack_channel: Option<String>,
+    /// 起始位置：分区分配后按此 seek，不修改已提交 offset
+    #[serde(default)]
+    pub start_offset: Option<StartOffset>,
+    /// header 过滤：仅发出满足全部条件的消息；其余消息不解析、不发出，offset 照常提交
+    #[serde(default)]
+    pub header_filter: BTreeMap<String, HeaderMatch>,
+    /// 采样决策字段（JSON 顶层布尔字段）：值为 false 的消息不发出，offset 照常提交
+    #[serde(default)]
+    pub respect_sampling_field: Option<String>,
+    /// 按消息 key 去重的时间窗口（毫秒）：窗口内同一 topic 的重复 key 只发出一次。
+    /// 尽力而为、仅限本进程内存，重启或分区迁移后不保证
+    #[serde(default)]
+    pub dedup_window_ms: Option<u64>,
+    /// 去重最多记住的 key 数（默认 10000），超出时淘汰最早的 key
+    #[serde(default)]
+    pub dedup_max_keys: Option<usize>,
+    /// 负载解码器名称（见 [`crate::kafka::register_decoder`]）；未配置时原样透传。
+    /// `avro` 为保留名：按 Confluent wire format 经 `schema_registry_url` 解码为 JSON
+    #[serde(default)]
+    pub value_format: Option<String>,
+    #[serde(default)]
+    pub schema_registry_url: Option<String>,
+    /// 以标签附加消息坐标（topic/partition/offset/时间戳）；为 None 时不附加
+    #[serde(default)]
+    pub metadata_tags: Option<MetadataTags>,
     //#[serde(default)]
     //pub tags: Vec<String>,
 }
 
+impl KafkaSourceConf {
+    /// 实际使用的消费组：优先显式配置，否则按 `{key}_group` 推导
+    pub fn effective_group_id(&self) -> String {
+        self.group_id
+            .clone()
+            .unwrap_or_else(|| format!("{}_group", self.key))
+    }
+}
+
+/// 消息坐标使用的标签名；某项为空串时不附加该项。
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct MetadataTags {
+    pub topic: String,
+    pub partition: String,
+    pub offset: String,
+    /// 消息时间戳（毫秒）；broker 未提供时间戳时不附加
+    pub timestamp: String,
+}
+
+impl Default for MetadataTags {
+    fn default() -> Self {
+        Self {
+            topic: "kafka_topic".to_string(),
+            partition: "kafka_partition".to_string(),
+            offset: "kafka_offset".to_string(),
+            timestamp: "kafka_timestamp".to_string(),
+        }
+    }
+}
+
+/// 单个 header 的过滤条件：字符串要求 header 值相等，`true`/`false` 要求 header 存在/不存在。
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum HeaderMatch {
+    Present(bool),
+    Equals(String),
+}
+
+impl HeaderMatch {
+    /// # args
+    /// * `value` - 消息中该 header 的值：不存在为 `None`，存在但无值为 `Some(None)`。
+    pub fn matches(&self, value: Option<Option<&[u8]>>) -> bool {
+        match self {
+            Self::Present(expected) => value.is_some() == *expected,
+            Self::Equals(expected) => value.flatten() == Some(expected.as_bytes()),
+        }
+    }
+}
+
+/// 消费起始位置：`earliest`、`latest`、数字 offset 或 RFC3339 时间戳。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum StartOffset {
+    Earliest,
+    Latest,
+    Offset(i64),
+    /// 毫秒级 Unix 时间戳，按分区解析为对应 offset
+    Timestamp(i64),
+}
+
+impl FromStr for StartOffset {
+    type Err = String;
+
+    fn from_str(raw: &str) -> Result<Self, Self::Err> {
+        let raw = raw.trim();
+        match raw.to_ascii_lowercase().as_str() {
+            "earliest" => return Ok(Self::Earliest),
+            "latest" => return Ok(Self::Latest),
+            _ => {}
+        }
+        if let Ok(offset) = raw.parse::<i64>() {
+            if offset < 0 {
+                return Err(format!("kafka.start_offset must be >= 0, got {offset}"));
+            }
+            return Ok(Self::Offset(offset));
+        }
+        chrono::DateTime::parse_from_rfc3339(raw)
+            .map(|ts| Self::Timestamp(ts.timestamp_millis()))
+            .map_err(|e| {
+                format!(
+                    "invalid kafka.start_offset '{raw}': expected earliest, latest, \
+                     an offset or an RFC3339 timestamp ({e})"
+                )
+            })
+    }
+}
+
+impl TryFrom<String> for StartOffset {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<StartOffset> for String {
+    fn from(value: StartOffset) -> Self {
+        value.to_string()
+    }
+}
+
+impl Display for StartOffset {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Earliest => f.write_str("earliest"),
+            Self::Latest => f.write_str("latest"),
+            Self::Offset(offset) => write!(f, "{offset}"),
+            Self::Timestamp(ms) => match chrono::DateTime::from_timestamp_millis(*ms) {
+                Some(ts) => f.write_str(&ts.to_rfc3339()),
+                None => write!(f, "{ms}"),
+            },
+        }
+    }
+}
+
 impl Validate for KafkaSourceConf {
     fn validate(&self) -> OrionConfResult<()> {
         if self.brokers.trim().is_empty() {
@@ -26,6 +183,29 @@ impl Validate for KafkaSourceConf {
     }
 }
 
+/// sink 消息值的编码：按 `fmt` 渲染的文本，或经 Schema Registry 的 Avro。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ValueFormat {
+    #[default]
+    Text,
+    Avro,
+}
+
+impl FromStr for ValueFormat {
+    type Err = String;
+
+    fn from_str(raw: &str) -> Result<Self, Self::Err> {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "text" => Ok(Self::Text),
+            "avro" => Ok(Self::Avro),
+            other => Err(format!(
+                "invalid kafka.value_format '{other}'; allowed: text,avro"
+            )),
+        }
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize, PartialEq, Clone)]
 pub struct KafkaSinkConf {
     pub brokers: String,
@@ -33,6 +213,47 @@ pub struct KafkaSinkConf {
     pub num_partitions: i32,
     pub replication: i32,
     pub config: Option<Vec<String>>,
+    /// flush/stop 等待在途消息确认的超时（毫秒）；超时仍有未确认消息时 stop 返回错误
+    #[serde(default)]
+    pub flush_timeout_ms: Option<u64>,
+    /// 作为消息 key 的记录字段；缺失时发送 null key
+    #[serde(default)]
+    pub key_field: Option<String>,
+    /// 附加到每条消息的静态 header
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    /// 以 header 透传的记录 tag 名
+    #[serde(default)]
+    pub tag_headers: Vec<String>,
+    /// 单条记录发送失败时转投的死信 topic
+    #[serde(default)]
+    pub dlq_topic: Option<String>,
+    /// 按记录字段动态路由 topic；字段缺失或为空时回退到 `topic`
+    #[serde(default)]
+    pub topic_field: Option<String>,
+    /// 消息值编码；`avro` 时需配置 `schema_registry_url`
+    #[serde(default)]
+    pub value_format: ValueFormat,
+    #[serde(default)]
+    pub schema_registry_url: Option<String>,
+    /// Avro schema（JSON）：配置时注册到 subject，否则取 subject 的最新版本
+    #[serde(default)]
+    pub value_schema: Option<String>,
+    /// Schema Registry subject；缺省为 `{topic}-value`
+    #[serde(default)]
+    pub value_subject: Option<String>,
+    /// 以记录字段的整数值作为目标分区（绕过 partitioner）；字段缺失时交由 partitioner
+    #[serde(default)]
+    pub partition_field: Option<String>,
+    /// 以 `key_field` 的 murmur2 哈希对实时分区数取模作为目标分区（与 Java 默认分区器一致）
+    #[serde(default)]
+    pub partition_by_key_hash: bool,
+    /// `fmt = proto/proto-text` 使用的 FileDescriptorSet：文件路径或 base64 内容
+    #[serde(default)]
+    pub proto_descriptor: Option<String>,
+    /// 消息全名（含 package）；描述符中仅有一个消息时可省略
+    #[serde(default)]
+    pub proto_message: Option<String>,
 }
 
 impl KafkaSinkConf {
@@ -42,6 +263,13 @@ impl KafkaSinkConf {
             ..Self::default()
         }
     }
+
+    /// Avro 使用的 subject（TopicNameStrategy）。
+    pub fn effective_value_subject(&self) -> String {
+        self.value_subject
+            .clone()
+            .unwrap_or_else(|| format!("{}-value", self.topic))
+    }
 }
 
 impl Default for KafkaSourceConf {
@@ -58,6 +286,18 @@ impl Default for KafkaSourceConf {
                 "auto.offset.reset = earliest".to_string(),
             ]),
             enable: false,
+            group_id: None,
+            fields: None,
+            commit_interval_ms: None,
+            ack_channel: None,
+            start_offset: None,
+            header_filter: BTreeMap::new(),
+            respect_sampling_field: None,
+            dedup_window_ms: None,
+            dedup_max_keys: None,
+            value_format: None,
+            schema_registry_url: None,
+            metadata_tags: None,
         }
     }
 }
@@ -74,6 +314,72 @@ impl Default for KafkaSinkConf {
                 "queue.buffering.max.kbytes = 2147483647".to_string(),
                 "message.max.bytes = 10485760".to_string(),
             ]),
+            flush_timeout_ms: None,
+            key_field: None,
+            headers: BTreeMap::new(),
+            tag_headers: Vec::new(),
+            dlq_topic: None,
+            topic_field: None,
+            value_format: ValueFormat::Text,
+            schema_registry_url: None,
+            value_schema: None,
+            value_subject: None,
+            partition_field: None,
+            partition_by_key_hash: false,
+            proto_descriptor: None,
+            proto_message: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn start_offset_parses_each_variant() {
+        assert_eq!("earliest".parse(), Ok(StartOffset::Earliest));
+        assert_eq!(" LATEST ".parse(), Ok(StartOffset::Latest));
+        assert_eq!("42".parse(), Ok(StartOffset::Offset(42)));
+        assert_eq!(
+            "2024-05-01T12:00:00Z".parse(),
+            Ok(StartOffset::Timestamp(1_714_564_800_000))
+        );
+        assert_eq!(
+            "2024-05-01T20:00:00+08:00".parse::<StartOffset>(),
+            Ok(StartOffset::Timestamp(1_714_564_800_000))
+        );
+    }
+
+    #[test]
+    fn start_offset_rejects_malformed_values() {
+        for raw in ["", "-1", "yesterday", "2024-05-01", "2024-13-01T00:00:00Z"] {
+            let err = raw.parse::<StartOffset>().expect_err(raw);
+            assert!(err.contains("kafka.start_offset"), "{raw}: {err}");
+        }
+    }
+
+    #[test]
+    fn header_match_conditions() {
+        let login = HeaderMatch::Equals("login".into());
+        assert!(login.matches(Some(Some(b"login"))));
+        assert!(!login.matches(Some(Some(b"logout"))));
+        assert!(!login.matches(Some(None)));
+        assert!(!login.matches(None));
+        assert!(HeaderMatch::Present(true).matches(Some(None)));
+        assert!(!HeaderMatch::Present(true).matches(None));
+        assert!(HeaderMatch::Present(false).matches(None));
+    }
+
+    #[test]
+    fn start_offset_roundtrips_through_string() {
+        for value in [
+            StartOffset::Earliest,
+            StartOffset::Latest,
+            StartOffset::Offset(7),
+            StartOffset::Timestamp(1_714_564_800_000),
+        ] {
+            assert_eq!(value.to_string().parse(), Ok(value));
         }
     }
 }
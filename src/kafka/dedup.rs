@@ -0,0 +1,102 @@
+//! Kafka source 按消息 key 去重：在时间窗口内同一 (topic, key) 只发出第一次投递，
+//! 用于折叠 at-least-once 重投产生的短时重复。
+//!
+//! 尽力而为且仅限本进程：状态只在内存中，进程重启、分区迁移到其他实例或超出窗口/容量后
+//! 重复消息仍会发出；无 key 的消息不参与去重。
+
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+/// 未配置 `dedup_max_keys` 时最多记住的 key 数
+pub const DEFAULT_DEDUP_MAX_KEYS: usize = 10_000;
+
+type DedupKey = (String, Vec<u8>);
+
+/// 按时间与容量淘汰的 key 窗口（超出容量时淘汰最早记录的 key）。
+#[derive(Debug)]
+pub struct KeyDedup {
+    window: Duration,
+    max_keys: usize,
+    /// key -> 首次出现时间
+    seen: HashMap<DedupKey, Instant>,
+    /// 按首次出现顺序排列，用于淘汰；重新记录的 key 会留下过期条目，淘汰时按时间比对跳过
+    order: VecDeque<(DedupKey, Instant)>,
+}
+
+impl KeyDedup {
+    pub fn new(window: Duration, max_keys: usize) -> Self {
+        Self {
+            window,
+            max_keys: max_keys.max(1),
+            seen: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    /// 记录一次投递。
+    ///
+    /// # args
+    /// * `topic`/`key` - 消息所在 topic 与消息 key。
+    /// * `now` - 投递时间。
+    ///
+    /// # return
+    /// * `bool` - 窗口内已出现过该 key（应丢弃）时为 true。
+    pub fn is_duplicate(&mut self, topic: &str, key: &[u8], now: Instant) -> bool {
+        self.evict(now);
+        let id = (topic.to_string(), key.to_vec());
+        if self.seen.contains_key(&id) {
+            return true;
+        }
+        self.seen.insert(id.clone(), now);
+        self.order.push_back((id, now));
+        self.evict(now);
+        false
+    }
+
+    fn evict(&mut self, now: Instant) {
+        while let Some((id, at)) = self.order.front() {
+            let expired = now.saturating_duration_since(*at) >= self.window;
+            if !expired && self.seen.len() <= self.max_keys {
+                break;
+            }
+            if self.seen.get(id) == Some(at) {
+                self.seen.remove(id);
+            }
+            self.order.pop_front();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn duplicates_collapse_within_window_only() {
+        let start = Instant::now();
+        let mut dedup = KeyDedup::new(Duration::from_secs(10), 100);
+        assert!(!dedup.is_duplicate("t", b"k1", start));
+        assert!(dedup.is_duplicate("t", b"k1", start + Duration::from_secs(1)));
+        assert!(
+            !dedup.is_duplicate("other", b"k1", start),
+            "keys are per topic"
+        );
+        assert!(!dedup.is_duplicate("t", b"k1", start + Duration::from_secs(10)));
+        assert!(dedup.is_duplicate("t", b"k1", start + Duration::from_secs(11)));
+    }
+
+    #[test]
+    fn oldest_keys_evicted_beyond_capacity() {
+        let now = Instant::now();
+        let mut dedup = KeyDedup::new(Duration::from_secs(60), 2);
+        for key in [b"a", b"b", b"c"] {
+            assert!(!dedup.is_duplicate("t", key, now));
+        }
+        assert_eq!(dedup.seen.len(), 2);
+        assert!(
+            !dedup.is_duplicate("t", b"a", now),
+            "evicted key is forgotten"
+        );
+        assert!(dedup.is_duplicate("t", b"c", now));
+    }
+}
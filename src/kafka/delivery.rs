@@ -0,0 +1,211 @@
+//! Kafka sink 投递回执：每条消息的投递结果累加到共享计数器，flush/stop 时按窗口汇总成功/失败数量。
+//! 计数器占用固定内存，长时间不 drain 也不会积压。
+
+use std::fmt::Display;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use wp_connector_api::{SinkError, SinkReason, SinkResult};
+
+/// 一段时间窗口（或累计）的投递统计。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DeliverySummary {
+    pub delivered: u64,
+    pub failed: u64,
+    pub last_error: Option<String>,
+}
+
+impl DeliverySummary {
+    pub fn total(&self) -> u64 {
+        self.delivered + self.failed
+    }
+
+    fn merge(&mut self, other: &DeliverySummary) {
+        self.delivered += other.delivered;
+        self.failed += other.failed;
+        if other.last_error.is_some() {
+            self.last_error = other.last_error.clone();
+        }
+    }
+
+    /// 有失败时转换为 `SinkError`，错误信息携带 broker 返回的错误码描述。
+    pub fn into_result(self, topic: &str) -> SinkResult<()> {
+        if self.failed == 0 {
+            return Ok(());
+        }
+        Err(SinkError::from(SinkReason::Sink(format!(
+            "kafka delivery to '{}' failed: {} of {} message(s), last error: {}",
+            topic,
+            self.failed,
+            self.total(),
+            self.last_error.unwrap_or_default()
+        ))))
+    }
+}
+
+/// ticket 与跟踪器共享的计数。
+#[derive(Default)]
+struct DeliveryCounts {
+    delivered: AtomicU64,
+    failed: AtomicU64,
+    outstanding: AtomicU64,
+    /// 上次 drain 以来最近一次失败；与 `failed` 在同一把锁内更新
+    last_error: Mutex<Option<String>>,
+}
+
+/// 投递跟踪器：发送前领取 ticket，投递完成后把结果累加到共享计数器。
+pub struct DeliveryTracker {
+    topic: Arc<str>,
+    counts: Arc<DeliveryCounts>,
+    totals: DeliverySummary,
+}
+
+impl DeliveryTracker {
+    pub fn new(topic: &str) -> Self {
+        Self {
+            topic: Arc::from(topic),
+            counts: Arc::new(DeliveryCounts::default()),
+            totals: DeliverySummary::default(),
+        }
+    }
+
+    /// 登记一条在途消息。
+    pub fn begin(&self) -> DeliveryTicket {
+        self.counts.outstanding.fetch_add(1, Ordering::Relaxed);
+        DeliveryTicket {
+            topic: self.topic.clone(),
+            counts: self.counts.clone(),
+        }
+    }
+
+    pub fn topic(&self) -> &str {
+        &self.topic
+    }
+
+    /// 尚未回报结果的消息数。
+    pub fn outstanding(&self) -> u64 {
+        self.counts.outstanding.load(Ordering::Relaxed)
+    }
+
+    /// 收取上次 drain 以来回报的结果，返回本次窗口统计并累加到总计中。
+    pub fn drain(&mut self) -> DeliverySummary {
+        let mut last_error = self
+            .counts
+            .last_error
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let delivered = self.counts.delivered.load(Ordering::Relaxed);
+        let failed = self.counts.failed.load(Ordering::Relaxed);
+        let window = DeliverySummary {
+            delivered: delivered - self.totals.delivered,
+            failed: failed - self.totals.failed,
+            last_error: last_error.take(),
+        };
+        drop(last_error);
+        self.totals.merge(&window);
+        window
+    }
+
+    /// 自创建以来的累计统计（仅包含已 drain 的结果）。
+    pub fn totals(&self) -> &DeliverySummary {
+        &self.totals
+    }
+}
+
+/// 在途消息凭据；投递完成后调用 [`DeliveryTicket::complete`] 回报结果。
+pub struct DeliveryTicket {
+    #[cfg_attr(not(feature = "prometheus"), allow(dead_code))]
+    topic: Arc<str>,
+    counts: Arc<DeliveryCounts>,
+}
+
+impl DeliveryTicket {
+    pub fn complete<T, E: Display>(self, result: &Result<T, E>) {
+        #[cfg(feature = "prometheus")]
+        metrics::record(&self.topic, result.is_ok());
+        match result {
+            Ok(_) => {
+                self.counts.delivered.fetch_add(1, Ordering::Relaxed);
+            }
+            Err(err) => {
+                let mut last_error = self
+                    .counts
+                    .last_error
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner);
+                *last_error = Some(err.to_string());
+                self.counts.failed.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        self.counts.outstanding.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+#[cfg(feature = "prometheus")]
+mod metrics {
+    use lazy_static::lazy_static;
+    use prometheus::{IntCounterVec, register_int_counter_vec};
+
+    lazy_static! {
+        static ref KAFKA_DELIVERY: IntCounterVec = register_int_counter_vec!(
+            "wparse_kafka_delivery",
+            "Kafka sink delivery reports by result.",
+            &["topic", "result"]
+        )
+        .expect("register wparse_kafka_delivery fail");
+    }
+
+    pub(super) fn record(topic: &str, ok: bool) {
+        let result = if ok { "delivered" } else { "failed" };
+        KAFKA_DELIVERY.with_label_values(&[topic, result]).inc();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drain_counts_reports_and_maps_failure() {
+        let mut tracker = DeliveryTracker::new("events");
+        let ok = tracker.begin();
+        let bad = tracker.begin();
+        let pending = tracker.begin();
+        assert_eq!(tracker.outstanding(), 3);
+
+        ok.complete(&Ok::<(), String>(()));
+        bad.complete(&Err::<(), _>(
+            "Broker: Message size too large (MsgSizeTooLarge)",
+        ));
+        assert_eq!(tracker.outstanding(), 1);
+
+        let window = tracker.drain();
+        assert_eq!(window.delivered, 1);
+        assert_eq!(window.failed, 1);
+        let err = window.into_result("events").expect_err("failure reported");
+        assert!(format!("{err}").contains("MsgSizeTooLarge"));
+
+        pending.complete(&Ok::<(), String>(()));
+        assert!(tracker.drain().into_result("events").is_ok());
+        assert_eq!(tracker.totals().total(), 3);
+        assert_eq!(tracker.outstanding(), 0);
+    }
+
+    #[test]
+    fn windows_only_count_reports_since_last_drain() {
+        let mut tracker = DeliveryTracker::new("events");
+        for _ in 0..1000 {
+            tracker.begin().complete(&Ok::<(), String>(()));
+        }
+        tracker.begin().complete(&Err::<(), _>("timed out"));
+        let window = tracker.drain();
+        assert_eq!((window.delivered, window.failed), (1000, 1));
+        assert_eq!(window.last_error.as_deref(), Some("timed out"));
+
+        tracker.begin().complete(&Ok::<(), String>(()));
+        let window = tracker.drain();
+        assert_eq!((window.delivered, window.failed), (1, 0));
+        assert!(window.last_error.is_none());
+        assert_eq!(tracker.totals().total(), 1002);
+        assert_eq!(tracker.totals().last_error.as_deref(), Some("timed out"));
+    }
+}
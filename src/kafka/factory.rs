@@ -1,5 +1,6 @@
 use async_trait::async_trait;
 use serde_json::{Value, json};
+use std::collections::{BTreeMap, HashMap};
 
 use wp_conf_base::ConfParser;
 use wp_connector_api::{
@@ -10,18 +11,91 @@ use wp_connector_api::{
 use wp_model_core::model::fmt_def::TextFmt;
 
 use crate::WP_SRC_VAL;
+use crate::common::enrich::{EnrichConf, EnrichSink};
+use crate::common::flush_notify::FlushNotifier;
+use crate::common::quarantine::{QuarantineConf, build_quarantine_sink};
+use crate::common::retry::{self, RetryingSink};
+use crate::common::secret;
+use crate::common::stats;
+use crate::common::transform::FieldTransforms;
 use crate::kafka::{
     KafkaSink, KafkaSource,
-    config::{KafkaSinkConf, KafkaSourceConf},
+    config::{HeaderMatch, KafkaSinkConf, KafkaSourceConf, MetadataTags, StartOffset, ValueFormat},
+    decoder_names, lookup_decoder,
+    source::AVRO_VALUE_FORMAT,
 };
 
 fn build_kafka_conf_from_spec(
     spec: &wp_connector_api::SourceSpec,
-) -> SourceResult<(KafkaSourceConf, String)> {
+) -> SourceResult<KafkaSourceConf> {
     let brokers = parse_required_string(spec.params.get("brokers"), "kafka.brokers")?;
     let topics = parse_topics(spec.params.get("topic"))?;
-    let group_id = parse_required_string(spec.params.get("group_id"), "kafka.group_id")?;
-    let config = parse_config(spec.params.get("config"))?;
+    // 未配置 group_id 时由 conf 回退为 `{name}_group`；显式配置则必须非空
+    let group_id = match spec.params.get("group_id") {
+        None => None,
+        value => Some(parse_required_string(value, "kafka.group_id")?),
+    };
+    let mut config = parse_config(spec.params.get("config"))?;
+    let security = parse_security(&spec.params).map_err(SourceReason::Other)?;
+    merge_config(&mut config, security);
+    let fields = parse_fields(spec.params.get("fields"))?;
+    let commit_interval_ms = parse_commit_interval(spec.params.get("commit_interval_ms"))?;
+    let ack_channel = match spec.params.get("ack_channel") {
+        None | Some(Value::Null) => None,
+        value => Some(parse_required_string(value, "kafka.ack_channel")?),
+    };
+    if ack_channel.is_some() && commit_interval_ms.is_none() {
+        return Err(SourceReason::Other(
+            "kafka.ack_channel requires kafka.commit_interval_ms".into(),
+        )
+        .into());
+    }
+    let start_offset = parse_start_offset(spec.params.get("start_offset"))?;
+    let header_filter = parse_header_filter(spec.params.get("header_filter"))?;
+    let respect_sampling_field = match spec.params.get("respect_sampling_field") {
+        None | Some(Value::Null) => None,
+        value => Some(parse_required_string(
+            value,
+            "kafka.respect_sampling_field",
+        )?),
+    };
+    let dedup_window_ms =
+        parse_positive_u64(spec.params.get("dedup_window_ms"), "kafka.dedup_window_ms")?;
+    let dedup_max_keys =
+        parse_positive_u64(spec.params.get("dedup_max_keys"), "kafka.dedup_max_keys")?
+            .map(|n| n as usize);
+    if dedup_max_keys.is_some() && dedup_window_ms.is_none() {
+        return Err(SourceReason::Other(
+            "kafka.dedup_max_keys requires kafka.dedup_window_ms".into(),
+        )
+        .into());
+    }
+    let value_format = match spec.params.get("value_format") {
+        None | Some(Value::Null) => None,
+        value => Some(parse_required_string(value, "kafka.value_format")?),
+    };
+    let schema_registry_url = match spec.params.get("schema_registry_url") {
+        None | Some(Value::Null) => None,
+        value => Some(parse_required_string(value, "kafka.schema_registry_url")?),
+    };
+    let avro = value_format.as_deref() == Some(AVRO_VALUE_FORMAT);
+    if avro && schema_registry_url.is_none() {
+        return Err(SourceReason::Other(
+            "kafka.schema_registry_url is required when value_format=avro".into(),
+        )
+        .into());
+    }
+    if let Some(name) = &value_format
+        && !avro
+        && lookup_decoder(name).is_none()
+    {
+        return Err(SourceReason::Other(format!(
+            "unknown kafka.value_format '{name}'; registered: {},{AVRO_VALUE_FORMAT}",
+            decoder_names().join(",")
+        ))
+        .into());
+    }
+    let metadata_tags = parse_metadata_tags(spec.params.get("metadata_tags"))?;
 
     let conf = KafkaSourceConf {
         key: spec.name.clone(),
@@ -30,8 +104,20 @@ fn build_kafka_conf_from_spec(
         config,
         //TODO: use spec.enable
         enable: true,
+        group_id,
+        fields,
+        commit_interval_ms,
+        ack_channel,
+        start_offset,
+        header_filter,
+        respect_sampling_field,
+        dedup_window_ms,
+        dedup_max_keys,
+        value_format,
+        schema_registry_url,
+        metadata_tags,
     };
-    Ok((conf, group_id))
+    Ok(conf)
 }
 
 fn build_kafka_sink_conf_from_spec(spec: &SinkSpec) -> SinkResult<(KafkaSinkConf, TextFmt)> {
@@ -40,8 +126,89 @@ fn build_kafka_sink_conf_from_spec(spec: &SinkSpec) -> SinkResult<(KafkaSinkConf
     let num_partitions =
         parse_positive_i32(spec.params.get("num_partitions"), "kafka.num_partitions")?;
     let replication = parse_positive_i32(spec.params.get("replication"), "kafka.replication")?;
-    let config = parse_sink_config(spec.params.get("config"))?;
+    let mut config = parse_sink_config(spec.params.get("config"))?;
+    let security = parse_security(&spec.params).map_err(SinkReason::sink)?;
+    merge_config(&mut config, security);
+    merge_config(&mut config, parse_acks(spec.params.get("acks"))?);
+    let flush_timeout_ms = parse_positive_i32(
+        spec.params.get("flush_timeout_ms"),
+        "kafka.flush_timeout_ms",
+    )?
+    .map(|ms| ms as u64);
+    let key_field = match spec.params.get("key_field") {
+        None | Some(Value::Null) => None,
+        value => Some(parse_sink_required_string(value, "kafka.key_field")?),
+    };
+    let headers = parse_headers(spec.params.get("headers"))?;
+    let tag_headers = parse_tag_headers(spec.params.get("tag_headers"))?;
+    let dlq_topic = match spec.params.get("dlq_topic") {
+        None | Some(Value::Null) => None,
+        value => Some(parse_sink_required_string(value, "kafka.dlq_topic")?),
+    };
+    let topic_field = match spec.params.get("topic_field") {
+        None | Some(Value::Null) => None,
+        value => Some(parse_sink_required_string(value, "kafka.topic_field")?),
+    };
+    if dlq_topic.as_deref() == Some(topic.as_str()) {
+        return Err(SinkReason::sink("kafka.dlq_topic must differ from kafka.topic").into());
+    }
     let fmt = parse_sink_fmt(spec.params.get("fmt"))?;
+    let value_format = match spec.params.get("value_format") {
+        None | Some(Value::Null) => ValueFormat::default(),
+        value => parse_sink_required_string(value, "kafka.value_format")?
+            .parse()
+            .map_err(SinkReason::sink)?,
+    };
+    let optional = |key: &str| match spec.params.get(key) {
+        None | Some(Value::Null) => Ok(None),
+        value => parse_sink_required_string(value, &format!("kafka.{key}")).map(Some),
+    };
+    let schema_registry_url = optional("schema_registry_url")?;
+    let value_schema = optional("value_schema")?;
+    let value_subject = optional("value_subject")?;
+    // proto 格式需要描述符才能编码，缺失时在校验阶段即报错而非运行时逐条失败
+    let proto_descriptor = optional("proto_descriptor")?;
+    let proto_message = optional("proto_message")?;
+    let fmt_name = spec
+        .params
+        .get("fmt")
+        .and_then(Value::as_str)
+        .map(str::trim);
+    if let Some(name @ ("proto" | "proto-text")) = fmt_name
+        && proto_descriptor.is_none()
+    {
+        return Err(
+            SinkReason::sink(format!("kafka.fmt={name} requires kafka.proto_descriptor")).into(),
+        );
+    }
+    if proto_message.is_some() && proto_descriptor.is_none() {
+        return Err(SinkReason::sink("kafka.proto_message requires kafka.proto_descriptor").into());
+    }
+    if value_format == ValueFormat::Avro && schema_registry_url.is_none() {
+        return Err(SinkReason::sink(
+            "kafka.schema_registry_url is required when value_format=avro",
+        )
+        .into());
+    }
+    let partition_field = optional("partition_field")?;
+    let partition_by_key_hash = match spec.params.get("partition_by_key_hash") {
+        None | Some(Value::Null) => false,
+        Some(Value::Bool(b)) => *b,
+        Some(_) => {
+            return Err(SinkReason::sink("kafka.partition_by_key_hash must be a boolean").into());
+        }
+    };
+    if partition_by_key_hash && partition_field.is_some() {
+        return Err(SinkReason::sink(
+            "kafka.partition_field and kafka.partition_by_key_hash are mutually exclusive",
+        )
+        .into());
+    }
+    if partition_by_key_hash && key_field.is_none() {
+        return Err(
+            SinkReason::sink("kafka.partition_by_key_hash requires kafka.key_field").into(),
+        );
+    }
 
     let conf = KafkaSinkConf {
         brokers,
@@ -49,10 +216,77 @@ fn build_kafka_sink_conf_from_spec(spec: &SinkSpec) -> SinkResult<(KafkaSinkConf
         num_partitions: num_partitions.unwrap_or_default(),
         replication: replication.unwrap_or_default(),
         config,
+        flush_timeout_ms,
+        key_field,
+        headers,
+        tag_headers,
+        dlq_topic,
+        topic_field,
+        value_format,
+        schema_registry_url,
+        value_schema,
+        value_subject,
+        partition_field,
+        partition_by_key_hash,
+        proto_descriptor,
+        proto_message,
     };
     Ok((conf, fmt))
 }
 
+/// 安全相关的一等参数 -> librdkafka 配置行（`key=value`）。
+fn parse_security(params: &ParamMap) -> Result<Vec<String>, String> {
+    const KEYS: [(&str, &str); 5] = [
+        ("security_protocol", "security.protocol"),
+        ("sasl_mechanism", "sasl.mechanism"),
+        ("sasl_username", "sasl.username"),
+        ("sasl_password", "sasl.password"),
+        ("ssl_ca_location", "ssl.ca.location"),
+    ];
+    let mut values = HashMap::new();
+    for (param, _) in KEYS {
+        match params.get(param) {
+            None | Some(Value::Null) => {}
+            Some(Value::String(raw)) if !raw.trim().is_empty() => {
+                values.insert(param, raw.trim().to_string());
+            }
+            Some(_) => return Err(format!("kafka.{param} must be a non-empty string")),
+        }
+    }
+    if let Some(protocol) = values.get("security_protocol") {
+        let protocol = protocol.to_ascii_uppercase();
+        if !matches!(
+            protocol.as_str(),
+            "PLAINTEXT" | "SSL" | "SASL_PLAINTEXT" | "SASL_SSL"
+        ) {
+            return Err(format!(
+                "invalid kafka.security_protocol: '{protocol}'; allowed: PLAINTEXT,SSL,SASL_PLAINTEXT,SASL_SSL"
+            ));
+        }
+        if protocol.starts_with("SASL_") {
+            if !values.contains_key("sasl_mechanism") {
+                return Err(format!("kafka.sasl_mechanism is required for {protocol}"));
+            }
+            if !values.contains_key("sasl_username") {
+                return Err(format!("kafka.sasl_username is required for {protocol}"));
+            }
+        }
+        values.insert("security_protocol", protocol);
+    }
+    Ok(KEYS
+        .iter()
+        .filter_map(|(param, key)| values.get(param).map(|v| format!("{key}={v}")))
+        .collect())
+}
+
+/// 追加安全配置到 `config`，与手写配置行同名时以一等参数为准（靠后生效）。
+fn merge_config(config: &mut Option<Vec<String>>, extra: Vec<String>) {
+    if extra.is_empty() {
+        return;
+    }
+    config.get_or_insert_with(Vec::new).extend(extra);
+}
+
 fn parse_required_string(value: Option<&Value>, field: &str) -> SourceResult<String> {
     if let Some(Value::String(raw)) = value {
         let trimmed = raw.trim();
@@ -137,6 +371,134 @@ fn parse_config(value: Option<&Value>) -> SourceResult<Option<Vec<String>>> {
     }
 }
 
+/// `commit_interval_ms`：批量提交间隔，必须为正整数。
+fn parse_commit_interval(value: Option<&Value>) -> SourceResult<Option<u64>> {
+    match value {
+        None | Some(Value::Null) => Ok(None),
+        Some(v) => match v.as_u64() {
+            Some(ms) if ms > 0 => Ok(Some(ms)),
+            _ => Err(SourceReason::Other("kafka.commit_interval_ms must be > 0".into()).into()),
+        },
+    }
+}
+
+/// 可选的正整数参数。
+fn parse_positive_u64(value: Option<&Value>, field: &str) -> SourceResult<Option<u64>> {
+    match value {
+        None | Some(Value::Null) => Ok(None),
+        Some(v) => match v.as_u64() {
+            Some(n) if n > 0 => Ok(Some(n)),
+            _ => Err(SourceReason::Other(format!("{field} must be > 0")).into()),
+        },
+    }
+}
+
+/// `start_offset`：`earliest`/`latest`/数字 offset/RFC3339 时间戳。
+fn parse_start_offset(value: Option<&Value>) -> SourceResult<Option<StartOffset>> {
+    let parsed = match value {
+        None | Some(Value::Null) => return Ok(None),
+        Some(Value::String(raw)) => raw.parse(),
+        Some(Value::Number(n)) => n.to_string().parse(),
+        Some(_) => Err("kafka.start_offset must be a string or integer".to_string()),
+    };
+    parsed.map(Some).map_err(|e| SourceReason::Other(e).into())
+}
+
+/// `header_filter`：header 名 -> 期望值（字符串）或是否存在（布尔）。
+fn parse_header_filter(value: Option<&Value>) -> SourceResult<BTreeMap<String, HeaderMatch>> {
+    let map = match value {
+        None | Some(Value::Null) => return Ok(BTreeMap::new()),
+        Some(Value::Object(map)) => map,
+        Some(_) => {
+            return Err(SourceReason::Other("kafka.header_filter must be a table".into()).into());
+        }
+    };
+    let mut filter = BTreeMap::new();
+    for (key, value) in map {
+        let key = key.trim();
+        if key.is_empty() {
+            return Err(
+                SourceReason::Other("kafka.header_filter keys must not be empty".into()).into(),
+            );
+        }
+        let cond = match value {
+            Value::String(s) => HeaderMatch::Equals(s.clone()),
+            Value::Bool(b) => HeaderMatch::Present(*b),
+            _ => {
+                return Err(SourceReason::Other(format!(
+                    "kafka.header_filter.{key} must be a string or bool"
+                ))
+                .into());
+            }
+        };
+        filter.insert(key.to_string(), cond);
+    }
+    Ok(filter)
+}
+
+/// `metadata_tags`：`true` 使用默认标签名，表则按项覆盖（空串表示不附加该项）。
+fn parse_metadata_tags(value: Option<&Value>) -> SourceResult<Option<MetadataTags>> {
+    let map = match value {
+        None | Some(Value::Null) | Some(Value::Bool(false)) => return Ok(None),
+        Some(Value::Bool(true)) => return Ok(Some(MetadataTags::default())),
+        Some(Value::Object(map)) => map,
+        Some(_) => {
+            return Err(
+                SourceReason::Other("kafka.metadata_tags must be a bool or table".into()).into(),
+            );
+        }
+    };
+    let mut tags = MetadataTags::default();
+    for (key, value) in map {
+        let slot = match key.as_str() {
+            "topic" => &mut tags.topic,
+            "partition" => &mut tags.partition,
+            "offset" => &mut tags.offset,
+            "timestamp" => &mut tags.timestamp,
+            other => {
+                return Err(SourceReason::Other(format!(
+                    "unknown kafka.metadata_tags.{other}; allowed: topic,partition,offset,timestamp"
+                ))
+                .into());
+            }
+        };
+        let Some(name) = value.as_str() else {
+            return Err(
+                SourceReason::Other(format!("kafka.metadata_tags.{key} must be a string")).into(),
+            );
+        };
+        *slot = name.trim().to_string();
+    }
+    Ok(Some(tags))
+}
+
+fn parse_fields(value: Option<&Value>) -> SourceResult<Option<Vec<String>>> {
+    match value {
+        None => Ok(None),
+        Some(Value::Array(values)) => {
+            let mut fields = Vec::new();
+            for value in values {
+                let Some(raw) = value.as_str() else {
+                    return Err(
+                        SourceReason::Other("kafka.fields entries must be strings".into()).into(),
+                    );
+                };
+                let trimmed = raw.trim();
+                if trimmed.is_empty() {
+                    continue;
+                }
+                fields.push(trimmed.to_string());
+            }
+            if fields.is_empty() {
+                Ok(None)
+            } else {
+                Ok(Some(fields))
+            }
+        }
+        Some(_) => Err(SourceReason::Other("kafka.fields must be an array".into()).into()),
+    }
+}
+
 fn parse_sink_required_string(value: Option<&Value>, field: &str) -> SinkResult<String> {
     if let Some(Value::String(raw)) = value {
         let trimmed = raw.trim();
@@ -196,6 +558,63 @@ fn parse_sink_config(value: Option<&Value>) -> SinkResult<Option<Vec<String>>> {
     }
 }
 
+/// `acks`：生产者确认级别，接受 `0`/`1`/`all`/`-1`（字符串或整数）。
+fn parse_acks(value: Option<&Value>) -> SinkResult<Vec<String>> {
+    let acks = match value {
+        None | Some(Value::Null) => return Ok(Vec::new()),
+        Some(Value::String(raw)) => raw.trim().to_ascii_lowercase(),
+        Some(Value::Number(n)) => n.to_string(),
+        Some(_) => return Err(SinkReason::sink("kafka.acks must be a string or integer").into()),
+    };
+    if !matches!(acks.as_str(), "0" | "1" | "all" | "-1") {
+        return Err(
+            SinkReason::sink(format!("invalid kafka.acks: '{acks}'; allowed: 0,1,all,-1")).into(),
+        );
+    }
+    Ok(vec![format!("acks={acks}")])
+}
+
+/// `headers`：静态消息 header（表，值须为字符串，允许空值）。
+fn parse_headers(value: Option<&Value>) -> SinkResult<BTreeMap<String, String>> {
+    let map = match value {
+        None | Some(Value::Null) => return Ok(BTreeMap::new()),
+        Some(Value::Object(map)) => map,
+        Some(_) => return Err(SinkReason::sink("kafka.headers must be a table").into()),
+    };
+    let mut headers = BTreeMap::new();
+    for (key, value) in map {
+        let key = key.trim();
+        if key.is_empty() {
+            return Err(SinkReason::sink("kafka.headers keys must not be empty").into());
+        }
+        let Some(value) = value.as_str() else {
+            return Err(SinkReason::sink(format!("kafka.headers.{key} must be a string")).into());
+        };
+        headers.insert(key.to_string(), value.to_string());
+    }
+    Ok(headers)
+}
+
+/// `tag_headers`：以 header 透传的 tag 名（字符串数组）。
+fn parse_tag_headers(value: Option<&Value>) -> SinkResult<Vec<String>> {
+    let values = match value {
+        None | Some(Value::Null) => return Ok(Vec::new()),
+        Some(Value::Array(values)) => values,
+        Some(_) => return Err(SinkReason::sink("kafka.tag_headers must be an array").into()),
+    };
+    let mut tags = Vec::new();
+    for value in values {
+        let tag = value.as_str().map(str::trim).unwrap_or("");
+        if tag.is_empty() {
+            return Err(
+                SinkReason::sink("kafka.tag_headers entries must be non-empty strings").into(),
+            );
+        }
+        tags.push(tag.to_string());
+    }
+    Ok(tags)
+}
+
 fn parse_sink_fmt(value: Option<&Value>) -> SinkResult<TextFmt> {
     match value {
         None => Ok(TextFmt::Json),
@@ -230,7 +649,10 @@ impl wp_connector_api::SourceFactory for KafkaSourceFactory {
     }
 
     fn validate_spec(&self, spec: &wp_connector_api::SourceSpec) -> SourceResult<()> {
+        let spec = &secret::resolve_source_spec(spec)?;
         build_kafka_conf_from_spec(spec)?;
+        QuarantineConf::from_params(&spec.params)
+            .map_err(|err| SourceReason::Other(err.to_string()))?;
         Ok(())
     }
 
@@ -239,7 +661,9 @@ impl wp_connector_api::SourceFactory for KafkaSourceFactory {
         spec: &wp_connector_api::SourceSpec,
         _ctx: &wp_connector_api::SourceBuildCtx,
     ) -> SourceResult<SourceSvcIns> {
-        let (conf, group_id) = build_kafka_conf_from_spec(spec)?;
+        let spec = &secret::resolve_source_spec(spec)?;
+        let conf = build_kafka_conf_from_spec(spec)?;
+        let group_id = conf.effective_group_id();
 
         let mut meta_tags = Tags::from_parse(&spec.tags);
         let access_source = spec.kind.clone();
@@ -247,6 +671,18 @@ impl wp_connector_api::SourceFactory for KafkaSourceFactory {
         let source = KafkaSource::new(spec.name.clone(), meta_tags.clone(), &group_id, &conf)
             .await
             .map_err(|err| SourceReason::Other(err.to_string()))?;
+        let quarantine =
+            match QuarantineConf::from_params(&spec.params)
+                .map_err(|err| SourceReason::Other(err.to_string()))?
+            {
+                Some(qconf) => Some(build_quarantine_sink(&qconf, &spec.name).await.map_err(
+                    |err| SourceReason::Other(format!("build quarantine failed: {err}")),
+                )?),
+                None => None,
+            };
+        let source = source
+            .with_quarantine(quarantine)
+            .with_stats(stats::register(&spec.name, self.kind()));
 
         let mut meta = SourceMeta::new(spec.name.clone(), spec.kind.clone());
         meta.tags = meta_tags;
@@ -264,15 +700,29 @@ impl SinkFactory for KafkaSinkFactory {
     }
 
     fn validate_spec(&self, spec: &SinkSpec) -> SinkResult<()> {
+        let spec = &secret::resolve_sink_spec(spec)?;
         build_kafka_sink_conf_from_spec(spec)?;
+        EnrichConf::from_params(&spec.params)?;
+        retry::validate_params(&spec.params)?;
+        FieldTransforms::from_params(&spec.params)?;
+        FlushNotifier::from_params(&spec.params)?;
         Ok(())
     }
 
     async fn build(&self, spec: &SinkSpec, _ctx: &SinkBuildCtx) -> SinkResult<SinkHandle> {
+        let spec = &secret::resolve_sink_spec(spec)?;
         let (conf, fmt) = build_kafka_sink_conf_from_spec(spec)?;
-        let sink = KafkaSink::from_conf(&conf, fmt).await.map_err(|err| {
-            SinkError::from(SinkReason::sink(format!("init kafka sink failed: {err}")))
-        })?;
+        let enrich = EnrichConf::from_params(&spec.params)?;
+        let transforms = FieldTransforms::from_params(&spec.params)?;
+        let sink = KafkaSink::from_conf(&conf, fmt)
+            .await
+            .map_err(|err| {
+                SinkError::from(SinkReason::sink(format!("init kafka sink failed: {err}")))
+            })?
+            .with_stats(stats::register(&spec.name, self.kind()))
+            .with_flush_notifier(FlushNotifier::from_params(&spec.params)?);
+        let sink = RetryingSink::from_spec(sink, spec).await?;
+        let sink = EnrichSink::new(sink, enrich).with_transforms(transforms);
         Ok(SinkHandle::new(Box::new(sink)))
     }
 }
@@ -283,10 +733,33 @@ impl SourceDefProvider for KafkaSourceFactory {
             id: "kafka_src".into(),
             kind: self.kind().into(),
             scope: ConnectorScope::Source,
-            allow_override: vec!["brokers", "topic", "group_id", "config"]
-                .into_iter()
-                .map(str::to_string)
-                .collect(),
+            allow_override: vec![
+                "brokers",
+                "topic",
+                "group_id",
+                "config",
+                "fields",
+                "commit_interval_ms",
+                "ack_channel",
+                "start_offset",
+                "header_filter",
+                "respect_sampling_field",
+                "dedup_window_ms",
+                "dedup_max_keys",
+                "value_format",
+                "schema_registry_url",
+                "metadata_tags",
+                "quarantine",
+                "security_protocol",
+                "sasl_mechanism",
+                "sasl_username",
+                "sasl_password",
+                "ssl_ca_location",
+                "secret_ref",
+            ]
+            .into_iter()
+            .map(str::to_string)
+            .collect(),
             default_params: kafka_source_defaults(),
             origin: Some("wp-connectors:kafka_source".into()),
         }
@@ -306,6 +779,34 @@ impl SinkDefProvider for KafkaSinkFactory {
                 "num_partitions",
                 "replication",
                 "config",
+                "acks",
+                "flush_timeout_ms",
+                "key_field",
+                "headers",
+                "tag_headers",
+                "dlq_topic",
+                "topic_field",
+                "partition_field",
+                "partition_by_key_hash",
+                "value_format",
+                "schema_registry_url",
+                "value_schema",
+                "value_subject",
+                "proto_descriptor",
+                "proto_message",
+                "security_protocol",
+                "sasl_mechanism",
+                "sasl_username",
+                "sasl_password",
+                "ssl_ca_location",
+                "secret_ref",
+                "enrich",
+                "enrich_overwrite",
+                "field_transforms",
+                "retry",
+                "write_deadline_ms",
+                "quarantine",
+                "flush_notify",
             ]
             .into_iter()
             .map(str::to_string)
@@ -377,13 +878,13 @@ mod tests {
         );
         let spec = build_source_spec(params);
 
-        let (conf, group_id) = build_kafka_conf_from_spec(&spec).expect("valid spec");
+        let conf = build_kafka_conf_from_spec(&spec).expect("valid spec");
         assert_eq!(conf.brokers, "localhost:9092");
         assert_eq!(
             conf.topic,
             vec!["topic_a".to_string(), "topic_b".to_string()]
         );
-        assert_eq!(group_id, "group-a");
+        assert_eq!(conf.effective_group_id(), "group-a");
         assert_eq!(
             conf.config.as_ref().unwrap(),
             &vec![
@@ -414,18 +915,273 @@ mod tests {
         params.insert("config".into(), json!("auto.offset.reset=latest"));
         let spec = build_source_spec(params);
 
-        let (conf, group_id) = build_kafka_conf_from_spec(&spec).expect("valid spec");
+        let conf = build_kafka_conf_from_spec(&spec).expect("valid spec");
         assert_eq!(
             conf.topic,
             vec!["topic_a".to_string(), "topic_b".to_string()]
         );
-        assert_eq!(group_id, "group-a");
+        assert_eq!(conf.effective_group_id(), "group-a");
         assert_eq!(
             conf.config,
             Some(vec!["auto.offset.reset=latest".to_string()])
         );
     }
 
+    #[test]
+    fn kafka_conf_from_spec_accepts_single_topic_and_rejects_empty_array() {
+        let mut params = BTreeMap::new();
+        params.insert("brokers".into(), json!("localhost:9092"));
+        params.insert("group_id".into(), json!("group-a"));
+        params.insert("topic".into(), json!("topic_a"));
+        let conf =
+            build_kafka_conf_from_spec(&build_source_spec(params.clone())).expect("single topic");
+        assert_eq!(conf.topic, vec!["topic_a".to_string()]);
+
+        params.insert("topic".into(), json!(["topic_a", "topic_b", "topic_c"]));
+        let conf =
+            build_kafka_conf_from_spec(&build_source_spec(params.clone())).expect("multi topics");
+        assert_eq!(conf.topic.len(), 3);
+
+        params.insert("topic".into(), json!([]));
+        let err = build_kafka_conf_from_spec(&build_source_spec(params.clone()))
+            .expect_err("empty array");
+        assert!(format!("{err}").contains("kafka.topic must not be empty"));
+
+        params.insert("topic".into(), json!(["topic_a", 1]));
+        let err =
+            build_kafka_conf_from_spec(&build_source_spec(params)).expect_err("non-string entry");
+        assert!(format!("{err}").contains("kafka.topic entries must be strings"));
+    }
+
+    #[test]
+    fn kafka_conf_from_spec_honors_group_id_with_name_fallback() {
+        let mut params = BTreeMap::new();
+        params.insert("brokers".into(), json!("localhost:9092"));
+        params.insert("topic".into(), json!("topic_a"));
+        params.insert("group_id".into(), json!(" custom-group "));
+        let conf =
+            build_kafka_conf_from_spec(&build_source_spec(params.clone())).expect("valid spec");
+        assert_eq!(conf.group_id.as_deref(), Some("custom-group"));
+        assert_eq!(conf.effective_group_id(), "custom-group");
+
+        params.remove("group_id");
+        let conf = build_kafka_conf_from_spec(&build_source_spec(params.clone()))
+            .expect("group_id optional");
+        assert_eq!(conf.group_id, None);
+        assert_eq!(conf.effective_group_id(), "kafka_source_group");
+
+        params.insert("group_id".into(), json!("  "));
+        let err = build_kafka_conf_from_spec(&build_source_spec(params)).expect_err("empty");
+        assert!(format!("{err}").contains("kafka.group_id"));
+    }
+
+    #[test]
+    fn kafka_conf_from_spec_parses_field_allowlist() {
+        let mut params = BTreeMap::new();
+        params.insert("brokers".into(), json!("localhost:9092"));
+        params.insert("topic".into(), json!("topic_a"));
+        params.insert("group_id".into(), json!("group-a"));
+        params.insert("fields".into(), json!(["host", " level ", ""]));
+        let spec = build_source_spec(params);
+
+        let conf = build_kafka_conf_from_spec(&spec).expect("valid spec");
+        assert_eq!(
+            conf.fields,
+            Some(vec!["host".to_string(), "level".to_string()])
+        );
+
+        let mut params = BTreeMap::new();
+        params.insert("brokers".into(), json!("localhost:9092"));
+        params.insert("topic".into(), json!("topic_a"));
+        params.insert("group_id".into(), json!("group-a"));
+        params.insert("fields".into(), json!("host"));
+        let err = build_kafka_conf_from_spec(&build_source_spec(params)).expect_err("not array");
+        assert!(format!("{err}").contains("kafka.fields"));
+    }
+
+    #[test]
+    fn kafka_conf_from_spec_parses_commit_interval() {
+        let mut params = BTreeMap::new();
+        params.insert("brokers".into(), json!("localhost:9092"));
+        params.insert("topic".into(), json!("topic_a"));
+        let conf = build_kafka_conf_from_spec(&build_source_spec(params.clone())).expect("valid");
+        assert_eq!(conf.commit_interval_ms, None);
+
+        params.insert("commit_interval_ms".into(), json!(500));
+        let conf = build_kafka_conf_from_spec(&build_source_spec(params.clone())).expect("valid");
+        assert_eq!(conf.commit_interval_ms, Some(500));
+
+        params.insert("commit_interval_ms".into(), json!(0));
+        let err = build_kafka_conf_from_spec(&build_source_spec(params)).expect_err("zero");
+        assert!(format!("{err}").contains("kafka.commit_interval_ms"));
+    }
+
+    #[test]
+    fn kafka_conf_from_spec_ack_channel_requires_commit_interval() {
+        let mut params = BTreeMap::new();
+        params.insert("brokers".into(), json!("localhost:9092"));
+        params.insert("topic".into(), json!("topic_a"));
+        params.insert("ack_channel".into(), json!("events-ckpt"));
+        let err = build_kafka_conf_from_spec(&build_source_spec(params.clone()))
+            .expect_err("no commit interval");
+        assert!(format!("{err}").contains("kafka.ack_channel"));
+
+        params.insert("commit_interval_ms".into(), json!(500));
+        let conf = build_kafka_conf_from_spec(&build_source_spec(params)).expect("valid");
+        assert_eq!(conf.ack_channel.as_deref(), Some("events-ckpt"));
+    }
+
+    #[test]
+    fn kafka_conf_from_spec_parses_start_offset() {
+        let mut params = BTreeMap::new();
+        params.insert("brokers".into(), json!("localhost:9092"));
+        params.insert("topic".into(), json!("topic_a"));
+        params.insert("start_offset".into(), json!(128));
+        let conf = build_kafka_conf_from_spec(&build_source_spec(params.clone())).expect("valid");
+        assert_eq!(conf.start_offset, Some(StartOffset::Offset(128)));
+
+        params.insert("start_offset".into(), json!("2024-05-01T12:00:00Z"));
+        let conf = build_kafka_conf_from_spec(&build_source_spec(params.clone())).expect("valid");
+        assert_eq!(
+            conf.start_offset,
+            Some(StartOffset::Timestamp(1_714_564_800_000))
+        );
+
+        params.insert("start_offset".into(), json!("2024-05-01 12:00"));
+        let err = build_kafka_conf_from_spec(&build_source_spec(params)).expect_err("bad ts");
+        assert!(format!("{err}").contains("invalid kafka.start_offset"));
+    }
+
+    #[test]
+    fn kafka_conf_from_spec_parses_header_filter() {
+        let mut params = BTreeMap::new();
+        params.insert("brokers".into(), json!("localhost:9092"));
+        params.insert("topic".into(), json!("topic_a"));
+        params.insert(
+            "header_filter".into(),
+            json!({ "event_type": "login", "trace_id": true }),
+        );
+        let conf = build_kafka_conf_from_spec(&build_source_spec(params.clone())).expect("valid");
+        assert_eq!(
+            conf.header_filter,
+            BTreeMap::from([
+                (
+                    "event_type".to_string(),
+                    HeaderMatch::Equals("login".into())
+                ),
+                ("trace_id".to_string(), HeaderMatch::Present(true)),
+            ])
+        );
+
+        params.insert("header_filter".into(), json!({ "event_type": 1 }));
+        let err = build_kafka_conf_from_spec(&build_source_spec(params)).expect_err("bad value");
+        assert!(format!("{err}").contains("kafka.header_filter.event_type"));
+    }
+
+    #[test]
+    fn kafka_conf_from_spec_parses_respect_sampling_field() {
+        let mut params = BTreeMap::new();
+        params.insert("brokers".into(), json!("localhost:9092"));
+        params.insert("topic".into(), json!("topic_a"));
+        let conf = build_kafka_conf_from_spec(&build_source_spec(params.clone())).expect("valid");
+        assert_eq!(conf.respect_sampling_field, None);
+
+        params.insert("respect_sampling_field".into(), json!("sampled"));
+        let conf = build_kafka_conf_from_spec(&build_source_spec(params.clone())).expect("valid");
+        assert_eq!(conf.respect_sampling_field.as_deref(), Some("sampled"));
+
+        params.insert("respect_sampling_field".into(), json!(" "));
+        assert!(build_kafka_conf_from_spec(&build_source_spec(params)).is_err());
+    }
+
+    #[test]
+    fn kafka_conf_from_spec_parses_dedup_window() {
+        let mut params = BTreeMap::new();
+        params.insert("brokers".into(), json!("localhost:9092"));
+        params.insert("topic".into(), json!("topic_a"));
+        params.insert("dedup_max_keys".into(), json!(100));
+        let err = build_kafka_conf_from_spec(&build_source_spec(params.clone()))
+            .expect_err("max keys without window");
+        assert!(format!("{err}").contains("dedup_window_ms"));
+
+        params.insert("dedup_window_ms".into(), json!(5000));
+        let conf = build_kafka_conf_from_spec(&build_source_spec(params.clone())).expect("valid");
+        assert_eq!(conf.dedup_window_ms, Some(5000));
+        assert_eq!(conf.dedup_max_keys, Some(100));
+
+        params.insert("dedup_window_ms".into(), json!(0));
+        assert!(build_kafka_conf_from_spec(&build_source_spec(params)).is_err());
+    }
+
+    #[test]
+    fn kafka_conf_from_spec_accepts_avro_with_registry() {
+        let mut params = BTreeMap::new();
+        params.insert("brokers".into(), json!("localhost:9092"));
+        params.insert("topic".into(), json!("topic_a"));
+        params.insert("value_format".into(), json!("avro"));
+        let err = build_kafka_conf_from_spec(&build_source_spec(params.clone()))
+            .expect_err("avro without registry");
+        assert!(
+            format!("{err}").contains("kafka.schema_registry_url"),
+            "{err}"
+        );
+
+        params.insert("schema_registry_url".into(), json!("http://registry:8081"));
+        let conf = build_kafka_conf_from_spec(&build_source_spec(params)).expect("valid");
+        assert_eq!(conf.value_format.as_deref(), Some("avro"));
+        assert_eq!(
+            conf.schema_registry_url.as_deref(),
+            Some("http://registry:8081")
+        );
+    }
+
+    #[test]
+    fn kafka_conf_from_spec_parses_metadata_tags() {
+        let mut params = BTreeMap::new();
+        params.insert("brokers".into(), json!("localhost:9092"));
+        params.insert("topic".into(), json!("topic_a"));
+        let conf = build_kafka_conf_from_spec(&build_source_spec(params.clone())).expect("valid");
+        assert_eq!(conf.metadata_tags, None);
+
+        params.insert("metadata_tags".into(), json!(true));
+        let conf = build_kafka_conf_from_spec(&build_source_spec(params.clone())).expect("valid");
+        assert_eq!(conf.metadata_tags, Some(MetadataTags::default()));
+
+        params.insert(
+            "metadata_tags".into(),
+            json!({ "offset": "src_offset", "timestamp": "" }),
+        );
+        let conf = build_kafka_conf_from_spec(&build_source_spec(params.clone())).expect("valid");
+        let tags = conf.metadata_tags.expect("enabled");
+        assert_eq!(tags.offset, "src_offset");
+        assert_eq!(tags.partition, "kafka_partition");
+        assert!(tags.timestamp.is_empty());
+
+        params.insert("metadata_tags".into(), json!({ "key": "k" }));
+        let err = build_kafka_conf_from_spec(&build_source_spec(params)).expect_err("unknown");
+        assert!(
+            format!("{err}").contains("kafka.metadata_tags.key"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn kafka_conf_from_spec_requires_registered_value_format() {
+        let mut params = BTreeMap::new();
+        params.insert("brokers".into(), json!("localhost:9092"));
+        params.insert("topic".into(), json!("topic_a"));
+        let conf = build_kafka_conf_from_spec(&build_source_spec(params.clone())).expect("valid");
+        assert_eq!(conf.value_format, None);
+
+        params.insert("value_format".into(), json!("json"));
+        let conf = build_kafka_conf_from_spec(&build_source_spec(params.clone())).expect("valid");
+        assert_eq!(conf.value_format.as_deref(), Some("json"));
+
+        params.insert("value_format".into(), json!("factory_unit_unknown"));
+        let err = build_kafka_conf_from_spec(&build_source_spec(params)).expect_err("unknown");
+        assert!(format!("{err}").contains("registered: "), "{err}");
+    }
+
     #[test]
     fn kafka_sink_conf_from_spec_parses_fields() {
         let mut params = BTreeMap::new();
@@ -468,6 +1224,256 @@ mod tests {
         assert!(msg.contains("invalid fmt"));
     }
 
+    #[test]
+    fn kafka_sink_conf_from_spec_requires_descriptor_for_proto_fmt() {
+        let mut params = BTreeMap::new();
+        params.insert("brokers".into(), json!("localhost:9092"));
+        params.insert("topic".into(), json!("sink-topic"));
+        for fmt in ["proto", "proto-text"] {
+            params.insert("fmt".into(), json!(fmt));
+            let err = build_kafka_sink_conf_from_spec(&build_sink_spec(params.clone()))
+                .expect_err("proto without descriptor");
+            assert!(format!("{err}").contains("kafka.proto_descriptor"), "{err}");
+        }
+        params.insert("fmt".into(), json!("json"));
+        params.insert("proto_message".into(), json!("wp.Event"));
+        let err = build_kafka_sink_conf_from_spec(&build_sink_spec(params.clone()))
+            .expect_err("message without descriptor");
+        assert!(format!("{err}").contains("kafka.proto_message"), "{err}");
+        params.insert("fmt".into(), json!("proto-text"));
+        params.insert("proto_descriptor".into(), json!("/etc/wp/event.pb"));
+        let (conf, fmt) =
+            build_kafka_sink_conf_from_spec(&build_sink_spec(params.clone())).expect("proto");
+        assert_eq!(fmt, TextFmt::from("proto-text"));
+        assert_eq!(conf.proto_descriptor.as_deref(), Some("/etc/wp/event.pb"));
+        assert_eq!(conf.proto_message.as_deref(), Some("wp.Event"));
+        for fmt in ["json", "csv", "show", "kv", "raw"] {
+            params.insert("fmt".into(), json!(fmt));
+            build_kafka_sink_conf_from_spec(&build_sink_spec(params.clone())).expect(fmt);
+        }
+    }
+
+    #[test]
+    fn kafka_security_params_translate_to_rdkafka_config() {
+        let mut params = BTreeMap::new();
+        params.insert("brokers".into(), json!("localhost:9092"));
+        params.insert("topic".into(), json!("topic_a"));
+        params.insert("group_id".into(), json!("group-a"));
+        params.insert("config".into(), json!(["auto.offset.reset=earliest"]));
+        params.insert("security_protocol".into(), json!("sasl_ssl"));
+        params.insert("sasl_mechanism".into(), json!("SCRAM-SHA-512"));
+        params.insert("sasl_username".into(), json!("alice"));
+        params.insert("sasl_password".into(), json!("secret"));
+        params.insert("ssl_ca_location".into(), json!("/etc/kafka/ca.pem"));
+
+        let conf = build_kafka_conf_from_spec(&build_source_spec(params.clone()))
+            .expect("valid source spec");
+        assert_eq!(
+            conf.config,
+            Some(vec![
+                "auto.offset.reset=earliest".to_string(),
+                "security.protocol=SASL_SSL".to_string(),
+                "sasl.mechanism=SCRAM-SHA-512".to_string(),
+                "sasl.username=alice".to_string(),
+                "sasl.password=secret".to_string(),
+                "ssl.ca.location=/etc/kafka/ca.pem".to_string(),
+            ])
+        );
+
+        params.remove("group_id");
+        params.remove("config");
+        let (sink_conf, _) =
+            build_kafka_sink_conf_from_spec(&build_sink_spec(params)).expect("valid sink spec");
+        let sink_config = sink_conf.config.expect("security config");
+        assert!(sink_config.contains(&"security.protocol=SASL_SSL".to_string()));
+        assert!(sink_config.contains(&"sasl.username=alice".to_string()));
+    }
+
+    #[test]
+    fn kafka_sasl_password_resolved_from_secret_file() {
+        let file =
+            std::env::temp_dir().join(format!("wp_kafka_secret_{}.toml", std::process::id()));
+        std::fs::write(&file, "kafka_password = \"from-file\"\n").unwrap();
+        let mut params = BTreeMap::new();
+        params.insert("brokers".into(), json!("localhost:9092"));
+        params.insert("topic".into(), json!("topic_a"));
+        params.insert("security_protocol".into(), json!("sasl_ssl"));
+        params.insert("sasl_mechanism".into(), json!("PLAIN"));
+        params.insert("sasl_username".into(), json!("alice"));
+        params.insert(
+            "secret_ref".into(),
+            json!({
+                "provider": "file",
+                "path": file.to_str().unwrap(),
+                "keys": { "sasl_password": "kafka_password" }
+            }),
+        );
+        let spec = build_sink_spec(params);
+        KafkaSinkFactory
+            .validate_spec(&spec)
+            .expect("valid with secret_ref");
+
+        let resolved = secret::resolve_sink_spec(&spec).expect("secret resolved");
+        let (conf, _) = build_kafka_sink_conf_from_spec(&resolved).expect("valid sink spec");
+        let config = conf.config.expect("security config");
+        assert!(config.contains(&"sasl.password=from-file".to_string()));
+        assert!(
+            !serde_json::to_string(&spec.params)
+                .unwrap()
+                .contains("from-file")
+        );
+        std::fs::remove_file(&file).unwrap();
+    }
+
+    #[test]
+    fn kafka_sasl_ssl_requires_mechanism_and_username() {
+        let mut params = BTreeMap::new();
+        params.insert("brokers".into(), json!("localhost:9092"));
+        params.insert("topic".into(), json!("topic_a"));
+        params.insert("security_protocol".into(), json!("SASL_SSL"));
+        params.insert("sasl_username".into(), json!("alice"));
+        let err = build_kafka_sink_conf_from_spec(&build_sink_spec(params.clone()))
+            .expect_err("mechanism missing");
+        assert!(format!("{err}").contains("kafka.sasl_mechanism"));
+
+        params.remove("sasl_username");
+        params.insert("sasl_mechanism".into(), json!("PLAIN"));
+        let err = build_kafka_conf_from_spec(&build_source_spec(params.clone()))
+            .expect_err("username missing");
+        assert!(format!("{err}").contains("kafka.sasl_username"));
+
+        params.insert("security_protocol".into(), json!("TLS"));
+        let err =
+            build_kafka_sink_conf_from_spec(&build_sink_spec(params)).expect_err("bad protocol");
+        assert!(format!("{err}").contains("invalid kafka.security_protocol"));
+    }
+
+    #[test]
+    fn kafka_sink_conf_from_spec_parses_acks_and_flush_timeout() {
+        let mut params = BTreeMap::new();
+        params.insert("brokers".into(), json!("localhost:9092"));
+        params.insert("topic".into(), json!("sink-topic"));
+        params.insert("acks".into(), json!("ALL"));
+        params.insert("flush_timeout_ms".into(), json!(10000));
+        let (conf, _) =
+            build_kafka_sink_conf_from_spec(&build_sink_spec(params.clone())).expect("valid");
+        assert_eq!(conf.config, Some(vec!["acks=all".to_string()]));
+        assert_eq!(conf.flush_timeout_ms, Some(10000));
+        assert_eq!(conf.key_field, None);
+
+        params.insert("acks".into(), json!(2));
+        let err = build_kafka_sink_conf_from_spec(&build_sink_spec(params.clone()))
+            .expect_err("bad acks");
+        assert!(format!("{err}").contains("invalid kafka.acks"));
+
+        params.insert("acks".into(), json!(1));
+        params.insert("flush_timeout_ms".into(), json!(0));
+        let err =
+            build_kafka_sink_conf_from_spec(&build_sink_spec(params)).expect_err("bad timeout");
+        assert!(format!("{err}").contains("kafka.flush_timeout_ms"));
+    }
+
+    #[test]
+    fn kafka_sink_conf_from_spec_parses_key_field() {
+        let mut params = BTreeMap::new();
+        params.insert("brokers".into(), json!("localhost:9092"));
+        params.insert("topic".into(), json!("sink-topic"));
+        params.insert("key_field".into(), json!(" user_id "));
+        let (conf, _) =
+            build_kafka_sink_conf_from_spec(&build_sink_spec(params.clone())).expect("valid");
+        assert_eq!(conf.key_field.as_deref(), Some("user_id"));
+
+        params.insert("key_field".into(), json!(""));
+        let err = build_kafka_sink_conf_from_spec(&build_sink_spec(params)).expect_err("empty key");
+        assert!(format!("{err}").contains("kafka.key_field"));
+    }
+
+    #[test]
+    fn kafka_sink_conf_from_spec_parses_headers() {
+        let mut params = BTreeMap::new();
+        params.insert("brokers".into(), json!("localhost:9092"));
+        params.insert("topic".into(), json!("sink-topic"));
+        params.insert("headers".into(), json!({"env": "prod", "trace": ""}));
+        params.insert("tag_headers".into(), json!(["tenant", " region "]));
+        let (conf, _) =
+            build_kafka_sink_conf_from_spec(&build_sink_spec(params.clone())).expect("valid");
+        assert_eq!(conf.headers.get("env").map(String::as_str), Some("prod"));
+        assert_eq!(conf.headers.get("trace").map(String::as_str), Some(""));
+        assert_eq!(
+            conf.tag_headers,
+            vec!["tenant".to_string(), "region".to_string()]
+        );
+
+        params.insert("headers".into(), json!({"retries": 3}));
+        let err = build_kafka_sink_conf_from_spec(&build_sink_spec(params.clone()))
+            .expect_err("non-string header");
+        assert!(format!("{err}").contains("kafka.headers.retries"));
+
+        params.remove("headers");
+        params.insert("tag_headers".into(), json!([""]));
+        let err = build_kafka_sink_conf_from_spec(&build_sink_spec(params)).expect_err("empty tag");
+        assert!(format!("{err}").contains("kafka.tag_headers"));
+    }
+
+    #[test]
+    fn kafka_sink_conf_from_spec_parses_dlq_topic() {
+        let mut params = BTreeMap::new();
+        params.insert("brokers".into(), json!("localhost:9092"));
+        params.insert("topic".into(), json!("sink-topic"));
+        params.insert("dlq_topic".into(), json!("sink-topic-dlq"));
+        let (conf, _) =
+            build_kafka_sink_conf_from_spec(&build_sink_spec(params.clone())).expect("valid");
+        assert_eq!(conf.dlq_topic.as_deref(), Some("sink-topic-dlq"));
+        assert_eq!(conf.topic_field, None);
+
+        params.insert("dlq_topic".into(), json!("sink-topic"));
+        let err =
+            build_kafka_sink_conf_from_spec(&build_sink_spec(params)).expect_err("same topic");
+        assert!(format!("{err}").contains("kafka.dlq_topic"));
+    }
+
+    #[test]
+    fn kafka_sink_conf_from_spec_parses_topic_field() {
+        let mut params = BTreeMap::new();
+        params.insert("brokers".into(), json!("localhost:9092"));
+        params.insert("topic".into(), json!("sink-topic"));
+        params.insert("topic_field".into(), json!("dataset"));
+        let (conf, _) =
+            build_kafka_sink_conf_from_spec(&build_sink_spec(params.clone())).expect("valid");
+        assert_eq!(conf.topic_field.as_deref(), Some("dataset"));
+
+        params.insert("topic_field".into(), json!("  "));
+        let err =
+            build_kafka_sink_conf_from_spec(&build_sink_spec(params)).expect_err("empty field");
+        assert!(format!("{err}").contains("kafka.topic_field"));
+    }
+
+    #[test]
+    fn kafka_sink_conf_from_spec_parses_explicit_partitioning() {
+        let mut params = BTreeMap::new();
+        params.insert("brokers".into(), json!("localhost:9092"));
+        params.insert("topic".into(), json!("sink-topic"));
+        params.insert("partition_field".into(), json!("shard"));
+        let (conf, _) =
+            build_kafka_sink_conf_from_spec(&build_sink_spec(params.clone())).expect("valid");
+        assert_eq!(conf.partition_field.as_deref(), Some("shard"));
+        assert!(!conf.partition_by_key_hash);
+
+        params.insert("partition_by_key_hash".into(), json!(true));
+        let err = build_kafka_sink_conf_from_spec(&build_sink_spec(params.clone()))
+            .expect_err("both partition modes");
+        assert!(format!("{err}").contains("mutually exclusive"));
+
+        params.remove("partition_field");
+        let err = build_kafka_sink_conf_from_spec(&build_sink_spec(params.clone()))
+            .expect_err("hash without key_field");
+        assert!(format!("{err}").contains("kafka.key_field"));
+
+        params.insert("key_field".into(), json!("user_id"));
+        let (conf, _) = build_kafka_sink_conf_from_spec(&build_sink_spec(params)).expect("valid");
+        assert!(conf.partition_by_key_hash);
+    }
+
     #[test]
     fn kafka_sink_conf_from_spec_supports_string_config() {
         let mut params = BTreeMap::new();
@@ -479,4 +1485,26 @@ mod tests {
         let (conf, _fmt) = build_kafka_sink_conf_from_spec(&spec).expect("valid sink spec");
         assert_eq!(conf.config, Some(vec!["acks=1".to_string()]));
     }
+
+    #[test]
+    fn kafka_sink_conf_from_spec_parses_avro_params() {
+        let mut params = BTreeMap::new();
+        params.insert("brokers".into(), json!("localhost:9092"));
+        params.insert("topic".into(), json!("events"));
+        params.insert("value_format".into(), json!("avro"));
+        let err = build_kafka_sink_conf_from_spec(&build_sink_spec(params.clone()))
+            .expect_err("registry missing");
+        assert!(format!("{err}").contains("kafka.schema_registry_url"));
+
+        params.insert("schema_registry_url".into(), json!("http://registry:8081"));
+        params.insert("value_subject".into(), json!("audit"));
+        let (conf, _fmt) =
+            build_kafka_sink_conf_from_spec(&build_sink_spec(params.clone())).expect("avro");
+        assert_eq!(conf.value_format, ValueFormat::Avro);
+        assert_eq!(conf.effective_value_subject(), "audit");
+        assert_eq!(conf.value_schema, None);
+
+        params.insert("value_format".into(), json!("protobuf"));
+        assert!(build_kafka_sink_conf_from_spec(&build_sink_spec(params)).is_err());
+    }
 }
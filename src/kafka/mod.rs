@@ -3,15 +3,34 @@
 //! 模块划分：
 //! - source：KafkaSource & 错误映射/建 Topic
 //! - sink：KafkaSink（AsyncRawDataSink/AsyncRecordSink）
+//! - avro：sink 的 Schema Registry Avro 编码
+//! - proto：sink 按 FileDescriptorSet 的 protobuf 编码
+//! - delivery：sink 投递回执跟踪与统计
+//! - commit：source 批量提交的 offset 跟踪
+//! - dedup：source 按消息 key 的窗口去重
+//! - decoder：source 按 `value_format` 选择的可插拔负载解码器
+//! - rebalance：source 消费者上下文（重平衡日志与计数）
 //! - factory：Source/Sink 工厂与注册函数
 
 //mod adapter;
+mod avro;
+mod commit;
 mod config;
+mod decoder;
+mod dedup;
+mod delivery;
 mod factory;
+mod proto;
+mod rebalance;
 mod sink;
 mod source;
 
 // 统一导出：便于上游 `wp_connectors::Source/Sink/Factory` 使用
+pub use config::{
+    HeaderMatch, KafkaSinkConf, KafkaSourceConf, MetadataTags, StartOffset, ValueFormat,
+};
+pub use decoder::{PayloadDecoder, decoder_names, lookup_decoder, register_decoder};
+pub use delivery::DeliverySummary;
 pub use factory::{KafkaSinkFactory, KafkaSourceFactory};
 pub use sink::KafkaSink;
 pub use source::KafkaSource;
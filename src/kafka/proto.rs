@@ -0,0 +1,245 @@
+//! Kafka sink 的 protobuf 编码：从 FileDescriptorSet 加载消息描述，
+//! `fmt = proto` 输出二进制 wire format，`fmt = proto-text` 输出文本格式（一行，以 `\n` 结尾）。
+//!
+//! 记录字段按消息描述中的同名字段转换；记录中缺失的字段保持未设置，
+//! 值无法转换为字段类型时以错误返回，由 sink 按 DLQ 配置处理。
+
+use std::path::Path;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use prost_reflect::prost::Message;
+use prost_reflect::{
+    DescriptorPool, DynamicMessage, FieldDescriptor, Kind, MessageDescriptor, Value as ProtoValue,
+};
+use wp_model_core::model::{DataRecord, DataType};
+
+pub(crate) struct ProtoEncoder {
+    message: MessageDescriptor,
+    /// true 时输出文本格式（`proto-text`）
+    text: bool,
+}
+
+impl ProtoEncoder {
+    /// # args
+    /// * `descriptor` - FileDescriptorSet 文件路径（`protoc --descriptor_set_out`），或其 base64 内容。
+    /// * `message` - 消息全名（含 package）；未配置时要求描述符中恰有一个消息。
+    /// * `text` - 是否输出文本格式。
+    pub(crate) fn load(
+        descriptor: &str,
+        message: Option<&str>,
+        text: bool,
+    ) -> anyhow::Result<Self> {
+        let bytes = read_descriptor(descriptor)?;
+        let pool = DescriptorPool::decode(bytes.as_slice())
+            .map_err(|e| anyhow::anyhow!("invalid kafka.proto_descriptor: {e}"))?;
+        let message = match message {
+            Some(name) => pool.get_message_by_name(name).ok_or_else(|| {
+                anyhow::anyhow!("kafka.proto_message '{name}' not found in descriptor")
+            })?,
+            None => {
+                let mut all = pool.all_messages().filter(|m| !m.is_map_entry());
+                match (all.next(), all.next()) {
+                    (Some(only), None) => only,
+                    (None, _) => anyhow::bail!("kafka.proto_descriptor contains no message"),
+                    (Some(_), Some(_)) => anyhow::bail!(
+                        "kafka.proto_descriptor contains several messages; set kafka.proto_message"
+                    ),
+                }
+            }
+        };
+        Ok(Self { message, text })
+    }
+
+    /// 将记录编码为消息值。
+    ///
+    /// # return
+    /// * `Result<Vec<u8>, String>` - 字段值与描述不匹配时返回描述。
+    pub(crate) fn encode(&self, data: &DataRecord) -> Result<Vec<u8>, String> {
+        let mut msg = DynamicMessage::new(self.message.clone());
+        for item in &data.items {
+            if *item.get_meta() == DataType::Ignore {
+                continue;
+            }
+            let Some(field) = self.message.get_field_by_name(item.get_name()) else {
+                continue;
+            };
+            let raw = item.get_value().to_string();
+            let value = convert(&field, &raw).ok_or_else(|| {
+                format!(
+                    "field '{}' value '{}' does not match proto type",
+                    field.name(),
+                    raw
+                )
+            })?;
+            msg.set_field(&field, value);
+        }
+        if self.text {
+            return Ok(format!("{}\n", msg.to_text_format()).into_bytes());
+        }
+        Ok(msg.encode_to_vec())
+    }
+}
+
+/// 描述符来源：存在的文件按路径读取，否则按 base64 解码。
+fn read_descriptor(raw: &str) -> anyhow::Result<Vec<u8>> {
+    let path = Path::new(raw.trim());
+    if path.is_file() {
+        return std::fs::read(path)
+            .map_err(|e| anyhow::anyhow!("read kafka.proto_descriptor '{}': {e}", path.display()));
+    }
+    STANDARD.decode(raw.trim()).map_err(|_| {
+        anyhow::anyhow!("kafka.proto_descriptor is neither a readable file nor base64")
+    })
+}
+
+/// 将字段的字符串形式转换为描述中的标量类型；嵌套消息与 repeated/map 字段不支持。
+fn convert(field: &FieldDescriptor, raw: &str) -> Option<ProtoValue> {
+    if field.is_list() || field.is_map() {
+        return None;
+    }
+    let trimmed = raw.trim();
+    match field.kind() {
+        Kind::String => Some(ProtoValue::String(raw.to_string())),
+        Kind::Bytes => Some(ProtoValue::Bytes(raw.as_bytes().to_vec().into())),
+        Kind::Bool => match trimmed.to_ascii_lowercase().as_str() {
+            "true" | "1" => Some(ProtoValue::Bool(true)),
+            "false" | "0" => Some(ProtoValue::Bool(false)),
+            _ => None,
+        },
+        Kind::Int32 | Kind::Sint32 | Kind::Sfixed32 => trimmed.parse().ok().map(ProtoValue::I32),
+        Kind::Int64 | Kind::Sint64 | Kind::Sfixed64 => trimmed.parse().ok().map(ProtoValue::I64),
+        Kind::Uint32 | Kind::Fixed32 => trimmed.parse().ok().map(ProtoValue::U32),
+        Kind::Uint64 | Kind::Fixed64 => trimmed.parse().ok().map(ProtoValue::U64),
+        Kind::Float => trimmed.parse().ok().map(ProtoValue::F32),
+        Kind::Double => trimmed.parse().ok().map(ProtoValue::F64),
+        // 枚举接受值名或数值
+        Kind::Enum(desc) => desc
+            .get_value_by_name(trimmed)
+            .map(|v| v.number())
+            .or_else(|| {
+                trimmed
+                    .parse()
+                    .ok()
+                    .filter(|n| desc.get_value(*n).is_some())
+            })
+            .map(ProtoValue::EnumNumber),
+        Kind::Message(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prost_reflect::prost_types::{
+        DescriptorProto, EnumDescriptorProto, EnumValueDescriptorProto, FieldDescriptorProto,
+        FileDescriptorProto, FileDescriptorSet,
+        field_descriptor_proto::{Label, Type},
+    };
+    use wp_model_core::model::DataField;
+
+    fn field(name: &str, number: i32, ty: Type, type_name: Option<&str>) -> FieldDescriptorProto {
+        FieldDescriptorProto {
+            name: Some(name.into()),
+            number: Some(number),
+            label: Some(Label::Optional as i32),
+            r#type: Some(ty as i32),
+            type_name: type_name.map(str::to_string),
+            json_name: Some(name.into()),
+            ..Default::default()
+        }
+    }
+
+    /// `package wp; enum Level {INFO=0; WARN=1;} message Event {string msg=1; int64 code=2; Level level=3;}`
+    fn descriptor_set() -> Vec<u8> {
+        let file = FileDescriptorProto {
+            name: Some("event.proto".into()),
+            package: Some("wp".into()),
+            syntax: Some("proto3".into()),
+            message_type: vec![DescriptorProto {
+                name: Some("Event".into()),
+                field: vec![
+                    field("msg", 1, Type::String, None),
+                    field("code", 2, Type::Int64, None),
+                    field("level", 3, Type::Enum, Some(".wp.Level")),
+                ],
+                ..Default::default()
+            }],
+            enum_type: vec![EnumDescriptorProto {
+                name: Some("Level".into()),
+                value: ["INFO", "WARN"]
+                    .iter()
+                    .enumerate()
+                    .map(|(n, name)| EnumValueDescriptorProto {
+                        name: Some(name.to_string()),
+                        number: Some(n as i32),
+                        ..Default::default()
+                    })
+                    .collect(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        FileDescriptorSet { file: vec![file] }.encode_to_vec()
+    }
+
+    fn event(code: &str) -> DataRecord {
+        let mut rec = DataRecord::default();
+        rec.append(DataField::from_chars("msg", "hello"));
+        rec.append(DataField::from_chars("code", code));
+        rec.append(DataField::from_chars("level", "WARN"));
+        rec.append(DataField::from_chars("extra", "ignored"));
+        rec
+    }
+
+    #[test]
+    fn descriptor_loads_from_file_and_base64() {
+        let bytes = descriptor_set();
+        let path = std::env::temp_dir().join(format!("wp_proto_{}.pb", std::process::id()));
+        std::fs::write(&path, &bytes).unwrap();
+        let from_file = ProtoEncoder::load(path.to_str().unwrap(), None, false).expect("file");
+        assert_eq!(from_file.message.full_name(), "wp.Event");
+        std::fs::remove_file(&path).ok();
+
+        let encoded = STANDARD.encode(&bytes);
+        let from_b64 = ProtoEncoder::load(&encoded, Some("wp.Event"), false).expect("base64");
+        assert_eq!(from_b64.message.full_name(), "wp.Event");
+
+        let err = ProtoEncoder::load(&encoded, Some("wp.Missing"), false)
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("wp.Missing"), "{err}");
+        assert!(ProtoEncoder::load("not a descriptor!", None, false).is_err());
+    }
+
+    #[test]
+    fn records_encode_to_wire_and_text_format() {
+        let encoded = STANDARD.encode(descriptor_set());
+        let encoder = ProtoEncoder::load(&encoded, None, false).unwrap();
+        let payload = encoder.encode(&event("7")).expect("encode");
+        let decoded = DynamicMessage::decode(encoder.message.clone(), payload.as_slice()).unwrap();
+        assert_eq!(
+            decoded.get_field_by_name("msg").unwrap().as_str(),
+            Some("hello")
+        );
+        assert_eq!(decoded.get_field_by_name("code").unwrap().as_i64(), Some(7));
+        assert_eq!(
+            decoded.get_field_by_name("level").unwrap().as_enum_number(),
+            Some(1)
+        );
+
+        let text = ProtoEncoder::load(&encoded, None, true).unwrap();
+        let line = String::from_utf8(text.encode(&event("7")).unwrap()).unwrap();
+        assert!(
+            line.ends_with('\n') && line.lines().count() == 1,
+            "{line:?}"
+        );
+        assert!(
+            line.contains("msg:\"hello\"") && line.contains("level:WARN"),
+            "{line}"
+        );
+
+        let err = encoder.encode(&event("seven")).unwrap_err();
+        assert!(err.contains("'code'"), "{err}");
+    }
+}
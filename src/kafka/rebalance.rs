@@ -0,0 +1,98 @@
+//! Kafka source 消费者上下文：记录组重平衡（分配/回收分区）事件。
+//!
+//! 回调在 librdkafka 的轮询线程中执行，只做原子计数与日志，不做任何阻塞操作。
+
+use rdkafka_wrap::TopicPartitionList;
+use rdkafka_wrap::client::ClientContext;
+use rdkafka_wrap::consumer::{ConsumerContext, Rebalance};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+pub(crate) struct SourceContext {
+    key: String,
+    group_id: String,
+    rebalances: Arc<AtomicU64>,
+}
+
+impl SourceContext {
+    pub(crate) fn new(key: &str, group_id: &str) -> Self {
+        Self {
+            key: key.to_string(),
+            group_id: group_id.to_string(),
+            rebalances: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// 重平衡计数句柄，可在 consumer 创建后继续读取。
+    pub(crate) fn rebalances(&self) -> Arc<AtomicU64> {
+        self.rebalances.clone()
+    }
+}
+
+impl ClientContext for SourceContext {}
+
+impl ConsumerContext for SourceContext {
+    fn post_rebalance(&self, rebalance: &Rebalance<'_>) {
+        let kind = match rebalance {
+            Rebalance::Assign(tpl) => {
+                wp_log::info_data!(
+                    "[kafka] {} ({}) assigned: {}",
+                    self.key,
+                    self.group_id,
+                    describe(tpl)
+                );
+                "assign"
+            }
+            Rebalance::Revoke(tpl) => {
+                wp_log::info_data!(
+                    "[kafka] {} ({}) revoked: {}",
+                    self.key,
+                    self.group_id,
+                    describe(tpl)
+                );
+                "revoke"
+            }
+            Rebalance::Error(err) => {
+                wp_log::warn_data!(
+                    "[kafka] {} ({}) rebalance error: {}",
+                    self.key,
+                    self.group_id,
+                    err
+                );
+                "error"
+            }
+        };
+        self.rebalances.fetch_add(1, Ordering::Relaxed);
+        #[cfg(feature = "prometheus")]
+        metrics::record(&self.group_id, kind);
+        #[cfg(not(feature = "prometheus"))]
+        let _ = kind;
+    }
+}
+
+fn describe(tpl: &TopicPartitionList) -> String {
+    tpl.elements()
+        .iter()
+        .map(|elem| format!("{}[{}]", elem.topic(), elem.partition()))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(feature = "prometheus")]
+mod metrics {
+    use lazy_static::lazy_static;
+    use prometheus::{IntCounterVec, register_int_counter_vec};
+
+    lazy_static! {
+        static ref KAFKA_REBALANCE: IntCounterVec = register_int_counter_vec!(
+            "kafka_rebalance_total",
+            "Kafka source consumer group rebalance events.",
+            &["group", "kind"]
+        )
+        .expect("register kafka_rebalance_total fail");
+    }
+
+    pub(super) fn record(group: &str, kind: &str) {
+        KAFKA_REBALANCE.with_label_values(&[group, kind]).inc();
+    }
+}
@@ -1,53 +1,500 @@
 use async_trait::async_trait;
 use orion_conf::ErrorOwe;
-use rdkafka_wrap::{KWProducer, KWProducerConf, OptionExt};
-use std::collections::HashMap;
+use rdkafka_wrap::admin::{AdminClient, AdminOptions, NewTopic, TopicReplication};
+use rdkafka_wrap::client::DefaultClientContext;
+use rdkafka_wrap::error::KafkaError;
+use rdkafka_wrap::message::{Header, OwnedHeaders};
+use rdkafka_wrap::producer::{FutureProducer, FutureRecord, Producer};
+use rdkafka_wrap::types::RDKafkaErrorCode;
+use rdkafka_wrap::util::Timeout;
+use rdkafka_wrap::{ClientConfig, KWProducer, KWProducerConf, OptionExt};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::sync::Arc;
 use std::time::Duration;
-use wp_connector_api::{AsyncCtrl, AsyncRawDataSink, AsyncRecordSink, SinkReason, SinkResult};
+use wp_connector_api::{
+    AsyncCtrl, AsyncRawDataSink, AsyncRecordSink, SinkError, SinkReason, SinkResult,
+};
 use wp_data_fmt::{DataFormat, FormatType};
 use wp_model_core::model::{DataRecord, fmt_def::TextFmt};
 
-use crate::kafka::config::KafkaSinkConf;
+use crate::common::flush_notify::FlushNotifier;
+use crate::common::reconnect::{ReconnectCoordinator, backend_key};
+use crate::common::retry::{self, PendingFlush};
+use crate::common::stats::{ConnectorStats, StatsHandle};
+use crate::kafka::avro::AvroEncoder;
+use crate::kafka::config::{KafkaSinkConf, ValueFormat};
+use crate::kafka::delivery::{DeliverySummary, DeliveryTracker};
+use crate::kafka::proto::ProtoEncoder;
 
 type AnyResult<T> = anyhow::Result<T>;
 
+const DEFAULT_FLUSH_TIMEOUT_MS: u64 = 3000;
+const QUEUE_FULL_BACKOFF: Duration = Duration::from_millis(10);
+/// 稍后重发可能成功的投递错误：本地队列满、超时、broker 不可达或 leader 切换中
+const TRANSIENT_CODES: [RDKafkaErrorCode; 10] = [
+    RDKafkaErrorCode::QueueFull,
+    RDKafkaErrorCode::MessageTimedOut,
+    RDKafkaErrorCode::RequestTimedOut,
+    RDKafkaErrorCode::BrokerTransportFailure,
+    RDKafkaErrorCode::AllBrokersDown,
+    RDKafkaErrorCode::NetworkException,
+    RDKafkaErrorCode::NotLeaderForPartition,
+    RDKafkaErrorCode::LeaderNotAvailable,
+    RDKafkaErrorCode::NotEnoughReplicas,
+    RDKafkaErrorCode::NotEnoughReplicasAfterAppend,
+];
+/// 转投 DLQ 时携带的错误描述与原始 topic
+pub const DLQ_ERROR_HEADER: &str = "wp_error";
+pub const DLQ_TOPIC_HEADER: &str = "wp_origin_topic";
+
 pub struct KafkaSink {
     pub(crate) inner: Arc<KWProducer>,
     pub(crate) fmt: TextFmt,
+    pub(crate) flush_timeout: Duration,
+    pub(crate) delivery: DeliveryTracker,
+    pub(crate) topic: String,
+    /// 从记录中取值作为消息 key 的字段名
+    pub(crate) key_field: Option<String>,
+    /// 附加到每条消息的静态 header
+    pub(crate) headers: BTreeMap<String, String>,
+    /// 以 header 形式透传的 tag 名（tag 在记录中以同名字段出现）
+    pub(crate) tag_headers: Vec<String>,
+    /// 记录写出使用的底层生产者（逐条 key/header 与批量入队）
+    pub(crate) direct: FutureProducer,
+    /// 单条记录发送失败时的死信 topic；未配置时错误照常上抛
+    pub(crate) dlq_topic: Option<String>,
+    /// 从记录中取值作为目标 topic 的字段名；缺失或为空时使用 `topic`
+    pub(crate) topic_field: Option<String>,
+    /// 已确认存在的 topic（动态路由发现新 topic 时按分区/副本配置创建）
+    pub(crate) known_topics: HashSet<String>,
+    pub(crate) conf: KafkaSinkConf,
+    /// 与同一集群的其他 sink 共享的重连退避
+    pub(crate) reconnect: ReconnectCoordinator,
+    /// `/stats` 自省状态（在途消息、最近 flush 与错误）
+    pub(crate) stats: StatsHandle,
+    /// `value_format = avro` 时的编码器；为 None 时按 `fmt` 渲染文本
+    pub(crate) avro: Option<AvroEncoder>,
+    /// `fmt = proto/proto-text` 时按描述符编码的编码器
+    pub(crate) proto: Option<ProtoEncoder>,
+    /// 显式分区使用的各 topic 实时分区数（越界时刷新）
+    pub(crate) partition_counts: HashMap<String, i32>,
+    /// 累计已投递（或已转投 DLQ）的记录数
+    pub(crate) accepted: u64,
+    /// 最近一次 flush 通知时的 `accepted`
+    pub(crate) notified: u64,
+    /// 上次 flush 通知以来已投递记录的 `wp_event_id`
+    pub(crate) event_ids: Vec<String>,
+    /// flush 确认后的回调
+    pub(crate) flush_notifier: FlushNotifier,
+}
+
+impl KafkaSink {
+    /// 发送一条消息并把投递结果回报给跟踪器。
+    async fn publish_tracked(&self, payload: &[u8]) -> SinkResult<()> {
+        let ticket = self.delivery.begin();
+        let result = self.inner.publish(payload, Default::default()).await;
+        self.stats
+            .record_delivery(u64::from(result.is_ok()), u64::from(result.is_err()));
+        ticket.complete(&result);
+        result.owe(SinkReason::Sink("kafka send fail".into()))?;
+        Ok(())
+    }
+
+    /// 组装待发送消息；key 为 None 时发送 null key，partition 为 None 时由 partitioner 决定。
+    fn build_record<'a>(
+        &self,
+        topic: &'a str,
+        payload: &'a [u8],
+        key: Option<&'a str>,
+        headers: &[(String, String)],
+        partition: Option<i32>,
+    ) -> FutureRecord<'a, str, [u8]> {
+        let mut record = FutureRecord::<str, [u8]>::to(topic).payload(payload);
+        if let Some(key) = key {
+            record = record.key(key);
+        }
+        if let Some(partition) = partition {
+            record = record.partition(partition);
+        }
+        if !headers.is_empty() {
+            let mut owned = OwnedHeaders::new_with_capacity(headers.len());
+            for (key, value) in headers {
+                owned = owned.insert(Header {
+                    key: key.as_str(),
+                    value: Some(value.as_str()),
+                });
+            }
+            record = record.headers(owned);
+        }
+        record
+    }
+
+    /// 发送到指定 topic 并等待投递结果（不计入投递统计）。
+    async fn send_direct(
+        &self,
+        topic: &str,
+        payload: &[u8],
+        key: Option<&str>,
+        headers: &[(String, String)],
+        partition: Option<i32>,
+    ) -> Result<(), KafkaError> {
+        let record = self.build_record(topic, payload, key, headers, partition);
+        self.direct
+            .send(record, Timeout::After(self.flush_timeout))
+            .await
+            .map(|_| ())
+            .map_err(|(err, _)| err)
+    }
+
+    /// 以指定 key 与 header 发送一条记录并等待投递结果；失败时按配置转投 DLQ。
+    async fn publish_direct(
+        &self,
+        topic: &str,
+        payload: &[u8],
+        key: Option<&str>,
+        headers: &[(String, String)],
+        partition: Option<i32>,
+    ) -> SinkResult<()> {
+        let ticket = self.delivery.begin();
+        let sent = self
+            .send_direct(topic, payload, key, headers, partition)
+            .await;
+        self.stats
+            .record_delivery(u64::from(sent.is_ok()), u64::from(sent.is_err()));
+        let (result, transient) = match sent {
+            Ok(()) => (Ok(()), false),
+            Err(err) => {
+                let transient = is_transient(&err);
+                let result = self
+                    .dead_letter(topic, payload, key, headers, err.to_string())
+                    .await;
+                (result, transient)
+            }
+        };
+        ticket.complete(&result);
+        result.map_err(|err| send_error(format!("kafka send fail: {err}"), transient))
+    }
+
+    /// 失败记录兜底：配置了 DLQ 时连同错误 header 转投，否则原样返回错误。
+    async fn dead_letter(
+        &self,
+        topic: &str,
+        payload: &[u8],
+        key: Option<&str>,
+        headers: &[(String, String)],
+        err: String,
+    ) -> Result<(), String> {
+        let Some(dlq) = self.dlq_topic.as_deref() else {
+            return Err(err);
+        };
+        wp_log::warn_data!(
+            "[kafka] send to '{}' failed, routing record to dlq '{}': {}",
+            topic,
+            dlq,
+            err
+        );
+        let mut headers = headers.to_vec();
+        headers.push((DLQ_ERROR_HEADER.to_string(), err.clone()));
+        headers.push((DLQ_TOPIC_HEADER.to_string(), topic.to_string()));
+        self.send_direct(dlq, payload, key, &headers, None)
+            .await
+            .map_err(|e| format!("{err}; dlq '{dlq}' also failed: {e}"))
+    }
+
+    /// 记录的消息值：Avro 模式按 schema 编码，proto 格式按描述符编码（不匹配时返回错误），
+    /// 否则按 `fmt` 渲染。
+    fn encode_value(&self, fmt: &FormatType, data: &DataRecord) -> Result<Vec<u8>, String> {
+        match (&self.avro, &self.proto) {
+            (Some(avro), _) => avro.encode(data),
+            (None, Some(proto)) => proto.encode(data),
+            (None, None) => Ok(render_line(fmt, data)),
+        }
+    }
+
+    /// 批量路径中的失败记录：重新渲染后走 [`Self::dead_letter`]。
+    async fn dead_letter_record(&self, data: &DataRecord, err: String) -> Result<(), String> {
+        if self.dlq_topic.is_none() {
+            return Err(err);
+        }
+        let line = render_line(&FormatType::from(&self.fmt), data);
+        let key = self.record_key(data);
+        let headers = self.record_headers(data);
+        let topic = self.route_topic(data);
+        self.dead_letter(&topic, &line, key.as_deref(), &headers, err)
+            .await
+    }
+
+    /// 按 `topic_field` 解析目标 topic；字段缺失或为空时回退到静态 `topic`。
+    fn route_topic(&self, data: &DataRecord) -> String {
+        self.topic_field
+            .as_deref()
+            .and_then(|field| data.get2(field))
+            .map(|f| f.get_value().to_string())
+            .filter(|topic| !topic.trim().is_empty())
+            .unwrap_or_else(|| self.topic.clone())
+    }
+
+    /// 首次路由到某 topic 时按 sink 的分区/副本配置创建。
+    async fn ensure_route(&mut self, topic: &str) -> SinkResult<()> {
+        if self.known_topics.contains(topic) {
+            return Ok(());
+        }
+        ensure_topic(&self.conf, topic)
+            .await
+            .owe(SinkReason::Sink(format!(
+                "kafka create topic '{topic}' fail"
+            )))?;
+        self.known_topics.insert(topic.to_string());
+        Ok(())
+    }
+
+    /// 计算显式分区：`partition_field` 取字段的整数值，`partition_by_key_hash` 取 key 的
+    /// murmur2 哈希对分区数取模；未配置或字段/key 缺失时返回 None，交由 partitioner。
+    ///
+    /// # return
+    /// * `Result<Option<i32>, String>` - 字段值非整数或超出 topic 实时分区范围时返回错误。
+    async fn record_partition(
+        &mut self,
+        topic: &str,
+        data: &DataRecord,
+    ) -> Result<Option<i32>, String> {
+        if self.conf.partition_by_key_hash {
+            let Some(key) = self.record_key(data) else {
+                return Ok(None);
+            };
+            let count = self.partition_count(topic, false).await?;
+            return Ok(Some(key_hash_partition(key.as_bytes(), count)));
+        }
+        let Some(field) = self.conf.partition_field.clone() else {
+            return Ok(None);
+        };
+        let Some(value) = data.get2(&field) else {
+            return Ok(None);
+        };
+        let raw = value.get_value().to_string();
+        let partition = raw
+            .trim()
+            .parse::<i32>()
+            .map_err(|_| format!("partition field '{field}' value '{raw}' is not an integer"))?;
+        let mut count = self.partition_count(topic, false).await?;
+        if !(0..count).contains(&partition) {
+            // 缓存的分区数可能已过期（topic 扩容），刷新一次再判断
+            count = self.partition_count(topic, true).await?;
+        }
+        if !(0..count).contains(&partition) {
+            return Err(format!(
+                "partition {partition} out of range for topic '{topic}' ({count} partitions)"
+            ));
+        }
+        Ok(Some(partition))
+    }
+
+    /// topic 的实时分区数；`refresh` 为 false 时优先使用缓存。
+    /// 元数据请求是阻塞调用，在阻塞线程池中执行。
+    async fn partition_count(&mut self, topic: &str, refresh: bool) -> Result<i32, String> {
+        if let Some(count) = self.partition_counts.get(topic).filter(|_| !refresh) {
+            return Ok(*count);
+        }
+        let direct = self.direct.clone();
+        let name = topic.to_string();
+        let timeout = Timeout::After(self.flush_timeout);
+        let count = tokio::task::spawn_blocking(move || {
+            let metadata = direct
+                .client()
+                .fetch_metadata(Some(&name), timeout)
+                .map_err(|e| format!("fetch metadata of '{name}' fail: {e}"))?;
+            metadata
+                .topics()
+                .iter()
+                .find(|t| t.name() == name)
+                .map(|t| t.partitions().len() as i32)
+                .filter(|count| *count > 0)
+                .ok_or_else(|| format!("topic '{name}' has no partitions"))
+        })
+        .await
+        .map_err(|e| format!("fetch metadata of '{topic}' join error: {e}"))??;
+        self.partition_counts.insert(topic.to_string(), count);
+        Ok(count)
+    }
+
+    /// 按 `key_field` 从记录中提取消息 key；字段缺失时返回 None。
+    fn record_key(&self, data: &DataRecord) -> Option<String> {
+        let field = self.key_field.as_deref()?;
+        data.get2(field).map(|f| f.get_value().to_string())
+    }
+
+    /// 汇总消息 header：静态 header 在前，随后是记录中存在的 tag（同名时 tag 覆盖）。
+    fn record_headers(&self, data: &DataRecord) -> Vec<(String, String)> {
+        let mut headers = self.headers.clone();
+        for tag in &self.tag_headers {
+            if let Some(field) = data.get2(tag) {
+                headers.insert(tag.clone(), field.get_value().to_string());
+            }
+        }
+        headers.into_iter().collect()
+    }
+
+    /// 累计投递统计（截至最近一次 flush/stop）。
+    pub fn delivery_summary(&self) -> &DeliverySummary {
+        self.delivery.totals()
+    }
+
+    /// 使用外部登记的状态句柄，以默认 topic 作为指标的写入目标。
+    pub fn with_stats(mut self, stats: StatsHandle) -> Self {
+        self.stats = stats.with_destination(self.topic.as_str());
+        self
+    }
+
+    pub fn with_flush_notifier(mut self, flush_notifier: FlushNotifier) -> Self {
+        self.flush_notifier = flush_notifier;
+        self
+    }
+
+    /// 记下已投递记录的 `wp_event_id`。
+    fn track_event_id(&mut self, data: &DataRecord) {
+        if let Some(field) = data.get2("wp_event_id") {
+            self.event_ids.push(field.get_value().to_string());
+        }
+    }
+
+    /// flush 确认后通知下游：上次通知以来投递的记录数与其 `wp_event_id`。
+    fn notify_flushed(&mut self) {
+        let records = (self.accepted - self.notified) as usize;
+        self.notified = self.accepted;
+        let event_ids = std::mem::take(&mut self.event_ids);
+        self.flush_notifier.notify(&self.topic, records, event_ids);
+    }
+
+    /// 当前在途消息、最近 flush 与最近错误的快照。
+    pub fn stats(&self) -> ConnectorStats {
+        self.stats.snapshot()
+    }
+
+    /// 等待所有在途消息得到 broker 确认（确认级别由 `acks` 决定），
+    /// 超过 `flush_timeout_ms` 仍有未确认消息时返回错误。
+    pub async fn flush(&self) -> SinkResult<()> {
+        let timeout = Timeout::After(self.flush_timeout);
+        let (inner, direct) = (self.inner.clone(), self.direct.clone());
+        // librdkafka 的 flush 阻塞至确认或超时：放到阻塞线程池，避免占住异步工作线程
+        let (inner_done, direct_done) = tokio::task::spawn_blocking(move || {
+            (inner.flush(timeout).is_ok(), direct.flush(timeout).is_ok())
+        })
+        .await
+        .map_err(|e| SinkError::from(SinkReason::Sink(format!("kafka flush join error: {}", e))))?;
+        let unacked = self.report_queued();
+        self.stats.set_in_flight(unacked as u64);
+        if inner_done && direct_done && unacked == 0 {
+            self.stats.mark_flush();
+            return Ok(());
+        }
+        let msg = format!(
+            "kafka flush of '{}' timed out after {}ms, {} message(s) still unacknowledged",
+            self.topic,
+            self.flush_timeout.as_millis(),
+            unacked
+        );
+        self.stats.record_error(&msg);
+        Err(retry::timeout_error(msg))
+    }
+
+    /// 生产者本地队列中等待发送或确认的消息数，作为 `/stats` 的缓存条数上报。
+    fn report_queued(&self) -> i32 {
+        let queued = self.direct.in_flight_count().max(0);
+        self.stats.set_buffered(queued as usize);
+        queued
+    }
+
+    /// 编码、路由并发送一条记录，见 [`AsyncRecordSink::sink_record`]。
+    async fn send_record(&mut self, data: &DataRecord) -> SinkResult<()> {
+        let fmt = FormatType::from(&self.fmt);
+        let topic = self.route_topic(data);
+        self.ensure_route(&topic).await?;
+        let encoded = self.encode_value(&fmt, data).map_err(|e| ("encode", e));
+        let partition = self
+            .record_partition(&topic, data)
+            .await
+            .map_err(|e| ("partition", e));
+        let (payload, partition) = match encoded.and_then(|p| partition.map(|n| (p, n))) {
+            Ok(resolved) => resolved,
+            Err((stage, err)) => {
+                // schema 不匹配或分区无效：按 DLQ 配置转投文本形式，否则报错
+                self.stats.record_delivery(0, 1);
+                let ticket = self.delivery.begin();
+                let result = self.dead_letter_record(data, err).await;
+                ticket.complete(&result);
+                return result
+                    .map_err(|e| SinkReason::Sink(format!("kafka {stage} fail: {e}")).into());
+            }
+        };
+        let key = self.record_key(data);
+        let headers = self.record_headers(data);
+        self.publish_direct(&topic, &payload, key.as_deref(), &headers, partition)
+            .await
+    }
+}
+
+/// 消息逐条交给 producer，sink 侧不攒批；到期时只等待在途消息确认。
+#[async_trait]
+impl PendingFlush for KafkaSink {
+    fn pending_len(&self) -> usize {
+        0
+    }
+
+    async fn flush_now(&mut self) -> SinkResult<()> {
+        self.flush().await?;
+        self.notify_flushed();
+        Ok(())
+    }
+
+    fn discard_pending(&mut self) -> usize {
+        0
+    }
+
+    fn accepted(&self) -> u64 {
+        self.accepted
+    }
 }
 
 #[async_trait]
 impl AsyncCtrl for KafkaSink {
+    /// 停止前先 [`KafkaSink::flush`]：返回 Ok 即表示此前写入的消息均已被 broker 确认；
+    /// 超时或投递失败时返回错误，由上层重放，语义为 at-least-once（可能重复，不会静默丢失）。
     async fn stop(&mut self) -> SinkResult<()> {
-        self.inner
-            .flush(rdkafka_wrap::util::Timeout::After(Duration::from_secs(3)))
-            .owe(SinkReason::Sink("kafka stop fail".into()))?;
+        self.flush().await?;
+        let window = self.delivery.drain();
+        wp_log::info_data!(
+            "[kafka] delivery report for '{}': delivered={}, failed={}, outstanding={}",
+            self.delivery.topic(),
+            window.delivered,
+            window.failed,
+            self.delivery.outstanding()
+        );
+        window.into_result(self.delivery.topic())?;
+        self.notify_flushed();
         Ok(())
     }
     async fn reconnect(&mut self) -> SinkResult<()> {
-        let conf = self.inner.conf.clone();
-        self.inner =
-            Arc::new(KWProducer::new(conf).owe(SinkReason::Sink("kafka  reconnect fail".into()))?);
+        let conf = &self.inner.conf;
+        let producer = self
+            .reconnect
+            .run(|| async move {
+                KWProducer::new(conf.clone()).owe(SinkReason::Sink("kafka  reconnect fail".into()))
+            })
+            .await?;
+        self.inner = Arc::new(producer);
         Ok(())
     }
 }
 
+/// 原始数据已由上游编码完毕：按原样字节发往静态 `topic`，不经 `fmt`/Avro 编码，
+/// 不追加换行，也不参与 key、header 与 topic/分区路由。
 #[async_trait]
 impl AsyncRawDataSink for KafkaSink {
     async fn sink_str(&mut self, data: &str) -> SinkResult<()> {
-        self.inner
-            .publish(data.as_bytes(), Default::default())
-            .await
-            .owe(SinkReason::Sink("kafka send fail".into()))?;
-        Ok(())
+        self.publish_tracked(data.as_bytes()).await
     }
     async fn sink_bytes(&mut self, data: &[u8]) -> SinkResult<()> {
-        self.inner
-            .publish(data, Default::default())
-            .await
-            .owe(SinkReason::Sink("kafka send fail".into()))?;
-        Ok(())
+        self.publish_tracked(data).await
     }
 
     async fn sink_str_batch(&mut self, data: Vec<&str>) -> SinkResult<()> {
@@ -65,23 +512,118 @@ impl AsyncRawDataSink for KafkaSink {
     }
 }
 
+/// 记录按 `fmt`（默认 json）渲染为一行文本（以 `\n` 结尾）；`value_format = avro` 时改为 Avro 编码，
+/// `fmt = proto/proto-text` 时按 `proto_descriptor` 编码。
 #[async_trait]
 impl AsyncRecordSink for KafkaSink {
     async fn sink_record(&mut self, data: &DataRecord) -> SinkResult<()> {
-        // 非文件类 sink 支持通过参数选择输出格式（默认 json）
-        let fmt = FormatType::from(&self.fmt);
-        let line = format!("{}\n", fmt.format_record(data));
-        self.inner
-            .publish(line.as_bytes(), Default::default())
-            .await
-            .owe(SinkReason::Sink("kafka send fail".into()))?;
+        self.send_record(data).await?;
+        self.accepted += 1;
+        self.track_event_id(data);
         Ok(())
     }
+
+    /// 先将整批记录入队（交由 librdkafka 按 `linger.ms`/`batch.size` 攒批），
+    /// 最后统一等待投递结果；失败时报告首个失败记录的下标。
     async fn sink_records(&mut self, data: Vec<Arc<DataRecord>>) -> SinkResult<()> {
-        for item in data {
-            self.sink_record(item.as_ref()).await?;
+        let fmt = FormatType::from(&self.fmt);
+        let total = data.len();
+        let mut pending = Vec::with_capacity(total);
+        let mut failed = Vec::new();
+        let topics = data
+            .iter()
+            .map(|item| self.route_topic(item))
+            .collect::<Vec<_>>();
+        for topic in &topics {
+            self.ensure_route(topic).await?;
+        }
+        let mut partitions = Vec::with_capacity(total);
+        for (item, topic) in data.iter().zip(&topics) {
+            partitions.push(self.record_partition(topic, item).await);
+        }
+        for (idx, item) in data.iter().enumerate() {
+            let ticket = self.delivery.begin();
+            let payload = match self.encode_value(&fmt, item) {
+                Ok(payload) => payload,
+                Err(err) => {
+                    failed.push((idx, ticket, err, false));
+                    continue;
+                }
+            };
+            let partition = match &partitions[idx] {
+                Ok(partition) => *partition,
+                Err(err) => {
+                    failed.push((idx, ticket, err.clone(), false));
+                    continue;
+                }
+            };
+            let key = self.record_key(item);
+            let headers = self.record_headers(item);
+            let mut record =
+                self.build_record(&topics[idx], &payload, key.as_deref(), &headers, partition);
+            loop {
+                match self.direct.send_result(record) {
+                    Ok(future) => {
+                        pending.push((idx, ticket, future));
+                        break;
+                    }
+                    Err((KafkaError::MessageProduction(RDKafkaErrorCode::QueueFull), back)) => {
+                        // 本地队列已满：等待后台投递腾出空间后重试
+                        record = back;
+                        tokio::time::sleep(QUEUE_FULL_BACKOFF).await;
+                    }
+                    Err((err, _)) => {
+                        failed.push((idx, ticket, err.to_string(), is_transient(&err)));
+                        break;
+                    }
+                }
+            }
+        }
+        self.stats.set_in_flight(pending.len() as u64);
+        self.report_queued();
+        for (idx, ticket, future) in pending {
+            match future.await {
+                Ok(Ok(_)) => ticket.complete(&Ok::<(), String>(())),
+                Ok(Err((err, _))) => {
+                    failed.push((idx, ticket, err.to_string(), is_transient(&err)))
+                }
+                Err(_) => failed.push((idx, ticket, "delivery canceled".to_string(), true)),
+            }
+        }
+        failed.sort_by_key(|(idx, _, _, _)| *idx);
+        // 转投 DLQ 的记录也计为失败：未写入目标 topic
+        self.stats
+            .record_delivery((total - failed.len()) as u64, failed.len() as u64);
+        let mut first_err: Option<(usize, String, bool)> = None;
+        for (idx, ticket, err, transient) in failed {
+            let result = self.dead_letter_record(&data[idx], err).await;
+            ticket.complete(&result);
+            if let Err(err) = result {
+                first_err.get_or_insert((idx, err, transient));
+            }
+        }
+        self.stats.set_in_flight(0);
+        self.report_queued();
+        match first_err {
+            None => {
+                self.accepted += total as u64;
+                for record in &data {
+                    self.track_event_id(record);
+                }
+                self.stats.mark_flush();
+                Ok(())
+            }
+            Some((idx, err, transient)) => {
+                // 首个失败之前的记录已投递；重试从失败记录开始（之后已投递的可能重复）
+                self.accepted += idx as u64;
+                for record in &data[..idx] {
+                    self.track_event_id(record);
+                }
+                let msg = format!("kafka batch send failed at record {idx} of {total}: {err}");
+                self.stats.record_error(&msg);
+                Err(send_error(msg, transient))
+            }
         }
-        Ok(())
     }
 }
 
@@ -95,7 +637,7 @@ impl KafkaSink {
         if let Some(items) = &conf.config {
             let mut m = HashMap::new();
             for c in items {
-                let v: Vec<&str> = c.split('=').collect();
+                let v: Vec<&str> = c.splitn(2, '=').collect();
                 if v.len() >= 2 {
                     m.insert(v[0].trim(), v[1].trim());
                 }
@@ -104,9 +646,198 @@ impl KafkaSink {
         }
         let producer = KWProducer::new(kc)?;
         producer.create_topic().await?;
+        let direct = direct_producer(conf)?;
+        let avro = match conf.value_format {
+            ValueFormat::Text => None,
+            ValueFormat::Avro => Some(AvroEncoder::resolve(conf).await?),
+        };
+        let proto_text = fmt == TextFmt::from("proto-text");
+        let proto = match &conf.proto_descriptor {
+            Some(descriptor) if proto_text || fmt == TextFmt::from("proto") => Some(
+                ProtoEncoder::load(descriptor, conf.proto_message.as_deref(), proto_text)?,
+            ),
+            _ => None,
+        };
+        let mut known_topics = HashSet::from([conf.topic.clone()]);
+        if let Some(dlq) = &conf.dlq_topic {
+            ensure_topic(conf, dlq).await?;
+            known_topics.insert(dlq.clone());
+        }
         Ok(Self {
             inner: Arc::new(producer),
             fmt,
+            flush_timeout: Duration::from_millis(
+                conf.flush_timeout_ms.unwrap_or(DEFAULT_FLUSH_TIMEOUT_MS),
+            ),
+            delivery: DeliveryTracker::new(&conf.topic),
+            topic: conf.topic.clone(),
+            key_field: conf.key_field.clone(),
+            headers: conf.headers.clone(),
+            tag_headers: conf.tag_headers.clone(),
+            direct,
+            dlq_topic: conf.dlq_topic.clone(),
+            topic_field: conf.topic_field.clone(),
+            known_topics,
+            conf: conf.clone(),
+            reconnect: ReconnectCoordinator::shared(
+                backend_key("kafka", &conf.brokers),
+                Default::default(),
+            ),
+            stats: StatsHandle::detached(&conf.topic, "kafka"),
+            avro,
+            proto,
+            partition_counts: HashMap::new(),
+            accepted: 0,
+            notified: 0,
+            event_ids: Vec::new(),
+            flush_notifier: FlushNotifier::default(),
         })
     }
 }
+
+/// 投递错误是否为瞬时错误（见 [`TRANSIENT_CODES`]）。
+fn is_transient(err: &KafkaError) -> bool {
+    err.rdkafka_error_code()
+        .is_some_and(|code| TRANSIENT_CODES.contains(&code))
+}
+
+/// 发送失败：瞬时错误按过载上报以便上层重试，其余为永久性错误。
+fn send_error(msg: String, transient: bool) -> SinkError {
+    if transient {
+        retry::busy_error(msg)
+    } else {
+        SinkReason::Sink(msg).into()
+    }
+}
+
+/// 记录按 `fmt` 渲染的消息值：一行文本，以 `\n` 结尾。
+fn render_line(fmt: &FormatType, data: &DataRecord) -> Vec<u8> {
+    format!("{}\n", fmt.format_record(data)).into_bytes()
+}
+
+/// 按 sink 的分区/副本配置创建 topic；已存在时忽略。
+async fn ensure_topic(conf: &KafkaSinkConf, topic: &str) -> AnyResult<()> {
+    let admin: AdminClient<DefaultClientContext> = ClientConfig::new()
+        .set("bootstrap.servers", &conf.brokers)
+        .create()?;
+    let new_topic = NewTopic::new(
+        topic,
+        conf.num_partitions.max(1),
+        TopicReplication::Fixed(conf.replication.max(1)),
+    );
+    for result in admin
+        .create_topics(vec![&new_topic], &AdminOptions::new())
+        .await?
+    {
+        match result {
+            Ok(_) | Err((_, RDKafkaErrorCode::TopicAlreadyExists)) => {}
+            Err((name, code)) => {
+                anyhow::bail!("create kafka topic '{name}' failed: {code}");
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 与 KWProducer 使用相同 broker 与配置行的底层生产者（用于记录写出）。
+fn direct_producer(conf: &KafkaSinkConf) -> AnyResult<FutureProducer> {
+    let mut cc = ClientConfig::new();
+    cc.set("bootstrap.servers", &conf.brokers);
+    for c in conf.config.iter().flatten() {
+        let v: Vec<&str> = c.splitn(2, '=').collect();
+        if v.len() >= 2 {
+            cc.set(v[0].trim(), v[1].trim());
+        }
+    }
+    Ok(cc.create()?)
+}
+
+/// Kafka Java 客户端 `Utils.murmur2` 的移植，使显式分区与 Java 默认分区器的 key 分布一致。
+fn murmur2(data: &[u8]) -> u32 {
+    const SEED: u32 = 0x9747_b28c;
+    const M: u32 = 0x5bd1_e995;
+    const R: u32 = 24;
+    let mut h = SEED ^ data.len() as u32;
+    let mut chunks = data.chunks_exact(4);
+    for chunk in &mut chunks {
+        let mut k = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        k = k.wrapping_mul(M);
+        k ^= k >> R;
+        k = k.wrapping_mul(M);
+        h = h.wrapping_mul(M);
+        h ^= k;
+    }
+    let tail = chunks.remainder();
+    if !tail.is_empty() {
+        for (i, b) in tail.iter().enumerate().rev() {
+            h ^= u32::from(*b) << (8 * i);
+        }
+        h = h.wrapping_mul(M);
+    }
+    h ^= h >> 13;
+    h = h.wrapping_mul(M);
+    h ^= h >> 15;
+    h
+}
+
+/// `toPositive(murmur2(key)) % count`。
+fn key_hash_partition(key: &[u8], count: i32) -> i32 {
+    ((murmur2(key) & 0x7fff_ffff) % count.max(1) as u32) as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wp_model_core::model::DataField;
+
+    fn sample() -> DataRecord {
+        let mut record = DataRecord::default();
+        record.append(DataField::from_chars("user", "alice"));
+        record.append(DataField::from_digit("code", 200));
+        record
+    }
+
+    fn rendered(fmt: &str) -> String {
+        let fmt = FormatType::from(&TextFmt::from(fmt));
+        String::from_utf8(render_line(&fmt, &sample())).expect("utf8")
+    }
+
+    #[test]
+    fn record_values_follow_fmt() {
+        let json = rendered("json");
+        assert!(json.ends_with("}\n") && json.lines().count() == 1, "{json}");
+        let value: serde_json::Value = serde_json::from_str(json.trim_end()).expect("json");
+        assert_eq!(value["user"], "alice");
+        assert_eq!(value["code"], 200);
+
+        for fmt in ["csv", "kv", "show", "raw"] {
+            let text = rendered(fmt);
+            assert!(text.ends_with('\n'), "{fmt}: {text:?}");
+            assert!(
+                text.contains("alice") && text.contains("200"),
+                "{fmt}: {text:?}"
+            );
+            assert_ne!(text, json, "{fmt} must not fall back to json");
+        }
+        let kv = rendered("kv");
+        assert!(kv.contains("user") && kv.contains("code"), "{kv:?}");
+    }
+
+    #[test]
+    fn murmur2_matches_java_client() {
+        // 取自 Kafka 客户端 UtilsTest
+        assert_eq!(murmur2(b"21") as i32, -973_932_308);
+        assert_eq!(murmur2(b"foobar") as i32, -790_332_482);
+        assert_eq!(murmur2(b"a-little-bit-long-string") as i32, -985_981_536);
+        assert_eq!(
+            murmur2(b"a-little-bit-longer-string") as i32,
+            -1_486_304_829
+        );
+        assert_eq!(murmur2(b"abc") as i32, 479_470_107);
+        assert!((0..6).contains(&key_hash_partition(b"user-1", 6)));
+        assert_eq!(
+            key_hash_partition(b"user-1", 6),
+            key_hash_partition(b"user-1", 6)
+        );
+    }
+}
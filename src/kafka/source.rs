@@ -3,25 +3,70 @@ use orion_error::ErrorOwe;
 use rdkafka_wrap::admin::{AdminClient, AdminOptions, NewTopic, TopicReplication};
 use rdkafka_wrap::client::DefaultClientContext;
 use rdkafka_wrap::config::RDKafkaLogLevel;
+use rdkafka_wrap::consumer::{CommitMode, Consumer, StreamConsumer};
 use rdkafka_wrap::error::KafkaError;
+use rdkafka_wrap::message::{BorrowedHeaders, Headers};
 use rdkafka_wrap::types::RDKafkaErrorCode;
-use rdkafka_wrap::{ClientConfig, KWConsumer, KWConsumerConf, Message};
-use std::collections::HashMap;
+use rdkafka_wrap::{ClientConfig, Message, Offset, TopicPartitionList};
+use std::collections::{BTreeMap, HashSet};
 use std::fmt::{Display, Formatter};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc::UnboundedReceiver;
 use wp_parse_api::RawData;
 
 use crate::WP_SRC_VAL;
+use crate::common::field_allowlist::FieldAllowlist;
+use crate::common::flush_notify::{self, FlushEvent};
+use crate::common::quarantine::{QuarantineEntry, send_entry};
+use crate::common::stats::{ConnectorStats, StatsHandle};
+use crate::kafka::avro::{AvroDecoder, DecodeError};
+use crate::kafka::commit::{CommitPosition, OffsetTracker};
+use crate::kafka::config::{HeaderMatch, MetadataTags, StartOffset};
+use crate::kafka::dedup::{DEFAULT_DEDUP_MAX_KEYS, KeyDedup};
+use crate::kafka::rebalance::SourceContext;
 use wp_connector_api::{
-    DataSource, SourceBatch, SourceError, SourceEvent, SourceReason, SourceResult, Tags,
+    DataSource, SinkHandle, SourceBatch, SourceError, SourceEvent, SourceReason, SourceResult, Tags,
 };
 
 type AnyResult<T> = anyhow::Result<T>;
 
+const SEEK_TIMEOUT: Duration = Duration::from_secs(5);
+/// 保留的 `value_format`：Confluent Avro，由 [`AvroDecoder`] 处理
+pub(crate) const AVRO_VALUE_FORMAT: &str = "avro";
+
 pub struct KafkaSource {
     key: String,
     tags: Tags,
-    consumer: KWConsumer,
+    consumer: StreamConsumer<SourceContext>,
+    group_id: String,
+    /// 组重平衡次数（由消费者上下文回调累加）
+    rebalances: Arc<AtomicU64>,
     event_seq: u64,
+    allowlist: Option<FieldAllowlist>,
+    quarantine: Option<SinkHandle>,
+    /// 批量提交模式下的 offset 跟踪；None 表示沿用自动提交
+    commits: Option<OffsetTracker>,
+    /// `ack_channel` 上的下游 flush 通知；为 None 时只确认被跳过的消息
+    acks: Option<UnboundedReceiver<FlushEvent>>,
+    /// 配置的起始位置及已完成定位的分区
+    start_offset: Option<StartOffset>,
+    positioned: HashSet<(String, i32)>,
+    /// header 过滤条件；为空时发出全部消息
+    header_filter: BTreeMap<String, HeaderMatch>,
+    /// 采样决策字段名及仅解析该字段的白名单
+    sampling: Option<(String, FieldAllowlist)>,
+    /// 按消息 key 的窗口去重；为 None 时不去重
+    dedup: Option<KeyDedup>,
+    /// `value_format` 对应的负载解码器；为 None 时原样透传
+    decoder: Option<Arc<dyn PayloadDecoder>>,
+    /// `value_format = avro` 时的 Schema Registry 解码器（替代 `decoder`）
+    avro: Option<AvroDecoder>,
+    /// 消息坐标标签名；为 None 时不附加
+    metadata_tags: Option<MetadataTags>,
+    /// `/stats` 自省状态（源端 lag）
+    stats: StatsHandle,
 }
 
 impl KafkaSource {
@@ -29,57 +74,432 @@ impl KafkaSource {
         &self.key
     }
 
+    pub fn group_id(&self) -> &str {
+        &self.group_id
+    }
+
+    /// 自创建以来观察到的重平衡事件数（分配与回收各计一次）。
+    pub fn rebalance_count(&self) -> u64 {
+        self.rebalances.load(Ordering::Relaxed)
+    }
+
     pub async fn new(
         key: String,
         tags: Tags,
         group_id: &str,
         config: &KafkaSourceConf,
     ) -> AnyResult<Self> {
+        let avro = match config.value_format.as_deref().map(str::trim) {
+            Some(AVRO_VALUE_FORMAT) => Some(AvroDecoder::new(
+                config.schema_registry_url.as_deref().ok_or_else(|| {
+                    anyhow::anyhow!("kafka.schema_registry_url is required for value_format=avro")
+                })?,
+            )?),
+            _ => None,
+        };
+        let decoder = match config.value_format.as_deref() {
+            Some(_) if avro.is_some() => None,
+            Some(name) => Some(
+                lookup_decoder(name)
+                    .ok_or_else(|| anyhow::anyhow!("unknown kafka.value_format '{name}'"))?,
+            ),
+            None => None,
+        };
         // Create topics if not exists (best-effort)
         create_topics(config).await?;
 
         wp_log::info_data!("[kafka] topics: {:?}, group_id: {}", config.topic, group_id);
-        let mut conf = KWConsumerConf::new(&config.brokers, group_id)
-            .set_log_level(RDKafkaLogLevel::Info)
-            .set_topics(config.topic.clone());
-        if let Some(config) = &config.config {
-            let mut map = HashMap::new();
-            for c in config {
-                let v: Vec<&str> = c.split('=').collect();
-                if v.len() >= 2 {
-                    map.insert(v[0].trim(), v[1].trim());
-                }
+        let commit_interval = config.commit_interval_ms.map(Duration::from_millis);
+        let mut conf = ClientConfig::new();
+        conf.set("bootstrap.servers", &config.brokers)
+            .set("group.id", group_id)
+            .set_log_level(RDKafkaLogLevel::Info);
+        for c in config.config.iter().flatten() {
+            let v: Vec<&str> = c.splitn(2, '=').collect();
+            if v.len() >= 2 {
+                conf.set(v[0].trim(), v[1].trim());
             }
-            conf = conf.set_config(map);
         }
-        let consumer = KWConsumer::new_subscribe(conf)?;
+        if commit_interval.is_some() {
+            conf.set("enable.auto.commit", "false");
+        }
+        // 自建上下文以接收重平衡回调
+        let context = SourceContext::new(&key, group_id);
+        let rebalances = context.rebalances();
+        let consumer: StreamConsumer<SourceContext> = conf.create_with_context(context)?;
+        let topics = config.topic.iter().map(String::as_str).collect::<Vec<_>>();
+        consumer.subscribe(&topics)?;
+        let allowlist = config
+            .fields
+            .as_ref()
+            .map(FieldAllowlist::new)
+            .filter(|allow| !allow.is_empty());
+        let stats = StatsHandle::detached(&key, "kafka");
         Ok(Self {
             key,
             consumer,
+            group_id: group_id.to_string(),
+            rebalances,
             tags,
             event_seq: 0,
+            allowlist,
+            quarantine: None,
+            commits: commit_interval.map(OffsetTracker::new),
+            acks: config.ack_channel.as_deref().map(flush_notify::subscribe),
+            start_offset: config.start_offset,
+            positioned: HashSet::new(),
+            header_filter: config.header_filter.clone(),
+            sampling: config
+                .respect_sampling_field
+                .clone()
+                .map(|field| (field.clone(), FieldAllowlist::new([field]))),
+            dedup: config.dedup_window_ms.map(|ms| {
+                let max_keys = config.dedup_max_keys.unwrap_or(DEFAULT_DEDUP_MAX_KEYS);
+                KeyDedup::new(Duration::from_millis(ms), max_keys)
+            }),
+            decoder,
+            avro,
+            metadata_tags: config.metadata_tags.clone(),
+            stats,
         })
     }
 
-    pub async fn recv_impl(&mut self) -> SourceResult<SourceBatch> {
+    /// 分区首次拉到消息（即完成分配）时按 `start_offset` seek；返回 true 表示已 seek，
+    /// 当前消息应丢弃并从新位置重新拉取。
+    fn position_partition(
+        &mut self,
+        topic: &str,
+        partition: i32,
+        offset: i64,
+    ) -> SourceResult<bool> {
+        let Some(start) = self.start_offset else {
+            return Ok(false);
+        };
+        if !self.positioned.insert((topic.to_string(), partition)) {
+            return Ok(false);
+        }
+        let target = match start {
+            StartOffset::Earliest => Offset::Beginning,
+            StartOffset::Latest => Offset::End,
+            StartOffset::Offset(o) => Offset::Offset(o),
+            StartOffset::Timestamp(ms) => self.offset_for_time(topic, partition, ms)?,
+        };
+        if target == Offset::Offset(offset) {
+            return Ok(false);
+        }
+        wp_log::info_data!(
+            "[kafka] seek {}[{}] to {:?} (start_offset={})",
+            topic,
+            partition,
+            target,
+            start
+        );
         self.consumer
+            .seek(topic, partition, target, SEEK_TIMEOUT)
+            .map_err(KafkaErrorWrapper)
+            .owe(SourceReason::SupplierError("kafka seek".to_string()))?;
+        Ok(true)
+    }
+
+    /// 通过 offsets-for-times 将时间戳解析为分区 offset；时间晚于末条消息时定位到末尾。
+    fn offset_for_time(&self, topic: &str, partition: i32, ms: i64) -> SourceResult<Offset> {
+        let mut tpl = TopicPartitionList::new();
+        tpl.add_partition_offset(topic, partition, Offset::Offset(ms))
+            .map_err(KafkaErrorWrapper)
+            .owe(SourceReason::SupplierError(
+                "kafka offsets_for_times".to_string(),
+            ))?;
+        let resolved = self
+            .consumer
+            .offsets_for_times(tpl, SEEK_TIMEOUT)
+            .map_err(KafkaErrorWrapper)
+            .owe(SourceReason::SupplierError(
+                "kafka offsets_for_times".to_string(),
+            ))?;
+        Ok(resolved
+            .find_partition(topic, partition)
+            .map(|elem| elem.offset())
+            .unwrap_or(Offset::End))
+    }
+
+    /// 取出 `ack_channel` 上已到达的 flush 通知，确认其覆盖的事件。
+    fn drain_acks(&mut self) {
+        let (Some(acks), Some(tracker)) = (self.acks.as_mut(), self.commits.as_mut()) else {
+            return;
+        };
+        while let Ok(event) = acks.try_recv() {
+            tracker.apply_flush(&event);
+        }
+    }
+
+    fn commit_positions(&mut self, positions: &[CommitPosition]) -> SourceResult<()> {
+        if positions.is_empty() {
+            return Ok(());
+        }
+        let mut tpl = TopicPartitionList::new();
+        for pos in positions {
+            tpl.add_partition_offset(&pos.topic, pos.partition, Offset::Offset(pos.offset))
+                .map_err(KafkaErrorWrapper)
+                .owe(SourceReason::SupplierError("kafka commit".to_string()))?;
+        }
+        self.consumer
+            .commit(&tpl, CommitMode::Async)
+            .map_err(KafkaErrorWrapper)
+            .owe(SourceReason::SupplierError("kafka commit".to_string()))?;
+        if let Some(tracker) = self.commits.as_mut() {
+            tracker.mark_committed(positions);
+        }
+        Ok(())
+    }
+
+    /// 到达提交间隔时提交；失败仅告警，下个周期重试。
+    fn commit_if_due(&mut self) {
+        let Some(due) = self
+            .commits
+            .as_mut()
+            .and_then(|tracker| tracker.take_due(Instant::now()))
+        else {
+            return;
+        };
+        if let Err(e) = self.commit_positions(&due) {
+            wp_log::warn_data!("[kafka] periodic offset commit failed: {}", e);
+        }
+    }
+
+    pub fn with_stats(mut self, stats: StatsHandle) -> Self {
+        self.stats = stats;
+        self
+    }
+
+    /// 当前源端 lag 的快照。
+    pub fn stats(&self) -> ConnectorStats {
+        self.stats.snapshot()
+    }
+
+    /// 按本地缓存的分区高水位估算 lag（不发起 broker 请求）；水位未知时保持上次值。
+    fn update_lag(&self, topic: &str, partition: i32, offset: i64) {
+        if let Ok((_, high)) = self.consumer.get_watermark_offsets(topic, partition)
+            && high >= 0
+        {
+            self.stats.set_lag((high - offset - 1).max(0));
+        }
+    }
+
+    /// 解析失败的负载写入隔离 sink（而非透传原文）
+    pub fn with_quarantine(mut self, quarantine: Option<SinkHandle>) -> Self {
+        self.quarantine = quarantine;
+        self
+    }
+
+    /// 丢弃一条消息但视为已处理：批量提交模式下立即确认，使 offset 可越过它提交。
+    fn skip_message(&mut self, topic: &str, partition: i32, offset: i64) -> SourceError {
+        self.event_seq = self.event_seq.wrapping_add(1);
+        if let Some(tracker) = self.commits.as_mut() {
+            tracker.track(self.event_seq, topic, partition, offset);
+            tracker.ack(self.event_seq);
+        }
+        SourceError::from(SourceReason::NotData)
+    }
+
+    /// 消息暂时无法处理（如 Schema Registry 不可用）：seek 回该 offset 使其重新投递，
+    /// 不跟踪、不确认，返回供应端错误。
+    fn redeliver(&mut self, topic: &str, partition: i32, offset: i64, reason: &str) -> SourceError {
+        wp_log::warn_data!(
+            "[kafka] {}[{}]@{} will be redelivered: {}",
+            topic,
+            partition,
+            offset,
+            reason
+        );
+        if let Err(e) = self
+            .consumer
+            .seek(topic, partition, Offset::Offset(offset), SEEK_TIMEOUT)
+        {
+            wp_log::warn_data!("[kafka] seek back for redelivery failed: {}", e);
+        }
+        SourceError::from(SourceReason::SupplierError(format!(
+            "kafka message not processed: {reason}"
+        )))
+    }
+
+    /// 解析失败的负载写入隔离 sink；写入成功返回 true，未配置或写入失败返回 false。
+    async fn quarantine_payload(
+        &mut self,
+        reason: &str,
+        raw: &[u8],
+        topic: &str,
+        partition: i32,
+        offset: i64,
+    ) -> bool {
+        let Some(quarantine) = self.quarantine.as_mut() else {
+            return false;
+        };
+        let entry = QuarantineEntry::new(self.key.clone(), reason.to_string(), raw)
+            .with_meta("topic", topic.to_string())
+            .with_meta("partition", partition)
+            .with_meta("offset", offset);
+        match send_entry(quarantine.sink.as_mut(), &entry).await {
+            Ok(()) => true,
+            Err(qe) => {
+                wp_log::warn_data!("[kafka] quarantine write failed: {}", qe);
+                false
+            }
+        }
+    }
+
+    pub async fn recv_impl(&mut self) -> SourceResult<SourceBatch> {
+        self.drain_acks();
+        self.commit_if_due();
+        let dedup = self.dedup.is_some();
+        let (raw, topic, partition, offset, timestamp, matched, key) = self
+            .consumer
             .recv()
             .await
             .map(|msg| {
-                let payload = Bytes::copy_from_slice(msg.payload().unwrap_or(&[]));
-                let mut stags = self.tags.clone();
-                stags.set(WP_SRC_VAL, msg.topic().to_string());
-                self.event_seq = self.event_seq.wrapping_add(1);
-                let event_id = self.event_seq;
-                vec![SourceEvent::new(
-                    event_id,
-                    self.key.clone(),
-                    RawData::Bytes(payload),
-                    stags.into(),
-                )]
+                // header 不匹配的消息不复制负载
+                let matched = headers_match(&self.header_filter, msg.headers());
+                let raw = match matched {
+                    true => msg.payload().unwrap_or(&[]).to_vec(),
+                    false => Vec::new(),
+                };
+                let key = msg.key().filter(|_| dedup).map(<[u8]>::to_vec);
+                (
+                    raw,
+                    msg.topic().to_string(),
+                    msg.partition(),
+                    msg.offset(),
+                    msg.timestamp().to_millis(),
+                    matched,
+                    key,
+                )
             })
             .map_err(KafkaErrorWrapper)
-            .owe(SourceReason::SupplierError("kafka".to_string()))
+            .owe(SourceReason::SupplierError("kafka".to_string()))?;
+        if self.position_partition(&topic, partition, offset)? {
+            return Err(SourceError::from(SourceReason::NotData));
+        }
+        self.update_lag(&topic, partition, offset);
+        if !matched {
+            return Err(self.skip_message(&topic, partition, offset));
+        }
+        let decoded = match (self.avro.as_mut(), &self.decoder) {
+            (Some(avro), _) => match avro.decode(&raw).await {
+                Err(DecodeError::Unavailable(e)) => {
+                    return Err(self.redeliver(&topic, partition, offset, &e));
+                }
+                other => Some(other.map_err(|e| e.to_string())),
+            },
+            (None, Some(decoder)) => Some(decoder.decode(&raw)),
+            (None, None) => None,
+        };
+        let raw = match decoded {
+            Some(Ok(decoded)) => decoded,
+            Some(Err(e)) => {
+                if self
+                    .quarantine_payload(&e, &raw, &topic, partition, offset)
+                    .await
+                {
+                    return Err(SourceError::from(SourceReason::NotData));
+                }
+                wp_log::warn_data!("[kafka] value_format decode failed: {}", e);
+                raw
+            }
+            None => raw,
+        };
+        if let Some((field, parser)) = &self.sampling
+            && sampled_out(parser, field, &raw)
+        {
+            return Err(self.skip_message(&topic, partition, offset));
+        }
+        if let (Some(dedup), Some(key)) = (self.dedup.as_mut(), key.as_deref())
+            && dedup.is_duplicate(&topic, key, Instant::now())
+        {
+            return Err(self.skip_message(&topic, partition, offset));
+        }
+        let payload = match &self.allowlist {
+            Some(allow) => match allow.project(&raw) {
+                Ok(projected) => Bytes::from(projected),
+                Err(e) => {
+                    let reason = e.to_string();
+                    if self
+                        .quarantine_payload(&reason, &raw, &topic, partition, offset)
+                        .await
+                    {
+                        return Err(SourceError::from(SourceReason::NotData));
+                    }
+                    wp_log::warn_data!("[kafka] field allowlist skipped: {}", e);
+                    Bytes::from(raw)
+                }
+            },
+            None => Bytes::from(raw),
+        };
+        let mut stags = self.tags.clone();
+        stags.set(WP_SRC_VAL, topic.clone());
+        if let Some(names) = &self.metadata_tags {
+            set_metadata_tags(&mut stags, names, &topic, partition, offset, timestamp);
+        }
+        self.event_seq = self.event_seq.wrapping_add(1);
+        let event_id = self.event_seq;
+        if let Some(tracker) = self.commits.as_mut() {
+            tracker.track(event_id, &topic, partition, offset);
+        }
+        Ok(vec![SourceEvent::new(
+            event_id,
+            self.key.clone(),
+            RawData::Bytes(payload),
+            stags.into(),
+        )])
+    }
+}
+
+/// 按配置的标签名附加消息坐标；标签名为空的项跳过。
+fn set_metadata_tags(
+    tags: &mut Tags,
+    names: &MetadataTags,
+    topic: &str,
+    partition: i32,
+    offset: i64,
+    timestamp: Option<i64>,
+) {
+    let values = [
+        (&names.topic, Some(topic.to_string())),
+        (&names.partition, Some(partition.to_string())),
+        (&names.offset, Some(offset.to_string())),
+        (&names.timestamp, timestamp.map(|ms| ms.to_string())),
+    ];
+    for (name, value) in values {
+        if let Some(value) = value
+            && !name.is_empty()
+        {
+            tags.set(name, value);
+        }
+    }
+}
+
+/// 消息 header 是否满足全部过滤条件；同名 header 出现多次时取第一个。
+fn headers_match(
+    filter: &BTreeMap<String, HeaderMatch>,
+    headers: Option<&BorrowedHeaders>,
+) -> bool {
+    filter.iter().all(|(key, cond)| {
+        let value = headers
+            .and_then(|headers| headers.iter().find(|header| header.key == key))
+            .map(|header| header.value);
+        cond.matches(value)
+    })
+}
+
+/// 采样字段明确为 false（`false`、`"false"`、`0`）时返回 true；
+/// 字段缺失、负载不是 JSON 对象或取其他值时保留消息。
+fn sampled_out(parser: &FieldAllowlist, field: &str, raw: &[u8]) -> bool {
+    let Ok(map) = parser.parse(raw) else {
+        return false;
+    };
+    match map.get(field) {
+        Some(serde_json::Value::Bool(sampled)) => !sampled,
+        Some(serde_json::Value::String(s)) => matches!(s.trim(), "false" | "0"),
+        Some(serde_json::Value::Number(n)) => n.as_f64() == Some(0.0),
+        _ => false,
     }
 }
 
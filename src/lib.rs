@@ -1,6 +1,9 @@
 /// Tag key for access source identifier
 pub const WP_SRC_VAL: &str = "wp_src_val";
 
+// 通用组件：各连接器共享（不依赖可选特性）
+pub mod common;
+
 // Kafka：默认启用（feature = "kafka" 是默认特性）
 #[cfg(feature = "kafka")]
 pub mod kafka;
@@ -17,16 +20,70 @@ pub mod prometheus;
 #[cfg(feature = "doris")]
 pub mod doris;
 
+// StarRocks：Stream Load sink，启用方式 `--features starrocks`
+#[cfg(feature = "starrocks")]
+pub mod starrocks;
+
 // VictoriaLog：可选功能，启用方式 `--features victorialog`
 #[cfg(feature = "victorialogs")]
 pub mod victorialogs;
 
-// Elasticsearch：预留可选特性，后续接入独立子 crate 时再暴露实现
+// Elasticsearch：bulk sink，启用方式 `--features elasticsearch`
 #[cfg(feature = "elasticsearch")]
-pub mod elasticsearch {
-    //! 占位模块：后续将通过 `wp_connectors::elasticsearch::*` 暴露 sink 实现
-}
+pub mod elasticsearch;
+
+// ClickHouse：HTTP JSONEachRow sink，启用方式 `--features clickhouse`
+#[cfg(feature = "clickhouse")]
+pub mod clickhouse;
+
+// OpenSearch：bulk sink（SigV4 签名、数据流），启用方式 `--features opensearch`
+#[cfg(feature = "opensearch")]
+pub mod opensearch;
+
+// TDengine：时序数据 sink（超级表/子表路由），启用方式 `--features tdengine`
+#[cfg(feature = "tdengine")]
+pub mod tdengine;
+
+// Null：丢弃型 sink，用于压测 source 吞吐，启用方式 `--features null`
+#[cfg(feature = "null")]
+pub mod null;
+
+// Stdout：把记录打印到 stdout/stderr 的调试 sink，启用方式 `--features stdout`
+#[cfg(feature = "stdout")]
+pub mod stdout;
+
+// TCP：流式 source（监听/连接），启用方式 `--features tcp`
+#[cfg(feature = "tcp")]
+pub mod tcp;
 
 // VictoriaMetrics：可选功能，启用方式 `--features victoriametric`
 #[cfg(feature = "victoriametrics")]
 pub mod victoriametrics;
+
+// Redis：列表/流/键值 sink，启用方式 `--features redis`
+#[cfg(feature = "redis")]
+pub mod redis;
+
+// PostgreSQL：逻辑复制 CDC source，启用方式 `--features postgres-cdc`
+#[cfg(feature = "postgres-cdc")]
+pub mod postgres;
+
+// S3：兼容 S3 协议的对象存储 sink（AWS S3 / MinIO 等），启用方式 `--features s3`
+#[cfg(feature = "s3")]
+pub mod s3;
+
+// File：本地文件 sink（按大小/日期轮转），启用方式 `--features file`
+#[cfg(feature = "file")]
+pub mod file;
+
+// HTTP：通用 webhook sink，启用方式 `--features http`
+#[cfg(feature = "http")]
+pub mod http;
+
+// NATS：Core NATS / JetStream 的 sink 与 source，启用方式 `--features nats`
+#[cfg(feature = "nats")]
+pub mod nats;
+
+// Pulsar：Apache Pulsar 的 sink 与 source，启用方式 `--features pulsar`
+#[cfg(feature = "pulsar")]
+pub mod pulsar;
@@ -0,0 +1,385 @@
+//! MySQL CDC（binlog 复制）的配置、位点与行变更映射。
+//!
+//! 变更以 JSON 文档输出，每行一条：
+//! ```json
+//! {"op":"update","database":"shop","table":"orders","ts":1700000000,
+//!  "binlog_file":"binlog.000003","binlog_pos":1289,
+//!  "before":{"id":1,"status":"new"},"after":{"id":1,"status":"paid"}}
+//! ```
+//! `insert` 的 `before` 与 `delete` 的 `after` 为 null。列名取自 binlog 的表元数据，
+//! 需服务端 `binlog_format=ROW`、`binlog_row_image=FULL`、`binlog_row_metadata=FULL`；
+//! 缺少列名时按 `col_<序号>` 命名。
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use mysql_async::Value as MysqlValue;
+use mysql_async::binlog::row::BinlogRow;
+use mysql_async::binlog::value::BinlogValue;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+use std::path::{Path, PathBuf};
+use wp_connector_api::ParamMap;
+
+/// 行变更类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CdcOp {
+    Insert,
+    Update,
+    Delete,
+}
+
+impl CdcOp {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Insert => "insert",
+            Self::Update => "update",
+            Self::Delete => "delete",
+        }
+    }
+
+    /// 由变更前/后镜像是否存在推断：仅后镜像为插入，仅前镜像为删除，两者皆有为更新。
+    pub fn from_images(has_before: bool, has_after: bool) -> Option<Self> {
+        match (has_before, has_after) {
+            (false, true) => Some(Self::Insert),
+            (true, true) => Some(Self::Update),
+            (true, false) => Some(Self::Delete),
+            (false, false) => None,
+        }
+    }
+}
+
+/// binlog 位点：文件+偏移，或 GTID 集合。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum BinlogPosition {
+    File { file: String, pos: u64 },
+    Gtid { gtid_set: String },
+}
+
+impl BinlogPosition {
+    /// 读取 checkpoint 文件；文件不存在或为空时返回 `None`。
+    pub fn load(path: &Path) -> anyhow::Result<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let contents = std::fs::read_to_string(path)?;
+        if contents.trim().is_empty() {
+            return Ok(None);
+        }
+        serde_json::from_str(&contents)
+            .map(Some)
+            .map_err(|e| anyhow::anyhow!("invalid binlog checkpoint '{}': {e}", path.display()))
+    }
+
+    /// 写入 checkpoint：先写临时文件再重命名，避免中途崩溃留下半个文件。
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, serde_json::to_string(self)?)?;
+        std::fs::rename(&tmp, path)?;
+        Ok(())
+    }
+}
+
+/// binlog checkpoint 文件路径，与查询模式的 checkpoint 同目录。
+pub fn checkpoint_path(key: &str) -> PathBuf {
+    PathBuf::from(format!("./.run/.checkpoints/{}.binlog.json", key))
+}
+
+/// CDC 模式配置。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MysqlCdcConf {
+    /// 以副本身份连接时使用的 server id，须在复制拓扑中唯一
+    pub server_id: u32,
+    /// 无 checkpoint 时的起始位点；未配置时从服务端当前位点开始
+    pub start: Option<BinlogPosition>,
+    /// 只采集的表（`database` 下）；为空时采集整个库
+    pub tables: Vec<String>,
+}
+
+impl MysqlCdcConf {
+    /// 读取 `server_id`、`binlog_file`/`binlog_pos`、`gtid_set` 与 `tables`/`table`。
+    pub fn from_params(params: &ParamMap) -> Result<Self, String> {
+        let server_id = match params.get("server_id").and_then(Value::as_u64) {
+            Some(id) if id > 0 && id <= u32::MAX as u64 => id as u32,
+            _ => return Err("mysql.server_id must be an integer in 1..=4294967295".into()),
+        };
+        let file = optional_str(params, "binlog_file")?;
+        let pos = match params.get("binlog_pos") {
+            None | Some(Value::Null) => None,
+            Some(v) => Some(
+                v.as_u64()
+                    .ok_or("mysql.binlog_pos must be a non-negative integer")?,
+            ),
+        };
+        let gtid_set = optional_str(params, "gtid_set")?;
+        let start = match (file, pos, gtid_set) {
+            (None, None, None) => None,
+            (Some(file), Some(pos), None) => Some(BinlogPosition::File { file, pos }),
+            (None, None, Some(gtid_set)) => Some(BinlogPosition::Gtid { gtid_set }),
+            (_, _, Some(_)) => {
+                return Err("mysql.gtid_set cannot be combined with binlog_file/binlog_pos".into());
+            }
+            _ => return Err("mysql.binlog_file and binlog_pos must be set together".into()),
+        };
+        let tables = match params.get("tables") {
+            None | Some(Value::Null) => optional_str(params, "table")?.into_iter().collect(),
+            Some(Value::Array(items)) => items
+                .iter()
+                .map(|item| match item.as_str().map(str::trim) {
+                    Some(s) if !s.is_empty() => Ok(s.to_string()),
+                    _ => Err("mysql.tables entries must be non-empty strings".to_string()),
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+            Some(_) => return Err("mysql.tables must be an array of strings".into()),
+        };
+        Ok(Self {
+            server_id,
+            start,
+            tables,
+        })
+    }
+
+    /// 是否采集该表。
+    pub fn captures(&self, table: &str) -> bool {
+        self.tables.is_empty() || self.tables.iter().any(|t| t == table)
+    }
+}
+
+fn optional_str(params: &ParamMap, key: &str) -> Result<Option<String>, String> {
+    match params.get(key) {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::String(s)) if !s.trim().is_empty() => Ok(Some(s.trim().to_string())),
+        Some(_) => Err(format!("mysql.{key} must be a non-empty string")),
+    }
+}
+
+/// 一行变更。
+#[derive(Debug, Clone, PartialEq)]
+pub struct RowChange {
+    pub op: CdcOp,
+    pub database: String,
+    pub table: String,
+    /// binlog 事件时间（Unix 秒）
+    pub timestamp: u32,
+    /// 所在事务提交后的位点
+    pub binlog_file: String,
+    pub binlog_pos: u64,
+    pub before: Option<Map<String, Value>>,
+    pub after: Option<Map<String, Value>>,
+}
+
+impl RowChange {
+    /// 输出给下游解析的 JSON 文档。
+    pub fn to_json(&self) -> String {
+        json!({
+            "op": self.op.as_str(),
+            "database": self.database,
+            "table": self.table,
+            "ts": self.timestamp,
+            "binlog_file": self.binlog_file,
+            "binlog_pos": self.binlog_pos,
+            "before": self.before,
+            "after": self.after,
+        })
+        .to_string()
+    }
+}
+
+/// 将 binlog 行镜像转换为 `列名 -> 值`。
+pub fn row_image(row: &BinlogRow) -> Map<String, Value> {
+    let columns = row.columns_ref();
+    (0..row.len())
+        .map(|idx| {
+            let name = columns
+                .get(idx)
+                .map(|c| c.name_str().into_owned())
+                .filter(|name| !name.is_empty())
+                .unwrap_or_else(|| format!("col_{}", idx));
+            let value = row.as_ref(idx).map(binlog_value).unwrap_or(Value::Null);
+            (name, value)
+        })
+        .collect()
+}
+
+/// binlog 列值转为 JSON：整数/浮点为数字，时间为文本，非 UTF-8 字节为 base64
+/// （与查询模式对二进制列的输出一致）。部分 JSON 更新（JSON diff）不支持，输出 null。
+pub fn binlog_value(value: &BinlogValue) -> Value {
+    match value {
+        BinlogValue::Value(v) => mysql_value(v),
+        BinlogValue::Jsonb(v) => v.clone().parse().map(Value::from).unwrap_or(Value::Null),
+        BinlogValue::JsonDiff(_) => Value::Null,
+    }
+}
+
+fn mysql_value(value: &MysqlValue) -> Value {
+    match value {
+        MysqlValue::NULL => Value::Null,
+        MysqlValue::Int(i) => json!(i),
+        MysqlValue::UInt(u) => json!(u),
+        MysqlValue::Float(f) => float_value(f64::from(*f)),
+        MysqlValue::Double(d) => float_value(*d),
+        MysqlValue::Bytes(bytes) => match std::str::from_utf8(bytes) {
+            Ok(s) => Value::String(s.to_string()),
+            Err(_) => Value::String(STANDARD.encode(bytes)),
+        },
+        MysqlValue::Date(y, mo, d, h, mi, s, us) => {
+            let mut out = format!("{:04}-{:02}-{:02} {:02}:{:02}:{:02}", y, mo, d, h, mi, s);
+            if *us > 0 {
+                out.push_str(&format!(".{:06}", us));
+            }
+            Value::String(out)
+        }
+        MysqlValue::Time(neg, days, h, mi, s, us) => {
+            let hours = *days * 24 + u32::from(*h);
+            let sign = if *neg { "-" } else { "" };
+            let mut out = format!("{}{:02}:{:02}:{:02}", sign, hours, mi, s);
+            if *us > 0 {
+                out.push_str(&format!(".{:06}", us));
+            }
+            Value::String(out)
+        }
+    }
+}
+
+/// NaN/无穷不是合法 JSON 数字，以文本输出。
+fn float_value(f: f64) -> Value {
+    serde_json::Number::from_f64(f)
+        .map(Value::Number)
+        .unwrap_or_else(|| Value::String(f.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn position_roundtrips_through_checkpoint_file() {
+        let path = std::env::temp_dir()
+            .join(format!("wp_mysql_binlog_{}", std::process::id()))
+            .join("src.binlog.json");
+        assert_eq!(BinlogPosition::load(&path).unwrap(), None);
+
+        let file = BinlogPosition::File {
+            file: "binlog.000003".into(),
+            pos: 1289,
+        };
+        file.save(&path).unwrap();
+        assert_eq!(BinlogPosition::load(&path).unwrap(), Some(file.clone()));
+        assert_eq!(
+            serde_json::to_value(&file).unwrap(),
+            json!({"kind": "file", "file": "binlog.000003", "pos": 1289})
+        );
+
+        let gtid: BinlogPosition = serde_json::from_str(
+            r#"{"kind":"gtid","gtid_set":"3e11fa47-71ca-11e1-9e33-c80aa9429562:1-5"}"#,
+        )
+        .unwrap();
+        gtid.save(&path).unwrap();
+        assert_eq!(BinlogPosition::load(&path).unwrap(), Some(gtid));
+
+        std::fs::write(&path, "{oops").unwrap();
+        assert!(BinlogPosition::load(&path).is_err());
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn row_change_maps_to_record_document() {
+        let mut before = Map::new();
+        before.insert(
+            "id".into(),
+            binlog_value(&BinlogValue::Value(MysqlValue::Int(1))),
+        );
+        before.insert(
+            "status".into(),
+            binlog_value(&BinlogValue::Value(MysqlValue::Bytes(b"new".to_vec()))),
+        );
+        let mut after = before.clone();
+        after.insert(
+            "status".into(),
+            binlog_value(&BinlogValue::Value(MysqlValue::Bytes(b"paid".to_vec()))),
+        );
+        let change = RowChange {
+            op: CdcOp::from_images(true, true).unwrap(),
+            database: "shop".into(),
+            table: "orders".into(),
+            timestamp: 1_700_000_000,
+            binlog_file: "binlog.000003".into(),
+            binlog_pos: 1289,
+            before: Some(before),
+            after: Some(after),
+        };
+        let doc: Value = serde_json::from_str(&change.to_json()).unwrap();
+        assert_eq!(
+            doc,
+            json!({
+                "op": "update", "database": "shop", "table": "orders", "ts": 1_700_000_000,
+                "binlog_file": "binlog.000003", "binlog_pos": 1289,
+                "before": {"id": 1, "status": "new"}, "after": {"id": 1, "status": "paid"},
+            })
+        );
+
+        assert_eq!(CdcOp::from_images(false, true), Some(CdcOp::Insert));
+        assert_eq!(CdcOp::from_images(true, false), Some(CdcOp::Delete));
+        let delete = RowChange {
+            op: CdcOp::Delete,
+            after: None,
+            ..change
+        };
+        let doc: Value = serde_json::from_str(&delete.to_json()).unwrap();
+        assert_eq!(doc["op"], "delete");
+        assert!(doc["after"].is_null());
+    }
+
+    #[test]
+    fn binlog_values_convert_to_json() {
+        let v = |value: MysqlValue| binlog_value(&BinlogValue::Value(value));
+        assert_eq!(v(MysqlValue::NULL), Value::Null);
+        assert_eq!(v(MysqlValue::UInt(u64::MAX)), json!(u64::MAX));
+        assert_eq!(v(MysqlValue::Double(1.5)), json!(1.5));
+        assert_eq!(v(MysqlValue::Double(f64::NAN)), json!("NaN"));
+        assert_eq!(v(MysqlValue::Bytes(vec![0xff, 0x00, 0x10])), json!("/wAQ"));
+        assert_eq!(
+            v(MysqlValue::Date(2024, 1, 2, 3, 4, 5, 600)),
+            json!("2024-01-02 03:04:05.000600")
+        );
+        assert_eq!(v(MysqlValue::Time(true, 1, 2, 3, 4, 0)), json!("-26:03:04"));
+    }
+
+    #[test]
+    fn cdc_conf_requires_server_id_and_consistent_start() {
+        let params =
+            |v: Value| -> ParamMap { v.as_object().unwrap().clone().into_iter().collect() };
+        let conf = MysqlCdcConf::from_params(&params(json!({
+            "server_id": 1001, "binlog_file": "binlog.000001", "binlog_pos": 4, "table": "orders"
+        })))
+        .unwrap();
+        assert_eq!(
+            conf.start,
+            Some(BinlogPosition::File {
+                file: "binlog.000001".into(),
+                pos: 4
+            })
+        );
+        assert!(conf.captures("orders") && !conf.captures("users"));
+
+        let conf = MysqlCdcConf::from_params(&params(json!({"server_id": 7}))).unwrap();
+        assert_eq!(conf.start, None);
+        assert!(conf.captures("anything"));
+
+        for bad in [
+            json!({}),
+            json!({"server_id": 0}),
+            json!({"server_id": 1, "binlog_file": "binlog.000001"}),
+            json!({"server_id": 1, "gtid_set": "x:1-2", "binlog_pos": 4}),
+            json!({"server_id": 1, "tables": ["a", ""]}),
+        ] {
+            assert!(
+                MysqlCdcConf::from_params(&params(bad.clone())).is_err(),
+                "{bad}"
+            );
+        }
+    }
+}
@@ -0,0 +1,231 @@
+//! MySQL CDC source：以副本身份订阅 binlog，按事务输出 insert/update/delete 行变更。
+//!
+//! 起始位点依次取 checkpoint、配置的 `binlog_file`/`binlog_pos` 或 `gtid_set`、
+//! 服务端当前位点。行变更在事务提交（XID）后才输出，checkpoint 只记录事务边界的文件位点，
+//! 在下一次 `receive` 时（上一批已交给下游后）写入，重启后从最后交付的事务之后继续。
+
+use async_trait::async_trait;
+use futures_util::StreamExt;
+use mysql_async::binlog::events::EventData;
+use mysql_async::prelude::Queryable;
+use mysql_async::{BinlogStream, BinlogStreamRequest, Conn, Opts, Row, Sid};
+use std::path::PathBuf;
+use std::str::FromStr;
+use wp_connector_api::{
+    DataSource, SourceBatch, SourceError, SourceEvent, SourceReason, SourceResult, Tags,
+};
+use wp_parse_api::RawData;
+
+use crate::mysql::binlog::{
+    BinlogPosition, CdcOp, MysqlCdcConf, RowChange, checkpoint_path, row_image,
+};
+use crate::mysql::config::MysqlConf;
+
+pub struct MysqlCdcSource {
+    key: String,
+    tags: Tags,
+    database: String,
+    conf: MysqlCdcConf,
+    stream: BinlogStream,
+    /// 当前读取到的 binlog 文件（由 rotate 事件更新）
+    file: String,
+    /// 当前事务内已解析、尚未提交的变更
+    pending: Vec<RowChange>,
+    /// 已交付给下游、待写入 checkpoint 的位点
+    delivered: Option<BinlogPosition>,
+    checkpoint_path: PathBuf,
+    event_seq: u64,
+}
+
+impl MysqlCdcSource {
+    pub async fn new(
+        key: String,
+        tags: Tags,
+        config: &MysqlConf,
+        conf: MysqlCdcConf,
+    ) -> anyhow::Result<Self> {
+        let checkpoint_path = checkpoint_path(&key);
+        let checkpoint = BinlogPosition::load(&checkpoint_path)?;
+        let opts = Opts::from_url(&config.get_database_url())?;
+        let mut conn = Conn::new(opts).await?;
+        let start = match checkpoint.or_else(|| conf.start.clone()) {
+            Some(start) => start,
+            None => current_position(&mut conn).await?,
+        };
+        wp_log::info_data!(
+            "[mysql-cdc] {} server_id: {}, database: {}, start: {:?}",
+            key,
+            conf.server_id,
+            config.database,
+            start
+        );
+
+        let request = BinlogStreamRequest::new(conf.server_id);
+        let (stream, file) = match &start {
+            BinlogPosition::File { file, pos } => {
+                let request = request.with_filename(file.as_bytes()).with_pos(*pos);
+                (conn.get_binlog_stream(request).await?, file.clone())
+            }
+            BinlogPosition::Gtid { gtid_set } => {
+                let sids = gtid_set
+                    .split(',')
+                    .map(|sid| Sid::from_str(sid.trim()))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|e| anyhow::anyhow!("invalid mysql.gtid_set: {e}"))?;
+                let request = request.with_gtid().with_gtid_set(sids);
+                // 文件名由连接后服务端发送的 rotate 事件给出
+                (conn.get_binlog_stream(request).await?, String::new())
+            }
+        };
+        Ok(Self {
+            key,
+            tags,
+            database: config.database.clone(),
+            conf,
+            stream,
+            file,
+            pending: Vec::new(),
+            delivered: None,
+            checkpoint_path,
+            event_seq: 0,
+        })
+    }
+
+    pub fn identifier(&self) -> &str {
+        &self.key
+    }
+
+    fn create_event(&mut self, change: &RowChange) -> SourceEvent {
+        self.event_seq = self.event_seq.wrapping_add(1);
+        SourceEvent::new(
+            self.event_seq,
+            self.key.clone(),
+            RawData::from_string(change.to_json()),
+            self.tags.clone().into(),
+        )
+    }
+
+    /// 写入上一批已交付的位点。
+    fn commit_delivered(&mut self) {
+        if let Some(pos) = self.delivered.take()
+            && let Err(e) = pos.save(&self.checkpoint_path)
+        {
+            wp_log::error_data!(
+                "[mysql-cdc] {} save checkpoint {:?} fail: {}",
+                self.key,
+                pos,
+                e
+            );
+        }
+    }
+
+    /// 读取 binlog 直到有事务提交且包含采集的变更。
+    async fn next_transaction(&mut self) -> SourceResult<Vec<RowChange>> {
+        loop {
+            let Some(event) = self.stream.next().await else {
+                return Err(SourceError::from(SourceReason::SupplierError(
+                    "mysql binlog stream closed".to_string(),
+                )));
+            };
+            let event = event.map_err(|e| stream_error("read binlog event", e))?;
+            let log_pos = u64::from(event.header().log_pos());
+            let timestamp = event.header().timestamp();
+            let data = event
+                .read_data()
+                .map_err(|e| stream_error("decode binlog event", e))?;
+            match data {
+                Some(EventData::RotateEvent(rotate)) => {
+                    self.file = rotate.name().into_owned();
+                }
+                Some(EventData::RowsEvent(rows)) => {
+                    let Some(tme) = self.stream.get_tme(rows.table_id()) else {
+                        return Err(SourceError::from(SourceReason::SupplierError(format!(
+                            "mysql binlog rows event for unknown table id {}",
+                            rows.table_id()
+                        ))));
+                    };
+                    let database = tme.database_name().into_owned();
+                    let table = tme.table_name().into_owned();
+                    if database != self.database || !self.conf.captures(&table) {
+                        continue;
+                    }
+                    for row in rows.rows(tme) {
+                        let (before, after) = row.map_err(|e| stream_error("decode row", e))?;
+                        let Some(op) = CdcOp::from_images(before.is_some(), after.is_some()) else {
+                            continue;
+                        };
+                        self.pending.push(RowChange {
+                            op,
+                            database: database.clone(),
+                            table: table.clone(),
+                            timestamp,
+                            binlog_file: String::new(),
+                            binlog_pos: 0,
+                            before: before.as_ref().map(row_image),
+                            after: after.as_ref().map(row_image),
+                        });
+                    }
+                }
+                Some(EventData::XidEvent(_)) => {
+                    let mut changes = std::mem::take(&mut self.pending);
+                    for change in &mut changes {
+                        change.binlog_file = self.file.clone();
+                        change.binlog_pos = log_pos;
+                    }
+                    self.delivered = Some(BinlogPosition::File {
+                        file: self.file.clone(),
+                        pos: log_pos,
+                    });
+                    if !changes.is_empty() {
+                        return Ok(changes);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// 服务端当前 binlog 位点（MySQL 8.4 起 `SHOW MASTER STATUS` 更名为 `SHOW BINARY LOG STATUS`）。
+async fn current_position(conn: &mut Conn) -> anyhow::Result<BinlogPosition> {
+    let row = match conn.query_first::<Row, _>("SHOW BINARY LOG STATUS").await {
+        Ok(row) => row,
+        Err(_) => conn.query_first::<Row, _>("SHOW MASTER STATUS").await?,
+    };
+    let row = row.ok_or_else(|| anyhow::anyhow!("mysql binary logging is not enabled"))?;
+    let file = row
+        .get_opt::<String, _>(0)
+        .and_then(Result::ok)
+        .ok_or_else(|| anyhow::anyhow!("mysql binlog status has no file"))?;
+    let pos = row
+        .get_opt::<u64, _>(1)
+        .and_then(Result::ok)
+        .ok_or_else(|| anyhow::anyhow!("mysql binlog status has no position"))?;
+    Ok(BinlogPosition::File { file, pos })
+}
+
+fn stream_error(what: &str, err: impl std::fmt::Display) -> SourceError {
+    SourceError::from(SourceReason::SupplierError(format!(
+        "mysql {what} fail: {err}"
+    )))
+}
+
+#[async_trait]
+impl DataSource for MysqlCdcSource {
+    async fn receive(&mut self) -> SourceResult<SourceBatch> {
+        self.commit_delivered();
+        let changes = self.next_transaction().await?;
+        Ok(changes
+            .iter()
+            .map(|change| self.create_event(change))
+            .collect())
+    }
+
+    fn try_receive(&mut self) -> Option<SourceBatch> {
+        None
+    }
+
+    fn identifier(&self) -> String {
+        self.key.clone()
+    }
+}
@@ -1,5 +1,10 @@
-use crate::mysql::config::MysqlConf;
+use crate::mysql::config::{InsertMode, MysqlConf};
 
+#[cfg(feature = "mysql-cdc")]
+use super::binlog::MysqlCdcConf;
+#[cfg(feature = "mysql-cdc")]
+use super::cdc_source::MysqlCdcSource;
+use super::poll_source::{MysqlPollConf, MysqlPollSource};
 use super::sink::MysqlSink;
 use super::source::MysqlSource;
 use async_trait::async_trait;
@@ -8,12 +13,22 @@ use serde_json::json;
 use std::time::Duration;
 use wp_conf_base::ConfParser;
 use wp_connector_api::{
-    ConnectorDef, ConnectorScope, ParamMap, SinkBuildCtx, SinkDefProvider, SinkError, SinkFactory,
-    SinkHandle, SinkReason, SinkResult, SinkSpec, SourceDefProvider, SourceFactory, SourceHandle,
-    SourceMeta, SourceReason, SourceResult, SourceSvcIns, Tags,
+    ConnectorDef, ConnectorScope, DataSource, ParamMap, SinkBuildCtx, SinkDefProvider, SinkError,
+    SinkFactory, SinkHandle, SinkReason, SinkResult, SinkSpec, SourceDefProvider, SourceFactory,
+    SourceHandle, SourceMeta, SourceReason, SourceResult, SourceSvcIns, Tags,
 };
 
 use crate::WP_SRC_VAL;
+use crate::common::enrich::{EnrichConf, EnrichSink};
+use crate::common::flush_notify::FlushNotifier;
+use crate::common::health::{CHECK_TIMEOUT, CheckConnection, Readiness, probe};
+use crate::common::reconfigure;
+use crate::common::retry::{self, RetryingSink};
+use crate::common::schema_file::ColumnSchema;
+use crate::common::secret;
+use crate::common::stats;
+use crate::common::table_route::TableTemplate;
+use crate::common::transform::FieldTransforms;
 
 pub struct MySQLSourceFactory;
 
@@ -24,6 +39,7 @@ impl wp_connector_api::SourceFactory for MySQLSourceFactory {
     }
 
     fn validate_spec(&self, spec: &wp_connector_api::SourceSpec) -> SourceResult<()> {
+        let spec = &secret::resolve_source_spec(spec)?;
         let endpoint = spec
             .params
             .get("endpoint")
@@ -41,6 +57,13 @@ impl wp_connector_api::SourceFactory for MySQLSourceFactory {
         if database.trim().is_empty() {
             return Err(SourceReason::Other("mysql.database must not be empty".into()).into());
         }
+        match source_mode(&spec.params)? {
+            SourceMode::Query => {}
+            SourceMode::Cdc => validate_cdc(&spec.params)?,
+            SourceMode::Poll => {
+                MysqlPollConf::from_params(&spec.params).map_err(SourceReason::Other)?;
+            }
+        }
 
         Ok(())
     }
@@ -50,6 +73,7 @@ impl wp_connector_api::SourceFactory for MySQLSourceFactory {
         spec: &wp_connector_api::SourceSpec,
         _ctx: &wp_connector_api::SourceBuildCtx,
     ) -> SourceResult<SourceSvcIns> {
+        let spec = &secret::resolve_source_spec(spec)?;
         let mut conf = MysqlConf::default();
 
         if let Some(s) = spec.params.get("endpoint").and_then(|v| v.as_str()) {
@@ -76,17 +100,90 @@ impl wp_connector_api::SourceFactory for MySQLSourceFactory {
         }
         let mut meta_tags = Tags::from_parse(&spec.tags);
         meta_tags.set(WP_SRC_VAL, "mysql");
-        let source = MysqlSource::new(spec.name.clone(), meta_tags.clone(), &conf)
-            .await
-            .map_err(|err| SourceReason::Other(err.to_string()))?;
+        let source: Box<dyn DataSource> = match source_mode(&spec.params)? {
+            SourceMode::Query => {
+                let source = MysqlSource::new(spec.name.clone(), meta_tags.clone(), &conf)
+                    .await
+                    .map_err(|err| SourceReason::Other(err.to_string()))?;
+                Box::new(source)
+            }
+            SourceMode::Cdc => build_cdc_source(spec, meta_tags.clone(), &conf).await?,
+            SourceMode::Poll => {
+                let poll = MysqlPollConf::from_params(&spec.params).map_err(SourceReason::Other)?;
+                let source =
+                    MysqlPollSource::new(spec.name.clone(), meta_tags.clone(), &conf, poll)
+                        .await
+                        .map_err(|err| SourceReason::Other(format!("mysql poll fail: {err}")))?;
+                Box::new(source)
+            }
+        };
 
         let mut meta = SourceMeta::new(spec.name.clone(), spec.kind.clone());
         meta.tags = meta_tags;
-        let handle = SourceHandle::new(Box::new(source), meta);
+        let handle = SourceHandle::new(source, meta);
         Ok(SourceSvcIns::new().with_sources(vec![handle]))
     }
 }
 
+/// source 读取方式：按 `table` 分页读取、binlog CDC，或按游标列增量轮询。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SourceMode {
+    Query,
+    Cdc,
+    Poll,
+}
+
+/// 解析 source `mode`（`query` | `cdc` | `poll`），缺省为 `query`。
+fn source_mode(params: &ParamMap) -> SourceResult<SourceMode> {
+    match params.get("mode") {
+        None => Ok(SourceMode::Query),
+        Some(v) => match v.as_str().map(|s| s.trim().to_ascii_lowercase()).as_deref() {
+            Some("query") => Ok(SourceMode::Query),
+            Some("cdc") => Ok(SourceMode::Cdc),
+            Some("poll") => Ok(SourceMode::Poll),
+            _ => Err(
+                SourceReason::Other("mysql.mode must be 'query', 'cdc' or 'poll'".into()).into(),
+            ),
+        },
+    }
+}
+
+#[cfg(feature = "mysql-cdc")]
+fn validate_cdc(params: &ParamMap) -> SourceResult<()> {
+    MysqlCdcConf::from_params(params).map_err(SourceReason::Other)?;
+    Ok(())
+}
+
+#[cfg(not(feature = "mysql-cdc"))]
+const CDC_DISABLED: &str = "mysql.mode 'cdc' requires the mysql-cdc feature";
+
+#[cfg(not(feature = "mysql-cdc"))]
+fn validate_cdc(_params: &ParamMap) -> SourceResult<()> {
+    Err(SourceReason::Other(CDC_DISABLED.into()).into())
+}
+
+#[cfg(feature = "mysql-cdc")]
+async fn build_cdc_source(
+    spec: &wp_connector_api::SourceSpec,
+    tags: Tags,
+    conf: &MysqlConf,
+) -> SourceResult<Box<dyn DataSource>> {
+    let cdc = MysqlCdcConf::from_params(&spec.params).map_err(SourceReason::Other)?;
+    let source = MysqlCdcSource::new(spec.name.clone(), tags, conf, cdc)
+        .await
+        .map_err(|err| SourceReason::Other(format!("mysql cdc fail: {err}")))?;
+    Ok(Box::new(source))
+}
+
+#[cfg(not(feature = "mysql-cdc"))]
+async fn build_cdc_source(
+    _spec: &wp_connector_api::SourceSpec,
+    _tags: Tags,
+    _conf: &MysqlConf,
+) -> SourceResult<Box<dyn DataSource>> {
+    Err(SourceReason::Other(CDC_DISABLED.into()).into())
+}
+
 pub struct MySQLSinkFactory;
 
 #[async_trait]
@@ -95,6 +192,7 @@ impl SinkFactory for MySQLSinkFactory {
         "mysql"
     }
     fn validate_spec(&self, spec: &SinkSpec) -> SinkResult<()> {
+        let spec = &secret::resolve_sink_spec(spec)?;
         let endpoint = spec
             .params
             .get("endpoint")
@@ -116,9 +214,45 @@ impl SinkFactory for MySQLSinkFactory {
         {
             return Err(SinkReason::sink("mysql.batch must be > 0").into());
         }
+        if let Some(v) = spec.params.get("transactional")
+            && !v.is_boolean()
+        {
+            return Err(SinkReason::sink("mysql.transactional must be a boolean").into());
+        }
+        if let Some(v) = spec.params.get("finalize_sql")
+            && v.as_str().is_none_or(|s| s.trim().is_empty())
+        {
+            return Err(SinkReason::sink("mysql.finalize_sql must be a non-empty string").into());
+        }
+        if let Some(v) = spec.params.get("create_table")
+            && !v.as_str().is_some_and(|s| s.contains("{table}"))
+        {
+            return Err(
+                SinkReason::sink("mysql.create_table must be a string containing {table}").into(),
+            );
+        }
+        if let Some(v) = spec.params.get("auto_schema") {
+            if !v.is_boolean() {
+                return Err(SinkReason::sink("mysql.auto_schema must be a boolean").into());
+            }
+            if v.as_bool() == Some(true) && spec.params.contains_key("create_table") {
+                return Err(SinkReason::sink(
+                    "mysql.auto_schema and mysql.create_table are mutually exclusive",
+                )
+                .into());
+            }
+        }
+        parse_insert_mode(spec)?;
+        load_schema_file(spec)?;
+        TableTemplate::from_params(&spec.params, "mysql")?;
+        EnrichConf::from_params(&spec.params)?;
+        retry::validate_params(&spec.params)?;
+        FieldTransforms::from_params(&spec.params)?;
+        FlushNotifier::from_params(&spec.params)?;
         Ok(())
     }
     async fn build(&self, spec: &SinkSpec, _ctx: &SinkBuildCtx) -> SinkResult<SinkHandle> {
+        let spec = &secret::resolve_sink_spec(spec)?;
         // Build Mysql conf from flat params
         let mut conf = MysqlConf::default();
         if let Some(s) = spec.params.get("endpoint").and_then(|v| v.as_str()) {
@@ -140,6 +274,25 @@ impl SinkFactory for MySQLSinkFactory {
         if let Some(i) = spec.params.get("batch").and_then(|v| v.as_u64()) {
             conf.batch = Some(i as usize);
         }
+        if let Some(b) = spec.params.get("transactional").and_then(|v| v.as_bool()) {
+            conf.transactional = b;
+        }
+        if let Some(s) = spec.params.get("finalize_sql").and_then(|v| v.as_str()) {
+            conf.finalize_sql = Some(s.to_string());
+        }
+        if let Some(s) = spec.params.get("create_table").and_then(|v| v.as_str()) {
+            conf.create_table = Some(s.trim().to_string());
+        }
+        if let Some(b) = spec.params.get("auto_schema").and_then(|v| v.as_bool()) {
+            conf.auto_schema = b;
+        }
+        conf.insert_mode = parse_insert_mode(spec)?;
+        let column_schema = load_schema_file(spec)?;
+        conf.schema_file = spec
+            .params
+            .get("schema_file")
+            .and_then(|v| v.as_str())
+            .map(str::to_string);
         // columns 列表在新版配置中不在 conf 中，作为外部参数传入 sink
         let mut columns: Vec<String> =
             if let Some(arr) = spec.params.get("columns").and_then(|v| v.as_array()) {
@@ -174,21 +327,93 @@ impl SinkFactory for MySQLSinkFactory {
             SinkError::from(SinkReason::sink(format!("connect mysql fail: {err}")))
         })?;
         let table = conf.table.clone().unwrap_or_else(|| spec.name.clone());
-        let sink = MysqlSink::new(db, table, columns, conf.batch, url);
+        let sink = MysqlSink::new(db, table, columns, conf.batch, url)
+            .with_transactional(conf.transactional)
+            .with_finalize_sql(conf.finalize_sql.clone())
+            .with_insert_mode(conf.insert_mode)
+            .with_column_schema(column_schema)
+            .with_create_table(conf.create_table.clone())
+            .with_auto_schema(conf.auto_schema)
+            .with_table_template(TableTemplate::from_params(&spec.params, "mysql")?)
+            .with_flush_notifier(FlushNotifier::from_params(&spec.params)?)
+            .with_stats(stats::register(&spec.name, self.kind()));
+        let enrich = EnrichConf::from_params(&spec.params)?;
+        let transforms = FieldTransforms::from_params(&spec.params)?;
+        let sink = RetryingSink::from_spec(sink, spec).await?;
+        reconfigure::register(&spec.name, sink.reconfigure_handle());
+        let sink = EnrichSink::new(sink, enrich).with_transforms(transforms);
         Ok(SinkHandle::new(Box::new(sink)))
     }
 }
 
+#[async_trait]
+impl CheckConnection for MySQLSinkFactory {
+    /// 以单连接执行 `SELECT 1`（sea-orm `ping`），校验地址、凭据与数据库。
+    async fn check_connection(&self, spec: &SinkSpec) -> Readiness {
+        let spec = match secret::resolve_sink_spec(spec) {
+            Ok(spec) => spec,
+            Err(err) => return Readiness::not_ready(self.kind(), err),
+        };
+        let param = |key: &str| spec.params.get(key).and_then(|v| v.as_str());
+        let mut conf = MysqlConf::default();
+        if let Some(s) = param("endpoint") {
+            conf.endpoint = s.to_string();
+        }
+        if let Some(s) = param("username") {
+            conf.username = s.to_string();
+        }
+        if let Some(s) = param("password") {
+            conf.password = s.to_string();
+        }
+        if let Some(s) = param("database") {
+            conf.database = s.to_string();
+        }
+        let target = format!("{}/{}", conf.endpoint, conf.database);
+        let mut opt = ConnectOptions::new(conf.get_database_url());
+        opt.max_connections(1)
+            .min_connections(0)
+            .connect_timeout(CHECK_TIMEOUT)
+            .acquire_timeout(CHECK_TIMEOUT)
+            .sqlx_logging(false);
+        probe(self.kind(), &target, async move {
+            let db = Database::connect(opt)
+                .await
+                .map_err(|e| format!("connect mysql fail: {e}"))?;
+            let pinged = db.ping().await.map_err(|e| format!("ping mysql fail: {e}"));
+            let _ = db.close().await;
+            pinged.map(|_| None)
+        })
+        .await
+    }
+}
+
 impl SourceDefProvider for MySQLSourceFactory {
     fn source_def(&self) -> ConnectorDef {
         ConnectorDef {
             id: "mysql_src".into(),
             kind: self.kind().into(),
             scope: ConnectorScope::Source,
-            allow_override: vec!["endpoint", "database", "table", "username", "batch"]
-                .into_iter()
-                .map(str::to_string)
-                .collect(),
+            allow_override: vec![
+                "endpoint",
+                "database",
+                "table",
+                "username",
+                "batch",
+                "secret_ref",
+                "mode",
+                "server_id",
+                "binlog_file",
+                "binlog_pos",
+                "gtid_set",
+                "tables",
+                "query",
+                "cursor_column",
+                "poll_interval_ms",
+                "cursor_start",
+            ]
+            .into_iter()
+            .map(str::to_string)
+            .collect(),
             default_params: mysql_source_defaults(),
             origin: Some("wp-connectors:mysql_source".into()),
         }
@@ -202,7 +427,27 @@ impl SinkDefProvider for MySQLSinkFactory {
             kind: self.kind().into(),
             scope: ConnectorScope::Sink,
             allow_override: vec![
-                "endpoint", "database", "table", "username", "batch", "columns",
+                "endpoint",
+                "database",
+                "table",
+                "table_template",
+                "username",
+                "secret_ref",
+                "batch",
+                "columns",
+                "transactional",
+                "finalize_sql",
+                "insert_mode",
+                "schema_file",
+                "create_table",
+                "auto_schema",
+                "enrich",
+                "enrich_overwrite",
+                "field_transforms",
+                "retry",
+                "write_deadline_ms",
+                "quarantine",
+                "flush_notify",
             ]
             .into_iter()
             .map(str::to_string)
@@ -213,6 +458,30 @@ impl SinkDefProvider for MySQLSinkFactory {
     }
 }
 
+/// 解析写入语句 `insert_mode`（`ignore` | `replace`），缺省为 `ignore`。
+fn parse_insert_mode(spec: &SinkSpec) -> SinkResult<InsertMode> {
+    match spec.params.get("insert_mode") {
+        None => Ok(InsertMode::default()),
+        Some(v) => match v.as_str() {
+            Some(s) => s.parse().map_err(|e: String| SinkReason::sink(e).into()),
+            None => Err(SinkReason::sink("mysql.insert_mode must be a string").into()),
+        },
+    }
+}
+
+/// 加载 `schema_file` 声明的列类型；未配置时返回 `None`。
+fn load_schema_file(spec: &SinkSpec) -> SinkResult<Option<ColumnSchema>> {
+    match spec.params.get("schema_file") {
+        None => Ok(None),
+        Some(v) => match v.as_str().map(str::trim) {
+            Some(path) if !path.is_empty() => ColumnSchema::load(path)
+                .map(Some)
+                .map_err(|e| SinkReason::sink(format!("mysql: {e}")).into()),
+            _ => Err(SinkReason::sink("mysql.schema_file must be a non-empty string").into()),
+        },
+    }
+}
+
 fn mysql_source_defaults() -> ParamMap {
     let mut params = ParamMap::new();
     params.insert("endpoint".into(), json!("mysql://localhost:3306"));
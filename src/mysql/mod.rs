@@ -2,16 +2,27 @@
 //!
 //! 模块划分：
 //! - source：MySqlSource & 错误映射/建 Topic
+//! - poll_source：按游标列增量轮询的 source（source 配置 `mode = "poll"`）
+//! - cdc_source：binlog 复制的 CDC source（`mysql-cdc` 特性，source 配置 `mode = "cdc"`）
 //! - sink：MySqlSink（AsyncRawDataSink/AsyncRecordSink）
 //! - factory：Source/Sink 工厂与注册函数
 
 mod adapter;
+#[cfg(feature = "mysql-cdc")]
+pub mod binlog;
+#[cfg(feature = "mysql-cdc")]
+mod cdc_source;
 mod config;
 mod factory;
+mod poll_source;
 mod sink;
 mod source;
 
 // 统一导出：便于上游 `wp_connector_mysql::Source/Sink/Factory` 使用
+#[cfg(feature = "mysql-cdc")]
+pub use cdc_source::MysqlCdcSource;
+pub use config::{InsertMode, MysqlConf};
 pub use factory::{MySQLSinkFactory, MySQLSourceFactory};
+pub use poll_source::{CursorStore, MysqlPollConf, MysqlPollSource};
 pub use sink::MysqlSink;
 pub use source::MysqlSource;
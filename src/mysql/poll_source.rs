@@ -0,0 +1,430 @@
+//! MySQL 增量轮询 source：用于无法接入 binlog 的表，按单调递增的游标列周期执行用户 SELECT，
+//! 只输出游标大于上次位置的新行。
+//!
+//! 配置示例：
+//! ```toml
+//! mode = "poll"
+//! query = "SELECT id, name, updated_at FROM orders"
+//! cursor_column = "id"        # 结果集中单调递增且唯一的列
+//! poll_interval_ms = 1000     # 无新行时的轮询间隔
+//! cursor_start = 0            # 可选：首次运行（无已保存游标）时的起点，缺省从头开始
+//! batch = 1024                # 每次查询最多读取的行数
+//! ```
+//!
+//! 游标在下一次 `receive` 时（上一批已交给下游后）写入 checkpoint，重启后从最后交付的行之后继续。
+//! 游标值相同的行在跨批次时可能被跳过，因此游标列应唯一。
+
+use async_trait::async_trait;
+use sea_orm::sqlx::{Column, Executor, TypeInfo};
+use sea_orm::{
+    ConnectOptions, ConnectionTrait, Database, DatabaseBackend, DatabaseConnection, Statement,
+    Value,
+};
+use serde_json::Value as JsonValue;
+use std::path::PathBuf;
+use std::time::Duration;
+use wp_connector_api::{
+    DataSource, ParamMap, SourceBatch, SourceError, SourceEvent, SourceReason, SourceResult, Tags,
+};
+use wp_parse_api::RawData;
+
+use crate::mysql::config::MysqlConf;
+
+const DEFAULT_POLL_INTERVAL_MS: u64 = 1000;
+const DEFAULT_POLL_BATCH: usize = 1024;
+/// 包裹用户查询的派生表别名
+const POLL_ALIAS: &str = "wp_poll";
+
+/// 轮询模式配置。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MysqlPollConf {
+    pub query: String,
+    pub cursor_column: String,
+    pub poll_interval: Duration,
+    /// 无已保存游标时的起点；缺省从头开始
+    pub cursor_start: Option<String>,
+    pub batch: usize,
+}
+
+impl MysqlPollConf {
+    /// 读取 `query`、`cursor_column`、`poll_interval_ms`、`cursor_start` 与 `batch`。
+    pub fn from_params(params: &ParamMap) -> Result<Self, String> {
+        let query = match params.get("query").and_then(JsonValue::as_str) {
+            Some(q) => q.trim().trim_end_matches(';').trim().to_string(),
+            None => return Err("mysql.query is required in poll mode".into()),
+        };
+        let is_select = query
+            .get(..6)
+            .is_some_and(|head| head.eq_ignore_ascii_case("select"));
+        if !is_select {
+            return Err("mysql.query must be a SELECT statement".into());
+        }
+        let cursor_column = match params.get("cursor_column").and_then(JsonValue::as_str) {
+            Some(c) if !c.trim().is_empty() && !c.contains('`') => c.trim().to_string(),
+            _ => {
+                return Err("mysql.cursor_column must be a non-empty column name".into());
+            }
+        };
+        let poll_interval_ms = match params.get("poll_interval_ms") {
+            None | Some(JsonValue::Null) => DEFAULT_POLL_INTERVAL_MS,
+            Some(v) => match v.as_u64() {
+                Some(ms) if ms > 0 => ms,
+                _ => return Err("mysql.poll_interval_ms must be a positive integer".into()),
+            },
+        };
+        let cursor_start = match params.get("cursor_start") {
+            None | Some(JsonValue::Null) => None,
+            Some(JsonValue::String(s)) if !s.trim().is_empty() => Some(s.trim().to_string()),
+            Some(JsonValue::Number(n)) => Some(n.to_string()),
+            Some(_) => return Err("mysql.cursor_start must be a string or number".into()),
+        };
+        let batch = match params.get("batch") {
+            None | Some(JsonValue::Null) => DEFAULT_POLL_BATCH,
+            Some(v) => match v.as_u64() {
+                Some(n) if n > 0 => n as usize,
+                _ => return Err("mysql.batch must be > 0".into()),
+            },
+        };
+        Ok(Self {
+            query,
+            cursor_column,
+            poll_interval: Duration::from_millis(poll_interval_ms),
+            cursor_start,
+            batch,
+        })
+    }
+
+    /// 轮询语句：输出每行的 JSON 与游标值（文本），按游标升序。
+    ///
+    /// # args
+    /// * `columns` - 用户查询的结果列：(列名, 是否二进制列)；二进制列以 base64 输出。
+    /// * `after_cursor` - 为 true 时只取游标大于绑定参数的行。
+    pub fn poll_sql(&self, columns: &[(String, bool)], after_cursor: bool) -> String {
+        let pairs = columns
+            .iter()
+            .map(|(name, binary)| {
+                let col = format!("{}.{}", POLL_ALIAS, quote_ident(name));
+                let value = if *binary {
+                    format!("TO_BASE64({col})")
+                } else {
+                    col
+                };
+                format!("'{}', {}", name.replace('\'', "''"), value)
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        let cursor = format!("{}.{}", POLL_ALIAS, quote_ident(&self.cursor_column));
+        let filter = if after_cursor {
+            format!(" WHERE {cursor} > ?")
+        } else {
+            String::new()
+        };
+        format!(
+            "SELECT CAST(JSON_OBJECT({pairs}) AS CHAR CHARACTER SET utf8mb4), CAST({cursor} AS CHAR) \
+             FROM ({}) AS {POLL_ALIAS}{filter} ORDER BY {cursor} LIMIT {}",
+            self.query, self.batch
+        )
+    }
+}
+
+fn quote_ident(name: &str) -> String {
+    format!("`{}`", name.replace('`', "``"))
+}
+
+fn is_binary_type(type_name: &str) -> bool {
+    let name = type_name.to_ascii_uppercase();
+    name.contains("BINARY") || name.contains("BLOB")
+}
+
+/// 游标的绑定值：整数按数值比较，其余（时间、字符串等）按文本交由服务端转换。
+pub fn cursor_value(cursor: &str) -> Value {
+    if let Ok(i) = cursor.parse::<i64>() {
+        return Value::from(i);
+    }
+    if let Ok(u) = cursor.parse::<u64>() {
+        return Value::from(u);
+    }
+    Value::from(cursor.to_string())
+}
+
+/// 游标 checkpoint 文件：`{"cursor":"<值>"}`。
+#[derive(Debug, Clone)]
+pub struct CursorStore {
+    path: PathBuf,
+}
+
+impl CursorStore {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// 与查询模式的 checkpoint 同目录。
+    pub fn for_source(key: &str) -> Self {
+        Self::new(format!("./.run/.checkpoints/{}.cursor.json", key))
+    }
+
+    /// 读取已保存的游标；文件不存在或为空时返回 `None`。
+    pub fn load(&self) -> anyhow::Result<Option<String>> {
+        if !self.path.exists() {
+            return Ok(None);
+        }
+        let contents = std::fs::read_to_string(&self.path)?;
+        if contents.trim().is_empty() {
+            return Ok(None);
+        }
+        let doc: JsonValue = serde_json::from_str(&contents)?;
+        match doc.get("cursor") {
+            Some(JsonValue::String(s)) => Ok(Some(s.clone())),
+            _ => anyhow::bail!("invalid cursor checkpoint '{}'", self.path.display()),
+        }
+    }
+
+    /// 保存游标：先写临时文件再重命名。
+    pub fn save(&self, cursor: &str) -> anyhow::Result<()> {
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let tmp = self.path.with_extension("tmp");
+        std::fs::write(&tmp, serde_json::json!({ "cursor": cursor }).to_string())?;
+        std::fs::rename(&tmp, &self.path)?;
+        Ok(())
+    }
+}
+
+pub struct MysqlPollSource {
+    key: String,
+    tags: Tags,
+    db: DatabaseConnection,
+    conf: MysqlPollConf,
+    /// 无游标 / 有游标时的轮询语句
+    first_sql: String,
+    next_sql: String,
+    /// 已读取到的最大游标
+    cursor: Option<String>,
+    /// 已交付给下游、待写入 checkpoint 的游标
+    delivered: Option<String>,
+    store: CursorStore,
+    event_seq: u64,
+}
+
+impl MysqlPollSource {
+    pub async fn new(
+        key: String,
+        tags: Tags,
+        config: &MysqlConf,
+        conf: MysqlPollConf,
+    ) -> anyhow::Result<Self> {
+        let store = CursorStore::for_source(&key);
+        let cursor = store.load()?.or_else(|| conf.cursor_start.clone());
+
+        let mut opt = ConnectOptions::new(config.get_database_url());
+        opt.max_connections(2)
+            .min_connections(1)
+            .connect_timeout(Duration::from_secs(8))
+            .acquire_timeout(Duration::from_secs(8))
+            .sqlx_logging(false);
+        let db = Database::connect(opt).await?;
+
+        // 结果列只在构建时读取一次：用户查询的列变化需要重建 source
+        let describe = db.get_mysql_connection_pool().describe(&conf.query).await?;
+        let columns = describe
+            .columns()
+            .iter()
+            .map(|c| (c.name().to_string(), is_binary_type(c.type_info().name())))
+            .collect::<Vec<_>>();
+        if !columns.iter().any(|(name, _)| *name == conf.cursor_column) {
+            anyhow::bail!(
+                "mysql.cursor_column '{}' is not a column of mysql.query",
+                conf.cursor_column
+            );
+        }
+        wp_log::info_data!("[mysql-poll] {} cursor: {:?}", key, cursor);
+        Ok(Self {
+            key,
+            tags,
+            db,
+            first_sql: conf.poll_sql(&columns, false),
+            next_sql: conf.poll_sql(&columns, true),
+            conf,
+            cursor,
+            delivered: None,
+            store,
+            event_seq: 0,
+        })
+    }
+
+    pub fn identifier(&self) -> &str {
+        &self.key
+    }
+
+    /// 写入上一批已交付的游标。
+    fn commit_delivered(&mut self) {
+        if let Some(cursor) = self.delivered.take()
+            && let Err(e) = self.store.save(&cursor)
+        {
+            wp_log::error_data!(
+                "[mysql-poll] {} save cursor {} fail: {}",
+                self.key,
+                cursor,
+                e
+            );
+        }
+    }
+
+    /// 读取游标之后的一批行：(行 JSON, 游标值)。
+    async fn poll_once(&self) -> SourceResult<Vec<(String, String)>> {
+        let state = match &self.cursor {
+            Some(cursor) => Statement::from_sql_and_values(
+                DatabaseBackend::MySql,
+                self.next_sql.clone(),
+                [cursor_value(cursor)],
+            ),
+            None => Statement::from_string(DatabaseBackend::MySql, self.first_sql.clone()),
+        };
+        let rows = self.db.query_all(state).await.map_err(|e| {
+            SourceError::from(SourceReason::SupplierError(format!("mysql poll fail: {e}")))
+        })?;
+        rows.iter()
+            .map(|row| {
+                let json = row.try_get_by_index::<String>(0);
+                let cursor = row.try_get_by_index::<Option<String>>(1);
+                match (json, cursor) {
+                    (Ok(json), Ok(Some(cursor))) => Ok((json, cursor)),
+                    (Ok(_), Ok(None)) => Err(SourceError::from(SourceReason::SupplierError(
+                        format!("mysql poll row has NULL {}", self.conf.cursor_column),
+                    ))),
+                    (Err(e), _) | (_, Err(e)) => Err(SourceError::from(
+                        SourceReason::SupplierError(format!("mysql poll read row fail: {e}")),
+                    )),
+                }
+            })
+            .collect()
+    }
+
+    fn create_event(&mut self, json: String) -> SourceEvent {
+        self.event_seq = self.event_seq.wrapping_add(1);
+        SourceEvent::new(
+            self.event_seq,
+            self.key.clone(),
+            RawData::from_string(json),
+            self.tags.clone().into(),
+        )
+    }
+}
+
+#[async_trait]
+impl DataSource for MysqlPollSource {
+    async fn receive(&mut self) -> SourceResult<SourceBatch> {
+        self.commit_delivered();
+        loop {
+            let rows = self.poll_once().await?;
+            let Some((_, last)) = rows.last() else {
+                tokio::time::sleep(self.conf.poll_interval).await;
+                continue;
+            };
+            self.cursor = Some(last.clone());
+            self.delivered = self.cursor.clone();
+            return Ok(rows
+                .into_iter()
+                .map(|(json, _)| self.create_event(json))
+                .collect());
+        }
+    }
+
+    fn try_receive(&mut self) -> Option<SourceBatch> {
+        None
+    }
+
+    fn identifier(&self) -> String {
+        self.key.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn conf() -> MysqlPollConf {
+        let mut params = ParamMap::new();
+        params.insert("query".into(), json!(" SELECT id, name FROM orders; "));
+        params.insert("cursor_column".into(), json!("id"));
+        params.insert("batch".into(), json!(2));
+        MysqlPollConf::from_params(&params).unwrap()
+    }
+
+    #[test]
+    fn poll_sql_advances_past_cursor() {
+        let conf = conf();
+        assert_eq!(conf.query, "SELECT id, name FROM orders");
+        assert_eq!(
+            conf.poll_interval,
+            Duration::from_millis(DEFAULT_POLL_INTERVAL_MS)
+        );
+        let columns = [("id".to_string(), false), ("it's".to_string(), true)];
+        assert_eq!(
+            conf.poll_sql(&columns, false),
+            "SELECT CAST(JSON_OBJECT('id', wp_poll.`id`, 'it''s', TO_BASE64(wp_poll.`it's`)) \
+             AS CHAR CHARACTER SET utf8mb4), CAST(wp_poll.`id` AS CHAR) \
+             FROM (SELECT id, name FROM orders) AS wp_poll ORDER BY wp_poll.`id` LIMIT 2"
+        );
+        assert!(
+            conf.poll_sql(&columns, true)
+                .contains("AS wp_poll WHERE wp_poll.`id` > ? ORDER BY")
+        );
+        assert_eq!(cursor_value("42"), Value::from(42i64));
+        assert_eq!(cursor_value("18446744073709551615"), Value::from(u64::MAX));
+        assert_eq!(
+            cursor_value("2024-01-01 00:00:00"),
+            Value::from("2024-01-01 00:00:00".to_string())
+        );
+    }
+
+    #[test]
+    fn cursor_store_resumes_after_restart() {
+        let dir = std::env::temp_dir().join(format!("wp_mysql_poll_{}", std::process::id()));
+        let store = CursorStore::new(dir.join("src.cursor.json"));
+        assert_eq!(
+            store.load().unwrap(),
+            None,
+            "first run has no stored cursor"
+        );
+        store.save("41").unwrap();
+        store.save("42").unwrap();
+        let restarted = CursorStore::new(dir.join("src.cursor.json"));
+        assert_eq!(restarted.load().unwrap().as_deref(), Some("42"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn poll_conf_validates_params() {
+        let base = || {
+            let mut params = ParamMap::new();
+            params.insert("query".into(), json!("select * from t"));
+            params.insert("cursor_column".into(), json!("id"));
+            params
+        };
+        let mut params = base();
+        params.insert("cursor_start".into(), json!(100));
+        params.insert("poll_interval_ms".into(), json!(250));
+        let conf = MysqlPollConf::from_params(&params).unwrap();
+        assert_eq!(conf.cursor_start.as_deref(), Some("100"));
+        assert_eq!(conf.poll_interval, Duration::from_millis(250));
+
+        for (key, value) in [
+            ("query", json!("DELETE FROM t")),
+            ("cursor_column", json!("")),
+            ("cursor_column", json!("a`b")),
+            ("poll_interval_ms", json!(0)),
+            ("cursor_start", json!(true)),
+            ("batch", json!(0)),
+        ] {
+            let mut params = base();
+            params.insert(key.into(), value.clone());
+            assert!(
+                MysqlPollConf::from_params(&params).is_err(),
+                "{key}={value}"
+            );
+        }
+    }
+}
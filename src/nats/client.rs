@@ -0,0 +1,22 @@
+use async_nats::{ConnectOptions, ServerAddr};
+
+use crate::nats::config::{NatsAuth, NatsConnConf};
+
+/// 按配置建立连接；`creds_file` 在连接前读取，文件不存在时直接报错。
+pub(crate) async fn connect(conf: &NatsConnConf, name: &str) -> anyhow::Result<async_nats::Client> {
+    let servers = conf
+        .servers
+        .iter()
+        .map(|s| {
+            s.parse::<ServerAddr>()
+                .map_err(|e| anyhow::anyhow!("invalid nats server '{s}': {e}"))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let options = ConnectOptions::new().name(name);
+    let options = match &conf.auth {
+        NatsAuth::None => options,
+        NatsAuth::Token(token) => options.token(token.clone()),
+        NatsAuth::CredsFile(path) => options.credentials_file(path).await?,
+    };
+    Ok(options.connect(servers.as_slice()).await?)
+}
@@ -0,0 +1,274 @@
+use serde_json::Value;
+use wp_connector_api::ParamMap;
+
+/// 连接认证方式。
+#[derive(Clone, PartialEq, Eq)]
+pub enum NatsAuth {
+    None,
+    Token(String),
+    /// `.creds` 文件路径（JWT + NKey）
+    CredsFile(String),
+}
+
+impl std::fmt::Debug for NatsAuth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::None => f.write_str("None"),
+            Self::Token(_) => f.write_str("Token(***)"),
+            Self::CredsFile(path) => f.debug_tuple("CredsFile").field(path).finish(),
+        }
+    }
+}
+
+/// sink 与 source 共用的连接配置。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NatsConnConf {
+    pub servers: Vec<String>,
+    pub auth: NatsAuth,
+}
+
+impl NatsConnConf {
+    /// `servers` 为逗号分隔的字符串或字符串数组；`token` 与 `creds_file` 二选一。
+    pub fn from_params(params: &ParamMap) -> Result<Self, String> {
+        let servers = match params.get("servers") {
+            Some(Value::String(s)) => s
+                .split(',')
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(str::to_string)
+                .collect(),
+            Some(Value::Array(items)) => items
+                .iter()
+                .map(|v| {
+                    v.as_str()
+                        .map(|s| s.trim().to_string())
+                        .ok_or_else(|| "nats.servers items must be strings".to_string())
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+            None | Some(Value::Null) => Vec::new(),
+            Some(_) => return Err("nats.servers must be a string or an array".into()),
+        };
+        if servers.is_empty() || servers.iter().any(String::is_empty) {
+            return Err("nats.servers must not be empty".into());
+        }
+        let auth = match (
+            optional_str(params, "token")?,
+            optional_str(params, "creds_file")?,
+        ) {
+            (None, None) => NatsAuth::None,
+            (Some(token), None) => NatsAuth::Token(token),
+            (None, Some(path)) => NatsAuth::CredsFile(path),
+            (Some(_), Some(_)) => {
+                return Err("nats.token cannot be combined with nats.creds_file".into());
+            }
+        };
+        Ok(Self { servers, auth })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NatsSinkConf {
+    /// 默认发布的 subject
+    pub subject: String,
+    /// 取记录中该字段的值作为 subject；缺失、为空或不是合法 subject 时回退到 `subject`
+    pub subject_field: Option<String>,
+    /// 通过 JetStream 发布并等待服务端确认
+    pub jetstream: bool,
+    pub fmt: String,
+}
+
+impl NatsSinkConf {
+    pub fn from_params(params: &ParamMap) -> Result<Self, String> {
+        let subject = optional_str(params, "subject")?.ok_or("nats.subject is required")?;
+        if !is_publish_subject(&subject) {
+            return Err(format!(
+                "nats.subject '{subject}' must not contain spaces or wildcards"
+            ));
+        }
+        Ok(Self {
+            subject,
+            subject_field: optional_str(params, "subject_field")?,
+            jetstream: optional_bool(params, "jetstream")?.unwrap_or(false),
+            fmt: optional_str(params, "fmt")?.unwrap_or_else(|| "json".into()),
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NatsSourceConf {
+    /// 订阅的 subject，可含 `*`/`>` 通配符；JetStream 下作为 consumer 的过滤 subject
+    pub subject: String,
+    /// Core NATS 队列组，组内成员分摊消息
+    pub queue_group: Option<String>,
+    /// JetStream stream 名；配置后以 `durable` 拉取消费
+    pub stream: Option<String>,
+    pub durable: Option<String>,
+    /// 单次 `receive` 最多返回的消息数
+    pub batch: usize,
+}
+
+impl NatsSourceConf {
+    pub fn from_params(params: &ParamMap) -> Result<Self, String> {
+        let subject = optional_str(params, "subject")?.ok_or("nats.subject is required")?;
+        if subject.contains(char::is_whitespace) {
+            return Err(format!("nats.subject '{subject}' must not contain spaces"));
+        }
+        let queue_group = optional_str(params, "queue_group")?;
+        let stream = optional_str(params, "stream")?;
+        let durable = optional_str(params, "durable")?;
+        match (&stream, &durable) {
+            (Some(_), None) => return Err("nats.stream requires nats.durable".into()),
+            (None, Some(_)) => return Err("nats.durable requires nats.stream".into()),
+            (Some(_), Some(_)) if queue_group.is_some() => {
+                return Err("nats.queue_group only applies to core NATS subscriptions".into());
+            }
+            _ => {}
+        }
+        let batch = match params.get("batch") {
+            None | Some(Value::Null) => 100,
+            Some(v) => match v.as_u64() {
+                Some(n) if n > 0 => n as usize,
+                _ => return Err("nats.batch must be a positive integer".into()),
+            },
+        };
+        Ok(Self {
+            subject,
+            queue_group,
+            stream,
+            durable,
+            batch,
+        })
+    }
+
+    pub fn is_jetstream(&self) -> bool {
+        self.durable.is_some()
+    }
+}
+
+/// 可用于发布的 subject：非空、无空白、各段非空且不含通配符。
+pub fn is_publish_subject(subject: &str) -> bool {
+    !subject.is_empty()
+        && !subject.contains(char::is_whitespace)
+        && subject
+            .split('.')
+            .all(|token| !token.is_empty() && token != "*" && token != ">")
+}
+
+fn optional_str(params: &ParamMap, key: &str) -> Result<Option<String>, String> {
+    match params.get(key) {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::String(s)) if !s.trim().is_empty() => Ok(Some(s.trim().to_string())),
+        Some(_) => Err(format!("nats.{key} must be a non-empty string")),
+    }
+}
+
+fn optional_bool(params: &ParamMap, key: &str) -> Result<Option<bool>, String> {
+    match params.get(key) {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::Bool(b)) => Ok(Some(*b)),
+        Some(_) => Err(format!("nats.{key} must be a boolean")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn params(pairs: &[(&str, Value)]) -> ParamMap {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn conn_conf_parses_servers_and_auth() {
+        let conf = NatsConnConf::from_params(&params(&[(
+            "servers",
+            json!("nats://a:4222, nats://b:4222"),
+        )]))
+        .unwrap();
+        assert_eq!(conf.servers, ["nats://a:4222", "nats://b:4222"]);
+        assert_eq!(conf.auth, NatsAuth::None);
+
+        let conf = NatsConnConf::from_params(&params(&[
+            ("servers", json!(["nats://a:4222"])),
+            ("creds_file", json!("/etc/nats/app.creds")),
+        ]))
+        .unwrap();
+        assert_eq!(conf.auth, NatsAuth::CredsFile("/etc/nats/app.creds".into()));
+
+        for bad in [
+            params(&[]),
+            params(&[("servers", json!(" , "))]),
+            params(&[("servers", json!(["a", 1]))]),
+            params(&[
+                ("servers", json!("a")),
+                ("token", json!("t")),
+                ("creds_file", json!("f")),
+            ]),
+        ] {
+            assert!(NatsConnConf::from_params(&bad).is_err(), "{bad:?}");
+        }
+        assert_eq!(
+            format!("{:?}", NatsAuth::Token("s3cr3t".into())),
+            "Token(***)"
+        );
+    }
+
+    #[test]
+    fn sink_conf_requires_publishable_subject() {
+        let conf = NatsSinkConf::from_params(&params(&[
+            ("subject", json!("logs.default")),
+            ("subject_field", json!("dataset")),
+        ]))
+        .unwrap();
+        assert_eq!(conf.subject_field.as_deref(), Some("dataset"));
+        assert!(!conf.jetstream);
+        assert_eq!(conf.fmt, "json");
+
+        for subject in ["", "logs.*", "logs.>", "logs..a", "a b"] {
+            assert!(
+                NatsSinkConf::from_params(&params(&[("subject", json!(subject))])).is_err(),
+                "{subject}"
+            );
+        }
+        assert!(
+            NatsSinkConf::from_params(&params(&[
+                ("subject", json!("a")),
+                ("jetstream", json!("yes"))
+            ]))
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn source_conf_checks_jetstream_options() {
+        let conf = NatsSourceConf::from_params(&params(&[("subject", json!("logs.>"))])).unwrap();
+        assert!(!conf.is_jetstream());
+        assert_eq!(conf.batch, 100);
+
+        let conf = NatsSourceConf::from_params(&params(&[
+            ("subject", json!("logs.>")),
+            ("stream", json!("LOGS")),
+            ("durable", json!("wp")),
+        ]))
+        .unwrap();
+        assert!(conf.is_jetstream());
+
+        for bad in [
+            params(&[("subject", json!("logs")), ("stream", json!("LOGS"))]),
+            params(&[("subject", json!("logs")), ("durable", json!("wp"))]),
+            params(&[
+                ("subject", json!("logs")),
+                ("stream", json!("LOGS")),
+                ("durable", json!("wp")),
+                ("queue_group", json!("q")),
+            ]),
+            params(&[("subject", json!("logs")), ("batch", json!(0))]),
+        ] {
+            assert!(NatsSourceConf::from_params(&bad).is_err(), "{bad:?}");
+        }
+    }
+}
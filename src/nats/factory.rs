@@ -0,0 +1,178 @@
+use async_trait::async_trait;
+use serde_json::json;
+use wp_connector_api::{
+    ConnectorDef, ConnectorScope, ParamMap, SinkBuildCtx, SinkDefProvider, SinkError, SinkFactory,
+    SinkHandle, SinkReason, SinkResult, SinkSpec, SourceDefProvider, SourceHandle, SourceMeta,
+    SourceReason, SourceResult, SourceSvcIns, Tags,
+};
+
+use super::client::connect;
+use super::config::{NatsConnConf, NatsSinkConf, NatsSourceConf};
+use super::sink::NatsSink;
+use super::source::NatsSource;
+use crate::WP_SRC_VAL;
+use crate::common::enrich::{EnrichConf, EnrichSink};
+use crate::common::retry::{self, RetryingSink};
+use crate::common::secret;
+use crate::common::stats;
+use crate::common::transform::FieldTransforms;
+
+pub struct NatsSourceFactory;
+
+#[async_trait]
+impl wp_connector_api::SourceFactory for NatsSourceFactory {
+    fn kind(&self) -> &'static str {
+        "nats"
+    }
+
+    fn validate_spec(&self, spec: &wp_connector_api::SourceSpec) -> SourceResult<()> {
+        let spec = &secret::resolve_source_spec(spec)?;
+        NatsConnConf::from_params(&spec.params).map_err(SourceReason::Other)?;
+        NatsSourceConf::from_params(&spec.params).map_err(SourceReason::Other)?;
+        Ok(())
+    }
+
+    async fn build(
+        &self,
+        spec: &wp_connector_api::SourceSpec,
+        _ctx: &wp_connector_api::SourceBuildCtx,
+    ) -> SourceResult<SourceSvcIns> {
+        let spec = &secret::resolve_source_spec(spec)?;
+        let conn = NatsConnConf::from_params(&spec.params).map_err(SourceReason::Other)?;
+        let conf = NatsSourceConf::from_params(&spec.params).map_err(SourceReason::Other)?;
+        let client = connect(&conn, &spec.name)
+            .await
+            .map_err(|err| SourceReason::Other(format!("nats connect fail: {err}")))?;
+        let mut meta_tags = Tags::from_parse(&spec.tags);
+        meta_tags.set(WP_SRC_VAL, spec.kind.clone());
+        let source = NatsSource::new(spec.name.clone(), meta_tags.clone(), client, &conf)
+            .await
+            .map_err(|err| SourceReason::Other(format!("nats subscribe fail: {err}")))?;
+
+        let mut meta = SourceMeta::new(spec.name.clone(), spec.kind.clone());
+        meta.tags = meta_tags;
+        let handle = SourceHandle::new(Box::new(source), meta);
+        Ok(SourceSvcIns::new().with_sources(vec![handle]))
+    }
+}
+
+impl SourceDefProvider for NatsSourceFactory {
+    fn source_def(&self) -> ConnectorDef {
+        ConnectorDef {
+            id: "nats_src".into(),
+            kind: self.kind().into(),
+            scope: ConnectorScope::Source,
+            allow_override: vec![
+                "servers",
+                "subject",
+                "queue_group",
+                "stream",
+                "durable",
+                "batch",
+                "creds_file",
+                "secret_ref",
+            ]
+            .into_iter()
+            .map(str::to_string)
+            .collect(),
+            default_params: nats_source_defaults(),
+            origin: Some("wp-connectors:nats_source".into()),
+        }
+    }
+}
+
+pub struct NatsSinkFactory;
+
+#[async_trait]
+impl SinkFactory for NatsSinkFactory {
+    fn kind(&self) -> &'static str {
+        "nats"
+    }
+
+    fn validate_spec(&self, spec: &SinkSpec) -> SinkResult<()> {
+        let spec = &secret::resolve_sink_spec(spec)?;
+        NatsConnConf::from_params(&spec.params).map_err(SinkReason::sink)?;
+        NatsSinkConf::from_params(&spec.params).map_err(SinkReason::sink)?;
+        retry::validate_params(&spec.params)?;
+        EnrichConf::from_params(&spec.params)?;
+        FieldTransforms::from_params(&spec.params)?;
+        Ok(())
+    }
+
+    async fn build(&self, spec: &SinkSpec, _ctx: &SinkBuildCtx) -> SinkResult<SinkHandle> {
+        let spec = &secret::resolve_sink_spec(spec)?;
+        let conn = NatsConnConf::from_params(&spec.params).map_err(SinkReason::sink)?;
+        let conf = NatsSinkConf::from_params(&spec.params).map_err(SinkReason::sink)?;
+        let client = connect(&conn, &spec.name).await.map_err(|err| {
+            SinkError::from(SinkReason::sink(format!("nats connect failed: {err}")))
+        })?;
+        let sink =
+            NatsSink::new(client, &conf).with_stats(stats::register(&spec.name, self.kind()));
+        let enrich = EnrichConf::from_params(&spec.params)?;
+        let transforms = FieldTransforms::from_params(&spec.params)?;
+        let sink = RetryingSink::from_spec(sink, spec).await?;
+        let sink = EnrichSink::new(sink, enrich).with_transforms(transforms);
+        Ok(SinkHandle::new(Box::new(sink)))
+    }
+}
+
+impl SinkDefProvider for NatsSinkFactory {
+    fn sink_def(&self) -> ConnectorDef {
+        ConnectorDef {
+            id: "nats_sink".into(),
+            kind: self.kind().into(),
+            scope: ConnectorScope::Sink,
+            allow_override: vec![
+                "servers",
+                "subject",
+                "subject_field",
+                "jetstream",
+                "fmt",
+                "creds_file",
+                "secret_ref",
+                "enrich",
+                "enrich_overwrite",
+                "field_transforms",
+                "retry",
+                "write_deadline_ms",
+                "quarantine",
+            ]
+            .into_iter()
+            .map(str::to_string)
+            .collect(),
+            default_params: nats_sink_defaults(),
+            origin: Some("wp-connectors:nats_sink".into()),
+        }
+    }
+}
+
+fn nats_source_defaults() -> ParamMap {
+    let mut params = ParamMap::new();
+    params.insert("servers".into(), json!("nats://127.0.0.1:4222"));
+    params.insert("subject".into(), json!("wp.events"));
+    params.insert("batch".into(), json!(100));
+    params
+}
+
+fn nats_sink_defaults() -> ParamMap {
+    let mut params = ParamMap::new();
+    params.insert("servers".into(), json!("nats://127.0.0.1:4222"));
+    params.insert("subject".into(), json!("wp.events"));
+    params.insert("jetstream".into(), json!(false));
+    params.insert("fmt".into(), json!("json"));
+    params
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_are_valid() {
+        NatsConnConf::from_params(&nats_source_defaults()).unwrap();
+        NatsSourceConf::from_params(&nats_source_defaults()).unwrap();
+        NatsConnConf::from_params(&nats_sink_defaults()).unwrap();
+        let conf = NatsSinkConf::from_params(&nats_sink_defaults()).unwrap();
+        assert_eq!(conf.subject, "wp.events");
+    }
+}
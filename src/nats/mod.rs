@@ -0,0 +1,18 @@
+//! NATS：Core NATS / JetStream 的 sink 与 source。
+//!
+//! 模块划分：
+//! - config：连接、sink、source 配置解析
+//! - client：建立连接（token / creds 文件认证）
+//! - sink：NatsSink（按字段路由 subject，可选 JetStream 确认）
+//! - source：NatsSource（订阅或 durable 拉取消费）
+//! - factory：Sink/Source 工厂
+
+mod client;
+pub mod config;
+mod factory;
+mod sink;
+mod source;
+
+pub use config::{NatsAuth, NatsConnConf, NatsSinkConf, NatsSourceConf};
+pub use factory::{NatsSinkFactory, NatsSourceFactory};
+pub use source::NatsSource;
@@ -0,0 +1,213 @@
+use std::sync::Arc;
+
+use async_nats::jetstream;
+use async_trait::async_trait;
+use bytes::Bytes;
+use wp_connector_api::{AsyncCtrl, AsyncRawDataSink, AsyncRecordSink, SinkError, SinkResult};
+use wp_data_fmt::{DataFormat, FormatType};
+use wp_model_core::model::{DataRecord, fmt_def::TextFmt};
+
+use crate::common::retry::{self, PendingFlush};
+use crate::common::stats::StatsHandle;
+use crate::nats::config::{NatsSinkConf, is_publish_subject};
+
+enum Publisher {
+    Core(async_nats::Client),
+    /// 每条消息等待 JetStream 的确认
+    JetStream(jetstream::Context),
+}
+
+/// 把记录按 `fmt` 渲染后发布到 subject；配置 `subject_field` 时按记录字段路由。
+pub(crate) struct NatsSink {
+    client: async_nats::Client,
+    publisher: Publisher,
+    subject: String,
+    subject_field: Option<String>,
+    fmt: TextFmt,
+    /// 已发布的记录数，供重试跳过已写出的前缀
+    accepted: u64,
+    /// `/stats` 自省状态（最近发布结果）
+    stats: StatsHandle,
+}
+
+impl NatsSink {
+    pub(crate) fn new(client: async_nats::Client, conf: &NatsSinkConf) -> Self {
+        let publisher = if conf.jetstream {
+            Publisher::JetStream(jetstream::new(client.clone()))
+        } else {
+            Publisher::Core(client.clone())
+        };
+        Self {
+            client,
+            publisher,
+            subject: conf.subject.clone(),
+            subject_field: conf.subject_field.clone(),
+            fmt: TextFmt::from(conf.fmt.as_str()),
+            accepted: 0,
+            stats: StatsHandle::detached(&conf.subject, "nats"),
+        }
+    }
+
+    /// 使用外部登记的状态句柄，以静态 `subject` 作为指标的写入目标。
+    pub(crate) fn with_stats(mut self, stats: StatsHandle) -> Self {
+        self.stats = stats.with_destination(self.subject.as_str());
+        self
+    }
+
+    /// 按 `subject_field` 解析目标 subject；字段缺失、为空或不可发布时回退到静态 `subject`。
+    fn route_subject(&self, data: &DataRecord) -> String {
+        route_subject(&self.subject, self.subject_field.as_deref(), data)
+    }
+
+    async fn publish(&self, subject: String, payload: Bytes) -> SinkResult<()> {
+        let result = self.send(subject, payload).await;
+        self.stats.record_flush(&result);
+        result
+    }
+
+    async fn send(&self, subject: String, payload: Bytes) -> SinkResult<()> {
+        match &self.publisher {
+            Publisher::Core(client) => client
+                .publish(subject.clone(), payload)
+                .await
+                .map_err(|e| publish_error(&subject, e)),
+            Publisher::JetStream(js) => js
+                .publish(subject.clone(), payload)
+                .await
+                .map_err(|e| publish_error(&subject, e))?
+                .await
+                .map(|_| ())
+                .map_err(|e| publish_error(&subject, e)),
+        }
+    }
+
+    async fn flush(&self) -> SinkResult<()> {
+        self.client
+            .flush()
+            .await
+            .map_err(|e| retry::network_error(format!("nats flush failed: {e}")))
+    }
+}
+
+pub(crate) fn route_subject(fallback: &str, field: Option<&str>, data: &DataRecord) -> String {
+    field
+        .and_then(|field| data.get2(field))
+        .map(|f| f.get_value().to_string())
+        .map(|s| s.trim().to_string())
+        .filter(|s| is_publish_subject(s))
+        .unwrap_or_else(|| fallback.to_string())
+}
+
+fn publish_error(subject: &str, err: impl std::fmt::Display) -> SinkError {
+    retry::network_error(format!("nats publish to '{subject}' failed: {err}"))
+}
+
+#[async_trait]
+impl AsyncCtrl for NatsSink {
+    async fn stop(&mut self) -> SinkResult<()> {
+        self.flush().await
+    }
+
+    async fn reconnect(&mut self) -> SinkResult<()> {
+        // 客户端自动重连，这里只确认缓冲的消息已写出
+        self.flush().await
+    }
+}
+
+#[async_trait]
+impl AsyncRecordSink for NatsSink {
+    async fn sink_record(&mut self, data: &DataRecord) -> SinkResult<()> {
+        let line = FormatType::from(&self.fmt).format_record(data);
+        self.publish(self.route_subject(data), Bytes::from(line))
+            .await?;
+        self.accepted += 1;
+        Ok(())
+    }
+
+    async fn sink_records(&mut self, data: Vec<Arc<DataRecord>>) -> SinkResult<()> {
+        let fmt = FormatType::from(&self.fmt);
+        for record in data {
+            let line = fmt.format_record(record.as_ref());
+            self.publish(self.route_subject(&record), Bytes::from(line))
+                .await?;
+            self.accepted += 1;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl PendingFlush for NatsSink {
+    fn pending_len(&self) -> usize {
+        0
+    }
+
+    async fn flush_now(&mut self) -> SinkResult<()> {
+        Ok(())
+    }
+
+    fn discard_pending(&mut self) -> usize {
+        0
+    }
+
+    fn accepted(&self) -> u64 {
+        self.accepted
+    }
+}
+
+#[async_trait]
+impl AsyncRawDataSink for NatsSink {
+    async fn sink_str(&mut self, data: &str) -> SinkResult<()> {
+        self.publish(
+            self.subject.clone(),
+            Bytes::copy_from_slice(data.as_bytes()),
+        )
+        .await
+    }
+
+    async fn sink_bytes(&mut self, data: &[u8]) -> SinkResult<()> {
+        self.publish(self.subject.clone(), Bytes::copy_from_slice(data))
+            .await
+    }
+
+    async fn sink_str_batch(&mut self, data: Vec<&str>) -> SinkResult<()> {
+        for item in data {
+            self.sink_str(item).await?;
+        }
+        Ok(())
+    }
+
+    async fn sink_bytes_batch(&mut self, data: Vec<&[u8]>) -> SinkResult<()> {
+        for item in data {
+            self.sink_bytes(item).await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wp_model_core::model::DataField;
+
+    #[test]
+    fn subject_routes_from_field_with_fallback() {
+        let mut record = DataRecord::default();
+        record.append(DataField::from_chars("dataset", "logs.nginx"));
+        record.append(DataField::from_chars("bad", "logs.*"));
+        record.append(DataField::from_chars("blank", " "));
+
+        assert_eq!(
+            route_subject("logs.default", Some("dataset"), &record),
+            "logs.nginx"
+        );
+        assert_eq!(route_subject("logs.default", None, &record), "logs.default");
+        for field in ["missing", "bad", "blank"] {
+            assert_eq!(
+                route_subject("logs.default", Some(field), &record),
+                "logs.default",
+                "{field}"
+            );
+        }
+    }
+}
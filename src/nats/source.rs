@@ -0,0 +1,170 @@
+//! NATS source：Core NATS 订阅（可选队列组）或 JetStream durable 拉取消费，
+//! 每条消息作为一个 `RawData` 事件输出，`WP_SRC_VAL` 标签为消息的 subject。
+//!
+//! JetStream 消息在下一次 `receive` 时确认（ack），即确认已交付给下游的上一批；
+//! 进程在确认前退出时，该批消息会在 ack wait 超时后重新投递（至少一次）。
+//! 批内续取出错时先交付已取到的消息，错误在下一次 `receive` 返回。
+
+use async_nats::jetstream::{self, consumer::pull};
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures_util::StreamExt;
+use wp_connector_api::{
+    DataSource, SourceBatch, SourceError, SourceEvent, SourceReason, SourceResult, Tags,
+};
+use wp_parse_api::RawData;
+
+use crate::WP_SRC_VAL;
+use crate::common::pull_batch::{MessagePull, PullBatcher};
+use crate::nats::config::NatsSourceConf;
+
+enum Subscription {
+    Core(async_nats::Subscriber),
+    JetStream(pull::Stream),
+}
+
+struct Inbox {
+    subscription: Subscription,
+    /// 已交付、待确认的 JetStream 消息
+    unacked: Vec<jetstream::Message>,
+}
+
+pub struct NatsSource {
+    key: String,
+    tags: Tags,
+    inbox: Inbox,
+    batcher: PullBatcher,
+    event_seq: u64,
+}
+
+impl NatsSource {
+    pub async fn new(
+        key: String,
+        tags: Tags,
+        client: async_nats::Client,
+        conf: &NatsSourceConf,
+    ) -> anyhow::Result<Self> {
+        let subscription = match (&conf.stream, &conf.durable) {
+            (Some(stream), Some(durable)) => {
+                let js = jetstream::new(client);
+                let stream = js.get_stream(stream).await?;
+                let consumer: pull::Consumer = stream
+                    .get_or_create_consumer(
+                        durable,
+                        pull::Config {
+                            durable_name: Some(durable.clone()),
+                            filter_subject: conf.subject.clone(),
+                            ..Default::default()
+                        },
+                    )
+                    .await?;
+                Subscription::JetStream(consumer.messages().await?)
+            }
+            _ => match &conf.queue_group {
+                Some(group) => Subscription::Core(
+                    client
+                        .queue_subscribe(conf.subject.clone(), group.clone())
+                        .await?,
+                ),
+                None => Subscription::Core(client.subscribe(conf.subject.clone()).await?),
+            },
+        };
+        wp_log::info_data!(
+            "[nats] {} subject: {}, stream: {:?}, durable: {:?}",
+            key,
+            conf.subject,
+            conf.stream,
+            conf.durable
+        );
+        Ok(Self {
+            key,
+            tags,
+            inbox: Inbox {
+                subscription,
+                unacked: Vec::new(),
+            },
+            batcher: PullBatcher::new(conf.batch),
+            event_seq: 0,
+        })
+    }
+
+    pub fn identifier(&self) -> &str {
+        &self.key
+    }
+
+    fn create_event(&mut self, subject: String, payload: Bytes) -> SourceEvent {
+        let mut tags = self.tags.clone();
+        tags.set(WP_SRC_VAL, subject);
+        self.event_seq = self.event_seq.wrapping_add(1);
+        SourceEvent::new(
+            self.event_seq,
+            self.key.clone(),
+            RawData::Bytes(payload),
+            tags.into(),
+        )
+    }
+}
+
+impl Inbox {
+    /// 确认上一批已交付的 JetStream 消息。
+    async fn ack_delivered(&mut self) -> SourceResult<()> {
+        for msg in self.unacked.drain(..) {
+            msg.ack()
+                .await
+                .map_err(|e| stream_error("ack jetstream message", e))?;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl MessagePull for Inbox {
+    async fn pull(&mut self) -> SourceResult<(String, Bytes)> {
+        match &mut self.subscription {
+            Subscription::Core(subscriber) => {
+                let msg = subscriber
+                    .next()
+                    .await
+                    .ok_or_else(|| stream_error("subscribe", "subscription closed"))?;
+                Ok((msg.subject.to_string(), msg.payload))
+            }
+            Subscription::JetStream(messages) => {
+                let msg = messages
+                    .next()
+                    .await
+                    .ok_or_else(|| stream_error("pull", "consumer stream closed"))?
+                    .map_err(|e| stream_error("pull", e))?;
+                let out = (msg.subject.to_string(), msg.payload.clone());
+                self.unacked.push(msg);
+                Ok(out)
+            }
+        }
+    }
+}
+
+fn stream_error(what: &str, err: impl std::fmt::Display) -> SourceError {
+    SourceError::from(SourceReason::SupplierError(format!(
+        "nats {what} fail: {err}"
+    )))
+}
+
+#[async_trait]
+impl DataSource for NatsSource {
+    /// 阻塞等待第一条消息，再取走已到达的消息，最多 `batch` 条。
+    async fn receive(&mut self) -> SourceResult<SourceBatch> {
+        self.inbox.ack_delivered().await?;
+        let messages = self.batcher.next_batch(&mut self.inbox).await?;
+        Ok(messages
+            .into_iter()
+            .map(|(subject, payload)| self.create_event(subject, payload))
+            .collect())
+    }
+
+    fn try_receive(&mut self) -> Option<SourceBatch> {
+        None
+    }
+
+    fn identifier(&self) -> String {
+        self.key.clone()
+    }
+}
@@ -0,0 +1,77 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde_json::json;
+use wp_connector_api::{
+    ConnectorDef, ConnectorScope, ParamMap, SinkBuildCtx, SinkDefProvider, SinkFactory, SinkHandle,
+    SinkReason, SinkResult, SinkSpec,
+};
+
+use super::sink::NullSink;
+
+pub struct NullSinkFactory;
+
+/// `latency_ms`：每次写入的模拟延迟，非负整数，缺省为 0。
+fn parse_latency(params: &ParamMap) -> SinkResult<Option<Duration>> {
+    match params.get("latency_ms") {
+        None | Some(serde_json::Value::Null) => Ok(None),
+        Some(v) => match v.as_u64() {
+            Some(ms) => Ok(Some(Duration::from_millis(ms))),
+            None => Err(SinkReason::sink("null.latency_ms must be a non-negative integer").into()),
+        },
+    }
+}
+
+#[async_trait]
+impl SinkFactory for NullSinkFactory {
+    fn kind(&self) -> &'static str {
+        "null"
+    }
+
+    fn validate_spec(&self, spec: &SinkSpec) -> SinkResult<()> {
+        parse_latency(&spec.params)?;
+        Ok(())
+    }
+
+    async fn build(&self, spec: &SinkSpec, _ctx: &SinkBuildCtx) -> SinkResult<SinkHandle> {
+        let sink = NullSink::new().with_latency(parse_latency(&spec.params)?);
+        Ok(SinkHandle::new(Box::new(sink)))
+    }
+}
+
+impl SinkDefProvider for NullSinkFactory {
+    fn sink_def(&self) -> ConnectorDef {
+        ConnectorDef {
+            id: "null_sink".into(),
+            kind: self.kind().into(),
+            scope: ConnectorScope::Sink,
+            allow_override: vec!["latency_ms"].into_iter().map(str::to_string).collect(),
+            default_params: null_defaults(),
+            origin: Some("wp-connectors:null_sink".into()),
+        }
+    }
+}
+
+fn null_defaults() -> ParamMap {
+    let mut params = ParamMap::new();
+    params.insert("latency_ms".into(), json!(0));
+    params
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn latency_ms_must_be_non_negative_integer() {
+        let mut params = ParamMap::new();
+        assert_eq!(parse_latency(&params).unwrap(), None);
+        params.insert("latency_ms".into(), json!(15));
+        assert_eq!(
+            parse_latency(&params).unwrap(),
+            Some(Duration::from_millis(15))
+        );
+        params.insert("latency_ms".into(), json!(-1));
+        assert!(parse_latency(&params).is_err());
+    }
+}
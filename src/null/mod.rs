@@ -0,0 +1,7 @@
+//! Null sink：接收并丢弃全部数据，仅计数；用于压测时隔离 source 吞吐。
+
+mod factory;
+mod sink;
+
+pub use factory::NullSinkFactory;
+pub use sink::{NullCounter, NullSink};
@@ -0,0 +1,147 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use wp_connector_api::{AsyncCtrl, AsyncRawDataSink, AsyncRecordSink, SinkResult};
+use wp_model_core::model::DataRecord;
+
+/// 丢弃计数：记录数、原始条目数与写入（flush）次数，可在 sink 装箱后继续读取。
+#[derive(Debug, Clone, Default)]
+pub struct NullCounter {
+    records: Arc<AtomicU64>,
+    raw: Arc<AtomicU64>,
+    flushes: Arc<AtomicU64>,
+}
+
+impl NullCounter {
+    pub fn records(&self) -> u64 {
+        self.records.load(Ordering::Relaxed)
+    }
+
+    pub fn raw(&self) -> u64 {
+        self.raw.load(Ordering::Relaxed)
+    }
+
+    pub fn flushes(&self) -> u64 {
+        self.flushes.load(Ordering::Relaxed)
+    }
+}
+
+/// 接收并丢弃所有记录/原始数据；可选每次写入附加固定延迟以模拟慢后端。
+#[derive(Debug, Default)]
+pub struct NullSink {
+    latency: Option<Duration>,
+    counter: NullCounter,
+}
+
+impl NullSink {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 每次写入（单条或批量）附加的模拟延迟
+    pub fn with_latency(mut self, latency: Option<Duration>) -> Self {
+        self.latency = latency.filter(|d| !d.is_zero());
+        self
+    }
+
+    pub fn counter(&self) -> NullCounter {
+        self.counter.clone()
+    }
+
+    async fn flush(&self, records: u64, raw: u64) {
+        if let Some(latency) = self.latency {
+            tokio::time::sleep(latency).await;
+        }
+        self.counter.records.fetch_add(records, Ordering::Relaxed);
+        self.counter.raw.fetch_add(raw, Ordering::Relaxed);
+        self.counter.flushes.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+#[async_trait]
+impl AsyncCtrl for NullSink {
+    async fn stop(&mut self) -> SinkResult<()> {
+        wp_log::info_data!(
+            "[null] discarded records={}, raw={}, flushes={}",
+            self.counter.records(),
+            self.counter.raw(),
+            self.counter.flushes()
+        );
+        Ok(())
+    }
+
+    async fn reconnect(&mut self) -> SinkResult<()> {
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl AsyncRecordSink for NullSink {
+    async fn sink_record(&mut self, _data: &DataRecord) -> SinkResult<()> {
+        self.flush(1, 0).await;
+        Ok(())
+    }
+
+    async fn sink_records(&mut self, data: Vec<Arc<DataRecord>>) -> SinkResult<()> {
+        self.flush(data.len() as u64, 0).await;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl AsyncRawDataSink for NullSink {
+    async fn sink_str(&mut self, _data: &str) -> SinkResult<()> {
+        self.flush(0, 1).await;
+        Ok(())
+    }
+
+    async fn sink_bytes(&mut self, _data: &[u8]) -> SinkResult<()> {
+        self.flush(0, 1).await;
+        Ok(())
+    }
+
+    async fn sink_str_batch(&mut self, data: Vec<&str>) -> SinkResult<()> {
+        self.flush(0, data.len() as u64).await;
+        Ok(())
+    }
+
+    async fn sink_bytes_batch(&mut self, data: Vec<&[u8]>) -> SinkResult<()> {
+        self.flush(0, data.len() as u64).await;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    #[tokio::test]
+    async fn counts_records_and_raw_input() {
+        let mut sink = NullSink::new();
+        let counter = sink.counter();
+        let records = (0..5).map(|_| Arc::new(DataRecord::default())).collect();
+        sink.sink_records(records).await.unwrap();
+        sink.sink_record(&DataRecord::default()).await.unwrap();
+        sink.sink_str_batch(vec!["a", "b"]).await.unwrap();
+        sink.sink_bytes(b"c").await.unwrap();
+        sink.stop().await.unwrap();
+
+        assert_eq!(counter.records(), 6);
+        assert_eq!(counter.raw(), 3);
+        assert_eq!(counter.flushes(), 4);
+    }
+
+    #[tokio::test]
+    async fn applies_latency_per_flush() {
+        let mut sink = NullSink::new().with_latency(Some(Duration::from_millis(20)));
+        let started = Instant::now();
+        for _ in 0..3 {
+            sink.sink_record(&DataRecord::default()).await.unwrap();
+        }
+        assert!(started.elapsed() >= Duration::from_millis(60));
+        assert_eq!(sink.counter().records(), 3);
+    }
+}
@@ -0,0 +1,75 @@
+use educe::Educe;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+use crate::common::sigv4::AwsCredentials;
+
+/// 认证方式。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OpenSearchAuthMode {
+    #[default]
+    None,
+    /// `username`/`password` 的 HTTP Basic 认证
+    Basic,
+    /// AWS Signature V4（Amazon OpenSearch Service 为 `es`，Serverless 为 `aoss`）
+    Sigv4,
+}
+
+impl FromStr for OpenSearchAuthMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "none" => Ok(Self::None),
+            "basic" => Ok(Self::Basic),
+            "sigv4" => Ok(Self::Sigv4),
+            other => Err(format!(
+                "invalid opensearch.auth_mode '{other}'; allowed: none,basic,sigv4"
+            )),
+        }
+    }
+}
+
+#[derive(Educe, Deserialize, Serialize, PartialEq, Clone)]
+#[educe(Debug, Default)]
+pub struct OpenSearchSinkConfig {
+    #[educe(Default = "http://127.0.0.1:9200")]
+    pub endpoint: String,
+    /// 目标索引；`data_stream` 为 true 时为数据流名
+    #[educe(Default = "wp-events")]
+    pub index: String,
+    /// 写入数据流：bulk action 使用 `create`，缺少 `@timestamp` 的记录以写入时间补齐
+    #[serde(default)]
+    pub data_stream: bool,
+    /// 以记录字段值作为文档 `_id`
+    #[serde(default)]
+    pub id_field: Option<String>,
+    /// 攒满该条数即发送一次 bulk 请求
+    #[educe(Default = 100)]
+    pub batch: usize,
+    #[educe(Default = 10000)]
+    pub timeout_ms: u64,
+    #[serde(default)]
+    pub auth_mode: OpenSearchAuthMode,
+    #[serde(default)]
+    pub username: Option<String>,
+    #[educe(Debug(ignore))]
+    #[serde(default)]
+    pub password: Option<String>,
+    /// SigV4 签名的区域与服务名
+    #[educe(Default = "us-east-1")]
+    pub region: String,
+    #[educe(Default = "es")]
+    pub service: String,
+    #[educe(Debug(ignore))]
+    #[serde(default)]
+    pub credentials: Option<AwsCredentials>,
+}
+
+impl OpenSearchSinkConfig {
+    /// bulk action 名：数据流只接受 `create`。
+    pub fn bulk_action(&self) -> &'static str {
+        if self.data_stream { "create" } else { "index" }
+    }
+}
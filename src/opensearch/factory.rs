@@ -0,0 +1,246 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde_json::{Value, json};
+use wp_connector_api::{
+    ConnectorDef, ConnectorScope, ParamMap, SinkBuildCtx, SinkDefProvider, SinkError, SinkFactory,
+    SinkHandle, SinkReason, SinkResult, SinkSpec,
+};
+
+use super::config::{OpenSearchAuthMode, OpenSearchSinkConfig};
+use super::sink::OpenSearchSink;
+use crate::common::enrich::{EnrichConf, EnrichSink};
+use crate::common::retry::{self, RetryingSink};
+use crate::common::secret;
+use crate::common::sigv4::AwsCredentials;
+use crate::common::stats;
+use crate::common::trace_context::TraceContext;
+use crate::common::transform::FieldTransforms;
+
+pub struct OpenSearchSinkFactory;
+
+#[async_trait]
+impl SinkFactory for OpenSearchSinkFactory {
+    fn kind(&self) -> &'static str {
+        "opensearch"
+    }
+
+    fn validate_spec(&self, spec: &SinkSpec) -> SinkResult<()> {
+        let spec = &secret::resolve_sink_spec(spec)?;
+        build_conf(&spec.params)?;
+        EnrichConf::from_params(&spec.params)?;
+        retry::validate_params(&spec.params)?;
+        FieldTransforms::from_params(&spec.params)?;
+        TraceContext::from_params(&spec.params)?;
+        Ok(())
+    }
+
+    async fn build(&self, spec: &SinkSpec, _ctx: &SinkBuildCtx) -> SinkResult<SinkHandle> {
+        let spec = &secret::resolve_sink_spec(spec)?;
+        let conf = build_conf(&spec.params)?;
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_millis(conf.timeout_ms))
+            .build()
+            .map_err(|err| {
+                SinkError::from(SinkReason::sink(format!(
+                    "build opensearch client failed: {err}"
+                )))
+            })?;
+        let sink = OpenSearchSink::new(client, &conf)
+            .with_trace_context(TraceContext::from_params(&spec.params)?)
+            .with_stats(stats::register(&spec.name, self.kind()));
+        let enrich = EnrichConf::from_params(&spec.params)?;
+        let transforms = FieldTransforms::from_params(&spec.params)?;
+        let sink = RetryingSink::from_spec(sink, spec).await?;
+        let sink = EnrichSink::new(sink, enrich).with_transforms(transforms);
+        Ok(SinkHandle::new(Box::new(sink)))
+    }
+}
+
+impl SinkDefProvider for OpenSearchSinkFactory {
+    fn sink_def(&self) -> ConnectorDef {
+        ConnectorDef {
+            id: "opensearch_sink".into(),
+            kind: self.kind().into(),
+            scope: ConnectorScope::Sink,
+            allow_override: vec![
+                "endpoint",
+                "index",
+                "data_stream",
+                "id_field",
+                "batch",
+                "timeout_ms",
+                "auth_mode",
+                "username",
+                "password",
+                "region",
+                "service",
+                "access_key_id",
+                "secret_access_key",
+                "session_token",
+                "secret_ref",
+                "trace_context",
+                "trace_id_field",
+                "tracestate",
+                "enrich",
+                "enrich_overwrite",
+                "field_transforms",
+                "retry",
+                "write_deadline_ms",
+                "quarantine",
+            ]
+            .into_iter()
+            .map(str::to_string)
+            .collect(),
+            default_params: opensearch_defaults(),
+            origin: Some("wp-connectors:opensearch_sink".into()),
+        }
+    }
+}
+
+fn opensearch_defaults() -> ParamMap {
+    let defaults = OpenSearchSinkConfig::default();
+    let mut params = ParamMap::new();
+    params.insert("endpoint".into(), json!(defaults.endpoint));
+    params.insert("index".into(), json!(defaults.index));
+    params.insert("data_stream".into(), json!(defaults.data_stream));
+    params.insert("batch".into(), json!(defaults.batch));
+    params.insert("timeout_ms".into(), json!(defaults.timeout_ms));
+    params.insert("auth_mode".into(), json!(defaults.auth_mode));
+    params.insert("region".into(), json!(defaults.region));
+    params.insert("service".into(), json!(defaults.service));
+    params
+}
+
+/// 由参数构建配置：basic 认证需 `username`，sigv4 认证需 `access_key_id`/`secret_access_key`。
+fn build_conf(params: &ParamMap) -> SinkResult<OpenSearchSinkConfig> {
+    let mut conf = OpenSearchSinkConfig::default();
+    if let Some(endpoint) = opt_str(params, "endpoint")? {
+        if !endpoint.starts_with("http://") && !endpoint.starts_with("https://") {
+            return Err(SinkReason::sink(
+                "opensearch.endpoint must start with http:// or https://",
+            )
+            .into());
+        }
+        conf.endpoint = endpoint;
+    }
+    if let Some(index) = opt_str(params, "index")? {
+        conf.index = index;
+    }
+    match params.get("data_stream") {
+        None | Some(Value::Null) => {}
+        Some(Value::Bool(b)) => conf.data_stream = *b,
+        Some(_) => return Err(SinkReason::sink("opensearch.data_stream must be a boolean").into()),
+    }
+    conf.id_field = opt_str(params, "id_field")?;
+    if let Some(n) = opt_positive(params, "batch")? {
+        conf.batch = n as usize;
+    }
+    if let Some(n) = opt_positive(params, "timeout_ms")? {
+        conf.timeout_ms = n;
+    }
+    if let Some(mode) = opt_str(params, "auth_mode")? {
+        conf.auth_mode = mode.parse().map_err(SinkReason::sink)?;
+    }
+    conf.username = opt_str(params, "username")?;
+    conf.password = opt_str(params, "password")?;
+    if let Some(region) = opt_str(params, "region")? {
+        conf.region = region;
+    }
+    if let Some(service) = opt_str(params, "service")? {
+        conf.service = service;
+    }
+    conf.credentials = match (
+        opt_str(params, "access_key_id")?,
+        opt_str(params, "secret_access_key")?,
+    ) {
+        (Some(access_key_id), Some(secret_access_key)) => Some(AwsCredentials {
+            access_key_id,
+            secret_access_key,
+            session_token: opt_str(params, "session_token")?,
+        }),
+        (None, None) => None,
+        _ => {
+            return Err(SinkReason::sink(
+                "opensearch.access_key_id and opensearch.secret_access_key must be set together",
+            )
+            .into());
+        }
+    };
+    match conf.auth_mode {
+        OpenSearchAuthMode::Basic if conf.username.is_none() => {
+            return Err(SinkReason::sink("opensearch.auth_mode=basic requires username").into());
+        }
+        OpenSearchAuthMode::Sigv4 if conf.credentials.is_none() => {
+            return Err(SinkReason::sink(
+                "opensearch.auth_mode=sigv4 requires access_key_id and secret_access_key",
+            )
+            .into());
+        }
+        _ => {}
+    }
+    Ok(conf)
+}
+
+/// 可选的非空字符串参数。
+fn opt_str(params: &ParamMap, key: &str) -> SinkResult<Option<String>> {
+    match params.get(key) {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::String(s)) if s.trim().is_empty() => Ok(None),
+        Some(Value::String(s)) => Ok(Some(s.trim().to_string())),
+        Some(_) => Err(SinkReason::sink(format!("opensearch.{key} must be a string")).into()),
+    }
+}
+
+/// 可选的正整数参数。
+fn opt_positive(params: &ParamMap, key: &str) -> SinkResult<Option<u64>> {
+    match params.get(key) {
+        None | Some(Value::Null) => Ok(None),
+        Some(v) => match v.as_u64() {
+            Some(n) if n > 0 => Ok(Some(n)),
+            _ => {
+                Err(SinkReason::sink(format!("opensearch.{key} must be a positive integer")).into())
+            }
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn build_conf_checks_auth_modes() {
+        let conf = build_conf(&opensearch_defaults()).expect("defaults are valid");
+        assert_eq!(conf.auth_mode, OpenSearchAuthMode::None);
+        assert_eq!(conf.bulk_action(), "index");
+
+        let mut p = opensearch_defaults();
+        p.insert("auth_mode".into(), json!("basic"));
+        assert!(build_conf(&p).is_err(), "username missing");
+        p.insert("username".into(), json!("admin"));
+        assert!(build_conf(&p).is_ok());
+
+        let mut p = opensearch_defaults();
+        p.insert("auth_mode".into(), json!("sigv4"));
+        p.insert("access_key_id".into(), json!("AK"));
+        assert!(build_conf(&p).is_err(), "secret key missing");
+        p.insert("secret_access_key".into(), json!("SK"));
+        p.insert("service".into(), json!("aoss"));
+        p.insert("data_stream".into(), json!(true));
+        let conf = build_conf(&p).expect("sigv4");
+        assert_eq!(conf.service, "aoss");
+        assert_eq!(conf.bulk_action(), "create");
+
+        for (key, value) in [
+            ("auth_mode", json!("kerberos")),
+            ("endpoint", json!("localhost:9200")),
+            ("data_stream", json!("yes")),
+            ("batch", json!(0)),
+        ] {
+            let mut p = opensearch_defaults();
+            p.insert(key.into(), value);
+            assert!(build_conf(&p).is_err(), "{key} should be rejected");
+        }
+    }
+}
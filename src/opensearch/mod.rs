@@ -0,0 +1,13 @@
+//! OpenSearch sink：bulk 写入索引或数据流，与 Elasticsearch sink 相互独立。
+//!
+//! 模块划分：
+//! - config：OpenSearchSinkConfig 与认证方式（none / basic / sigv4）
+//! - sink：OpenSearchSink（攒批、bulk 请求体、SigV4 签名、逐项错误检查）
+//! - factory：Sink 工厂
+
+pub mod config;
+mod factory;
+mod sink;
+
+pub use config::{OpenSearchAuthMode, OpenSearchSinkConfig};
+pub use factory::OpenSearchSinkFactory;
@@ -0,0 +1,605 @@
+use std::collections::HashSet;
+use std::fmt::Write;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{SecondsFormat, Utc};
+use reqwest::Url;
+use reqwest::header::CONTENT_TYPE;
+use wp_connector_api::{
+    AsyncCtrl, AsyncRawDataSink, AsyncRecordSink, SinkError, SinkReason, SinkResult,
+};
+use wp_data_fmt::{DataFormat, FormatType};
+use wp_model_core::model::{DataField, DataRecord, fmt_def::TextFmt};
+
+use crate::common::reconnect::{ReconnectCoordinator, backend_key, probe_endpoint};
+use crate::common::retry::{self, PendingFlush};
+use crate::common::sigv4::{SigV4, SignRequest, hex_sha256};
+use crate::common::stats::StatsHandle;
+use crate::common::trace_context::TraceContext;
+use crate::opensearch::config::{OpenSearchAuthMode, OpenSearchSinkConfig};
+
+/// 数据流要求的时间字段
+const TIMESTAMP_FIELD: &str = "@timestamp";
+/// bulk 失败汇总中最多列出的文档数
+const MAX_REPORTED_ITEM_ERRORS: usize = 5;
+
+/// 待发送文档：(_id, json)
+type BulkDoc = (Option<String>, String);
+/// 缓存中的文档及其接收序号（见 [`PendingFlush::pending_seqs`]）
+type Buffered = (u64, BulkDoc);
+/// bulk 响应中失败的文档：(请求内位置, 状态码, 描述)
+type ItemFailure = (usize, u16, String);
+
+/// 攒批写入 OpenSearch：action 行不带 `_type`，满 `batch` 条时 POST `/_bulk`。
+/// 请求失败时保留整批；部分文档失败时丢弃被永久拒绝的文档（如 400 mapping 错误），
+/// 仅保留 408/429/5xx 的文档由外层重试。
+pub(crate) struct OpenSearchSink {
+    client: reqwest::Client,
+    bulk_url: String,
+    index: String,
+    action: &'static str,
+    data_stream: bool,
+    id_field: Option<String>,
+    batch: usize,
+    auth_mode: OpenSearchAuthMode,
+    basic: Option<(String, Option<String>)>,
+    signer: Option<SigV4>,
+    pending: Vec<Buffered>,
+    /// 为每个 bulk 请求附加 W3C trace 头；为 None 时不附加
+    trace_context: Option<TraceContext>,
+    /// 缓存批内第一条记录携带的 trace id
+    pending_trace_id: Option<String>,
+    /// 累计进入缓存的文档数
+    accepted: u64,
+    /// `/stats` 自省状态（缓存、最近 flush 与错误）
+    stats: StatsHandle,
+}
+
+impl OpenSearchSink {
+    pub(crate) fn new(client: reqwest::Client, conf: &OpenSearchSinkConfig) -> Self {
+        Self {
+            client,
+            bulk_url: format!("{}/_bulk", conf.endpoint.trim_end_matches('/')),
+            index: conf.index.clone(),
+            action: conf.bulk_action(),
+            data_stream: conf.data_stream,
+            id_field: conf.id_field.clone(),
+            batch: conf.batch.max(1),
+            auth_mode: conf.auth_mode,
+            basic: conf
+                .username
+                .clone()
+                .map(|user| (user, conf.password.clone())),
+            signer: conf
+                .credentials
+                .clone()
+                .map(|c| SigV4::new(c, conf.region.clone(), &conf.service)),
+            pending: Vec::new(),
+            trace_context: None,
+            pending_trace_id: None,
+            accepted: 0,
+            stats: StatsHandle::detached(&conf.index, "opensearch"),
+        }
+    }
+
+    /// 使用外部登记的状态句柄，以配置的索引作为指标的写入目标。
+    pub(crate) fn with_stats(mut self, stats: StatsHandle) -> Self {
+        self.stats = stats.with_destination(self.index.as_str());
+        self
+    }
+
+    pub(crate) fn with_trace_context(mut self, trace_context: Option<TraceContext>) -> Self {
+        self.trace_context = trace_context;
+        self
+    }
+
+    /// 缓存批内尚无 trace id 时取记录携带的 trace id。
+    fn track_trace_id(&mut self, record: &DataRecord) {
+        if self.pending_trace_id.is_none() {
+            self.pending_trace_id = self
+                .trace_context
+                .as_ref()
+                .and_then(|trace| trace.record_trace_id(record));
+        }
+    }
+
+    /// 序列化记录；写入数据流且缺少 `@timestamp` 时补齐当前时间。
+    fn format_doc(&self, record: &DataRecord) -> BulkDoc {
+        let id = self
+            .id_field
+            .as_deref()
+            .and_then(|field| record.get2(field))
+            .map(|f| f.get_value().to_string());
+        let fmt = FormatType::from(&TextFmt::Json);
+        if self.data_stream && record.get2(TIMESTAMP_FIELD).is_none() {
+            let mut stamped = record.clone();
+            let now = Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true);
+            stamped.append(DataField::from_chars(TIMESTAMP_FIELD, now.as_str()));
+            return (id, fmt.format_record(&stamped));
+        }
+        (id, fmt.format_record(record))
+    }
+
+    async fn push(&mut self, docs: impl IntoIterator<Item = BulkDoc>) -> SinkResult<()> {
+        for doc in docs {
+            self.pending.push((self.accepted, doc));
+            self.accepted += 1;
+        }
+        self.stats.set_buffered(self.pending.len());
+        if self.pending.len() >= self.batch {
+            self.flush().await?;
+        }
+        Ok(())
+    }
+
+    /// 当前缓存对应的 bulk 请求体（NDJSON）。
+    fn bulk_body(&self) -> Vec<u8> {
+        let mut body = String::new();
+        for (_, (id, doc)) in &self.pending {
+            let _ = write!(
+                body,
+                "{{\"{}\":{{\"_index\":{}",
+                self.action,
+                serde_json::Value::from(self.index.as_str())
+            );
+            if let Some(id) = id {
+                let _ = write!(body, ",\"_id\":{}", serde_json::Value::from(id.as_str()));
+            }
+            body.push_str("}}\n");
+            body.push_str(doc.trim_end_matches('\n'));
+            body.push('\n');
+        }
+        body.into_bytes()
+    }
+
+    async fn flush(&mut self) -> SinkResult<()> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+        let result = self.flush_bulk().await;
+        self.stats.record_flush(&result);
+        self.stats.set_buffered(self.pending.len());
+        result
+    }
+
+    /// 发送当前缓存：写入成功与被拒绝的文档移出缓存，可重试的文档保留。
+    async fn flush_bulk(&mut self) -> SinkResult<()> {
+        let body = self.bulk_body();
+        let text = self.send(body).await?;
+        let failed = bulk_item_failures(&text);
+        if failed.is_empty() {
+            self.pending.clear();
+            self.pending_trace_id = None;
+            return Ok(());
+        }
+        let summary = summarize_failures(&failed, self.pending.len());
+        let (retryable, rejected): (Vec<_>, Vec<_>) = failed
+            .iter()
+            .partition(|(_, status, _)| retry::retryable_status(*status));
+        if !rejected.is_empty() {
+            wp_log::error_data!(
+                "opensearch drop {} rejected documents: {}",
+                rejected.len(),
+                summary
+            );
+        }
+        let keep: HashSet<usize> = retryable.iter().map(|(pos, _, _)| *pos).collect();
+        let mut pos = 0;
+        self.pending.retain(|_| {
+            pos += 1;
+            keep.contains(&(pos - 1))
+        });
+        if self.pending.is_empty() {
+            self.pending_trace_id = None;
+            return Ok(());
+        }
+        Err(retry::status_error(
+            retryable[0].1,
+            format!("opensearch bulk partial failure: {summary}"),
+        ))
+    }
+
+    /// 发送 bulk 请求并返回响应体，非 2xx 响应转为错误。
+    async fn send(&self, body: Vec<u8>) -> SinkResult<String> {
+        let mut req = self
+            .client
+            .post(&self.bulk_url)
+            .header(CONTENT_TYPE, "application/x-ndjson");
+        match self.auth_mode {
+            OpenSearchAuthMode::None => {}
+            OpenSearchAuthMode::Basic => {
+                if let Some((user, password)) = &self.basic {
+                    req = req.basic_auth(user, password.as_deref());
+                }
+            }
+            OpenSearchAuthMode::Sigv4 => {
+                let signer = self
+                    .signer
+                    .as_ref()
+                    .ok_or_else(|| os_error("sign request", "sigv4 credentials missing"))?;
+                for (name, value) in self.sign(signer, &body)? {
+                    req = req.header(name, value);
+                }
+            }
+        }
+        if let Some(trace) = &self.trace_context {
+            for (name, value) in trace.headers_with_id(self.pending_trace_id.clone()) {
+                req = req.header(name, value);
+            }
+        }
+        let resp = req.body(body).send().await.map_err(|e| {
+            retry::transport_error(format!("opensearch bulk send failed: {e}"), e.is_timeout())
+        })?;
+        let status = resp.status();
+        let text = resp.text().await.map_err(|e| {
+            retry::transport_error(
+                format!("opensearch bulk read response failed: {e}"),
+                e.is_timeout(),
+            )
+        })?;
+        if !status.is_success() {
+            return Err(retry::status_error(
+                status.as_u16(),
+                format!("opensearch bulk failed: {status}: {text}"),
+            ));
+        }
+        Ok(text)
+    }
+
+    fn sign(&self, signer: &SigV4, body: &[u8]) -> SinkResult<Vec<(String, String)>> {
+        let url = Url::parse(&self.bulk_url).map_err(|e| os_error("build url", e))?;
+        let host = match (url.host_str(), url.port()) {
+            (Some(host), Some(port)) => format!("{host}:{port}"),
+            (Some(host), None) => host.to_string(),
+            (None, _) => return Err(os_error("build url", "endpoint has no host")),
+        };
+        let payload = hex_sha256(body);
+        Ok(signer.sign(
+            &SignRequest {
+                method: "POST",
+                host: &host,
+                path: url.path(),
+                query: &[],
+                headers: &[],
+                payload_sha256: &payload,
+            },
+            Utc::now(),
+        ))
+    }
+}
+
+/// 解析 bulk 响应，`errors` 为 true 时列出失败文档（位置、状态码、索引与原因）。
+fn bulk_item_failures(body: &str) -> Vec<ItemFailure> {
+    let Ok(resp) = serde_json::from_str::<serde_json::Value>(body) else {
+        return Vec::new();
+    };
+    if resp.get("errors").and_then(|v| v.as_bool()) != Some(true) {
+        return Vec::new();
+    }
+    let Some(items) = resp.get("items").and_then(|v| v.as_array()) else {
+        return Vec::new();
+    };
+    items
+        .iter()
+        .enumerate()
+        // 每项形如 {"index": {...}}，键为 action 名
+        .filter_map(|(pos, item)| {
+            item.as_object()
+                .and_then(|o| o.values().next())
+                .map(|result| (pos, result))
+        })
+        .filter_map(|(pos, result)| {
+            let status = result.get("status").and_then(|v| v.as_u64()).unwrap_or(0) as u16;
+            if (200..300).contains(&status) {
+                return None;
+            }
+            let index = result.get("_index").and_then(|v| v.as_str()).unwrap_or("?");
+            let error = result.get("error");
+            let kind = error
+                .and_then(|e| e.get("type"))
+                .and_then(|v| v.as_str())
+                .unwrap_or("unknown");
+            let reason = error
+                .and_then(|e| e.get("reason"))
+                .and_then(|v| v.as_str())
+                .unwrap_or("");
+            Some((pos, status, format!("[{index} {status}] {kind}: {reason}")))
+        })
+        .collect()
+}
+
+/// 失败文档汇总，最多列出 [`MAX_REPORTED_ITEM_ERRORS`] 条。
+fn summarize_failures(failed: &[ItemFailure], total: usize) -> String {
+    let mut summary = format!("{} of {} items failed: ", failed.len(), total);
+    let listed: Vec<&str> = failed
+        .iter()
+        .take(MAX_REPORTED_ITEM_ERRORS)
+        .map(|(_, _, desc)| desc.as_str())
+        .collect();
+    summary.push_str(&listed.join("; "));
+    if failed.len() > MAX_REPORTED_ITEM_ERRORS {
+        let _ = write!(
+            summary,
+            "; ... {} more",
+            failed.len() - MAX_REPORTED_ITEM_ERRORS
+        );
+    }
+    summary
+}
+
+fn os_error(what: &str, err: impl std::fmt::Display) -> SinkError {
+    SinkError::from(SinkReason::sink(format!("opensearch {what} failed: {err}")))
+}
+
+#[async_trait]
+impl PendingFlush for OpenSearchSink {
+    fn pending_len(&self) -> usize {
+        self.pending.len()
+    }
+
+    async fn flush_now(&mut self) -> SinkResult<()> {
+        self.flush().await
+    }
+
+    fn discard_pending(&mut self) -> usize {
+        let discarded = self.pending.len();
+        self.pending.clear();
+        self.pending_trace_id = None;
+        self.stats.set_buffered(0);
+        discarded
+    }
+
+    fn accepted(&self) -> u64 {
+        self.accepted
+    }
+
+    fn pending_seqs(&self) -> Vec<u64> {
+        self.pending.iter().map(|(seq, _)| *seq).collect()
+    }
+}
+
+#[async_trait]
+impl AsyncCtrl for OpenSearchSink {
+    async fn stop(&mut self) -> SinkResult<()> {
+        self.flush().await
+    }
+
+    /// 不发请求（免去签名），只确认集群主机可连通，按后端共享退避。
+    async fn reconnect(&mut self) -> SinkResult<()> {
+        ReconnectCoordinator::shared(
+            backend_key("opensearch", &self.bulk_url),
+            Default::default(),
+        )
+        .run(|| probe_endpoint(&self.bulk_url))
+        .await
+    }
+}
+
+#[async_trait]
+impl AsyncRecordSink for OpenSearchSink {
+    async fn sink_record(&mut self, data: &DataRecord) -> SinkResult<()> {
+        let doc = self.format_doc(data);
+        self.track_trace_id(data);
+        self.push([doc]).await
+    }
+
+    async fn sink_records(&mut self, data: Vec<Arc<DataRecord>>) -> SinkResult<()> {
+        let docs: Vec<BulkDoc> = data
+            .iter()
+            .map(|record| self.format_doc(record.as_ref()))
+            .collect();
+        data.iter()
+            .for_each(|record| self.track_trace_id(record.as_ref()));
+        self.push(docs).await
+    }
+}
+
+/// 原始数据须为 JSON 文档，按原样写入。
+#[async_trait]
+impl AsyncRawDataSink for OpenSearchSink {
+    async fn sink_str(&mut self, data: &str) -> SinkResult<()> {
+        self.push([(None, data.to_string())]).await
+    }
+
+    async fn sink_bytes(&mut self, data: &[u8]) -> SinkResult<()> {
+        self.sink_str(&String::from_utf8_lossy(data)).await
+    }
+
+    async fn sink_str_batch(&mut self, data: Vec<&str>) -> SinkResult<()> {
+        self.push(data.into_iter().map(|s| (None, s.to_string())))
+            .await
+    }
+
+    async fn sink_bytes_batch(&mut self, data: Vec<&[u8]>) -> SinkResult<()> {
+        self.push(
+            data.into_iter()
+                .map(|b| (None, String::from_utf8_lossy(b).into_owned())),
+        )
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::sigv4::AwsCredentials;
+    use crate::common::stats::StatsHandle;
+    use httpmock::prelude::*;
+
+    fn client() -> reqwest::Client {
+        reqwest::Client::builder()
+            .no_proxy()
+            .build()
+            .expect("client")
+    }
+
+    fn record(fields: &[(&str, &str)]) -> DataRecord {
+        let mut record = DataRecord::default();
+        for (name, value) in fields {
+            record.append(DataField::from_chars(*name, *value));
+        }
+        record
+    }
+
+    #[test]
+    fn bulk_header_has_no_type_and_follows_action() {
+        let conf = OpenSearchSinkConfig {
+            index: "logs".into(),
+            id_field: Some("id".into()),
+            ..Default::default()
+        };
+        let mut sink = OpenSearchSink::new(client(), &conf);
+        sink.pending = vec![
+            (0, sink.format_doc(&record(&[("id", "a\"1"), ("msg", "x")]))),
+            (1, sink.format_doc(&record(&[("msg", "y")]))),
+        ];
+        let body = String::from_utf8(sink.bulk_body()).unwrap();
+        let lines: Vec<&str> = body.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[0], r#"{"index":{"_index":"logs","_id":"a\"1"}}"#);
+        assert_eq!(lines[2], r#"{"index":{"_index":"logs"}}"#);
+        assert!(!body.contains("_type"));
+        assert!(lines[3].contains("\"msg\":\"y\""), "{body}");
+
+        let conf = OpenSearchSinkConfig {
+            index: "logs-app".into(),
+            data_stream: true,
+            ..Default::default()
+        };
+        let mut sink = OpenSearchSink::new(client(), &conf);
+        sink.pending = vec![
+            (0, sink.format_doc(&record(&[("msg", "x")]))),
+            (
+                1,
+                sink.format_doc(&record(&[("@timestamp", "2024-03-09T00:00:00Z")])),
+            ),
+        ];
+        let body = String::from_utf8(sink.bulk_body()).unwrap();
+        let lines: Vec<&str> = body.lines().collect();
+        assert_eq!(lines[0], r#"{"create":{"_index":"logs-app"}}"#);
+        assert!(lines[1].contains("\"@timestamp\""), "stamped: {body}");
+        assert_eq!(lines[3].matches("@timestamp").count(), 1);
+    }
+
+    #[test]
+    fn item_errors_are_summarized() {
+        assert!(bulk_item_failures(r#"{"errors":false,"items":[]}"#).is_empty());
+        let failed = bulk_item_failures(
+            r#"{"errors":true,"items":[{"create":{"_index":"logs","status":201}},{"create":{"_index":"logs","status":400,"error":{"type":"mapper_parsing_exception","reason":"bad field"}}}]}"#,
+        );
+        assert_eq!(failed.len(), 1);
+        assert_eq!((failed[0].0, failed[0].1), (1, 400));
+        assert_eq!(
+            summarize_failures(&failed, 2),
+            "1 of 2 items failed: [logs 400] mapper_parsing_exception: bad field"
+        );
+    }
+
+    #[tokio::test]
+    async fn sigv4_requests_carry_signature_headers() {
+        let server = MockServer::start_async().await;
+        let bulk = server.mock(|when, then| {
+            when.method(POST)
+                .path("/_bulk")
+                .header("content-type", "application/x-ndjson")
+                .header_exists("x-amz-date")
+                .header_exists("x-amz-content-sha256")
+                .header("x-amz-security-token", "session")
+                .header_exists("authorization");
+            then.status(200).body(r#"{"errors":false,"items":[]}"#);
+        });
+        let conf = OpenSearchSinkConfig {
+            endpoint: server.base_url(),
+            auth_mode: OpenSearchAuthMode::Sigv4,
+            service: "aoss".into(),
+            credentials: Some(AwsCredentials {
+                access_key_id: "AK".into(),
+                secret_access_key: "SK".into(),
+                session_token: Some("session".into()),
+            }),
+            ..Default::default()
+        };
+        let mut sink = OpenSearchSink::new(client(), &conf);
+        let headers = sink
+            .sign(sink.signer.as_ref().unwrap(), b"")
+            .expect("signed");
+        let auth = &headers
+            .iter()
+            .find(|(k, _)| k == "authorization")
+            .unwrap()
+            .1;
+        assert!(auth.contains("/us-east-1/aoss/aws4_request"), "{auth}");
+
+        sink.sink_record(&record(&[("msg", "x")]))
+            .await
+            .expect("buffered");
+        sink.stop().await.expect("flushed");
+        bulk.assert_hits(1);
+        assert_eq!(sink.pending_len(), 0);
+    }
+
+    #[tokio::test]
+    async fn failed_items_keep_batch() {
+        let server = MockServer::start_async().await;
+        let bulk = server.mock(|when, then| {
+            when.method(POST).path("/_bulk").header_exists("authorization");
+            then.status(200).body(
+                r#"{"errors":true,"items":[{"index":{"_index":"wp-events","status":429,"error":{"type":"es_rejected_execution_exception","reason":"queue full"}}}]}"#,
+            );
+        });
+        let conf = OpenSearchSinkConfig {
+            endpoint: server.base_url(),
+            auth_mode: OpenSearchAuthMode::Basic,
+            username: Some("admin".into()),
+            password: Some("admin".into()),
+            batch: 1,
+            ..Default::default()
+        };
+        let mut sink = OpenSearchSink::new(client(), &conf);
+        let err = sink
+            .sink_record(&record(&[("msg", "x")]))
+            .await
+            .expect_err("item failure");
+        assert!(format!("{err}").contains("queue full"), "{err}");
+        assert_eq!(retry::FailureKind::of(&err), retry::FailureKind::Retryable);
+        bulk.assert_hits(1);
+        assert_eq!(sink.pending_len(), 1);
+    }
+
+    #[tokio::test]
+    async fn rejected_items_are_dropped_and_retryable_items_kept() {
+        let server = MockServer::start_async().await;
+        let mut bulk = server.mock(|when, then| {
+            when.method(POST).path("/_bulk");
+            then.status(200).body(
+                r#"{"errors":true,"items":[{"index":{"_index":"logs","status":201}},{"index":{"_index":"logs","status":400,"error":{"type":"mapper_parsing_exception","reason":"bad field"}}},{"index":{"_index":"logs","status":503,"error":{"type":"unavailable_shards_exception","reason":"primary shard is not active"}}}]}"#,
+            );
+        });
+        let conf = OpenSearchSinkConfig {
+            endpoint: server.base_url(),
+            batch: 3,
+            ..Default::default()
+        };
+        let mut sink = OpenSearchSink::new(client(), &conf);
+        let docs = ["ok", "bad", "later"].map(|msg| Arc::new(record(&[("msg", msg)])));
+        let err = sink
+            .sink_records(docs.to_vec())
+            .await
+            .expect_err("shard unavailable");
+        assert_eq!(retry::FailureKind::of(&err), retry::FailureKind::Retryable);
+        bulk.assert_hits(1);
+        assert_eq!(sink.pending_seqs(), vec![2]);
+        assert!(sink.pending[0].1.1.contains("later"));
+
+        bulk.delete();
+        server.mock(|when, then| {
+            when.method(POST).path("/_bulk");
+            then.status(200).body(
+                r#"{"errors":true,"items":[{"index":{"_index":"logs","status":400,"error":{"type":"mapper_parsing_exception","reason":"bad field"}}}]}"#,
+            );
+        });
+        sink.flush_now().await.expect("rejected document dropped");
+        assert_eq!(sink.pending_len(), 0);
+    }
+}
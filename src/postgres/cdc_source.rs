@@ -0,0 +1,244 @@
+//! PostgreSQL CDC source：通过 SQL 接口读取 `pgoutput` 逻辑复制槽，按事务输出
+//! insert/update/delete 行变更。
+//!
+//! 每次用 `pg_logical_slot_peek_binary_changes` 查看槽中的完整事务（不消费），
+//! 交付给下游后，在下一次 `receive` 时写入 LSN checkpoint 并用
+//! `pg_replication_slot_advance` 推进复制槽。启动时若 checkpoint 领先于复制槽，
+//! 先推进复制槽，因此重启后不会重复输出已交付的事务；在推进前崩溃时，
+//! 最后一批事务会再次输出（至少一次）。
+
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio_postgres::{Client, Config, NoTls};
+use wp_connector_api::{
+    DataSource, SourceBatch, SourceError, SourceEvent, SourceReason, SourceResult, Tags,
+};
+use wp_parse_api::RawData;
+
+use crate::postgres::pgoutput::{
+    Lsn, LsnCheckpoint, PgOutputMessage, PostgresCdcConf, Relation, RowChange, checkpoint_path,
+};
+
+const PEEK_CHANGES_SQL: &str = "SELECT data FROM pg_logical_slot_peek_binary_changes(\
+     $1, NULL, $2, 'proto_version', '1', 'publication_names', $3)";
+
+pub struct PostgresCdcSource {
+    key: String,
+    tags: Tags,
+    conf: PostgresCdcConf,
+    client: Client,
+    relations: HashMap<u32, Relation>,
+    /// 已交付给下游、待写入 checkpoint 并推进复制槽的位点
+    delivered: Option<Lsn>,
+    checkpoint_path: PathBuf,
+    event_seq: u64,
+}
+
+impl PostgresCdcSource {
+    pub async fn new(
+        key: String,
+        tags: Tags,
+        pg_config: &Config,
+        conf: PostgresCdcConf,
+    ) -> anyhow::Result<Self> {
+        let (client, connection) = pg_config.connect(NoTls).await?;
+        let conn_key = key.clone();
+        tokio::spawn(async move {
+            if let Err(e) = connection.await {
+                wp_log::error_data!("[postgres-cdc] {} connection closed: {}", conn_key, e);
+            }
+        });
+        ensure_slot(&client, &conf).await?;
+
+        let checkpoint_path = checkpoint_path(&key);
+        let checkpoint = LsnCheckpoint::load(&checkpoint_path)?;
+        if let Some(checkpoint) = checkpoint {
+            advance_slot(&client, &conf.slot, checkpoint.lsn).await?;
+        }
+        wp_log::info_data!(
+            "[postgres-cdc] {} slot: {}, publication: {}, checkpoint: {:?}",
+            key,
+            conf.slot,
+            conf.publication,
+            checkpoint.map(|c| c.lsn.to_string())
+        );
+        Ok(Self {
+            key,
+            tags,
+            conf,
+            client,
+            relations: HashMap::new(),
+            delivered: None,
+            checkpoint_path,
+            event_seq: 0,
+        })
+    }
+
+    pub fn identifier(&self) -> &str {
+        &self.key
+    }
+
+    fn create_event(&mut self, change: &RowChange) -> SourceEvent {
+        self.event_seq = self.event_seq.wrapping_add(1);
+        SourceEvent::new(
+            self.event_seq,
+            self.key.clone(),
+            RawData::from_string(change.to_json()),
+            self.tags.clone().into(),
+        )
+    }
+
+    /// 写入上一批已交付的位点并推进复制槽。
+    async fn commit_delivered(&mut self) -> SourceResult<()> {
+        let Some(lsn) = self.delivered.take() else {
+            return Ok(());
+        };
+        if let Err(e) = (LsnCheckpoint { lsn }).save(&self.checkpoint_path) {
+            wp_log::error_data!(
+                "[postgres-cdc] {} save checkpoint {} fail: {}",
+                self.key,
+                lsn,
+                e
+            );
+        }
+        advance_slot(&self.client, &self.conf.slot, lsn)
+            .await
+            .map_err(|e| stream_error("advance replication slot", e))
+    }
+
+    /// 读取复制槽中的完整事务，返回其中的行变更；事务均不含变更时返回空。
+    async fn peek_changes(&mut self) -> SourceResult<Vec<RowChange>> {
+        let rows = self
+            .client
+            .query(
+                PEEK_CHANGES_SQL,
+                &[
+                    &self.conf.slot,
+                    &(self.conf.batch as i32),
+                    &self.conf.publication,
+                ],
+            )
+            .await
+            .map_err(|e| stream_error("peek slot changes", e))?;
+        let mut changes = Vec::new();
+        let mut pending = Vec::new();
+        let mut commit_ts = 0;
+        for row in rows {
+            let data: Vec<u8> = row.get(0);
+            let message =
+                PgOutputMessage::parse(&data).map_err(|e| stream_error("decode pgoutput", e))?;
+            match &message {
+                PgOutputMessage::Begin { commit_ts: ts, .. } => {
+                    commit_ts = *ts;
+                    pending.clear();
+                }
+                PgOutputMessage::Relation(relation) => {
+                    self.relations.insert(relation.id, relation.clone());
+                }
+                PgOutputMessage::Commit { end_lsn, .. } => {
+                    for change in &mut pending {
+                        change.lsn = *end_lsn;
+                    }
+                    changes.append(&mut pending);
+                    self.delivered = Some(*end_lsn);
+                }
+                _ => {
+                    let change =
+                        RowChange::from_message(&message, &self.relations, commit_ts, Lsn(0))
+                            .map_err(|e| stream_error("map pgoutput change", e))?;
+                    pending.extend(change);
+                }
+            }
+        }
+        Ok(changes)
+    }
+
+    /// 轮询直到有事务提交且包含采集的变更；只含无关表的事务直接推进复制槽。
+    async fn next_changes(&mut self) -> SourceResult<Vec<RowChange>> {
+        loop {
+            let changes = self.peek_changes().await?;
+            if !changes.is_empty() {
+                return Ok(changes);
+            }
+            if self.delivered.is_some() {
+                self.commit_delivered().await?;
+                continue;
+            }
+            tokio::time::sleep(Duration::from_millis(self.conf.poll_interval_ms)).await;
+        }
+    }
+}
+
+/// 检查复制槽；不存在时按 `create_slot` 创建或报错。
+async fn ensure_slot(client: &Client, conf: &PostgresCdcConf) -> anyhow::Result<()> {
+    let row = client
+        .query_opt(
+            "SELECT plugin FROM pg_replication_slots WHERE slot_name = $1",
+            &[&conf.slot],
+        )
+        .await?;
+    match row {
+        Some(row) => {
+            let plugin: Option<String> = row.get(0);
+            if plugin.as_deref() != Some("pgoutput") {
+                anyhow::bail!(
+                    "replication slot '{}' uses plugin {:?}, expected pgoutput",
+                    conf.slot,
+                    plugin
+                );
+            }
+        }
+        None if conf.create_slot => {
+            client
+                .execute(
+                    "SELECT pg_create_logical_replication_slot($1, 'pgoutput')",
+                    &[&conf.slot],
+                )
+                .await?;
+        }
+        None => anyhow::bail!(
+            "replication slot '{}' does not exist (set create_slot = true to create it)",
+            conf.slot
+        ),
+    }
+    Ok(())
+}
+
+/// 推进复制槽到 `lsn`；复制槽不会后退，已在其后时为空操作。
+async fn advance_slot(client: &Client, slot: &str, lsn: Lsn) -> Result<(), tokio_postgres::Error> {
+    client
+        .execute(
+            "SELECT pg_replication_slot_advance($1, $2::text::pg_lsn)",
+            &[&slot, &lsn.to_string()],
+        )
+        .await
+        .map(|_| ())
+}
+
+fn stream_error(what: &str, err: impl std::fmt::Display) -> SourceError {
+    SourceError::from(SourceReason::SupplierError(format!(
+        "postgres {what} fail: {err}"
+    )))
+}
+
+#[async_trait]
+impl DataSource for PostgresCdcSource {
+    async fn receive(&mut self) -> SourceResult<SourceBatch> {
+        self.commit_delivered().await?;
+        let changes = self.next_changes().await?;
+        Ok(changes
+            .iter()
+            .map(|change| self.create_event(change))
+            .collect())
+    }
+
+    fn try_receive(&mut self) -> Option<SourceBatch> {
+        None
+    }
+
+    fn identifier(&self) -> String {
+        self.key.clone()
+    }
+}
@@ -0,0 +1,181 @@
+use async_trait::async_trait;
+use serde_json::json;
+use wp_connector_api::{
+    ConnectorDef, ConnectorScope, ParamMap, SourceDefProvider, SourceHandle, SourceMeta,
+    SourceReason, SourceResult, SourceSvcIns, Tags,
+};
+
+use super::cdc_source::PostgresCdcSource;
+use super::pgoutput::PostgresCdcConf;
+use crate::WP_SRC_VAL;
+use crate::common::secret;
+
+const DEFAULT_PORT: u16 = 5432;
+
+pub struct PostgresSourceFactory;
+
+#[async_trait]
+impl wp_connector_api::SourceFactory for PostgresSourceFactory {
+    fn kind(&self) -> &'static str {
+        "postgres"
+    }
+
+    fn validate_spec(&self, spec: &wp_connector_api::SourceSpec) -> SourceResult<()> {
+        let spec = &secret::resolve_source_spec(spec)?;
+        pg_config(&spec.params)?;
+        PostgresCdcConf::from_params(&spec.params).map_err(SourceReason::Other)?;
+        Ok(())
+    }
+
+    async fn build(
+        &self,
+        spec: &wp_connector_api::SourceSpec,
+        _ctx: &wp_connector_api::SourceBuildCtx,
+    ) -> SourceResult<SourceSvcIns> {
+        let spec = &secret::resolve_source_spec(spec)?;
+        let config = pg_config(&spec.params)?;
+        let cdc = PostgresCdcConf::from_params(&spec.params).map_err(SourceReason::Other)?;
+        let mut meta_tags = Tags::from_parse(&spec.tags);
+        meta_tags.set(WP_SRC_VAL, "postgres");
+        let source = PostgresCdcSource::new(spec.name.clone(), meta_tags.clone(), &config, cdc)
+            .await
+            .map_err(|err| SourceReason::Other(format!("postgres cdc fail: {err}")))?;
+
+        let mut meta = SourceMeta::new(spec.name.clone(), spec.kind.clone());
+        meta.tags = meta_tags;
+        let handle = SourceHandle::new(Box::new(source), meta);
+        Ok(SourceSvcIns::new().with_sources(vec![handle]))
+    }
+}
+
+/// 由 `endpoint`（`host[:port]`）、`database`、`username`、`password` 构建连接配置。
+fn pg_config(params: &ParamMap) -> SourceResult<tokio_postgres::Config> {
+    let get = |key: &str| params.get(key).and_then(|v| v.as_str()).map(str::trim);
+    let endpoint = get("endpoint").unwrap_or("");
+    if endpoint.is_empty() {
+        return Err(SourceReason::Other("postgres.endpoint must not be empty".into()).into());
+    }
+    let endpoint = endpoint.strip_prefix("postgres://").unwrap_or(endpoint);
+    let (host, port) = split_host_port(endpoint)?;
+    let database = get("database").unwrap_or("");
+    if database.is_empty() {
+        return Err(SourceReason::Other("postgres.database must not be empty".into()).into());
+    }
+    let mut config = tokio_postgres::Config::new();
+    config
+        .host(host)
+        .port(port)
+        .dbname(database)
+        .user(get("username").unwrap_or("postgres"))
+        .application_name("wp-connectors");
+    if let Some(password) = get("password") {
+        config.password(password);
+    }
+    Ok(config)
+}
+
+/// 拆分 `host[:port]`；IPv6 地址带端口时写作 `[addr]:port`，不带端口的裸 IPv6 地址整体作为主机。
+fn split_host_port(endpoint: &str) -> SourceResult<(&str, u16)> {
+    let invalid = || SourceReason::Other(format!("postgres.endpoint '{endpoint}' is invalid"));
+    let (host, port) = if let Some(rest) = endpoint.strip_prefix('[') {
+        let (host, tail) = rest.split_once(']').ok_or_else(invalid)?;
+        match tail {
+            "" => (host, None),
+            _ => (host, Some(tail.strip_prefix(':').ok_or_else(invalid)?)),
+        }
+    } else {
+        match endpoint.split_once(':') {
+            Some((host, port)) if !port.contains(':') => (host, Some(port)),
+            _ => (endpoint, None),
+        }
+    };
+    if host.is_empty() {
+        return Err(invalid().into());
+    }
+    let port = match port {
+        Some(port) => port.parse::<u16>().map_err(|_| {
+            SourceReason::Other(format!("postgres.endpoint has invalid port '{port}'"))
+        })?,
+        None => DEFAULT_PORT,
+    };
+    Ok((host, port))
+}
+
+impl SourceDefProvider for PostgresSourceFactory {
+    fn source_def(&self) -> ConnectorDef {
+        ConnectorDef {
+            id: "postgres_src".into(),
+            kind: self.kind().into(),
+            scope: ConnectorScope::Source,
+            allow_override: vec![
+                "endpoint",
+                "database",
+                "username",
+                "secret_ref",
+                "publication",
+                "slot",
+                "create_slot",
+                "batch",
+                "poll_interval_ms",
+            ]
+            .into_iter()
+            .map(str::to_string)
+            .collect(),
+            default_params: postgres_source_defaults(),
+            origin: Some("wp-connectors:postgres_source".into()),
+        }
+    }
+}
+
+fn postgres_source_defaults() -> ParamMap {
+    let mut params = ParamMap::new();
+    params.insert("endpoint".into(), json!("localhost:5432"));
+    params.insert("database".into(), json!("postgres"));
+    params.insert("username".into(), json!("postgres"));
+    params.insert("publication".into(), json!("wp_publication"));
+    params.insert("slot".into(), json!("wp_slot"));
+    params.insert("create_slot".into(), json!(false));
+    params
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pg_config_parses_endpoint() {
+        let mut params = postgres_source_defaults();
+        let config = pg_config(&params).expect("defaults");
+        assert_eq!(config.get_ports(), &[5432]);
+        assert_eq!(config.get_dbname(), Some("postgres"));
+
+        params.insert("endpoint".into(), json!("postgres://db.internal:6543"));
+        let config = pg_config(&params).expect("with scheme");
+        assert_eq!(config.get_ports(), &[6543]);
+        params.insert("endpoint".into(), json!("db.internal"));
+        assert_eq!(pg_config(&params).unwrap().get_ports(), &[DEFAULT_PORT]);
+
+        params.insert("endpoint".into(), json!("db:abc"));
+        assert!(pg_config(&params).is_err());
+
+        for (endpoint, host, port) in [
+            ("[::1]:6543", "::1", 6543),
+            ("[fe80::1]", "fe80::1", DEFAULT_PORT),
+            ("2001:db8::5", "2001:db8::5", DEFAULT_PORT),
+        ] {
+            assert_eq!(
+                split_host_port(endpoint).unwrap(),
+                (host, port),
+                "{endpoint}"
+            );
+        }
+        for endpoint in ["[::1", "[::1]6543", "[::1]:x", "[]:5432"] {
+            assert!(split_host_port(endpoint).is_err(), "{endpoint}");
+        }
+        params.insert("endpoint".into(), json!(""));
+        assert!(pg_config(&params).is_err());
+        params.insert("endpoint".into(), json!("db"));
+        params.remove("database");
+        assert!(pg_config(&params).is_err());
+    }
+}
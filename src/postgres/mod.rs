@@ -0,0 +1,14 @@
+//! wp-connector-postgres: PostgreSQL 逻辑复制 CDC source
+//!
+//! 模块划分：
+//! - pgoutput：CDC 配置、LSN 位点与 pgoutput 消息到行变更的映射
+//! - cdc_source：读取逻辑复制槽并按事务输出变更的 source
+//! - factory：Source 工厂
+
+mod cdc_source;
+mod factory;
+pub mod pgoutput;
+
+pub use cdc_source::PostgresCdcSource;
+pub use factory::PostgresSourceFactory;
+pub use pgoutput::{Lsn, PostgresCdcConf};
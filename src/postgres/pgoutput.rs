@@ -0,0 +1,760 @@
+//! PostgreSQL CDC（逻辑复制）的配置、LSN 位点与 pgoutput 消息解析。
+//!
+//! 变更以 JSON 文档输出，每行一条：
+//! ```json
+//! {"op":"update","schema":"public","table":"orders","ts":1700000000,"lsn":"0/16B3748",
+//!  "before":{"id":1,"status":"new"},"after":{"id":1,"status":"paid"}}
+//! ```
+//! `insert` 的 `before` 与 `delete` 的 `after` 为 null；`update` 的 `before` 仅在表的
+//! `REPLICA IDENTITY` 为 `FULL`（整行）或更新了 replica identity 键列（只含键列）时存在，
+//! `delete` 的 `before` 同样按 replica identity 只含键列或为整行。pgoutput 的键元组（`K`）
+//! 按关系的全部列发送、非键列以 null 占位，这些占位列不在镜像中出现；未变化的 TOAST 列同样不出现。
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use wp_connector_api::ParamMap;
+
+/// PostgreSQL 纪元（2000-01-01）相对 Unix 纪元的秒数
+const PG_EPOCH_OFFSET_SECS: i64 = 946_684_800;
+
+/// 行变更类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CdcOp {
+    Insert,
+    Update,
+    Delete,
+}
+
+impl CdcOp {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Insert => "insert",
+            Self::Update => "update",
+            Self::Delete => "delete",
+        }
+    }
+}
+
+/// WAL 位置（`pg_lsn`），文本形式为 `高 32 位/低 32 位` 的十六进制。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Lsn(pub u64);
+
+impl Display for Lsn {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:X}/{:X}", self.0 >> 32, self.0 & 0xffff_ffff)
+    }
+}
+
+impl FromStr for Lsn {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid pg_lsn '{s}'");
+        let (hi, lo) = s.trim().split_once('/').ok_or_else(invalid)?;
+        let hi = u32::from_str_radix(hi, 16).map_err(|_| invalid())?;
+        let lo = u32::from_str_radix(lo, 16).map_err(|_| invalid())?;
+        Ok(Self((u64::from(hi) << 32) | u64::from(lo)))
+    }
+}
+
+impl Serialize for Lsn {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Lsn {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// 已交付的位点：下游已收到 `lsn` 及之前提交的全部事务。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LsnCheckpoint {
+    pub lsn: Lsn,
+}
+
+impl LsnCheckpoint {
+    /// 读取 checkpoint 文件；文件不存在或为空时返回 `None`。
+    pub fn load(path: &Path) -> anyhow::Result<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let contents = std::fs::read_to_string(path)?;
+        if contents.trim().is_empty() {
+            return Ok(None);
+        }
+        serde_json::from_str(&contents)
+            .map(Some)
+            .map_err(|e| anyhow::anyhow!("invalid lsn checkpoint '{}': {e}", path.display()))
+    }
+
+    /// 写入 checkpoint：先写临时文件再重命名，避免中途崩溃留下半个文件。
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, serde_json::to_string(self)?)?;
+        std::fs::rename(&tmp, path)?;
+        Ok(())
+    }
+}
+
+/// LSN checkpoint 文件路径，与 MySQL 的 checkpoint 同目录。
+pub fn checkpoint_path(key: &str) -> PathBuf {
+    PathBuf::from(format!("./.run/.checkpoints/{}.pg_lsn.json", key))
+}
+
+/// CDC 配置。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PostgresCdcConf {
+    /// 发布名（`CREATE PUBLICATION`），决定采集哪些表
+    pub publication: String,
+    /// 逻辑复制槽名，插件须为 `pgoutput`
+    pub slot: String,
+    /// 复制槽不存在时是否自动创建
+    pub create_slot: bool,
+    /// 每次读取的最大变更数（按事务边界截断）
+    pub batch: usize,
+    /// 没有新变更时的轮询间隔（毫秒）
+    pub poll_interval_ms: u64,
+}
+
+impl PostgresCdcConf {
+    /// 读取 `publication`、`slot`、`create_slot`、`batch` 与 `poll_interval_ms`。
+    pub fn from_params(params: &ParamMap) -> Result<Self, String> {
+        let publication =
+            optional_str(params, "publication")?.ok_or("postgres.publication is required")?;
+        let slot = optional_str(params, "slot")?.ok_or("postgres.slot is required")?;
+        // 复制槽名只允许小写字母、数字与下划线
+        if !slot
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+        {
+            return Err("postgres.slot may only contain lower case letters, digits and '_'".into());
+        }
+        let create_slot = match params.get("create_slot") {
+            None | Some(Value::Null) => false,
+            Some(Value::Bool(b)) => *b,
+            Some(_) => return Err("postgres.create_slot must be a boolean".into()),
+        };
+        let batch = optional_positive(params, "batch")?.unwrap_or(1000) as usize;
+        let poll_interval_ms = optional_positive(params, "poll_interval_ms")?.unwrap_or(1000);
+        Ok(Self {
+            publication,
+            slot,
+            create_slot,
+            batch,
+            poll_interval_ms,
+        })
+    }
+}
+
+fn optional_str(params: &ParamMap, key: &str) -> Result<Option<String>, String> {
+    match params.get(key) {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::String(s)) if !s.trim().is_empty() => Ok(Some(s.trim().to_string())),
+        Some(_) => Err(format!("postgres.{key} must be a non-empty string")),
+    }
+}
+
+fn optional_positive(params: &ParamMap, key: &str) -> Result<Option<u64>, String> {
+    match params.get(key) {
+        None | Some(Value::Null) => Ok(None),
+        Some(v) => match v.as_u64() {
+            Some(n) if n > 0 => Ok(Some(n)),
+            _ => Err(format!("postgres.{key} must be a positive integer")),
+        },
+    }
+}
+
+/// 关系（表）元数据中的一列。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RelationColumn {
+    pub name: String,
+    pub type_oid: u32,
+    /// 属于 replica identity 键（`FULL` 时所有列均是）
+    pub key: bool,
+}
+
+/// `Relation` 消息：在该表的首个变更之前发送。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Relation {
+    pub id: u32,
+    pub schema: String,
+    pub table: String,
+    pub columns: Vec<RelationColumn>,
+}
+
+/// 元组中一列的值。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TupleValue {
+    Null,
+    /// 未变化的 TOAST 值，消息中不携带内容
+    Unchanged,
+    Text(String),
+}
+
+/// `Update`/`Delete` 携带的旧行。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OldTuple {
+    /// `K`：replica identity 键，按全部列发送，非键列为 null 占位
+    Key(Vec<TupleValue>),
+    /// `O`：`REPLICA IDENTITY FULL` 时的整行
+    Full(Vec<TupleValue>),
+}
+
+/// pgoutput（协议版本 1）消息；与变更无关的消息归为 `Other`。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PgOutputMessage {
+    Begin {
+        final_lsn: Lsn,
+        /// 提交时间（自 2000-01-01 起的微秒）
+        commit_ts: i64,
+        xid: u32,
+    },
+    Commit {
+        commit_lsn: Lsn,
+        end_lsn: Lsn,
+    },
+    Relation(Relation),
+    Insert {
+        relation: u32,
+        new: Vec<TupleValue>,
+    },
+    Update {
+        relation: u32,
+        old: Option<OldTuple>,
+        new: Vec<TupleValue>,
+    },
+    Delete {
+        relation: u32,
+        old: OldTuple,
+    },
+    Other(u8),
+}
+
+/// 按字节顺序读取 pgoutput 消息体（网络字节序）。
+struct Reader<'a> {
+    buf: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], String> {
+        if self.buf.len() < n {
+            return Err("pgoutput message truncated".into());
+        }
+        let (head, tail) = self.buf.split_at(n);
+        self.buf = tail;
+        Ok(head)
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn i16(&mut self) -> Result<i16, String> {
+        Ok(i16::from_be_bytes(
+            self.take(2)?.try_into().expect("2 bytes"),
+        ))
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_be_bytes(
+            self.take(4)?.try_into().expect("4 bytes"),
+        ))
+    }
+
+    fn i64(&mut self) -> Result<i64, String> {
+        Ok(i64::from_be_bytes(
+            self.take(8)?.try_into().expect("8 bytes"),
+        ))
+    }
+
+    fn lsn(&mut self) -> Result<Lsn, String> {
+        Ok(Lsn(self.i64()? as u64))
+    }
+
+    fn cstr(&mut self) -> Result<String, String> {
+        let end = self
+            .buf
+            .iter()
+            .position(|b| *b == 0)
+            .ok_or("pgoutput string not terminated")?;
+        let s = String::from_utf8_lossy(&self.buf[..end]).into_owned();
+        self.buf = &self.buf[end + 1..];
+        Ok(s)
+    }
+
+    /// 读取 `K`/`O` 标记后的旧行。
+    fn old_tuple(&mut self, tag: u8) -> Result<OldTuple, String> {
+        match tag {
+            b'K' => Ok(OldTuple::Key(self.tuple()?)),
+            b'O' => Ok(OldTuple::Full(self.tuple()?)),
+            other => Err(unexpected_tag(other)),
+        }
+    }
+
+    fn tuple(&mut self) -> Result<Vec<TupleValue>, String> {
+        let count = self.i16()?;
+        (0..count)
+            .map(|_| match self.u8()? {
+                b'n' => Ok(TupleValue::Null),
+                b'u' => Ok(TupleValue::Unchanged),
+                b't' => {
+                    let len = self.u32()? as usize;
+                    Ok(TupleValue::Text(
+                        String::from_utf8_lossy(self.take(len)?).into_owned(),
+                    ))
+                }
+                kind => Err(format!(
+                    "unsupported pgoutput tuple kind '{}'",
+                    kind as char
+                )),
+            })
+            .collect()
+    }
+}
+
+impl PgOutputMessage {
+    pub fn parse(data: &[u8]) -> Result<Self, String> {
+        let mut r = Reader { buf: data };
+        let tag = r.u8()?;
+        let message = match tag {
+            b'B' => Self::Begin {
+                final_lsn: r.lsn()?,
+                commit_ts: r.i64()?,
+                xid: r.u32()?,
+            },
+            b'C' => {
+                let _flags = r.u8()?;
+                Self::Commit {
+                    commit_lsn: r.lsn()?,
+                    end_lsn: r.lsn()?,
+                }
+            }
+            b'R' => {
+                let id = r.u32()?;
+                let schema = r.cstr()?;
+                let table = r.cstr()?;
+                let _replica_identity = r.u8()?;
+                let count = r.i16()?;
+                let columns = (0..count)
+                    .map(|_| {
+                        let flags = r.u8()?;
+                        let name = r.cstr()?;
+                        let type_oid = r.u32()?;
+                        let _type_mod = r.u32()?;
+                        Ok(RelationColumn {
+                            name,
+                            type_oid,
+                            key: flags & 1 != 0,
+                        })
+                    })
+                    .collect::<Result<Vec<_>, String>>()?;
+                Self::Relation(Relation {
+                    id,
+                    schema,
+                    table,
+                    columns,
+                })
+            }
+            b'I' => {
+                let relation = r.u32()?;
+                expect_tag(r.u8()?, b"N")?;
+                Self::Insert {
+                    relation,
+                    new: r.tuple()?,
+                }
+            }
+            b'U' => {
+                let relation = r.u32()?;
+                let (old, new) = match r.u8()? {
+                    b'N' => (None, r.tuple()?),
+                    tag => {
+                        let old = r.old_tuple(tag)?;
+                        expect_tag(r.u8()?, b"N")?;
+                        (Some(old), r.tuple()?)
+                    }
+                };
+                Self::Update { relation, old, new }
+            }
+            b'D' => {
+                let relation = r.u32()?;
+                let tag = r.u8()?;
+                Self::Delete {
+                    relation,
+                    old: r.old_tuple(tag)?,
+                }
+            }
+            other => Self::Other(other),
+        };
+        Ok(message)
+    }
+}
+
+fn expect_tag(tag: u8, allowed: &[u8]) -> Result<(), String> {
+    if allowed.contains(&tag) {
+        Ok(())
+    } else {
+        Err(unexpected_tag(tag))
+    }
+}
+
+fn unexpected_tag(tag: u8) -> String {
+    format!("unexpected pgoutput tuple tag '{}'", tag as char)
+}
+
+/// 一行变更。
+#[derive(Debug, Clone, PartialEq)]
+pub struct RowChange {
+    pub op: CdcOp,
+    pub schema: String,
+    pub table: String,
+    /// 事务提交时间（Unix 秒）
+    pub timestamp: i64,
+    /// 所在事务的提交位点
+    pub lsn: Lsn,
+    pub before: Option<Map<String, Value>>,
+    pub after: Option<Map<String, Value>>,
+}
+
+impl RowChange {
+    /// 由变更消息与其关系元数据构建；`commit_ts` 为 `Begin` 消息中的提交时间。
+    pub fn from_message(
+        message: &PgOutputMessage,
+        relations: &HashMap<u32, Relation>,
+        commit_ts: i64,
+        lsn: Lsn,
+    ) -> Result<Option<Self>, String> {
+        let (op, relation, before, after) = match message {
+            PgOutputMessage::Insert { relation, new } => (CdcOp::Insert, relation, None, Some(new)),
+            PgOutputMessage::Update { relation, old, new } => {
+                (CdcOp::Update, relation, old.as_ref(), Some(new))
+            }
+            PgOutputMessage::Delete { relation, old } => (CdcOp::Delete, relation, Some(old), None),
+            _ => return Ok(None),
+        };
+        let relation = relations
+            .get(relation)
+            .ok_or_else(|| format!("pgoutput change for unknown relation {relation}"))?;
+        Ok(Some(Self {
+            op,
+            schema: relation.schema.clone(),
+            table: relation.table.clone(),
+            timestamp: commit_ts.div_euclid(1_000_000) + PG_EPOCH_OFFSET_SECS,
+            lsn,
+            before: before.map(|old| match old {
+                OldTuple::Key(tuple) => tuple_image(relation, tuple, true),
+                OldTuple::Full(tuple) => tuple_image(relation, tuple, false),
+            }),
+            after: after.map(|tuple| tuple_image(relation, tuple, false)),
+        }))
+    }
+
+    /// 输出给下游解析的 JSON 文档。
+    pub fn to_json(&self) -> String {
+        json!({
+            "op": self.op.as_str(),
+            "schema": self.schema,
+            "table": self.table,
+            "ts": self.timestamp,
+            "lsn": self.lsn,
+            "before": self.before,
+            "after": self.after,
+        })
+        .to_string()
+    }
+}
+
+/// 将元组转换为 `列名 -> 值`，未变化的 TOAST 列不输出；`key_only` 时只输出 replica identity 键列。
+pub fn tuple_image(
+    relation: &Relation,
+    tuple: &[TupleValue],
+    key_only: bool,
+) -> Map<String, Value> {
+    relation
+        .columns
+        .iter()
+        .zip(tuple)
+        .filter(|(column, _)| !key_only || column.key)
+        .filter_map(|(column, value)| {
+            let value = match value {
+                TupleValue::Null => Value::Null,
+                TupleValue::Unchanged => return None,
+                TupleValue::Text(text) => text_value(column.type_oid, text),
+            };
+            Some((column.name.clone(), value))
+        })
+        .collect()
+}
+
+/// 按列类型将文本值转为 JSON：布尔、整数、浮点与 json/jsonb 转为对应类型，
+/// 其余（含 numeric，避免精度丢失）保留文本。
+fn text_value(type_oid: u32, text: &str) -> Value {
+    match type_oid {
+        // bool
+        16 => Value::Bool(text == "t"),
+        // int2, int4, int8, oid
+        20 | 21 | 23 | 26 => text
+            .parse::<i64>()
+            .map(Value::from)
+            .unwrap_or_else(|_| Value::String(text.to_string())),
+        // float4, float8；NaN/Infinity 不是合法 JSON 数字，保留文本
+        700 | 701 => text
+            .parse::<f64>()
+            .ok()
+            .and_then(serde_json::Number::from_f64)
+            .map(Value::Number)
+            .unwrap_or_else(|| Value::String(text.to_string())),
+        // json, jsonb
+        114 | 3802 => {
+            serde_json::from_str(text).unwrap_or_else(|_| Value::String(text.to_string()))
+        }
+        _ => Value::String(text.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 按 pgoutput 格式拼装消息体。
+    #[derive(Default)]
+    struct Msg(Vec<u8>);
+
+    impl Msg {
+        fn u8(mut self, v: u8) -> Self {
+            self.0.push(v);
+            self
+        }
+        fn i16(mut self, v: i16) -> Self {
+            self.0.extend_from_slice(&v.to_be_bytes());
+            self
+        }
+        fn u32(mut self, v: u32) -> Self {
+            self.0.extend_from_slice(&v.to_be_bytes());
+            self
+        }
+        fn i64(mut self, v: i64) -> Self {
+            self.0.extend_from_slice(&v.to_be_bytes());
+            self
+        }
+        fn cstr(mut self, v: &str) -> Self {
+            self.0.extend_from_slice(v.as_bytes());
+            self.0.push(0);
+            self
+        }
+        fn text(self, v: &str) -> Self {
+            let mut msg = self.u8(b't').u32(v.len() as u32);
+            msg.0.extend_from_slice(v.as_bytes());
+            msg
+        }
+    }
+
+    /// `identity` 为 `d`（默认，主键 `id` 为键）或 `f`（FULL，所有列均为键）。
+    fn relation_message(identity: u8) -> Vec<u8> {
+        let mut msg = Msg::default()
+            .u8(b'R')
+            .u32(16384)
+            .cstr("public")
+            .cstr("orders")
+            .u8(identity)
+            .i16(4);
+        for (name, oid) in [("id", 23), ("status", 25), ("paid", 16), ("meta", 3802)] {
+            let key = identity == b'f' || name == "id";
+            msg = msg.u8(key.into()).cstr(name).u32(oid).u32(u32::MAX);
+        }
+        msg.0
+    }
+
+    fn relations(identity: u8) -> HashMap<u32, Relation> {
+        let PgOutputMessage::Relation(rel) =
+            PgOutputMessage::parse(&relation_message(identity)).unwrap()
+        else {
+            panic!("relation message");
+        };
+        HashMap::from([(rel.id, rel)])
+    }
+
+    #[test]
+    fn lsn_roundtrips_text_and_checkpoint_file() {
+        let lsn: Lsn = "16/B374D848".parse().unwrap();
+        assert_eq!(lsn, Lsn(0x16_B374_D848));
+        assert_eq!(lsn.to_string(), "16/B374D848");
+        assert_eq!(Lsn(0).to_string(), "0/0");
+        assert!("0/16B3748".parse::<Lsn>().unwrap() < lsn);
+        for bad in ["", "16", "16/", "x/1", "1/2/3", "100000000/0"] {
+            assert!(bad.parse::<Lsn>().is_err(), "{bad}");
+        }
+
+        let checkpoint = LsnCheckpoint { lsn };
+        assert_eq!(
+            serde_json::to_value(checkpoint).unwrap(),
+            json!({"lsn": "16/B374D848"})
+        );
+        let path = std::env::temp_dir()
+            .join(format!("wp_pg_lsn_{}", std::process::id()))
+            .join("src.pg_lsn.json");
+        assert_eq!(LsnCheckpoint::load(&path).unwrap(), None);
+        checkpoint.save(&path).unwrap();
+        assert_eq!(LsnCheckpoint::load(&path).unwrap(), Some(checkpoint));
+        std::fs::write(&path, r#"{"lsn":"oops"}"#).unwrap();
+        assert!(LsnCheckpoint::load(&path).is_err());
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn begin_and_commit_messages_parse() {
+        let begin = Msg::default().u8(b'B').i64(0x100).i64(1_000_000).u32(42).0;
+        assert_eq!(
+            PgOutputMessage::parse(&begin).unwrap(),
+            PgOutputMessage::Begin {
+                final_lsn: Lsn(0x100),
+                commit_ts: 1_000_000,
+                xid: 42
+            }
+        );
+        let commit = Msg::default().u8(b'C').u8(0).i64(0x100).i64(0x128).i64(0).0;
+        assert_eq!(
+            PgOutputMessage::parse(&commit).unwrap(),
+            PgOutputMessage::Commit {
+                commit_lsn: Lsn(0x100),
+                end_lsn: Lsn(0x128)
+            }
+        );
+        assert_eq!(
+            PgOutputMessage::parse(b"Tdata").unwrap(),
+            PgOutputMessage::Other(b'T')
+        );
+        assert!(PgOutputMessage::parse(&begin[..10]).is_err());
+    }
+
+    #[test]
+    fn relation_messages_map_changes_to_records() {
+        let relations = relations(b'f');
+        let rel = &relations[&16384];
+        assert_eq!(
+            (rel.schema.as_str(), rel.table.as_str()),
+            ("public", "orders")
+        );
+        assert_eq!(
+            rel.columns[1],
+            RelationColumn {
+                name: "status".into(),
+                type_oid: 25,
+                key: true,
+            }
+        );
+
+        let update = Msg::default()
+            .u8(b'U')
+            .u32(16384)
+            .u8(b'O')
+            .i16(4)
+            .text("1")
+            .text("new")
+            .text("f")
+            .u8(b'n')
+            .u8(b'N')
+            .i16(4)
+            .text("1")
+            .text("paid")
+            .text("t")
+            .u8(b'u')
+            .0;
+        let message = PgOutputMessage::parse(&update).unwrap();
+        let change = RowChange::from_message(&message, &relations, 1_000_000, Lsn(0x128))
+            .unwrap()
+            .expect("row change");
+        let doc: Value = serde_json::from_str(&change.to_json()).unwrap();
+        assert_eq!(
+            doc,
+            json!({
+                "op": "update", "schema": "public", "table": "orders",
+                "ts": 946_684_801, "lsn": "0/128",
+                "before": {"id": 1, "status": "new", "paid": false, "meta": null},
+                "after": {"id": 1, "status": "paid", "paid": true},
+            })
+        );
+
+        let insert = Msg::default()
+            .u8(b'I')
+            .u32(16384)
+            .u8(b'N')
+            .i16(4)
+            .text("2")
+            .text("new")
+            .text("f")
+            .text(r#"{"a":1}"#)
+            .0;
+        let message = PgOutputMessage::parse(&insert).unwrap();
+        let change = RowChange::from_message(&message, &relations, 0, Lsn(1))
+            .unwrap()
+            .unwrap();
+        assert_eq!(change.op, CdcOp::Insert);
+        assert!(change.before.is_none());
+        assert_eq!(change.after.unwrap()["meta"], json!({"a": 1}));
+
+        // 默认 replica identity：键元组按全部列发送，非键列为 null 占位
+        let keyed = relations(b'd');
+        assert!(keyed[&16384].columns[0].key && !keyed[&16384].columns[1].key);
+        let delete = Msg::default()
+            .u8(b'D')
+            .u32(16384)
+            .u8(b'K')
+            .i16(4)
+            .text("2")
+            .u8(b'n')
+            .u8(b'n')
+            .u8(b'n')
+            .0;
+        let message = PgOutputMessage::parse(&delete).unwrap();
+        let change = RowChange::from_message(&message, &keyed, 0, Lsn(1))
+            .unwrap()
+            .unwrap();
+        assert_eq!(change.op, CdcOp::Delete);
+        assert_eq!(
+            change.before.unwrap(),
+            json!({"id": 2}).as_object().unwrap().clone()
+        );
+        assert!(change.after.is_none());
+
+        let unknown = Msg::default().u8(b'D').u32(1).u8(b'K').i16(0).0;
+        let message = PgOutputMessage::parse(&unknown).unwrap();
+        assert!(RowChange::from_message(&message, &relations, 0, Lsn(1)).is_err());
+    }
+
+    #[test]
+    fn cdc_conf_requires_publication_and_slot() {
+        let params =
+            |v: Value| -> ParamMap { v.as_object().unwrap().clone().into_iter().collect() };
+        let conf = PostgresCdcConf::from_params(&params(json!({
+            "publication": "wp_pub", "slot": "wp_slot", "create_slot": true
+        })))
+        .unwrap();
+        assert_eq!(conf.slot, "wp_slot");
+        assert!(conf.create_slot);
+        assert_eq!((conf.batch, conf.poll_interval_ms), (1000, 1000));
+
+        for bad in [
+            json!({"slot": "wp_slot"}),
+            json!({"publication": "wp_pub"}),
+            json!({"publication": "wp_pub", "slot": "Bad-Slot"}),
+            json!({"publication": "wp_pub", "slot": "s", "create_slot": "yes"}),
+            json!({"publication": "wp_pub", "slot": "s", "batch": 0}),
+        ] {
+            assert!(
+                PostgresCdcConf::from_params(&params(bad.clone())).is_err(),
+                "{bad}"
+            );
+        }
+    }
+}
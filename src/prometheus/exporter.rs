@@ -1,9 +1,13 @@
 #![allow(dead_code)] // Prometheus 导出器目前仅在上游服务注册时使用
 
+use actix_web::dev::ServerHandle;
 use actix_web::{App, HttpRequest, HttpResponse, HttpServer, get};
 use async_trait::async_trait;
 use prometheus::Encoder;
+use std::io;
 use std::sync::Arc;
+use std::thread::JoinHandle;
+use tokio::sync::oneshot;
 use wp_connector_api::{SinkReason, SinkResult};
 use wp_model_core::model::DataRecord;
 use wp_model_core::model::Value;
@@ -12,8 +16,6 @@ use super::metrics::IntoOptField; // 使 .opt() 可见
 use super::metrics::{parse_all_stat, parse_success_stat, receive_data_stat, sink_stat};
 use orion_exp::ValueGet0; // 使 .get_value() 可见
 
-type AnyResult<T> = anyhow::Result<T>;
-
 #[get("/metrics")]
 async fn metrics(_req: HttpRequest) -> HttpResponse {
     let encoder = prometheus::TextEncoder::new();
@@ -25,10 +27,85 @@ async fn metrics(_req: HttpRequest) -> HttpResponse {
     }
 }
 
-#[derive(Clone)]
+/// 各连接器的缓存/lag 状态（见 [`crate::common::stats`]），JSON 数组。
+#[get("/stats")]
+async fn stats(_req: HttpRequest) -> HttpResponse {
+    HttpResponse::Ok().json(crate::common::stats::snapshot_all())
+}
+
 pub(crate) struct PrometheusExporter {
     pub(super) source_key_format: String,
     pub(super) sink_key_format: String,
+    // 指标 HTTP 服务；stop 时关闭，之后可在同一地址重新构建
+    pub(super) server: Option<MetricsServer>,
+}
+
+/// 在独立线程的 runtime 上运行的 `/metrics`、`/stats` HTTP 服务。
+pub(crate) struct MetricsServer {
+    handle: ServerHandle,
+    thread: JoinHandle<()>,
+}
+
+impl MetricsServer {
+    /// 绑定 `endpoint` 并在后台线程启动服务；地址已被占用等绑定失败时返回错误。
+    pub(super) async fn start(endpoint: String) -> SinkResult<Self> {
+        let (tx, rx) = oneshot::channel::<Result<ServerHandle, String>>();
+        let thread = std::thread::Builder::new()
+            .name("prometheus-exporter".into())
+            .spawn(move || {
+                let rt = match tokio::runtime::Runtime::new() {
+                    Ok(rt) => rt,
+                    Err(e) => {
+                        let _ = tx.send(Err(format!("prometheus runtime build fail: {}", e)));
+                        return;
+                    }
+                };
+                rt.block_on(async move {
+                    let server = HttpServer::new(|| App::new().service(metrics).service(stats))
+                        .disable_signals()
+                        .bind(endpoint.as_str());
+                    let server = match server {
+                        Ok(server) => server.run(),
+                        Err(e) => {
+                            let _ = tx.send(Err(bind_error(&endpoint, &e)));
+                            return;
+                        }
+                    };
+                    let _ = tx.send(Ok(server.handle()));
+                    if let Err(e) = server.await {
+                        wp_log::error_data!("prometheus exporter on {} stopped: {}", endpoint, e);
+                    }
+                });
+            })
+            .map_err(|e| {
+                SinkReason::Sink(format!("prometheus exporter thread spawn fail: {}", e))
+            })?;
+        match rx.await {
+            Ok(Ok(handle)) => Ok(Self { handle, thread }),
+            Ok(Err(msg)) => Err(SinkReason::Sink(msg).into()),
+            Err(_) => {
+                Err(SinkReason::Sink("prometheus exporter exited before binding".into()).into())
+            }
+        }
+    }
+
+    /// 优雅关闭服务并等待线程退出，返回后端口已释放。
+    pub(super) async fn stop(self) -> SinkResult<()> {
+        self.handle.stop(true).await;
+        let thread = self.thread;
+        match tokio::task::spawn_blocking(move || thread.join()).await {
+            Ok(Ok(())) => Ok(()),
+            _ => Err(SinkReason::Sink("prometheus exporter thread panicked".into()).into()),
+        }
+    }
+}
+
+fn bind_error(endpoint: &str, err: &io::Error) -> String {
+    if err.kind() == io::ErrorKind::AddrInUse {
+        format!("prometheus.endpoint {} is already in use", endpoint)
+    } else {
+        format!("prometheus.endpoint {} bind fail: {}", endpoint, err)
+    }
 }
 
 #[async_trait]
@@ -63,7 +140,10 @@ impl wp_connector_api::AsyncRecordSink for PrometheusExporter {
 #[async_trait]
 impl wp_connector_api::AsyncCtrl for PrometheusExporter {
     async fn stop(&mut self) -> SinkResult<()> {
-        Ok(())
+        match self.server.take() {
+            Some(server) => server.stop().await,
+            None => Ok(()),
+        }
     }
     async fn reconnect(&mut self) -> SinkResult<()> {
         Ok(())
@@ -101,12 +181,32 @@ impl wp_connector_api::AsyncRawDataSink for PrometheusExporter {
     }
 }
 
-impl PrometheusExporter {
-    pub(super) async fn metrics_service(endpoint: String) -> AnyResult<()> {
-        HttpServer::new(|| App::new().service(metrics))
-            .bind(endpoint.as_str())?
-            .run()
-            .await?;
-        Ok(())
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::{TestRequest, call_and_read_body_json, init_service};
+    use serde_json::Value as JsonValue;
+
+    #[actix_web::test]
+    async fn stats_endpoint_reports_buffered_sink() {
+        let handle = crate::common::stats::register("stats_http_sink", "doris");
+        handle.set_buffered(3);
+        handle.set_in_flight(1);
+
+        let app = init_service(App::new().service(metrics).service(stats)).await;
+        let req = TestRequest::get().uri("/stats").to_request();
+        let body: JsonValue = call_and_read_body_json(&app, req).await;
+        let entry = body
+            .as_array()
+            .expect("array")
+            .iter()
+            .find(|item| item["name"] == "stats_http_sink")
+            .expect("registered sink listed");
+        assert_eq!(entry["kind"], "doris");
+        assert_eq!(entry["buffered_records"], 3);
+        assert_eq!(entry["in_flight"], 1);
+        assert!(entry["last_flush"].is_null());
+        assert!(entry["last_error"].is_null());
+        assert!(entry["source_lag"].is_null());
     }
 }
@@ -8,7 +8,7 @@ use wp_connector_api::{
 };
 
 use super::config::Prometheus;
-use super::exporter::PrometheusExporter;
+use super::exporter::{MetricsServer, PrometheusExporter};
 
 struct PrometheusFactory;
 
@@ -43,16 +43,11 @@ impl SinkFactory for PrometheusFactory {
         if let Some(s) = spec.params.get("sink_key_format").and_then(|v| v.as_str()) {
             conf.sink_key_format = s.to_string();
         }
-        let endpoint = conf.endpoint.clone();
-        std::thread::spawn(move || {
-            let rt = tokio::runtime::Runtime::new().unwrap();
-            rt.block_on(async move {
-                let _ = PrometheusExporter::metrics_service(endpoint).await;
-            });
-        });
+        let server = MetricsServer::start(conf.endpoint.clone()).await?;
         let sink = PrometheusExporter {
             source_key_format: conf.source_key_format.clone(),
             sink_key_format: conf.sink_key_format.clone(),
+            server: Some(server),
         };
         Ok(SinkHandle::new(Box::new(sink)))
     }
@@ -81,3 +76,48 @@ fn prometheus_defaults() -> ParamMap {
     params.insert("sink_key_format".into(), json!("sink"));
     params
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+    use std::path::PathBuf;
+
+    fn spec(endpoint: &str) -> SinkSpec {
+        let mut params = BTreeMap::new();
+        params.insert("endpoint".into(), json!(endpoint));
+        SinkSpec {
+            name: "prometheus_sink".into(),
+            kind: "prometheus".into(),
+            connector_id: String::new(),
+            group: "test".into(),
+            params,
+            filter: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn exporter_stops_and_rebinds_same_port() {
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .and_then(|l| l.local_addr())
+            .expect("free port")
+            .port();
+        let spec = spec(&format!("127.0.0.1:{port}"));
+        let ctx = SinkBuildCtx::new(PathBuf::from("."));
+
+        let mut first = PrometheusFactory
+            .build(&spec, &ctx)
+            .await
+            .expect("first build");
+        let err = PrometheusFactory
+            .build(&spec, &ctx)
+            .await
+            .err()
+            .expect("port still held");
+        assert!(err.to_string().contains("already in use"), "{err}");
+
+        first.sink.stop().await.expect("stop server");
+        let mut second = PrometheusFactory.build(&spec, &ctx).await.expect("rebuild");
+        second.sink.stop().await.expect("stop rebuilt server");
+    }
+}
@@ -0,0 +1,15 @@
+use ::pulsar::{Authentication, Pulsar, TokioExecutor};
+
+use crate::pulsar::config::PulsarConnConf;
+
+/// 按配置建立客户端连接；配置 `token` 时使用 JWT 认证。
+pub(crate) async fn connect(conf: &PulsarConnConf) -> anyhow::Result<Pulsar<TokioExecutor>> {
+    let mut builder = Pulsar::builder(conf.service_url.clone(), TokioExecutor);
+    if let Some(token) = &conf.token {
+        builder = builder.with_auth(Authentication {
+            name: "token".into(),
+            data: token.clone().into_bytes(),
+        });
+    }
+    Ok(builder.build().await?)
+}
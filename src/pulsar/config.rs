@@ -0,0 +1,190 @@
+use serde_json::Value;
+use wp_connector_api::ParamMap;
+
+/// sink 与 source 共用的连接配置。
+#[derive(Clone, PartialEq, Eq)]
+pub struct PulsarConnConf {
+    /// `pulsar://host:6650` 或 `pulsar+ssl://host:6651`
+    pub service_url: String,
+    /// JWT token 认证；未配置时匿名连接
+    pub token: Option<String>,
+}
+
+impl std::fmt::Debug for PulsarConnConf {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PulsarConnConf")
+            .field("service_url", &self.service_url)
+            .field("token", &self.token.as_ref().map(|_| "***"))
+            .finish()
+    }
+}
+
+impl PulsarConnConf {
+    pub fn from_params(params: &ParamMap) -> Result<Self, String> {
+        let service_url =
+            optional_str(params, "service_url")?.ok_or("pulsar.service_url is required")?;
+        if !service_url.starts_with("pulsar://") && !service_url.starts_with("pulsar+ssl://") {
+            return Err("pulsar.service_url must start with pulsar:// or pulsar+ssl://".into());
+        }
+        Ok(Self {
+            service_url,
+            token: optional_str(params, "token")?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PulsarSinkConf {
+    pub topic: String,
+    /// 生产者批量发送的消息数；未配置时逐条发送
+    pub batch_size: Option<u32>,
+    pub fmt: String,
+}
+
+impl PulsarSinkConf {
+    pub fn from_params(params: &ParamMap) -> Result<Self, String> {
+        Ok(Self {
+            topic: optional_str(params, "topic")?.ok_or("pulsar.topic is required")?,
+            batch_size: optional_positive(params, "batch_size")?
+                .map(|n| u32::try_from(n).map_err(|_| "pulsar.batch_size is too large"))
+                .transpose()?,
+            fmt: optional_str(params, "fmt")?.unwrap_or_else(|| "json".into()),
+        })
+    }
+}
+
+/// 订阅类型，对应 Pulsar 的 SubType。
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum PulsarSubType {
+    Exclusive,
+    #[default]
+    Shared,
+    Failover,
+    KeyShared,
+}
+
+impl PulsarSubType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Exclusive => "exclusive",
+            Self::Shared => "shared",
+            Self::Failover => "failover",
+            Self::KeyShared => "key_shared",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PulsarSourceConf {
+    pub topic: String,
+    pub subscription: String,
+    pub sub_type: PulsarSubType,
+    /// 单次 `receive` 最多返回的消息数
+    pub batch: usize,
+}
+
+impl PulsarSourceConf {
+    pub fn from_params(params: &ParamMap) -> Result<Self, String> {
+        let sub_type = match optional_str(params, "sub_type")?.as_deref() {
+            None | Some("shared") => PulsarSubType::Shared,
+            Some("exclusive") => PulsarSubType::Exclusive,
+            Some("failover") => PulsarSubType::Failover,
+            Some("key_shared") => PulsarSubType::KeyShared,
+            Some(_) => {
+                return Err(
+                    "pulsar.sub_type must be one of: exclusive, shared, failover, key_shared"
+                        .into(),
+                );
+            }
+        };
+        Ok(Self {
+            topic: optional_str(params, "topic")?.ok_or("pulsar.topic is required")?,
+            subscription: optional_str(params, "subscription")?
+                .ok_or("pulsar.subscription is required")?,
+            sub_type,
+            batch: optional_positive(params, "batch")?.unwrap_or(100) as usize,
+        })
+    }
+}
+
+fn optional_str(params: &ParamMap, key: &str) -> Result<Option<String>, String> {
+    match params.get(key) {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::String(s)) if !s.trim().is_empty() => Ok(Some(s.trim().to_string())),
+        Some(_) => Err(format!("pulsar.{key} must be a non-empty string")),
+    }
+}
+
+fn optional_positive(params: &ParamMap, key: &str) -> Result<Option<u64>, String> {
+    match params.get(key) {
+        None | Some(Value::Null) => Ok(None),
+        Some(v) => match v.as_u64() {
+            Some(n) if n > 0 => Ok(Some(n)),
+            _ => Err(format!("pulsar.{key} must be a positive integer")),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn params(pairs: &[(&str, Value)]) -> ParamMap {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn conn_conf_requires_pulsar_url_and_hides_token() {
+        let conf = PulsarConnConf::from_params(&params(&[
+            ("service_url", json!("pulsar://127.0.0.1:6650")),
+            ("token", json!("jwt-secret")),
+        ]))
+        .unwrap();
+        assert_eq!(conf.token.as_deref(), Some("jwt-secret"));
+        assert!(!format!("{conf:?}").contains("jwt-secret"));
+
+        for url in [json!("http://127.0.0.1:8080"), json!(""), json!(6650)] {
+            assert!(PulsarConnConf::from_params(&params(&[("service_url", url)])).is_err());
+        }
+    }
+
+    #[test]
+    fn sink_and_source_conf_validate_params() {
+        let conf = PulsarSinkConf::from_params(&params(&[
+            ("topic", json!("persistent://public/default/logs")),
+            ("batch_size", json!(500)),
+        ]))
+        .unwrap();
+        assert_eq!(conf.batch_size, Some(500));
+        assert_eq!(conf.fmt, "json");
+        assert!(PulsarSinkConf::from_params(&params(&[])).is_err());
+        assert!(
+            PulsarSinkConf::from_params(&params(&[
+                ("topic", json!("logs")),
+                ("batch_size", json!(0))
+            ]))
+            .is_err()
+        );
+
+        let conf = PulsarSourceConf::from_params(&params(&[
+            ("topic", json!("logs")),
+            ("subscription", json!("wp")),
+        ]))
+        .unwrap();
+        assert_eq!(conf.sub_type, PulsarSubType::Shared);
+        assert_eq!(conf.batch, 100);
+        assert!(PulsarSourceConf::from_params(&params(&[("topic", json!("logs"))])).is_err());
+        assert!(
+            PulsarSourceConf::from_params(&params(&[
+                ("topic", json!("logs")),
+                ("subscription", json!("wp")),
+                ("sub_type", json!("broadcast")),
+            ]))
+            .is_err()
+        );
+    }
+}
@@ -0,0 +1,173 @@
+use async_trait::async_trait;
+use serde_json::json;
+use wp_connector_api::{
+    ConnectorDef, ConnectorScope, ParamMap, SinkBuildCtx, SinkDefProvider, SinkError, SinkFactory,
+    SinkHandle, SinkReason, SinkResult, SinkSpec, SourceDefProvider, SourceHandle, SourceMeta,
+    SourceReason, SourceResult, SourceSvcIns, Tags,
+};
+
+use super::config::{PulsarConnConf, PulsarSinkConf, PulsarSourceConf};
+use super::sink::PulsarSink;
+use super::source::PulsarSource;
+use crate::WP_SRC_VAL;
+use crate::common::enrich::{EnrichConf, EnrichSink};
+use crate::common::secret;
+use crate::common::stats;
+use crate::common::transform::FieldTransforms;
+
+pub struct PulsarSourceFactory;
+
+#[async_trait]
+impl wp_connector_api::SourceFactory for PulsarSourceFactory {
+    fn kind(&self) -> &'static str {
+        "pulsar"
+    }
+
+    fn validate_spec(&self, spec: &wp_connector_api::SourceSpec) -> SourceResult<()> {
+        let spec = &secret::resolve_source_spec(spec)?;
+        PulsarConnConf::from_params(&spec.params).map_err(SourceReason::Other)?;
+        PulsarSourceConf::from_params(&spec.params).map_err(SourceReason::Other)?;
+        Ok(())
+    }
+
+    async fn build(
+        &self,
+        spec: &wp_connector_api::SourceSpec,
+        _ctx: &wp_connector_api::SourceBuildCtx,
+    ) -> SourceResult<SourceSvcIns> {
+        let spec = &secret::resolve_source_spec(spec)?;
+        let conn = PulsarConnConf::from_params(&spec.params).map_err(SourceReason::Other)?;
+        let conf = PulsarSourceConf::from_params(&spec.params).map_err(SourceReason::Other)?;
+        let mut meta_tags = Tags::from_parse(&spec.tags);
+        meta_tags.set(WP_SRC_VAL, spec.kind.clone());
+        let source = PulsarSource::new(spec.name.clone(), meta_tags.clone(), &conn, &conf)
+            .await
+            .map_err(|err| SourceReason::Other(format!("pulsar subscribe fail: {err}")))?;
+
+        let mut meta = SourceMeta::new(spec.name.clone(), spec.kind.clone());
+        meta.tags = meta_tags;
+        let handle = SourceHandle::new(Box::new(source), meta);
+        Ok(SourceSvcIns::new().with_sources(vec![handle]))
+    }
+}
+
+impl SourceDefProvider for PulsarSourceFactory {
+    fn source_def(&self) -> ConnectorDef {
+        ConnectorDef {
+            id: "pulsar_src".into(),
+            kind: self.kind().into(),
+            scope: ConnectorScope::Source,
+            allow_override: vec![
+                "service_url",
+                "topic",
+                "subscription",
+                "sub_type",
+                "batch",
+                "secret_ref",
+            ]
+            .into_iter()
+            .map(str::to_string)
+            .collect(),
+            default_params: pulsar_source_defaults(),
+            origin: Some("wp-connectors:pulsar_source".into()),
+        }
+    }
+}
+
+pub struct PulsarSinkFactory;
+
+#[async_trait]
+impl SinkFactory for PulsarSinkFactory {
+    fn kind(&self) -> &'static str {
+        "pulsar"
+    }
+
+    fn validate_spec(&self, spec: &SinkSpec) -> SinkResult<()> {
+        let spec = &secret::resolve_sink_spec(spec)?;
+        PulsarConnConf::from_params(&spec.params).map_err(SinkReason::sink)?;
+        PulsarSinkConf::from_params(&spec.params).map_err(SinkReason::sink)?;
+        EnrichConf::from_params(&spec.params)?;
+        FieldTransforms::from_params(&spec.params)?;
+        Ok(())
+    }
+
+    async fn build(&self, spec: &SinkSpec, _ctx: &SinkBuildCtx) -> SinkResult<SinkHandle> {
+        let spec = &secret::resolve_sink_spec(spec)?;
+        let conn = PulsarConnConf::from_params(&spec.params).map_err(SinkReason::sink)?;
+        let conf = PulsarSinkConf::from_params(&spec.params).map_err(SinkReason::sink)?;
+        let sink = PulsarSink::from_conf(&conn, &conf)
+            .await
+            .map_err(|err| {
+                SinkError::from(SinkReason::sink(format!(
+                    "build pulsar producer failed: {err}"
+                )))
+            })?
+            .with_stats(stats::register(&spec.name, self.kind()));
+        let enrich = EnrichConf::from_params(&spec.params)?;
+        let transforms = FieldTransforms::from_params(&spec.params)?;
+        let sink = EnrichSink::new(sink, enrich).with_transforms(transforms);
+        Ok(SinkHandle::new(Box::new(sink)))
+    }
+}
+
+impl SinkDefProvider for PulsarSinkFactory {
+    fn sink_def(&self) -> ConnectorDef {
+        ConnectorDef {
+            id: "pulsar_sink".into(),
+            kind: self.kind().into(),
+            scope: ConnectorScope::Sink,
+            allow_override: vec![
+                "service_url",
+                "topic",
+                "batch_size",
+                "fmt",
+                "secret_ref",
+                "enrich",
+                "enrich_overwrite",
+                "field_transforms",
+            ]
+            .into_iter()
+            .map(str::to_string)
+            .collect(),
+            default_params: pulsar_sink_defaults(),
+            origin: Some("wp-connectors:pulsar_sink".into()),
+        }
+    }
+}
+
+fn pulsar_source_defaults() -> ParamMap {
+    let mut params = ParamMap::new();
+    params.insert("service_url".into(), json!("pulsar://127.0.0.1:6650"));
+    params.insert(
+        "topic".into(),
+        json!("persistent://public/default/wp-events"),
+    );
+    params.insert("subscription".into(), json!("wp-connectors"));
+    params.insert("sub_type".into(), json!("shared"));
+    params.insert("batch".into(), json!(100));
+    params
+}
+
+fn pulsar_sink_defaults() -> ParamMap {
+    let mut params = ParamMap::new();
+    params.insert("service_url".into(), json!("pulsar://127.0.0.1:6650"));
+    params.insert(
+        "topic".into(),
+        json!("persistent://public/default/wp-events"),
+    );
+    params.insert("fmt".into(), json!("json"));
+    params
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_are_valid() {
+        PulsarConnConf::from_params(&pulsar_source_defaults()).unwrap();
+        PulsarSourceConf::from_params(&pulsar_source_defaults()).unwrap();
+        let conf = PulsarSinkConf::from_params(&pulsar_sink_defaults()).unwrap();
+        assert_eq!(conf.batch_size, None);
+    }
+}
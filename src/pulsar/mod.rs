@@ -0,0 +1,19 @@
+//! Apache Pulsar：sink 与 source。
+//!
+//! 模块划分：
+//! - config：连接、sink、source 配置解析
+//! - client：建立客户端连接（可选 token 认证）
+//! - sink：PulsarSink（按 TextFmt 渲染后发送，可选生产者批量）
+//! - source：PulsarSource（订阅、延迟确认）
+//! - factory：Sink/Source 工厂
+
+mod client;
+pub mod config;
+mod factory;
+mod sink;
+mod source;
+
+pub use config::{PulsarConnConf, PulsarSinkConf, PulsarSourceConf, PulsarSubType};
+pub use factory::{PulsarSinkFactory, PulsarSourceFactory};
+pub use sink::PulsarSink;
+pub use source::PulsarSource;
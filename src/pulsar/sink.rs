@@ -0,0 +1,144 @@
+use std::sync::Arc;
+
+use ::pulsar::producer::{ProducerOptions, SendFuture};
+use ::pulsar::{Producer, TokioExecutor};
+use async_trait::async_trait;
+use wp_connector_api::{
+    AsyncCtrl, AsyncRawDataSink, AsyncRecordSink, SinkError, SinkReason, SinkResult,
+};
+use wp_data_fmt::{DataFormat, FormatType};
+use wp_model_core::model::{DataRecord, fmt_def::TextFmt};
+
+use crate::common::stats::StatsHandle;
+use crate::pulsar::client::connect;
+use crate::pulsar::config::{PulsarConnConf, PulsarSinkConf};
+
+/// 把记录按 `fmt` 渲染后发送到 topic；每次调用返回前等待本批消息的发送回执。
+pub struct PulsarSink {
+    producer: Producer<TokioExecutor>,
+    fmt: TextFmt,
+    batching: bool,
+    topic: String,
+    /// `/stats` 自省状态（最近发送结果）
+    stats: StatsHandle,
+}
+
+impl PulsarSink {
+    pub async fn from_conf(conn: &PulsarConnConf, conf: &PulsarSinkConf) -> anyhow::Result<Self> {
+        let client = connect(conn).await?;
+        let producer = client
+            .producer()
+            .with_topic(conf.topic.clone())
+            .with_options(ProducerOptions {
+                batch_size: conf.batch_size,
+                ..Default::default()
+            })
+            .build()
+            .await?;
+        Ok(Self {
+            producer,
+            fmt: TextFmt::from(conf.fmt.as_str()),
+            batching: conf.batch_size.is_some(),
+            topic: conf.topic.clone(),
+            stats: StatsHandle::detached(&conf.topic, "pulsar"),
+        })
+    }
+
+    /// 使用外部登记的状态句柄（见 [`crate::common::stats::register`]），
+    /// 以 topic 作为指标的写入目标。
+    pub fn with_stats(mut self, stats: StatsHandle) -> Self {
+        self.stats = stats.with_destination(self.topic.as_str());
+        self
+    }
+
+    async fn send_all(&mut self, payloads: Vec<Vec<u8>>) -> SinkResult<()> {
+        let result = self.send_payloads(payloads).await;
+        self.stats.record_flush(&result);
+        result
+    }
+
+    async fn send_payloads(&mut self, payloads: Vec<Vec<u8>>) -> SinkResult<()> {
+        let mut receipts: Vec<SendFuture> = Vec::with_capacity(payloads.len());
+        for payload in payloads {
+            receipts.push(
+                self.producer
+                    .send_non_blocking(payload)
+                    .await
+                    .map_err(|e| send_error("send", e))?,
+            );
+        }
+        if self.batching {
+            // 不足一批的消息立即发出，避免回执等待批次填满
+            self.producer
+                .send_batch()
+                .await
+                .map_err(|e| send_error("flush batch", e))?;
+        }
+        for receipt in receipts {
+            receipt.await.map_err(|e| send_error("receipt", e))?;
+        }
+        Ok(())
+    }
+}
+
+fn send_error(what: &str, err: impl std::fmt::Display) -> SinkError {
+    SinkError::from(SinkReason::sink(format!("pulsar {what} failed: {err}")))
+}
+
+#[async_trait]
+impl AsyncCtrl for PulsarSink {
+    async fn stop(&mut self) -> SinkResult<()> {
+        if self.batching {
+            self.producer
+                .send_batch()
+                .await
+                .map_err(|e| send_error("flush batch", e))?;
+        }
+        self.producer
+            .close()
+            .await
+            .map_err(|e| send_error("close producer", e))
+    }
+
+    async fn reconnect(&mut self) -> SinkResult<()> {
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl AsyncRecordSink for PulsarSink {
+    async fn sink_record(&mut self, data: &DataRecord) -> SinkResult<()> {
+        let line = FormatType::from(&self.fmt).format_record(data);
+        self.send_all(vec![line.into_bytes()]).await
+    }
+
+    async fn sink_records(&mut self, data: Vec<Arc<DataRecord>>) -> SinkResult<()> {
+        let fmt = FormatType::from(&self.fmt);
+        let payloads = data
+            .iter()
+            .map(|record| fmt.format_record(record.as_ref()).into_bytes())
+            .collect();
+        self.send_all(payloads).await
+    }
+}
+
+#[async_trait]
+impl AsyncRawDataSink for PulsarSink {
+    async fn sink_str(&mut self, data: &str) -> SinkResult<()> {
+        self.send_all(vec![data.as_bytes().to_vec()]).await
+    }
+
+    async fn sink_bytes(&mut self, data: &[u8]) -> SinkResult<()> {
+        self.send_all(vec![data.to_vec()]).await
+    }
+
+    async fn sink_str_batch(&mut self, data: Vec<&str>) -> SinkResult<()> {
+        self.send_all(data.into_iter().map(|s| s.as_bytes().to_vec()).collect())
+            .await
+    }
+
+    async fn sink_bytes_batch(&mut self, data: Vec<&[u8]>) -> SinkResult<()> {
+        self.send_all(data.into_iter().map(<[u8]>::to_vec).collect())
+            .await
+    }
+}
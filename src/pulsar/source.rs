@@ -0,0 +1,147 @@
+//! Pulsar source：以配置的订阅类型消费 topic，每条消息作为一个 `RawData` 事件输出，
+//! `WP_SRC_VAL` 标签为消息所属 topic。
+//!
+//! 消息在下一次 `receive` 时确认（ack），即确认已交付给下游的上一批；
+//! 进程在确认前退出时，未确认的消息由 broker 重新投递（至少一次）。
+//! 批内续取出错时先交付已取到的消息，错误在下一次 `receive` 返回。
+
+use ::pulsar::consumer::Message;
+use ::pulsar::{Consumer, SubType, TokioExecutor};
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures_util::TryStreamExt;
+use wp_connector_api::{
+    DataSource, SourceBatch, SourceError, SourceEvent, SourceReason, SourceResult, Tags,
+};
+use wp_parse_api::RawData;
+
+use crate::WP_SRC_VAL;
+use crate::common::pull_batch::{MessagePull, PullBatcher};
+use crate::pulsar::client::connect;
+use crate::pulsar::config::{PulsarConnConf, PulsarSourceConf, PulsarSubType};
+
+struct Inbox {
+    consumer: Consumer<Vec<u8>, TokioExecutor>,
+    /// 已交付、待确认的消息
+    unacked: Vec<Message<Vec<u8>>>,
+}
+
+pub struct PulsarSource {
+    key: String,
+    tags: Tags,
+    inbox: Inbox,
+    batcher: PullBatcher,
+    event_seq: u64,
+}
+
+impl PulsarSource {
+    pub async fn new(
+        key: String,
+        tags: Tags,
+        conn: &PulsarConnConf,
+        conf: &PulsarSourceConf,
+    ) -> anyhow::Result<Self> {
+        let client = connect(conn).await?;
+        let consumer = client
+            .consumer()
+            .with_topic(conf.topic.clone())
+            .with_subscription(conf.subscription.clone())
+            .with_subscription_type(match conf.sub_type {
+                PulsarSubType::Exclusive => SubType::Exclusive,
+                PulsarSubType::Shared => SubType::Shared,
+                PulsarSubType::Failover => SubType::Failover,
+                PulsarSubType::KeyShared => SubType::KeyShared,
+            })
+            .with_consumer_name(key.clone())
+            .build()
+            .await?;
+        wp_log::info_data!(
+            "[pulsar] {} topic: {}, subscription: {} ({})",
+            key,
+            conf.topic,
+            conf.subscription,
+            conf.sub_type.as_str()
+        );
+        Ok(Self {
+            key,
+            tags,
+            inbox: Inbox {
+                consumer,
+                unacked: Vec::new(),
+            },
+            batcher: PullBatcher::new(conf.batch),
+            event_seq: 0,
+        })
+    }
+
+    pub fn identifier(&self) -> &str {
+        &self.key
+    }
+
+    fn create_event(&mut self, topic: String, payload: Bytes) -> SourceEvent {
+        let mut tags = self.tags.clone();
+        tags.set(WP_SRC_VAL, topic);
+        self.event_seq = self.event_seq.wrapping_add(1);
+        SourceEvent::new(
+            self.event_seq,
+            self.key.clone(),
+            RawData::Bytes(payload),
+            tags.into(),
+        )
+    }
+}
+
+impl Inbox {
+    /// 确认上一批已交付的消息。
+    async fn ack_delivered(&mut self) -> SourceResult<()> {
+        for msg in std::mem::take(&mut self.unacked) {
+            self.consumer
+                .ack(&msg)
+                .await
+                .map_err(|e| stream_error("ack message", e))?;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl MessagePull for Inbox {
+    async fn pull(&mut self) -> SourceResult<(String, Bytes)> {
+        let msg = self
+            .consumer
+            .try_next()
+            .await
+            .map_err(|e| stream_error("receive", e))?
+            .ok_or_else(|| stream_error("receive", "consumer stream closed"))?;
+        let out = (msg.topic.clone(), Bytes::from(msg.payload.data.clone()));
+        self.unacked.push(msg);
+        Ok(out)
+    }
+}
+
+fn stream_error(what: &str, err: impl std::fmt::Display) -> SourceError {
+    SourceError::from(SourceReason::SupplierError(format!(
+        "pulsar {what} fail: {err}"
+    )))
+}
+
+#[async_trait]
+impl DataSource for PulsarSource {
+    /// 阻塞等待第一条消息，再取走已到达的消息，最多 `batch` 条。
+    async fn receive(&mut self) -> SourceResult<SourceBatch> {
+        self.inbox.ack_delivered().await?;
+        let messages = self.batcher.next_batch(&mut self.inbox).await?;
+        Ok(messages
+            .into_iter()
+            .map(|(topic, payload)| self.create_event(topic, payload))
+            .collect())
+    }
+
+    fn try_receive(&mut self) -> Option<SourceBatch> {
+        None
+    }
+
+    fn identifier(&self) -> String {
+        self.key.clone()
+    }
+}
@@ -0,0 +1,50 @@
+use educe::Educe;
+use serde::Deserialize;
+use serde::Serialize;
+
+/// 记录的写入方式。
+#[derive(Debug, Default, Deserialize, Serialize, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum RedisMode {
+    /// `LPUSH key payload`
+    #[default]
+    List,
+    /// `XADD key * <stream_field> payload`
+    Stream,
+    /// `SET key payload [EX ttl]`
+    Set,
+}
+
+impl RedisMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::List => "list",
+            Self::Stream => "stream",
+            Self::Set => "set",
+        }
+    }
+}
+
+#[derive(Educe, Deserialize, Serialize, PartialEq, Clone)]
+#[educe(Debug, Default)]
+pub struct RedisSinkConfig {
+    #[educe(Default = "redis://127.0.0.1:6379")]
+    pub url: String,
+    #[serde(default)]
+    pub mode: RedisMode,
+    /// 固定写入的键；与 `key_field` 二选一
+    pub key: Option<String>,
+    /// 取记录中该字段的值作为键；`set` 模式必填。批量写入时缺少该字段的记录被丢弃
+    pub key_field: Option<String>,
+    /// `set` 模式下键的过期时间（秒）；未配置时不过期
+    pub ttl_secs: Option<u64>,
+    /// `stream` 模式下负载所在的条目字段名
+    #[educe(Default = "data")]
+    pub stream_field: String,
+    /// 记录的渲染格式
+    #[educe(Default = "json")]
+    pub fmt: String,
+    /// 连接池大小
+    #[educe(Default = 4)]
+    pub pool_size: usize,
+}
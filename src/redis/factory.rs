@@ -0,0 +1,228 @@
+use async_trait::async_trait;
+use deadpool_redis::{PoolConfig, Runtime};
+use serde_json::{Value, json};
+use wp_connector_api::{
+    ConnectorDef, ConnectorScope, ParamMap, SinkBuildCtx, SinkDefProvider, SinkError, SinkFactory,
+    SinkHandle, SinkReason, SinkResult, SinkSpec,
+};
+use wp_model_core::model::fmt_def::TextFmt;
+
+use super::config::{RedisMode, RedisSinkConfig};
+use super::sink::RedisSink;
+use crate::common::enrich::{EnrichConf, EnrichSink};
+use crate::common::retry::{self, RetryingSink};
+use crate::common::stats;
+use crate::common::transform::FieldTransforms;
+
+pub struct RedisSinkFactory;
+
+#[async_trait]
+impl SinkFactory for RedisSinkFactory {
+    fn kind(&self) -> &'static str {
+        "redis"
+    }
+
+    fn validate_spec(&self, spec: &SinkSpec) -> SinkResult<()> {
+        build_conf(&spec.params)?;
+        EnrichConf::from_params(&spec.params)?;
+        retry::validate_params(&spec.params)?;
+        FieldTransforms::from_params(&spec.params)?;
+        Ok(())
+    }
+
+    async fn build(&self, spec: &SinkSpec, _ctx: &SinkBuildCtx) -> SinkResult<SinkHandle> {
+        let conf = build_conf(&spec.params)?;
+        let fmt = TextFmt::from(conf.fmt.as_str());
+        let mut pool_conf = deadpool_redis::Config::from_url(conf.url.clone());
+        pool_conf.pool = Some(PoolConfig::new(conf.pool_size));
+        let pool = pool_conf
+            .create_pool(Some(Runtime::Tokio1))
+            .map_err(|err| {
+                SinkError::from(SinkReason::sink(format!("build redis pool failed: {err}")))
+            })?;
+        let sink =
+            RedisSink::new(pool, &conf, fmt).with_stats(stats::register(&spec.name, self.kind()));
+        sink.ping().await?;
+        let enrich = EnrichConf::from_params(&spec.params)?;
+        let transforms = FieldTransforms::from_params(&spec.params)?;
+        let sink = RetryingSink::from_spec(sink, spec).await?;
+        let sink = EnrichSink::new(sink, enrich).with_transforms(transforms);
+        Ok(SinkHandle::new(Box::new(sink)))
+    }
+}
+
+impl SinkDefProvider for RedisSinkFactory {
+    fn sink_def(&self) -> ConnectorDef {
+        ConnectorDef {
+            id: "redis_sink".into(),
+            kind: self.kind().into(),
+            scope: ConnectorScope::Sink,
+            allow_override: vec![
+                "url",
+                "mode",
+                "key",
+                "key_field",
+                "ttl_secs",
+                "stream_field",
+                "fmt",
+                "pool_size",
+                "enrich",
+                "enrich_overwrite",
+                "field_transforms",
+                "retry",
+                "write_deadline_ms",
+                "quarantine",
+            ]
+            .into_iter()
+            .map(str::to_string)
+            .collect(),
+            default_params: redis_defaults(),
+            origin: Some("wp-connectors:redis_sink".into()),
+        }
+    }
+}
+
+fn redis_defaults() -> ParamMap {
+    let defaults = RedisSinkConfig::default();
+    let mut params = ParamMap::new();
+    params.insert("url".into(), json!(defaults.url));
+    params.insert("mode".into(), json!(defaults.mode.as_str()));
+    params.insert("fmt".into(), json!(defaults.fmt));
+    params.insert("pool_size".into(), json!(defaults.pool_size));
+    params
+}
+
+/// 由参数构建配置：`url` 必填，`key` 与 `key_field` 恰配置一个，`set` 模式须用 `key_field`。
+fn build_conf(params: &ParamMap) -> SinkResult<RedisSinkConfig> {
+    let mut conf = RedisSinkConfig::default();
+    let url = params.get("url").and_then(|v| v.as_str()).unwrap_or("");
+    if url.trim().is_empty() {
+        return Err(SinkReason::sink("redis.url must not be empty").into());
+    }
+    conf.url = url.trim().to_string();
+    if let Some(v) = params.get("mode").filter(|v| !v.is_null()) {
+        conf.mode = match v.as_str() {
+            Some("list") => RedisMode::List,
+            Some("stream") => RedisMode::Stream,
+            Some("set") => RedisMode::Set,
+            _ => {
+                return Err(
+                    SinkReason::sink("redis.mode must be one of: list, stream, set").into(),
+                );
+            }
+        };
+    }
+    conf.key = opt_str(params, "key")?;
+    conf.key_field = opt_str(params, "key_field")?;
+    match (&conf.key, &conf.key_field) {
+        (None, None) => {
+            return Err(SinkReason::sink("redis.key or redis.key_field is required").into());
+        }
+        (Some(_), Some(_)) => {
+            return Err(
+                SinkReason::sink("redis.key cannot be combined with redis.key_field").into(),
+            );
+        }
+        (Some(_), None) if conf.mode == RedisMode::Set => {
+            return Err(SinkReason::sink("redis.mode=set requires redis.key_field").into());
+        }
+        _ => {}
+    }
+    conf.ttl_secs = opt_positive(params, "ttl_secs")?;
+    if conf.ttl_secs.is_some() && conf.mode != RedisMode::Set {
+        return Err(SinkReason::sink("redis.ttl_secs only applies to redis.mode=set").into());
+    }
+    if let Some(field) = opt_str(params, "stream_field")? {
+        conf.stream_field = field;
+    }
+    if let Some(s) = params.get("fmt").and_then(|v| v.as_str()) {
+        conf.fmt = s.to_string();
+    }
+    if let Some(size) = opt_positive(params, "pool_size")? {
+        conf.pool_size = size as usize;
+    }
+    Ok(conf)
+}
+
+/// 可选的非空字符串参数。
+fn opt_str(params: &ParamMap, key: &str) -> SinkResult<Option<String>> {
+    match params.get(key) {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::String(s)) if !s.trim().is_empty() => Ok(Some(s.trim().to_string())),
+        Some(_) => Err(SinkReason::sink(format!("redis.{key} must be a non-empty string")).into()),
+    }
+}
+
+/// 可选的正整数参数。
+fn opt_positive(params: &ParamMap, key: &str) -> SinkResult<Option<u64>> {
+    match params.get(key) {
+        None | Some(Value::Null) => Ok(None),
+        Some(v) => match v.as_u64() {
+            Some(n) if n > 0 => Ok(Some(n)),
+            _ => Err(SinkReason::sink(format!("redis.{key} must be a positive integer")).into()),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(key: &str) -> ParamMap {
+        let mut params = redis_defaults();
+        params.insert("key".into(), json!(key));
+        params
+    }
+
+    #[test]
+    fn build_conf_requires_url_and_key() {
+        let conf = build_conf(&params("events")).expect("valid");
+        assert_eq!(conf.mode, RedisMode::List);
+        assert_eq!(conf.key.as_deref(), Some("events"));
+        assert_eq!(conf.pool_size, 4);
+
+        let mut p = params("events");
+        p.insert("url".into(), json!(" "));
+        assert!(build_conf(&p).is_err());
+        let mut p = params("events");
+        p.remove("key");
+        assert!(build_conf(&p).is_err());
+        p.insert("key".into(), json!(""));
+        assert!(build_conf(&p).is_err());
+        p.insert("key".into(), json!("events"));
+        p.insert("key_field".into(), json!("user"));
+        assert!(build_conf(&p).is_err(), "key and key_field are exclusive");
+    }
+
+    #[test]
+    fn build_conf_checks_mode_specific_options() {
+        let mut p = params("events");
+        p.insert("mode".into(), json!("stream"));
+        p.insert("stream_field".into(), json!("payload"));
+        let conf = build_conf(&p).expect("stream");
+        assert_eq!(conf.mode, RedisMode::Stream);
+        assert_eq!(conf.stream_field, "payload");
+
+        p.insert("ttl_secs".into(), json!(30));
+        assert!(build_conf(&p).is_err(), "ttl only for set");
+
+        p.insert("mode".into(), json!("set"));
+        assert!(build_conf(&p).is_err(), "set needs key_field");
+        p.remove("key");
+        p.insert("key_field".into(), json!("session_id"));
+        let conf = build_conf(&p).expect("set");
+        assert_eq!(conf.ttl_secs, Some(30));
+        assert_eq!(conf.key_field.as_deref(), Some("session_id"));
+
+        for bad in [json!(0), json!(-1), json!("30")] {
+            p.insert("ttl_secs".into(), bad);
+            assert!(build_conf(&p).is_err());
+        }
+        p.remove("ttl_secs");
+        p.insert("mode".into(), json!("hash"));
+        assert!(build_conf(&p).is_err());
+        p.insert("mode".into(), json!("set"));
+        p.insert("pool_size".into(), json!(0));
+        assert!(build_conf(&p).is_err());
+    }
+}
@@ -0,0 +1,8 @@
+//! Redis sink：按 `mode` 将记录写入列表（LPUSH）、流（XADD）或带 TTL 的键（SET）。
+
+pub mod config;
+mod factory;
+mod sink;
+
+pub use config::{RedisMode, RedisSinkConfig};
+pub use factory::RedisSinkFactory;
@@ -0,0 +1,367 @@
+use std::sync::Arc;
+
+use ::redis::{ErrorKind, Pipeline, RedisError, ToRedisArgs};
+use async_trait::async_trait;
+use deadpool_redis::Pool;
+use wp_connector_api::{
+    AsyncCtrl, AsyncRawDataSink, AsyncRecordSink, SinkError, SinkReason, SinkResult,
+};
+use wp_data_fmt::{DataFormat, FormatType};
+use wp_model_core::model::{DataRecord, fmt_def::TextFmt};
+
+use crate::common::retry::{self, PendingFlush};
+use crate::common::stats::StatsHandle;
+use crate::redis::config::{RedisMode, RedisSinkConfig};
+
+/// 记录的目标键：固定键或取自记录字段。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum RedisKey {
+    Fixed(String),
+    Field(String),
+}
+
+impl RedisKey {
+    fn label(&self) -> &str {
+        match self {
+            RedisKey::Fixed(key) | RedisKey::Field(key) => key,
+        }
+    }
+}
+
+/// 每次写入调用合并为一个 pipeline 发送，连接取自连接池。
+pub(crate) struct RedisSink {
+    pool: Pool,
+    mode: RedisMode,
+    key: RedisKey,
+    ttl_secs: Option<u64>,
+    stream_field: String,
+    fmt: TextFmt,
+    /// 累计处理完成的记录数（含因缺少键字段而丢弃的记录）
+    accepted: u64,
+    /// `/stats` 自省状态（最近写入与错误）
+    stats: StatsHandle,
+}
+
+impl RedisSink {
+    /// `conf` 须已通过工厂校验：`key` 与 `key_field` 恰有一个。
+    pub(crate) fn new(pool: Pool, conf: &RedisSinkConfig, fmt: TextFmt) -> Self {
+        let key = match (&conf.key_field, &conf.key) {
+            (Some(field), _) => RedisKey::Field(field.clone()),
+            (None, key) => RedisKey::Fixed(key.clone().unwrap_or_default()),
+        };
+        Self {
+            pool,
+            mode: conf.mode,
+            stats: StatsHandle::detached(key.label(), "redis"),
+            key,
+            ttl_secs: conf.ttl_secs,
+            stream_field: conf.stream_field.clone(),
+            fmt,
+            accepted: 0,
+        }
+    }
+
+    /// 使用外部登记的状态句柄，以固定键（或键字段名）作为指标的写入目标。
+    pub(crate) fn with_stats(mut self, stats: StatsHandle) -> Self {
+        self.stats = stats.with_destination(self.key.label());
+        self
+    }
+
+    fn record_key(&self, data: &DataRecord) -> SinkResult<String> {
+        match &self.key {
+            RedisKey::Fixed(key) => Ok(key.clone()),
+            RedisKey::Field(field) => data
+                .get2(field)
+                .map(|f| f.get_value().to_string())
+                .filter(|v| !v.is_empty())
+                .ok_or_else(|| {
+                    SinkError::from(SinkReason::sink(format!(
+                        "redis key field '{field}' missing or empty in record"
+                    )))
+                }),
+        }
+    }
+
+    /// 原始数据没有记录字段可取，仅支持固定键。
+    fn raw_key(&self) -> SinkResult<&str> {
+        match &self.key {
+            RedisKey::Fixed(key) => Ok(key),
+            RedisKey::Field(_) => Err(SinkReason::sink(
+                "redis raw data requires a fixed key instead of key_field",
+            )
+            .into()),
+        }
+    }
+
+    /// 按 `mode` 向 pipeline 追加一条写入命令。
+    fn append<T: ToRedisArgs>(&self, pipe: &mut Pipeline, key: &str, payload: T) {
+        let cmd = match self.mode {
+            RedisMode::List => pipe.cmd("LPUSH").arg(key).arg(payload),
+            RedisMode::Stream => pipe
+                .cmd("XADD")
+                .arg(key)
+                .arg("*")
+                .arg(&self.stream_field)
+                .arg(payload),
+            RedisMode::Set => {
+                let cmd = pipe.cmd("SET").arg(key).arg(payload);
+                match self.ttl_secs {
+                    Some(ttl) => cmd.arg("EX").arg(ttl),
+                    None => cmd,
+                }
+            }
+        };
+        cmd.ignore();
+    }
+
+    fn append_record(&self, pipe: &mut Pipeline, data: &DataRecord) -> SinkResult<()> {
+        let key = self.record_key(data)?;
+        let payload = FormatType::from(&self.fmt).format_record(data);
+        self.append(pipe, &key, payload);
+        Ok(())
+    }
+
+    /// 为一批记录构造 pipeline；取不到键的记录记日志后丢弃，不影响同批其他记录。
+    ///
+    /// # return
+    /// * `(Pipeline, usize)` - pipeline 与其中的命令数。
+    fn batch_pipeline(&self, data: &[Arc<DataRecord>]) -> (Pipeline, usize) {
+        let mut pipe = ::redis::pipe();
+        let mut appended = 0;
+        for record in data {
+            match self.append_record(&mut pipe, record) {
+                Ok(()) => appended += 1,
+                Err(e) => wp_log::error_data!("redis drop record: {}", e),
+            }
+        }
+        (pipe, appended)
+    }
+
+    async fn execute(&self, pipe: Pipeline) -> SinkResult<()> {
+        let result = self.query(pipe).await;
+        self.stats.record_flush(&result);
+        result
+    }
+
+    async fn query(&self, pipe: Pipeline) -> SinkResult<()> {
+        let mut conn = self
+            .pool
+            .get()
+            .await
+            .map_err(|e| retry::network_error(format!("redis pool get failed: {e}")))?;
+        pipe.query_async::<()>(&mut conn).await.map_err(|e| {
+            let msg = format!("redis {} write failed: {e}", self.mode.as_str());
+            redis_error(msg, &e)
+        })
+    }
+
+    /// 从连接池取连接并执行 `PING`。
+    pub(crate) async fn ping(&self) -> SinkResult<()> {
+        let mut conn = self
+            .pool
+            .get()
+            .await
+            .map_err(|e| retry::network_error(format!("redis pool get failed: {e}")))?;
+        ::redis::cmd("PING")
+            .query_async::<String>(&mut conn)
+            .await
+            .map(|_| ())
+            .map_err(|e| SinkError::from(SinkReason::sink(format!("redis ping failed: {e}"))))
+    }
+}
+
+#[async_trait]
+impl PendingFlush for RedisSink {
+    fn pending_len(&self) -> usize {
+        0
+    }
+
+    async fn flush_now(&mut self) -> SinkResult<()> {
+        Ok(())
+    }
+
+    fn discard_pending(&mut self) -> usize {
+        0
+    }
+
+    fn accepted(&self) -> u64 {
+        self.accepted
+    }
+}
+
+/// 按 Redis 错误归类：连接与超时错误、服务端加载中或集群切换为可重试错误。
+fn redis_error(msg: String, err: &RedisError) -> SinkError {
+    if err.is_timeout() {
+        return retry::timeout_error(msg);
+    }
+    if err.is_io_error() || err.is_connection_dropped() || err.is_connection_refusal() {
+        return retry::network_error(msg);
+    }
+    match err.kind() {
+        ErrorKind::BusyLoadingError
+        | ErrorKind::TryAgain
+        | ErrorKind::ClusterDown
+        | ErrorKind::MasterDown => retry::busy_error(msg),
+        _ => SinkError::from(SinkReason::sink(msg)),
+    }
+}
+
+#[async_trait]
+impl AsyncCtrl for RedisSink {
+    async fn stop(&mut self) -> SinkResult<()> {
+        self.pool.close();
+        Ok(())
+    }
+
+    async fn reconnect(&mut self) -> SinkResult<()> {
+        self.ping().await
+    }
+}
+
+#[async_trait]
+impl AsyncRecordSink for RedisSink {
+    async fn sink_record(&mut self, data: &DataRecord) -> SinkResult<()> {
+        let mut pipe = ::redis::pipe();
+        self.append_record(&mut pipe, data)?;
+        self.execute(pipe).await?;
+        self.accepted += 1;
+        Ok(())
+    }
+
+    async fn sink_records(&mut self, data: Vec<Arc<DataRecord>>) -> SinkResult<()> {
+        if data.is_empty() {
+            return Ok(());
+        }
+        let (pipe, appended) = self.batch_pipeline(&data);
+        if appended > 0 {
+            self.execute(pipe).await?;
+        }
+        self.accepted += data.len() as u64;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl AsyncRawDataSink for RedisSink {
+    async fn sink_str(&mut self, data: &str) -> SinkResult<()> {
+        self.sink_bytes(data.as_bytes()).await
+    }
+
+    async fn sink_bytes(&mut self, data: &[u8]) -> SinkResult<()> {
+        let mut pipe = ::redis::pipe();
+        self.append(&mut pipe, self.raw_key()?, data);
+        self.execute(pipe).await
+    }
+
+    async fn sink_str_batch(&mut self, data: Vec<&str>) -> SinkResult<()> {
+        self.sink_bytes_batch(data.into_iter().map(str::as_bytes).collect())
+            .await
+    }
+
+    async fn sink_bytes_batch(&mut self, data: Vec<&[u8]>) -> SinkResult<()> {
+        if data.is_empty() {
+            return Ok(());
+        }
+        let key = self.raw_key()?;
+        let mut pipe = ::redis::pipe();
+        for item in data {
+            self.append(&mut pipe, key, item);
+        }
+        self.execute(pipe).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use deadpool_redis::Runtime;
+    use wp_model_core::model::DataField;
+
+    fn test_sink(conf: RedisSinkConfig) -> RedisSink {
+        // 创建连接池不会建立连接，测试只检查生成的命令
+        let pool = deadpool_redis::Config::from_url("redis://127.0.0.1:1")
+            .create_pool(Some(Runtime::Tokio1))
+            .expect("pool");
+        RedisSink::new(pool, &conf, TextFmt::Json)
+    }
+
+    fn packed(pipe: &Pipeline) -> String {
+        String::from_utf8_lossy(&pipe.get_packed_pipeline()).into_owned()
+    }
+
+    fn record(user: &str) -> DataRecord {
+        let mut record = DataRecord::default();
+        record.append(DataField::from_chars("user", user));
+        record
+    }
+
+    #[test]
+    fn commands_follow_mode() {
+        let mut conf = RedisSinkConfig {
+            key: Some("events".into()),
+            ..Default::default()
+        };
+        let mut pipe = ::redis::pipe();
+        test_sink(conf.clone())
+            .append_record(&mut pipe, &record("alice"))
+            .unwrap();
+        let text = packed(&pipe);
+        assert!(text.contains("LPUSH\r\n$6\r\nevents\r\n"), "{text}");
+        assert!(text.contains("alice"), "{text}");
+
+        conf.mode = RedisMode::Stream;
+        let mut pipe = ::redis::pipe();
+        test_sink(conf.clone())
+            .append_record(&mut pipe, &record("bob"))
+            .unwrap();
+        let text = packed(&pipe);
+        assert!(
+            text.contains("XADD\r\n$6\r\nevents\r\n$1\r\n*\r\n$4\r\ndata\r\n"),
+            "{text}"
+        );
+
+        conf.mode = RedisMode::Set;
+        conf.key = None;
+        conf.key_field = Some("user".into());
+        conf.ttl_secs = Some(60);
+        let mut pipe = ::redis::pipe();
+        test_sink(conf)
+            .append_record(&mut pipe, &record("carol"))
+            .unwrap();
+        let text = packed(&pipe);
+        assert!(text.contains("SET\r\n$5\r\ncarol\r\n"), "{text}");
+        assert!(text.ends_with("$2\r\nEX\r\n$2\r\n60\r\n"), "{text}");
+    }
+
+    #[test]
+    fn key_field_must_be_present() {
+        let sink = test_sink(RedisSinkConfig {
+            mode: RedisMode::Set,
+            key_field: Some("session".into()),
+            ..Default::default()
+        });
+        let mut pipe = ::redis::pipe();
+        let err = sink.append_record(&mut pipe, &record("alice")).unwrap_err();
+        assert!(format!("{err}").contains("session"), "{err}");
+        assert!(sink.raw_key().is_err(), "raw data needs a fixed key");
+    }
+
+    #[test]
+    fn records_missing_key_field_are_dropped_from_batch() {
+        let sink = test_sink(RedisSinkConfig {
+            mode: RedisMode::Set,
+            key_field: Some("user".into()),
+            ..Default::default()
+        });
+        let batch = vec![
+            Arc::new(record("alice")),
+            Arc::new(DataRecord::default()),
+            Arc::new(record("bob")),
+        ];
+        let (pipe, appended) = sink.batch_pipeline(&batch);
+        assert_eq!(appended, 2);
+        let text = packed(&pipe);
+        assert!(text.contains("$5\r\nalice\r\n"), "{text}");
+        assert!(text.contains("$3\r\nbob\r\n"), "{text}");
+    }
+}
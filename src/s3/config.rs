@@ -0,0 +1,112 @@
+use chrono::{DateTime, Utc};
+use educe::Educe;
+use serde::Deserialize;
+use serde::Serialize;
+
+/// 对象键模板支持的占位符
+pub const KEY_TOKENS: [&str; 9] = ["prefix", "yyyy", "MM", "dd", "HH", "mm", "ts", "seq", "ext"];
+/// S3 分段上传除最后一段外的最小段大小
+pub const MIN_PART_SIZE: usize = 5 * 1024 * 1024;
+
+/// 访问凭证；未配置时发送匿名请求。
+pub use crate::common::sigv4::AwsCredentials as S3Credentials;
+
+#[derive(Educe, Deserialize, Serialize, PartialEq, Clone)]
+#[educe(Debug, Default)]
+pub struct S3SinkConfig {
+    /// 服务地址，按 path-style 访问：`{endpoint}/{bucket}/{key}`
+    #[educe(Default = "https://s3.amazonaws.com")]
+    pub endpoint: String,
+    pub bucket: String,
+    /// 对象键前缀，非空时以 `/` 结尾
+    pub prefix: String,
+    #[educe(Default = "us-east-1")]
+    pub region: String,
+    #[educe(Debug(ignore))]
+    pub credentials: Option<S3Credentials>,
+    /// 记录的渲染格式，每条一行；`json` 即 NDJSON
+    #[educe(Default = "json")]
+    pub format: String,
+    /// 对象键模板，默认为 Hive 风格的日期/小时分区
+    #[educe(Default = "{prefix}dt={yyyy}-{MM}-{dd}/hour={HH}/part-{ts}-{seq}.{ext}")]
+    pub key_template: String,
+    /// 缓冲达到该字节数（默认 64 MiB）时滚动上传
+    #[educe(Default = 67108864)]
+    pub roll_size_bytes: usize,
+    /// 缓冲中最早的数据超过该时长（秒）时滚动上传
+    #[educe(Default = 300)]
+    pub roll_interval_secs: u64,
+    /// 对象超过该大小（默认 8 MiB）时改用分段上传，每段为该大小
+    #[educe(Default = 8388608)]
+    pub part_size_bytes: usize,
+}
+
+impl S3SinkConfig {
+    /// 对象扩展名：`json` 为 `ndjson`，`csv` 为 `csv`，其余为 `log`。
+    pub fn extension(&self) -> &'static str {
+        match self.format.as_str() {
+            "json" => "ndjson",
+            "csv" => "csv",
+            _ => "log",
+        }
+    }
+}
+
+/// 校验对象键模板：占位符须为 [`KEY_TOKENS`] 之一，且同时含 `{ts}` 与 `{seq}` 以免对象互相覆盖：
+/// `{seq}` 在每个进程内从 0 计数，重启后会重复；`{ts}` 在同一毫秒内滚动的多个分区间会重复。
+pub fn validate_key_template(template: &str) -> Result<(), String> {
+    let mut rest = template;
+    let (mut ts, mut seq) = (false, false);
+    while let Some(start) = rest.find('{') {
+        let end = rest[start..]
+            .find('}')
+            .ok_or_else(|| format!("s3.key_template has an unclosed '{{' in '{template}'"))?;
+        let token = &rest[start + 1..start + end];
+        if !KEY_TOKENS.contains(&token) {
+            return Err(format!("s3.key_template has unknown token '{{{token}}}'"));
+        }
+        ts |= token == "ts";
+        seq |= token == "seq";
+        rest = &rest[start + end + 1..];
+    }
+    if !(ts && seq) {
+        return Err("s3.key_template must contain both {ts} and {seq}".into());
+    }
+    Ok(())
+}
+
+/// 按模板渲染对象键；日期占位符取缓冲打开时间（UTC），`{ts}` 为其毫秒时间戳。
+pub fn render_key(
+    template: &str,
+    prefix: &str,
+    opened_at: DateTime<Utc>,
+    seq: u64,
+    ext: &str,
+) -> String {
+    let mut key = template.to_string();
+    let values = [
+        ("prefix", prefix.to_string()),
+        ("yyyy", opened_at.format("%Y").to_string()),
+        ("MM", opened_at.format("%m").to_string()),
+        ("dd", opened_at.format("%d").to_string()),
+        ("HH", opened_at.format("%H").to_string()),
+        ("mm", opened_at.format("%M").to_string()),
+        ("ts", opened_at.timestamp_millis().to_string()),
+        ("seq", format!("{seq:06}")),
+        ("ext", ext.to_string()),
+    ];
+    for (token, value) in values {
+        key = key.replace(&format!("{{{token}}}"), &value);
+    }
+    key
+}
+
+/// 规范化前缀：去掉开头的 `/`，非空时补齐结尾的 `/`。
+pub fn normalize_prefix(prefix: &str) -> String {
+    let prefix = prefix.trim().trim_start_matches('/');
+    if prefix.is_empty() || prefix.ends_with('/') {
+        prefix.to_string()
+    } else {
+        format!("{prefix}/")
+    }
+}
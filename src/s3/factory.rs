@@ -0,0 +1,290 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde_json::{Value, json};
+use wp_connector_api::{
+    ConnectorDef, ConnectorScope, ParamMap, SinkBuildCtx, SinkDefProvider, SinkError, SinkFactory,
+    SinkHandle, SinkReason, SinkResult, SinkSpec,
+};
+use wp_model_core::model::fmt_def::TextFmt;
+
+use super::config::{MIN_PART_SIZE, S3Credentials, S3SinkConfig, validate_key_template};
+use super::sink::S3Sink;
+use crate::common::enrich::{EnrichConf, EnrichSink};
+use crate::common::partition::PartitionTemplate;
+use crate::common::retry::{self, RetryingSink};
+use crate::common::secret;
+use crate::common::stats;
+use crate::common::transform::FieldTransforms;
+
+pub struct S3SinkFactory;
+
+#[async_trait]
+impl SinkFactory for S3SinkFactory {
+    fn kind(&self) -> &'static str {
+        "s3"
+    }
+
+    fn validate_spec(&self, spec: &SinkSpec) -> SinkResult<()> {
+        let spec = &secret::resolve_sink_spec(spec)?;
+        build_conf(&spec.params)?;
+        PartitionTemplate::from_params(&spec.params, "s3")?;
+        EnrichConf::from_params(&spec.params)?;
+        retry::validate_params(&spec.params)?;
+        FieldTransforms::from_params(&spec.params)?;
+        Ok(())
+    }
+
+    async fn build(&self, spec: &SinkSpec, _ctx: &SinkBuildCtx) -> SinkResult<SinkHandle> {
+        let spec = &secret::resolve_sink_spec(spec)?;
+        let conf = build_conf(&spec.params)?;
+        let fmt = TextFmt::from(conf.format.as_str());
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(60))
+            .build()
+            .map_err(|err| {
+                SinkError::from(SinkReason::sink(format!("build s3 client failed: {err}")))
+            })?;
+        let partition = PartitionTemplate::from_params(&spec.params, "s3")?;
+        let sink = S3Sink::new(client, &conf, fmt)
+            .with_partition(partition)
+            .with_stats(stats::register(&spec.name, self.kind()));
+        let enrich = EnrichConf::from_params(&spec.params)?;
+        let transforms = FieldTransforms::from_params(&spec.params)?;
+        let sink = RetryingSink::from_spec(sink, spec).await?;
+        let sink = EnrichSink::new(sink, enrich).with_transforms(transforms);
+        Ok(SinkHandle::new(Box::new(sink)))
+    }
+}
+
+impl SinkDefProvider for S3SinkFactory {
+    fn sink_def(&self) -> ConnectorDef {
+        ConnectorDef {
+            id: "s3_sink".into(),
+            kind: self.kind().into(),
+            scope: ConnectorScope::Sink,
+            allow_override: vec![
+                "endpoint",
+                "bucket",
+                "prefix",
+                "region",
+                "access_key_id",
+                "secret_access_key",
+                "session_token",
+                "format",
+                "key_template",
+                "roll_size_bytes",
+                "roll_interval_secs",
+                "part_size_bytes",
+                "partition_template",
+                "partition_time_field",
+                "enrich",
+                "enrich_overwrite",
+                "field_transforms",
+                "retry",
+                "write_deadline_ms",
+                "quarantine",
+                "secret_ref",
+            ]
+            .into_iter()
+            .map(str::to_string)
+            .collect(),
+            default_params: s3_defaults(),
+            origin: Some("wp-connectors:s3_sink".into()),
+        }
+    }
+}
+
+fn s3_defaults() -> ParamMap {
+    let defaults = S3SinkConfig::default();
+    let mut params = ParamMap::new();
+    params.insert("endpoint".into(), json!(defaults.endpoint));
+    params.insert("region".into(), json!(defaults.region));
+    params.insert("format".into(), json!(defaults.format));
+    params.insert("key_template".into(), json!(defaults.key_template));
+    params.insert("roll_size_bytes".into(), json!(defaults.roll_size_bytes));
+    params.insert(
+        "roll_interval_secs".into(),
+        json!(defaults.roll_interval_secs),
+    );
+    params.insert("part_size_bytes".into(), json!(defaults.part_size_bytes));
+    params
+}
+
+/// 由参数构建配置：`bucket` 必填，凭证两项须同时配置，暂不支持 `parquet`。
+fn build_conf(params: &ParamMap) -> SinkResult<S3SinkConfig> {
+    let bucket = opt_str(params, "bucket")?
+        .ok_or_else(|| SinkError::from(SinkReason::sink("s3.bucket must not be empty")))?;
+    let mut conf = S3SinkConfig {
+        bucket,
+        ..Default::default()
+    };
+    if let Some(endpoint) = opt_str(params, "endpoint")? {
+        if !endpoint.starts_with("http://") && !endpoint.starts_with("https://") {
+            return Err(SinkReason::sink("s3.endpoint must start with http:// or https://").into());
+        }
+        conf.endpoint = endpoint;
+    }
+    if let Some(prefix) = opt_str(params, "prefix")? {
+        conf.prefix = prefix;
+    }
+    if let Some(region) = opt_str(params, "region")? {
+        conf.region = region;
+    }
+    conf.credentials = match (
+        opt_str(params, "access_key_id")?,
+        opt_str(params, "secret_access_key")?,
+    ) {
+        (Some(access_key_id), Some(secret_access_key)) => Some(S3Credentials {
+            access_key_id,
+            secret_access_key,
+            session_token: opt_str(params, "session_token")?,
+        }),
+        (None, None) => None,
+        _ => {
+            return Err(SinkReason::sink(
+                "s3.access_key_id and s3.secret_access_key must be set together",
+            )
+            .into());
+        }
+    };
+    if let Some(format) = opt_str(params, "format")? {
+        if format == "parquet" {
+            return Err(SinkReason::sink(
+                "s3.format=parquet is not supported yet, use json or a text format",
+            )
+            .into());
+        }
+        conf.format = format;
+    }
+    if let Some(template) = opt_str(params, "key_template")? {
+        conf.key_template = template;
+    }
+    validate_key_template(&conf.key_template).map_err(SinkReason::sink)?;
+    if let Some(n) = opt_positive(params, "roll_size_bytes")? {
+        conf.roll_size_bytes = n as usize;
+    }
+    if let Some(n) = opt_positive(params, "roll_interval_secs")? {
+        conf.roll_interval_secs = n;
+    }
+    if let Some(n) = opt_positive(params, "part_size_bytes")? {
+        conf.part_size_bytes = n as usize;
+    }
+    if conf.part_size_bytes < MIN_PART_SIZE {
+        return Err(SinkReason::sink(format!(
+            "s3.part_size_bytes must be >= {MIN_PART_SIZE} (5 MiB)"
+        ))
+        .into());
+    }
+    Ok(conf)
+}
+
+/// 可选的非空字符串参数。
+fn opt_str(params: &ParamMap, key: &str) -> SinkResult<Option<String>> {
+    match params.get(key) {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::String(s)) if s.trim().is_empty() => Ok(None),
+        Some(Value::String(s)) => Ok(Some(s.trim().to_string())),
+        Some(_) => Err(SinkReason::sink(format!("s3.{key} must be a string")).into()),
+    }
+}
+
+/// 可选的正整数参数。
+fn opt_positive(params: &ParamMap, key: &str) -> SinkResult<Option<u64>> {
+    match params.get(key) {
+        None | Some(Value::Null) => Ok(None),
+        Some(v) => match v.as_u64() {
+            Some(n) if n > 0 => Ok(Some(n)),
+            _ => Err(SinkReason::sink(format!("s3.{key} must be a positive integer")).into()),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::s3::config::render_key;
+    use chrono::{TimeZone, Utc};
+
+    fn params() -> ParamMap {
+        let mut params = s3_defaults();
+        params.insert("bucket".into(), json!("logs"));
+        params
+    }
+
+    #[test]
+    fn build_conf_applies_defaults_and_checks_params() {
+        let conf = build_conf(&params()).expect("valid");
+        assert_eq!(conf.bucket, "logs");
+        assert_eq!(conf.extension(), "ndjson");
+        assert!(conf.credentials.is_none());
+
+        let mut p = params();
+        p.remove("bucket");
+        assert!(build_conf(&p).is_err());
+
+        let mut p = params();
+        p.insert("access_key_id".into(), json!("AK"));
+        assert!(build_conf(&p).is_err(), "secret key missing");
+        p.insert("secret_access_key".into(), json!("SK"));
+        let conf = build_conf(&p).expect("credentials");
+        assert_eq!(conf.credentials.unwrap().access_key_id, "AK");
+
+        let mut p = params();
+        p.insert("format".into(), json!("parquet"));
+        assert!(build_conf(&p).is_err());
+
+        let mut p = params();
+        p.insert("part_size_bytes".into(), json!(1024));
+        assert!(build_conf(&p).is_err(), "below the S3 part minimum");
+
+        let mut p = params();
+        p.insert("roll_interval_secs".into(), json!(0));
+        assert!(build_conf(&p).is_err());
+
+        let mut p = params();
+        p.insert("endpoint".into(), json!("minio:9000"));
+        assert!(build_conf(&p).is_err());
+    }
+
+    #[test]
+    fn key_template_is_validated_and_rendered() {
+        let mut p = params();
+        p.insert(
+            "key_template".into(),
+            json!("{prefix}{yyyy}/{MM}/{dd}/data.{ext}"),
+        );
+        assert!(
+            build_conf(&p).is_err(),
+            "objects would overwrite each other"
+        );
+        p.insert("key_template".into(), json!("{prefix}{date}/{seq}.{ext}"));
+        assert!(build_conf(&p).is_err(), "unknown token");
+        p.insert(
+            "key_template".into(),
+            json!("{prefix}{yyyy}{MM}{dd}/{HH}{mm}-{seq}.{ext}"),
+        );
+        assert!(
+            build_conf(&p).is_err(),
+            "{{seq}} restarts at 0 and would overwrite objects after a restart"
+        );
+        p.insert(
+            "key_template".into(),
+            json!("{prefix}{yyyy}{MM}{dd}/{HH}{mm}-{ts}-{seq}.{ext}"),
+        );
+        let conf = build_conf(&p).expect("valid template");
+
+        let at = Utc.with_ymd_and_hms(2024, 3, 9, 7, 5, 0).unwrap();
+        assert_eq!(
+            render_key(&conf.key_template, "app/", at, 12, "ndjson"),
+            format!("app/20240309/0705-{}-000012.ndjson", at.timestamp_millis())
+        );
+        assert_eq!(
+            render_key(&S3SinkConfig::default().key_template, "", at, 0, "log"),
+            format!(
+                "dt=2024-03-09/hour=07/part-{}-000000.log",
+                at.timestamp_millis()
+            )
+        );
+    }
+}
@@ -0,0 +1,13 @@
+//! S3 兼容对象存储 sink：攒批后按日期分区滚动上传 NDJSON 等文本对象。
+//!
+//! 模块划分：
+//! - config：S3SinkConfig 与对象键模板
+//! - sink：S3Sink（缓冲、滚动判断、单次/分段上传）
+//! - factory：Sink 工厂
+
+pub mod config;
+mod factory;
+mod sink;
+
+pub use config::{S3Credentials, S3SinkConfig};
+pub use factory::S3SinkFactory;
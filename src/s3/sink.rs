@@ -0,0 +1,744 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use reqwest::{Method, Url};
+use wp_connector_api::{
+    AsyncCtrl, AsyncRawDataSink, AsyncRecordSink, SinkError, SinkReason, SinkResult,
+};
+use wp_data_fmt::{DataFormat, FormatType};
+use wp_model_core::model::{DataRecord, fmt_def::TextFmt};
+
+use crate::common::csv::CsvBatch;
+use crate::common::partition::PartitionTemplate;
+use crate::common::retry::{self, PendingFlush};
+use crate::common::sigv4::{SigV4, SignRequest, canonical_query, hex_sha256, uri_encode};
+use crate::common::stats::StatsHandle;
+use crate::s3::config::{S3SinkConfig, normalize_prefix, render_key};
+
+/// 滚动条件：缓冲字节数或最早数据的缓冲时长达到上限。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct RollPolicy {
+    pub max_bytes: usize,
+    pub max_age: Duration,
+}
+
+impl RollPolicy {
+    pub(crate) fn should_roll(&self, buffered_bytes: usize, age: Duration) -> bool {
+        buffered_bytes > 0 && (buffered_bytes >= self.max_bytes || age >= self.max_age)
+    }
+}
+
+/// 一个分区的缓冲及其对应的对象：打开时刻决定键中的日期与 `{ts}`，上传失败重试时键不变。
+struct OpenObject {
+    started: Instant,
+    opened_at: DateTime<Utc>,
+    /// 缓冲中记录的接收序号
+    seqs: Vec<u64>,
+    buffer: Vec<u8>,
+    csv: Option<CsvBatch>,
+}
+
+impl OpenObject {
+    fn bytes(&self) -> usize {
+        self.buffer.len() + self.csv.as_ref().map_or(0, CsvBatch::bytes)
+    }
+}
+
+/// 攒批写入 S3：每条记录按 `format` 渲染为一行，满足 [`RollPolicy`] 时上传为一个对象。
+/// 滚动条件在每次写入与 `stop` 时检查，没有后台定时器。
+///
+/// `format = csv` 时记录缓冲在 [`CsvBatch`] 中，每个对象以表头开头，列为对象内所有记录字段的并集。
+///
+/// 配置 `partition_template` 时按记录渲染分区目录，各分区分别缓冲、滚动，
+/// 分区目录接在 `{prefix}` 之后；原始数据按当前时间与默认分区名渲染。
+pub(crate) struct S3Sink {
+    client: reqwest::Client,
+    endpoint: String,
+    bucket: String,
+    prefix: String,
+    key_template: String,
+    ext: &'static str,
+    fmt: TextFmt,
+    signer: Option<SigV4>,
+    roll: RollPolicy,
+    part_size: usize,
+    partition: Option<PartitionTemplate>,
+    /// 按分区目录缓冲的对象；未配置分区模板时只有空目录一项
+    open: BTreeMap<String, OpenObject>,
+    /// 已上传的对象数，用于 `{seq}`
+    seq: u64,
+    /// 累计进入缓存的记录数
+    accepted: u64,
+    /// `/stats` 自省状态（缓存、最近上传与错误）
+    stats: StatsHandle,
+}
+
+impl S3Sink {
+    pub(crate) fn new(client: reqwest::Client, conf: &S3SinkConfig, fmt: TextFmt) -> Self {
+        Self {
+            client,
+            endpoint: conf.endpoint.trim_end_matches('/').to_string(),
+            bucket: conf.bucket.clone(),
+            prefix: normalize_prefix(&conf.prefix),
+            key_template: conf.key_template.clone(),
+            ext: conf.extension(),
+            fmt,
+            signer: conf
+                .credentials
+                .clone()
+                .map(|c| SigV4::new(c, conf.region.clone(), "s3")),
+            roll: RollPolicy {
+                max_bytes: conf.roll_size_bytes,
+                max_age: Duration::from_secs(conf.roll_interval_secs),
+            },
+            part_size: conf.part_size_bytes.max(1),
+            partition: None,
+            open: BTreeMap::new(),
+            seq: 0,
+            accepted: 0,
+            stats: StatsHandle::detached(&conf.bucket, "s3"),
+        }
+    }
+
+    /// 使用外部登记的状态句柄，以 bucket 作为指标的写入目标。
+    pub(crate) fn with_stats(mut self, stats: StatsHandle) -> Self {
+        self.stats = stats.with_destination(self.bucket.as_str());
+        self
+    }
+
+    pub(crate) fn with_partition(mut self, partition: Option<PartitionTemplate>) -> Self {
+        self.partition = partition;
+        self
+    }
+
+    /// 记录所属分区的对象，不存在时打开一个新对象。
+    fn open_record(&mut self, dir: String) -> &mut OpenObject {
+        let open = self.open.entry(dir).or_insert_with(|| OpenObject {
+            started: Instant::now(),
+            opened_at: Utc::now(),
+            seqs: Vec::new(),
+            buffer: Vec::new(),
+            csv: None,
+        });
+        open.seqs.push(self.accepted);
+        self.accepted += 1;
+        open
+    }
+
+    fn partition_dir(&self, record: &DataRecord) -> String {
+        self.partition
+            .as_ref()
+            .map(|p| p.render(record, Utc::now().naive_utc()))
+            .unwrap_or_default()
+    }
+
+    fn append_record(&mut self, record: &DataRecord) {
+        let dir = self.partition_dir(record);
+        let line =
+            (self.fmt != TextFmt::Csv).then(|| FormatType::from(&self.fmt).format_record(record));
+        let open = self.open_record(dir);
+        match line {
+            Some(line) => push_line(&mut open.buffer, line.as_bytes()),
+            None => open.csv.get_or_insert_with(CsvBatch::default).push(record),
+        }
+    }
+
+    fn append_line(&mut self, line: &[u8]) {
+        let dir = self.partition_dir(&DataRecord::default());
+        let open = self.open_record(dir);
+        push_line(&mut open.buffer, line);
+    }
+
+    async fn roll_if_due(&mut self) -> SinkResult<()> {
+        self.stats.set_buffered(self.pending_len());
+        let due: Vec<String> = self
+            .open
+            .iter()
+            .filter(|(_, o)| self.roll.should_roll(o.bytes(), o.started.elapsed()))
+            .map(|(dir, _)| dir.clone())
+            .collect();
+        for dir in due {
+            self.roll_object(&dir).await?;
+        }
+        Ok(())
+    }
+
+    /// 上传所有分区的缓冲。
+    async fn roll_now(&mut self) -> SinkResult<()> {
+        let dirs: Vec<String> = self.open.keys().cloned().collect();
+        for dir in dirs {
+            self.roll_object(&dir).await?;
+        }
+        Ok(())
+    }
+
+    /// 上传一个分区的缓冲为一个对象；失败时保留缓冲，下次以相同的键重试。
+    async fn roll_object(&mut self, dir: &str) -> SinkResult<()> {
+        let Some(open) = self.open.get(dir) else {
+            return Ok(());
+        };
+        let prefix = if dir.is_empty() {
+            self.prefix.clone()
+        } else {
+            format!("{}{dir}/", self.prefix)
+        };
+        let key = render_key(
+            &self.key_template,
+            &prefix,
+            open.opened_at,
+            self.seq,
+            self.ext,
+        );
+        let mut body = open.csv.as_ref().map(CsvBatch::finish).unwrap_or_default();
+        body.extend_from_slice(&open.buffer);
+        let result = if body.len() > self.part_size {
+            self.multipart_upload(&key, body).await
+        } else {
+            self.send(Method::PUT, &key, &[], body).await.map(|_| ())
+        };
+        self.stats.record_flush(&result);
+        result?;
+        self.open.remove(dir);
+        self.seq += 1;
+        self.stats.set_buffered(self.pending_len());
+        Ok(())
+    }
+
+    async fn multipart_upload(&self, key: &str, body: Vec<u8>) -> SinkResult<()> {
+        let resp = self
+            .send(Method::POST, key, &[("uploads", "")], Vec::new())
+            .await?;
+        let text = resp
+            .text()
+            .await
+            .map_err(|e| s3_error("read upload id", e))?;
+        let upload_id = xml_value(&text, "UploadId")
+            .ok_or_else(|| s3_error("initiate multipart upload", "response has no UploadId"))?;
+        let result = match self.upload_parts(key, &upload_id, &body).await {
+            Ok(etags) => self.complete_upload(key, &upload_id, &etags).await,
+            Err(err) => Err(err),
+        };
+        if result.is_err() {
+            // 尽力清理未完成的上传，避免残留分段持续计费
+            if let Err(abort) = self
+                .send(Method::DELETE, key, &[("uploadId", &upload_id)], Vec::new())
+                .await
+            {
+                wp_log::error_data!("[s3] abort multipart upload {} fail: {}", key, abort);
+            }
+        }
+        result
+    }
+
+    /// 合并已上传的分段。S3 可能先返回 200 再处理合并，合并失败时响应体为 `<Error>`，
+    /// 因此需检查响应体；这类失败（如 InternalError）可重试。
+    async fn complete_upload(
+        &self,
+        key: &str,
+        upload_id: &str,
+        etags: &[String],
+    ) -> SinkResult<()> {
+        let resp = self
+            .send(
+                Method::POST,
+                key,
+                &[("uploadId", upload_id)],
+                complete_body(etags).into_bytes(),
+            )
+            .await?;
+        let text = resp.text().await.map_err(|e| {
+            retry::transport_error(
+                format!("s3 read complete multipart upload response of {key} failed: {e}"),
+                e.is_timeout(),
+            )
+        })?;
+        if text.contains("<Error>") {
+            return Err(retry::busy_error(format!(
+                "s3 complete multipart upload of {key} failed: {}: {}",
+                xml_value(&text, "Code").unwrap_or_default(),
+                xml_value(&text, "Message").unwrap_or_default()
+            )));
+        }
+        Ok(())
+    }
+
+    async fn upload_parts(
+        &self,
+        key: &str,
+        upload_id: &str,
+        body: &[u8],
+    ) -> SinkResult<Vec<String>> {
+        let mut etags = Vec::new();
+        for (idx, chunk) in body.chunks(self.part_size).enumerate() {
+            let part_number = (idx + 1).to_string();
+            let resp = self
+                .send(
+                    Method::PUT,
+                    key,
+                    &[("partNumber", &part_number), ("uploadId", upload_id)],
+                    chunk.to_vec(),
+                )
+                .await?;
+            let etag = resp
+                .headers()
+                .get(reqwest::header::ETAG)
+                .and_then(|v| v.to_str().ok())
+                .ok_or_else(|| {
+                    s3_error("upload part", format!("part {part_number} has no ETag"))
+                })?;
+            etags.push(etag.to_string());
+        }
+        Ok(etags)
+    }
+
+    /// 发送（按需签名的）请求，非 2xx 响应转为错误。
+    async fn send(
+        &self,
+        method: Method,
+        key: &str,
+        query: &[(&str, &str)],
+        body: Vec<u8>,
+    ) -> SinkResult<reqwest::Response> {
+        let path = format!("/{}/{}", self.bucket, key);
+        let mut url = format!("{}{}", self.endpoint, uri_encode(&path, false));
+        if !query.is_empty() {
+            url.push('?');
+            url.push_str(&canonical_query(query));
+        }
+        let parsed = Url::parse(&url).map_err(|e| s3_error("build url", e))?;
+        let mut req = self.client.request(method.clone(), parsed.clone());
+        if let Some(signer) = &self.signer {
+            let host = match (parsed.host_str(), parsed.port()) {
+                (Some(host), Some(port)) => format!("{host}:{port}"),
+                (Some(host), None) => host.to_string(),
+                (None, _) => return Err(s3_error("build url", "endpoint has no host")),
+            };
+            let payload = hex_sha256(&body);
+            let headers = signer.sign(
+                &SignRequest {
+                    method: method.as_str(),
+                    host: &host,
+                    path: &endpoint_path(&self.endpoint, &path),
+                    query,
+                    headers: &[],
+                    payload_sha256: &payload,
+                },
+                Utc::now(),
+            );
+            for (name, value) in headers {
+                req = req.header(name, value);
+            }
+        }
+        let resp = req.body(body).send().await.map_err(|e| {
+            retry::transport_error(format!("s3 {method} {key} failed: {e}"), e.is_timeout())
+        })?;
+        if !resp.status().is_success() {
+            let status = resp.status();
+            let text = resp.text().await.unwrap_or_default();
+            return Err(retry::status_error(
+                status.as_u16(),
+                format!("s3 {method} {key} failed: {status}: {text}"),
+            ));
+        }
+        Ok(resp)
+    }
+}
+
+/// 签名用的对象路径：包含 endpoint 自带的路径前缀（如反向代理下的 `/s3`）。
+fn endpoint_path(endpoint: &str, path: &str) -> String {
+    let base = Url::parse(endpoint)
+        .map(|u| u.path().trim_end_matches('/').to_string())
+        .unwrap_or_default();
+    format!("{base}{path}")
+}
+
+/// 取出 XML 中第一个 `<tag>` 的文本。
+fn xml_value(xml: &str, tag: &str) -> Option<String> {
+    let open = format!("<{tag}>");
+    let start = xml.find(&open)? + open.len();
+    let end = xml[start..].find(&format!("</{tag}>"))? + start;
+    Some(xml[start..end].to_string())
+}
+
+fn complete_body(etags: &[String]) -> String {
+    let parts: String = etags
+        .iter()
+        .enumerate()
+        .map(|(idx, etag)| {
+            format!(
+                "<Part><PartNumber>{}</PartNumber><ETag>{}</ETag></Part>",
+                idx + 1,
+                etag
+            )
+        })
+        .collect();
+    format!("<CompleteMultipartUpload>{parts}</CompleteMultipartUpload>")
+}
+
+fn push_line(buffer: &mut Vec<u8>, line: &[u8]) {
+    buffer.extend_from_slice(line);
+    if !line.ends_with(b"\n") {
+        buffer.push(b'\n');
+    }
+}
+
+fn s3_error(what: &str, err: impl std::fmt::Display) -> SinkError {
+    SinkError::from(SinkReason::sink(format!("s3 {what} failed: {err}")))
+}
+
+#[async_trait]
+impl PendingFlush for S3Sink {
+    fn pending_len(&self) -> usize {
+        self.open.values().map(|o| o.seqs.len()).sum()
+    }
+
+    async fn flush_now(&mut self) -> SinkResult<()> {
+        self.roll_now().await
+    }
+
+    fn discard_pending(&mut self) -> usize {
+        let discarded = self.pending_len();
+        self.open.clear();
+        self.stats.set_buffered(0);
+        discarded
+    }
+
+    fn accepted(&self) -> u64 {
+        self.accepted
+    }
+
+    fn pending_seqs(&self) -> Vec<u64> {
+        self.open
+            .values()
+            .flat_map(|o| o.seqs.iter().copied())
+            .collect()
+    }
+}
+
+#[async_trait]
+impl AsyncCtrl for S3Sink {
+    async fn stop(&mut self) -> SinkResult<()> {
+        self.roll_now().await
+    }
+
+    async fn reconnect(&mut self) -> SinkResult<()> {
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl AsyncRecordSink for S3Sink {
+    async fn sink_record(&mut self, data: &DataRecord) -> SinkResult<()> {
+        self.append_record(data);
+        self.roll_if_due().await
+    }
+
+    async fn sink_records(&mut self, data: Vec<Arc<DataRecord>>) -> SinkResult<()> {
+        for record in data {
+            self.append_record(record.as_ref());
+        }
+        self.roll_if_due().await
+    }
+}
+
+#[async_trait]
+impl AsyncRawDataSink for S3Sink {
+    async fn sink_str(&mut self, data: &str) -> SinkResult<()> {
+        self.sink_bytes(data.as_bytes()).await
+    }
+
+    async fn sink_bytes(&mut self, data: &[u8]) -> SinkResult<()> {
+        self.append_line(data);
+        self.roll_if_due().await
+    }
+
+    async fn sink_str_batch(&mut self, data: Vec<&str>) -> SinkResult<()> {
+        for item in data {
+            self.append_line(item.as_bytes());
+        }
+        self.roll_if_due().await
+    }
+
+    async fn sink_bytes_batch(&mut self, data: Vec<&[u8]>) -> SinkResult<()> {
+        for item in data {
+            self.append_line(item);
+        }
+        self.roll_if_due().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::s3::config::S3Credentials;
+    use httpmock::prelude::*;
+    use wp_model_core::model::DataField;
+
+    fn test_sink(endpoint: &str, roll_size_bytes: usize, part_size_bytes: usize) -> S3Sink {
+        let conf = S3SinkConfig {
+            endpoint: endpoint.to_string(),
+            bucket: "logs".into(),
+            prefix: "archive".into(),
+            roll_size_bytes,
+            part_size_bytes,
+            ..Default::default()
+        };
+        let client = reqwest::Client::builder()
+            .no_proxy()
+            .build()
+            .expect("client");
+        S3Sink::new(client, &conf, TextFmt::Json)
+    }
+
+    fn record(msg: &str) -> DataRecord {
+        let mut record = DataRecord::default();
+        record.append(DataField::from_chars("msg", msg));
+        record
+    }
+
+    #[test]
+    fn roll_policy_triggers_on_size_or_age() {
+        let roll = RollPolicy {
+            max_bytes: 100,
+            max_age: Duration::from_secs(60),
+        };
+        assert!(
+            !roll.should_roll(0, Duration::from_secs(3600)),
+            "empty never rolls"
+        );
+        assert!(!roll.should_roll(99, Duration::from_secs(59)));
+        assert!(roll.should_roll(100, Duration::ZERO));
+        assert!(roll.should_roll(1, Duration::from_secs(60)));
+    }
+
+    #[tokio::test]
+    async fn records_roll_into_partitioned_objects() {
+        let server = MockServer::start_async().await;
+        let put = server.mock(|when, then| {
+            when.method(PUT)
+                .path_contains("/logs/archive/dt=")
+                .path_contains("-000000.ndjson")
+                .body_contains("\"msg\":\"a\"")
+                .body_contains("\"msg\":\"b\"");
+            then.status(200);
+        });
+        let mut sink = test_sink(&server.base_url(), 1 << 20, 1 << 20);
+
+        sink.sink_records(vec![Arc::new(record("a")), Arc::new(record("b"))])
+            .await
+            .expect("buffered");
+        put.assert_hits(0);
+        assert_eq!(sink.pending_len(), 2);
+        sink.stop().await.expect("rolled on stop");
+        put.assert_hits(1);
+        assert_eq!(sink.pending_len(), 0);
+        assert_eq!(sink.seq, 1);
+
+        // 达到滚动大小时立即上传
+        let mut sink = test_sink(&server.base_url(), 1, 1 << 20);
+        sink.sink_records(vec![Arc::new(record("a")), Arc::new(record("b"))])
+            .await
+            .expect("rolled on size");
+        put.assert_hits(2);
+    }
+
+    #[tokio::test]
+    async fn csv_objects_start_with_header_and_union_columns() {
+        let server = MockServer::start_async().await;
+        let put = server.mock(|when, then| {
+            when.method(PUT)
+                .path_contains("-000000.csv")
+                .body("msg,code\na,\nb,500\n");
+            then.status(200);
+        });
+        let conf = S3SinkConfig {
+            endpoint: server.base_url(),
+            bucket: "logs".into(),
+            format: "csv".into(),
+            ..Default::default()
+        };
+        let client = reqwest::Client::builder()
+            .no_proxy()
+            .build()
+            .expect("client");
+        let mut sink = S3Sink::new(client, &conf, TextFmt::Csv);
+        let mut with_code = record("b");
+        with_code.append(DataField::from_chars("code", "500"));
+        sink.sink_records(vec![Arc::new(record("a")), Arc::new(with_code)])
+            .await
+            .expect("buffered");
+        assert_eq!(sink.pending_len(), 2);
+        sink.stop().await.expect("rolled on stop");
+        put.assert_hits(1);
+
+        // 下一个对象重新确定列并输出表头
+        let next = server.mock(|when, then| {
+            when.method(PUT)
+                .path_contains("-000001.csv")
+                .body("msg\nc\n");
+            then.status(200);
+        });
+        sink.sink_record(&record("c")).await.expect("buffered");
+        sink.stop().await.expect("rolled on stop");
+        next.assert_hits(1);
+    }
+
+    #[tokio::test]
+    async fn partition_template_buffers_objects_per_partition() {
+        let server = MockServer::start_async().await;
+        let hour = |h: &str, msg: &str| {
+            let path = format!("/logs/archive/dt=2024-03-09/hour={h}/");
+            let msg = format!("\"msg\":\"{msg}\"");
+            server.mock(move |when, then| {
+                when.method(PUT).path_contains(path).body_contains(msg);
+                then.status(200);
+            })
+        };
+        let seven = hour("07", "c");
+        let eight = hour("08", "b");
+        let conf = S3SinkConfig {
+            endpoint: server.base_url(),
+            bucket: "logs".into(),
+            prefix: "archive".into(),
+            key_template: "{prefix}part-{ts}-{seq}.{ext}".into(),
+            ..Default::default()
+        };
+        let client = reqwest::Client::builder()
+            .no_proxy()
+            .build()
+            .expect("client");
+        let template =
+            PartitionTemplate::parse("dt=%Y-%m-%d/hour=%H", Some("ts".into())).expect("template");
+        let mut sink = S3Sink::new(client, &conf, TextFmt::Json).with_partition(Some(template));
+        let at = |msg: &str, ts: &str| {
+            let mut record = record(msg);
+            record.append(DataField::from_chars("ts", ts));
+            Arc::new(record)
+        };
+        sink.sink_records(vec![
+            at("a", "2024-03-09T07:10:00Z"),
+            at("b", "2024-03-09T08:00:00Z"),
+            at("c", "2024-03-09T07:59:59Z"),
+        ])
+        .await
+        .expect("buffered");
+        assert_eq!(sink.open.len(), 2);
+        assert_eq!(sink.pending_len(), 3);
+        sink.stop().await.expect("rolled on stop");
+        seven.assert_hits(1);
+        eight.assert_hits(1);
+        assert_eq!(sink.pending_len(), 0);
+        assert_eq!(sink.seq, 2);
+    }
+
+    #[tokio::test]
+    async fn failed_upload_keeps_buffer() {
+        let server = MockServer::start_async().await;
+        let put = server.mock(|when, then| {
+            when.method(PUT);
+            then.status(503).body("SlowDown");
+        });
+        let mut sink = test_sink(&server.base_url(), 1, 1 << 20);
+        let err = sink.sink_record(&record("a")).await.expect_err("503");
+        assert!(format!("{err}").contains("503"), "{err}");
+        put.assert_hits(1);
+        assert_eq!(sink.pending_len(), 1);
+        assert_eq!(sink.seq, 0);
+    }
+
+    #[tokio::test]
+    async fn large_objects_use_multipart_upload() {
+        let server = MockServer::start_async().await;
+        let init = server.mock(|when, then| {
+            when.method(POST).query_param_exists("uploads");
+            then.status(200).body(
+                "<InitiateMultipartUploadResult><UploadId>up-1</UploadId></InitiateMultipartUploadResult>",
+            );
+        });
+        let part = server.mock(|when, then| {
+            when.method(PUT)
+                .query_param("uploadId", "up-1")
+                .query_param_exists("partNumber");
+            then.status(200).header("ETag", "\"etag\"");
+        });
+        let complete = server.mock(|when, then| {
+            when.method(POST)
+                .query_param("uploadId", "up-1")
+                .body_contains("<PartNumber>3</PartNumber>");
+            then.status(200);
+        });
+        let mut sink = test_sink(&server.base_url(), 1 << 20, 8);
+        sink.sink_str("0123456789abcdef").await.expect("buffered");
+        sink.stop().await.expect("multipart");
+        init.assert_hits(1);
+        // 17 字节（含换行）按 8 字节分为 3 段
+        part.assert_hits(3);
+        complete.assert_hits(1);
+    }
+
+    #[tokio::test]
+    async fn complete_error_body_fails_the_upload() {
+        let server = MockServer::start_async().await;
+        server.mock(|when, then| {
+            when.method(POST).query_param_exists("uploads");
+            then.status(200).body(
+                "<InitiateMultipartUploadResult><UploadId>up-2</UploadId></InitiateMultipartUploadResult>",
+            );
+        });
+        server.mock(|when, then| {
+            when.method(PUT).query_param("uploadId", "up-2");
+            then.status(200).header("ETag", "\"etag\"");
+        });
+        server.mock(|when, then| {
+            when.method(POST).query_param("uploadId", "up-2");
+            then.status(200).body(
+                "<Error><Code>InternalError</Code><Message>We encountered an internal error.</Message></Error>",
+            );
+        });
+        let abort = server.mock(|when, then| {
+            when.method(DELETE).query_param("uploadId", "up-2");
+            then.status(204);
+        });
+        let mut sink = test_sink(&server.base_url(), 1 << 20, 8);
+        sink.sink_str("0123456789abcdef").await.expect("buffered");
+        let err = sink.stop().await.expect_err("complete failed");
+        assert!(format!("{err}").contains("InternalError"), "{err}");
+        assert_eq!(retry::FailureKind::of(&err), retry::FailureKind::Retryable);
+        abort.assert_hits(1);
+        assert_eq!(sink.pending_len(), 1);
+        assert_eq!(sink.seq, 0);
+    }
+
+    #[tokio::test]
+    async fn signed_requests_carry_authorization() {
+        let server = MockServer::start_async().await;
+        let put = server.mock(|when, then| {
+            when.method(PUT)
+                .header_exists("authorization")
+                .header_exists("x-amz-date")
+                .header("x-amz-security-token", "session");
+            then.status(200);
+        });
+        let conf = S3SinkConfig {
+            endpoint: server.base_url(),
+            bucket: "logs".into(),
+            credentials: Some(S3Credentials {
+                access_key_id: "AK".into(),
+                secret_access_key: "SK".into(),
+                session_token: Some("session".into()),
+            }),
+            ..Default::default()
+        };
+        let client = reqwest::Client::builder()
+            .no_proxy()
+            .build()
+            .expect("client");
+        let mut sink = S3Sink::new(client, &conf, TextFmt::Json);
+        sink.sink_record(&record("a")).await.expect("buffered");
+        sink.stop().await.expect("signed put");
+        put.assert_hits(1);
+    }
+}
@@ -0,0 +1,123 @@
+use educe::Educe;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+/// FE 默认 HTTP 端口（Stream Load 入口）
+const DEFAULT_HTTP_PORT: u16 = 8030;
+
+/// Stream Load 请求体格式。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StarRocksFormat {
+    #[default]
+    Json,
+    Csv,
+}
+
+impl FromStr for StarRocksFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "json" => Ok(Self::Json),
+            "csv" => Ok(Self::Csv),
+            other => Err(format!(
+                "invalid starrocks.format '{other}'; allowed: json,csv"
+            )),
+        }
+    }
+}
+
+/// Configuration for building a [`StarRocksSink`](crate::starrocks::StarRocksSink).
+#[derive(Educe, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[educe(Debug, Default)]
+pub struct StarRocksSinkConfig {
+    /// 不含库名的 MySQL 协议连接串，用于建库建表与读取表结构，如 `mysql://fe:9030`
+    pub endpoint: String,
+    /// Stream Load 使用的 FE HTTP 地址；缺省为 endpoint 主机的 8030 端口
+    #[serde(default)]
+    pub http_endpoint: Option<String>,
+    pub database: String,
+    pub user: String,
+    #[educe(Debug(ignore))]
+    pub password: String,
+    pub table: String,
+    /// 表不存在时执行的建表模板，`{table}` 替换为表名
+    #[serde(default)]
+    pub create_table: Option<String>,
+    /// 攒满该条数即发起一次 Stream Load
+    #[educe(Default = 1024)]
+    pub batch_size: usize,
+    #[serde(default)]
+    pub format: StarRocksFormat,
+    /// CSV 列分隔符
+    #[educe(Default = "\t")]
+    pub column_separator: String,
+    /// 目标列白名单：配置后仅写入这些列
+    #[serde(default)]
+    pub columns: Option<Vec<String>>,
+}
+
+impl StarRocksSinkConfig {
+    /// Stream Load 的 HTTP 基地址：显式配置的 `http_endpoint`，否则为 `http://<endpoint 主机>:8030`。
+    pub fn stream_load_base(&self) -> String {
+        if let Some(http) = &self.http_endpoint {
+            return http.trim_end_matches('/').to_string();
+        }
+        let (base, _) = split_query(&self.endpoint);
+        let authority = base.split_once("://").map_or(base, |(_, rest)| rest);
+        let authority = authority.split('/').next().unwrap_or(authority);
+        let authority = authority
+            .rsplit_once('@')
+            .map_or(authority, |(_, host)| host);
+        let host = authority
+            .rsplit_once(':')
+            .map_or(authority, |(host, _)| host);
+        format!("http://{}:{}", host, DEFAULT_HTTP_PORT)
+    }
+
+    /// 带数据库后缀的连接串：`<endpoint>/<database>[?params]`。
+    pub fn database_dsn(&self) -> String {
+        let (base, query) = split_query(&self.endpoint);
+        let mut dsn = format!("{}/{}", base.trim_end_matches('/'), self.database);
+        if let Some(q) = query {
+            dsn.push('?');
+            dsn.push_str(q);
+        }
+        dsn
+    }
+}
+
+fn split_query(input: &str) -> (&str, Option<&str>) {
+    match input.split_once('?') {
+        Some((left, right)) => (left, Some(right)),
+        None => (input, None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn endpoints_derive_from_mysql_dsn() {
+        let conf = StarRocksSinkConfig {
+            endpoint: "mysql://root@fe.local:9030?ssl-mode=disabled".into(),
+            database: "wp".into(),
+            ..Default::default()
+        };
+        assert_eq!(conf.stream_load_base(), "http://fe.local:8030");
+        assert_eq!(
+            conf.database_dsn(),
+            "mysql://root@fe.local:9030/wp?ssl-mode=disabled"
+        );
+        let conf = StarRocksSinkConfig {
+            http_endpoint: Some("http://lb:18030/".into()),
+            ..conf
+        };
+        assert_eq!(conf.stream_load_base(), "http://lb:18030");
+
+        assert_eq!("CSV".parse::<StarRocksFormat>(), Ok(StarRocksFormat::Csv));
+        assert!("parquet".parse::<StarRocksFormat>().is_err());
+    }
+}
@@ -0,0 +1,231 @@
+use async_trait::async_trait;
+use serde_json::{Value, json};
+use wp_connector_api::{
+    ConnectorDef, ConnectorScope, ParamMap, SinkBuildCtx, SinkDefProvider, SinkError, SinkFactory,
+    SinkHandle, SinkReason, SinkResult, SinkSpec,
+};
+
+use crate::common::enrich::{EnrichConf, EnrichSink};
+use crate::common::retry::{self, RetryingSink};
+use crate::common::secret;
+use crate::common::stats;
+use crate::common::trace_context::TraceContext;
+use crate::common::transform::FieldTransforms;
+use crate::starrocks::config::{StarRocksFormat, StarRocksSinkConfig};
+use crate::starrocks::sink::StarRocksSink;
+
+pub struct StarRocksSinkFactory;
+
+#[async_trait]
+impl SinkFactory for StarRocksSinkFactory {
+    fn kind(&self) -> &'static str {
+        "starrocks"
+    }
+
+    fn validate_spec(&self, spec: &SinkSpec) -> SinkResult<()> {
+        let spec = &secret::resolve_sink_spec(spec)?;
+        build_conf(&spec.params)?;
+        EnrichConf::from_params(&spec.params)?;
+        retry::validate_params(&spec.params)?;
+        FieldTransforms::from_params(&spec.params)?;
+        TraceContext::from_params(&spec.params)?;
+        Ok(())
+    }
+
+    async fn build(&self, spec: &SinkSpec, _ctx: &SinkBuildCtx) -> SinkResult<SinkHandle> {
+        let spec = &secret::resolve_sink_spec(spec)?;
+        let conf = build_conf(&spec.params)?;
+        let sink = StarRocksSink::new(conf)
+            .await
+            .map_err(|err| {
+                SinkError::from(SinkReason::sink(format!(
+                    "init starrocks sink failed: {err}"
+                )))
+            })?
+            .with_trace_context(TraceContext::from_params(&spec.params)?)
+            .with_stats(stats::register(&spec.name, self.kind()));
+        let enrich = EnrichConf::from_params(&spec.params)?;
+        let transforms = FieldTransforms::from_params(&spec.params)?;
+        let sink = RetryingSink::from_spec(sink, spec).await?;
+        let sink = EnrichSink::new(sink, enrich).with_transforms(transforms);
+        Ok(SinkHandle::new(Box::new(sink)))
+    }
+}
+
+impl SinkDefProvider for StarRocksSinkFactory {
+    fn sink_def(&self) -> ConnectorDef {
+        ConnectorDef {
+            id: "starrocks_sink".into(),
+            kind: self.kind().into(),
+            scope: ConnectorScope::Sink,
+            allow_override: vec![
+                "endpoint",
+                "http_endpoint",
+                "database",
+                "user",
+                "password",
+                "secret_ref",
+                "table",
+                "create_table",
+                "batch",
+                "format",
+                "column_separator",
+                "columns",
+                "enrich",
+                "enrich_overwrite",
+                "field_transforms",
+                "retry",
+                "write_deadline_ms",
+                "quarantine",
+                "trace_context",
+                "trace_id_field",
+                "tracestate",
+            ]
+            .into_iter()
+            .map(str::to_string)
+            .collect(),
+            default_params: starrocks_defaults(),
+            origin: Some("wp-connectors:starrocks_sink".into()),
+        }
+    }
+}
+
+fn starrocks_defaults() -> ParamMap {
+    let defaults = StarRocksSinkConfig::default();
+    let mut params = ParamMap::new();
+    params.insert("endpoint".into(), json!("mysql://127.0.0.1:9030"));
+    params.insert("database".into(), json!("wp_data"));
+    params.insert("user".into(), json!("root"));
+    params.insert("password".into(), json!(""));
+    params.insert("table".into(), json!("wp_events"));
+    params.insert("batch".into(), json!(defaults.batch_size));
+    params.insert("format".into(), json!("json"));
+    params.insert("column_separator".into(), json!(defaults.column_separator));
+    params
+}
+
+/// 由参数构建配置：`endpoint`/`database`/`user`/`table` 必填。
+fn build_conf(params: &ParamMap) -> SinkResult<StarRocksSinkConfig> {
+    let mut conf = StarRocksSinkConfig {
+        endpoint: required_str(params, "endpoint")?,
+        database: required_str(params, "database")?,
+        user: required_str(params, "user")?,
+        password: opt_str(params, "password")?.unwrap_or_default(),
+        table: required_str(params, "table")?,
+        http_endpoint: opt_str(params, "http_endpoint")?,
+        create_table: opt_str(params, "create_table")?,
+        ..Default::default()
+    };
+    if let Some(http) = &conf.http_endpoint
+        && !http.starts_with("http://")
+        && !http.starts_with("https://")
+    {
+        return Err(SinkReason::sink(
+            "starrocks.http_endpoint must start with http:// or https://",
+        )
+        .into());
+    }
+    if let Some(batch) = params.get("batch").filter(|v| !v.is_null()) {
+        conf.batch_size = batch
+            .as_u64()
+            .filter(|n| *n > 0)
+            .ok_or_else(|| SinkReason::sink("starrocks.batch must be a positive integer"))?
+            as usize;
+    }
+    if let Some(format) = opt_str(params, "format")? {
+        conf.format = format
+            .parse::<StarRocksFormat>()
+            .map_err(SinkReason::sink)?;
+    }
+    // 分隔符可以是空白字符（如缺省的制表符），不做 trim
+    match params.get("column_separator") {
+        None | Some(Value::Null) => {}
+        Some(Value::String(sep)) if !sep.is_empty() && !sep.contains(['\n', '\r', '"', '\\']) => {
+            conf.column_separator = sep.clone();
+        }
+        Some(_) => {
+            return Err(SinkReason::sink(
+                "starrocks.column_separator must be a non-empty string without newlines, quotes or backslashes",
+            )
+            .into());
+        }
+    }
+    conf.columns = match params.get("columns") {
+        None | Some(Value::Null) => None,
+        Some(Value::Array(items)) if !items.is_empty() => Some(
+            items
+                .iter()
+                .map(|item| {
+                    item.as_str()
+                        .map(|s| s.trim().to_string())
+                        .filter(|s| !s.is_empty())
+                        .ok_or_else(|| {
+                            SinkReason::sink("starrocks.columns must be an array of column names")
+                        })
+                })
+                .collect::<Result<_, _>>()?,
+        ),
+        Some(_) => {
+            return Err(
+                SinkReason::sink("starrocks.columns must be a non-empty array of strings").into(),
+            );
+        }
+    };
+    Ok(conf)
+}
+
+fn required_str(params: &ParamMap, key: &str) -> SinkResult<String> {
+    opt_str(params, key)?
+        .ok_or_else(|| SinkReason::sink(format!("starrocks.{key} must not be empty")).into())
+}
+
+/// 可选的非空字符串参数。
+fn opt_str(params: &ParamMap, key: &str) -> SinkResult<Option<String>> {
+    match params.get(key) {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::String(s)) if s.trim().is_empty() => Ok(None),
+        Some(Value::String(s)) => Ok(Some(s.trim().to_string())),
+        Some(_) => Err(SinkReason::sink(format!("starrocks.{key} must be a string")).into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn build_conf_applies_defaults_and_checks_params() {
+        let conf = build_conf(&starrocks_defaults()).expect("defaults are valid");
+        assert_eq!(conf.format, StarRocksFormat::Json);
+        assert_eq!(conf.column_separator, "\t");
+        assert_eq!(conf.batch_size, 1024);
+        assert_eq!(conf.stream_load_base(), "http://127.0.0.1:8030");
+
+        let mut p = starrocks_defaults();
+        p.insert("format".into(), json!("csv"));
+        p.insert("column_separator".into(), json!("|"));
+        p.insert("columns".into(), json!(["name", "score"]));
+        p.insert(
+            "create_table".into(),
+            json!("CREATE TABLE {table} (name STRING)"),
+        );
+        let conf = build_conf(&p).expect("csv");
+        assert_eq!(conf.format, StarRocksFormat::Csv);
+        assert_eq!(conf.column_separator, "|");
+        assert_eq!(conf.columns, Some(vec!["name".into(), "score".into()]));
+        assert!(conf.create_table.is_some());
+
+        for (key, value) in [
+            ("table", json!("")),
+            ("batch", json!(0)),
+            ("format", json!("parquet")),
+            ("column_separator", json!("\n")),
+            ("columns", json!([])),
+            ("http_endpoint", json!("fe:8030")),
+        ] {
+            let mut p = starrocks_defaults();
+            p.insert(key.into(), value);
+            assert!(build_conf(&p).is_err(), "{key} should be rejected");
+        }
+    }
+}
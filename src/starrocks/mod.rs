@@ -0,0 +1,12 @@
+//! StarRocks sink：沿用 Doris 的 Stream Load 导入方式。
+//!
+//! 建 sink 时经 MySQL 协议建库建表并读取列序，写入以 JSON 或 CSV 批次走 HTTP Stream Load，
+//! 每批携带唯一 label 以保证重试幂等。
+
+mod config;
+mod factory;
+mod sink;
+
+pub use config::{StarRocksFormat, StarRocksSinkConfig};
+pub use factory::StarRocksSinkFactory;
+pub use sink::StarRocksSink;
@@ -0,0 +1,573 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use sqlx::mysql::{MySqlConnectOptions, MySqlPoolOptions};
+use sqlx::raw_sql;
+use wp_connector_api::{
+    AsyncCtrl, AsyncRawDataSink, AsyncRecordSink, SinkError, SinkReason, SinkResult,
+};
+use wp_log::warn_data;
+use wp_model_core::model::{DataRecord, DataType};
+
+use crate::common::reconnect::{ReconnectCoordinator, backend_key, probe_endpoint};
+use crate::common::retry::{self, PendingFlush};
+use crate::common::stats::StatsHandle;
+use crate::common::trace_context::TraceContext;
+use crate::doris::stream_load::{StreamLoadFormat, StreamLoader, next_label};
+use crate::doris::{ensure_table_exists, load_table_columns, quote_identifier, sanitize_options};
+use crate::starrocks::config::{StarRocksFormat, StarRocksSinkConfig};
+
+/// CSV 中的 NULL 标记
+const CSV_NULL: &str = "\\N";
+
+/// 已冻结的批次：label 与请求体在首次导入前确定，成功前的重试原样重发。
+struct FrozenBatch {
+    label: String,
+    body: Vec<u8>,
+    /// 对应缓存开头的记录数
+    records: usize,
+    /// 其中没有可写字段、未写入请求体的记录数，导入成功时计为失败
+    unwritable: usize,
+    /// 批内第一条记录携带的 trace id
+    trace_id: Option<String>,
+}
+
+/// 以 Stream Load 攒批写入 StarRocks：建 sink 时经 MySQL 协议建库建表并读取列序，
+/// 每批携带唯一 label，导入失败时保留批次与 label 原样重试，由服务端去重。
+pub struct StarRocksSink {
+    loader: StreamLoader,
+    /// Stream Load 所在的 FE HTTP 地址，重连时探测
+    load_base: String,
+    database: String,
+    table: String,
+    format: StarRocksFormat,
+    column_separator: String,
+    /// 写入列，按表结构顺序
+    columns: Vec<String>,
+    column_set: HashSet<String>,
+    batch_size: usize,
+    pending: Vec<DataRecord>,
+    /// 导入中的批次，成功前重试沿用；期间新到的记录留待下一批次
+    in_flight: Option<FrozenBatch>,
+    /// 为每个导入请求附加 W3C trace 头；为 None 时不附加
+    trace_context: Option<TraceContext>,
+    /// 累计进入缓存的记录数
+    accepted: u64,
+    /// `/stats` 自省状态（缓存、最近导入与错误）
+    stats: StatsHandle,
+}
+
+impl StarRocksSink {
+    /// 建库建表（按需）、读取表结构并准备 Stream Load 客户端。
+    pub async fn new(config: StarRocksSinkConfig) -> anyhow::Result<Self> {
+        let admin = MySqlPoolOptions::new()
+            .max_connections(1)
+            .connect_with(sanitize_options(
+                config.endpoint.parse::<MySqlConnectOptions>()?,
+                &config.user,
+                &config.password,
+            ))
+            .await?;
+        let create_sql = format!(
+            "CREATE DATABASE IF NOT EXISTS {}",
+            quote_identifier(&config.database)
+        );
+        raw_sql(&create_sql).execute(&admin).await?;
+        admin.close().await;
+
+        let pool = MySqlPoolOptions::new()
+            .max_connections(1)
+            .connect_with(sanitize_options(
+                config.database_dsn().parse::<MySqlConnectOptions>()?,
+                &config.user,
+                &config.password,
+            ))
+            .await?;
+        ensure_table_exists(
+            &pool,
+            &config.database,
+            &config.table,
+            config.create_table.as_deref(),
+        )
+        .await?;
+        let table_columns = load_table_columns(&pool, &config.database, &config.table).await?;
+        pool.close().await;
+
+        let columns = select_columns(
+            table_columns.into_iter().map(|(name, _)| name).collect(),
+            config.columns.as_deref(),
+        )?;
+        Self::with_columns(&config, columns)
+    }
+
+    /// 以给定列序构建，不访问 MySQL 协议端口。
+    fn with_columns(config: &StarRocksSinkConfig, columns: Vec<String>) -> anyhow::Result<Self> {
+        let format = match config.format {
+            StarRocksFormat::Json => StreamLoadFormat::Json,
+            StarRocksFormat::Csv => StreamLoadFormat::Csv {
+                column_separator: config.column_separator.clone(),
+                columns: columns.clone(),
+            },
+        };
+        // StarRocks FE 拒绝不带 100-continue 的 Stream Load 请求
+        let loader = StreamLoader::new(
+            &config.stream_load_base(),
+            &config.database,
+            &config.user,
+            &config.password,
+        )?
+        .with_format(format)
+        .with_header("Expect", "100-continue");
+        Ok(Self {
+            loader,
+            load_base: config.stream_load_base(),
+            database: config.database.clone(),
+            table: config.table.clone(),
+            format: config.format,
+            column_separator: config.column_separator.clone(),
+            column_set: columns.iter().cloned().collect(),
+            columns,
+            batch_size: config.batch_size.max(1),
+            pending: Vec::new(),
+            in_flight: None,
+            trace_context: None,
+            accepted: 0,
+            stats: StatsHandle::detached(&config.table, "starrocks"),
+        })
+    }
+
+    pub fn with_trace_context(mut self, trace_context: Option<TraceContext>) -> Self {
+        self.trace_context = trace_context;
+        self
+    }
+
+    /// 使用外部登记的状态句柄（见 [`crate::common::stats::register`]），
+    /// 以目标表作为指标的写入目标。
+    pub fn with_stats(mut self, stats: StatsHandle) -> Self {
+        self.stats = stats.with_destination(self.table.as_str());
+        self
+    }
+
+    /// 记录中落在写入列内的字段；同名字段取第一个。
+    fn writable_fields<'a>(&self, record: &'a DataRecord) -> HashMap<&'a str, String> {
+        let mut fields = HashMap::new();
+        for field in &record.items {
+            if *field.get_meta() == DataType::Ignore || !self.column_set.contains(field.get_name())
+            {
+                continue;
+            }
+            fields
+                .entry(field.get_name())
+                .or_insert_with(|| field.get_value().to_string());
+        }
+        fields
+    }
+
+    /// 将缓存批次编码为请求体，同时返回没有可写字段而被略过的记录数；
+    /// 所有记录都没有可写字段时请求体为 None。
+    fn load_body(&self) -> (Option<Vec<u8>>, usize) {
+        let rows: Vec<HashMap<&str, String>> = self
+            .pending
+            .iter()
+            .map(|record| self.writable_fields(record))
+            .filter(|fields| !fields.is_empty())
+            .collect();
+        let unwritable = self.pending.len() - rows.len();
+        if rows.is_empty() {
+            return (None, unwritable);
+        }
+        let body = match self.format {
+            StarRocksFormat::Json => {
+                let rows: Vec<serde_json::Map<String, serde_json::Value>> = rows
+                    .into_iter()
+                    .map(|fields| {
+                        // 按列序输出，便于排查导入失败的批次
+                        self.columns
+                            .iter()
+                            .filter_map(|col| {
+                                fields
+                                    .get(col.as_str())
+                                    .map(|v| (col.clone(), serde_json::Value::String(v.clone())))
+                            })
+                            .collect()
+                    })
+                    .collect();
+                serde_json::to_vec(&rows).ok()
+            }
+            StarRocksFormat::Csv => {
+                let mut body = String::new();
+                for fields in rows {
+                    let line = self
+                        .columns
+                        .iter()
+                        .map(|col| match fields.get(col.as_str()) {
+                            Some(value) => csv_value(value, &self.column_separator),
+                            None => CSV_NULL.to_string(),
+                        })
+                        .collect::<Vec<_>>()
+                        .join(&self.column_separator);
+                    body.push_str(&line);
+                    body.push('\n');
+                }
+                Some(body.into_bytes())
+            }
+        };
+        (body, unwritable)
+    }
+
+    /// 将缓存冻结为待导入批次；缓存为空或没有可写字段时返回 false。
+    ///
+    /// 没有可写字段的记录不进入请求体，记为投递失败并记录错误：整批都不可写时直接丢弃缓存，
+    /// 否则随批次导入成功时计数。
+    fn freeze_batch(&mut self) -> bool {
+        if self.pending.is_empty() {
+            return false;
+        }
+        let (body, unwritable) = self.load_body();
+        if unwritable > 0 {
+            let msg = format!(
+                "starrocks dropped {} records without writable columns for table `{}`",
+                unwritable, self.table
+            );
+            warn_data!("{}", msg);
+            self.stats.record_error(msg);
+        }
+        let Some(body) = body else {
+            self.stats.record_delivery(0, unwritable as u64);
+            self.pending.clear();
+            self.stats.set_buffered(0);
+            return false;
+        };
+        let trace_id = self.trace_context.as_ref().and_then(|trace| {
+            self.pending
+                .iter()
+                .find_map(|record| trace.record_trace_id(record))
+        });
+        self.in_flight = Some(FrozenBatch {
+            label: next_label(&self.database, &self.table),
+            body,
+            records: self.pending.len(),
+            unwritable,
+            trace_id,
+        });
+        true
+    }
+
+    /// 导入冻结的批次，成功后继续导入其后缓存的记录；失败时保留记录与冻结的批次，
+    /// 下次以同一 label 与请求体重试。
+    async fn flush_pending(&mut self) -> SinkResult<()> {
+        loop {
+            if self.in_flight.is_none() && !self.freeze_batch() {
+                return Ok(());
+            }
+            let Some(batch) = &self.in_flight else {
+                return Ok(());
+            };
+            let trace = self
+                .trace_context
+                .as_ref()
+                .map(|trace| trace.headers_with_id(batch.trace_id.clone()))
+                .unwrap_or_default();
+            let result = self
+                .loader
+                .load_with_headers(&self.table, &batch.label, batch.body.clone(), &trace)
+                .await
+                .map_err(|e| {
+                    let msg = format!("starrocks stream load fail (label {}): {}", batch.label, e);
+                    retry::reclassify(&e, msg)
+                });
+            self.stats.record_flush(&result);
+            result?;
+            let (records, unwritable) = (batch.records, batch.unwritable);
+            self.stats
+                .record_delivery((records - unwritable) as u64, unwritable as u64);
+            self.pending.drain(..records);
+            self.in_flight = None;
+            self.stats.set_buffered(self.pending.len());
+        }
+    }
+
+    async fn flush_if_full(&mut self) -> SinkResult<()> {
+        self.stats.set_buffered(self.pending.len());
+        if self.pending.len() >= self.batch_size {
+            self.flush_pending().await?;
+        }
+        Ok(())
+    }
+}
+
+/// 按白名单筛选表列（保持表结构列序），白名单中不存在的列视为配置错误。
+fn select_columns(
+    columns: Vec<String>,
+    whitelist: Option<&[String]>,
+) -> anyhow::Result<Vec<String>> {
+    if columns.is_empty() {
+        anyhow::bail!("starrocks table has no columns");
+    }
+    let Some(allowed) = whitelist else {
+        return Ok(columns);
+    };
+    if let Some(unknown) = allowed.iter().find(|col| !columns.contains(col)) {
+        anyhow::bail!("starrocks.columns references unknown column `{unknown}`");
+    }
+    Ok(columns
+        .into_iter()
+        .filter(|col| allowed.contains(col))
+        .collect())
+}
+
+/// CSV 单元格：含分隔符、换行、引号或反斜杠时以 `"` 包裹，内部的 `"` 与 `\` 以 `\` 转义。
+fn csv_value(value: &str, separator: &str) -> String {
+    let needs_enclose = value.contains(separator) || value.contains(['\n', '\r', '"', '\\']);
+    if !needs_enclose {
+        return value.to_string();
+    }
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('"');
+    for c in value.chars() {
+        if c == '"' || c == '\\' {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out.push('"');
+    out
+}
+
+fn sink_error(msg: impl Into<String>) -> SinkError {
+    SinkError::from(SinkReason::Sink(msg.into()))
+}
+
+#[async_trait]
+impl PendingFlush for StarRocksSink {
+    fn pending_len(&self) -> usize {
+        self.pending.len()
+    }
+
+    async fn flush_now(&mut self) -> SinkResult<()> {
+        self.flush_pending().await
+    }
+
+    fn discard_pending(&mut self) -> usize {
+        let discarded = self.pending.len();
+        self.pending.clear();
+        self.in_flight = None;
+        self.stats.set_buffered(0);
+        discarded
+    }
+
+    fn accepted(&self) -> u64 {
+        self.accepted
+    }
+}
+
+#[async_trait]
+impl AsyncCtrl for StarRocksSink {
+    async fn stop(&mut self) -> SinkResult<()> {
+        self.flush_pending().await
+    }
+
+    /// 不发导入请求，只确认 FE HTTP 端口可连通，按后端共享退避。
+    async fn reconnect(&mut self) -> SinkResult<()> {
+        ReconnectCoordinator::shared(
+            backend_key("starrocks", &self.load_base),
+            Default::default(),
+        )
+        .run(|| probe_endpoint(&self.load_base))
+        .await
+    }
+}
+
+#[async_trait]
+impl AsyncRecordSink for StarRocksSink {
+    async fn sink_record(&mut self, data: &DataRecord) -> SinkResult<()> {
+        self.pending.push(data.clone());
+        self.accepted += 1;
+        self.flush_if_full().await
+    }
+
+    async fn sink_records(&mut self, data: Vec<Arc<DataRecord>>) -> SinkResult<()> {
+        self.pending
+            .extend(data.iter().map(|record| record.as_ref().clone()));
+        self.accepted += data.len() as u64;
+        self.flush_if_full().await
+    }
+}
+
+#[async_trait]
+impl AsyncRawDataSink for StarRocksSink {
+    async fn sink_str(&mut self, _data: &str) -> SinkResult<()> {
+        Err(sink_error("starrocks sink does not accept raw text input"))
+    }
+
+    async fn sink_bytes(&mut self, _data: &[u8]) -> SinkResult<()> {
+        Err(sink_error("starrocks sink does not accept raw byte input"))
+    }
+
+    async fn sink_str_batch(&mut self, _data: Vec<&str>) -> SinkResult<()> {
+        Err(sink_error("starrocks sink does not accept raw batch input"))
+    }
+
+    async fn sink_bytes_batch(&mut self, _data: Vec<&[u8]>) -> SinkResult<()> {
+        Err(sink_error(
+            "starrocks sink does not accept raw batch byte input",
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use httpmock::prelude::*;
+    use wp_model_core::model::DataField;
+
+    fn columns(names: &[&str]) -> Vec<String> {
+        names.iter().map(|n| n.to_string()).collect()
+    }
+
+    fn test_sink(base: &str, format: StarRocksFormat, batch_size: usize) -> StarRocksSink {
+        let conf = StarRocksSinkConfig {
+            endpoint: "mysql://127.0.0.1:9030".into(),
+            http_endpoint: Some(base.to_string()),
+            database: "wp".into(),
+            user: "root".into(),
+            table: "events".into(),
+            batch_size,
+            format,
+            ..Default::default()
+        };
+        StarRocksSink::with_columns(&conf, columns(&["name", "score", "note"])).expect("sink")
+    }
+
+    fn record(fields: &[(&str, &str)]) -> DataRecord {
+        let mut record = DataRecord::default();
+        for (name, value) in fields {
+            record.append(DataField::from_chars(*name, *value));
+        }
+        record
+    }
+
+    #[test]
+    fn bodies_follow_column_order() {
+        let mut sink = test_sink("http://127.0.0.1:8030", StarRocksFormat::Json, 10);
+        sink.pending = vec![
+            record(&[("score", "9.5"), ("name", "a\"b"), ("unknown", "x")]),
+            record(&[("unknown", "only")]),
+            record(&[("name", "c")]),
+        ];
+        let (body, unwritable) = sink.load_body();
+        assert_eq!(
+            String::from_utf8(body.unwrap()).unwrap(),
+            r#"[{"name":"a\"b","score":"9.5"},{"name":"c"}]"#
+        );
+        assert_eq!(unwritable, 1);
+
+        sink.format = StarRocksFormat::Csv;
+        sink.pending = vec![
+            record(&[("note", "tab\there"), ("name", "a\"b\\c"), ("score", "1")]),
+            record(&[("name", "plain")]),
+        ];
+        assert_eq!(
+            String::from_utf8(sink.load_body().0.unwrap()).unwrap(),
+            "\"a\\\"b\\\\c\"\t1\t\"tab\there\"\nplain\t\\N\t\\N\n"
+        );
+
+        sink.pending = vec![record(&[("unknown", "only")])];
+        assert_eq!(sink.load_body(), (None, 1));
+    }
+
+    #[tokio::test]
+    async fn unwritable_records_count_as_failed() {
+        let server = MockServer::start_async().await;
+        let load = server.mock(|when, then| {
+            when.method(PUT)
+                .path("/api/wp/events/_stream_load")
+                .body("a\t\\N\t\\N\n");
+            then.status(200).body(r#"{"Status":"Success"}"#);
+        });
+        let mut sink = test_sink(&server.base_url(), StarRocksFormat::Csv, 2);
+        sink.sink_record(&record(&[("unknown", "x")]))
+            .await
+            .expect("buffered");
+        sink.sink_record(&record(&[("name", "a")]))
+            .await
+            .expect("loaded");
+        load.assert_hits(1);
+        sink.sink_record(&record(&[("unknown", "y")]))
+            .await
+            .expect("buffered");
+        sink.flush_now().await.expect("nothing to load");
+        load.assert_hits(1);
+
+        let stats = sink.stats.snapshot();
+        assert_eq!(stats.succeeded_records, 1);
+        assert_eq!(stats.failed_records, 2);
+        assert_eq!(stats.buffered_records, 0);
+        assert!(
+            stats
+                .last_error
+                .is_some_and(|e| e.contains("without writable columns"))
+        );
+    }
+
+    #[test]
+    fn column_whitelist_keeps_table_order() {
+        let table = columns(&["id", "name", "score"]);
+        assert_eq!(
+            select_columns(table.clone(), Some(&columns(&["score", "name"]))).unwrap(),
+            columns(&["name", "score"])
+        );
+        assert!(select_columns(table, Some(&columns(&["nope"]))).is_err());
+        assert!(select_columns(Vec::new(), None).is_err());
+    }
+
+    #[tokio::test]
+    async fn failed_batch_retries_with_same_label() {
+        let server = MockServer::start_async().await;
+        let mut rejected = server.mock(|when, then| {
+            when.method(PUT).path("/api/wp/events/_stream_load");
+            then.status(200)
+                .body(r#"{"Status":"Fail","Message":"too many filtered rows"}"#);
+        });
+        let mut sink = test_sink(&server.base_url(), StarRocksFormat::Csv, 2);
+        sink.sink_record(&record(&[("name", "a")]))
+            .await
+            .expect("buffered");
+        rejected.assert_hits(0);
+        let err = sink
+            .sink_record(&record(&[("name", "b")]))
+            .await
+            .expect_err("rejected");
+        assert!(format!("{err}").contains("too many filtered rows"), "{err}");
+        let label = sink.in_flight.as_ref().expect("batch kept").label.clone();
+        assert!(label.starts_with("wp_wp_events_"), "{label}");
+        assert_eq!(sink.pending_len(), 2);
+        rejected.delete();
+        // 失败后到达的记录不并入已冻结的批次
+        sink.pending.push(record(&[("name", "c")]));
+
+        let accepted = server.mock(|when, then| {
+            when.method(PUT)
+                .path("/api/wp/events/_stream_load")
+                .header("label", label.as_str())
+                .header("format", "csv")
+                .header("columns", "name,score,note")
+                .header("expect", "100-continue")
+                .body("a\t\\N\t\\N\nb\t\\N\t\\N\n");
+            then.status(200)
+                .body(r#"{"Status":"Label Already Exists","ExistingJobStatus":"FINISHED"}"#);
+        });
+        let next = server.mock(|when, then| {
+            when.method(PUT)
+                .path("/api/wp/events/_stream_load")
+                .body("c\t\\N\t\\N\n");
+            then.status(200).body(r#"{"Status":"Success"}"#);
+        });
+        sink.flush_now().await.expect("retried with same label");
+        accepted.assert_hits(1);
+        next.assert_hits(1);
+        assert_eq!(sink.pending_len(), 0);
+        assert!(sink.in_flight.is_none());
+    }
+}
@@ -0,0 +1,123 @@
+use async_trait::async_trait;
+use serde_json::json;
+use wp_connector_api::{
+    ConnectorDef, ConnectorScope, ParamMap, SinkBuildCtx, SinkDefProvider, SinkFactory, SinkHandle,
+    SinkReason, SinkResult, SinkSpec,
+};
+use wp_model_core::model::fmt_def::TextFmt;
+
+use super::sink::{StdTarget, StdoutSink};
+
+pub struct StdoutSinkFactory;
+
+/// 解析后的配置：输出目标、格式与是否美化 JSON。
+#[derive(Debug, PartialEq)]
+struct StdoutConf {
+    target: StdTarget,
+    fmt: String,
+    pretty: bool,
+}
+
+fn build_conf(params: &ParamMap) -> SinkResult<StdoutConf> {
+    let target = match params.get("target").filter(|v| !v.is_null()) {
+        None => StdTarget::Stdout,
+        Some(v) => match v.as_str() {
+            Some("stdout") => StdTarget::Stdout,
+            Some("stderr") => StdTarget::Stderr,
+            _ => {
+                return Err(
+                    SinkReason::sink("stdout.target must be one of: stdout, stderr").into(),
+                );
+            }
+        },
+    };
+    let fmt = params
+        .get("fmt")
+        .and_then(|v| v.as_str())
+        .unwrap_or("json")
+        .to_string();
+    let pretty = match params.get("pretty").filter(|v| !v.is_null()) {
+        None => false,
+        Some(v) => v
+            .as_bool()
+            .ok_or_else(|| SinkReason::sink("stdout.pretty must be a boolean"))?,
+    };
+    Ok(StdoutConf {
+        target,
+        fmt,
+        pretty,
+    })
+}
+
+#[async_trait]
+impl SinkFactory for StdoutSinkFactory {
+    fn kind(&self) -> &'static str {
+        "stdout"
+    }
+
+    fn validate_spec(&self, spec: &SinkSpec) -> SinkResult<()> {
+        build_conf(&spec.params)?;
+        Ok(())
+    }
+
+    async fn build(&self, spec: &SinkSpec, _ctx: &SinkBuildCtx) -> SinkResult<SinkHandle> {
+        let conf = build_conf(&spec.params)?;
+        let sink =
+            StdoutSink::new(conf.target, TextFmt::from(conf.fmt.as_str())).with_pretty(conf.pretty);
+        Ok(SinkHandle::new(Box::new(sink)))
+    }
+}
+
+impl SinkDefProvider for StdoutSinkFactory {
+    fn sink_def(&self) -> ConnectorDef {
+        ConnectorDef {
+            id: "stdout_sink".into(),
+            kind: self.kind().into(),
+            scope: ConnectorScope::Sink,
+            allow_override: vec!["target", "fmt", "pretty"]
+                .into_iter()
+                .map(str::to_string)
+                .collect(),
+            default_params: stdout_defaults(),
+            origin: Some("wp-connectors:stdout_sink".into()),
+        }
+    }
+}
+
+fn stdout_defaults() -> ParamMap {
+    let mut params = ParamMap::new();
+    params.insert("target".into(), json!(StdTarget::default().as_str()));
+    params.insert("fmt".into(), json!("json"));
+    params.insert("pretty".into(), json!(false));
+    params
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn build_conf_parses_target_and_pretty() {
+        let conf = build_conf(&stdout_defaults()).unwrap();
+        assert_eq!(
+            conf,
+            StdoutConf {
+                target: StdTarget::Stdout,
+                fmt: "json".into(),
+                pretty: false,
+            }
+        );
+        let mut params = stdout_defaults();
+        params.insert("target".into(), json!("stderr"));
+        params.insert("pretty".into(), json!(true));
+        let conf = build_conf(&params).unwrap();
+        assert_eq!(conf.target, StdTarget::Stderr);
+        assert!(conf.pretty);
+
+        params.insert("target".into(), json!("file"));
+        assert!(build_conf(&params).is_err());
+        params.insert("target".into(), json!("stdout"));
+        params.insert("pretty".into(), json!("yes"));
+        assert!(build_conf(&params).is_err());
+    }
+}
@@ -0,0 +1,7 @@
+//! Stdout sink：把记录按格式打印到 stdout/stderr，便于联调时直接查看数据。
+
+mod factory;
+mod sink;
+
+pub use factory::StdoutSinkFactory;
+pub use sink::{StdTarget, StdoutSink};
@@ -0,0 +1,207 @@
+use std::io::{self, Write};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use wp_connector_api::{
+    AsyncCtrl, AsyncRawDataSink, AsyncRecordSink, SinkError, SinkReason, SinkResult,
+};
+use wp_data_fmt::{DataFormat, FormatType};
+use wp_model_core::model::{DataRecord, fmt_def::TextFmt};
+
+/// 输出目标。
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum StdTarget {
+    #[default]
+    Stdout,
+    Stderr,
+}
+
+impl StdTarget {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Stdout => "stdout",
+            Self::Stderr => "stderr",
+        }
+    }
+}
+
+/// 每条记录按 `fmt` 渲染为一行写到 stdout/stderr；`pretty` 时 JSON 输出为多行缩进格式。
+/// 原始数据按原样输出并补齐换行。
+pub struct StdoutSink {
+    fmt: TextFmt,
+    pretty: bool,
+    writer: Box<dyn Write + Send>,
+}
+
+impl StdoutSink {
+    pub fn new(target: StdTarget, fmt: TextFmt) -> Self {
+        let writer: Box<dyn Write + Send> = match target {
+            StdTarget::Stdout => Box::new(io::stdout()),
+            StdTarget::Stderr => Box::new(io::stderr()),
+        };
+        Self {
+            fmt,
+            pretty: false,
+            writer,
+        }
+    }
+
+    /// 仅对 JSON 格式生效
+    pub fn with_pretty(mut self, pretty: bool) -> Self {
+        self.pretty = pretty;
+        self
+    }
+
+    fn render(&self, record: &DataRecord) -> String {
+        let line = FormatType::from(&self.fmt).format_record(record);
+        if !self.pretty || !matches!(self.fmt, TextFmt::Json) {
+            return line;
+        }
+        serde_json::from_str::<serde_json::Value>(&line)
+            .and_then(|v| serde_json::to_string_pretty(&v))
+            .unwrap_or(line)
+    }
+
+    fn write_lines<'a>(&mut self, lines: impl IntoIterator<Item = &'a [u8]>) -> SinkResult<()> {
+        write_lines(&mut self.writer, lines)
+            .map_err(|e| SinkError::from(SinkReason::sink(format!("stdout write failed: {e}"))))
+    }
+}
+
+fn write_lines<'a>(
+    writer: &mut dyn Write,
+    lines: impl IntoIterator<Item = &'a [u8]>,
+) -> io::Result<()> {
+    for line in lines {
+        writer.write_all(line)?;
+        if !line.ends_with(b"\n") {
+            writer.write_all(b"\n")?;
+        }
+    }
+    writer.flush()
+}
+
+#[async_trait]
+impl AsyncCtrl for StdoutSink {
+    async fn stop(&mut self) -> SinkResult<()> {
+        self.writer
+            .flush()
+            .map_err(|e| SinkError::from(SinkReason::sink(format!("stdout flush failed: {e}"))))
+    }
+
+    async fn reconnect(&mut self) -> SinkResult<()> {
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl AsyncRecordSink for StdoutSink {
+    async fn sink_record(&mut self, data: &DataRecord) -> SinkResult<()> {
+        let line = self.render(data);
+        self.write_lines([line.as_bytes()])
+    }
+
+    async fn sink_records(&mut self, data: Vec<Arc<DataRecord>>) -> SinkResult<()> {
+        let lines: Vec<String> = data.iter().map(|r| self.render(r.as_ref())).collect();
+        self.write_lines(lines.iter().map(|l| l.as_bytes()))
+    }
+}
+
+#[async_trait]
+impl AsyncRawDataSink for StdoutSink {
+    async fn sink_str(&mut self, data: &str) -> SinkResult<()> {
+        self.write_lines([data.as_bytes()])
+    }
+
+    async fn sink_bytes(&mut self, data: &[u8]) -> SinkResult<()> {
+        self.write_lines([data])
+    }
+
+    async fn sink_str_batch(&mut self, data: Vec<&str>) -> SinkResult<()> {
+        self.write_lines(data.into_iter().map(str::as_bytes))
+    }
+
+    async fn sink_bytes_batch(&mut self, data: Vec<&[u8]>) -> SinkResult<()> {
+        self.write_lines(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use wp_model_core::model::DataField;
+
+    /// 捕获输出的共享缓冲
+    #[derive(Clone, Default)]
+    struct Captured(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Captured {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Captured {
+        fn text(&self) -> String {
+            String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+        }
+    }
+
+    fn captured_sink(fmt: TextFmt, pretty: bool) -> (StdoutSink, Captured) {
+        let out = Captured::default();
+        let mut sink = StdoutSink::new(StdTarget::Stdout, fmt).with_pretty(pretty);
+        sink.writer = Box::new(out.clone());
+        (sink, out)
+    }
+
+    fn sample() -> DataRecord {
+        let mut record = DataRecord::default();
+        record.append(DataField::from_chars("user", "alice"));
+        record.append(DataField::from_digit("code", 200));
+        record
+    }
+
+    #[tokio::test]
+    async fn records_are_printed_one_per_line() {
+        let (mut sink, out) = captured_sink(TextFmt::Json, false);
+        sink.sink_records(vec![Arc::new(sample()), Arc::new(sample())])
+            .await
+            .unwrap();
+        let text = out.text();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 2, "{text}");
+        let value: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(value["user"], "alice");
+        assert_eq!(value["code"], 200);
+    }
+
+    #[tokio::test]
+    async fn pretty_prints_json_only() {
+        let (mut sink, out) = captured_sink(TextFmt::Json, true);
+        sink.sink_record(&sample()).await.unwrap();
+        let text = out.text();
+        assert!(text.contains("\n  \"user\": \"alice\""), "{text}");
+        assert!(text.ends_with("}\n"));
+
+        let (mut sink, out) = captured_sink(TextFmt::Csv, true);
+        sink.sink_record(&sample()).await.unwrap();
+        let text = out.text();
+        assert_eq!(text.lines().count(), 1, "{text}");
+        assert!(text.contains("alice"));
+    }
+
+    #[tokio::test]
+    async fn raw_data_is_printed_as_is() {
+        let (mut sink, out) = captured_sink(TextFmt::Json, true);
+        sink.sink_str("plain line").await.unwrap();
+        sink.sink_bytes(b"bytes\n").await.unwrap();
+        sink.sink_str_batch(vec!["a", "b"]).await.unwrap();
+        assert_eq!(out.text(), "plain line\nbytes\na\nb\n");
+    }
+}
@@ -0,0 +1,58 @@
+use serde_json::Value;
+use std::str::FromStr;
+use wp_connector_api::ParamMap;
+
+use crate::common::framing::FramingConf;
+
+/// 套接字角色。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TcpMode {
+    /// 绑定 `address` 并接收任意数量的客户端
+    #[default]
+    Listen,
+    /// 主动连接 `address`，断开后按退避重连
+    Connect,
+}
+
+impl FromStr for TcpMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "listen" => Ok(Self::Listen),
+            "connect" => Ok(Self::Connect),
+            other => Err(format!(
+                "invalid tcp.mode '{other}'; allowed: listen,connect"
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TcpSourceConf {
+    // 监听或连接的地址，如 `0.0.0.0:9000`
+    pub address: String,
+    pub mode: TcpMode,
+    pub framing: FramingConf,
+}
+
+impl TcpSourceConf {
+    /// 读取 `address`/`mode` 及分帧参数。
+    pub fn from_params(params: &ParamMap) -> Result<Self, String> {
+        let address = match params.get("address") {
+            Some(Value::String(s)) if !s.trim().is_empty() => s.trim().to_string(),
+            _ => return Err("tcp.address must be a non-empty string".into()),
+        };
+        let mode = match params.get("mode") {
+            None | Some(Value::Null) => TcpMode::default(),
+            Some(Value::String(s)) => s.parse()?,
+            Some(_) => return Err("tcp.mode must be a string".into()),
+        };
+        let framing = FramingConf::from_params(params).map_err(|e| format!("tcp.{e}"))?;
+        Ok(Self {
+            address,
+            mode,
+            framing,
+        })
+    }
+}
@@ -0,0 +1,66 @@
+use async_trait::async_trait;
+use serde_json::json;
+use wp_connector_api::{
+    ConnectorDef, ConnectorScope, ParamMap, SourceBuildCtx, SourceDefProvider, SourceFactory,
+    SourceHandle, SourceMeta, SourceReason, SourceResult, SourceSpec, SourceSvcIns, Tags,
+};
+
+use crate::tcp::{TcpSource, TcpSourceConf};
+
+pub struct TcpSourceFactory;
+
+#[async_trait]
+impl SourceFactory for TcpSourceFactory {
+    fn kind(&self) -> &'static str {
+        "tcp"
+    }
+
+    fn validate_spec(&self, spec: &SourceSpec) -> SourceResult<()> {
+        TcpSourceConf::from_params(&spec.params).map_err(SourceReason::Other)?;
+        Ok(())
+    }
+
+    async fn build(&self, spec: &SourceSpec, _ctx: &SourceBuildCtx) -> SourceResult<SourceSvcIns> {
+        let conf = TcpSourceConf::from_params(&spec.params).map_err(SourceReason::Other)?;
+        let meta_tags = Tags::from_parse(&spec.tags);
+        let source = TcpSource::new(spec.name.clone(), meta_tags.clone(), &conf)
+            .await
+            .map_err(|err| SourceReason::Other(format!("tcp {} fail: {}", conf.address, err)))?;
+
+        let mut meta = SourceMeta::new(spec.name.clone(), spec.kind.clone());
+        meta.tags = meta_tags;
+        let handle = SourceHandle::new(Box::new(source), meta);
+        Ok(SourceSvcIns::new().with_sources(vec![handle]))
+    }
+}
+
+impl SourceDefProvider for TcpSourceFactory {
+    fn source_def(&self) -> ConnectorDef {
+        ConnectorDef {
+            id: "tcp_src".into(),
+            kind: self.kind().into(),
+            scope: ConnectorScope::Source,
+            allow_override: vec![
+                "address",
+                "mode",
+                "framing",
+                "length_width",
+                "length_endian",
+                "max_frame_bytes",
+            ]
+            .into_iter()
+            .map(str::to_string)
+            .collect(),
+            default_params: tcp_source_defaults(),
+            origin: Some("wp-connectors:tcp_source".into()),
+        }
+    }
+}
+
+fn tcp_source_defaults() -> ParamMap {
+    let mut params = ParamMap::new();
+    params.insert("address".into(), json!("0.0.0.0:9000"));
+    params.insert("mode".into(), json!("listen"));
+    params.insert("framing".into(), json!("newline"));
+    params
+}
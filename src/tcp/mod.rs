@@ -0,0 +1,14 @@
+//! wp-connector-tcp: TCP Source + Factory
+//!
+//! 模块划分：
+//! - config：TcpSourceConf（地址、监听/连接模式、分帧）
+//! - source：TcpSource（监听模式接收多个客户端；连接模式断线后按退避重连）
+//! - factory：Source 工厂
+
+mod config;
+mod factory;
+mod source;
+
+pub use config::{TcpMode, TcpSourceConf};
+pub use factory::TcpSourceFactory;
+pub use source::TcpSource;
@@ -0,0 +1,311 @@
+use async_trait::async_trait;
+use bytes::Bytes;
+use std::net::SocketAddr;
+use tokio::io::AsyncReadExt;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+use tokio::task::{JoinHandle, JoinSet};
+use wp_connector_api::{
+    DataSource, SourceBatch, SourceError, SourceEvent, SourceReason, SourceResult, Tags,
+};
+use wp_parse_api::RawData;
+
+use crate::WP_SRC_VAL;
+use crate::common::framing::{FrameDecoder, FramingConf};
+use crate::common::reconnect::ReconnectPolicy;
+use crate::tcp::config::{TcpMode, TcpSourceConf};
+
+/// 读取任务与 `receive` 之间的缓冲帧数；写满时读取任务等待，对端随之被 TCP 反压。
+const CHANNEL_CAPACITY: usize = 1024;
+/// 单次 `receive` 最多返回的帧数。
+const MAX_BATCH: usize = 128;
+const READ_BUF_SIZE: usize = 64 * 1024;
+
+/// (对端地址, 帧负载)
+type Frame = (String, Vec<u8>);
+
+pub struct TcpSource {
+    key: String,
+    tags: Tags,
+    rx: mpsc::Receiver<Frame>,
+    local_addr: Option<SocketAddr>,
+    task: JoinHandle<()>,
+    event_seq: u64,
+}
+
+impl TcpSource {
+    /// 监听模式在返回前完成绑定，地址被占用等错误直接返回；连接模式在后台建立连接。
+    pub async fn new(key: String, tags: Tags, conf: &TcpSourceConf) -> std::io::Result<Self> {
+        let (tx, rx) = mpsc::channel(CHANNEL_CAPACITY);
+        let framing = conf.framing;
+        let (local_addr, task) = match conf.mode {
+            TcpMode::Listen => {
+                let listener = TcpListener::bind(&conf.address).await?;
+                let local_addr = listener.local_addr()?;
+                wp_log::info_data!("[tcp] {} listening on {}", key, local_addr);
+                (
+                    Some(local_addr),
+                    tokio::spawn(accept_loop(listener, framing, tx)),
+                )
+            }
+            TcpMode::Connect => {
+                let address = conf.address.clone();
+                let policy = ReconnectPolicy::default();
+                (
+                    None,
+                    tokio::spawn(connect_loop(address, framing, policy, tx)),
+                )
+            }
+        };
+        Ok(Self {
+            key,
+            tags,
+            rx,
+            local_addr,
+            task,
+            event_seq: 0,
+        })
+    }
+
+    pub fn identifier(&self) -> &str {
+        &self.key
+    }
+
+    /// 监听模式下实际绑定的地址（`address` 端口为 0 时由系统分配）。
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.local_addr
+    }
+
+    fn create_event(&mut self, peer: String, frame: Vec<u8>) -> SourceEvent {
+        let mut tags = self.tags.clone();
+        tags.set(WP_SRC_VAL, peer);
+        self.event_seq = self.event_seq.wrapping_add(1);
+        SourceEvent::new(
+            self.event_seq,
+            self.key.clone(),
+            RawData::Bytes(Bytes::from(frame)),
+            tags.into(),
+        )
+    }
+}
+
+impl Drop for TcpSource {
+    fn drop(&mut self) {
+        // 中止接收/重连任务；监听模式下各客户端任务随 JoinSet 一并中止
+        self.task.abort();
+    }
+}
+
+/// 接收客户端连接，每个客户端一个读取任务。
+async fn accept_loop(listener: TcpListener, framing: FramingConf, tx: mpsc::Sender<Frame>) {
+    let mut clients = JoinSet::new();
+    loop {
+        while clients.try_join_next().is_some() {}
+        match listener.accept().await {
+            Ok((stream, peer)) => {
+                wp_log::info_data!("[tcp] client connected: {}", peer);
+                let tx = tx.clone();
+                clients.spawn(async move {
+                    let peer = peer.to_string();
+                    match read_frames(stream, &peer, framing, &tx).await {
+                        Ok(()) => wp_log::info_data!("[tcp] client closed: {}", peer),
+                        Err(e) => wp_log::warn_data!("[tcp] client {} dropped: {}", peer, e),
+                    }
+                });
+            }
+            Err(e) => {
+                wp_log::warn_data!("[tcp] accept fail: {}", e);
+                tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+            }
+        }
+        if tx.is_closed() {
+            return;
+        }
+    }
+}
+
+/// 连接远端并读取；连接失败或断开后按退避重连，直至 source 被丢弃。
+async fn connect_loop(
+    address: String,
+    framing: FramingConf,
+    policy: ReconnectPolicy,
+    tx: mpsc::Sender<Frame>,
+) {
+    let mut retry = 0u32;
+    loop {
+        match TcpStream::connect(&address).await {
+            Ok(stream) => {
+                retry = 0;
+                wp_log::info_data!("[tcp] connected to {}", address);
+                match read_frames(stream, &address, framing, &tx).await {
+                    Ok(()) => wp_log::info_data!("[tcp] {} closed the connection", address),
+                    Err(e) => wp_log::warn_data!("[tcp] connection to {} lost: {}", address, e),
+                }
+            }
+            Err(e) => wp_log::warn_data!("[tcp] connect {} fail: {}", address, e),
+        }
+        if tx.is_closed() {
+            return;
+        }
+        retry = retry.saturating_add(1);
+        tokio::time::sleep(policy.backoff(retry)).await;
+    }
+}
+
+/// 读取单个连接直至 EOF，按分帧配置切出帧并送入通道。
+async fn read_frames(
+    mut stream: TcpStream,
+    peer: &str,
+    framing: FramingConf,
+    tx: &mpsc::Sender<Frame>,
+) -> Result<(), String> {
+    let mut decoder = FrameDecoder::new(framing);
+    let mut buf = vec![0u8; READ_BUF_SIZE];
+    loop {
+        let n = stream.read(&mut buf).await.map_err(|e| e.to_string())?;
+        let (frames, failed) = if n == 0 {
+            (decoder.finish()?.into_iter().collect(), None)
+        } else {
+            match decoder.push(&buf[..n]) {
+                Ok(frames) => (frames, None),
+                // 先交付出错前已完整的帧，再断开连接
+                Err(err) => (err.frames, Some(err.reason)),
+            }
+        };
+        for frame in frames {
+            tx.send((peer.to_string(), frame))
+                .await
+                .map_err(|_| "source closed".to_string())?;
+        }
+        if let Some(reason) = failed {
+            return Err(reason);
+        }
+        if n == 0 {
+            return Ok(());
+        }
+    }
+}
+
+#[async_trait]
+impl DataSource for TcpSource {
+    async fn receive(&mut self) -> SourceResult<SourceBatch> {
+        let Some((peer, frame)) = self.rx.recv().await else {
+            return Err(SourceError::from(SourceReason::SupplierError(
+                "tcp reader stopped".to_string(),
+            )));
+        };
+        let mut batch = vec![self.create_event(peer, frame)];
+        while batch.len() < MAX_BATCH {
+            match self.rx.try_recv() {
+                Ok((peer, frame)) => batch.push(self.create_event(peer, frame)),
+                Err(_) => break,
+            }
+        }
+        Ok(batch)
+    }
+
+    fn try_receive(&mut self) -> Option<SourceBatch> {
+        None
+    }
+
+    fn identifier(&self) -> String {
+        self.key.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::framing::{Endian, Framing};
+    use std::time::Duration;
+    use tokio::io::AsyncWriteExt;
+
+    fn conf(address: String, mode: TcpMode, framing: Framing) -> TcpSourceConf {
+        TcpSourceConf {
+            address,
+            mode,
+            framing: FramingConf {
+                framing,
+                ..Default::default()
+            },
+        }
+    }
+
+    async fn receive_n(source: &mut TcpSource, n: usize) -> Vec<SourceEvent> {
+        let mut events = Vec::new();
+        while events.len() < n {
+            let batch = tokio::time::timeout(Duration::from_secs(5), source.receive())
+                .await
+                .expect("frames within timeout")
+                .unwrap();
+            events.extend(batch);
+        }
+        events
+    }
+
+    fn payloads(events: &[SourceEvent]) -> Vec<Vec<u8>> {
+        let mut out = events
+            .iter()
+            .map(|e| match &e.payload {
+                RawData::String(s) => s.as_bytes().to_vec(),
+                RawData::Bytes(b) => b.to_vec(),
+            })
+            .collect::<Vec<_>>();
+        out.sort();
+        out
+    }
+
+    #[tokio::test]
+    async fn listen_mode_emits_frames_from_multiple_clients() {
+        let conf = conf("127.0.0.1:0".into(), TcpMode::Listen, Framing::Newline);
+        let mut source = TcpSource::new("tcp_test".into(), Tags::default(), &conf)
+            .await
+            .unwrap();
+        let addr = source.local_addr().unwrap();
+
+        let mut a = TcpStream::connect(addr).await.unwrap();
+        let mut b = TcpStream::connect(addr).await.unwrap();
+        a.write_all(b"{\"n\":1}\n{\"n\"").await.unwrap();
+        b.write_all(b"from-b\r\n").await.unwrap();
+        a.write_all(b":2}\n").await.unwrap();
+        a.shutdown().await.unwrap();
+
+        let events = receive_n(&mut source, 3).await;
+        assert_eq!(
+            payloads(&events),
+            vec![
+                b"from-b".to_vec(),
+                b"{\"n\":1}".to_vec(),
+                b"{\"n\":2}".to_vec()
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn connect_mode_reconnects_after_peer_closes() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let framing = Framing::LengthPrefixed {
+            width: 2,
+            endian: Endian::Big,
+        };
+        let conf = conf(
+            listener.local_addr().unwrap().to_string(),
+            TcpMode::Connect,
+            framing,
+        );
+        let mut source = TcpSource::new("tcp_test".into(), Tags::default(), &conf)
+            .await
+            .unwrap();
+
+        for payload in [b"first".as_slice(), b"second".as_slice()] {
+            let (mut peer, _) = listener.accept().await.unwrap();
+            peer.write_all(&(payload.len() as u16).to_be_bytes())
+                .await
+                .unwrap();
+            peer.write_all(payload).await.unwrap();
+            drop(peer);
+            let events = receive_n(&mut source, 1).await;
+            assert_eq!(payloads(&events), vec![payload.to_vec()]);
+        }
+    }
+}
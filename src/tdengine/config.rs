@@ -0,0 +1,39 @@
+use educe::Educe;
+use serde::{Deserialize, Serialize};
+
+#[derive(Educe, Deserialize, Serialize, PartialEq, Clone)]
+#[educe(Debug, Default)]
+pub struct TdengineSinkConfig {
+    /// taosAdapter REST 地址
+    #[educe(Default = "http://127.0.0.1:6041")]
+    pub url: String,
+    pub database: String,
+    /// 超级表名；子表按 `tag_fields` 的取值派生并在写入时自动创建
+    pub stable: String,
+    /// 作为 TAGS 的记录字段，按超级表的标签顺序
+    pub tag_fields: Vec<String>,
+    /// 时间戳字段，同时是超级表的首列；记录缺少该字段时使用写入时间
+    #[educe(Default = "ts")]
+    pub ts_field: String,
+    #[educe(Default = "root")]
+    pub user: String,
+    #[educe(Debug(ignore))]
+    #[educe(Default = "taosdata")]
+    pub password: String,
+    /// 攒满该条数（所有子表合计）即发送一条多表 INSERT
+    #[educe(Default = 500)]
+    pub batch: usize,
+    #[educe(Default = 10000)]
+    pub timeout_ms: u64,
+}
+
+impl TdengineSinkConfig {
+    /// REST SQL 入口：`{url}/rest/sql/{database}`。
+    pub fn sql_url(&self) -> String {
+        format!(
+            "{}/rest/sql/{}",
+            self.url.trim_end_matches('/'),
+            self.database
+        )
+    }
+}
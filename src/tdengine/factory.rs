@@ -0,0 +1,211 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde_json::{Value, json};
+use wp_connector_api::{
+    ConnectorDef, ConnectorScope, ParamMap, SinkBuildCtx, SinkDefProvider, SinkError, SinkFactory,
+    SinkHandle, SinkReason, SinkResult, SinkSpec,
+};
+
+use super::config::TdengineSinkConfig;
+use super::sink::TdengineSink;
+use crate::common::enrich::{EnrichConf, EnrichSink};
+use crate::common::retry::{self, RetryingSink};
+use crate::common::secret;
+use crate::common::stats;
+use crate::common::transform::FieldTransforms;
+
+pub struct TdengineSinkFactory;
+
+#[async_trait]
+impl SinkFactory for TdengineSinkFactory {
+    fn kind(&self) -> &'static str {
+        "tdengine"
+    }
+
+    fn validate_spec(&self, spec: &SinkSpec) -> SinkResult<()> {
+        let spec = &secret::resolve_sink_spec(spec)?;
+        build_conf(&spec.params)?;
+        EnrichConf::from_params(&spec.params)?;
+        retry::validate_params(&spec.params)?;
+        FieldTransforms::from_params(&spec.params)?;
+        Ok(())
+    }
+
+    async fn build(&self, spec: &SinkSpec, _ctx: &SinkBuildCtx) -> SinkResult<SinkHandle> {
+        let spec = &secret::resolve_sink_spec(spec)?;
+        let conf = build_conf(&spec.params)?;
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_millis(conf.timeout_ms))
+            .build()
+            .map_err(|err| {
+                SinkError::from(SinkReason::sink(format!(
+                    "build tdengine client failed: {err}"
+                )))
+            })?;
+        let sink =
+            TdengineSink::new(client, &conf).with_stats(stats::register(&spec.name, self.kind()));
+        let enrich = EnrichConf::from_params(&spec.params)?;
+        let transforms = FieldTransforms::from_params(&spec.params)?;
+        let sink = RetryingSink::from_spec(sink, spec).await?;
+        let sink = EnrichSink::new(sink, enrich).with_transforms(transforms);
+        Ok(SinkHandle::new(Box::new(sink)))
+    }
+}
+
+impl SinkDefProvider for TdengineSinkFactory {
+    fn sink_def(&self) -> ConnectorDef {
+        ConnectorDef {
+            id: "tdengine_sink".into(),
+            kind: self.kind().into(),
+            scope: ConnectorScope::Sink,
+            allow_override: vec![
+                "url",
+                "database",
+                "stable",
+                "tag_fields",
+                "ts_field",
+                "user",
+                "password",
+                "batch",
+                "timeout_ms",
+                "secret_ref",
+                "enrich",
+                "enrich_overwrite",
+                "field_transforms",
+                "retry",
+                "write_deadline_ms",
+                "quarantine",
+            ]
+            .into_iter()
+            .map(str::to_string)
+            .collect(),
+            default_params: tdengine_defaults(),
+            origin: Some("wp-connectors:tdengine_sink".into()),
+        }
+    }
+}
+
+fn tdengine_defaults() -> ParamMap {
+    let defaults = TdengineSinkConfig::default();
+    let mut params = ParamMap::new();
+    params.insert("url".into(), json!(defaults.url));
+    params.insert("ts_field".into(), json!(defaults.ts_field));
+    params.insert("user".into(), json!(defaults.user));
+    params.insert("password".into(), json!(defaults.password));
+    params.insert("batch".into(), json!(defaults.batch));
+    params.insert("timeout_ms".into(), json!(defaults.timeout_ms));
+    params
+}
+
+/// 由参数构建配置：`database`、`stable` 与非空的 `tag_fields` 必填。
+fn build_conf(params: &ParamMap) -> SinkResult<TdengineSinkConfig> {
+    let mut conf = TdengineSinkConfig {
+        database: required_str(params, "database")?,
+        stable: required_str(params, "stable")?,
+        ..Default::default()
+    };
+    if let Some(url) = opt_str(params, "url")? {
+        if !url.starts_with("http://") && !url.starts_with("https://") {
+            return Err(
+                SinkReason::sink("tdengine.url must start with http:// or https://").into(),
+            );
+        }
+        conf.url = url;
+    }
+    conf.tag_fields = match params.get("tag_fields") {
+        Some(Value::Array(items)) if !items.is_empty() => items
+            .iter()
+            .map(|item| {
+                item.as_str()
+                    .map(|s| s.trim().to_string())
+                    .filter(|s| !s.is_empty())
+                    .ok_or_else(|| SinkReason::sink("tdengine.tag_fields must contain field names"))
+            })
+            .collect::<Result<_, _>>()?,
+        _ => {
+            return Err(SinkReason::sink(
+                "tdengine.tag_fields must be a non-empty array of strings",
+            )
+            .into());
+        }
+    };
+    if let Some(ts_field) = opt_str(params, "ts_field")? {
+        conf.ts_field = ts_field;
+    }
+    if conf.tag_fields.contains(&conf.ts_field) {
+        return Err(SinkReason::sink("tdengine.ts_field must not be one of tag_fields").into());
+    }
+    if let Some(user) = opt_str(params, "user")? {
+        conf.user = user;
+    }
+    if let Some(password) = opt_str(params, "password")? {
+        conf.password = password;
+    }
+    if let Some(n) = opt_positive(params, "batch")? {
+        conf.batch = n as usize;
+    }
+    if let Some(n) = opt_positive(params, "timeout_ms")? {
+        conf.timeout_ms = n;
+    }
+    Ok(conf)
+}
+
+fn required_str(params: &ParamMap, key: &str) -> SinkResult<String> {
+    opt_str(params, key)?
+        .ok_or_else(|| SinkReason::sink(format!("tdengine.{key} must not be empty")).into())
+}
+
+/// 可选的非空字符串参数。
+fn opt_str(params: &ParamMap, key: &str) -> SinkResult<Option<String>> {
+    match params.get(key) {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::String(s)) if s.trim().is_empty() => Ok(None),
+        Some(Value::String(s)) => Ok(Some(s.trim().to_string())),
+        Some(_) => Err(SinkReason::sink(format!("tdengine.{key} must be a string")).into()),
+    }
+}
+
+/// 可选的正整数参数。
+fn opt_positive(params: &ParamMap, key: &str) -> SinkResult<Option<u64>> {
+    match params.get(key) {
+        None | Some(Value::Null) => Ok(None),
+        Some(v) => match v.as_u64() {
+            Some(n) if n > 0 => Ok(Some(n)),
+            _ => Err(SinkReason::sink(format!("tdengine.{key} must be a positive integer")).into()),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params() -> ParamMap {
+        let mut params = tdengine_defaults();
+        params.insert("database".into(), json!("power"));
+        params.insert("stable".into(), json!("meters"));
+        params.insert("tag_fields".into(), json!(["location", "group_id"]));
+        params
+    }
+
+    #[test]
+    fn build_conf_requires_routing_fields() {
+        let conf = build_conf(&params()).expect("valid");
+        assert_eq!(conf.sql_url(), "http://127.0.0.1:6041/rest/sql/power");
+        assert_eq!(conf.tag_fields, ["location", "group_id"]);
+        assert_eq!(conf.ts_field, "ts");
+
+        for (key, value) in [
+            ("stable", json!("")),
+            ("tag_fields", json!([])),
+            ("ts_field", json!("location")),
+            ("url", json!("127.0.0.1:6041")),
+            ("batch", json!(0)),
+        ] {
+            let mut p = params();
+            p.insert(key.into(), value);
+            assert!(build_conf(&p).is_err(), "{key} should be rejected");
+        }
+    }
+}
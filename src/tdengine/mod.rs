@@ -0,0 +1,15 @@
+//! TDengine sink：经 taosAdapter REST 接口写入超级表。
+//!
+//! 模块划分：
+//! - config：TdengineSinkConfig
+//! - sql：子表命名与多表 INSERT 拼装
+//! - sink：TdengineSink（按标签路由子表、攒批写入）
+//! - factory：Sink 工厂
+
+pub mod config;
+mod factory;
+mod sink;
+mod sql;
+
+pub use config::TdengineSinkConfig;
+pub use factory::TdengineSinkFactory;
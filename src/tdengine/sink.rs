@@ -0,0 +1,340 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use wp_connector_api::{
+    AsyncCtrl, AsyncRawDataSink, AsyncRecordSink, SinkError, SinkReason, SinkResult,
+};
+use wp_model_core::model::{DataRecord, DataType};
+
+use crate::common::retry::{self, PendingFlush};
+use crate::common::stats::StatsHandle;
+use crate::tdengine::config::TdengineSinkConfig;
+use crate::tdengine::sql::{SubTableBatch, field_literal, insert_sql, subtable_name};
+
+/// REST 接口的响应：`code` 为 0 表示成功，否则 `desc` 为错误描述。
+#[derive(Debug, Deserialize)]
+struct RestResponse {
+    code: i64,
+    #[serde(default)]
+    desc: String,
+}
+
+/// 经 taosAdapter REST 接口写入 TDengine：按标签字段把记录路由到子表，
+/// 攒满 `batch` 条后合并为一条多表 INSERT，子表不存在时自动创建。
+pub(crate) struct TdengineSink {
+    client: reqwest::Client,
+    sql_url: String,
+    user: String,
+    password: String,
+    stable: String,
+    tag_fields: Vec<String>,
+    ts_field: String,
+    batch: usize,
+    pending: BTreeMap<String, SubTableBatch>,
+    pending_rows: usize,
+    /// 累计进入缓存的记录数
+    accepted: u64,
+    /// `/stats` 自省状态（缓存、最近 flush 与错误）
+    stats: StatsHandle,
+}
+
+impl TdengineSink {
+    pub(crate) fn new(client: reqwest::Client, conf: &TdengineSinkConfig) -> Self {
+        Self {
+            client,
+            sql_url: conf.sql_url(),
+            user: conf.user.clone(),
+            password: conf.password.clone(),
+            stable: conf.stable.clone(),
+            tag_fields: conf.tag_fields.clone(),
+            ts_field: conf.ts_field.clone(),
+            batch: conf.batch.max(1),
+            pending: BTreeMap::new(),
+            pending_rows: 0,
+            accepted: 0,
+            stats: StatsHandle::detached(&conf.stable, "tdengine"),
+        }
+    }
+
+    /// 使用外部登记的状态句柄，以超级表作为指标的写入目标。
+    pub(crate) fn with_stats(mut self, stats: StatsHandle) -> Self {
+        self.stats = stats.with_destination(self.stable.as_str());
+        self
+    }
+
+    /// 把记录放入对应子表的缓存：标签字段写入 TAGS，其余字段作为普通列，时间戳列在首位。
+    fn buffer_record(&mut self, record: &DataRecord) {
+        let tag_values: Vec<Option<String>> = self
+            .tag_fields
+            .iter()
+            .map(|tag| record.get2(tag).map(|f| f.get_value().to_string()))
+            .collect();
+        let table = subtable_name(&self.stable, &tag_values);
+        let ts = record
+            .get2(&self.ts_field)
+            .map(field_literal)
+            .unwrap_or_else(|| "NOW".to_string());
+        let mut row = vec![(self.ts_field.clone(), ts)];
+        for field in &record.items {
+            let name = field.get_name();
+            if *field.get_meta() == DataType::Ignore
+                || name == self.ts_field
+                || self.tag_fields.iter().any(|tag| tag == name)
+                || row.iter().any(|(col, _)| col == name)
+            {
+                continue;
+            }
+            row.push((name.to_string(), field_literal(field)));
+        }
+        let tag_fields = &self.tag_fields;
+        let batch = self.pending.entry(table).or_insert_with(|| SubTableBatch {
+            tags: tag_fields
+                .iter()
+                .map(|tag| {
+                    record
+                        .get2(tag)
+                        .map(field_literal)
+                        .unwrap_or_else(|| "NULL".to_string())
+                })
+                .collect(),
+            ..Default::default()
+        });
+        batch.push_row(row);
+        self.pending_rows += 1;
+        self.accepted += 1;
+        self.stats.set_buffered(self.pending_rows);
+    }
+
+    async fn flush_if_full(&mut self) -> SinkResult<()> {
+        if self.pending_rows >= self.batch {
+            self.flush().await?;
+        }
+        Ok(())
+    }
+
+    /// 发送当前缓存；失败时保留缓存，由外层重试。
+    async fn flush(&mut self) -> SinkResult<()> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+        let sql = insert_sql(&self.stable, &self.tag_fields, &self.pending);
+        let result = self.execute(sql).await;
+        self.stats.record_flush(&result);
+        result?;
+        self.pending.clear();
+        self.pending_rows = 0;
+        Ok(())
+    }
+
+    async fn execute(&self, sql: String) -> SinkResult<()> {
+        let resp = self
+            .client
+            .post(&self.sql_url)
+            .basic_auth(&self.user, Some(&self.password))
+            .body(sql)
+            .send()
+            .await
+            .map_err(|e| {
+                retry::transport_error(format!("tdengine request failed: {e}"), e.is_timeout())
+            })?;
+        let status = resp.status();
+        let text = resp.text().await.map_err(|e| {
+            retry::transport_error(
+                format!("tdengine read response failed: {e}"),
+                e.is_timeout(),
+            )
+        })?;
+        // 网关或 taosAdapter 过载时响应体不是 REST 结果，按状态码归类
+        let parsed: RestResponse = serde_json::from_str(&text).map_err(|_| {
+            retry::status_error(
+                status.as_u16(),
+                format!("tdengine unexpected response ({status}): {text}"),
+            )
+        })?;
+        // taosAdapter 对服务端不可用、超时等返回 5xx，SQL 与数据错误返回 200/4xx
+        if parsed.code != 0 {
+            return Err(retry::status_error(
+                status.as_u16(),
+                format!(
+                    "tdengine insert failed (code {:#x}): {}",
+                    parsed.code, parsed.desc
+                ),
+            ));
+        }
+        Ok(())
+    }
+}
+
+fn td_error(msg: String) -> SinkError {
+    SinkError::from(SinkReason::sink(format!("tdengine {msg}")))
+}
+
+#[async_trait]
+impl PendingFlush for TdengineSink {
+    fn pending_len(&self) -> usize {
+        self.pending_rows
+    }
+
+    async fn flush_now(&mut self) -> SinkResult<()> {
+        self.flush().await
+    }
+
+    fn discard_pending(&mut self) -> usize {
+        let discarded = self.pending_rows;
+        self.pending.clear();
+        self.pending_rows = 0;
+        self.stats.set_buffered(0);
+        discarded
+    }
+
+    fn accepted(&self) -> u64 {
+        self.accepted
+    }
+}
+
+#[async_trait]
+impl AsyncCtrl for TdengineSink {
+    async fn stop(&mut self) -> SinkResult<()> {
+        self.flush().await
+    }
+
+    async fn reconnect(&mut self) -> SinkResult<()> {
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl AsyncRecordSink for TdengineSink {
+    async fn sink_record(&mut self, data: &DataRecord) -> SinkResult<()> {
+        self.buffer_record(data);
+        self.flush_if_full().await
+    }
+
+    async fn sink_records(&mut self, data: Vec<Arc<DataRecord>>) -> SinkResult<()> {
+        for record in &data {
+            self.buffer_record(record.as_ref());
+        }
+        self.flush_if_full().await
+    }
+}
+
+#[async_trait]
+impl AsyncRawDataSink for TdengineSink {
+    async fn sink_str(&mut self, _data: &str) -> SinkResult<()> {
+        Err(td_error("sink does not accept raw text input".into()))
+    }
+
+    async fn sink_bytes(&mut self, _data: &[u8]) -> SinkResult<()> {
+        Err(td_error("sink does not accept raw byte input".into()))
+    }
+
+    async fn sink_str_batch(&mut self, _data: Vec<&str>) -> SinkResult<()> {
+        Err(td_error("sink does not accept raw batch input".into()))
+    }
+
+    async fn sink_bytes_batch(&mut self, _data: Vec<&[u8]>) -> SinkResult<()> {
+        Err(td_error("sink does not accept raw batch byte input".into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::retry::FailureKind;
+    use httpmock::prelude::*;
+    use wp_model_core::model::DataField;
+
+    fn test_sink(url: &str, batch: usize) -> TdengineSink {
+        let conf = TdengineSinkConfig {
+            url: url.to_string(),
+            database: "power".into(),
+            stable: "meters".into(),
+            tag_fields: vec!["location".into()],
+            batch,
+            ..Default::default()
+        };
+        let client = reqwest::Client::builder()
+            .no_proxy()
+            .build()
+            .expect("client");
+        TdengineSink::new(client, &conf)
+    }
+
+    fn reading(location: &str, ts: i64, current: i64) -> DataRecord {
+        let mut record = DataRecord::default();
+        record.append(DataField::from_chars("location", location));
+        record.append(DataField::from_digit("ts", ts));
+        record.append(DataField::from_digit("current", current));
+        record
+    }
+
+    #[test]
+    fn records_route_to_subtables_by_tag() {
+        let mut sink = test_sink("http://127.0.0.1:6041", 10);
+        sink.buffer_record(&reading("bj", 1, 15));
+        sink.buffer_record(&reading("sh", 2, 25));
+        sink.buffer_record(&reading("bj", 3, 35));
+        assert_eq!(sink.pending_len(), 3);
+        let tables: Vec<&String> = sink.pending.keys().collect();
+        assert_eq!(tables, ["meters_bj", "meters_sh"]);
+        let bj = &sink.pending["meters_bj"];
+        assert_eq!(bj.tags, ["'bj'"]);
+        assert_eq!(bj.columns, ["ts", "current"]);
+        assert_eq!(bj.rows.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn batch_is_sent_as_one_insert_and_kept_on_error() {
+        let server = MockServer::start_async().await;
+        let mut rejected = server.mock(|when, then| {
+            when.method(POST).path("/rest/sql/power");
+            then.status(200)
+                .body(r#"{"code":9826,"desc":"Table does not exist"}"#);
+        });
+        let mut sink = test_sink(&server.base_url(), 2);
+        sink.sink_record(&reading("bj", 1, 15))
+            .await
+            .expect("buffered");
+        let err = sink
+            .sink_record(&reading("sh", 2, 25))
+            .await
+            .expect_err("rejected");
+        assert!(format!("{err}").contains("Table does not exist"), "{err}");
+        assert_eq!(FailureKind::of(&err), FailureKind::Permanent);
+        assert_eq!(sink.pending_len(), 2);
+        rejected.delete();
+
+        let accepted = server.mock(|when, then| {
+            when.method(POST)
+                .path("/rest/sql/power")
+                .header_exists("authorization")
+                .body_contains("INSERT INTO `meters_bj` USING `meters` (`location`) TAGS ('bj')")
+                .body_contains("`meters_sh` USING `meters`");
+            then.status(200).body(
+                r#"{"code":0,"column_meta":[["affected_rows","INT",4]],"data":[[2]],"rows":1}"#,
+            );
+        });
+        sink.flush_now().await.expect("retried");
+        accepted.assert_hits(1);
+        assert_eq!(sink.pending_len(), 0);
+    }
+
+    #[tokio::test]
+    async fn unavailable_server_error_is_retryable() {
+        let server = MockServer::start_async().await;
+        server.mock(|when, then| {
+            when.method(POST).path("/rest/sql/power");
+            then.status(503)
+                .body(r#"{"code":1035,"desc":"Sync leader is unreachable"}"#);
+        });
+        let mut sink = test_sink(&server.base_url(), 1);
+        let err = sink
+            .sink_record(&reading("bj", 1, 15))
+            .await
+            .expect_err("unavailable");
+        assert_eq!(FailureKind::of(&err), FailureKind::Retryable);
+        assert_eq!(sink.pending_len(), 1);
+    }
+}
@@ -0,0 +1,215 @@
+//! 子表命名与多表 INSERT 语句拼装。
+
+use std::collections::BTreeMap;
+
+use sha2::{Digest, Sha256};
+use wp_model_core::model::{DataField, DataType, Value};
+
+/// TDengine 表名长度上限
+const MAX_TABLE_NAME_LEN: usize = 192;
+
+/// 一个子表的待写入数据：标签字面量、列名（时间戳列在首位）与各行字面量。
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct SubTableBatch {
+    pub tags: Vec<String>,
+    pub columns: Vec<String>,
+    pub rows: Vec<BTreeMap<String, String>>,
+}
+
+impl SubTableBatch {
+    /// 追加一行；新出现的列追加到列序末尾，缺失的列写入 NULL。
+    pub(crate) fn push_row(&mut self, row: Vec<(String, String)>) {
+        for (column, _) in &row {
+            if !self.columns.contains(column) {
+                self.columns.push(column.clone());
+            }
+        }
+        self.rows.push(row.into_iter().collect());
+    }
+}
+
+/// 子表名：`<stable>_<标签值>`，保留大小写（表名以反引号引用，区分大小写）。
+/// 标签值缺失、为空或含字母数字以外的字符（包括分隔符 `_`）时，非字母数字字符替换为 `_`
+/// 并追加超级表名与原始标签值的哈希；超长时同样截断后追加哈希，避免不同标签组合落到同一子表。
+pub(crate) fn subtable_name(stable: &str, tag_values: &[Option<String>]) -> String {
+    let raw = tag_values
+        .iter()
+        .map(|v| v.as_deref().unwrap_or("null"))
+        .collect::<Vec<_>>()
+        .join("_");
+    let base = format!("{stable}_{raw}");
+    let plain = stable
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && tag_values.iter().all(|v| {
+            v.as_deref()
+                .is_some_and(|v| !v.is_empty() && v.chars().all(|c| c.is_ascii_alphanumeric()))
+        });
+    if plain && base.len() <= MAX_TABLE_NAME_LEN {
+        return base;
+    }
+    let sanitized: String = base
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    let digest = Sha256::digest(format!("{stable}\0{tag_values:?}").as_bytes());
+    let hash: String = digest[..8].iter().map(|b| format!("{b:02x}")).collect();
+    let keep = MAX_TABLE_NAME_LEN - hash.len() - 1;
+    let prefix: String = sanitized.chars().take(keep).collect();
+    format!("{prefix}_{hash}")
+}
+
+/// 以反引号引用标识符。
+pub(crate) fn quote_ident(name: &str) -> String {
+    format!("`{}`", name.replace('`', "``"))
+}
+
+/// 字符串字面量：单引号包裹，转义 `'` 与 `\`。
+pub(crate) fn quote_str(value: &str) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('\'');
+    for c in value.chars() {
+        if c == '\'' || c == '\\' {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out.push('\'');
+    out
+}
+
+/// 字段值的 SQL 字面量：数值与布尔原样输出，时间转为毫秒时间戳，其余按字符串引用。
+pub(crate) fn field_literal(field: &DataField) -> String {
+    match (field.get_meta(), field.get_value()) {
+        (_, Value::Time(dt)) => dt.and_utc().timestamp_millis().to_string(),
+        (DataType::Digit | DataType::Float | DataType::Bool, value) => value.to_string(),
+        (_, value) => quote_str(&value.to_string()),
+    }
+}
+
+/// 拼装一条多表 INSERT，子表不存在时按 `USING ... TAGS` 自动创建。
+///
+/// 形如 `INSERT INTO t1 USING st (tag...) TAGS (...) (col...) VALUES (...) (...) t2 USING ...`。
+pub(crate) fn insert_sql(
+    stable: &str,
+    tag_fields: &[String],
+    batches: &BTreeMap<String, SubTableBatch>,
+) -> String {
+    let tag_columns = tag_fields
+        .iter()
+        .map(|t| quote_ident(t))
+        .collect::<Vec<_>>()
+        .join(",");
+    let mut sql = String::from("INSERT INTO");
+    for (table, batch) in batches {
+        let columns = batch
+            .columns
+            .iter()
+            .map(|c| quote_ident(c))
+            .collect::<Vec<_>>()
+            .join(",");
+        sql.push_str(&format!(
+            " {} USING {} ({}) TAGS ({}) ({}) VALUES",
+            quote_ident(table),
+            quote_ident(stable),
+            tag_columns,
+            batch.tags.join(","),
+            columns
+        ));
+        for row in &batch.rows {
+            let values = batch
+                .columns
+                .iter()
+                .map(|c| row.get(c).map_or("NULL", String::as_str))
+                .collect::<Vec<_>>()
+                .join(",");
+            sql.push_str(&format!(" ({values})"));
+        }
+    }
+    sql
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn subtable_names_are_stable_and_collision_free() {
+        assert_eq!(
+            subtable_name("meters", &[Some("beijing".into()), Some("42".into())]),
+            "meters_beijing_42"
+        );
+        assert_eq!(
+            subtable_name("Meters", &[Some("D1001".into())]),
+            "Meters_D1001"
+        );
+        assert_ne!(
+            subtable_name("meters", &[Some("Host".into())]),
+            subtable_name("meters", &[Some("host".into())])
+        );
+        // 标签值含分隔符时带上哈希，拼接结果相同的标签组合不会落到同一子表
+        assert_ne!(
+            subtable_name("meters", &[Some("a_b".into()), Some("c".into())]),
+            subtable_name("meters", &[Some("a".into()), Some("b_c".into())])
+        );
+        // 替换过字符的名字带上哈希，"a-b" 与 "a_b" 不会落到同一子表
+        let dashed = subtable_name("meters", &[Some("a-b".into())]);
+        let underscored = subtable_name("meters", &[Some("a_b".into())]);
+        assert!(dashed.starts_with("meters_a_b_"), "{dashed}");
+        assert_ne!(dashed, underscored);
+        assert_ne!(
+            subtable_name("meters", &[None]),
+            subtable_name("meters", &[Some("null".into())])
+        );
+        assert_eq!(
+            subtable_name("meters", &[Some("x".into())]),
+            subtable_name("meters", &[Some("x".into())])
+        );
+        let long = subtable_name("meters", &[Some("v".repeat(300))]);
+        assert_eq!(long.len(), MAX_TABLE_NAME_LEN);
+    }
+
+    #[test]
+    fn insert_sql_groups_rows_per_subtable() {
+        let mut d1 = SubTableBatch {
+            tags: vec![quote_str("bj"), "2".into()],
+            ..Default::default()
+        };
+        d1.push_row(vec![
+            ("ts".into(), "1700000000000".into()),
+            ("current".into(), "10.2".into()),
+        ]);
+        d1.push_row(vec![
+            ("ts".into(), "1700000001000".into()),
+            ("note".into(), quote_str("it's")),
+        ]);
+        let mut d2 = SubTableBatch {
+            tags: vec![quote_str("sh"), "NULL".into()],
+            ..Default::default()
+        };
+        d2.push_row(vec![
+            ("ts".into(), "NOW".into()),
+            ("current".into(), "1".into()),
+        ]);
+        let batches = BTreeMap::from([
+            ("meters_bj_2".to_string(), d1),
+            ("meters_sh".to_string(), d2),
+        ]);
+        assert_eq!(
+            insert_sql("meters", &["location".into(), "group_id".into()], &batches),
+            "INSERT INTO `meters_bj_2` USING `meters` (`location`,`group_id`) TAGS ('bj',2) \
+             (`ts`,`current`,`note`) VALUES (1700000000000,10.2,NULL) (1700000001000,NULL,'it\\'s') \
+             `meters_sh` USING `meters` (`location`,`group_id`) TAGS ('sh',NULL) \
+             (`ts`,`current`) VALUES (NOW,1)"
+        );
+    }
+
+    #[test]
+    fn literals_follow_field_types() {
+        assert_eq!(field_literal(&DataField::from_digit("n", 7)), "7");
+        assert_eq!(
+            field_literal(&DataField::from_chars("s", "a\\b")),
+            "'a\\\\b'"
+        );
+    }
+}
@@ -1,6 +1,20 @@
 use educe::Educe;
 use serde::Deserialize;
 use serde::Serialize;
+
+/// 写入与健康检查请求的认证方式。
+#[derive(Debug, Default, Deserialize, Serialize, PartialEq, Eq, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum VictoriaLogAuth {
+    #[default]
+    None,
+    Basic {
+        username: String,
+        password: String,
+    },
+    Bearer(String),
+}
+
 #[derive(Educe, Deserialize, Serialize, PartialEq, Clone)]
 #[educe(Debug, Default)]
 pub struct VictoriaLog {
@@ -11,4 +25,14 @@ pub struct VictoriaLog {
     pub create_time_field: Option<String>,
     #[educe(Default = 0.1)]
     pub flush_interval_secs: f64,
+    #[educe(Default = 1000)]
+    pub batch: usize,
+    /// `_msg` 的渲染格式
+    #[educe(Default = "json")]
+    pub fmt: String,
+    #[serde(default)]
+    pub auth: VictoriaLogAuth,
+    /// 作为日志流标签的字段（`VL-Stream-Fields`）；为空时不声明流字段
+    #[serde(default)]
+    pub stream_fields: Vec<String>,
 }
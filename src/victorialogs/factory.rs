@@ -1,15 +1,20 @@
 use std::time::Duration;
 
 use async_trait::async_trait;
-use serde_json::json;
+use serde_json::{Value, json};
 use wp_connector_api::{
     ConnectorDef, ConnectorScope, ParamMap, SinkBuildCtx, SinkDefProvider, SinkError, SinkFactory,
     SinkHandle, SinkReason, SinkResult, SinkSpec,
 };
 use wp_model_core::model::fmt_def::TextFmt;
 
-use super::config::VictoriaLog;
-use super::sink::VictoriaLogSink;
+use super::config::{VictoriaLog, VictoriaLogAuth};
+use crate::common::enrich::{EnrichConf, EnrichSink};
+use crate::common::reconfigure;
+use crate::common::retry::{self, RetryingSink};
+use crate::common::stats;
+use crate::common::trace_context::TraceContext;
+use crate::common::transform::FieldTransforms;
 
 pub struct VictoriaLogSinkFactory;
 
@@ -19,37 +24,16 @@ impl SinkFactory for VictoriaLogSinkFactory {
         "victorialogs"
     }
     fn validate_spec(&self, spec: &SinkSpec) -> SinkResult<()> {
-        let endpoint = spec
-            .params
-            .get("endpoint")
-            .and_then(|v| v.as_str())
-            .unwrap_or("");
-        if endpoint.trim().is_empty() {
-            return Err(SinkReason::sink("victorialog.endpoint must not be empty").into());
-        }
+        build_conf(&spec.params)?;
+        EnrichConf::from_params(&spec.params)?;
+        retry::validate_params(&spec.params)?;
+        FieldTransforms::from_params(&spec.params)?;
+        TraceContext::from_params(&spec.params)?;
         Ok(())
     }
     async fn build(&self, spec: &SinkSpec, _ctx: &SinkBuildCtx) -> SinkResult<SinkHandle> {
-        let mut conf = VictoriaLog::default();
-        if let Some(s) = spec.params.get("endpoint").and_then(|v| v.as_str()) {
-            conf.endpoint = s.to_string();
-        }
-        if let Some(s) = spec.params.get("insert_path").and_then(|v| v.as_str()) {
-            conf.insert_path = s.to_string();
-        }
-        if let Some(s) = spec
-            .params
-            .get("create_time_field")
-            .and_then(|v| v.as_str())
-        {
-            conf.create_time_field = Some(s.to_string());
-        }
-        let fmt = spec
-            .params
-            .get("fmt")
-            .and_then(|v| v.as_str())
-            .map(TextFmt::from)
-            .unwrap_or(TextFmt::Json);
+        let conf = build_conf(&spec.params)?;
+        let fmt = TextFmt::from(conf.fmt.as_str());
         let client = reqwest::Client::builder()
             .timeout(Duration::from_secs(5))
             .build()
@@ -64,7 +48,17 @@ impl SinkFactory for VictoriaLogSinkFactory {
             client,
             fmt,
             conf.create_time_field.clone(),
-        );
+        )
+        .with_batch(conf.batch)
+        .with_auth(conf.auth.clone())
+        .with_stream_fields(conf.stream_fields.clone())
+        .with_trace_context(TraceContext::from_params(&spec.params)?)
+        .with_stats(stats::register(&spec.name, self.kind()));
+        let enrich = EnrichConf::from_params(&spec.params)?;
+        let transforms = FieldTransforms::from_params(&spec.params)?;
+        let sink = RetryingSink::from_spec(sink, spec).await?;
+        reconfigure::register(&spec.name, sink.reconfigure_handle());
+        let sink = EnrichSink::new(sink, enrich).with_transforms(transforms);
         Ok(SinkHandle::new(Box::new(sink)))
     }
 }
@@ -75,10 +69,30 @@ impl SinkDefProvider for VictoriaLogSinkFactory {
             id: "victorialog_sink".into(),
             kind: self.kind().into(),
             scope: ConnectorScope::Sink,
-            allow_override: vec!["endpoint", "insert_path", "fmt"]
-                .into_iter()
-                .map(str::to_string)
-                .collect(),
+            allow_override: vec![
+                "endpoint",
+                "insert_path",
+                "fmt",
+                "create_time_field",
+                "timestamp_field",
+                "batch",
+                "username",
+                "password",
+                "token",
+                "stream_fields",
+                "trace_context",
+                "trace_id_field",
+                "tracestate",
+                "enrich",
+                "enrich_overwrite",
+                "field_transforms",
+                "retry",
+                "write_deadline_ms",
+                "quarantine",
+            ]
+            .into_iter()
+            .map(str::to_string)
+            .collect(),
             default_params: victorialog_defaults(),
             origin: Some("wp-connectors:victorialog_sink".into()),
         }
@@ -90,5 +104,187 @@ fn victorialog_defaults() -> ParamMap {
     params.insert("endpoint".into(), json!("http://localhost:8481"));
     params.insert("insert_path".into(), json!("/insert/json"));
     params.insert("fmt".into(), json!("json"));
+    params.insert("batch".into(), json!(VictoriaLog::default().batch));
     params
 }
+
+/// 由参数构建配置：`endpoint` 必填，其余缺省取 [`VictoriaLog::default`]。
+fn build_conf(params: &ParamMap) -> SinkResult<VictoriaLog> {
+    let mut conf = VictoriaLog::default();
+    let endpoint = params
+        .get("endpoint")
+        .and_then(|v| v.as_str())
+        .unwrap_or("");
+    if endpoint.trim().is_empty() {
+        return Err(SinkReason::sink("victorialog.endpoint must not be empty").into());
+    }
+    conf.endpoint = endpoint.to_string();
+    if let Some(s) = params.get("insert_path").and_then(|v| v.as_str()) {
+        conf.insert_path = s.to_string();
+    }
+    // `timestamp_field` 为 `create_time_field` 的别名
+    let time_fields = ["create_time_field", "timestamp_field"]
+        .iter()
+        .filter_map(|key| params.get(*key).and_then(|v| v.as_str()))
+        .collect::<Vec<_>>();
+    if let [first, rest @ ..] = time_fields.as_slice() {
+        if rest.iter().any(|other| other != first) {
+            return Err(SinkReason::sink(
+                "victorialog.timestamp_field conflicts with victorialog.create_time_field",
+            )
+            .into());
+        }
+        conf.create_time_field = Some(first.to_string());
+    }
+    if let Some(s) = params.get("fmt").and_then(|v| v.as_str()) {
+        conf.fmt = s.to_string();
+    }
+    if let Some(batch) = parse_batch(params)? {
+        conf.batch = batch;
+    }
+    conf.auth = parse_auth(params)?;
+    conf.stream_fields = parse_stream_fields(params)?;
+    Ok(conf)
+}
+
+/// `stream_fields`：可选的字段名数组，元素须为非空且不重复的字符串。
+fn parse_stream_fields(params: &ParamMap) -> SinkResult<Vec<String>> {
+    let Some(value) = params.get("stream_fields").filter(|v| !v.is_null()) else {
+        return Ok(Vec::new());
+    };
+    let invalid = || -> SinkError {
+        SinkReason::sink("victorialog.stream_fields must be a string array").into()
+    };
+    let mut fields: Vec<String> = Vec::new();
+    for item in value.as_array().ok_or_else(invalid)? {
+        let field = item.as_str().map(str::trim).filter(|s| !s.is_empty());
+        let field = field.ok_or_else(invalid)?;
+        if fields.iter().any(|f| f == field) {
+            return Err(SinkReason::sink(format!(
+                "victorialog.stream_fields contains duplicate field '{field}'"
+            ))
+            .into());
+        }
+        fields.push(field.to_string());
+    }
+    Ok(fields)
+}
+
+/// `username`/`password` 为 Basic 认证，`token` 为 Bearer 认证，二者互斥。
+fn parse_auth(params: &ParamMap) -> SinkResult<VictoriaLogAuth> {
+    let get = |key: &str| -> SinkResult<Option<String>> {
+        match params.get(key) {
+            None | Some(Value::Null) => Ok(None),
+            Some(Value::String(s)) if !s.is_empty() => Ok(Some(s.clone())),
+            Some(_) => Err(SinkReason::sink(format!(
+                "victorialog.{key} must be a non-empty string"
+            ))
+            .into()),
+        }
+    };
+    match (get("username")?, get("password")?, get("token")?) {
+        (None, None, None) => Ok(VictoriaLogAuth::None),
+        (Some(username), password, None) => Ok(VictoriaLogAuth::Basic {
+            username,
+            password: password.unwrap_or_default(),
+        }),
+        (None, None, Some(token)) => Ok(VictoriaLogAuth::Bearer(token)),
+        (None, Some(_), None) => {
+            Err(SinkReason::sink("victorialog.password requires victorialog.username").into())
+        }
+        _ => Err(
+            SinkReason::sink("victorialog.token cannot be combined with username/password").into(),
+        ),
+    }
+}
+
+/// `batch` 须为正整数；未配置时返回 `None`。
+fn parse_batch(params: &ParamMap) -> SinkResult<Option<usize>> {
+    match params.get("batch") {
+        None | Some(Value::Null) => Ok(None),
+        Some(v) => match v.as_u64() {
+            Some(n) if n > 0 => Ok(Some(n as usize)),
+            _ => Err(SinkReason::sink("victorialog.batch must be a positive integer").into()),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(endpoint: &str) -> ParamMap {
+        let mut params = victorialog_defaults();
+        params.insert("endpoint".into(), json!(endpoint));
+        params
+    }
+
+    #[test]
+    fn build_conf_rejects_empty_endpoint() {
+        assert!(build_conf(&params("http://vl:9428")).is_ok());
+        for endpoint in ["", "   "] {
+            let err = build_conf(&params(endpoint)).expect_err("empty endpoint");
+            assert!(format!("{err}").contains("victorialog.endpoint"), "{err}");
+        }
+        let mut missing = params("http://vl:9428");
+        missing.remove("endpoint");
+        assert!(build_conf(&missing).is_err());
+    }
+
+    #[test]
+    fn build_conf_parses_batch_fmt_and_auth() {
+        let mut p = params("http://vl:9428");
+        p.insert("batch".into(), json!(50));
+        p.insert("fmt".into(), json!("kv"));
+        p.insert("token".into(), json!("t0k"));
+        let conf = build_conf(&p).expect("valid");
+        assert_eq!(conf.batch, 50);
+        assert_eq!(conf.fmt, "kv");
+        assert_eq!(conf.auth, VictoriaLogAuth::Bearer("t0k".into()));
+
+        p.insert("username".into(), json!("vl"));
+        assert!(build_conf(&p).is_err(), "token and basic are exclusive");
+        p.remove("token");
+        p.insert("password".into(), json!("secret"));
+        assert_eq!(
+            build_conf(&p).unwrap().auth,
+            VictoriaLogAuth::Basic {
+                username: "vl".into(),
+                password: "secret".into()
+            }
+        );
+        p.insert("batch".into(), json!(0));
+        assert!(build_conf(&p).is_err());
+    }
+
+    #[test]
+    fn build_conf_parses_stream_fields() {
+        let mut p = params("http://vl:9428");
+        assert!(build_conf(&p).unwrap().stream_fields.is_empty());
+        p.insert("stream_fields".into(), json!(["host", " app "]));
+        assert_eq!(build_conf(&p).unwrap().stream_fields, vec!["host", "app"]);
+        for bad in [
+            json!("host"),
+            json!(["host", ""]),
+            json!(["host", 1]),
+            json!(["a", "a"]),
+        ] {
+            p.insert("stream_fields".into(), bad);
+            assert!(build_conf(&p).is_err());
+        }
+    }
+
+    #[test]
+    fn timestamp_field_aliases_create_time_field() {
+        let mut p = params("http://vl:9428");
+        p.insert("timestamp_field".into(), json!("event_time"));
+        assert_eq!(
+            build_conf(&p).unwrap().create_time_field.as_deref(),
+            Some("event_time")
+        );
+        p.insert("create_time_field".into(), json!("event_time"));
+        assert!(build_conf(&p).is_ok());
+        p.insert("create_time_field".into(), json!("ingest_time"));
+        assert!(build_conf(&p).is_err());
+    }
+}
@@ -2,5 +2,5 @@ pub mod config;
 mod factory;
 mod sink;
 
-pub use config::VictoriaLog;
+pub use config::{VictoriaLog, VictoriaLogAuth};
 pub use factory::VictoriaLogSinkFactory;
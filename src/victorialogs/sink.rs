@@ -3,11 +3,26 @@ use std::sync::Arc;
 
 use async_trait::async_trait;
 use wp_connector_api::{
-    AsyncCtrl, AsyncRawDataSink, AsyncRecordSink, SinkError, SinkReason, SinkResult,
+    AsyncCtrl, AsyncRawDataSink, AsyncRecordSink, ParamMap, SinkError, SinkReason, SinkResult,
 };
 use wp_data_fmt::{DataFormat, FormatType};
 use wp_log::error_data;
-use wp_model_core::model::{DataRecord, Value, fmt_def::TextFmt};
+use wp_model_core::model::{DataRecord, fmt_def::TextFmt};
+
+use crate::common::reconfigure::{self, Reconfigure};
+use crate::common::reconnect::{ReconnectCoordinator, backend_key};
+use crate::common::record_time::record_time;
+use crate::common::retry::{self, PendingFlush};
+use crate::common::stats::StatsHandle;
+use crate::common::trace_context::TraceContext;
+use crate::victorialogs::config::VictoriaLogAuth;
+
+/// 重连时探测的健康检查路径
+const HEALTH_PATH: &str = "/health";
+/// 默认每条记录发送一次，与未配置 `batch` 时的行为一致
+const DEFAULT_BATCH: usize = 1;
+/// 声明日志流字段的请求头，其余字段作为普通日志字段写入
+const STREAM_FIELDS_HEADER: &str = "VL-Stream-Fields";
 
 pub(crate) struct VictoriaLogSink {
     endpoint: String,
@@ -15,32 +30,42 @@ pub(crate) struct VictoriaLogSink {
     client: reqwest::Client,
     fmt: TextFmt,
     create_time_field: Option<String>,
+    reconnect: ReconnectCoordinator,
+    /// 为每个写入请求附加 W3C trace 头；为 None 时不附加
+    trace_context: Option<TraceContext>,
+    /// 缓存达到该条数时合并为一个请求发送
+    batch: usize,
+    /// 待发送的 JSON 行
+    buffer: Vec<String>,
+    /// 缓存批内第一条记录携带的 trace id
+    buffer_trace_id: Option<String>,
+    /// 累计进入缓存的记录数
+    accepted: u64,
+    auth: VictoriaLogAuth,
+    /// 作为流标签的字段；为空时不发送 `VL-Stream-Fields`
+    stream_fields: Vec<String>,
+    /// `/stats` 自省状态（最近 flush 与错误）
+    stats: StatsHandle,
 }
 
 impl VictoriaLogSink {
-    /// 解析时间字段为字符串，逻辑与原实现一致：优先使用 create_time_field，回退当前时间。
+    /// 解析记录时间为 `_time`（纳秒时间戳字符串）：`create_time_field` 为时间值、
+    /// epoch 毫秒或 RFC3339 字符串时使用该时间，字段缺失或无法解析时回退为当前时间。
     fn resolve_timestamp_str(&self, data: &DataRecord) -> String {
-        let now = chrono::Utc::now()
-            .timestamp_nanos_opt()
-            .unwrap_or(chrono::Utc::now().timestamp_millis())
-            .to_string();
-
-        let Some(field) = &self.create_time_field else {
-            return now;
-        };
-        let Some(orin_timestamp) = data.get2(field) else {
-            return now;
-        };
-
-        if let Value::Time(dt) = &orin_timestamp.value {
-            return dt
-                .and_utc()
-                .timestamp_nanos_opt()
-                .unwrap_or_else(|| dt.and_utc().timestamp_millis())
-                .to_string();
+        let at = self
+            .create_time_field
+            .as_deref()
+            .and_then(|field| record_time(data, field))
+            .and_then(|dt| dt.and_utc().timestamp_nanos_opt());
+        match at {
+            Some(nanos) => nanos.to_string(),
+            None => {
+                let now = chrono::Utc::now();
+                now.timestamp_nanos_opt()
+                    .unwrap_or_else(|| now.timestamp_millis())
+                    .to_string()
+            }
         }
-
-        now
     }
 
     pub(crate) fn new(
@@ -50,19 +75,68 @@ impl VictoriaLogSink {
         fmt: TextFmt,
         create_time_field: Option<String>,
     ) -> Self {
+        let reconnect = ReconnectCoordinator::shared(
+            backend_key("victorialogs", &endpoint),
+            Default::default(),
+        );
+        let stats = StatsHandle::detached(&endpoint, "victorialogs");
         Self {
             endpoint,
             insert_path,
             client,
             fmt,
             create_time_field,
+            reconnect,
+            trace_context: None,
+            batch: DEFAULT_BATCH,
+            buffer: Vec::new(),
+            buffer_trace_id: None,
+            accepted: 0,
+            auth: VictoriaLogAuth::None,
+            stream_fields: Vec::new(),
+            stats,
         }
     }
-}
 
-#[async_trait]
-impl AsyncRecordSink for VictoriaLogSink {
-    async fn sink_record(&mut self, data: &DataRecord) -> SinkResult<()> {
+    pub(crate) fn with_batch(mut self, batch: usize) -> Self {
+        self.batch = batch.max(1);
+        self
+    }
+
+    pub(crate) fn with_auth(mut self, auth: VictoriaLogAuth) -> Self {
+        self.auth = auth;
+        self
+    }
+
+    /// 按配置为请求附加认证头。
+    fn authorize(&self, req: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        match &self.auth {
+            VictoriaLogAuth::None => req,
+            VictoriaLogAuth::Basic { username, password } => {
+                req.basic_auth(username, Some(password))
+            }
+            VictoriaLogAuth::Bearer(token) => req.bearer_auth(token),
+        }
+    }
+
+    pub(crate) fn with_stream_fields(mut self, stream_fields: Vec<String>) -> Self {
+        self.stream_fields = stream_fields;
+        self
+    }
+
+    /// 使用外部登记的状态句柄，以 endpoint 作为指标的写入目标。
+    pub(crate) fn with_stats(mut self, stats: StatsHandle) -> Self {
+        self.stats = stats.with_destination(self.endpoint.as_str());
+        self
+    }
+
+    pub(crate) fn with_trace_context(mut self, trace_context: Option<TraceContext>) -> Self {
+        self.trace_context = trace_context;
+        self
+    }
+
+    /// 将记录转换为一行 JSON：字段值、格式化后的 `_msg` 与 `_time`。
+    fn build_line(&self, data: &DataRecord) -> SinkResult<String> {
         let mut value_map = data
             .items
             .clone()
@@ -74,38 +148,82 @@ impl AsyncRecordSink for VictoriaLogSink {
         let formatted_msg = fmt.format_record(data);
         value_map.insert("_msg".to_string(), formatted_msg.clone());
         value_map.insert("_time".to_string(), timestamp);
-        let res = serde_json::to_string(&value_map).map_err(|e| {
+        serde_json::to_string(&value_map).map_err(|e| {
             SinkError::from(SinkReason::Sink(format!(
                 "build jsonline for victorialogs flush fail: {}",
                 e
             )))
-        })?;
+        })
+    }
 
-        match self
-            .client
-            .post(format!("{}{}", self.endpoint, self.insert_path))
-            .body(res)
-            .send()
-            .await
-        {
+    /// 将缓存的 JSON 行合并为一个请求发送；失败时保留缓存。
+    async fn flush(&mut self) -> SinkResult<()> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+        let result = self.send_buffer().await;
+        self.stats.record_flush(&result);
+        result?;
+        self.buffer.clear();
+        self.buffer_trace_id = None;
+        Ok(())
+    }
+
+    async fn send_buffer(&self) -> SinkResult<()> {
+        let mut req = self
+            .authorize(
+                self.client
+                    .post(format!("{}{}", self.endpoint, self.insert_path)),
+            )
+            .body(self.buffer.join("\n"));
+        if !self.stream_fields.is_empty() {
+            req = req.header(STREAM_FIELDS_HEADER, self.stream_fields.join(","));
+        }
+        if let Some(trace) = &self.trace_context {
+            for (name, value) in trace.headers_with_id(self.buffer_trace_id.clone()) {
+                req = req.header(name, value);
+            }
+        }
+        match req.send().await {
             Ok(resp) => {
-                if !resp.status().is_success() {
+                let status = resp.status();
+                if !status.is_success() {
                     error_data!("reqwest send error, text: {:?}", resp.text().await);
-                    return Err(SinkError::from(SinkReason::Sink(
-                        "reqwest send error".to_string(),
-                    )));
+                    return Err(retry::status_error(
+                        status.as_u16(),
+                        format!("reqwest send error: {}", status),
+                    ));
                 }
             }
             Err(e) => {
                 error_data!("reqwest send error, text: {:?}", e);
-                return Err(SinkError::from(SinkReason::Sink(format!(
-                    "reqwest send fail: {}",
-                    e
-                ))));
+                return Err(retry::transport_error(
+                    format!("reqwest send fail: {}", e),
+                    e.is_timeout(),
+                ));
             }
         };
         Ok(())
     }
+}
+
+#[async_trait]
+impl AsyncRecordSink for VictoriaLogSink {
+    async fn sink_record(&mut self, data: &DataRecord) -> SinkResult<()> {
+        let line = self.build_line(data)?;
+        if self.buffer_trace_id.is_none() {
+            self.buffer_trace_id = self
+                .trace_context
+                .as_ref()
+                .and_then(|trace| trace.record_trace_id(data));
+        }
+        self.buffer.push(line);
+        self.accepted += 1;
+        if self.buffer.len() >= self.batch {
+            self.flush().await?;
+        }
+        Ok(())
+    }
 
     async fn sink_records(&mut self, data: Vec<Arc<DataRecord>>) -> SinkResult<()> {
         for record in data {
@@ -115,13 +233,67 @@ impl AsyncRecordSink for VictoriaLogSink {
     }
 }
 
+/// 可热更新：`batch`。
+impl Reconfigure for VictoriaLogSink {
+    fn reconfigure(&mut self, params: &ParamMap) -> SinkResult<()> {
+        reconfigure::check_keys(params, &["batch"], "victorialog")?;
+        if let Some(batch) = reconfigure::opt_batch(params, &["batch"], "victorialog")? {
+            self.batch = batch;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl PendingFlush for VictoriaLogSink {
+    fn pending_len(&self) -> usize {
+        self.buffer.len()
+    }
+
+    async fn flush_now(&mut self) -> SinkResult<()> {
+        self.flush().await
+    }
+
+    fn discard_pending(&mut self) -> usize {
+        let discarded = self.buffer.len();
+        self.buffer.clear();
+        self.buffer_trace_id = None;
+        discarded
+    }
+
+    fn accepted(&self) -> u64 {
+        self.accepted
+    }
+}
+
 #[async_trait]
 impl AsyncCtrl for VictoriaLogSink {
     async fn stop(&mut self) -> SinkResult<()> {
-        Ok(())
+        self.flush().await
     }
     async fn reconnect(&mut self) -> SinkResult<()> {
-        Ok(())
+        let url = format!("{}{}", self.endpoint, HEALTH_PATH);
+        let this = &*self;
+        this.reconnect
+            .run(|| {
+                let req = this.authorize(this.client.get(&url));
+                async move {
+                    let resp = req.send().await.map_err(|e| {
+                        SinkError::from(SinkReason::Sink(format!(
+                            "victorialogs health check fail: {}",
+                            e
+                        )))
+                    })?;
+                    if !resp.status().is_success() {
+                        return Err(SinkError::from(SinkReason::Sink(format!(
+                            "victorialogs health check status: {}",
+                            resp.status()
+                        ))));
+                    }
+                    Ok(())
+                }
+            })
+            .await
     }
 }
 
@@ -179,6 +351,149 @@ mod tests {
         assert!(result.is_ok(), "sink_record should return Ok");
     }
 
+    #[tokio::test]
+    async fn trace_context_headers_attached_to_insert_requests() {
+        let server = MockServer::start_async().await;
+        let traced = server.mock(|when, then| {
+            when.method(POST)
+                .path("/insert")
+                .header_exists("traceparent")
+                .header("tracestate", "wp=pipeline");
+            then.status(200);
+        });
+        let trace = TraceContext {
+            trace_id_field: Some("trace_id".into()),
+            tracestate: Some("wp=pipeline".into()),
+        };
+        let mut sink = create_test_sink(None).with_trace_context(Some(trace));
+        sink.endpoint = server.base_url();
+
+        let mut record = DataRecord::default();
+        record.append(DataField::from_chars(
+            "trace_id",
+            "4bf92f3577b34da6a3ce929d0e0e4736",
+        ));
+        sink.sink_record(&record).await.expect("traced insert");
+        sink.sink_record(&DataRecord::default())
+            .await
+            .expect("generated trace id");
+        traced.assert_hits(2);
+    }
+
+    #[tokio::test]
+    async fn records_are_buffered_until_batch_is_full_or_stopped() {
+        let server = MockServer::start_async().await;
+        let insert = server.mock(|when, then| {
+            when.method(POST).path("/insert").matches(|req| {
+                let body = req.body.clone().unwrap_or_default();
+                let body = String::from_utf8_lossy(&body);
+                body.lines().count() == 2
+                    && body
+                        .lines()
+                        .all(|l| serde_json::from_str::<serde_json::Value>(l).is_ok())
+            });
+            then.status(200);
+        });
+        let mut sink = create_test_sink(None).with_batch(3);
+        sink.endpoint = server.base_url();
+
+        let records = vec![Arc::new(DataRecord::default()); 2];
+        sink.sink_records(records).await.expect("buffered");
+        insert.assert_hits(0);
+        sink.stop().await.expect("flush on stop");
+        insert.assert_hits(1);
+
+        // 达到 batch 时立即合并发送
+        let mut sink = sink.with_batch(2);
+        sink.sink_record(&DataRecord::default())
+            .await
+            .expect("buffered");
+        insert.assert_hits(1);
+        sink.sink_record(&DataRecord::default())
+            .await
+            .expect("batch full");
+        insert.assert_hits(2);
+        sink.stop().await.expect("nothing buffered");
+        insert.assert_hits(2);
+    }
+
+    #[tokio::test]
+    async fn auth_headers_follow_config() {
+        let server = MockServer::start_async().await;
+        let bearer = server.mock(|when, then| {
+            when.method(POST)
+                .path("/insert")
+                .header("authorization", "Bearer t0k");
+            then.status(200);
+        });
+        let basic = server.mock(|when, then| {
+            // base64("vl:secret")
+            when.method(GET)
+                .path(HEALTH_PATH)
+                .header("authorization", "Basic dmw6c2VjcmV0");
+            then.status(200);
+        });
+
+        let mut sink = create_test_sink(None).with_auth(VictoriaLogAuth::Bearer("t0k".into()));
+        sink.endpoint = server.base_url();
+        sink.sink_record(&DataRecord::default())
+            .await
+            .expect("bearer insert");
+        bearer.assert_hits(1);
+
+        let mut sink = sink.with_auth(VictoriaLogAuth::Basic {
+            username: "vl".into(),
+            password: "secret".into(),
+        });
+        sink.reconnect().await.expect("basic health check");
+        basic.assert_hits(1);
+    }
+
+    #[tokio::test]
+    async fn stream_fields_allowlist_selects_stream_labels() {
+        let server = MockServer::start_async().await;
+        let insert = server.mock(|when, then| {
+            when.method(POST)
+                .path("/insert")
+                .header(STREAM_FIELDS_HEADER, "host,app")
+                .matches(|req| {
+                    let body = req.body.clone().unwrap_or_default();
+                    let line: serde_json::Value = serde_json::from_slice(&body).unwrap_or_default();
+                    // 流标签与普通字段都在日志行中，由请求头区分
+                    line["host"] == "web-1" && line["app"] == "api" && line["user"] == "u-42"
+                });
+            then.status(200);
+        });
+        let mut sink = create_test_sink(None).with_stream_fields(vec!["host".into(), "app".into()]);
+        sink.endpoint = server.base_url();
+
+        let mut record = DataRecord::default();
+        record.append(DataField::from_chars("host", "web-1"));
+        record.append(DataField::from_chars("app", "api"));
+        record.append(DataField::from_chars("user", "u-42"));
+        sink.sink_record(&record)
+            .await
+            .expect("stream labelled insert");
+        insert.assert_hits(1);
+
+        // 未配置 stream_fields 时不声明流字段，行为与之前一致
+        let plain = server.mock(|when, then| {
+            when.method(POST).path("/plain").matches(|req| {
+                req.headers.as_ref().is_none_or(|headers| {
+                    headers
+                        .iter()
+                        .all(|(name, _)| !name.eq_ignore_ascii_case(STREAM_FIELDS_HEADER))
+                })
+            });
+            then.status(200);
+        });
+        let mut sink = create_test_sink(None);
+        sink.endpoint = server.base_url();
+        sink.insert_path = "/plain".into();
+        sink.sink_record(&record).await.expect("plain insert");
+        plain.assert_hits(1);
+    }
+
     /// 创建用于测试的 VictoriaLogSink 实例
     ///
     /// # 参数
@@ -202,6 +517,27 @@ mod tests {
         )
     }
 
+    #[test]
+    fn timestamp_field_parses_epoch_millis_and_rfc3339() {
+        let sink = create_test_sink(Some("ts"));
+        let stamp = |value: &str| {
+            let mut record = DataRecord::default();
+            record.append(DataField::from_chars("ts", value));
+            sink.resolve_timestamp_str(&record)
+        };
+        assert_eq!(stamp("1714564800123"), "1714564800123000000");
+        assert_eq!(stamp("2024-05-01T20:00:00.5+08:00"), "1714564800500000000");
+
+        // 缺失或无法解析时回退为当前时间
+        let before = chrono::Utc::now().timestamp_nanos_opt().unwrap();
+        for fallback in [
+            stamp("yesterday"),
+            sink.resolve_timestamp_str(&DataRecord::default()),
+        ] {
+            assert!(fallback.parse::<i64>().unwrap() >= before, "{fallback}");
+        }
+    }
+
     #[tokio::test]
     async fn test_resolve_timestamp_str() {
         // 测试用例定义
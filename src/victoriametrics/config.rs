@@ -8,4 +8,18 @@ pub struct VictoriaMetric {
     pub insert_url: String,
     #[educe(Default = 0.1)]
     pub flush_interval_secs: f64,
+    /// 作为 label 附加到各序列的记录字段名。
+    #[serde(default)]
+    pub label_fields: Vec<String>,
+    /// 作为固定 label 附加到各序列的 `key:value` tag。
+    #[serde(default)]
+    pub label_tags: Vec<String>,
+    /// 每个字段 label 最多保留的不同取值数，超出的取值归入 `__other__`，以限制序列数。
+    #[educe(Default = 100)]
+    #[serde(default = "default_label_value_limit")]
+    pub label_value_limit: usize,
+}
+
+fn default_label_value_limit() -> usize {
+    100
 }
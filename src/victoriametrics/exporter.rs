@@ -2,6 +2,7 @@ use std::time::Duration;
 
 use async_trait::async_trait;
 use orion_conf::StructError;
+use prometheus::proto::MetricFamily;
 use prometheus::{Encoder, TextEncoder};
 use std::sync::Arc;
 use tokio::{sync::oneshot, task::JoinHandle};
@@ -9,7 +10,9 @@ use wp_connector_api::{SinkReason, SinkResult};
 use wp_log::{error_data, info_data};
 use wp_model_core::model::{DataRecord, Value};
 
-use crate::victoriametrics::metrics::{sink_type_stat, source_type_stat};
+use crate::common::reconnect::{ReconnectCoordinator, backend_key, probe_endpoint};
+use crate::common::stats::StatsHandle;
+use crate::victoriametrics::metrics::{LabeledMetrics, sink_type_stat, source_type_stat};
 
 use super::metrics::{parse_all_stat, parse_success_stat, receive_data_stat, sink_stat};
 
@@ -17,8 +20,11 @@ pub(crate) struct VictoriaMetricExporter {
     insert_url: String,
     client: reqwest::Client,
     flush_interval: Duration,
+    labeled: Option<Arc<LabeledMetrics>>,
     stop_tx: Option<oneshot::Sender<()>>,
     flush_handle: Option<JoinHandle<()>>,
+    /// `/stats` 自省状态（最近推送结果），与定时推送任务共享
+    stats: StatsHandle,
 }
 
 impl Clone for VictoriaMetricExporter {
@@ -27,8 +33,10 @@ impl Clone for VictoriaMetricExporter {
             insert_url: self.insert_url.clone(),
             client: self.client.clone(),
             flush_interval: self.flush_interval,
+            labeled: self.labeled.clone(),
             stop_tx: None,
             flush_handle: None,
+            stats: self.stats.clone(),
         }
     }
 }
@@ -40,16 +48,36 @@ impl VictoriaMetricExporter {
         flush_interval: Duration,
     ) -> Self {
         Self {
+            stats: StatsHandle::detached(&insert_url, "victoriametrics"),
             insert_url,
             flush_interval,
+            labeled: None,
             stop_tx: None,
             flush_handle: None,
             client,
         }
     }
 
+    /// 使用外部登记的状态句柄，以导入地址作为指标的写入目标。
+    pub(crate) fn with_stats(mut self, stats: StatsHandle) -> Self {
+        self.stats = stats.with_destination(self.insert_url.as_str());
+        self
+    }
+
+    /// 配置后记录只计入带附加 label 的独立指标集，推送时也只导出该指标集。
+    pub(crate) fn with_labels(mut self, labeled: LabeledMetrics) -> Self {
+        self.labeled = Some(Arc::new(labeled));
+        self
+    }
+
     pub(crate) async fn save_metric_to_victoriametric(&self) -> SinkResult<()> {
-        Self::push_metrics(&self.client, &self.insert_url).await
+        let metric_families = match &self.labeled {
+            Some(labeled) => labeled.gather(),
+            None => prometheus::gather(),
+        };
+        let result = Self::push_metrics(&self.client, &self.insert_url, &metric_families).await;
+        self.stats.record_flush(&result);
+        result
     }
 
     pub(crate) fn start_flush_task(&mut self) {
@@ -99,24 +127,37 @@ impl VictoriaMetricExporter {
         }
     }
 
-    async fn push_metrics(client: &reqwest::Client, insert_url: &str) -> SinkResult<()> {
-        let encoder = TextEncoder::new();
-        let metric_families = prometheus::gather();
+    /// 将上游已格式化好的 exposition 文本直接转发到导入接口。
+    async fn push_raw(&self, body: Vec<u8>) -> SinkResult<()> {
+        if body.is_empty() {
+            return Ok(());
+        }
+        let result = Self::post_body(&self.client, &self.insert_url, body).await;
+        self.stats.record_flush(&result);
+        result
+    }
+
+    async fn push_metrics(
+        client: &reqwest::Client,
+        insert_url: &str,
+        metric_families: &[MetricFamily],
+    ) -> SinkResult<()> {
         if metric_families.is_empty() {
             info_data!("No metrics to export");
             return Ok(());
         }
-        let mut buffer = Vec::new();
-        if let Err(e) = encoder.encode(&metric_families, &mut buffer) {
-            return Err(
-                StructError::from(SinkReason::Sink("prometheus encode error".to_string()))
-                    .with_detail(e.to_string()),
-            );
-        }
+        let buffer = encode_metrics(metric_families)?;
+        Self::post_body(client, insert_url, buffer).await
+    }
 
+    async fn post_body(
+        client: &reqwest::Client,
+        insert_url: &str,
+        body: Vec<u8>,
+    ) -> SinkResult<()> {
         let response = client
             .post(insert_url)
-            .body(buffer)
+            .body(body)
             .send()
             .await
             .map_err(|e| {
@@ -140,6 +181,10 @@ impl VictoriaMetricExporter {
 #[async_trait]
 impl wp_connector_api::AsyncRecordSink for VictoriaMetricExporter {
     async fn sink_record(&mut self, data: &DataRecord) -> SinkResult<()> {
+        if let Some(labeled) = &self.labeled {
+            labeled.observe(data);
+            return Ok(());
+        }
         if let Some(Value::Chars(field)) = data.get2("stage").map(|x| x.get_value()) {
             match field.as_str() {
                 "Pick" => {
@@ -174,40 +219,144 @@ impl wp_connector_api::AsyncCtrl for VictoriaMetricExporter {
         self.stop_flush_task().await;
         Ok(())
     }
+    /// 不推送指标，只确认导入地址可连通，按后端共享退避。
     async fn reconnect(&mut self) -> SinkResult<()> {
-        Ok(())
+        ReconnectCoordinator::shared(
+            backend_key("victoriametrics", &self.insert_url),
+            Default::default(),
+        )
+        .run(|| probe_endpoint(&self.insert_url))
+        .await
     }
 }
 
 #[async_trait]
 impl wp_connector_api::AsyncRawDataSink for VictoriaMetricExporter {
-    async fn sink_str(&mut self, _data: &str) -> SinkResult<()> {
-        Err(SinkReason::Sink(
-            "VictoriaMetric exporter does not support raw input; route TDC metrics only".into(),
-        )
-        .into())
+    async fn sink_str(&mut self, data: &str) -> SinkResult<()> {
+        validate_exposition(data)?;
+        self.push_raw(data.as_bytes().to_vec()).await
     }
 
-    async fn sink_bytes(&mut self, _data: &[u8]) -> SinkResult<()> {
-        Err(SinkReason::Sink(
-            "VictoriaMetric exporter does not support raw bytes; route TDC metrics only".into(),
-        )
-        .into())
+    async fn sink_bytes(&mut self, data: &[u8]) -> SinkResult<()> {
+        let text = std::str::from_utf8(data).map_err(|e| {
+            StructError::from(SinkReason::Sink("raw metrics must be utf-8".to_string()))
+                .with_detail(e.to_string())
+        })?;
+        self.sink_str(text).await
     }
 
-    async fn sink_str_batch(&mut self, _data: Vec<&str>) -> SinkResult<()> {
-        Err(SinkReason::Sink(
-            "VictoriaMetric exporter does not support raw input; route TDC metrics only".into(),
-        )
-        .into())
+    async fn sink_str_batch(&mut self, data: Vec<&str>) -> SinkResult<()> {
+        for item in &data {
+            validate_exposition(item)?;
+        }
+        self.push_raw(join_lines(data.iter().map(|s| s.as_bytes())))
+            .await
     }
 
-    async fn sink_bytes_batch(&mut self, _data: Vec<&[u8]>) -> SinkResult<()> {
-        Err(SinkReason::Sink(
-            "VictoriaMetric exporter does not support raw bytes; route TDC metrics only".into(),
-        )
-        .into())
+    async fn sink_bytes_batch(&mut self, data: Vec<&[u8]>) -> SinkResult<()> {
+        for item in &data {
+            let text = std::str::from_utf8(item).map_err(|e| {
+                StructError::from(SinkReason::Sink("raw metrics must be utf-8".to_string()))
+                    .with_detail(e.to_string())
+            })?;
+            validate_exposition(text)?;
+        }
+        self.push_raw(join_lines(data.into_iter())).await
+    }
+}
+
+/// 按 Prometheus 文本格式编码，label 值中的 `\\`、`"` 与换行会被转义。
+fn encode_metrics(metric_families: &[MetricFamily]) -> SinkResult<Vec<u8>> {
+    let mut buffer = Vec::new();
+    TextEncoder::new()
+        .encode(metric_families, &mut buffer)
+        .map_err(|e| {
+            StructError::from(SinkReason::Sink("prometheus encode error".to_string()))
+                .with_detail(e.to_string())
+        })?;
+    Ok(buffer)
+}
+
+/// 多段原始文本按行拼接为一个请求体。
+fn join_lines<'a>(items: impl Iterator<Item = &'a [u8]>) -> Vec<u8> {
+    let mut body = Vec::new();
+    for item in items {
+        body.extend_from_slice(item);
+        if !item.ends_with(b"\n") {
+            body.push(b'\n');
+        }
+    }
+    body
+}
+
+/// 校验 Prometheus exposition 文本：`name{labels} value [timestamp]`，允许空行与 `#` 注释。
+fn validate_exposition(text: &str) -> SinkResult<()> {
+    for (idx, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Err(reason) = check_sample_line(line) {
+            return Err(StructError::from(SinkReason::Sink(format!(
+                "invalid exposition line {}: {}",
+                idx + 1,
+                reason
+            )))
+            .with_detail(line.to_string()));
+        }
+    }
+    Ok(())
+}
+
+fn check_sample_line(line: &str) -> Result<(), &'static str> {
+    let name_end = line
+        .find(|c: char| c == '{' || c.is_whitespace())
+        .ok_or("missing value")?;
+    let name = &line[..name_end];
+    let mut chars = name.chars();
+    let valid_name = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == ':')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == ':');
+    if !valid_name {
+        return Err("invalid metric name");
+    }
+    let mut rest = &line[name_end..];
+    if rest.starts_with('{') {
+        let close = labels_end(rest).ok_or("unterminated label set")?;
+        rest = &rest[close + 1..];
+    }
+    let mut parts = rest.split_whitespace();
+    let value = parts.next().ok_or("missing value")?;
+    let valid_value = matches!(value, "NaN" | "+Inf" | "-Inf") || value.parse::<f64>().is_ok();
+    if !valid_value {
+        return Err("invalid sample value");
+    }
+    if let Some(ts) = parts.next()
+        && ts.parse::<i64>().is_err()
+    {
+        return Err("invalid timestamp");
+    }
+    if parts.next().is_some() {
+        return Err("unexpected trailing content");
     }
+    Ok(())
+}
+
+/// 返回与起始 `{` 匹配的 `}` 位置，跳过引号内（含转义）的内容。
+fn labels_end(text: &str) -> Option<usize> {
+    let mut in_quote = false;
+    let mut escaped = false;
+    for (i, c) in text.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_quote => escaped = true,
+            '"' => in_quote = !in_quote,
+            '}' if !in_quote => return Some(i),
+            _ => {}
+        }
+    }
+    None
 }
 
 #[cfg(test)]
@@ -296,6 +445,129 @@ mod tests {
         assert_eq!(sink_gauge.get(), 1.0);
     }
 
+    #[tokio::test]
+    async fn raw_exposition_lines_forwarded_to_import_endpoint() {
+        use httpmock::prelude::*;
+        use wp_connector_api::AsyncRawDataSink;
+
+        let server = MockServer::start_async().await;
+        let import = server.mock(|when, then| {
+            when.method(POST)
+                .path("/insert")
+                .body_contains("metric{label=\"v\"} 1.0\n");
+            then.status(204);
+        });
+        let client = reqwest::Client::builder()
+            .no_proxy()
+            .build()
+            .expect("client");
+        let mut exporter =
+            VictoriaMetricExporter::new(server.url("/insert"), client, Duration::from_secs(1));
+
+        exporter
+            .sink_str("# TYPE metric gauge\nmetric{label=\"v\"} 1.0\n")
+            .await
+            .expect("forwarded");
+        exporter
+            .sink_bytes_batch(vec![
+                b"metric{label=\"v\"} 1.0".as_slice(),
+                b"other_total{a=\"x}y\"} 3 1700000000000".as_slice(),
+            ])
+            .await
+            .expect("batch forwarded");
+        import.assert_hits(2);
+    }
+
+    #[test]
+    fn exposition_validation_rejects_malformed_lines() {
+        let sample = "up 1\nhttp_requests_total{code=\"200\"} 1027 1395066363000";
+        assert!(validate_exposition(sample).is_ok());
+        assert!(validate_exposition("nan_metric NaN").is_ok());
+        assert!(validate_exposition("{\"json\": true}").is_err());
+        assert!(validate_exposition("metric{label=\"v\" 1.0").is_err());
+        assert!(validate_exposition("metric abc").is_err());
+        assert!(validate_exposition("1metric 1").is_err());
+
+        let body = join_lines([b"a 1".as_slice(), b"b{x=\"}\"} 2\n".as_slice()].into_iter());
+        assert_eq!(body, b"a 1\nb{x=\"}\"} 2\n".to_vec());
+        assert!(validate_exposition(std::str::from_utf8(&body).unwrap()).is_ok());
+    }
+
+    #[tokio::test]
+    async fn label_fields_and_tags_escaped_in_exposition() {
+        let labeled = LabeledMetrics::new(
+            &["host".to_string(), "app.name".to_string()],
+            &[("env".to_string(), "pro\"d".to_string())],
+            10,
+        )
+        .expect("labels");
+        let mut exporter = test_exporter().with_labels(labeled);
+
+        let mut record = DataRecord::default();
+        record.append(DataField::from_chars("stage", "Pick"));
+        record.append(DataField::from_chars("target", "label-target"));
+        record.append(DataField::from_digit("total", 3));
+        record.append(DataField::from_chars("host", "a\"b\\c\nd"));
+        exporter.sink_record(&record).await.unwrap();
+
+        let families = exporter.labeled.as_ref().expect("labeled").gather();
+        let text = String::from_utf8(encode_metrics(&families).unwrap()).unwrap();
+        let line = text
+            .lines()
+            .find(|l| l.starts_with("wparse_receive_data{"))
+            .expect("receive series");
+        assert!(line.contains(r#"host="a\"b\\c\nd""#), "{line}");
+        assert!(line.contains(r#"app_name="""#), "{line}");
+        assert!(line.contains(r#"env="pro\"d""#), "{line}");
+        assert!(line.ends_with(" 3"), "{line}");
+        assert!(validate_exposition(&text).is_ok());
+    }
+
+    #[test]
+    fn label_names_sanitized_and_conflicts_rejected() {
+        use crate::victoriametrics::metrics::label_name;
+        assert_eq!(label_name("app.name"), "app_name");
+        assert_eq!(label_name("1st"), "_1st");
+        assert!(LabeledMetrics::new(&["pid".to_string()], &[], 10).is_err());
+        assert!(
+            LabeledMetrics::new(&["a-b".to_string()], &[("a_b".into(), "x".into())], 10).is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn label_values_beyond_limit_fold_into_other() {
+        use crate::victoriametrics::metrics::OTHER_LABEL_VALUE;
+        let labeled = LabeledMetrics::new(&["host".to_string()], &[], 2).expect("labels");
+        let mut exporter = test_exporter().with_labels(labeled);
+        for host in ["a", "b", "c", "d", "a"] {
+            let mut record = DataRecord::default();
+            record.append(DataField::from_chars("stage", "Pick"));
+            record.append(DataField::from_chars("target", "label-target"));
+            record.append(DataField::from_digit("total", 1));
+            record.append(DataField::from_chars("host", host));
+            exporter.sink_record(&record).await.unwrap();
+        }
+
+        let families = exporter.labeled.as_ref().expect("labeled").gather();
+        let text = String::from_utf8(encode_metrics(&families).unwrap()).unwrap();
+        let series: Vec<&str> = text
+            .lines()
+            .filter(|l| l.starts_with("wparse_receive_data{"))
+            .collect();
+        assert_eq!(series.len(), 3, "{text}");
+        let value_of = |host: &str| {
+            let label = format!("host=\"{host}\"");
+            series
+                .iter()
+                .find(|l| l.contains(&label))
+                .and_then(|l| l.rsplit(' ').next())
+                .map(str::to_string)
+        };
+        assert_eq!(value_of("a").as_deref(), Some("2"));
+        assert_eq!(value_of("b").as_deref(), Some("1"));
+        assert_eq!(value_of(OTHER_LABEL_VALUE).as_deref(), Some("2"));
+    }
+
     #[tokio::test]
     async fn flush_task_start_and_stop_transitions() {
         let mut exporter = test_exporter();
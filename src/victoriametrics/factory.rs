@@ -9,6 +9,8 @@ use wp_connector_api::{
 
 use super::config::VictoriaMetric;
 use super::exporter::VictoriaMetricExporter;
+use super::metrics::LabeledMetrics;
+use crate::common::stats;
 
 pub struct VictoriaMetricFactory;
 
@@ -26,6 +28,14 @@ impl SinkFactory for VictoriaMetricFactory {
         if endpoint.trim().is_empty() {
             return Err(SinkReason::sink("victoriametric.endpoint must not be empty").into());
         }
+        let fields = parse_str_list(&spec.params, "label_fields")?;
+        let tags = parse_label_tags(&parse_str_list(&spec.params, "label_tags")?)?;
+        let limit = parse_label_value_limit(&spec.params)?;
+        labeled_metrics(
+            &fields,
+            &tags,
+            limit.unwrap_or(VictoriaMetric::default().label_value_limit),
+        )?;
         Ok(())
     }
     async fn build(&self, spec: &SinkSpec, _ctx: &SinkBuildCtx) -> SinkResult<SinkHandle> {
@@ -44,6 +54,12 @@ impl SinkFactory for VictoriaMetricFactory {
         if let Some(s) = spec.params.get("insert_url").and_then(|v| v.as_str()) {
             conf.insert_url = s.to_string();
         }
+        conf.label_fields = parse_str_list(&spec.params, "label_fields")?;
+        conf.label_tags = parse_str_list(&spec.params, "label_tags")?;
+        let tags = parse_label_tags(&conf.label_tags)?;
+        if let Some(limit) = parse_label_value_limit(&spec.params)? {
+            conf.label_value_limit = limit;
+        }
 
         let client = reqwest::Client::builder()
             .timeout(Duration::from_secs(5))
@@ -57,7 +73,15 @@ impl SinkFactory for VictoriaMetricFactory {
             conf.insert_url.clone(),
             client,
             Duration::from_secs_f64(conf.flush_interval_secs),
-        );
+        )
+        .with_stats(stats::register(&spec.name, self.kind()));
+        if !conf.label_fields.is_empty() || !tags.is_empty() {
+            sink = sink.with_labels(labeled_metrics(
+                &conf.label_fields,
+                &tags,
+                conf.label_value_limit,
+            )?);
+        }
         sink.start_flush_task();
         Ok(SinkHandle::new(Box::new(sink)))
     }
@@ -69,10 +93,16 @@ impl SinkDefProvider for VictoriaMetricFactory {
             id: "victoriametric_sink".into(),
             kind: self.kind().into(),
             scope: ConnectorScope::Sink,
-            allow_override: vec!["endpoint", "flush_interval_secs"]
-                .into_iter()
-                .map(str::to_string)
-                .collect(),
+            allow_override: vec![
+                "endpoint",
+                "flush_interval_secs",
+                "label_fields",
+                "label_tags",
+                "label_value_limit",
+            ]
+            .into_iter()
+            .map(str::to_string)
+            .collect(),
             default_params: victoriametric_defaults(),
             origin: Some("wp-connectors:victoriametric_sink".into()),
         }
@@ -85,3 +115,58 @@ fn victoriametric_defaults() -> ParamMap {
     params.insert("flush_interval_secs".into(), json!(5.0));
     params
 }
+
+fn labeled_metrics(
+    fields: &[String],
+    tags: &[(String, String)],
+    value_limit: usize,
+) -> SinkResult<LabeledMetrics> {
+    LabeledMetrics::new(fields, tags, value_limit)
+        .map_err(|err| SinkReason::sink(format!("victoriametric labels invalid: {err}")).into())
+}
+
+/// 可选的字符串数组参数，元素须为非空字符串。
+fn parse_str_list(params: &ParamMap, key: &str) -> SinkResult<Vec<String>> {
+    let Some(value) = params.get(key).filter(|v| !v.is_null()) else {
+        return Ok(Vec::new());
+    };
+    let invalid = || -> SinkError {
+        SinkReason::sink(format!("victoriametric.{key} must be a string array")).into()
+    };
+    let mut items = Vec::new();
+    for item in value.as_array().ok_or_else(invalid)? {
+        let item = item.as_str().map(str::trim).filter(|s| !s.is_empty());
+        items.push(item.ok_or_else(invalid)?.to_string());
+    }
+    Ok(items)
+}
+
+/// 可选的 `label_value_limit`，须为正整数。
+fn parse_label_value_limit(params: &ParamMap) -> SinkResult<Option<usize>> {
+    let Some(value) = params.get("label_value_limit").filter(|v| !v.is_null()) else {
+        return Ok(None);
+    };
+    match value.as_u64().filter(|n| *n > 0) {
+        Some(n) => Ok(Some(n as usize)),
+        None => Err(SinkReason::sink(
+            "victoriametric.label_value_limit must be a positive integer",
+        )
+        .into()),
+    }
+}
+
+/// `label_tags` 的元素与 `tags` 相同，为 `key:value` 形式。
+fn parse_label_tags(items: &[String]) -> SinkResult<Vec<(String, String)>> {
+    items
+        .iter()
+        .map(|item| match item.split_once(':') {
+            Some((key, value)) if !key.trim().is_empty() => {
+                Ok((key.trim().to_string(), value.trim().to_string()))
+            }
+            _ => Err(SinkReason::sink(format!(
+                "victoriametric.label_tags item '{item}' must be 'key:value'"
+            ))
+            .into()),
+        })
+        .collect()
+}
@@ -1,3 +1,6 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::{Mutex, PoisonError};
+
 use lazy_static::lazy_static;
 use orion_exp::ValueGet0;
 use prometheus::GaugeVec;
@@ -23,7 +26,8 @@ impl<'a> IntoOptField<'a> for Option<&'a ModelField<Value>> {
         OptField(self)
     }
 }
-use prometheus::{IntCounterVec, register_int_counter_vec};
+use prometheus::proto::MetricFamily;
+use prometheus::{IntCounterVec, Opts, Registry, register_int_counter_vec};
 use uuid::Uuid;
 use wp_model_core::model::DataRecord;
 use wp_model_core::model::Value;
@@ -142,6 +146,243 @@ pub fn sink_type_stat(data: &DataRecord) {
     SINK_TYPES.with_label_values(&values.values()).set(flag);
 }
 
+/// 附加记录字段/标签作为 label 的指标集：与全局指标同名，但注册在独立 registry 中，
+/// label 为内置 label 加上配置的字段 label（记录缺失该字段时取空串）与固定的 tag label。
+/// 每个字段 label 只保留最先出现的 `value_limit` 个取值，之后的新取值记为 [`OTHER_LABEL_VALUE`]，
+/// 避免高基数字段撑爆序列数。
+/// label 值的转义（`\\`、`"`、换行）由 `TextEncoder` 按 exposition 格式完成。
+pub(crate) struct LabeledMetrics {
+    registry: Registry,
+    // (记录字段名, label 名)
+    labels: Vec<(String, String)>,
+    value_limit: usize,
+    // 各字段 label 已出现的取值，与 labels 一一对应
+    seen_values: Mutex<Vec<HashSet<String>>>,
+    recv: IntCounterVec,
+    source_types: GaugeVec,
+    parse_success: IntCounterVec,
+    parse_all: IntCounterVec,
+    send_sink: IntCounterVec,
+    sink_types: GaugeVec,
+}
+
+/// 字段 label 取值超出上限后使用的取值。
+pub(crate) const OTHER_LABEL_VALUE: &str = "__other__";
+
+impl LabeledMetrics {
+    /// 按字段名与 `(key, value)` tag 创建；名称中 label 不允许的字符替换为 `_`，
+    /// 与内置 label 或彼此重名时返回错误。`value_limit` 为每个字段 label 的取值上限。
+    pub(crate) fn new(
+        fields: &[String],
+        tags: &[(String, String)],
+        value_limit: usize,
+    ) -> Result<Self, String> {
+        let mut seen: Vec<String> = Vec::with_capacity(fields.len() + tags.len());
+        let names = fields
+            .iter()
+            .map(String::as_str)
+            .chain(tags.iter().map(|(k, _)| k.as_str()));
+        for name in names {
+            let label = label_name(name);
+            if builtin_labels().contains(&label.as_str()) {
+                return Err(format!("label `{label}` conflicts with a built-in label"));
+            }
+            if seen.contains(&label) {
+                return Err(format!("label `{label}` is configured more than once"));
+            }
+            seen.push(label);
+        }
+        let labels: Vec<(String, String)> =
+            fields.iter().map(|f| (f.clone(), label_name(f))).collect();
+        let const_labels: HashMap<String, String> = tags
+            .iter()
+            .map(|(k, v)| (label_name(k), v.clone()))
+            .collect();
+        let registry = Registry::new();
+        let opts =
+            |name: &str, help: &str| Opts::new(name, help).const_labels(const_labels.clone());
+        let names = |base: Vec<&'static str>| {
+            let mut names: Vec<String> = base.into_iter().map(str::to_string).collect();
+            names.extend(labels.iter().map(|(_, l)| l.clone()));
+            names
+        };
+        let counter = |name: &str, help: &str, base| -> Result<IntCounterVec, String> {
+            let names = names(base);
+            let vec = IntCounterVec::new(opts(name, help), &as_strs(&names))
+                .map_err(|e| e.to_string())?;
+            registry
+                .register(Box::new(vec.clone()))
+                .map_err(|e| e.to_string())?;
+            Ok(vec)
+        };
+        let gauge = |name: &str, help: &str, base| -> Result<GaugeVec, String> {
+            let names = names(base);
+            let vec =
+                GaugeVec::new(opts(name, help), &as_strs(&names)).map_err(|e| e.to_string())?;
+            registry
+                .register(Box::new(vec.clone()))
+                .map_err(|e| e.to_string())?;
+            Ok(vec)
+        };
+        let recv = counter(
+            "wparse_receive_data",
+            "Number of logs obtained from the data source.",
+            RecvMetrics::labels(),
+        )?;
+        let source_types = gauge(
+            "wparse_source_types",
+            "The count of source types.",
+            SourceTypeMetrics::labels(),
+        )?;
+        let parse_success = counter(
+            "wparse_parse_success",
+            "Number of logs parse.",
+            ParseMetrics::labels(),
+        )?;
+        let parse_all = counter(
+            "wparse_parse_all",
+            "Number of logs parse.",
+            ParseAllMetrics::labels(),
+        )?;
+        let send_sink = counter(
+            "wparse_send_to_sink",
+            "The count of send to sink.",
+            SinkMetrics::labels(),
+        )?;
+        let sink_types = gauge(
+            "wparse_sink_types",
+            "The count of sink types.",
+            SinkTypeMetrics::labels(),
+        )?;
+        Ok(Self {
+            registry,
+            seen_values: Mutex::new(vec![HashSet::new(); labels.len()]),
+            labels,
+            value_limit,
+            recv,
+            source_types,
+            parse_success,
+            parse_all,
+            send_sink,
+            sink_types,
+        })
+    }
+
+    /// 按记录的 `stage` 更新对应指标。
+    pub(crate) fn observe(&self, data: &DataRecord) {
+        let Some(Value::Chars(stage)) = data.get2("stage").map(|x| x.get_value()) else {
+            return;
+        };
+        let extra = self.label_values(data);
+        let with = |base: Vec<&str>| {
+            let mut values: Vec<String> = base.into_iter().map(str::to_string).collect();
+            values.extend(extra.iter().cloned());
+            values
+        };
+        match stage.as_str() {
+            "Pick" => {
+                let (values, total) = source_values(data);
+                let values = with(values.values());
+                self.recv
+                    .with_label_values(&as_strs(&values))
+                    .inc_by(total as u64);
+                let (values, flag) = source_type_values(data);
+                let values = with(values.values());
+                self.source_types
+                    .with_label_values(&as_strs(&values))
+                    .set(flag);
+            }
+            "Parse" => {
+                let (values, success) = parse_success(data);
+                let values = with(values.values());
+                self.parse_success
+                    .with_label_values(&as_strs(&values))
+                    .inc_by(success);
+                let (values, all) = parse_all(data);
+                let values = with(values.values());
+                self.parse_all
+                    .with_label_values(&as_strs(&values))
+                    .inc_by(all);
+            }
+            "Sink" => {
+                let (values, count) = send_sink(data);
+                let values = with(values.values());
+                self.send_sink
+                    .with_label_values(&as_strs(&values))
+                    .inc_by(count);
+                let (values, flag) = sink_type_values(data);
+                let values = with(values.values());
+                self.sink_types
+                    .with_label_values(&as_strs(&values))
+                    .set(flag);
+            }
+            _ => {}
+        }
+    }
+
+    pub(crate) fn gather(&self) -> Vec<MetricFamily> {
+        self.registry.gather()
+    }
+
+    fn label_values(&self, data: &DataRecord) -> Vec<String> {
+        let mut seen = self
+            .seen_values
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        self.labels
+            .iter()
+            .zip(seen.iter_mut())
+            .map(|((field, _), seen)| {
+                let value = data
+                    .get2(field)
+                    .map(|f| f.get_value().to_string())
+                    .unwrap_or_default();
+                if seen.contains(&value) {
+                    value
+                } else if seen.len() < self.value_limit {
+                    seen.insert(value.clone());
+                    value
+                } else {
+                    OTHER_LABEL_VALUE.to_string()
+                }
+            })
+            .collect()
+    }
+}
+
+fn as_strs(values: &[String]) -> Vec<&str> {
+    values.iter().map(String::as_str).collect()
+}
+
+/// 内置指标使用的全部 label 名。
+fn builtin_labels() -> Vec<&'static str> {
+    let mut labels = RecvMetrics::labels();
+    labels.extend(SourceTypeMetrics::labels());
+    labels.extend(ParseMetrics::labels());
+    labels.extend(ParseAllMetrics::labels());
+    labels.extend(SinkMetrics::labels());
+    labels.extend(SinkTypeMetrics::labels());
+    labels
+}
+
+/// 将字段名转换为合法的 Prometheus label 名：`[a-zA-Z_][a-zA-Z0-9_]*`。
+pub(crate) fn label_name(field: &str) -> String {
+    let mut name: String = field
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if !name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+        name.insert(0, '_');
+    }
+    name
+}
+
 macro_rules! generate_metrics {
     ($name:ident; $($field:ident), *) => {
         #[derive(Default, Debug)] pub struct $name { $(pub $field: String,)* }
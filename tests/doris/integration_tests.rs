@@ -6,7 +6,7 @@ use anyhow::Context;
 use serde_json::Value;
 use sqlx::{Row, mysql::MySqlPool, raw_sql};
 use std::collections::BTreeMap;
-use wp_connector_api::{SinkBuildCtx, SinkFactory, SinkSpec};
+use wp_connector_api::{AsyncCtrl, AsyncRecordSink, SinkBuildCtx, SinkFactory, SinkSpec};
 use wp_connectors::doris::{DorisSink, DorisSinkConfig, DorisSinkFactory};
 use wp_model_core::model::{DataField, DataRecord};
 
@@ -137,6 +137,108 @@ async fn doris_sink_new_initializes_via_direct_config() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[ignore = "not ready"]
+#[tokio::test]
+async fn doris_sink_reloads_columns_after_alter_and_retries() -> anyhow::Result<()> {
+    if should_skip_integration() {
+        eprintln!("⚠️  Skipping Doris integration test ({} set)", SKIP_ENV);
+        return Ok(());
+    }
+
+    let table = "doris_schema_reload_test";
+    let mut config = integration_config();
+    config.table = table.into();
+    config.batch_size = 10;
+    let mut sink = DorisSink::new(config).await?;
+    let pool = sink.pool.clone();
+    raw_sql(&format!("TRUNCATE TABLE `{}`", table))
+        .execute(&pool)
+        .await?;
+
+    // 运行期间删除 score 列：缓存的列序已过期，首次写入会报 Unknown column
+    raw_sql(&format!("ALTER TABLE `{}` DROP COLUMN `score`", table))
+        .execute(&pool)
+        .await?;
+
+    let mut carol = DataRecord::default();
+    carol.append(DataField::from_digit("id", 3));
+    carol.append(DataField::from_chars("name", "Carol"));
+    carol.append(DataField::from_chars("score", "76.0"));
+    sink.sink_record(&carol).await?;
+    sink.stop().await?;
+
+    let rows = raw_sql(&format!("SELECT id, name FROM `{}`", table))
+        .fetch_all(&pool)
+        .await?;
+    assert_eq!(rows.len(), 1);
+    let name: String = rows[0].try_get("name")?;
+    assert_eq!(name, "Carol");
+
+    raw_sql(&format!("DROP TABLE `{}`", table))
+        .execute(&pool)
+        .await?;
+    Ok(())
+}
+
+#[ignore = "not ready"]
+#[tokio::test]
+async fn doris_sink_binds_quotes_backslashes_and_numbers() -> anyhow::Result<()> {
+    if should_skip_integration() {
+        eprintln!("⚠️  Skipping Doris integration test ({} set)", SKIP_ENV);
+        return Ok(());
+    }
+
+    let table = "doris_bind_params_test";
+    let mut config = integration_config();
+    config.table = table.into();
+    config.batch_size = 10;
+    let mut sink = DorisSink::new(config).await?;
+    let pool = sink.pool.clone();
+    raw_sql(&format!("TRUNCATE TABLE `{}`", table))
+        .execute(&pool)
+        .await?;
+
+    let names = [
+        "O'Brien",
+        r"C:\temp\'quoted'\",
+        "line1\nline2'); DROP TABLE x; --",
+    ];
+    for (idx, name) in names.iter().enumerate() {
+        let mut rec = DataRecord::default();
+        rec.append(DataField::from_digit("id", idx as i64 + 1));
+        rec.append(DataField::from_chars("name", name));
+        rec.append(DataField::from_chars("score", "12.25"));
+        sink.sink_record(&rec).await?;
+    }
+    // score 缺失：应写入 SQL NULL
+    let mut missing = DataRecord::default();
+    missing.append(DataField::from_digit("id", 4));
+    missing.append(DataField::from_chars("name", "no score"));
+    sink.sink_record(&missing).await?;
+    sink.stop().await?;
+
+    let rows = raw_sql(&format!(
+        "SELECT id, name, score FROM `{}` ORDER BY id",
+        table
+    ))
+    .fetch_all(&pool)
+    .await?;
+    assert_eq!(rows.len(), 4);
+    for (row, expected) in rows.iter().zip(names) {
+        let name: String = row.try_get("name")?;
+        let score: f64 = row.try_get("score")?;
+        assert_eq!(name, expected);
+        assert!((score - 12.25).abs() < 1e-9);
+    }
+    let score: Option<f64> = rows[3].try_get("score")?;
+    assert_eq!(score, None);
+
+    raw_sql(&format!("DROP TABLE `{}`", table))
+        .execute(&pool)
+        .await?;
+    Ok(())
+}
+
 fn should_skip_integration() -> bool {
     std::env::var(SKIP_ENV).is_ok()
 }
@@ -0,0 +1,65 @@
+//! Batched publish: `sink_records` enqueues the whole batch before awaiting delivery;
+//! all 10k records must arrive on the topic.
+
+use rdkafka_wrap::{KWConsumer, KWConsumerConf};
+use std::sync::Arc;
+use tokio::time::timeout;
+use wp_connector_api::{AsyncCtrl, AsyncRecordSink};
+use wp_connectors::kafka::{KafkaSink, KafkaSinkConf};
+use wp_model_core::model::{DataField, DataRecord, fmt_def::TextFmt};
+
+use crate::common;
+
+#[tokio::test]
+async fn kafka_sink_records_publishes_10k_in_one_batch() -> anyhow::Result<()> {
+    if !common::is_kafka_available().await {
+        return Ok(());
+    }
+    let topic = common::generate_test_topic_name("batch_10k");
+    let conf = KafkaSinkConf {
+        brokers: common::TEST_KAFKA_BROKERS.to_string(),
+        topic: topic.clone(),
+        num_partitions: 1,
+        replication: 1,
+        config: Some(vec![
+            "linger.ms=20".to_string(),
+            "batch.size=262144".to_string(),
+        ]),
+        flush_timeout_ms: Some(30_000),
+        ..Default::default()
+    };
+    let mut sink = KafkaSink::from_conf(&conf, TextFmt::Json).await?;
+
+    let produced = 10_000;
+    let records = (0..produced)
+        .map(|i| {
+            let mut rec = DataRecord::default();
+            rec.append(DataField::from_chars("seq", i.to_string().as_str()));
+            Arc::new(rec)
+        })
+        .collect::<Vec<_>>();
+    sink.sink_records(records).await?;
+    sink.stop().await?;
+    assert_eq!(sink.delivery_summary().delivered, produced as u64);
+
+    let group = common::generate_test_group_id("batch_10k");
+    let consumer_conf = KWConsumerConf::new(common::TEST_KAFKA_BROKERS, &group)
+        .set_config(std::collections::HashMap::from([
+            ("enable.partition.eof", "false"),
+            ("auto.offset.reset", "earliest"),
+        ]))
+        .set_topics(vec![topic.as_str()]);
+    let consumer = KWConsumer::new_subscribe(consumer_conf)?;
+    let mut received = 0usize;
+    let outcome = timeout(common::TEST_TIMEOUT * 6, async {
+        while received < produced {
+            if consumer.recv().await.is_ok() {
+                received += 1;
+            }
+        }
+    })
+    .await;
+    assert!(outcome.is_ok(), "consume timeout after {received} records");
+    assert_eq!(received, produced);
+    Ok(())
+}
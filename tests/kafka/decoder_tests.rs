@@ -0,0 +1,95 @@
+//! Pluggable decoders: a decoder registered with `register_decoder` is selected by
+//! `value_format` and translates each payload before it is emitted.
+
+use rdkafka_wrap::{KWProducer, KWProducerConf};
+use wp_connector_api::{DataSource, Tags};
+use wp_connectors::kafka::{KafkaSource, KafkaSourceConf, PayloadDecoder, register_decoder};
+use wp_parse_api::RawData;
+
+use crate::common;
+
+/// `mybinary`：4 字节大端 id + UTF-8 名称，翻译为 JSON 对象。
+struct MyBinary;
+
+impl PayloadDecoder for MyBinary {
+    fn decode(&self, payload: &[u8]) -> Result<Vec<u8>, String> {
+        if payload.len() < 4 {
+            return Err(format!(
+                "mybinary payload too short: {} bytes",
+                payload.len()
+            ));
+        }
+        let (id, name) = payload.split_at(4);
+        let id = u32::from_be_bytes([id[0], id[1], id[2], id[3]]);
+        let name = std::str::from_utf8(name).map_err(|e| e.to_string())?;
+        Ok(serde_json::json!({ "id": id, "name": name })
+            .to_string()
+            .into_bytes())
+    }
+}
+
+fn encode(id: u32, name: &str) -> Vec<u8> {
+    let mut payload = id.to_be_bytes().to_vec();
+    payload.extend_from_slice(name.as_bytes());
+    payload
+}
+
+#[tokio::test]
+async fn kafka_source_uses_registered_decoder() -> anyhow::Result<()> {
+    if !common::is_kafka_available().await {
+        return Ok(());
+    }
+    register_decoder("mybinary", MyBinary);
+    let topic = common::generate_test_topic_name("decoder");
+    let pconf = KWProducerConf::new(common::TEST_KAFKA_BROKERS).set_topic_conf(&topic, 1, 1);
+    let producer = KWProducer::new(pconf)?;
+    producer.create_topic().await?;
+    for (id, name) in [(7, "alice"), (42, "bob")] {
+        producer
+            .publish(&encode(id, name), Default::default())
+            .await?;
+    }
+
+    let group_id = common::generate_test_group_id("decoder");
+    let conf = KafkaSourceConf {
+        key: "decoder".to_string(),
+        brokers: common::TEST_KAFKA_BROKERS.to_string(),
+        topic: vec![topic.clone()],
+        config: Some(vec!["auto.offset.reset=earliest".to_string()]),
+        enable: true,
+        group_id: Some(group_id.clone()),
+        value_format: Some("mybinary".to_string()),
+        ..Default::default()
+    };
+    let mut source = KafkaSource::new(
+        conf.key.clone(),
+        Tags::from_parse(&Vec::new()),
+        &group_id,
+        &conf,
+    )
+    .await?;
+
+    let payloads = tokio::time::timeout(common::TEST_TIMEOUT, async {
+        let mut payloads = Vec::new();
+        while payloads.len() < 2 {
+            let Ok(batch) = source.receive().await else {
+                continue;
+            };
+            for event in batch {
+                payloads.push(match event.payload {
+                    RawData::String(s) => s,
+                    RawData::Bytes(b) => String::from_utf8_lossy(&b).to_string(),
+                });
+            }
+        }
+        payloads
+    })
+    .await
+    .map_err(|_| anyhow::anyhow!("recv timeout"))?;
+
+    assert_eq!(
+        payloads,
+        vec![r#"{"id":7,"name":"alice"}"#, r#"{"id":42,"name":"bob"}"#]
+    );
+    Ok(())
+}
@@ -0,0 +1,92 @@
+//! Key dedup: with `dedup_window_ms`, a message key delivered twice within the window is
+//! emitted once; other keys and keyless messages pass through.
+
+use wp_connector_api::{AsyncCtrl, AsyncRecordSink, DataSource, Tags};
+use wp_connectors::kafka::{KafkaSink, KafkaSinkConf, KafkaSource, KafkaSourceConf};
+use wp_model_core::model::{DataField, DataRecord, fmt_def::TextFmt};
+use wp_parse_api::RawData;
+
+use crate::common;
+
+fn record(user_id: Option<&str>, msg: &str) -> DataRecord {
+    let mut rec = DataRecord::default();
+    if let Some(user_id) = user_id {
+        rec.append(DataField::from_chars("user_id", user_id));
+    }
+    rec.append(DataField::from_chars("msg", msg));
+    rec
+}
+
+#[tokio::test]
+async fn kafka_source_collapses_duplicate_keys_within_window() -> anyhow::Result<()> {
+    if !common::is_kafka_available().await {
+        return Ok(());
+    }
+    let topic = common::generate_test_topic_name("dedup");
+    let sink_conf = KafkaSinkConf {
+        brokers: common::TEST_KAFKA_BROKERS.to_string(),
+        topic: topic.clone(),
+        num_partitions: 1,
+        replication: 1,
+        key_field: Some("user_id".to_string()),
+        ..Default::default()
+    };
+    let mut sink = KafkaSink::from_conf(&sink_conf, TextFmt::Json).await?;
+    // 同一 key 连续投递两次，模拟 at-least-once 重投
+    for (key, msg) in [
+        (Some("u-1"), "first"),
+        (Some("u-1"), "redelivered"),
+        (Some("u-2"), "other-key"),
+        (None, "keyless"),
+        (None, "keyless"),
+    ] {
+        sink.sink_record(&record(key, msg)).await?;
+    }
+    sink.stop().await?;
+
+    let group_id = common::generate_test_group_id("dedup");
+    let conf = KafkaSourceConf {
+        key: "dedup".to_string(),
+        brokers: common::TEST_KAFKA_BROKERS.to_string(),
+        topic: vec![topic.clone()],
+        config: Some(vec!["auto.offset.reset=earliest".to_string()]),
+        enable: true,
+        group_id: Some(group_id.clone()),
+        dedup_window_ms: Some(60_000),
+        ..Default::default()
+    };
+    let mut source = KafkaSource::new(
+        conf.key.clone(),
+        Tags::from_parse(&Vec::new()),
+        &group_id,
+        &conf,
+    )
+    .await?;
+
+    let payloads = tokio::time::timeout(common::TEST_TIMEOUT, async {
+        let mut payloads = Vec::new();
+        while payloads.len() < 4 {
+            // 重复消息以 NotData 返回
+            let Ok(batch) = source.receive().await else {
+                continue;
+            };
+            for event in batch {
+                payloads.push(match event.payload {
+                    RawData::String(s) => s,
+                    RawData::Bytes(b) => String::from_utf8_lossy(&b).to_string(),
+                });
+            }
+        }
+        payloads
+    })
+    .await
+    .map_err(|_| anyhow::anyhow!("recv timeout"))?;
+
+    let msgs = ["first", "other-key", "keyless", "keyless"];
+    assert_eq!(payloads.len(), msgs.len());
+    for (payload, msg) in payloads.iter().zip(msgs) {
+        assert!(payload.contains(msg), "expected {msg} in {payload}");
+    }
+    assert!(!payloads.iter().any(|p| p.contains("redelivered")));
+    Ok(())
+}
@@ -0,0 +1,44 @@
+//! Delivery report: after producing a batch and stopping the sink, the delivery
+//! summary must account for every produced message.
+
+use std::sync::Arc;
+use wp_connector_api::{AsyncCtrl, AsyncRecordSink};
+use wp_connectors::kafka::{KafkaSink, KafkaSinkConf};
+use wp_model_core::model::{DataField, DataRecord, fmt_def::TextFmt};
+
+use crate::common;
+
+#[tokio::test]
+async fn kafka_sink_delivery_report_matches_produced_count() -> anyhow::Result<()> {
+    if !common::is_kafka_available().await {
+        return Ok(());
+    }
+    let topic = common::generate_test_topic_name("delivery");
+    let conf = KafkaSinkConf {
+        brokers: common::TEST_KAFKA_BROKERS.to_string(),
+        topic: topic.clone(),
+        num_partitions: 1,
+        replication: 1,
+        config: Some(vec!["acks=all".to_string()]),
+        flush_timeout_ms: Some(10_000),
+        key_field: None,
+        ..Default::default()
+    };
+    let mut sink = KafkaSink::from_conf(&conf, TextFmt::Json).await?;
+
+    let produced = 50;
+    let records = (0..produced)
+        .map(|i| {
+            let mut rec = DataRecord::default();
+            rec.append(DataField::from_chars("seq", i.to_string().as_str()));
+            Arc::new(rec)
+        })
+        .collect::<Vec<_>>();
+    sink.sink_records(records).await?;
+    sink.stop().await?;
+
+    let summary = sink.delivery_summary();
+    assert_eq!(summary.delivered, produced as u64);
+    assert_eq!(summary.failed, 0);
+    Ok(())
+}
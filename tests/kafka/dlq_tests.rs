@@ -0,0 +1,114 @@
+//! Dead-letter routing: a record the broker refuses (the topic's `max.message.bytes`
+//! is lowered below the record size) goes to `dlq_topic` with an error header; without
+//! a DLQ the failure still surfaces from `sink_record`.
+
+use rdkafka_wrap::admin::{AdminClient, AdminOptions, AlterConfig, ResourceSpecifier};
+use rdkafka_wrap::client::DefaultClientContext;
+use rdkafka_wrap::message::Headers;
+use rdkafka_wrap::{ClientConfig, KWConsumer, KWConsumerConf, Message};
+use std::time::Duration;
+use tokio::time::timeout;
+use wp_connector_api::{AsyncCtrl, AsyncRecordSink};
+use wp_connectors::kafka::{KafkaSink, KafkaSinkConf};
+use wp_model_core::model::{DataField, DataRecord, fmt_def::TextFmt};
+
+use crate::common;
+
+async fn sink_with_tiny_topic(topic: &str, dlq: Option<&str>) -> anyhow::Result<KafkaSink> {
+    let conf = KafkaSinkConf {
+        brokers: common::TEST_KAFKA_BROKERS.to_string(),
+        topic: topic.to_string(),
+        num_partitions: 1,
+        replication: 1,
+        config: None,
+        flush_timeout_ms: Some(10_000),
+        dlq_topic: dlq.map(str::to_string),
+        ..Default::default()
+    };
+    let sink = KafkaSink::from_conf(&conf, TextFmt::Json).await?;
+
+    let admin: AdminClient<DefaultClientContext> = ClientConfig::new()
+        .set("bootstrap.servers", common::TEST_KAFKA_BROKERS)
+        .create()?;
+    let alter = AlterConfig::new(ResourceSpecifier::Topic(topic)).set("max.message.bytes", "512");
+    for result in admin
+        .alter_configs(vec![&alter], &AdminOptions::new())
+        .await?
+    {
+        result.map_err(|(_, code)| anyhow::anyhow!("alter topic config failed: {code}"))?;
+    }
+    // 等待 broker 应用新的 topic 配置
+    tokio::time::sleep(Duration::from_secs(1)).await;
+    Ok(sink)
+}
+
+fn oversized_record() -> DataRecord {
+    let mut rec = DataRecord::default();
+    rec.append(DataField::from_chars("id", "big-1"));
+    rec.append(DataField::from_chars("body", "x".repeat(4096).as_str()));
+    rec
+}
+
+#[tokio::test]
+async fn kafka_sink_routes_rejected_record_to_dlq() -> anyhow::Result<()> {
+    if !common::is_kafka_available().await {
+        return Ok(());
+    }
+    let topic = common::generate_test_topic_name("dlq_main");
+    let dlq = format!("{topic}_dlq");
+    let mut sink = sink_with_tiny_topic(&topic, Some(&dlq)).await?;
+
+    sink.sink_record(&oversized_record()).await?;
+    sink.stop().await?;
+
+    let group = common::generate_test_group_id("dlq");
+    let consumer_conf = KWConsumerConf::new(common::TEST_KAFKA_BROKERS, &group)
+        .set_config(std::collections::HashMap::from([
+            ("enable.partition.eof", "false"),
+            ("auto.offset.reset", "earliest"),
+        ]))
+        .set_topics(vec![dlq.as_str()]);
+    let consumer = KWConsumer::new_subscribe(consumer_conf)?;
+    let (payload, error, origin) = timeout(common::TEST_TIMEOUT, async {
+        loop {
+            let Ok(msg) = consumer.recv().await else {
+                continue;
+            };
+            let mut error = None;
+            let mut origin = None;
+            for header in msg.headers().iter().flat_map(|h| h.iter()) {
+                let value = header.value.map(|v| String::from_utf8_lossy(v).to_string());
+                match header.key {
+                    "wp_error" => error = value,
+                    "wp_origin_topic" => origin = value,
+                    _ => {}
+                }
+            }
+            let payload = String::from_utf8_lossy(msg.payload().unwrap_or(&[])).to_string();
+            break (payload, error, origin);
+        }
+    })
+    .await
+    .map_err(|_| anyhow::anyhow!("dlq consume timeout"))?;
+
+    assert!(payload.contains("big-1"));
+    assert!(error.is_some_and(|e| !e.is_empty()));
+    assert_eq!(origin.as_deref(), Some(topic.as_str()));
+    Ok(())
+}
+
+#[tokio::test]
+async fn kafka_sink_without_dlq_propagates_send_error() -> anyhow::Result<()> {
+    if !common::is_kafka_available().await {
+        return Ok(());
+    }
+    let topic = common::generate_test_topic_name("dlq_none");
+    let mut sink = sink_with_tiny_topic(&topic, None).await?;
+
+    let err = sink
+        .sink_record(&oversized_record())
+        .await
+        .expect_err("oversized record must fail without dlq");
+    assert!(format!("{err}").contains("kafka send fail"));
+    Ok(())
+}
@@ -0,0 +1,52 @@
+//! Flush on stop: a message written right before `stop` must already be acknowledged
+//! by the broker when `stop` returns, so it is consumable afterwards.
+
+use rdkafka_wrap::{KWConsumer, KWConsumerConf, Message};
+use tokio::time::timeout;
+use wp_connector_api::{AsyncCtrl, AsyncRawDataSink};
+use wp_connectors::kafka::{KafkaSink, KafkaSinkConf};
+use wp_model_core::model::fmt_def::TextFmt;
+
+use crate::common;
+
+#[tokio::test]
+async fn kafka_sink_stop_waits_for_acknowledgement() -> anyhow::Result<()> {
+    if !common::is_kafka_available().await {
+        return Ok(());
+    }
+    let topic = common::generate_test_topic_name("flush_stop");
+    let conf = KafkaSinkConf {
+        brokers: common::TEST_KAFKA_BROKERS.to_string(),
+        topic: topic.clone(),
+        num_partitions: 1,
+        replication: 1,
+        config: Some(vec!["acks=all".to_string(), "linger.ms=500".to_string()]),
+        flush_timeout_ms: Some(10_000),
+        ..Default::default()
+    };
+    let mut sink = KafkaSink::from_conf(&conf, TextFmt::Json).await?;
+    sink.sink_str("last words before shutdown").await?;
+    sink.stop().await?;
+    drop(sink);
+
+    let group = common::generate_test_group_id("flush_stop");
+    let consumer_conf = KWConsumerConf::new(common::TEST_KAFKA_BROKERS, &group)
+        .set_config(std::collections::HashMap::from([
+            ("enable.partition.eof", "false"),
+            ("auto.offset.reset", "earliest"),
+        ]))
+        .set_topics(vec![topic.as_str()]);
+    let consumer = KWConsumer::new_subscribe(consumer_conf)?;
+    let payload = timeout(common::TEST_TIMEOUT, async {
+        loop {
+            if let Ok(msg) = consumer.recv().await {
+                break msg.payload().map(|p| p.to_vec()).unwrap_or_default();
+            }
+        }
+    })
+    .await
+    .map_err(|_| anyhow::anyhow!("message lost on stop"))?;
+
+    assert_eq!(payload, b"last words before shutdown");
+    Ok(())
+}
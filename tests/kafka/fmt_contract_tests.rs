@@ -0,0 +1,62 @@
+//! `fmt` contract: raw-data methods publish bytes verbatim, record methods render the
+//! record with the configured `fmt` as one newline-terminated line.
+
+use rdkafka_wrap::{KWConsumer, KWConsumerConf, Message};
+use tokio::time::timeout;
+use wp_connector_api::{AsyncCtrl, AsyncRawDataSink, AsyncRecordSink};
+use wp_connectors::kafka::{KafkaSink, KafkaSinkConf};
+use wp_model_core::model::{DataField, DataRecord, fmt_def::TextFmt};
+
+use crate::common;
+
+async fn consume_payloads(topic: &str, count: usize) -> anyhow::Result<Vec<Vec<u8>>> {
+    let group = common::generate_test_group_id("fmt_contract");
+    let conf = KWConsumerConf::new(common::TEST_KAFKA_BROKERS, &group)
+        .set_config(std::collections::HashMap::from([
+            ("enable.partition.eof", "false"),
+            ("auto.offset.reset", "earliest"),
+        ]))
+        .set_topics(vec![topic]);
+    let consumer = KWConsumer::new_subscribe(conf)?;
+    let mut payloads = Vec::new();
+    timeout(common::TEST_TIMEOUT, async {
+        while payloads.len() < count {
+            if let Ok(msg) = consumer.recv().await {
+                payloads.push(msg.payload().unwrap_or_default().to_vec());
+            }
+        }
+    })
+    .await
+    .map_err(|_| anyhow::anyhow!("consume timeout"))?;
+    Ok(payloads)
+}
+
+#[tokio::test]
+async fn kafka_sink_raw_bytes_bypass_fmt() -> anyhow::Result<()> {
+    if !common::is_kafka_available().await {
+        return Ok(());
+    }
+    let topic = common::generate_test_topic_name("fmt_contract");
+    let conf = KafkaSinkConf {
+        brokers: common::TEST_KAFKA_BROKERS.to_string(),
+        topic: topic.clone(),
+        num_partitions: 1,
+        replication: 1,
+        ..Default::default()
+    };
+    let mut sink = KafkaSink::from_conf(&conf, TextFmt::Csv).await?;
+    sink.sink_str("already,encoded").await?;
+    sink.sink_bytes(b"\x00\x01binary").await?;
+    let mut rec = DataRecord::default();
+    rec.append(DataField::from_chars("user", "alice"));
+    sink.sink_record(&rec).await?;
+    sink.stop().await?;
+
+    let payloads = consume_payloads(&topic, 3).await?;
+    assert_eq!(payloads[0], b"already,encoded");
+    assert_eq!(payloads[1], b"\x00\x01binary");
+    let line = String::from_utf8_lossy(&payloads[2]);
+    assert!(line.ends_with('\n') && line.contains("alice"), "{line:?}");
+    assert!(!line.contains('{'), "record must follow fmt=csv: {line:?}");
+    Ok(())
+}
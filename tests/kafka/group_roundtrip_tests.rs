@@ -0,0 +1,81 @@
+//! Consumer-group roundtrip: two sources subscribed to the same topic with distinct
+//! `group_id`s must each receive every message.
+
+use rdkafka_wrap::{KWProducer, KWProducerConf};
+use wp_connector_api::{DataSource, Tags};
+use wp_connectors::kafka::{KafkaSource, KafkaSourceConf};
+use wp_parse_api::RawData;
+
+use crate::common;
+
+fn source_conf(key: &str, topic: &str, group_id: &str) -> KafkaSourceConf {
+    KafkaSourceConf {
+        key: key.to_string(),
+        brokers: common::TEST_KAFKA_BROKERS.to_string(),
+        topic: vec![topic.to_string()],
+        config: Some(vec!["auto.offset.reset=earliest".to_string()]),
+        enable: true,
+        group_id: Some(group_id.to_string()),
+        fields: None,
+        commit_interval_ms: None,
+        ack_channel: None,
+        start_offset: None,
+        header_filter: Default::default(),
+        respect_sampling_field: None,
+        dedup_window_ms: None,
+        dedup_max_keys: None,
+        value_format: None,
+        schema_registry_url: None,
+        metadata_tags: None,
+    }
+}
+
+async fn recv_payload(source: &mut KafkaSource) -> anyhow::Result<String> {
+    let batch = tokio::time::timeout(common::TEST_TIMEOUT, source.receive())
+        .await
+        .map_err(|_| anyhow::anyhow!("recv timeout"))??;
+    let event = batch
+        .into_iter()
+        .next()
+        .ok_or_else(|| anyhow::anyhow!("empty batch"))?;
+    Ok(match event.payload {
+        RawData::String(s) => s,
+        RawData::Bytes(b) => String::from_utf8_lossy(&b).to_string(),
+    })
+}
+
+#[tokio::test]
+async fn kafka_sources_with_distinct_groups_both_receive() -> anyhow::Result<()> {
+    if !common::is_kafka_available().await {
+        return Ok(());
+    }
+    let topic = common::generate_test_topic_name("group_rt");
+    let expected = "hello-two-groups";
+
+    let pconf = KWProducerConf::new(common::TEST_KAFKA_BROKERS).set_topic_conf(&topic, 1, 1);
+    let producer = KWProducer::new(pconf)?;
+    producer.create_topic().await?;
+    producer
+        .publish(expected.as_bytes(), Default::default())
+        .await?;
+
+    // 同名 source，不同消费组：各自独立消费
+    let mut sources = Vec::new();
+    for group in ["a", "b"] {
+        let group_id = common::generate_test_group_id(&format!("group_rt_{group}"));
+        let conf = source_conf("group_rt", &topic, &group_id);
+        let source = KafkaSource::new(
+            "group_rt".to_string(),
+            Tags::from_parse(&Vec::new()),
+            &conf.effective_group_id(),
+            &conf,
+        )
+        .await?;
+        assert_eq!(source.group_id(), group_id);
+        sources.push(source);
+    }
+    for source in sources.iter_mut() {
+        assert_eq!(recv_payload(source).await?, expected);
+    }
+    Ok(())
+}
@@ -0,0 +1,88 @@
+//! Header filter roundtrip: the source only emits messages whose headers satisfy
+//! `header_filter`; other messages are consumed and skipped.
+
+use std::collections::BTreeMap;
+use wp_connector_api::{AsyncCtrl, AsyncRecordSink, DataSource, Tags};
+use wp_connectors::kafka::{HeaderMatch, KafkaSink, KafkaSinkConf, KafkaSource, KafkaSourceConf};
+use wp_model_core::model::{DataField, DataRecord, fmt_def::TextFmt};
+use wp_parse_api::RawData;
+
+use crate::common;
+
+#[tokio::test]
+async fn kafka_source_emits_only_messages_matching_header_filter() -> anyhow::Result<()> {
+    if !common::is_kafka_available().await {
+        return Ok(());
+    }
+    let topic = common::generate_test_topic_name("header_filter");
+    let sink_conf = KafkaSinkConf {
+        brokers: common::TEST_KAFKA_BROKERS.to_string(),
+        topic: topic.clone(),
+        num_partitions: 1,
+        replication: 1,
+        tag_headers: vec!["event_type".to_string()],
+        ..Default::default()
+    };
+    let mut sink = KafkaSink::from_conf(&sink_conf, TextFmt::Json).await?;
+    let messages = [
+        (Some("login"), "first-login"),
+        (None, "no-header"),
+        (Some("logout"), "logout"),
+        (Some("login"), "second-login"),
+    ];
+    for (event_type, msg) in messages {
+        let mut rec = DataRecord::default();
+        if let Some(event_type) = event_type {
+            rec.append(DataField::from_chars("event_type", event_type));
+        }
+        rec.append(DataField::from_chars("msg", msg));
+        sink.sink_record(&rec).await?;
+    }
+    sink.stop().await?;
+
+    let group_id = common::generate_test_group_id("header_filter");
+    let conf = KafkaSourceConf {
+        key: "header_filter".to_string(),
+        brokers: common::TEST_KAFKA_BROKERS.to_string(),
+        topic: vec![topic.clone()],
+        config: Some(vec!["auto.offset.reset=earliest".to_string()]),
+        enable: true,
+        group_id: Some(group_id.clone()),
+        header_filter: BTreeMap::from([(
+            "event_type".to_string(),
+            HeaderMatch::Equals("login".to_string()),
+        )]),
+        ..Default::default()
+    };
+    let mut source = KafkaSource::new(
+        conf.key.clone(),
+        Tags::from_parse(&Vec::new()),
+        &group_id,
+        &conf,
+    )
+    .await?;
+
+    // 被过滤的消息以 NotData 返回，跳过即可
+    let payloads = tokio::time::timeout(common::TEST_TIMEOUT, async {
+        let mut payloads = Vec::new();
+        while payloads.len() < 2 {
+            let Ok(batch) = source.receive().await else {
+                continue;
+            };
+            for event in batch {
+                payloads.push(match event.payload {
+                    RawData::String(s) => s,
+                    RawData::Bytes(b) => String::from_utf8_lossy(&b).to_string(),
+                });
+            }
+        }
+        payloads
+    })
+    .await
+    .map_err(|_| anyhow::anyhow!("recv timeout"))?;
+
+    assert_eq!(payloads.len(), 2, "{payloads:?}");
+    assert!(payloads[0].contains("first-login"), "{payloads:?}");
+    assert!(payloads[1].contains("second-login"), "{payloads:?}");
+    Ok(())
+}
@@ -0,0 +1,70 @@
+//! Message header roundtrip: tags listed in `tag_headers` and static `headers` are
+//! attached to each produced message as UTF-8 key/value headers.
+
+use rdkafka_wrap::message::Headers;
+use rdkafka_wrap::{KWConsumer, KWConsumerConf, Message};
+use std::collections::BTreeMap;
+use tokio::time::timeout;
+use wp_connector_api::{AsyncCtrl, AsyncRecordSink};
+use wp_connectors::kafka::{KafkaSink, KafkaSinkConf};
+use wp_model_core::model::{DataField, DataRecord, fmt_def::TextFmt};
+
+use crate::common;
+
+#[tokio::test]
+async fn kafka_sink_propagates_tags_as_message_headers() -> anyhow::Result<()> {
+    if !common::is_kafka_available().await {
+        return Ok(());
+    }
+    let topic = common::generate_test_topic_name("header_rt");
+    let conf = KafkaSinkConf {
+        brokers: common::TEST_KAFKA_BROKERS.to_string(),
+        topic: topic.clone(),
+        num_partitions: 1,
+        replication: 1,
+        headers: BTreeMap::from([("origin".to_string(), String::new())]),
+        tag_headers: vec!["tenant".to_string(), "region".to_string()],
+        ..Default::default()
+    };
+    let mut sink = KafkaSink::from_conf(&conf, TextFmt::Json).await?;
+
+    let mut rec = DataRecord::default();
+    rec.append(DataField::from_chars("tenant", "acme"));
+    rec.append(DataField::from_chars("region", "eu-west"));
+    rec.append(DataField::from_chars("msg", "hello"));
+    sink.sink_record(&rec).await?;
+    sink.stop().await?;
+
+    let group = common::generate_test_group_id("header_rt");
+    let consumer_conf = KWConsumerConf::new(common::TEST_KAFKA_BROKERS, &group)
+        .set_config(std::collections::HashMap::from([
+            ("enable.partition.eof", "false"),
+            ("auto.offset.reset", "earliest"),
+        ]))
+        .set_topics(vec![topic.as_str()]);
+    let consumer = KWConsumer::new_subscribe(consumer_conf)?;
+    let headers = timeout(common::TEST_TIMEOUT, async {
+        loop {
+            if let Ok(msg) = consumer.recv().await {
+                let mut found = BTreeMap::new();
+                if let Some(headers) = msg.headers() {
+                    for header in headers.iter() {
+                        let value = header
+                            .value
+                            .map(|v| String::from_utf8_lossy(v).to_string())
+                            .unwrap_or_default();
+                        found.insert(header.key.to_string(), value);
+                    }
+                }
+                break found;
+            }
+        }
+    })
+    .await
+    .map_err(|_| anyhow::anyhow!("consume timeout"))?;
+
+    assert_eq!(headers.get("tenant").map(String::as_str), Some("acme"));
+    assert_eq!(headers.get("region").map(String::as_str), Some("eu-west"));
+    assert_eq!(headers.get("origin").map(String::as_str), Some(""));
+    Ok(())
+}
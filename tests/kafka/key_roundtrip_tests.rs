@@ -0,0 +1,85 @@
+//! Message key roundtrip: with `key_field` set, the sink uses the record field as the
+//! Kafka message key and falls back to a null key when the field is missing.
+
+use rdkafka_wrap::{KWConsumer, KWConsumerConf, Message};
+use tokio::time::timeout;
+use wp_connector_api::{AsyncCtrl, AsyncRecordSink};
+use wp_connectors::kafka::{KafkaSink, KafkaSinkConf};
+use wp_model_core::model::{DataField, DataRecord, fmt_def::TextFmt};
+
+use crate::common;
+
+async fn keyed_sink(topic: &str) -> anyhow::Result<KafkaSink> {
+    let conf = KafkaSinkConf {
+        brokers: common::TEST_KAFKA_BROKERS.to_string(),
+        topic: topic.to_string(),
+        num_partitions: 1,
+        replication: 1,
+        config: None,
+        flush_timeout_ms: None,
+        key_field: Some("user_id".to_string()),
+        ..Default::default()
+    };
+    KafkaSink::from_conf(&conf, TextFmt::Json).await
+}
+
+/// 依次消费 `count` 条消息，返回各自的 key（UTF-8）。
+async fn consume_keys(topic: &str, count: usize) -> anyhow::Result<Vec<Option<String>>> {
+    let group = common::generate_test_group_id("key_rt");
+    let conf = KWConsumerConf::new(common::TEST_KAFKA_BROKERS, &group)
+        .set_config(std::collections::HashMap::from([
+            ("enable.partition.eof", "false"),
+            ("auto.offset.reset", "earliest"),
+        ]))
+        .set_topics(vec![topic]);
+    let consumer = KWConsumer::new_subscribe(conf)?;
+    let mut keys = Vec::new();
+    timeout(common::TEST_TIMEOUT, async {
+        while keys.len() < count {
+            if let Ok(msg) = consumer.recv().await {
+                keys.push(msg.key().map(|k| String::from_utf8_lossy(k).to_string()));
+            }
+        }
+    })
+    .await
+    .map_err(|_| anyhow::anyhow!("consume timeout"))?;
+    Ok(keys)
+}
+
+#[tokio::test]
+async fn kafka_sink_uses_record_field_as_message_key() -> anyhow::Result<()> {
+    if !common::is_kafka_available().await {
+        return Ok(());
+    }
+    let topic = common::generate_test_topic_name("key_rt");
+    let mut sink = keyed_sink(&topic).await?;
+
+    let mut rec = DataRecord::default();
+    rec.append(DataField::from_chars("user_id", "u-42"));
+    rec.append(DataField::from_chars("msg", "hello"));
+    sink.sink_record(&rec).await?;
+    sink.stop().await?;
+
+    assert_eq!(
+        consume_keys(&topic, 1).await?,
+        vec![Some("u-42".to_string())]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn kafka_sink_missing_key_field_sends_null_key() -> anyhow::Result<()> {
+    if !common::is_kafka_available().await {
+        return Ok(());
+    }
+    let topic = common::generate_test_topic_name("key_null");
+    let mut sink = keyed_sink(&topic).await?;
+
+    let mut rec = DataRecord::default();
+    rec.append(DataField::from_chars("msg", "no key here"));
+    sink.sink_record(&rec).await?;
+    sink.stop().await?;
+
+    assert_eq!(consume_keys(&topic, 1).await?, vec![None]);
+    Ok(())
+}
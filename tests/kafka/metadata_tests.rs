@@ -0,0 +1,76 @@
+//! Message coordinates: with `metadata_tags`, each emitted event carries the topic,
+//! partition, offset and timestamp of its Kafka message as tags under the configured names.
+
+use wp_connector_api::{AsyncCtrl, AsyncRecordSink, DataSource, Tags};
+use wp_connectors::kafka::{KafkaSink, KafkaSinkConf, KafkaSource, KafkaSourceConf, MetadataTags};
+use wp_model_core::model::{DataField, DataRecord, fmt_def::TextFmt};
+
+use crate::common;
+
+#[tokio::test]
+async fn kafka_source_tags_events_with_message_coordinates() -> anyhow::Result<()> {
+    if !common::is_kafka_available().await {
+        return Ok(());
+    }
+    let topic = common::generate_test_topic_name("metadata");
+    let sink_conf = KafkaSinkConf {
+        brokers: common::TEST_KAFKA_BROKERS.to_string(),
+        topic: topic.clone(),
+        num_partitions: 1,
+        replication: 1,
+        ..Default::default()
+    };
+    let mut sink = KafkaSink::from_conf(&sink_conf, TextFmt::Json).await?;
+    for msg in ["first", "second"] {
+        let mut rec = DataRecord::default();
+        rec.append(DataField::from_chars("msg", msg));
+        sink.sink_record(&rec).await?;
+    }
+    sink.stop().await?;
+
+    let group_id = common::generate_test_group_id("metadata");
+    let conf = KafkaSourceConf {
+        key: "metadata".to_string(),
+        brokers: common::TEST_KAFKA_BROKERS.to_string(),
+        topic: vec![topic.clone()],
+        config: Some(vec!["auto.offset.reset=earliest".to_string()]),
+        enable: true,
+        group_id: Some(group_id.clone()),
+        // 改名避免与业务字段冲突
+        metadata_tags: Some(MetadataTags {
+            offset: "src_offset".to_string(),
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    let mut source = KafkaSource::new(
+        conf.key.clone(),
+        Tags::from_parse(&Vec::new()),
+        &group_id,
+        &conf,
+    )
+    .await?;
+
+    let events = tokio::time::timeout(common::TEST_TIMEOUT, async {
+        let mut events = Vec::new();
+        while events.len() < 2 {
+            let Ok(batch) = source.receive().await else {
+                continue;
+            };
+            events.extend(batch);
+        }
+        events
+    })
+    .await
+    .map_err(|_| anyhow::anyhow!("recv timeout"))?;
+
+    for (expected_offset, event) in ["0", "1"].into_iter().zip(&events) {
+        assert_eq!(event.tags.get("kafka_topic"), Some(topic.as_str()));
+        assert_eq!(event.tags.get("kafka_partition"), Some("0"));
+        assert_eq!(event.tags.get("src_offset"), Some(expected_offset));
+        assert_eq!(event.tags.get("kafka_offset"), None);
+        let ts = event.tags.get("kafka_timestamp").expect("timestamp tag");
+        assert!(ts.parse::<i64>().is_ok_and(|ms| ms > 0), "{ts}");
+    }
+    Ok(())
+}
@@ -0,0 +1,137 @@
+//! Explicit partitioning: `partition_field` pins each record to the partition named by
+//! the field, and `partition_by_key_hash` keeps every record of a key on one partition.
+
+use rdkafka_wrap::{KWConsumer, KWConsumerConf, Message};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::time::timeout;
+use wp_connector_api::{AsyncCtrl, AsyncRecordSink};
+use wp_connectors::kafka::{KafkaSink, KafkaSinkConf};
+use wp_model_core::model::{DataField, DataRecord, fmt_def::TextFmt};
+
+use crate::common;
+
+const PARTITIONS: i32 = 4;
+
+fn partitioned_conf(topic: &str) -> KafkaSinkConf {
+    KafkaSinkConf {
+        brokers: common::TEST_KAFKA_BROKERS.to_string(),
+        topic: topic.to_string(),
+        num_partitions: PARTITIONS,
+        replication: 1,
+        key_field: Some("user_id".to_string()),
+        ..Default::default()
+    }
+}
+
+fn record(user_id: &str, shard: Option<&str>) -> Arc<DataRecord> {
+    let mut rec = DataRecord::default();
+    rec.append(DataField::from_chars("user_id", user_id));
+    if let Some(shard) = shard {
+        rec.append(DataField::from_chars("shard", shard));
+    }
+    Arc::new(rec)
+}
+
+/// 消费 `count` 条消息，返回 (key, 分区)。
+async fn consume_partitions(topic: &str, count: usize) -> anyhow::Result<Vec<(String, i32)>> {
+    let group = common::generate_test_group_id("partition");
+    let conf = KWConsumerConf::new(common::TEST_KAFKA_BROKERS, &group)
+        .set_config(HashMap::from([
+            ("enable.partition.eof", "false"),
+            ("auto.offset.reset", "earliest"),
+        ]))
+        .set_topics(vec![topic]);
+    let consumer = KWConsumer::new_subscribe(conf)?;
+    let mut seen = Vec::new();
+    timeout(common::TEST_TIMEOUT, async {
+        while seen.len() < count {
+            if let Ok(msg) = consumer.recv().await {
+                let key = String::from_utf8_lossy(msg.key().unwrap_or(&[])).to_string();
+                seen.push((key, msg.partition()));
+            }
+        }
+    })
+    .await
+    .map_err(|_| anyhow::anyhow!("consume timeout on {topic}"))?;
+    Ok(seen)
+}
+
+#[tokio::test]
+async fn kafka_sink_sends_to_partition_from_record_field() -> anyhow::Result<()> {
+    if !common::is_kafka_available().await {
+        return Ok(());
+    }
+    let topic = common::generate_test_topic_name("partition_field");
+    let conf = KafkaSinkConf {
+        partition_field: Some("shard".to_string()),
+        ..partitioned_conf(&topic)
+    };
+    let mut sink = KafkaSink::from_conf(&conf, TextFmt::Json).await?;
+
+    sink.sink_records(vec![
+        record("u-1", Some("2")),
+        record("u-2", Some("0")),
+        record("u-1", Some("2")),
+        record("u-3", Some("3")),
+    ])
+    .await?;
+    let err = sink
+        .sink_record(&record("u-4", Some("9")))
+        .await
+        .expect_err("out of range");
+    assert!(err.to_string().contains("out of range"), "{err}");
+    sink.stop().await?;
+
+    let mut seen = consume_partitions(&topic, 4).await?;
+    seen.sort();
+    assert_eq!(
+        seen,
+        vec![
+            ("u-1".to_string(), 2),
+            ("u-1".to_string(), 2),
+            ("u-2".to_string(), 0),
+            ("u-3".to_string(), 3),
+        ]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn kafka_sink_key_hash_keeps_each_key_on_one_partition() -> anyhow::Result<()> {
+    if !common::is_kafka_available().await {
+        return Ok(());
+    }
+    let topic = common::generate_test_topic_name("partition_hash");
+    let conf = KafkaSinkConf {
+        partition_by_key_hash: true,
+        ..partitioned_conf(&topic)
+    };
+    let mut sink = KafkaSink::from_conf(&conf, TextFmt::Json).await?;
+
+    // Java 客户端 `toPositive(murmur2(key)) % 4` 的结果
+    let expected = HashMap::from([
+        ("alpha", 0),
+        ("bravo", 1),
+        ("charlie", 0),
+        ("delta", 2),
+        ("echo", 3),
+    ]);
+    let records = (0..4)
+        .flat_map(|_| expected.keys().map(|key| record(key, None)))
+        .collect::<Vec<_>>();
+    sink.sink_records(records).await?;
+    sink.stop().await?;
+
+    let mut placement: HashMap<String, i32> = HashMap::new();
+    for (key, partition) in consume_partitions(&topic, expected.len() * 4).await? {
+        assert_eq!(
+            expected.get(key.as_str()),
+            Some(&partition),
+            "key {key} landed on partition {partition}"
+        );
+        placement.insert(key, partition);
+    }
+    assert_eq!(placement.len(), expected.len());
+    Ok(())
+}
@@ -0,0 +1,110 @@
+//! Quarantine roundtrip: with `fields` set, a payload that is not a JSON object is
+//! written to the quarantine sink with its coordinates and the source moves on.
+
+use rdkafka_wrap::{KWProducer, KWProducerConf};
+use serde_json::{Value, json};
+use wp_connector_api::{DataSource, ParamMap, Tags};
+use wp_connectors::common::quarantine::{QuarantineConf, build_quarantine_sink};
+use wp_connectors::kafka::{KafkaSource, KafkaSourceConf};
+use wp_parse_api::RawData;
+
+use crate::common;
+
+const MALFORMED: &str = r#"{"host": "a", "level": "#;
+
+/// 读取 `count` 条负载，跳过以 NotData 返回的消息。
+async fn read_payloads(source: &mut KafkaSource, count: usize) -> anyhow::Result<Vec<String>> {
+    tokio::time::timeout(common::TEST_TIMEOUT, async {
+        let mut payloads = Vec::new();
+        while payloads.len() < count {
+            let Ok(batch) = source.receive().await else {
+                continue;
+            };
+            for event in batch {
+                payloads.push(match event.payload {
+                    RawData::String(s) => s,
+                    RawData::Bytes(b) => String::from_utf8_lossy(&b).to_string(),
+                });
+            }
+        }
+        payloads
+    })
+    .await
+    .map_err(|_| anyhow::anyhow!("recv timeout"))
+}
+
+async fn source(
+    name: &str,
+    topic: &str,
+    fields: Option<Vec<String>>,
+) -> anyhow::Result<KafkaSource> {
+    let group_id = common::generate_test_group_id(name);
+    let conf = KafkaSourceConf {
+        key: name.to_string(),
+        brokers: common::TEST_KAFKA_BROKERS.to_string(),
+        topic: vec![topic.to_string()],
+        config: Some(vec!["auto.offset.reset=earliest".to_string()]),
+        enable: true,
+        group_id: Some(group_id.clone()),
+        fields,
+        ..Default::default()
+    };
+    KafkaSource::new(
+        conf.key.clone(),
+        Tags::from_parse(&Vec::new()),
+        &group_id,
+        &conf,
+    )
+    .await
+}
+
+#[tokio::test]
+async fn kafka_source_quarantines_malformed_payload() -> anyhow::Result<()> {
+    if !common::is_kafka_available().await {
+        return Ok(());
+    }
+    let topic = common::generate_test_topic_name("quarantine_src");
+    let bad_topic = common::generate_test_topic_name("quarantine_bad");
+    let pconf = KWProducerConf::new(common::TEST_KAFKA_BROKERS).set_topic_conf(&topic, 1, 1);
+    let producer = KWProducer::new(pconf)?;
+    producer.create_topic().await?;
+    for payload in [MALFORMED, r#"{"host":"b","level":"info"}"#] {
+        producer
+            .publish(payload.as_bytes(), Default::default())
+            .await?;
+    }
+
+    let mut params = ParamMap::new();
+    params.insert(
+        "quarantine".into(),
+        json!({"kind": "kafka", "params": {
+            "brokers": common::TEST_KAFKA_BROKERS,
+            "topic": bad_topic,
+            "num_partitions": 1,
+            "replication": 1,
+        }}),
+    );
+    let qconf = QuarantineConf::from_params(&params)?.expect("quarantine configured");
+    let quarantine = build_quarantine_sink(&qconf, "quarantine_src").await?;
+    let mut src = source("quarantine_src", &topic, Some(vec!["host".to_string()]))
+        .await?
+        .with_quarantine(Some(quarantine));
+
+    // 隔离的消息以 NotData 返回，源端继续处理下一条
+    let kept = read_payloads(&mut src, 1).await?;
+    assert_eq!(
+        serde_json::from_str::<Value>(&kept[0])?,
+        json!({"host": "b"})
+    );
+
+    let mut bad = source("quarantine_bad", &bad_topic, None).await?;
+    let lines = read_payloads(&mut bad, 1).await?;
+    let entry: Value = serde_json::from_str(&lines[0])?;
+    assert_eq!(entry["source"], json!("quarantine_src"));
+    assert_eq!(entry["payload"], json!(MALFORMED));
+    assert!(entry["error"].as_str().is_some_and(|e| !e.is_empty()));
+    assert_eq!(entry["meta"]["topic"], json!(topic));
+    assert_eq!(entry["meta"]["partition"], json!(0));
+    assert_eq!(entry["meta"]["offset"], json!(0));
+    Ok(())
+}
@@ -0,0 +1,82 @@
+//! Rebalance visibility: adding a second consumer to the group triggers a rebalance that
+//! the first source observes through its rebalance counter.
+
+use rdkafka_wrap::{KWProducer, KWProducerConf};
+use std::time::{Duration, Instant};
+use wp_connector_api::{DataSource, Tags};
+use wp_connectors::kafka::{KafkaSource, KafkaSourceConf};
+
+use crate::common;
+
+const POLL: Duration = Duration::from_millis(500);
+
+async fn new_source(key: &str, topic: &str, group_id: &str) -> anyhow::Result<KafkaSource> {
+    let conf = KafkaSourceConf {
+        key: key.to_string(),
+        brokers: common::TEST_KAFKA_BROKERS.to_string(),
+        topic: vec![topic.to_string()],
+        config: Some(vec!["auto.offset.reset=earliest".to_string()]),
+        enable: true,
+        group_id: Some(group_id.to_string()),
+        ..Default::default()
+    };
+    Ok(KafkaSource::new(
+        key.to_string(),
+        Tags::from_parse(&Vec::new()),
+        group_id,
+        &conf,
+    )
+    .await?)
+}
+
+/// 轮询直到条件满足；接收结果本身不关心（可能超时或无数据）。
+async fn poll_until(
+    sources: &mut [&mut KafkaSource],
+    mut done: impl FnMut(&[&mut KafkaSource]) -> bool,
+) -> bool {
+    let deadline = Instant::now() + common::TEST_TIMEOUT * 2;
+    while Instant::now() < deadline {
+        for source in sources.iter_mut() {
+            let _ = tokio::time::timeout(POLL, source.receive()).await;
+        }
+        if done(sources) {
+            return true;
+        }
+    }
+    false
+}
+
+#[tokio::test]
+async fn kafka_source_counts_rebalance_when_group_grows() -> anyhow::Result<()> {
+    if !common::is_kafka_available().await {
+        return Ok(());
+    }
+    let topic = common::generate_test_topic_name("rebalance");
+    let group_id = common::generate_test_group_id("rebalance");
+
+    let pconf = KWProducerConf::new(common::TEST_KAFKA_BROKERS).set_topic_conf(&topic, 2, 1);
+    let producer = KWProducer::new(pconf)?;
+    producer.create_topic().await?;
+    for i in 0..4 {
+        producer
+            .publish(format!("msg-{i}").as_bytes(), Default::default())
+            .await?;
+    }
+
+    let mut first = new_source("rebalance_a", &topic, &group_id).await?;
+    assert!(
+        poll_until(&mut [&mut first], |s| s[0].rebalance_count() >= 1).await,
+        "initial assignment not observed"
+    );
+    let before = first.rebalance_count();
+
+    let mut second = new_source("rebalance_b", &topic, &group_id).await?;
+    assert!(
+        poll_until(&mut [&mut first, &mut second], |s| {
+            s[0].rebalance_count() > before
+        })
+        .await,
+        "rebalance after second consumer joined not observed"
+    );
+    Ok(())
+}
@@ -0,0 +1,71 @@
+//! Sampling decision: with `respect_sampling_field`, records whose sampling field is false
+//! are consumed but not emitted; sampled records pass through unchanged.
+
+use rdkafka_wrap::{KWProducer, KWProducerConf};
+use wp_connector_api::{DataSource, Tags};
+use wp_connectors::kafka::{KafkaSource, KafkaSourceConf};
+use wp_parse_api::RawData;
+
+use crate::common;
+
+#[tokio::test]
+async fn kafka_source_drops_records_not_sampled() -> anyhow::Result<()> {
+    if !common::is_kafka_available().await {
+        return Ok(());
+    }
+    let topic = common::generate_test_topic_name("sampling");
+    let pconf = KWProducerConf::new(common::TEST_KAFKA_BROKERS).set_topic_conf(&topic, 1, 1);
+    let producer = KWProducer::new(pconf)?;
+    producer.create_topic().await?;
+    let messages = [
+        r#"{"msg":"kept-1","sampled":true}"#,
+        r#"{"msg":"dropped-1","sampled":false}"#,
+        r#"{"msg":"no-decision"}"#,
+        r#"{"msg":"dropped-2","sampled":false}"#,
+        r#"{"msg":"kept-2","sampled":true}"#,
+    ];
+    for msg in messages {
+        producer.publish(msg.as_bytes(), Default::default()).await?;
+    }
+
+    let group_id = common::generate_test_group_id("sampling");
+    let conf = KafkaSourceConf {
+        key: "sampling".to_string(),
+        brokers: common::TEST_KAFKA_BROKERS.to_string(),
+        topic: vec![topic.clone()],
+        config: Some(vec!["auto.offset.reset=earliest".to_string()]),
+        enable: true,
+        group_id: Some(group_id.clone()),
+        respect_sampling_field: Some("sampled".to_string()),
+        ..Default::default()
+    };
+    let mut source = KafkaSource::new(
+        conf.key.clone(),
+        Tags::from_parse(&Vec::new()),
+        &group_id,
+        &conf,
+    )
+    .await?;
+
+    let payloads = tokio::time::timeout(common::TEST_TIMEOUT, async {
+        let mut payloads = Vec::new();
+        while payloads.len() < 3 {
+            // 未采样的消息以 NotData 返回
+            let Ok(batch) = source.receive().await else {
+                continue;
+            };
+            for event in batch {
+                payloads.push(match event.payload {
+                    RawData::String(s) => s,
+                    RawData::Bytes(b) => String::from_utf8_lossy(&b).to_string(),
+                });
+            }
+        }
+        payloads
+    })
+    .await
+    .map_err(|_| anyhow::anyhow!("recv timeout"))?;
+
+    assert_eq!(payloads, vec![messages[0], messages[2], messages[4]]);
+    Ok(())
+}
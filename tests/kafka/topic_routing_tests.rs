@@ -0,0 +1,79 @@
+//! Dynamic topic routing: with `topic_field` set, each record goes to the topic named
+//! by that field; records without it fall back to the static topic.
+
+use rdkafka_wrap::{KWConsumer, KWConsumerConf, Message};
+use std::sync::Arc;
+use tokio::time::timeout;
+use wp_connector_api::{AsyncCtrl, AsyncRecordSink};
+use wp_connectors::kafka::{KafkaSink, KafkaSinkConf};
+use wp_model_core::model::{DataField, DataRecord, fmt_def::TextFmt};
+
+use crate::common;
+
+/// 从 `topic` 消费 `count` 条消息并返回负载。
+async fn consume_payloads(topic: &str, count: usize) -> anyhow::Result<Vec<String>> {
+    let group = common::generate_test_group_id("route");
+    let conf = KWConsumerConf::new(common::TEST_KAFKA_BROKERS, &group)
+        .set_config(std::collections::HashMap::from([
+            ("enable.partition.eof", "false"),
+            ("auto.offset.reset", "earliest"),
+        ]))
+        .set_topics(vec![topic]);
+    let consumer = KWConsumer::new_subscribe(conf)?;
+    let mut payloads = Vec::new();
+    timeout(common::TEST_TIMEOUT, async {
+        while payloads.len() < count {
+            if let Ok(msg) = consumer.recv().await {
+                payloads.push(String::from_utf8_lossy(msg.payload().unwrap_or(&[])).to_string());
+            }
+        }
+    })
+    .await
+    .map_err(|_| anyhow::anyhow!("consume timeout on {topic}"))?;
+    Ok(payloads)
+}
+
+fn record(dataset: Option<&str>, msg: &str) -> Arc<DataRecord> {
+    let mut rec = DataRecord::default();
+    if let Some(dataset) = dataset {
+        rec.append(DataField::from_chars("dataset", dataset));
+    }
+    rec.append(DataField::from_chars("msg", msg));
+    Arc::new(rec)
+}
+
+#[tokio::test]
+async fn kafka_sink_routes_records_by_topic_field() -> anyhow::Result<()> {
+    if !common::is_kafka_available().await {
+        return Ok(());
+    }
+    let fallback = common::generate_test_topic_name("route_default");
+    let audit = common::generate_test_topic_name("route_audit");
+    let access = common::generate_test_topic_name("route_access");
+    let conf = KafkaSinkConf {
+        brokers: common::TEST_KAFKA_BROKERS.to_string(),
+        topic: fallback.clone(),
+        num_partitions: 1,
+        replication: 1,
+        topic_field: Some("dataset".to_string()),
+        ..Default::default()
+    };
+    let mut sink = KafkaSink::from_conf(&conf, TextFmt::Json).await?;
+
+    sink.sink_records(vec![
+        record(Some(&audit), "audit-1"),
+        record(Some(&access), "access-1"),
+        record(Some(&audit), "audit-2"),
+        record(None, "fallback-1"),
+    ])
+    .await?;
+    sink.stop().await?;
+
+    let audit_msgs = consume_payloads(&audit, 2).await?;
+    assert!(audit_msgs[0].contains("audit-1") && audit_msgs[1].contains("audit-2"));
+    let access_msgs = consume_payloads(&access, 1).await?;
+    assert!(access_msgs[0].contains("access-1"));
+    let fallback_msgs = consume_payloads(&fallback, 1).await?;
+    assert!(fallback_msgs[0].contains("fallback-1"));
+    Ok(())
+}
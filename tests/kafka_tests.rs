@@ -6,3 +6,54 @@ mod integration_tests;
 
 #[path = "common.rs"]
 mod common;
+
+#[path = "kafka/group_roundtrip_tests.rs"]
+mod group_roundtrip_tests;
+
+#[path = "kafka/delivery_tests.rs"]
+mod delivery_tests;
+
+#[path = "kafka/key_roundtrip_tests.rs"]
+mod key_roundtrip_tests;
+
+#[path = "kafka/header_roundtrip_tests.rs"]
+mod header_roundtrip_tests;
+
+#[path = "kafka/header_filter_tests.rs"]
+mod header_filter_tests;
+
+#[path = "kafka/sampling_tests.rs"]
+mod sampling_tests;
+
+#[path = "kafka/dedup_tests.rs"]
+mod dedup_tests;
+
+#[path = "kafka/decoder_tests.rs"]
+mod decoder_tests;
+
+#[path = "kafka/batch_tests.rs"]
+mod batch_tests;
+
+#[path = "kafka/dlq_tests.rs"]
+mod dlq_tests;
+
+#[path = "kafka/topic_routing_tests.rs"]
+mod topic_routing_tests;
+
+#[path = "kafka/partition_tests.rs"]
+mod partition_tests;
+
+#[path = "kafka/rebalance_tests.rs"]
+mod rebalance_tests;
+
+#[path = "kafka/flush_on_stop_tests.rs"]
+mod flush_on_stop_tests;
+
+#[path = "kafka/metadata_tests.rs"]
+mod metadata_tests;
+
+#[path = "kafka/fmt_contract_tests.rs"]
+mod fmt_contract_tests;
+
+#[path = "kafka/quarantine_tests.rs"]
+mod quarantine_tests;
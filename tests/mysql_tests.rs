@@ -0,0 +1,5 @@
+#![cfg(feature = "mysql")]
+// Wrapper test to include tests under tests/mysql/
+
+#[path = "mysql/integration_tests.rs"]
+mod integration_tests;
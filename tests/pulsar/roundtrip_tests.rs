@@ -0,0 +1,95 @@
+//! Pulsar sink → source roundtrip; skipped when no broker listens on localhost:6650
+//! or `SKIP_PULSAR_INTEGRATION_TESTS` is set.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::time::timeout;
+use wp_connector_api::{AsyncCtrl, AsyncRecordSink, DataSource, Tags};
+use wp_connectors::pulsar::{
+    PulsarConnConf, PulsarSink, PulsarSinkConf, PulsarSource, PulsarSourceConf, PulsarSubType,
+};
+use wp_model_core::model::{DataField, DataRecord};
+use wp_parse_api::RawData;
+
+const TEST_PULSAR_URL: &str = "pulsar://localhost:6650";
+
+async fn is_pulsar_available() -> bool {
+    if std::env::var("SKIP_PULSAR_INTEGRATION_TESTS").is_ok() {
+        println!("⚠️  Skipping Pulsar integration tests (SKIP_PULSAR_INTEGRATION_TESTS is set)");
+        return false;
+    }
+    match tokio::net::TcpStream::connect("localhost:6650").await {
+        Ok(_) => true,
+        Err(e) => {
+            println!("⚠️  Pulsar is not available: {}", e);
+            false
+        }
+    }
+}
+
+fn record(msg: &str) -> Arc<DataRecord> {
+    let mut rec = DataRecord::default();
+    rec.append(DataField::from_chars("msg", msg));
+    Arc::new(rec)
+}
+
+#[tokio::test]
+async fn pulsar_sink_records_are_received_by_source() -> anyhow::Result<()> {
+    if !is_pulsar_available().await {
+        return Ok(());
+    }
+    let topic = format!(
+        "persistent://public/default/wp_roundtrip_{}",
+        chrono::Utc::now().timestamp_millis()
+    );
+    let conn = PulsarConnConf {
+        service_url: TEST_PULSAR_URL.to_string(),
+        token: None,
+    };
+    // 先订阅，确保能收到之后发送的消息
+    let mut source = PulsarSource::new(
+        "pulsar_roundtrip".into(),
+        Tags::default(),
+        &conn,
+        &PulsarSourceConf {
+            topic: topic.clone(),
+            subscription: "wp_roundtrip".into(),
+            sub_type: PulsarSubType::Exclusive,
+            batch: 10,
+        },
+    )
+    .await?;
+    let mut sink = PulsarSink::from_conf(
+        &conn,
+        &PulsarSinkConf {
+            topic: topic.clone(),
+            batch_size: Some(10),
+            fmt: "json".into(),
+        },
+    )
+    .await?;
+    sink.sink_records(vec![record("first"), record("second")])
+        .await?;
+    sink.stop().await?;
+
+    let payloads = timeout(Duration::from_secs(15), async {
+        let mut payloads = Vec::new();
+        while payloads.len() < 2 {
+            let Ok(batch) = source.receive().await else {
+                continue;
+            };
+            for event in batch {
+                payloads.push(match event.payload {
+                    RawData::String(s) => s,
+                    RawData::Bytes(b) => String::from_utf8_lossy(&b).to_string(),
+                });
+            }
+        }
+        payloads
+    })
+    .await
+    .map_err(|_| anyhow::anyhow!("receive timeout on {topic}"))?;
+    assert!(payloads[0].contains("first") && payloads[1].contains("second"));
+    Ok(())
+}
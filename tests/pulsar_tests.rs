@@ -0,0 +1,5 @@
+#![cfg(feature = "pulsar")]
+// Wrapper test to include tests under tests/pulsar/
+
+#[path = "pulsar/roundtrip_tests.rs"]
+mod roundtrip_tests;